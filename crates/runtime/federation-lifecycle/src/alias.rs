@@ -0,0 +1,345 @@
+//! Persistent ID aliases after federation merges
+//!
+//! Once a merge completes, external systems still hold the old federation and member
+//! DIDs. The alias service records every old-to-new mapping from the merge's trust
+//! mapping so those references keep resolving: `resolve_alias` follows an old DID to
+//! its current one, through several merges if needed, and `aliases_of` answers the
+//! reverse question. Aliases can carry an expiry so they only hold for the transition
+//! period. Every change is written to the service's [`AliasStore`].
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::MergeProcess;
+use chrono::{DateTime, Duration, Utc};
+use icn_identity::Did;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// What an alias refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AliasKind {
+    /// A source federation of a merge
+    Federation,
+    /// A member whose DID changed in a merge
+    Member,
+}
+
+/// A mapping from a DID that no longer exists to the one that replaced it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasEntry {
+    /// The DID external systems may still use
+    pub old_id: Did,
+    /// The DID it was replaced by
+    pub new_id: Did,
+    /// What the DIDs refer to
+    pub kind: AliasKind,
+    /// Merge process the mapping came from
+    pub process_id: String,
+    /// When the alias was recorded
+    pub created_at: DateTime<Utc>,
+    /// When the alias stops resolving, if it only holds for a transition period
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl AliasEntry {
+    /// Whether the alias resolves at `at`
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        self.expires_at.map(|expires| at < expires).unwrap_or(true)
+    }
+}
+
+/// Where the alias service keeps its entries
+pub trait AliasStore: Send + Sync {
+    /// Load the stored entries, if any were saved
+    fn load(&self) -> LifecycleResult<Option<Vec<u8>>>;
+
+    /// Replace the stored entries
+    fn save(&self, data: &[u8]) -> LifecycleResult<()>;
+}
+
+/// Alias store kept in memory, for tests and single-process deployments
+#[derive(Debug, Default)]
+pub struct InMemoryAliasStore {
+    data: Mutex<Option<Vec<u8>>>,
+}
+
+impl AliasStore for InMemoryAliasStore {
+    fn load(&self) -> LifecycleResult<Option<Vec<u8>>> {
+        let data = self.data.lock()
+            .map_err(|_| LifecycleError::StorageError("Alias store lock poisoned".to_string()))?;
+        Ok(data.clone())
+    }
+
+    fn save(&self, data: &[u8]) -> LifecycleResult<()> {
+        let mut stored = self.data.lock()
+            .map_err(|_| LifecycleError::StorageError("Alias store lock poisoned".to_string()))?;
+        *stored = Some(data.to_vec());
+        Ok(())
+    }
+}
+
+/// Alias store backed by a JSON file
+#[derive(Debug, Clone)]
+pub struct FileAliasStore {
+    path: PathBuf,
+}
+
+impl FileAliasStore {
+    /// Keep aliases in the file at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl AliasStore for FileAliasStore {
+    fn load(&self) -> LifecycleResult<Option<Vec<u8>>> {
+        match std::fs::read(&self.path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(LifecycleError::StorageError(format!(
+                "Failed to read aliases from {}: {}", self.path.display(), e
+            ))),
+        }
+    }
+
+    fn save(&self, data: &[u8]) -> LifecycleResult<()> {
+        // Write to a temporary file first so a crash can't leave a partial file
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, data)
+            .and_then(|_| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| LifecycleError::StorageError(format!(
+                "Failed to write aliases to {}: {}", self.path.display(), e
+            )))
+    }
+}
+
+/// Upper bound on how many merges a single DID is followed through
+const MAX_ALIAS_HOPS: usize = 32;
+
+/// Resolves DIDs replaced by merges to their current DIDs
+pub struct AliasService<S: AliasStore> {
+    store: S,
+    entries: HashMap<Did, AliasEntry>,
+}
+
+impl<S: AliasStore> AliasService<S> {
+    /// Open the service, loading any aliases already in the store
+    pub fn open(store: S) -> LifecycleResult<Self> {
+        let entries: Vec<AliasEntry> = match store.load()? {
+            Some(data) => serde_json::from_slice(&data)
+                .map_err(|e| LifecycleError::StorageError(format!("Failed to deserialize aliases: {}", e)))?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            store,
+            entries: entries.into_iter().map(|e| (e.old_id.clone(), e)).collect(),
+        })
+    }
+
+    fn persist(&self) -> LifecycleResult<()> {
+        let mut entries: Vec<&AliasEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.old_id.cmp(&b.old_id));
+        let data = serde_json::to_vec(&entries)
+            .map_err(|e| LifecycleError::StorageError(format!("Failed to serialize aliases: {}", e)))?;
+        self.store.save(&data)
+    }
+
+    /// Record the aliases of a merge: both source federations map to the new
+    /// federation, and every member DID the trust mapping changes maps to its new DID.
+    /// With a `transition` period the aliases stop resolving once it has passed.
+    /// Returns the number of aliases recorded.
+    pub fn record_merge(&mut self, process: &MergeProcess, transition: Option<Duration>) -> LifecycleResult<usize> {
+        let now = Utc::now();
+        let expires_at = transition.map(|period| now + period);
+
+        let mut mappings: Vec<(Did, Did, AliasKind)> = vec![
+            (process.federation_a_id.clone(), process.new_federation_id.clone(), AliasKind::Federation),
+            (process.federation_b_id.clone(), process.new_federation_id.clone(), AliasKind::Federation),
+        ];
+        mappings.extend(process.trust_mapping.did_mappings.iter()
+            .map(|(old, new)| (old.clone(), new.clone(), AliasKind::Member)));
+        mappings.retain(|(old, new, _)| old != new);
+
+        // Check everything first so a conflicting merge records nothing
+        for (old, new, _) in &mappings {
+            if let Some(existing) = self.entries.get(old) {
+                if existing.is_active(now) && &existing.new_id != new {
+                    return Err(LifecycleError::TrustMappingError(format!(
+                        "{} is already an alias of {} from merge process {}",
+                        old, existing.new_id, existing.process_id
+                    )));
+                }
+            }
+            if self.resolve_alias_at(new, now).as_ref() == Some(old) {
+                return Err(LifecycleError::TrustMappingError(format!(
+                    "Mapping {} to {} would create an alias cycle", old, new
+                )));
+            }
+        }
+
+        let recorded = mappings.len();
+        for (old_id, new_id, kind) in mappings {
+            self.entries.insert(old_id.clone(), AliasEntry {
+                old_id,
+                new_id,
+                kind,
+                process_id: process.id.clone(),
+                created_at: now,
+                expires_at,
+            });
+        }
+        self.persist()?;
+        Ok(recorded)
+    }
+
+    /// The current DID for an old one, following aliases through successive merges.
+    /// Returns None if `old_id` isn't an active alias.
+    pub fn resolve_alias(&self, old_id: &Did) -> Option<Did> {
+        self.resolve_alias_at(old_id, Utc::now())
+    }
+
+    /// [`resolve_alias`](Self::resolve_alias) as of `at`
+    pub fn resolve_alias_at(&self, old_id: &Did, at: DateTime<Utc>) -> Option<Did> {
+        let mut current = self.entries.get(old_id).filter(|e| e.is_active(at))?.new_id.clone();
+        for _ in 0..MAX_ALIAS_HOPS {
+            match self.entries.get(&current).filter(|e| e.is_active(at)) {
+                Some(next) => current = next.new_id.clone(),
+                None => return Some(current),
+            }
+        }
+        Some(current)
+    }
+
+    /// The DID to use for a reference: its current DID if it is an alias, otherwise
+    /// the reference itself
+    pub fn canonical_id(&self, id: &Did) -> Did {
+        self.resolve_alias(id).unwrap_or_else(|| id.clone())
+    }
+
+    /// Every old DID that resolves to `new_id`, directly or through earlier merges, sorted
+    pub fn aliases_of(&self, new_id: &Did) -> Vec<Did> {
+        let now = Utc::now();
+        let mut aliases: Vec<Did> = self.entries.values()
+            .filter(|e| e.is_active(now) && self.resolve_alias_at(&e.old_id, now).as_ref() == Some(new_id))
+            .map(|e| e.old_id.clone())
+            .collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// The recorded entry for an old DID, active or not
+    pub fn entry(&self, old_id: &Did) -> Option<&AliasEntry> {
+        self.entries.get(old_id)
+    }
+
+    /// Drop aliases whose transition period has passed. Returns how many were removed.
+    pub fn prune_expired(&mut self, at: DateTime<Utc>) -> LifecycleResult<usize> {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| e.is_active(at));
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.persist()?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LineageAttestation, LineageAttestationType, MergeProposal, MergeStatus, PolicyConflictReport,
+        PolicyMergeStrategy, PreMergeBundle, QuorumConfig, TrustMapping,
+    };
+    use icn_identity::QuorumProof;
+
+    fn merge(id: &str, a: &str, b: &str, new: &str, members: &[(&str, &str)]) -> MergeProcess {
+        let proposal = MergeProposal {
+            src_fed_a: a.to_string(),
+            src_fed_b: b.to_string(),
+            new_meta_cid: cid::Cid::default(),
+            quorum_cfg: QuorumConfig { threshold: 1, authorized_signers: vec![], weights: None },
+            challenge_window_secs: 0,
+            approval_a: None,
+            approval_b: None,
+            stages: Vec::new(),
+            policy_merge_strategy: PolicyMergeStrategy::default(),
+            additional_sources: Vec::new(),
+        };
+        MergeProcess {
+            id: id.to_string(),
+            federation_a_id: a.to_string(),
+            federation_b_id: b.to_string(),
+            additional_federation_ids: Vec::new(),
+            new_federation_id: new.to_string(),
+            merge_proposal: proposal,
+            trust_mapping: TrustMapping {
+                did_mappings: members.iter().map(|(o, n)| (o.to_string(), n.to_string())).collect(),
+                role_assignments: HashMap::new(),
+                credential_validations: vec![],
+            },
+            merged_policy: HashMap::new(),
+            policy_conflicts: PolicyConflictReport::default(),
+            merged_bundle: PreMergeBundle {
+                dag_roots: vec![],
+                metadata: HashMap::new(),
+                lineage: LineageAttestation {
+                    parents: vec![a.to_string(), b.to_string()],
+                    children: vec![new.to_string()],
+                    typ: LineageAttestationType::Merge,
+                    proof: QuorumProof::default(),
+                    timestamp: Utc::now(),
+                    metadata: HashMap::new(),
+                },
+                proofs: vec![],
+            },
+            status: MergeStatus::Completed,
+            start_time: Utc::now(),
+            completion_time: Some(Utc::now()),
+            completed_stages: Vec::new(),
+            checkpoint_votes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_aliases_resolve_through_merges_and_persist() {
+        let store = InMemoryAliasStore::default();
+        let mut service = AliasService::open(store).unwrap();
+
+        service.record_merge(&merge("m1", "did:icn:a", "did:icn:b", "did:icn:ab", &[
+            ("did:icn:b:carol", "did:icn:ab:carol"),
+            ("did:icn:a:alice", "did:icn:a:alice"),
+        ]), None).unwrap();
+        service.record_merge(&merge("m2", "did:icn:ab", "did:icn:c", "did:icn:abc", &[]), None).unwrap();
+
+        assert_eq!(service.resolve_alias(&"did:icn:a".to_string()), Some("did:icn:abc".to_string()));
+        assert_eq!(service.resolve_alias(&"did:icn:b:carol".to_string()), Some("did:icn:ab:carol".to_string()));
+        assert_eq!(service.resolve_alias(&"did:icn:a:alice".to_string()), None);
+        assert_eq!(service.canonical_id(&"did:icn:a:alice".to_string()), "did:icn:a:alice");
+        assert_eq!(service.aliases_of(&"did:icn:abc".to_string()), vec![
+            "did:icn:a".to_string(), "did:icn:ab".to_string(), "did:icn:b".to_string(), "did:icn:c".to_string(),
+        ]);
+
+        // A DID can't be remapped to a different federation while its alias holds
+        assert!(service.record_merge(&merge("m3", "did:icn:a", "did:icn:d", "did:icn:ad", &[]), None).is_err());
+        assert_eq!(service.resolve_alias(&"did:icn:d".to_string()), None);
+
+        // Reopening the service from the same store keeps the aliases
+        let reopened = AliasService::open(service.store).unwrap();
+        assert_eq!(reopened.resolve_alias(&"did:icn:b".to_string()), Some("did:icn:abc".to_string()));
+    }
+
+    #[test]
+    fn test_transition_period_expiry() {
+        let mut service = AliasService::open(InMemoryAliasStore::default()).unwrap();
+        service.record_merge(&merge("m1", "did:icn:a", "did:icn:b", "did:icn:ab", &[]), Some(Duration::days(30))).unwrap();
+
+        let later = Utc::now() + Duration::days(31);
+        assert!(service.resolve_alias(&"did:icn:a".to_string()).is_some());
+        assert_eq!(service.resolve_alias_at(&"did:icn:a".to_string(), later), None);
+        assert_eq!(service.prune_expired(later).unwrap(), 2);
+        assert!(service.entry(&"did:icn:a".to_string()).is_none());
+    }
+}
@@ -1,7 +1,8 @@
+use crate::delta::TrustBundleChain;
 use crate::error::{LifecycleError, LifecycleResult};
 use crate::types::{
-    LineageAttestation, LineageAttestationType, PartitionMap, PreMergeBundle, QuorumConfig,
-    SplitBundle,
+    LineageAttestation, LineageAttestationType, PartitionMap, PolicyConflict, PolicyConflictReport,
+    PolicyMergeStrategy, PolicyResolution, PreMergeBundle, QuorumConfig, SplitBundle,
 };
 use cid::{Cid, Version};
 use chrono::Utc;
@@ -104,6 +105,7 @@ impl SplitBundle {
             partition_map,
             lineage,
             proofs,
+            carve_manifests: Vec::new(),
         }
     }
 
@@ -132,6 +134,7 @@ impl SplitBundle {
             partition_map,
             lineage,
             proofs: vec![proof],
+            carve_manifests: Vec::new(),
         };
 
         Ok(bundle)
@@ -189,12 +192,20 @@ pub fn create_trust_mapping(
     federation_a_members: &[Did],
     federation_b_members: &[Did],
     new_federation_id: &Did,
+) -> LifecycleResult<HashMap<Did, Did>> {
+    create_multi_trust_mapping(&[federation_a_members, federation_b_members], new_federation_id)
+}
+
+/// Create a trust mapping from the members of any number of source federations
+pub fn create_multi_trust_mapping(
+    source_members: &[&[Did]],
+    new_federation_id: &Did,
 ) -> LifecycleResult<HashMap<Did, Did>> {
     let mut mapping = HashMap::new();
 
     // In a real implementation, this would create a mapping based on complex rules
     // Here we create a simple 1:1 mapping to the new federation
-    for member in federation_a_members.iter().chain(federation_b_members.iter()) {
+    for member in source_members.iter().flat_map(|members| members.iter()) {
         // Generate a deterministic mapping
         // In a real implementation, this would preserve the identity while changing the federation context
         mapping.insert(member.clone(), member.clone());
@@ -203,17 +214,89 @@ pub fn create_trust_mapping(
     Ok(mapping)
 }
 
-/// Helper function to create a merged governance policy
+/// Whether a lower value of a policy field is the stricter one (e.g., `max_delegations`,
+/// `spending_limit`); for other fields a higher value (quorum, threshold) is stricter
+fn lower_is_stricter(field: &str) -> bool {
+    field.starts_with("max_") || field.ends_with("_limit") || field.ends_with("_cap")
+}
+
+/// The stricter of two values of a policy field, if both are numeric
+fn stricter_side(field: &str, value_a: &str, value_b: &str) -> Option<PolicyResolution> {
+    let a: f64 = value_a.trim().parse().ok()?;
+    let b: f64 = value_b.trim().parse().ok()?;
+    let a_stricter = if lower_is_stricter(field) { a <= b } else { a >= b };
+    Some(if a_stricter { PolicyResolution::TookA } else { PolicyResolution::TookB })
+}
+
+/// Resolve one conflicting policy field under `strategy`
+fn resolve_policy_conflict(
+    field: &str,
+    value_a: &str,
+    value_b: &str,
+    strategy: &PolicyMergeStrategy,
+) -> PolicyConflict {
+    let resolution = match strategy {
+        PolicyMergeStrategy::StricterWins => stricter_side(field, value_a, value_b),
+        PolicyMergeStrategy::LooserWins => stricter_side(field, value_a, value_b).map(|side| match side {
+            PolicyResolution::TookA => PolicyResolution::TookB,
+            _ => PolicyResolution::TookA,
+        }),
+        PolicyMergeStrategy::RequireNewVote => None,
+        PolicyMergeStrategy::Custom(agreed) => agreed.get(field).map(|_| PolicyResolution::Agreed),
+    }
+    // Values that can't be ranked are left to the new federation
+    .unwrap_or(PolicyResolution::PendingVote);
+
+    let merged_value = match (&resolution, strategy) {
+        (PolicyResolution::TookA, _) => Some(value_a.to_string()),
+        (PolicyResolution::TookB, _) => Some(value_b.to_string()),
+        (PolicyResolution::Agreed, PolicyMergeStrategy::Custom(agreed)) => agreed.get(field).cloned(),
+        _ => None,
+    };
+
+    PolicyConflict {
+        field: field.to_string(),
+        value_a: value_a.to_string(),
+        value_b: value_b.to_string(),
+        resolution,
+        merged_value,
+    }
+}
+
+/// Helper function to create a merged governance policy.
+///
+/// Fields set by only one federation, or set to the same value by both, are kept.
+/// Fields the federations set differently are resolved by `strategy`; every such field
+/// is listed in the returned report, and fields awaiting a vote are left out of the
+/// merged policy.
 pub fn create_merged_governance_policy(
     policy_a: &HashMap<String, String>,
     policy_b: &HashMap<String, String>,
-) -> LifecycleResult<HashMap<String, String>> {
+    strategy: &PolicyMergeStrategy,
+) -> LifecycleResult<(HashMap<String, String>, PolicyConflictReport)> {
     let mut merged_policy = HashMap::new();
+    let mut conflicts = Vec::new();
 
-    // Merge policies, prioritizing policy_a in case of conflicts
-    // In a real implementation, this would use more sophisticated policy merging logic
-    merged_policy.extend(policy_b.clone());
-    merged_policy.extend(policy_a.clone());
+    for (field, value_b) in policy_b {
+        if !policy_a.contains_key(field) {
+            merged_policy.insert(field.clone(), value_b.clone());
+        }
+    }
+    for (field, value_a) in policy_a {
+        match policy_b.get(field) {
+            Some(value_b) if value_b != value_a => {
+                let conflict = resolve_policy_conflict(field, value_a, value_b, strategy);
+                if let Some(value) = &conflict.merged_value {
+                    merged_policy.insert(field.clone(), value.clone());
+                }
+                conflicts.push(conflict);
+            }
+            _ => {
+                merged_policy.insert(field.clone(), value_a.clone());
+            }
+        }
+    }
+    conflicts.sort_by(|x, y| x.field.cmp(&y.field));
 
     // Add a merge indicator
     merged_policy.insert(
@@ -221,7 +304,11 @@ pub fn create_merged_governance_policy(
         Utc::now().to_rfc3339(),
     );
 
-    Ok(merged_policy)
+    let report = PolicyConflictReport {
+        strategy: strategy.clone(),
+        conflicts,
+    };
+    Ok((merged_policy, report))
 }
 
 /// Helper function to create a merged trust bundle
@@ -230,15 +317,58 @@ pub fn create_merged_trust_bundle(
     bundle_b: &PreMergeBundle,
     new_federation_id: &Did,
 ) -> LifecycleResult<PreMergeBundle> {
+    create_multi_merged_trust_bundle(&[bundle_a, bundle_b], new_federation_id)
+}
+
+/// Create a merged governance policy from the policies of any number of source
+/// federations.
+///
+/// The policies are merged in order, as with [`create_merged_governance_policy`]: each
+/// conflict is between the policy merged so far (`value_a`) and the next source
+/// (`value_b`). A field put to a vote in any round stays out of the merged policy.
+pub fn create_multi_merged_governance_policy(
+    policies: &[&HashMap<String, String>],
+    strategy: &PolicyMergeStrategy,
+) -> LifecycleResult<(HashMap<String, String>, PolicyConflictReport)> {
+    let (first, rest) = policies.split_first()
+        .ok_or_else(|| LifecycleError::InvalidProposal("A merge needs at least one source policy".to_string()))?;
+
+    let mut merged_policy = (*first).clone();
+    let mut conflicts = Vec::new();
+    for policy in rest {
+        let (merged, report) = create_merged_governance_policy(&merged_policy, policy, strategy)?;
+        merged_policy = merged;
+        conflicts.extend(report.conflicts);
+    }
+    for conflict in &conflicts {
+        if conflict.resolution == PolicyResolution::PendingVote {
+            merged_policy.remove(&conflict.field);
+        }
+    }
+
+    let report = PolicyConflictReport {
+        strategy: strategy.clone(),
+        conflicts,
+    };
+    Ok((merged_policy, report))
+}
+
+/// Create a single merged trust bundle from the bundles of any number of source federations
+pub fn create_multi_merged_trust_bundle(
+    bundles: &[&PreMergeBundle],
+    new_federation_id: &Did,
+) -> LifecycleResult<PreMergeBundle> {
+    let first = bundles.first()
+        .ok_or_else(|| LifecycleError::InvalidProposal("A merge needs at least one source bundle".to_string()))?;
+
     // Combine DAG roots
-    let mut dag_roots = bundle_a.dag_roots.clone();
-    dag_roots.extend(bundle_b.dag_roots.clone());
-
-    // Combine metadata
-    let mut metadata = bundle_a.metadata.clone();
-    for (k, v) in &bundle_b.metadata {
-        if !metadata.contains_key(k) {
-            metadata.insert(k.clone(), v.clone());
+    let dag_roots = bundles.iter().flat_map(|bundle| bundle.dag_roots.iter().cloned()).collect();
+
+    // Combine metadata; earlier sources win
+    let mut metadata = HashMap::new();
+    for bundle in bundles {
+        for (k, v) in &bundle.metadata {
+            metadata.entry(k.clone()).or_insert_with(|| v.clone());
         }
     }
     metadata.insert(
@@ -248,20 +378,20 @@ pub fn create_merged_trust_bundle(
 
     // Create lineage attestation
     let lineage = LineageAttestation {
-        parents: bundle_a.lineage.parents.iter()
-            .chain(bundle_b.lineage.parents.iter())
-            .cloned()
+        parents: bundles.iter()
+            .flat_map(|bundle| bundle.lineage.parents.iter().cloned())
             .collect(),
         children: vec![new_federation_id.clone()],
         typ: LineageAttestationType::Merge,
-        proof: bundle_a.proofs[0].clone(), // Use first proof as lineage proof
+        proof: first.proofs.first().cloned().ok_or_else(|| {
+            LifecycleError::VerificationFailed("Source bundle carries no proof".to_string())
+        })?, // Use first proof as lineage proof
         timestamp: Utc::now(),
         metadata: HashMap::new(),
     };
 
     // Combine proofs
-    let mut proofs = bundle_a.proofs.clone();
-    proofs.extend(bundle_b.proofs.clone());
+    let proofs = bundles.iter().flat_map(|bundle| bundle.proofs.iter().cloned()).collect();
 
     Ok(PreMergeBundle {
         dag_roots,
@@ -303,5 +433,112 @@ pub fn create_split_trust_bundle(
         partition_map,
         lineage,
         proofs: original_bundle.proofs.clone(),
+        carve_manifests: original_bundle.carve_manifests.iter()
+            .filter(|m| &m.target_federation == federation_id)
+            .cloned()
+            .collect(),
     })
-} 
\ No newline at end of file
+} 
+/// Create a merged trust bundle from the consolidated views of two trust bundle chains.
+///
+/// Pending deltas are folded in, so the merge operates on each federation's current
+/// membership rather than its last full bundle.
+pub fn create_merged_trust_bundle_from_chains(
+    chain_a: &TrustBundleChain,
+    chain_b: &TrustBundleChain,
+    proof_a: QuorumProof,
+    proof_b: QuorumProof,
+    new_federation_id: &Did,
+) -> LifecycleResult<PreMergeBundle> {
+    let lineage_for = |chain: &TrustBundleChain, proof: &QuorumProof| LineageAttestation {
+        parents: vec![chain.base.federation_id.clone()],
+        children: vec![new_federation_id.clone()],
+        typ: LineageAttestationType::Merge,
+        proof: proof.clone(),
+        timestamp: Utc::now(),
+        metadata: HashMap::new(),
+    };
+
+    let bundle_a = PreMergeBundle::from_trust_bundle_chain(chain_a, lineage_for(chain_a, &proof_a), vec![proof_a.clone()])?;
+    let bundle_b = PreMergeBundle::from_trust_bundle_chain(chain_b, lineage_for(chain_b, &proof_b), vec![proof_b.clone()])?;
+
+    let mut merged = create_merged_trust_bundle(&bundle_a, &bundle_b, new_federation_id)?;
+
+    // The merged membership is the union of both consolidated views
+    let mut members: Vec<Did> = chain_a.consolidated_view()?.members.into_keys()
+        .chain(chain_b.consolidated_view()?.members.into_keys())
+        .collect();
+    members.sort();
+    members.dedup();
+    merged.metadata.insert("members".to_string(), members.join(","));
+    if let Some(cid_b) = bundle_b.metadata.get("trust_bundle_cid") {
+        merged.metadata.insert("trust_bundle_cid_b".to_string(), cid_b.clone());
+    }
+
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_conflicting_policy_fields_follow_the_strategy() {
+        let policy_a = policy(&[("quorum", "0.5"), ("max_delegations", "5"), ("voting", "ranked"), ("charter", "v1")]);
+        let policy_b = policy(&[("quorum", "0.66"), ("max_delegations", "3"), ("voting", "approval"), ("charter", "v1"), ("dues", "10")]);
+
+        let (merged, report) = create_merged_governance_policy(&policy_a, &policy_b, &PolicyMergeStrategy::StricterWins).unwrap();
+        assert_eq!(merged["quorum"], "0.66");
+        assert_eq!(merged["max_delegations"], "3");
+        assert_eq!(merged["charter"], "v1");
+        assert_eq!(merged["dues"], "10");
+        // Values that can't be ranked go to a vote and stay out of the merged policy
+        assert!(!merged.contains_key("voting"));
+        assert_eq!(report.pending_vote(), vec!["voting"]);
+        let fields: Vec<&str> = report.conflicts.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["max_delegations", "quorum", "voting"]);
+
+        let (merged, _) = create_merged_governance_policy(&policy_a, &policy_b, &PolicyMergeStrategy::LooserWins).unwrap();
+        assert_eq!(merged["quorum"], "0.5");
+        assert_eq!(merged["max_delegations"], "5");
+
+        let (merged, report) = create_merged_governance_policy(&policy_a, &policy_b, &PolicyMergeStrategy::RequireNewVote).unwrap();
+        assert!(!merged.contains_key("quorum"));
+        assert_eq!(report.pending_vote().len(), 3);
+
+        let agreed = policy(&[("voting", "ranked"), ("quorum", "0.6")]);
+        let (merged, report) = create_merged_governance_policy(&policy_a, &policy_b, &PolicyMergeStrategy::Custom(agreed)).unwrap();
+        assert_eq!(merged["voting"], "ranked");
+        assert_eq!(merged["quorum"], "0.6");
+        assert_eq!(report.pending_vote(), vec!["max_delegations"]);
+        assert_eq!(report.conflicts[1].resolution, PolicyResolution::Agreed);
+    }
+
+    #[test]
+    fn test_policies_of_several_federations_merge_in_order() {
+        let policy_a = policy(&[("quorum", "0.5"), ("voting", "ranked")]);
+        let policy_b = policy(&[("quorum", "0.66"), ("voting", "approval"), ("dues", "10")]);
+        let policy_c = policy(&[("quorum", "0.6"), ("voting", "ranked"), ("dues", "12")]);
+        let policy_d = policy(&[("charter", "v2")]);
+
+        let (merged, report) = create_multi_merged_governance_policy(
+            &[&policy_a, &policy_b, &policy_c, &policy_d],
+            &PolicyMergeStrategy::StricterWins,
+        ).unwrap();
+        assert_eq!(merged["quorum"], "0.66");
+        assert_eq!(merged["dues"], "12");
+        assert_eq!(merged["charter"], "v2");
+        // A field put to a vote between A and B stays out even though C sets it
+        assert!(!merged.contains_key("voting"));
+        assert_eq!(report.pending_vote(), vec!["voting"]);
+
+        let members = [vec!["did:icn:alice".to_string()], vec!["did:icn:bob".to_string()], vec!["did:icn:carol".to_string()]];
+        let sources: Vec<&[Did]> = members.iter().map(|m| m.as_slice()).collect();
+        let mapping = create_multi_trust_mapping(&sources, &"did:icn:merged".to_string()).unwrap();
+        assert_eq!(mapping.len(), 3);
+    }
+}
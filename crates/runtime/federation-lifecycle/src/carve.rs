@@ -0,0 +1,289 @@
+//! Scoped DAG carving for federation splits
+//!
+//! When a cooperative splits off, it takes the part of the parent's DAG history that is
+//! relevant to it. The carve starts from every node issued by a departing member and
+//! follows parent links back, so the carved subgraph holds those nodes and all the
+//! history they build on. Each node is copied into the new federation's DAG with its
+//! parent links rewritten to the copies, tagged with the CID of the node it came from,
+//! and recorded in a [`CarveManifest`] that travels in the [`SplitBundle`].
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::SplitBundle;
+use cid::Cid;
+use chrono::{DateTime, Utc};
+use icn_dag::{DagManager, DagNode, DagNodeBuilder, Signer};
+use icn_identity::{Did, IdentityId};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::debug;
+
+/// Tag prefix linking a carved node back to the node it was copied from
+pub const CARVED_FROM_TAG_PREFIX: &str = "carved-from:";
+
+/// Issuer and parent links of a DAG node, enough to compute a carve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeLinks {
+    pub issuer: Did,
+    pub parents: Vec<Cid>,
+}
+
+impl From<&DagNode> for NodeLinks {
+    fn from(node: &DagNode) -> Self {
+        Self {
+            issuer: node.issuer.0.clone(),
+            parents: node.parents.clone(),
+        }
+    }
+}
+
+/// A node copied into the new federation's DAG
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarvedNode {
+    /// CID of the node in the parent federation's DAG
+    pub origin_cid: Cid,
+
+    /// CID of the copy in the new federation's DAG
+    pub carved_cid: Cid,
+}
+
+/// Record of the history carved out of a parent federation's DAG
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarveManifest {
+    /// Federation the history was carved from
+    pub origin_federation: Did,
+
+    /// Federation the history was copied into
+    pub target_federation: Did,
+
+    /// Members whose nodes seeded the carve
+    pub members: Vec<Did>,
+
+    /// Nodes issued by departing members, in the parent's DAG
+    pub seed_nodes: Vec<Cid>,
+
+    /// Every copied node, in the order it was copied (parents first)
+    pub nodes: Vec<CarvedNode>,
+
+    /// When the carve was made
+    pub carved_at: DateTime<Utc>,
+}
+
+impl CarveManifest {
+    /// CID of the copy of an origin node, if it was carved
+    pub fn carved_cid(&self, origin_cid: &Cid) -> Option<&Cid> {
+        self.nodes.iter()
+            .find(|n| &n.origin_cid == origin_cid)
+            .map(|n| &n.carved_cid)
+    }
+}
+
+impl SplitBundle {
+    /// Attach the manifest of a DAG carve to the bundle
+    pub fn with_carve_manifest(mut self, manifest: CarveManifest) -> Self {
+        self.carve_manifests.push(manifest);
+        self
+    }
+
+    /// The carve manifest for one of the resulting federations
+    pub fn carve_manifest_for(&self, federation: &Did) -> Option<&CarveManifest> {
+        self.carve_manifests.iter().find(|m| &m.target_federation == federation)
+    }
+}
+
+/// Nodes issued by any of the given members
+pub fn seed_nodes(graph: &HashMap<Cid, NodeLinks>, members: &[Did]) -> Vec<Cid> {
+    let mut seeds: Vec<Cid> = graph.iter()
+        .filter(|(_, links)| members.contains(&links.issuer))
+        .map(|(cid, _)| *cid)
+        .collect();
+    seeds.sort();
+    seeds
+}
+
+/// The seeds and every node reachable from them through parent links
+pub fn reachable_subgraph(graph: &HashMap<Cid, NodeLinks>, seeds: &[Cid]) -> LifecycleResult<HashSet<Cid>> {
+    let mut reachable = HashSet::new();
+    let mut queue: VecDeque<Cid> = seeds.iter().copied().collect();
+
+    while let Some(cid) = queue.pop_front() {
+        if !reachable.insert(cid) {
+            continue;
+        }
+        let links = graph.get(&cid).ok_or_else(|| {
+            LifecycleError::DagAnchoringFailed(format!("Node {} is missing from the parent DAG", cid))
+        })?;
+        queue.extend(links.parents.iter().copied());
+    }
+
+    Ok(reachable)
+}
+
+/// Order a subgraph so every node comes after its parents
+pub fn parents_first_order(graph: &HashMap<Cid, NodeLinks>, subgraph: &HashSet<Cid>) -> LifecycleResult<Vec<Cid>> {
+    let mut pending: HashMap<Cid, usize> = HashMap::new();
+    let mut children: HashMap<Cid, Vec<Cid>> = HashMap::new();
+    for cid in subgraph {
+        let parents: Vec<Cid> = graph[cid].parents.iter().filter(|p| subgraph.contains(p)).copied().collect();
+        pending.insert(*cid, parents.len());
+        for parent in parents {
+            children.entry(parent).or_default().push(*cid);
+        }
+    }
+
+    let mut ready: Vec<Cid> = pending.iter().filter(|(_, n)| **n == 0).map(|(cid, _)| *cid).collect();
+    ready.sort();
+    let mut queue: VecDeque<Cid> = ready.into();
+    let mut order = Vec::with_capacity(subgraph.len());
+
+    while let Some(cid) = queue.pop_front() {
+        order.push(cid);
+        let mut released = Vec::new();
+        for child in children.get(&cid).into_iter().flatten() {
+            let count = pending.get_mut(child).expect("child is in the subgraph");
+            *count -= 1;
+            if *count == 0 {
+                released.push(*child);
+            }
+        }
+        released.sort();
+        queue.extend(released);
+    }
+
+    if order.len() != subgraph.len() {
+        return Err(LifecycleError::DagAnchoringFailed("Parent DAG contains a cycle".to_string()));
+    }
+    Ok(order)
+}
+
+/// Load every node of a DAG, walking back from its tips
+async fn load_graph(dag: &impl DagManager) -> LifecycleResult<HashMap<Cid, DagNode>> {
+    let mut nodes = HashMap::new();
+    let mut queue: VecDeque<Cid> = dag.get_tips().await
+        .map_err(|e| LifecycleError::DagAnchoringFailed(format!("Failed to load DAG tips: {}", e)))?
+        .into();
+
+    while let Some(cid) = queue.pop_front() {
+        if nodes.contains_key(&cid) {
+            continue;
+        }
+        let node = dag.get_node(&cid).await
+            .map_err(|e| LifecycleError::DagAnchoringFailed(format!("Failed to load node {}: {}", cid, e)))?
+            .ok_or_else(|| LifecycleError::DagAnchoringFailed(format!("Node {} is missing from the parent DAG", cid)))?;
+        queue.extend(node.parents.iter().copied());
+        nodes.insert(cid, node);
+    }
+
+    Ok(nodes)
+}
+
+/// Carve the history relevant to a departing group of members out of the parent DAG
+/// and copy it into the new federation's DAG.
+///
+/// Copies are issued by the new federation and signed with its signer; the original
+/// issuer and CID remain available through the manifest and the `carved-from:` tag.
+pub async fn carve_scoped_dag(
+    source: &impl DagManager,
+    target: &impl DagManager,
+    signer: &impl Signer,
+    origin_federation: &Did,
+    target_federation: &Did,
+    members: &[Did],
+) -> LifecycleResult<CarveManifest> {
+    let nodes = load_graph(source).await?;
+    let graph: HashMap<Cid, NodeLinks> = nodes.iter().map(|(cid, node)| (*cid, NodeLinks::from(node))).collect();
+
+    let seeds = seed_nodes(&graph, members);
+    let subgraph = reachable_subgraph(&graph, &seeds)?;
+    let order = parents_first_order(&graph, &subgraph)?;
+
+    let mut carved: HashMap<Cid, Cid> = HashMap::new();
+    let mut manifest_nodes = Vec::with_capacity(order.len());
+
+    for origin_cid in order {
+        let node = &nodes[&origin_cid];
+        let parents = node.parents.iter()
+            .filter_map(|p| carved.get(p).copied())
+            .collect();
+
+        let mut builder = DagNodeBuilder::new()
+            .payload(node.payload.clone())
+            .parents(parents)
+            .issuer(IdentityId(target_federation.clone()))
+            .timestamp(node.metadata.timestamp)
+            .sequence(node.metadata.sequence)
+            .tag(format!("{}{}", CARVED_FROM_TAG_PREFIX, origin_cid));
+        if let Some(content_type) = &node.metadata.content_type {
+            builder = builder.content_type(content_type.clone());
+        }
+        for tag in &node.metadata.tags {
+            builder = builder.tag(tag.clone());
+        }
+
+        let copy = builder.build_signed(signer)
+            .map_err(|e| LifecycleError::DagAnchoringFailed(format!("Failed to sign carved node: {}", e)))?;
+        let carved_cid = target.store_node(&copy).await
+            .map_err(|e| LifecycleError::DagAnchoringFailed(format!("Failed to store carved node: {}", e)))?;
+
+        carved.insert(origin_cid, carved_cid);
+        manifest_nodes.push(CarvedNode { origin_cid, carved_cid });
+    }
+
+    debug!("Carved {} of {} nodes from {} into {}",
+           manifest_nodes.len(), nodes.len(), origin_federation, target_federation);
+
+    Ok(CarveManifest {
+        origin_federation: origin_federation.clone(),
+        target_federation: target_federation.clone(),
+        members: members.to_vec(),
+        seed_nodes: seeds,
+        nodes: manifest_nodes,
+        carved_at: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use multihash::{Code, MultihashDigest};
+
+    fn cid(name: &str) -> Cid {
+        Cid::new_v1(0x71, Code::Sha2_256.digest(name.as_bytes()))
+    }
+
+    fn links(issuer: &str, parents: &[&str]) -> NodeLinks {
+        NodeLinks {
+            issuer: issuer.to_string(),
+            parents: parents.iter().map(|p| cid(p)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_carve_follows_history_of_departing_members() {
+        // genesis <- charter <- alice_vote <- bob_vote
+        //                    <- carol_vote
+        let graph: HashMap<Cid, NodeLinks> = [
+            ("genesis", links("did:icn:fed", &[])),
+            ("charter", links("did:icn:fed", &["genesis"])),
+            ("alice_vote", links("did:icn:alice", &["charter"])),
+            ("bob_vote", links("did:icn:bob", &["alice_vote"])),
+            ("carol_vote", links("did:icn:carol", &["charter"])),
+        ].into_iter().map(|(name, l)| (cid(name), l)).collect();
+
+        let seeds = seed_nodes(&graph, &["did:icn:alice".to_string()]);
+        assert_eq!(seeds, vec![cid("alice_vote")]);
+
+        let subgraph = reachable_subgraph(&graph, &seeds).unwrap();
+        let expected: HashSet<Cid> = ["genesis", "charter", "alice_vote"].iter().map(|n| cid(n)).collect();
+        assert_eq!(subgraph, expected);
+
+        assert_eq!(
+            parents_first_order(&graph, &subgraph).unwrap(),
+            vec![cid("genesis"), cid("charter"), cid("alice_vote")]
+        );
+
+        // A dangling parent link means the parent DAG is incomplete
+        let mut broken = graph.clone();
+        broken.insert(cid("orphan"), links("did:icn:alice", &["missing"]));
+        assert!(reachable_subgraph(&broken, &[cid("orphan")]).is_err());
+    }
+}
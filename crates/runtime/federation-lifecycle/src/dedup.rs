@@ -0,0 +1,462 @@
+//! Cross-federation identity de-duplication for merges
+//!
+//! The same person may be a member of both merging federations under two DIDs. While
+//! the trust mapping is under review, a [`DuplicateDetector`] looks for pairs of
+//! members, one from each federation, that probably belong to the same person:
+//!
+//! - one declared the other as an alias of theirs (stronger if both did)
+//! - both DIDs are linked to the same verified external account
+//! - both DIDs control the same email address or phone number, as shown by a valid
+//!   contact attestation matching the address on record
+//!
+//! Each signal carries a weight, and a pair is flagged once its combined confidence
+//! reaches the detector's threshold. Nothing is merged automatically: a flagged pair
+//! is only consolidated once both DIDs confirm it in the [`DuplicateReview`]. The
+//! consolidation maps both DIDs to one DID in the merged federation, gathers the roles
+//! and credentials of both under it and moves the second DID's balance onto the first,
+//! so the ledger union credits the person once.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::impact::{HeldCredential, MergeImpactContext};
+use crate::types::MergeProcess;
+use chrono::{DateTime, Utc};
+use icn_economics::Ledger;
+use icn_identity::{ContactAttestation, ContactChannel, Did, ExternalAccountLink, IdentityId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Combined confidence at which a pair is flagged, by default
+pub const DEFAULT_DUPLICATE_THRESHOLD: f64 = 0.6;
+
+/// A member's statement that another DID is also theirs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeclaredAlias {
+    pub did: Did,
+    pub also_known_as: Did,
+    pub declared_at: DateTime<Utc>,
+}
+
+/// An address a federation holds on record for a member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberContact {
+    pub did: Did,
+    pub channel: ContactChannel,
+    pub address: String,
+}
+
+/// Evidence the detector compares members on
+#[derive(Debug, Clone, Default)]
+pub struct IdentityEvidence {
+    pub declared_aliases: Vec<DeclaredAlias>,
+    pub account_links: Vec<ExternalAccountLink>,
+    pub contacts: Vec<MemberContact>,
+    /// Attestations that members control their addresses on record
+    pub contact_attestations: Vec<ContactAttestation>,
+}
+
+/// Why two DIDs are thought to belong to the same person
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DuplicateSignal {
+    /// One DID declared the other as an alias; `mutual` if both did
+    DeclaredAlias { mutual: bool },
+    /// Both DIDs are linked to the same external account
+    SharedExternalAccount { provider: String, external_account: String },
+    /// Both DIDs control the same verified address
+    SharedContact { channel: ContactChannel },
+}
+
+impl DuplicateSignal {
+    /// How strongly the signal alone suggests a duplicate, between 0 and 1
+    pub fn weight(&self) -> f64 {
+        match self {
+            DuplicateSignal::DeclaredAlias { mutual: true } => 0.95,
+            DuplicateSignal::DeclaredAlias { mutual: false } => 0.6,
+            DuplicateSignal::SharedExternalAccount { .. } => 0.8,
+            DuplicateSignal::SharedContact { channel: ContactChannel::Email } => 0.7,
+            DuplicateSignal::SharedContact { channel: ContactChannel::Phone } => 0.6,
+        }
+    }
+}
+
+/// A pair of members probably belonging to the same person
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
+    /// Member of federation A
+    pub did_a: Did,
+    /// Member of federation B
+    pub did_b: Did,
+    pub signals: Vec<DuplicateSignal>,
+    /// Combined confidence of the signals, between 0 and 1
+    pub confidence: f64,
+    /// DIDs of the pair that confirmed they are the same person
+    pub confirmations: Vec<Did>,
+}
+
+impl DuplicateCandidate {
+    /// Whether both DIDs confirmed the pair
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmations.contains(&self.did_a) && self.confirmations.contains(&self.did_b)
+    }
+}
+
+/// Flags members of two federations that probably are the same person
+#[derive(Debug, Clone)]
+pub struct DuplicateDetector {
+    threshold: f64,
+}
+
+impl Default for DuplicateDetector {
+    fn default() -> Self {
+        Self { threshold: DEFAULT_DUPLICATE_THRESHOLD }
+    }
+}
+
+impl DuplicateDetector {
+    /// Create a detector with the default threshold
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flag pairs whose combined confidence reaches `threshold`
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Flag probable duplicates between the members of two federations, most
+    /// confident first. Contact attestations count if they're valid at `at`.
+    pub fn detect(
+        &self,
+        members_a: &[Did],
+        members_b: &[Did],
+        evidence: &IdentityEvidence,
+        at: DateTime<Utc>,
+    ) -> Vec<DuplicateCandidate> {
+        let mut signals: BTreeMap<(Did, Did), Vec<DuplicateSignal>> = BTreeMap::new();
+        let mut add = |group: &[&Did], signal: DuplicateSignal| {
+            for a in group.iter().filter(|did| members_a.contains(**did)) {
+                for b in group.iter().filter(|did| members_b.contains(**did)) {
+                    if a != b {
+                        signals.entry(((*a).clone(), (*b).clone())).or_default().push(signal.clone());
+                    }
+                }
+            }
+        };
+
+        // Declared aliases, either way round
+        let declared = |from: &Did, to: &Did| evidence.declared_aliases.iter()
+            .any(|alias| &alias.did == from && &alias.also_known_as == to);
+        for a in members_a {
+            for b in members_b {
+                let (a_to_b, b_to_a) = (declared(a, b), declared(b, a));
+                if a != b && (a_to_b || b_to_a) {
+                    add(&[a, b], DuplicateSignal::DeclaredAlias { mutual: a_to_b && b_to_a });
+                }
+            }
+        }
+
+        // DIDs sharing a linked external account
+        let mut accounts: BTreeMap<(&str, &str), Vec<&Did>> = BTreeMap::new();
+        for link in &evidence.account_links {
+            accounts.entry((link.provider.as_str(), link.external_account.as_str())).or_default().push(&link.did);
+        }
+        for ((provider, external_account), dids) in accounts {
+            add(&dids, DuplicateSignal::SharedExternalAccount {
+                provider: provider.to_string(),
+                external_account: external_account.to_string(),
+            });
+        }
+
+        // DIDs sharing an address they're attested to control
+        let mut addresses: HashMap<(ContactChannel, String), Vec<&Did>> = HashMap::new();
+        for contact in &evidence.contacts {
+            let did = IdentityId::new(contact.did.clone());
+            let verified = evidence.contact_attestations.iter().any(|attestation| {
+                attestation.channel == contact.channel
+                    && attestation.matches_address(&contact.address)
+                    && attestation.is_valid_for(&did, at).unwrap_or(false)
+            });
+            if verified {
+                addresses.entry((contact.channel, contact.channel.normalize(&contact.address)))
+                    .or_default()
+                    .push(&contact.did);
+            }
+        }
+        for ((channel, _), dids) in addresses {
+            add(&dids, DuplicateSignal::SharedContact { channel });
+        }
+
+        let mut candidates: Vec<DuplicateCandidate> = signals.into_iter()
+            .map(|((did_a, did_b), mut signals)| {
+                signals.dedup();
+                let confidence = 1.0 - signals.iter().map(|s| 1.0 - s.weight()).product::<f64>();
+                DuplicateCandidate { did_a, did_b, signals, confidence, confirmations: Vec::new() }
+            })
+            .filter(|candidate| candidate.confidence >= self.threshold)
+            .collect();
+        candidates.sort_by(|x, y| y.confidence.total_cmp(&x.confidence));
+        candidates
+    }
+}
+
+/// Two identities consolidated into one for the merged federation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdentityConsolidation {
+    /// Member DID in federation A, which the consolidated identity continues
+    pub primary: Did,
+    /// Member DID in federation B, folded into the primary
+    pub merged: Did,
+    /// The person's DID in the merged federation
+    pub new_did: Did,
+    /// Credentials of both DIDs
+    pub credentials: Vec<HeldCredential>,
+    /// Combined balance of both DIDs
+    pub balance: u64,
+    pub consolidated_at: DateTime<Utc>,
+}
+
+/// Probable duplicates flagged for a merge's trust mapping review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateReview {
+    pub process_id: String,
+    /// Flagged pairs not yet consolidated
+    pub candidates: Vec<DuplicateCandidate>,
+    pub consolidations: Vec<IdentityConsolidation>,
+}
+
+impl DuplicateReview {
+    /// Flag probable duplicates among the members of a merge
+    pub fn for_merge(context: &MergeImpactContext, evidence: &IdentityEvidence, detector: &DuplicateDetector) -> Self {
+        Self {
+            process_id: context.process_id.clone(),
+            candidates: detector.detect(&context.members_a, &context.members_b, evidence, Utc::now()),
+            consolidations: Vec::new(),
+        }
+    }
+
+    fn candidate_mut(&mut self, did_a: &Did, did_b: &Did) -> LifecycleResult<&mut DuplicateCandidate> {
+        self.candidates.iter_mut()
+            .find(|c| &c.did_a == did_a && &c.did_b == did_b)
+            .ok_or_else(|| LifecycleError::IdentityError(format!(
+                "{} and {} aren't flagged as duplicates in merge process {}", did_a, did_b, self.process_id
+            )))
+    }
+
+    /// Record one DID of a flagged pair confirming they're the same person. Returns
+    /// whether both DIDs have now confirmed.
+    pub fn confirm(&mut self, did_a: &Did, did_b: &Did, confirming: &Did) -> LifecycleResult<bool> {
+        let candidate = self.candidate_mut(did_a, did_b)?;
+        if confirming != did_a && confirming != did_b {
+            return Err(LifecycleError::AuthorizationFailed(format!(
+                "Only {} or {} can confirm they are the same person", did_a, did_b
+            )));
+        }
+        if !candidate.confirmations.contains(confirming) {
+            candidate.confirmations.push(confirming.clone());
+        }
+        Ok(candidate.is_confirmed())
+    }
+
+    /// Consolidate a confirmed pair: both DIDs map to the primary's DID in the merged
+    /// federation, which receives the roles, credentials and balance of both
+    pub fn merge_identities(
+        &mut self,
+        process: &mut MergeProcess,
+        context: &mut MergeImpactContext,
+        did_a: &Did,
+        did_b: &Did,
+    ) -> LifecycleResult<IdentityConsolidation> {
+        if context.process_id != process.id || self.process_id != process.id {
+            return Err(LifecycleError::InvalidProposal(format!(
+                "Duplicate review and impact context must both be for process {}", process.id
+            )));
+        }
+        if !self.candidate_mut(did_a, did_b)?.is_confirmed() {
+            return Err(LifecycleError::AuthorizationFailed(format!(
+                "{} and {} haven't both confirmed they are the same person", did_a, did_b
+            )));
+        }
+
+        // Both DIDs map to the primary's DID, which takes the roles of both
+        let mapping = &mut process.trust_mapping;
+        let new_did = mapping.did_mappings.get(did_a).cloned().unwrap_or_else(|| did_a.clone());
+        let merged_target = mapping.did_mappings.insert(did_b.clone(), new_did.clone())
+            .unwrap_or_else(|| did_b.clone());
+        mapping.did_mappings.insert(did_a.clone(), new_did.clone());
+        if merged_target != new_did {
+            if let Some(roles) = mapping.role_assignments.remove(&merged_target) {
+                let assigned = mapping.role_assignments.entry(new_did.clone()).or_default();
+                for role in roles {
+                    if !assigned.contains(&role) {
+                        assigned.push(role);
+                    }
+                }
+            }
+        }
+        context.trust_mapping = process.trust_mapping.clone();
+
+        // The merged DID's holdings move to the primary
+        let mut credentials = context.credentials.remove(did_a).unwrap_or_default();
+        for credential in context.credentials.remove(did_b).unwrap_or_default() {
+            if !credentials.contains(&credential) {
+                credentials.push(credential);
+            }
+        }
+        context.credentials.insert(did_a.clone(), credentials.clone());
+
+        if let Some(roles) = context.current_roles.remove(did_b) {
+            let current = context.current_roles.entry(did_a.clone()).or_default();
+            for role in roles {
+                if !current.contains(&role) {
+                    current.push(role);
+                }
+            }
+        }
+
+        context.members_b.retain(|member| member != did_b);
+        if !context.members_b.contains(did_a) {
+            context.members_b.push(did_a.clone());
+        }
+
+        let balance = context.ledger_a.accounts().get(did_a).copied().unwrap_or(0)
+            .saturating_add(context.ledger_b.accounts().get(did_b).copied().unwrap_or(0));
+        context.ledger_b = rekey_account(&context.ledger_b, did_b, did_a)?;
+
+        let consolidation = IdentityConsolidation {
+            primary: did_a.clone(),
+            merged: did_b.clone(),
+            new_did,
+            credentials,
+            balance,
+            consolidated_at: Utc::now(),
+        };
+        self.candidates.retain(|c| !(&c.did_a == did_a && &c.did_b == did_b));
+        self.consolidations.push(consolidation.clone());
+        Ok(consolidation)
+    }
+}
+
+/// A copy of a ledger with one account's balance moved to another account
+fn rekey_account(ledger: &Ledger, from: &Did, to: &Did) -> LifecycleResult<Ledger> {
+    let mut rekeyed = Ledger::new();
+    for (account, balance) in ledger.accounts() {
+        let account = if account == from { to } else { account };
+        if !rekeyed.has_account(account) {
+            rekeyed.create_account(account).map_err(|e| {
+                LifecycleError::LedgerOperationFailed(format!("Failed to create account {}: {}", account, e))
+            })?;
+        }
+        if *balance > 0 {
+            rekeyed.credit(account, *balance).map_err(|e| {
+                LifecycleError::LedgerOperationFailed(format!("Failed to credit account {}: {}", account, e))
+            })?;
+        }
+    }
+    Ok(rekeyed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LineageAttestation, LineageAttestationType, MergeProposal, MergeStatus, PolicyConflictReport, PolicyMergeStrategy,
+        PreMergeBundle, QuorumConfig, TrustMapping,
+    };
+    use icn_identity::QuorumProof;
+
+    fn merge_process() -> MergeProcess {
+        let mut did_mappings = HashMap::new();
+        did_mappings.insert("did:icn:a:dana".to_string(), "did:icn:ab:dana".to_string());
+        did_mappings.insert("did:icn:b:d-smith".to_string(), "did:icn:ab:d-smith".to_string());
+        let mut role_assignments = HashMap::new();
+        role_assignments.insert("did:icn:ab:dana".to_string(), vec!["member".to_string()]);
+        role_assignments.insert("did:icn:ab:d-smith".to_string(), vec!["member".to_string(), "treasurer".to_string()]);
+
+        MergeProcess {
+            id: "merge-1".to_string(),
+            federation_a_id: "did:icn:a".to_string(),
+            federation_b_id: "did:icn:b".to_string(),
+            additional_federation_ids: Vec::new(),
+            new_federation_id: "did:icn:ab".to_string(),
+            merge_proposal: MergeProposal {
+                src_fed_a: "did:icn:a".to_string(),
+                src_fed_b: "did:icn:b".to_string(),
+                new_meta_cid: cid::Cid::default(),
+                quorum_cfg: QuorumConfig { threshold: 1, authorized_signers: vec![], weights: None },
+                challenge_window_secs: 0,
+                approval_a: None,
+                approval_b: None,
+                stages: vec![],
+                policy_merge_strategy: PolicyMergeStrategy::default(),
+                additional_sources: Vec::new(),
+            },
+            trust_mapping: TrustMapping { did_mappings, role_assignments, credential_validations: vec![] },
+            merged_policy: HashMap::new(),
+            policy_conflicts: PolicyConflictReport::default(),
+            merged_bundle: PreMergeBundle {
+                dag_roots: vec![],
+                metadata: HashMap::new(),
+                lineage: LineageAttestation {
+                    parents: vec!["did:icn:a".to_string(), "did:icn:b".to_string()],
+                    children: vec!["did:icn:ab".to_string()],
+                    typ: LineageAttestationType::Merge,
+                    proof: QuorumProof::default(),
+                    timestamp: Utc::now(),
+                    metadata: HashMap::new(),
+                },
+                proofs: vec![],
+            },
+            status: MergeStatus::Initiated,
+            start_time: Utc::now(),
+            completion_time: None,
+            completed_stages: Vec::new(),
+            checkpoint_votes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_flagged_duplicates_merge_after_both_confirm() {
+        let mut process = merge_process();
+        let mut context = MergeImpactContext::new(&process);
+        context.members_a = vec!["did:icn:a:dana".to_string(), "did:icn:a:eli".to_string()];
+        context.members_b = vec!["did:icn:b:d-smith".to_string(), "did:icn:b:fay".to_string()];
+        context.credentials.insert("did:icn:b:d-smith".to_string(), vec![HeldCredential {
+            credential_type: "MembershipCredential".to_string(),
+            issuer: "did:icn:b".to_string(),
+        }]);
+        context.ledger_a.create_account("did:icn:a:dana").unwrap();
+        context.ledger_a.credit("did:icn:a:dana", 20).unwrap();
+        context.ledger_b.create_account("did:icn:b:d-smith").unwrap();
+        context.ledger_b.credit("did:icn:b:d-smith", 30).unwrap();
+
+        let (dana, smith) = ("did:icn:a:dana".to_string(), "did:icn:b:d-smith".to_string());
+        let evidence = IdentityEvidence {
+            declared_aliases: vec![
+                DeclaredAlias { did: dana.clone(), also_known_as: smith.clone(), declared_at: Utc::now() },
+                // A one-sided claim on its own stays below a stricter threshold
+                DeclaredAlias { did: "did:icn:a:eli".to_string(), also_known_as: "did:icn:b:fay".to_string(), declared_at: Utc::now() },
+                DeclaredAlias { did: smith.clone(), also_known_as: dana.clone(), declared_at: Utc::now() },
+            ],
+            ..Default::default()
+        };
+        let mut review = DuplicateReview::for_merge(&context, &evidence, &DuplicateDetector::new().with_threshold(0.7));
+        assert_eq!(review.candidates.len(), 1);
+        assert_eq!(review.candidates[0].signals, vec![DuplicateSignal::DeclaredAlias { mutual: true }]);
+
+        // Nothing merges until both DIDs confirm, and only they can confirm
+        assert!(review.confirm(&dana, &smith, &"did:icn:a:eli".to_string()).is_err());
+        assert!(!review.confirm(&dana, &smith, &dana).unwrap());
+        assert!(review.merge_identities(&mut process, &mut context, &dana, &smith).is_err());
+        assert!(review.confirm(&dana, &smith, &smith).unwrap());
+
+        let consolidation = review.merge_identities(&mut process, &mut context, &dana, &smith).unwrap();
+        assert_eq!(consolidation.new_did, "did:icn:ab:dana");
+        assert_eq!(consolidation.balance, 50);
+        assert_eq!(consolidation.credentials.len(), 1);
+        assert_eq!(process.trust_mapping.did_mappings[&smith], "did:icn:ab:dana");
+        assert_eq!(process.trust_mapping.role_assignments["did:icn:ab:dana"], vec!["member".to_string(), "treasurer".to_string()]);
+        assert!(!process.trust_mapping.role_assignments.contains_key("did:icn:ab:d-smith"));
+        assert_eq!(context.ledger_b.accounts().get(&dana).copied(), Some(30));
+        assert!(review.candidates.is_empty());
+    }
+}
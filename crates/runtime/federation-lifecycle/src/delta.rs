@@ -0,0 +1,393 @@
+//! Differential trust bundle updates
+//!
+//! Re-issuing a full trust bundle for every membership change is heavy. Instead, changes
+//! are published as deltas (add/remove members, rotate keys) chained from the last full
+//! bundle. Each delta references the CID of the full bundle it builds on and the CID of
+//! the previous delta, so the chain can be verified and replayed into a consolidated view.
+//! Once the chain grows past a threshold it is consolidated into a new full bundle.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::{LineageAttestation, PreMergeBundle};
+use cid::Cid;
+use chrono::{DateTime, Utc};
+use icn_identity::{Did, QuorumProof};
+use multihash::{Code, MultihashDigest};
+use serde::{Deserialize, Serialize};
+use serde_ipld_dagcbor as cbor;
+use std::collections::{BTreeMap, HashMap};
+
+/// Default number of deltas after which the chain should be consolidated
+pub const DEFAULT_CONSOLIDATION_THRESHOLD: usize = 32;
+
+/// A complete, self-contained trust bundle for a federation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FullTrustBundle {
+    /// The federation this bundle describes
+    pub federation_id: Did,
+
+    /// Epoch of this bundle; incremented on every consolidation
+    pub epoch: u64,
+
+    /// Members and their current verification keys
+    pub members: BTreeMap<Did, String>,
+
+    /// DAG roots anchored by this bundle
+    pub dag_roots: Vec<Cid>,
+
+    /// Additional metadata
+    pub metadata: HashMap<String, String>,
+
+    /// When this bundle was issued
+    pub issued_at: DateTime<Utc>,
+}
+
+impl FullTrustBundle {
+    /// Serialize the bundle to CBOR bytes
+    pub fn to_cbor(&self) -> LifecycleResult<Vec<u8>> {
+        cbor::to_vec(self).map_err(|e| {
+            LifecycleError::BundleSerializationError(format!("Failed to serialize trust bundle: {}", e))
+        })
+    }
+
+    /// Calculate the CID for this bundle
+    pub fn calculate_cid(&self) -> LifecycleResult<Cid> {
+        let cbor_bytes = self.to_cbor()?;
+        let hash = Code::Sha2_256.digest(&cbor_bytes);
+        Ok(Cid::new_v1(0x71, hash))
+    }
+
+    /// Apply a single delta operation
+    fn apply(&mut self, op: &DeltaOperation) -> LifecycleResult<()> {
+        match op {
+            DeltaOperation::AddMember { did, verification_key } => {
+                if self.members.contains_key(did) {
+                    return Err(LifecycleError::TrustBundleError(format!("Member {} already present", did)));
+                }
+                self.members.insert(did.clone(), verification_key.clone());
+            }
+            DeltaOperation::RemoveMember { did } => {
+                if self.members.remove(did).is_none() {
+                    return Err(LifecycleError::TrustBundleError(format!("Member {} not present", did)));
+                }
+            }
+            DeltaOperation::RotateKey { did, new_verification_key } => {
+                match self.members.get_mut(did) {
+                    Some(key) => *key = new_verification_key.clone(),
+                    None => {
+                        return Err(LifecycleError::TrustBundleError(format!(
+                            "Cannot rotate key of unknown member {}", did
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A single change to a trust bundle
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DeltaOperation {
+    /// Admit a new member
+    AddMember { did: Did, verification_key: String },
+
+    /// Remove an existing member
+    RemoveMember { did: Did },
+
+    /// Replace a member's verification key
+    RotateKey { did: Did, new_verification_key: String },
+}
+
+/// An incremental update chained from a full trust bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustBundleDelta {
+    /// The federation this delta applies to
+    pub federation_id: Did,
+
+    /// CID of the full bundle this chain is based on
+    pub base_cid: Cid,
+
+    /// CID of the previous delta in the chain (None for the first delta)
+    pub prev_cid: Option<Cid>,
+
+    /// Position of this delta in the chain, starting at 1
+    pub sequence: u64,
+
+    /// Operations applied by this delta, in order
+    pub operations: Vec<DeltaOperation>,
+
+    /// When this delta was issued
+    pub timestamp: DateTime<Utc>,
+
+    /// Quorum proof authorizing this delta
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<QuorumProof>,
+}
+
+impl TrustBundleDelta {
+    /// Serialize the delta to CBOR bytes
+    pub fn to_cbor(&self) -> LifecycleResult<Vec<u8>> {
+        cbor::to_vec(self).map_err(|e| {
+            LifecycleError::BundleSerializationError(format!("Failed to serialize trust bundle delta: {}", e))
+        })
+    }
+
+    /// Calculate the CID for this delta
+    pub fn calculate_cid(&self) -> LifecycleResult<Cid> {
+        let cbor_bytes = self.to_cbor()?;
+        let hash = Code::Sha2_256.digest(&cbor_bytes);
+        Ok(Cid::new_v1(0x71, hash))
+    }
+}
+
+/// A full trust bundle together with the deltas issued since
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustBundleChain {
+    /// The last full bundle
+    pub base: FullTrustBundle,
+
+    /// CID of the last full bundle
+    pub base_cid: Cid,
+
+    /// Deltas issued since the last full bundle, in order
+    pub deltas: Vec<TrustBundleDelta>,
+
+    /// Number of deltas after which the chain should be consolidated
+    pub consolidation_threshold: usize,
+}
+
+impl TrustBundleChain {
+    /// Start a new chain from a full bundle
+    pub fn new(base: FullTrustBundle) -> LifecycleResult<Self> {
+        let base_cid = base.calculate_cid()?;
+        Ok(Self {
+            base,
+            base_cid,
+            deltas: Vec::new(),
+            consolidation_threshold: DEFAULT_CONSOLIDATION_THRESHOLD,
+        })
+    }
+
+    /// CID of the latest delta, if any
+    pub fn head_cid(&self) -> LifecycleResult<Option<Cid>> {
+        self.deltas.last().map(|d| d.calculate_cid()).transpose()
+    }
+
+    /// Issue a new delta on top of the chain.
+    ///
+    /// The operations are validated against the current consolidated view before the
+    /// delta is appended.
+    pub fn append(
+        &mut self,
+        operations: Vec<DeltaOperation>,
+        proof: Option<QuorumProof>,
+    ) -> LifecycleResult<&TrustBundleDelta> {
+        if operations.is_empty() {
+            return Err(LifecycleError::TrustBundleError("Delta must contain at least one operation".to_string()));
+        }
+
+        // Validate against the current view so invalid deltas never enter the chain
+        let mut view = self.consolidated_view()?;
+        for op in &operations {
+            view.apply(op)?;
+        }
+
+        let delta = TrustBundleDelta {
+            federation_id: self.base.federation_id.clone(),
+            base_cid: self.base_cid,
+            prev_cid: self.head_cid()?,
+            sequence: self.deltas.len() as u64 + 1,
+            operations,
+            timestamp: Utc::now(),
+            proof,
+        };
+
+        self.deltas.push(delta);
+        Ok(self.deltas.last().expect("delta was just pushed"))
+    }
+
+    /// Verify that every delta links to the base and to its predecessor
+    pub fn verify_chain(&self) -> LifecycleResult<()> {
+        if self.base.calculate_cid()? != self.base_cid {
+            return Err(LifecycleError::VerificationFailed("Base trust bundle CID mismatch".to_string()));
+        }
+
+        let mut prev_cid: Option<Cid> = None;
+        for (index, delta) in self.deltas.iter().enumerate() {
+            if delta.federation_id != self.base.federation_id {
+                return Err(LifecycleError::VerificationFailed(format!(
+                    "Delta {} belongs to federation {}", delta.sequence, delta.federation_id
+                )));
+            }
+            if delta.base_cid != self.base_cid {
+                return Err(LifecycleError::VerificationFailed(format!(
+                    "Delta {} is not based on the current full bundle", delta.sequence
+                )));
+            }
+            if delta.sequence != index as u64 + 1 || delta.prev_cid != prev_cid {
+                return Err(LifecycleError::VerificationFailed(format!(
+                    "Delta {} is not chained to its predecessor", delta.sequence
+                )));
+            }
+            prev_cid = Some(delta.calculate_cid()?);
+        }
+
+        Ok(())
+    }
+
+    /// Replay all deltas over the base bundle
+    pub fn consolidated_view(&self) -> LifecycleResult<FullTrustBundle> {
+        let mut view = self.base.clone();
+        for delta in &self.deltas {
+            for op in &delta.operations {
+                view.apply(op)?;
+            }
+        }
+        Ok(view)
+    }
+
+    /// Whether the chain has grown long enough to be consolidated
+    pub fn needs_consolidation(&self) -> bool {
+        self.deltas.len() >= self.consolidation_threshold
+    }
+
+    /// Fold all deltas into a new full bundle and restart the chain from it
+    pub fn consolidate(&mut self) -> LifecycleResult<&FullTrustBundle> {
+        self.verify_chain()?;
+
+        let mut consolidated = self.consolidated_view()?;
+        consolidated.epoch = self.base.epoch + 1;
+        consolidated.issued_at = Utc::now();
+        consolidated.metadata.insert("consolidated_from".to_string(), self.base_cid.to_string());
+        consolidated.metadata.insert("consolidated_deltas".to_string(), self.deltas.len().to_string());
+
+        self.base_cid = consolidated.calculate_cid()?;
+        self.base = consolidated;
+        self.deltas.clear();
+
+        Ok(&self.base)
+    }
+
+    /// Consolidate only if the threshold has been reached
+    pub fn consolidate_if_needed(&mut self) -> LifecycleResult<bool> {
+        if self.needs_consolidation() {
+            self.consolidate()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl PreMergeBundle {
+    /// Create a pre-merge bundle from the consolidated view of a trust bundle chain
+    pub fn from_trust_bundle_chain(
+        chain: &TrustBundleChain,
+        lineage: LineageAttestation,
+        proofs: Vec<QuorumProof>,
+    ) -> LifecycleResult<Self> {
+        chain.verify_chain()?;
+        let view = chain.consolidated_view()?;
+        let view_cid = view.calculate_cid()?;
+
+        let mut metadata = view.metadata.clone();
+        metadata.insert("trust_bundle_cid".to_string(), view_cid.to_string());
+        metadata.insert("trust_bundle_epoch".to_string(), view.epoch.to_string());
+        metadata.insert("trust_bundle_deltas".to_string(), chain.deltas.len().to_string());
+        metadata.insert(
+            "members".to_string(),
+            view.members.keys().cloned().collect::<Vec<_>>().join(","),
+        );
+
+        Ok(Self {
+            dag_roots: view.dag_roots,
+            metadata,
+            lineage,
+            proofs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_bundle() -> FullTrustBundle {
+        let mut members = BTreeMap::new();
+        members.insert("did:icn:alice".to_string(), "key-alice-1".to_string());
+        members.insert("did:icn:bob".to_string(), "key-bob-1".to_string());
+        FullTrustBundle {
+            federation_id: "did:icn:fed".to_string(),
+            epoch: 1,
+            members,
+            dag_roots: vec![],
+            metadata: HashMap::new(),
+            issued_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_deltas_chain_and_replay() {
+        let mut chain = TrustBundleChain::new(base_bundle()).unwrap();
+
+        chain.append(vec![DeltaOperation::AddMember {
+            did: "did:icn:carol".to_string(),
+            verification_key: "key-carol-1".to_string(),
+        }], None).unwrap();
+        chain.append(vec![
+            DeltaOperation::RemoveMember { did: "did:icn:bob".to_string() },
+            DeltaOperation::RotateKey {
+                did: "did:icn:alice".to_string(),
+                new_verification_key: "key-alice-2".to_string(),
+            },
+        ], None).unwrap();
+
+        chain.verify_chain().unwrap();
+        assert_eq!(chain.deltas[1].prev_cid, Some(chain.deltas[0].calculate_cid().unwrap()));
+
+        let view = chain.consolidated_view().unwrap();
+        assert_eq!(view.members.len(), 2);
+        assert_eq!(view.members.get("did:icn:alice"), Some(&"key-alice-2".to_string()));
+        assert!(!view.members.contains_key("did:icn:bob"));
+    }
+
+    #[test]
+    fn test_invalid_delta_is_rejected() {
+        let mut chain = TrustBundleChain::new(base_bundle()).unwrap();
+
+        let result = chain.append(vec![DeltaOperation::RemoveMember { did: "did:icn:mallory".to_string() }], None);
+        assert!(result.is_err());
+        assert!(chain.deltas.is_empty());
+    }
+
+    #[test]
+    fn test_tampered_chain_fails_verification() {
+        let mut chain = TrustBundleChain::new(base_bundle()).unwrap();
+        chain.append(vec![DeltaOperation::RemoveMember { did: "did:icn:bob".to_string() }], None).unwrap();
+        chain.append(vec![DeltaOperation::RemoveMember { did: "did:icn:alice".to_string() }], None).unwrap();
+
+        chain.deltas.remove(0);
+        assert!(chain.verify_chain().is_err());
+    }
+
+    #[test]
+    fn test_consolidation_restarts_chain() {
+        let mut chain = TrustBundleChain::new(base_bundle()).unwrap();
+        chain.consolidation_threshold = 2;
+
+        chain.append(vec![DeltaOperation::RemoveMember { did: "did:icn:bob".to_string() }], None).unwrap();
+        assert!(!chain.consolidate_if_needed().unwrap());
+
+        chain.append(vec![DeltaOperation::AddMember {
+            did: "did:icn:dave".to_string(),
+            verification_key: "key-dave-1".to_string(),
+        }], None).unwrap();
+        let expected = chain.consolidated_view().unwrap().members;
+
+        assert!(chain.consolidate_if_needed().unwrap());
+        assert!(chain.deltas.is_empty());
+        assert_eq!(chain.base.epoch, 2);
+        assert_eq!(chain.base.members, expected);
+        chain.verify_chain().unwrap();
+    }
+}
@@ -1,6 +1,8 @@
 use crate::error::{LifecycleError, LifecycleResult};
 use crate::types::PartitionMap;
 use icn_economics::Ledger;
+use icn_economics::capital::{CapitalAccount, union_capital_accounts, split_capital_accounts, total_capital};
+use icn_economics::rounding::RoundingPolicy;
 use icn_identity::Did;
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
@@ -72,6 +74,19 @@ pub fn union_ledgers_impl(ledger_a: &Ledger, ledger_b: &Ledger) -> LifecycleResu
 /// but remains available for internal use.
 #[cfg(test)]
 pub fn shard_ledger(parent_ledger: &Ledger, partition_map: &PartitionMap) -> LifecycleResult<(Ledger, Ledger)> {
+    shard_ledger_with_rounding(parent_ledger, partition_map, &RoundingPolicy::default())
+}
+
+/// Shard a ledger into two based on a partition map, rounding under `rounding`.
+///
+/// Accounts held by the federation itself, in neither partition, are split between the
+/// two ledgers in proportion to the members each side takes. The residue the policy
+/// keeps back goes to the side taking more members, A on a tie, so no unit is lost.
+pub fn shard_ledger_with_rounding(
+    parent_ledger: &Ledger,
+    partition_map: &PartitionMap,
+    rounding: &RoundingPolicy,
+) -> LifecycleResult<(Ledger, Ledger)> {
     // Create two new ledgers
     let mut ledger_a = Ledger::new();
     let mut ledger_b = Ledger::new();
@@ -112,8 +127,19 @@ pub fn shard_ledger(parent_ledger: &Ledger, partition_map: &PartitionMap) -> Lif
                 })?;
             }
         } else {
-            // Account not in either partition, log warning
-            warn!("Account {} not found in partition map, balance will be lost", account);
+            // Account held by the federation, split between both sides
+            let (to_a, to_b) = split_shared_balance(*balance, partition_map, rounding)?;
+            for (ledger, share, side) in [(&mut ledger_a, to_a, "A"), (&mut ledger_b, to_b, "B")] {
+                ledger.create_account(&account_did).map_err(|e| {
+                    LifecycleError::LedgerOperationFailed(format!("Failed to create shared account in ledger {}: {}", side, e))
+                })?;
+                if share > 0 {
+                    ledger.credit(&account_did, share).map_err(|e| {
+                        LifecycleError::LedgerOperationFailed(format!("Failed to credit shared account in ledger {}: {}", side, e))
+                    })?;
+                }
+            }
+            debug!("Shared account {} split {} / {}", account, to_a, to_b);
         }
     }
     
@@ -129,7 +155,25 @@ pub fn shard_ledger(parent_ledger: &Ledger, partition_map: &PartitionMap) -> Lif
 /// Core functionality for sharding ledgers, not marked with #[cfg(test)]
 /// so it can be used in production code
 pub fn shard_ledger_impl(parent_ledger: &Ledger, partition_map: &PartitionMap) -> LifecycleResult<(Ledger, Ledger)> {
-    shard_ledger(parent_ledger, partition_map)
+    shard_ledger_with_rounding(parent_ledger, partition_map, &RoundingPolicy::default())
+}
+
+/// The amounts of a federation-held balance going to ledger A and B, in proportion to
+/// the members each side takes
+fn split_shared_balance(
+    balance: u64,
+    partition_map: &PartitionMap,
+    rounding: &RoundingPolicy,
+) -> LifecycleResult<(u64, u64)> {
+    let (members_a, members_b) = (partition_map.members_a.len() as u64, partition_map.members_b.len() as u64);
+    let split = rounding.split(balance, &[members_a, members_b])
+        .map_err(|e| LifecycleError::EconomicInconsistency(e.to_string()))?;
+    let (to_a, to_b) = (split.shares[0], split.shares[1]);
+    if members_a >= members_b {
+        Ok((to_a + split.residue, to_b))
+    } else {
+        Ok((to_a, to_b + split.residue))
+    }
 }
 
 /// Verify that economic balance is preserved during operations
@@ -193,6 +237,52 @@ pub fn create_transfer_plan(
     Ok(transfer_plan)
 }
 
+/// Union the member capital accounts of two merging federations into the new federation
+pub fn union_capital_accounts_impl(
+    accounts_a: &[CapitalAccount],
+    accounts_b: &[CapitalAccount],
+    new_federation_id: &str,
+    merged_at: i64,
+) -> LifecycleResult<Vec<CapitalAccount>> {
+    let merged = union_capital_accounts(accounts_a, accounts_b, new_federation_id, merged_at)
+        .map_err(|e| LifecycleError::EconomicInconsistency(e.to_string()))?;
+
+    let source_total = total_capital(accounts_a) + total_capital(accounts_b);
+    if total_capital(&merged) != source_total {
+        return Err(LifecycleError::EconomicInconsistency(format!(
+            "Capital mismatch: source total {} != merged total {}", source_total, total_capital(&merged)
+        )));
+    }
+
+    debug!("Merged {} capital accounts into {}", merged.len(), new_federation_id);
+    Ok(merged)
+}
+
+/// Allocate a splitting federation's member capital accounts according to the partition map
+pub fn shard_capital_accounts(
+    accounts: &[CapitalAccount],
+    partition_map: &PartitionMap,
+    federation_a_id: &str,
+    federation_b_id: &str,
+) -> LifecycleResult<(Vec<CapitalAccount>, Vec<CapitalAccount>)> {
+    let (accounts_a, accounts_b) = split_capital_accounts(
+        accounts,
+        federation_a_id,
+        &partition_map.members_a,
+        federation_b_id,
+        &partition_map.members_b,
+    ).map_err(|e| LifecycleError::EconomicInconsistency(e.to_string()))?;
+
+    let child_total = total_capital(&accounts_a) + total_capital(&accounts_b);
+    if total_capital(accounts) != child_total {
+        return Err(LifecycleError::EconomicInconsistency(format!(
+            "Capital mismatch: parent total {} != child total {}", total_capital(accounts), child_total
+        )));
+    }
+
+    Ok((accounts_a, accounts_b))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,4 +420,40 @@ mod tests {
         
         assert_eq!(total_parent, total_a + total_b);
     }
+
+    #[test]
+    fn test_shard_ledger_splits_shared_accounts_to_the_unit() {
+        let mut partition_map = create_test_partition_map();
+        partition_map.members_b.push("did:icn:test:5".to_string());
+
+        let policies = ["down", "half_even", "down(unit=10, residue=reserve)", "half_even(unit=100, residue=reserve)"];
+        for spec in policies {
+            let rounding = RoundingPolicy::from_economic_model(spec).unwrap();
+            for treasury in [0, 1, 2, 999, 1_001, 12_345] {
+                let mut parent_ledger = create_test_ledger();
+                parent_ledger.create_account("did:icn:federation:treasury").unwrap();
+                if treasury > 0 {
+                    parent_ledger.credit("did:icn:federation:treasury", treasury).unwrap();
+                }
+
+                let (ledger_a, ledger_b) = shard_ledger_with_rounding(&parent_ledger, &partition_map, &rounding).unwrap();
+                let to_a = ledger_a.balance("did:icn:federation:treasury").unwrap();
+                let to_b = ledger_b.balance("did:icn:federation:treasury").unwrap();
+                assert_eq!(to_a + to_b, treasury, "{} on {}", spec, treasury);
+
+                let total_parent: u64 = parent_ledger.accounts().values().sum();
+                let total_children: u64 = ledger_a.accounts().values().sum::<u64>() + ledger_b.accounts().values().sum::<u64>();
+                assert_eq!(total_parent, total_children, "{} on {}", spec, treasury);
+            }
+        }
+
+        // A third of 1_001 in hundreds to A leaves 1 for B, which takes more members
+        let hundreds = RoundingPolicy::from_economic_model("half_even(unit=100, residue=reserve)").unwrap();
+        let mut parent_ledger = create_test_ledger();
+        parent_ledger.create_account("did:icn:federation:treasury").unwrap();
+        parent_ledger.credit("did:icn:federation:treasury", 1_001).unwrap();
+        let (ledger_a, ledger_b) = shard_ledger_with_rounding(&parent_ledger, &partition_map, &hundreds).unwrap();
+        assert_eq!(ledger_a.balance("did:icn:federation:treasury").unwrap(), 300);
+        assert_eq!(ledger_b.balance("did:icn:federation:treasury").unwrap(), 701);
+    }
 } 
\ No newline at end of file
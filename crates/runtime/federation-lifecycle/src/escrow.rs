@@ -0,0 +1,676 @@
+//! Escrow of disputed assets during contested splits
+//!
+//! When the two sides of a split can't agree on who gets an allocation, the split
+//! shouldn't wait for arbitration to finish. The contested allocations are flagged and
+//! taken out of the partition map into a [`DisputeEscrow`]: an account controlled
+//! jointly by a signer for each resulting federation and an arbiter, of whom a
+//! threshold (two of the three by default) must sign to move anything out.
+//!
+//! Release is tied to the outcome of the dispute. The arbiter records the outcome,
+//! signed, saying for each asset whether it goes to one side, is divided between them,
+//! or stays as the partition map first allocated it. Releasing then pays out exactly
+//! that outcome into the partition map; the threshold signs over the outcome, so
+//! signers can't release on terms other than the ones decided.
+//!
+//! Every step is appended to an audit trail whose entries are content-addressed and
+//! chained by CID like trust bundle deltas. [`DisputeEscrow::anchor`] records the
+//! head of the trail in a lineage attestation, so anchoring the lineage anchors the
+//! whole history of the escrow.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::{LineageAttestation, PartitionMap, QuorumConfig, ResourceAllocation, SplitProcess};
+use chrono::{DateTime, Utc};
+use cid::Cid;
+use icn_economics::rounding::RoundingPolicy;
+use icn_identity::{Did, IdentityId, Signature};
+use multihash::{Code, MultihashDigest};
+use serde::{Deserialize, Serialize};
+use serde_ipld_dagcbor as cbor;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// Lineage metadata key holding the CID of the latest escrow audit entry
+pub const ESCROW_AUDIT_CID_METADATA_KEY: &str = "escrow_audit_cid";
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u64 = 10_000;
+
+/// Signers needed to release escrowed assets unless configured otherwise
+pub const DEFAULT_ESCROW_THRESHOLD: u32 = 2;
+
+/// What kind of allocation an escrowed asset was taken from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EscrowedAssetKind {
+    /// A shared resource, keyed by resource ID
+    Resource,
+    /// A member's ledger balance, keyed by member DID
+    LedgerBalance,
+}
+
+/// A contested allocation held in escrow
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EscrowedAsset {
+    /// Resource ID or member DID
+    pub asset_id: String,
+
+    pub kind: EscrowedAssetKind,
+
+    /// What the partition map allocated to federation A before the asset was flagged
+    pub allocated_a: u64,
+
+    /// What the partition map allocated to federation B before the asset was flagged
+    pub allocated_b: u64,
+
+    /// Metadata of the resource allocation, restored on release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl EscrowedAsset {
+    /// The whole amount held
+    pub fn amount(&self) -> u64 {
+        self.allocated_a.saturating_add(self.allocated_b)
+    }
+}
+
+/// How a dispute decided one escrowed asset
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EscrowAward {
+    /// Everything to federation A
+    ToA,
+    /// Everything to federation B
+    ToB,
+    /// Federation A gets `a_bps` of the amount, federation B the rest
+    Divided { a_bps: u64 },
+    /// As the partition map allocated it; the claim was withdrawn or dismissed
+    AsAllocated,
+}
+
+impl EscrowAward {
+    /// The amounts going to federation A and B. A divided asset is split under
+    /// `rounding`; the escrow has no reserve of its own, so a residue the policy keeps
+    /// back goes to the side awarded more, A on a tie.
+    fn split(&self, asset: &EscrowedAsset, rounding: &RoundingPolicy) -> LifecycleResult<(u64, u64)> {
+        let amount = asset.amount();
+        Ok(match self {
+            EscrowAward::ToA => (amount, 0),
+            EscrowAward::ToB => (0, amount),
+            EscrowAward::Divided { a_bps } => {
+                let split = rounding.split(amount, &[*a_bps, BASIS_POINTS - *a_bps])
+                    .map_err(|e| LifecycleError::EconomicInconsistency(e.to_string()))?;
+                let (to_a, to_b) = (split.shares[0], split.shares[1]);
+                if *a_bps * 2 >= BASIS_POINTS {
+                    (to_a + split.residue, to_b)
+                } else {
+                    (to_a, to_b + split.residue)
+                }
+            }
+            EscrowAward::AsAllocated => (asset.allocated_a, asset.allocated_b),
+        })
+    }
+}
+
+/// The arbitration outcome for every asset in an escrow
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DisputeOutcome {
+    /// Reference of the dispute case the outcome was decided in
+    pub dispute_ref: String,
+
+    /// Award per asset ID
+    pub awards: BTreeMap<String, EscrowAward>,
+
+    pub decided_at: DateTime<Utc>,
+}
+
+/// Where an escrow stands
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EscrowStatus {
+    /// Assets are held while the dispute is arbitrated
+    Holding,
+    /// The arbiter recorded the outcome; waiting for the release signatures
+    OutcomeRecorded,
+    /// The assets were paid out as the outcome decided
+    Released,
+}
+
+/// A step in an escrow's history
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EscrowAction {
+    /// The escrow was opened with its flagged assets
+    Opened { assets: Vec<String> },
+    /// The arbiter recorded the outcome, with its hash
+    OutcomeRecorded { dispute_ref: String, outcome_hash: String },
+    /// The outcome was paid out, signed by the listed signers
+    Released { signers: Vec<Did> },
+}
+
+/// A content-addressed entry in an escrow's audit trail
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EscrowAuditEntry {
+    pub escrow_id: String,
+
+    /// Position in the trail, from 1
+    pub sequence: u64,
+
+    /// CID of the entry before this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cid: Option<Cid>,
+
+    pub action: EscrowAction,
+
+    /// Who took the step
+    pub actor: Did,
+
+    pub timestamp: DateTime<Utc>,
+}
+
+impl EscrowAuditEntry {
+    /// Serialize the entry to CBOR bytes
+    pub fn to_cbor(&self) -> LifecycleResult<Vec<u8>> {
+        cbor::to_vec(self).map_err(|e| {
+            LifecycleError::BundleSerializationError(format!("Failed to serialize escrow audit entry: {}", e))
+        })
+    }
+
+    /// Calculate the CID for this entry
+    pub fn calculate_cid(&self) -> LifecycleResult<Cid> {
+        let cbor_bytes = self.to_cbor()?;
+        let hash = Code::Sha2_256.digest(&cbor_bytes);
+        Ok(Cid::new_v1(0x71, hash))
+    }
+}
+
+/// The parties controlling an escrow
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EscrowParties {
+    /// Signs for federation A
+    pub signer_a: Did,
+    /// Signs for federation B
+    pub signer_b: Did,
+    /// Decides the dispute and can break a deadlock between the sides
+    pub arbiter: Did,
+}
+
+/// Contested allocations of a split, held until their dispute is decided
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisputeEscrow {
+    pub id: String,
+
+    /// The split process whose allocations are held
+    pub split_process_id: String,
+
+    /// DID of the escrow account the assets are held in
+    pub account_did: Did,
+
+    pub federation_a_id: Did,
+    pub federation_b_id: Did,
+    pub parties: EscrowParties,
+
+    /// Who must sign a release, and how many of them
+    pub release_quorum: QuorumConfig,
+
+    pub assets: Vec<EscrowedAsset>,
+    pub status: EscrowStatus,
+    pub outcome: Option<DisputeOutcome>,
+
+    /// How divided awards are rounded when they are released
+    #[serde(default)]
+    pub rounding: RoundingPolicy,
+
+    /// Every step taken, oldest first
+    pub audit_trail: Vec<EscrowAuditEntry>,
+}
+
+/// Remove a flagged allocation from both sides of a partition map
+fn take_allocation(partition_map: &mut PartitionMap, asset_id: &str) -> Option<EscrowedAsset> {
+    let a = partition_map.resources_a.remove(asset_id);
+    let b = partition_map.resources_b.remove(asset_id);
+    if a.is_some() || b.is_some() {
+        let metadata = a.iter().chain(b.iter()).find_map(|r| r.metadata.clone());
+        return Some(EscrowedAsset {
+            asset_id: asset_id.to_string(),
+            kind: EscrowedAssetKind::Resource,
+            allocated_a: a.map(|r| r.amount).unwrap_or(0),
+            allocated_b: b.map(|r| r.amount).unwrap_or(0),
+            metadata,
+        });
+    }
+
+    let a = partition_map.ledger_a.remove(asset_id);
+    let b = partition_map.ledger_b.remove(asset_id);
+    if a.is_none() && b.is_none() {
+        return None;
+    }
+    Some(EscrowedAsset {
+        asset_id: asset_id.to_string(),
+        kind: EscrowedAssetKind::LedgerBalance,
+        allocated_a: a.unwrap_or(0),
+        allocated_b: b.unwrap_or(0),
+        metadata: None,
+    })
+}
+
+/// Add a released amount to one side of a partition map
+fn credit(resources: &mut HashMap<String, ResourceAllocation>, ledger: &mut HashMap<Did, u64>, asset: &EscrowedAsset, amount: u64) {
+    if amount == 0 {
+        return;
+    }
+    match asset.kind {
+        EscrowedAssetKind::Resource => {
+            let allocation = resources.entry(asset.asset_id.clone()).or_insert_with(|| ResourceAllocation {
+                resource_id: asset.asset_id.clone(),
+                amount: 0,
+                metadata: asset.metadata.clone(),
+            });
+            allocation.amount += amount;
+        }
+        EscrowedAssetKind::LedgerBalance => {
+            *ledger.entry(asset.asset_id.clone()).or_insert(0) += amount;
+        }
+    }
+}
+
+impl DisputeEscrow {
+    /// Flag contested allocations of a split and move them out of its partition map
+    /// into a new escrow. Each flagged ID names a resource or a member's ledger balance
+    /// allocated to either side.
+    pub fn open(
+        process: &SplitProcess,
+        partition_map: &mut PartitionMap,
+        flagged: &[String],
+        parties: EscrowParties,
+        threshold: u32,
+    ) -> LifecycleResult<Self> {
+        if flagged.is_empty() {
+            return Err(LifecycleError::InvalidProposal("An escrow needs at least one flagged allocation".to_string()));
+        }
+        if !(2..=3).contains(&threshold) {
+            return Err(LifecycleError::InvalidProposal(format!(
+                "Escrow releases need 2 or 3 of the 3 signers, not {}", threshold
+            )));
+        }
+        let signers = vec![parties.signer_a.clone(), parties.signer_b.clone(), parties.arbiter.clone()];
+        if (1..signers.len()).any(|i| signers[..i].contains(&signers[i])) {
+            return Err(LifecycleError::InvalidProposal("Escrow signers must be three different identities".to_string()));
+        }
+
+        // Check every flag before touching the partition map
+        for asset_id in flagged {
+            let known = partition_map.resources_a.contains_key(asset_id)
+                || partition_map.resources_b.contains_key(asset_id)
+                || partition_map.ledger_a.contains_key(asset_id)
+                || partition_map.ledger_b.contains_key(asset_id);
+            if !known {
+                return Err(LifecycleError::PartitionMapError(format!(
+                    "{} is not allocated in the partition map of split {}", asset_id, process.id
+                )));
+            }
+        }
+        let mut assets = Vec::new();
+        for asset_id in flagged {
+            if assets.iter().any(|a: &EscrowedAsset| &a.asset_id == asset_id) {
+                continue;
+            }
+            assets.extend(take_allocation(partition_map, asset_id));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut escrow = Self {
+            account_did: format!("did:icn:escrow:{}", id),
+            id,
+            split_process_id: process.id.clone(),
+            federation_a_id: process.federation_a_id.clone(),
+            federation_b_id: process.federation_b_id.clone(),
+            parties,
+            release_quorum: QuorumConfig { threshold, authorized_signers: signers, weights: None },
+            assets,
+            status: EscrowStatus::Holding,
+            outcome: None,
+            rounding: RoundingPolicy::default(),
+            audit_trail: Vec::new(),
+        };
+        let opened = EscrowAction::Opened { assets: escrow.assets.iter().map(|a| a.asset_id.clone()).collect() };
+        escrow.append_audit(opened, process.original_federation_id.clone())?;
+
+        tracing::info!(
+            "Opened escrow {} holding {} contested allocations of split {}",
+            escrow.id, escrow.assets.len(), escrow.split_process_id
+        );
+        Ok(escrow)
+    }
+
+    /// Round divided awards under the splitting federation's rounding policy
+    pub fn with_rounding(mut self, rounding: RoundingPolicy) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Total held in the escrow
+    pub fn total_held(&self) -> u64 {
+        self.assets.iter().map(|a| a.amount()).sum()
+    }
+
+    fn append_audit(&mut self, action: EscrowAction, actor: Did) -> LifecycleResult<&EscrowAuditEntry> {
+        let entry = EscrowAuditEntry {
+            escrow_id: self.id.clone(),
+            sequence: self.audit_trail.len() as u64 + 1,
+            prev_cid: self.head_cid()?,
+            action,
+            actor,
+            timestamp: Utc::now(),
+        };
+        self.audit_trail.push(entry);
+        Ok(self.audit_trail.last().expect("entry was just pushed"))
+    }
+
+    /// CID of the latest audit entry
+    pub fn head_cid(&self) -> LifecycleResult<Option<Cid>> {
+        self.audit_trail.last().map(|e| e.calculate_cid()).transpose()
+    }
+
+    /// Check that every audit entry links to the one before it
+    pub fn verify_audit_trail(&self) -> LifecycleResult<bool> {
+        let mut prev = None;
+        for (i, entry) in self.audit_trail.iter().enumerate() {
+            if entry.escrow_id != self.id || entry.sequence != i as u64 + 1 || entry.prev_cid != prev {
+                return Ok(false);
+            }
+            prev = Some(entry.calculate_cid()?);
+        }
+        Ok(true)
+    }
+
+    /// Record the head of the audit trail in a lineage attestation, so anchoring the
+    /// lineage anchors the escrow's history. Returns the CID.
+    pub fn anchor(&self, lineage: &mut LineageAttestation) -> LifecycleResult<Cid> {
+        let cid = self.head_cid()?.ok_or_else(|| {
+            LifecycleError::InvalidDagState(format!("Escrow {} has no audit trail", self.id))
+        })?;
+        lineage.metadata.insert(ESCROW_AUDIT_CID_METADATA_KEY.to_string(), cid.to_string());
+        Ok(cid)
+    }
+
+    /// Bytes the arbiter signs to record `outcome`
+    pub fn outcome_payload(&self, outcome: &DisputeOutcome) -> LifecycleResult<Vec<u8>> {
+        cbor::to_vec(&(&self.id, outcome)).map_err(|e| {
+            LifecycleError::BundleSerializationError(format!("Failed to serialize dispute outcome: {}", e))
+        })
+    }
+
+    /// Bytes the release signers sign: the escrow, the outcome being paid out and how
+    /// divided awards are rounded
+    pub fn release_payload(&self) -> LifecycleResult<Vec<u8>> {
+        let outcome = self.outcome.as_ref().ok_or_else(|| {
+            LifecycleError::InvalidFederationState(format!("Escrow {} has no recorded outcome", self.id))
+        })?;
+        cbor::to_vec(&("release", &self.id, &self.account_did, outcome, &self.rounding)).map_err(|e| {
+            LifecycleError::BundleSerializationError(format!("Failed to serialize escrow release: {}", e))
+        })
+    }
+
+    /// Record the dispute's outcome, signed by the arbiter. Every held asset needs an
+    /// award, and nothing else may be awarded.
+    pub fn record_outcome(&mut self, outcome: DisputeOutcome, arbiter_signature: &Signature) -> LifecycleResult<()> {
+        if self.status != EscrowStatus::Holding {
+            return Err(LifecycleError::InvalidFederationState(format!(
+                "Escrow {} is {:?}, not holding", self.id, self.status
+            )));
+        }
+        for asset in &self.assets {
+            if !outcome.awards.contains_key(&asset.asset_id) {
+                return Err(LifecycleError::InvalidProposal(format!(
+                    "Outcome {} doesn't award escrowed asset {}", outcome.dispute_ref, asset.asset_id
+                )));
+            }
+        }
+        for (asset_id, award) in &outcome.awards {
+            if !self.assets.iter().any(|a| &a.asset_id == asset_id) {
+                return Err(LifecycleError::InvalidProposal(format!(
+                    "Outcome {} awards {}, which escrow {} doesn't hold", outcome.dispute_ref, asset_id, self.id
+                )));
+            }
+            if let EscrowAward::Divided { a_bps } = award {
+                if *a_bps > BASIS_POINTS {
+                    return Err(LifecycleError::InvalidProposal(format!(
+                        "Award of {} bps for {} is more than the whole asset", a_bps, asset_id
+                    )));
+                }
+            }
+        }
+
+        let payload = self.outcome_payload(&outcome)?;
+        let arbiter = IdentityId::new(&self.parties.arbiter);
+        let signed = icn_identity::verify_signature(&payload, arbiter_signature, &arbiter)
+            .map_err(|e| LifecycleError::VerificationFailed(format!("Arbiter signature failed to verify: {}", e)))?;
+        if !signed {
+            return Err(LifecycleError::VerificationFailed(format!(
+                "Outcome {} is not signed by arbiter {}", outcome.dispute_ref, self.parties.arbiter
+            )));
+        }
+
+        let action = EscrowAction::OutcomeRecorded {
+            dispute_ref: outcome.dispute_ref.clone(),
+            outcome_hash: Sha256::digest(&payload).iter().map(|b| format!("{:02x}", b)).collect(),
+        };
+        self.outcome = Some(outcome);
+        self.status = EscrowStatus::OutcomeRecorded;
+        self.append_audit(action, self.parties.arbiter.clone())?;
+        Ok(())
+    }
+
+    /// Signers whose signature over the release payload is valid, each counted once
+    fn valid_release_signers(&self, signatures: &[(Did, Signature)]) -> LifecycleResult<Vec<Did>> {
+        let payload = self.release_payload()?;
+        let mut signers: Vec<Did> = Vec::new();
+        for (signer, signature) in signatures {
+            if signers.contains(signer) || !self.release_quorum.authorized_signers.contains(signer) {
+                continue;
+            }
+            let valid = icn_identity::verify_signature(&payload, signature, &IdentityId::new(signer))
+                .map_err(|e| LifecycleError::VerificationFailed(format!("Signature from {} failed to verify: {}", signer, e)))?;
+            if valid {
+                signers.push(signer.clone());
+            }
+        }
+        Ok(signers)
+    }
+
+    /// Pay out the recorded outcome into the split's partition map once enough of the
+    /// escrow's signers have signed the release. Returns the amounts credited to
+    /// federation A and B per asset.
+    pub fn release(
+        &mut self,
+        partition_map: &mut PartitionMap,
+        signatures: &[(Did, Signature)],
+    ) -> LifecycleResult<BTreeMap<String, (u64, u64)>> {
+        if self.status != EscrowStatus::OutcomeRecorded {
+            return Err(LifecycleError::InvalidFederationState(format!(
+                "Escrow {} can't be released while {:?}", self.id, self.status
+            )));
+        }
+        let signers = self.valid_release_signers(signatures)?;
+        if (signers.len() as u32) < self.release_quorum.threshold {
+            return Err(LifecycleError::QuorumNotMet(format!(
+                "Releasing escrow {} needs {} signers, got {}", self.id, self.release_quorum.threshold, signers.len()
+            )));
+        }
+
+        let outcome = self.outcome.clone().expect("status is OutcomeRecorded");
+        // Every award is worked out before anything is credited
+        let awarded = self.assets.iter()
+            .map(|asset| outcome.awards[&asset.asset_id].split(asset, &self.rounding))
+            .collect::<LifecycleResult<Vec<_>>>()?;
+        let mut released = BTreeMap::new();
+        for (asset, (to_a, to_b)) in self.assets.iter().zip(awarded) {
+            credit(&mut partition_map.resources_a, &mut partition_map.ledger_a, asset, to_a);
+            credit(&mut partition_map.resources_b, &mut partition_map.ledger_b, asset, to_b);
+            released.insert(asset.asset_id.clone(), (to_a, to_b));
+        }
+
+        self.status = EscrowStatus::Released;
+        let actor = signers[0].clone();
+        self.append_audit(EscrowAction::Released { signers }, actor)?;
+
+        tracing::info!("Released escrow {} as decided in {}", self.id, outcome.dispute_ref);
+        Ok(released)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LineageAttestationType, SplitProposal, SplitStatus, SplitBundle, TrustMapping};
+    use icn_identity::QuorumProof;
+
+    fn split_process() -> SplitProcess {
+        let lineage = LineageAttestation {
+            parents: vec!["did:icn:fed".to_string()],
+            children: vec!["did:icn:fed-a".to_string(), "did:icn:fed-b".to_string()],
+            typ: LineageAttestationType::Split,
+            proof: QuorumProof::default(),
+            timestamp: Utc::now(),
+            metadata: HashMap::new(),
+        };
+        let bundle = SplitBundle::new(Cid::default(), partition_map(), lineage, vec![]);
+        let empty_mapping = || TrustMapping {
+            did_mappings: HashMap::new(),
+            role_assignments: HashMap::new(),
+            credential_validations: vec![],
+        };
+        SplitProcess {
+            id: "split-1".to_string(),
+            original_federation_id: "did:icn:fed".to_string(),
+            federation_a_id: "did:icn:fed-a".to_string(),
+            federation_b_id: "did:icn:fed-b".to_string(),
+            split_proposal: SplitProposal {
+                parent_fed: "did:icn:fed".to_string(),
+                partition_map_cid: Cid::default(),
+                quorum_cfg: QuorumConfig { threshold: 1, authorized_signers: vec![], weights: None },
+                challenge_window_secs: 86_400,
+                approval: None,
+                federation_a_id: None,
+                federation_b_id: None,
+            },
+            trust_mapping_a: empty_mapping(),
+            trust_mapping_b: empty_mapping(),
+            policy_a: HashMap::new(),
+            policy_b: HashMap::new(),
+            bundle_a: bundle.clone(),
+            bundle_b: bundle,
+            status: SplitStatus::Initiated,
+            start_time: Utc::now(),
+            completion_time: None,
+            completed_phases: Vec::new(),
+        }
+    }
+
+    fn partition_map() -> PartitionMap {
+        let resource = |amount| ResourceAllocation { resource_id: "warehouse".to_string(), amount, metadata: None };
+        PartitionMap {
+            members_a: vec!["did:icn:alice".to_string()],
+            members_b: vec!["did:icn:bob".to_string()],
+            resources_a: HashMap::from([("warehouse".to_string(), resource(600))]),
+            resources_b: HashMap::from([("warehouse".to_string(), resource(400))]),
+            ledger_a: HashMap::from([("did:icn:alice".to_string(), 50)]),
+            ledger_b: HashMap::from([("did:icn:bob".to_string(), 80)]),
+        }
+    }
+
+    fn parties() -> EscrowParties {
+        EscrowParties {
+            signer_a: "did:icn:fed-a:treasurer".to_string(),
+            signer_b: "did:icn:fed-b:treasurer".to_string(),
+            arbiter: "did:icn:arbiter".to_string(),
+        }
+    }
+
+    fn signature() -> Signature {
+        Signature(vec![9; 64])
+    }
+
+    #[test]
+    fn test_escrow_holds_contested_allocations_until_outcome_is_signed() {
+        let process = split_process();
+        let mut map = partition_map();
+        let mut escrow = DisputeEscrow::open(
+            &process, &mut map, &["warehouse".to_string(), "did:icn:bob".to_string()], parties(), DEFAULT_ESCROW_THRESHOLD,
+        ).unwrap();
+
+        // The flagged allocations left the partition map
+        assert!(!map.resources_a.contains_key("warehouse") && !map.resources_b.contains_key("warehouse"));
+        assert!(!map.ledger_b.contains_key("did:icn:bob"));
+        assert_eq!(map.ledger_a.get("did:icn:alice"), Some(&50));
+        assert_eq!(escrow.total_held(), 1_080);
+        assert!(DisputeEscrow::open(&process, &mut partition_map(), &["nothing".to_string()], parties(), 2).is_err());
+
+        // Nothing moves before the outcome is recorded
+        assert!(escrow.release(&mut map, &[("did:icn:arbiter".to_string(), signature())]).is_err());
+
+        let outcome = DisputeOutcome {
+            dispute_ref: "arbitration-7".to_string(),
+            awards: BTreeMap::from([
+                ("warehouse".to_string(), EscrowAward::Divided { a_bps: 7_500 }),
+                ("did:icn:bob".to_string(), EscrowAward::AsAllocated),
+            ]),
+            decided_at: Utc::now(),
+        };
+        let mut incomplete = outcome.clone();
+        incomplete.awards.remove("did:icn:bob");
+        assert!(escrow.record_outcome(incomplete, &signature()).is_err());
+        assert!(escrow.record_outcome(outcome.clone(), &Signature(vec![1, 2, 3, 4, 5, 6, 7, 8])).is_err());
+        escrow.record_outcome(outcome.clone(), &signature()).unwrap();
+
+        // One signer isn't enough, nor is an outsider or the same signer twice
+        let too_few = [
+            ("did:icn:fed-a:treasurer".to_string(), signature()),
+            ("did:icn:fed-a:treasurer".to_string(), signature()),
+            ("did:icn:mallory".to_string(), signature()),
+        ];
+        assert!(matches!(escrow.release(&mut map, &too_few), Err(LifecycleError::QuorumNotMet(_))));
+
+        let released = escrow.release(&mut map, &[
+            ("did:icn:fed-a:treasurer".to_string(), signature()),
+            ("did:icn:arbiter".to_string(), signature()),
+        ]).unwrap();
+        assert_eq!(released["warehouse"], (750, 250));
+        assert_eq!(map.resources_a["warehouse"].amount, 750);
+        assert_eq!(map.resources_b["warehouse"].amount, 250);
+        assert_eq!(map.ledger_b.get("did:icn:bob"), Some(&80));
+        assert_eq!(escrow.status, EscrowStatus::Released);
+
+        // The trail records every step and is anchored by its head
+        assert_eq!(escrow.audit_trail.len(), 3);
+        assert!(escrow.verify_audit_trail().unwrap());
+        let mut lineage = process.bundle_a.lineage.clone();
+        let cid = escrow.anchor(&mut lineage).unwrap();
+        assert_eq!(lineage.metadata[ESCROW_AUDIT_CID_METADATA_KEY], cid.to_string());
+
+        let mut tampered = escrow.clone();
+        tampered.audit_trail[1].actor = "did:icn:mallory".to_string();
+        assert!(!tampered.verify_audit_trail().unwrap());
+    }
+
+    #[test]
+    fn test_divided_awards_release_every_unit() {
+        let warehouse = EscrowedAsset {
+            asset_id: "warehouse".to_string(),
+            kind: EscrowedAssetKind::Resource,
+            allocated_a: 1_001,
+            allocated_b: 0,
+            metadata: None,
+        };
+        let policies = ["down", "half_even", "down(unit=10, residue=reserve)", "half_even(unit=100, residue=reserve)"];
+        for spec in policies {
+            let rounding = RoundingPolicy::from_economic_model(spec).unwrap();
+            for a_bps in [0, 1, 3_333, 5_000, 6_667, 9_999, 10_000] {
+                let (to_a, to_b) = EscrowAward::Divided { a_bps }.split(&warehouse, &rounding).unwrap();
+                assert_eq!(to_a + to_b, 1_001, "{} at {} bps", spec, a_bps);
+            }
+        }
+
+        // Half of 1_001 in hundreds leaves 1 for the side awarded more
+        let hundreds = RoundingPolicy::from_economic_model("half_even(unit=100, residue=reserve)").unwrap();
+        assert_eq!(EscrowAward::Divided { a_bps: 5_000 }.split(&warehouse, &hundreds).unwrap(), (501, 500));
+        assert_eq!(EscrowAward::Divided { a_bps: 2_500 }.split(&warehouse, &hundreds).unwrap(), (200, 801));
+    }}
+}
@@ -2,20 +2,49 @@ use crate::error::{LifecycleError, LifecycleResult};
 use crate::types::{
     LineageAttestation, LineageAttestationType, MergeStatus, PreMergeBundle, SplitBundle, SplitStatus,
 };
+use async_trait::async_trait;
 use cid::Cid;
 use icn_core_vm::HostContext;
 use icn_dag::{DagManager, DagNode, DagNodeType};
 use icn_economics::Ledger;
 use icn_federation::Federation;
-use icn_identity::{Did, create_federation_did, ExecutionReceipt, VerifiableCredential};
+use icn_identity::{Did, create_federation_did, ExecutionReceipt, QuorumProof, VerifiableCredential};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
+use std::sync::Mutex;
 use tracing::{debug, info, warn};
 
 /// Execute a federation merge operation
 pub async fn execute_merge(
     ctx: &mut HostContext,
     bundle: PreMergeBundle,
+) -> LifecycleResult<ExecutionReceipt> {
+    run_merge(ctx, bundle, |_, _| Ok(())).await
+}
+
+/// Execute a federation merge operation, recording the inverse of each step in `store`
+/// under `process_id` as it completes, so a merge that fails midway can be undone with
+/// [`rollback_merge`]
+pub async fn execute_merge_with_rollback(
+    ctx: &mut HostContext,
+    process_id: &str,
+    bundle: PreMergeBundle,
+    store: &impl CompensationStore,
+) -> LifecycleResult<ExecutionReceipt> {
+    let mut log = CompensationLog::new(process_id, bundle.lineage.parents.clone());
+    store.save(&log)?;
+
+    run_merge(ctx, bundle, |step, action| {
+        log.record(step, action);
+        store.save(&log)
+    }).await
+}
+
+async fn run_merge(
+    ctx: &mut HostContext,
+    bundle: PreMergeBundle,
+    mut record: impl FnMut(&str, CompensatingAction) -> LifecycleResult<()>,
 ) -> LifecycleResult<ExecutionReceipt> {
     info!("Executing federation merge operation");
     
@@ -60,6 +89,7 @@ pub async fn execute_merge(
         })?;
     
     debug!("Created federation genesis node with CID: {}", genesis_cid);
+    record("anchor_genesis", CompensatingAction::RetractDagNode { cid: genesis_cid.to_string() })?;
     
     // Create lineage attestation node
     let lineage_node = create_lineage_node(
@@ -78,6 +108,7 @@ pub async fn execute_merge(
         })?;
     
     debug!("Created lineage attestation node with CID: {}", lineage_cid);
+    record("anchor_lineage", CompensatingAction::RetractDagNode { cid: lineage_cid.to_string() })?;
     
     // Create MergeBridge node
     let bridge_node = create_merge_bridge_node(
@@ -98,6 +129,7 @@ pub async fn execute_merge(
         })?;
     
     debug!("Created merge bridge node with CID: {}", bridge_cid);
+    record("anchor_bridge", CompensatingAction::RetractDagNode { cid: bridge_cid.to_string() })?;
     
     // Prepare receipt information
     let receipt_data = json!({
@@ -127,6 +159,195 @@ pub async fn execute_merge(
     Ok(receipt)
 }
 
+/// Inverse of a merge step, recorded once the step completes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CompensatingAction {
+    /// Retract a node the merge anchored to the DAG. The DAG is append-only, so the
+    /// node stays but is listed as retracted by the aborted-merge attestation.
+    RetractDagNode { cid: String },
+
+    /// Put a federation's ledger back to its balances from before the merge
+    RestoreLedger { federation_id: Did, balances: HashMap<Did, u64> },
+}
+
+/// A completed merge step and its inverse
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompensationEntry {
+    /// The step (e.g., "anchor_genesis", "union_ledgers")
+    pub step: String,
+
+    /// How to undo it
+    pub action: CompensatingAction,
+
+    /// When the step completed
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The inverse operations of a merge's completed steps, in the order the steps completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompensationLog {
+    /// The merge process
+    pub process_id: String,
+
+    /// The federations being merged, which a rollback restores
+    pub federations: Vec<Did>,
+
+    /// Steps still to undo
+    pub steps: Vec<CompensationEntry>,
+
+    /// Steps a rollback has undone, in the order they were undone
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compensated: Vec<CompensationEntry>,
+
+    /// When the rollback finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rolled_back_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl CompensationLog {
+    /// An empty log for a merge of `federations`
+    pub fn new(process_id: &str, federations: Vec<Did>) -> Self {
+        Self {
+            process_id: process_id.to_string(),
+            federations,
+            steps: Vec::new(),
+            compensated: Vec::new(),
+            rolled_back_at: None,
+        }
+    }
+
+    /// Record the inverse of a step that just completed. Steps done outside the
+    /// executor, like unioning ledgers, are recorded here too.
+    pub fn record(&mut self, step: &str, action: CompensatingAction) {
+        self.steps.push(CompensationEntry {
+            step: step.to_string(),
+            action,
+            recorded_at: chrono::Utc::now(),
+        });
+    }
+}
+
+/// Where compensation logs are kept
+pub trait CompensationStore: Send + Sync {
+    /// Load the compensation log of a merge process, if one was saved
+    fn load(&self, process_id: &str) -> LifecycleResult<Option<CompensationLog>>;
+
+    /// Replace the compensation log of a merge process
+    fn save(&self, log: &CompensationLog) -> LifecycleResult<()>;
+}
+
+/// Compensation store kept in memory, for tests and single-process deployments
+#[derive(Debug, Default)]
+pub struct InMemoryCompensationStore {
+    logs: Mutex<HashMap<String, CompensationLog>>,
+}
+
+impl CompensationStore for InMemoryCompensationStore {
+    fn load(&self, process_id: &str) -> LifecycleResult<Option<CompensationLog>> {
+        let logs = self.logs.lock()
+            .map_err(|_| LifecycleError::StorageError("Compensation store lock poisoned".to_string()))?;
+        Ok(logs.get(process_id).cloned())
+    }
+
+    fn save(&self, log: &CompensationLog) -> LifecycleResult<()> {
+        let mut logs = self.logs.lock()
+            .map_err(|_| LifecycleError::StorageError("Compensation store lock poisoned".to_string()))?;
+        logs.insert(log.process_id.clone(), log.clone());
+        Ok(())
+    }
+}
+
+/// Applies compensating actions during a rollback
+#[async_trait]
+pub trait MergeCompensator: Send {
+    /// Mark a DAG node anchored by the merge as retracted
+    async fn retract_dag_node(&mut self, cid: &str) -> LifecycleResult<()>;
+
+    /// Put a federation's ledger back to `balances`
+    async fn restore_ledger(&mut self, federation_id: &Did, balances: &HashMap<Did, u64>) -> LifecycleResult<()>;
+
+    /// Anchor the attestation recording the aborted merge, returning its CID
+    async fn anchor_attestation(&mut self, attestation: &LineageAttestation) -> LifecycleResult<String>;
+}
+
+/// Outcome of a merge rollback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeRollback {
+    pub process_id: String,
+
+    /// Steps undone, in the order they were undone
+    pub compensated: Vec<CompensationEntry>,
+
+    /// Lineage attestation recording the aborted attempt
+    pub attestation: LineageAttestation,
+
+    /// CID of the anchored attestation
+    pub attestation_cid: String,
+}
+
+/// Undo the completed steps of a failed merge, newest first, returning the federations
+/// to their pre-merge state, then anchor a lineage attestation recording the aborted
+/// attempt. The log is saved after every undone step, so a rollback that fails midway
+/// can be retried and continues where it stopped. Callers then mark the merge process
+/// [`MergeStatus::RolledBack`].
+pub async fn rollback_merge(
+    process_id: &str,
+    store: &impl CompensationStore,
+    compensator: &mut impl MergeCompensator,
+) -> LifecycleResult<MergeRollback> {
+    let mut log = store.load(process_id)?
+        .ok_or_else(|| LifecycleError::ProcessNotFound(format!("No compensation log for merge {}", process_id)))?;
+    if log.rolled_back_at.is_some() {
+        return Err(LifecycleError::InvalidFederationState(
+            format!("Merge {} has already been rolled back", process_id)
+        ));
+    }
+
+    while let Some(entry) = log.steps.last().cloned() {
+        match &entry.action {
+            CompensatingAction::RetractDagNode { cid } => compensator.retract_dag_node(cid).await?,
+            CompensatingAction::RestoreLedger { federation_id, balances } => {
+                compensator.restore_ledger(federation_id, balances).await?
+            }
+        }
+        debug!("Undid step {} of merge {}", entry.step, process_id);
+
+        log.steps.pop();
+        log.compensated.push(entry);
+        store.save(&log)?;
+    }
+
+    let retracted: Vec<&str> = log.compensated.iter()
+        .filter_map(|entry| match &entry.action {
+            CompensatingAction::RetractDagNode { cid } => Some(cid.as_str()),
+            _ => None,
+        })
+        .collect();
+    let attestation = LineageAttestation {
+        parents: log.federations.clone(),
+        children: log.federations.clone(),
+        typ: LineageAttestationType::AbortedMerge,
+        proof: QuorumProof::default(),
+        timestamp: chrono::Utc::now(),
+        metadata: HashMap::from([
+            ("merge_process_id".to_string(), process_id.to_string()),
+            ("retracted_nodes".to_string(), retracted.join(",")),
+        ]),
+    };
+    let attestation_cid = compensator.anchor_attestation(&attestation).await?;
+
+    log.rolled_back_at = Some(chrono::Utc::now());
+    store.save(&log)?;
+    info!("Rolled back merge {} ({} steps undone)", process_id, log.compensated.len());
+
+    Ok(MergeRollback {
+        process_id: process_id.to_string(),
+        compensated: log.compensated,
+        attestation,
+        attestation_cid,
+    })
+}
+
 /// Execute a federation split operation
 pub async fn execute_split(
     ctx: &mut HostContext,
@@ -288,6 +509,8 @@ pub async fn execute_split(
         "lineage_cid": lineage_cid.to_string(),
         "bridge_cid_a": bridge_cid_a.to_string(),
         "bridge_cid_b": bridge_cid_b.to_string(),
+        "carved_nodes_a": bundle.carve_manifest_for(&federation_a_did).map(|m| m.nodes.len()).unwrap_or(0),
+        "carved_nodes_b": bundle.carve_manifest_for(&federation_b_did).map(|m| m.nodes.len()).unwrap_or(0),
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "status": "completed"
     });
@@ -346,6 +569,7 @@ fn create_lineage_node(
     let issuer_did = match lineage.typ {
         LineageAttestationType::Merge => lineage.children[0].clone(),
         LineageAttestationType::Split => lineage.parents[0].clone(),
+        LineageAttestationType::AbortedMerge => lineage.parents[0].clone(),
     };
     
     Ok(DagNode {
@@ -429,4 +653,68 @@ fn create_split_bridge_node(
         issuer: federation_did.to_string(),
         timestamp: chrono::Utc::now(),
     })
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records what it was asked to undo, failing the first ledger restore if `fail_ledger`
+    #[derive(Default)]
+    struct RecordingCompensator {
+        undone: Vec<String>,
+        fail_ledger: bool,
+    }
+
+    #[async_trait]
+    impl MergeCompensator for RecordingCompensator {
+        async fn retract_dag_node(&mut self, cid: &str) -> LifecycleResult<()> {
+            self.undone.push(format!("retract {}", cid));
+            Ok(())
+        }
+
+        async fn restore_ledger(&mut self, federation_id: &Did, _balances: &HashMap<Did, u64>) -> LifecycleResult<()> {
+            if std::mem::take(&mut self.fail_ledger) {
+                return Err(LifecycleError::LedgerOperationFailed("ledger unavailable".to_string()));
+            }
+            self.undone.push(format!("restore {}", federation_id));
+            Ok(())
+        }
+
+        async fn anchor_attestation(&mut self, _attestation: &LineageAttestation) -> LifecycleResult<String> {
+            Ok("bafy-aborted-merge".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollback_undoes_steps_newest_first() {
+        let store = InMemoryCompensationStore::default();
+        let mut log = CompensationLog::new("merge-1", vec!["did:icn:fed-a".to_string(), "did:icn:fed-b".to_string()]);
+        log.record("anchor_genesis", CompensatingAction::RetractDagNode { cid: "bafy-genesis".to_string() });
+        log.record("union_ledgers", CompensatingAction::RestoreLedger {
+            federation_id: "did:icn:fed-a".to_string(),
+            balances: HashMap::from([("did:icn:alice".to_string(), 40)]),
+        });
+        log.record("anchor_lineage", CompensatingAction::RetractDagNode { cid: "bafy-lineage".to_string() });
+        store.save(&log).unwrap();
+
+        // The ledger restore fails once; the retried rollback doesn't retract the lineage node twice
+        let mut compensator = RecordingCompensator { fail_ledger: true, ..Default::default() };
+        assert!(rollback_merge("merge-1", &store, &mut compensator).await.is_err());
+        let rollback = rollback_merge("merge-1", &store, &mut compensator).await.unwrap();
+
+        assert_eq!(compensator.undone, vec![
+            "retract bafy-lineage".to_string(),
+            "restore did:icn:fed-a".to_string(),
+            "retract bafy-genesis".to_string(),
+        ]);
+        assert_eq!(rollback.compensated.len(), 3);
+        assert_eq!(rollback.attestation.typ, LineageAttestationType::AbortedMerge);
+        assert_eq!(rollback.attestation.children, rollback.attestation.parents);
+        assert_eq!(rollback.attestation.metadata["retracted_nodes"], "bafy-lineage,bafy-genesis");
+
+        // A merge can only be rolled back once
+        assert!(rollback_merge("merge-1", &store, &mut compensator).await.is_err());
+        assert!(rollback_merge("merge-2", &store, &mut compensator).await.is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Merged governance event histories
+//!
+//! A merged federation inherits the governance record of both of its sources. The
+//! merged history imports each source federation's event log as it was recorded: every
+//! event keeps its original ID, timestamp and issuer, and the credential that attested
+//! it is carried along untouched, so its original signature still verifies. Each
+//! imported event is tagged with its provenance, the federation it came from and its
+//! position in that federation's log, and the combined history is kept in
+//! chronological order for queries across both sources.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::MergeProcess;
+use icn_governance_kernel::events::{GovernanceEvent, GovernanceEventType};
+use icn_identity::{Did, VerifiableCredential};
+use serde::{Deserialize, Serialize};
+
+/// An event as recorded in its source federation's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceEvent {
+    pub event: GovernanceEvent,
+
+    /// Credential issued for the event, with its original proof
+    pub credential: Option<VerifiableCredential>,
+}
+
+/// Where an imported event came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventProvenance {
+    /// Federation whose log recorded the event
+    pub origin_federation: Did,
+
+    /// Position of the event in its origin's log
+    pub original_sequence: u64,
+
+    /// Merge process the event was imported by
+    pub process_id: String,
+}
+
+/// An event in the merged history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedEvent {
+    /// Position of the event in the merged history
+    pub sequence: u64,
+
+    pub event: GovernanceEvent,
+    pub credential: Option<VerifiableCredential>,
+    pub provenance: EventProvenance,
+}
+
+/// Filters for querying a merged history; unset fields match every event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventHistoryQuery {
+    pub origin_federation: Option<Did>,
+    pub event_type: Option<GovernanceEventType>,
+    pub proposal_id: Option<String>,
+    /// Earliest event timestamp, inclusive
+    pub since: Option<u64>,
+    /// Latest event timestamp, exclusive
+    pub until: Option<u64>,
+}
+
+impl EventHistoryQuery {
+    /// Whether an event passes every filter
+    pub fn matches(&self, merged: &MergedEvent) -> bool {
+        let event = &merged.event;
+        self.origin_federation.as_ref().map_or(true, |origin| &merged.provenance.origin_federation == origin)
+            && self.event_type.as_ref().map_or(true, |event_type| &event.event_type == event_type)
+            && self.proposal_id.as_ref().map_or(true, |id| event.proposal_cid.as_ref() == Some(id))
+            && self.since.map_or(true, |since| event.timestamp >= since)
+            && self.until.map_or(true, |until| event.timestamp < until)
+    }
+}
+
+/// The combined governance event history of a merged federation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedEventHistory {
+    /// The federation the merge produced
+    pub merged_federation: Did,
+
+    /// Merge process the history belongs to
+    pub process_id: String,
+
+    /// Federations whose logs have been imported, in import order
+    pub sources: Vec<Did>,
+
+    /// Every imported event, oldest first
+    events: Vec<MergedEvent>,
+}
+
+impl MergedEventHistory {
+    /// An empty history for a merged federation
+    pub fn new(merged_federation: &Did, process_id: &str) -> Self {
+        Self {
+            merged_federation: merged_federation.clone(),
+            process_id: process_id.to_string(),
+            sources: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// The history of a merge, with the logs of both source federations imported
+    pub fn from_merge(
+        process: &MergeProcess,
+        log_a: &[SourceEvent],
+        log_b: &[SourceEvent],
+    ) -> LifecycleResult<Self> {
+        let mut history = Self::new(&process.new_federation_id, &process.id);
+        history.import(&process.federation_a_id, log_a)?;
+        history.import(&process.federation_b_id, log_b)?;
+        Ok(history)
+    }
+
+    /// Import a source federation's log, given in the order it was recorded. Each
+    /// source can be imported once. Returns the number of events imported.
+    pub fn import(&mut self, origin_federation: &Did, log: &[SourceEvent]) -> LifecycleResult<usize> {
+        if self.sources.contains(origin_federation) {
+            return Err(LifecycleError::InvalidFederationState(format!(
+                "Event log of {} was already imported into {}", origin_federation, self.merged_federation
+            )));
+        }
+
+        self.events.extend(log.iter().enumerate().map(|(i, source)| MergedEvent {
+            sequence: 0,
+            event: source.event.clone(),
+            credential: source.credential.clone(),
+            provenance: EventProvenance {
+                origin_federation: origin_federation.clone(),
+                original_sequence: i as u64,
+                process_id: self.process_id.clone(),
+            },
+        }));
+        self.sources.push(origin_federation.clone());
+
+        // Events from the same instant keep their source order; ties between sources
+        // go to the source imported first
+        let sources = self.sources.clone();
+        let source_rank = |origin: &Did| sources.iter().position(|s| s == origin).unwrap_or(usize::MAX);
+        self.events.sort_by(|a, b| {
+            a.event.timestamp.cmp(&b.event.timestamp)
+                .then_with(|| source_rank(&a.provenance.origin_federation).cmp(&source_rank(&b.provenance.origin_federation)))
+                .then_with(|| a.provenance.original_sequence.cmp(&b.provenance.original_sequence))
+        });
+        for (i, merged) in self.events.iter_mut().enumerate() {
+            merged.sequence = i as u64;
+        }
+
+        Ok(log.len())
+    }
+
+    /// Every event, oldest first
+    pub fn events(&self) -> &[MergedEvent] {
+        &self.events
+    }
+
+    /// Events passing a query, oldest first
+    pub fn query(&self, query: &EventHistoryQuery) -> Vec<&MergedEvent> {
+        self.events.iter().filter(|e| query.matches(e)).collect()
+    }
+
+    /// The imported copy of an event from a source log
+    pub fn locate(&self, origin_federation: &Did, original_sequence: u64) -> Option<&MergedEvent> {
+        self.events.iter().find(|e| {
+            &e.provenance.origin_federation == origin_federation && e.provenance.original_sequence == original_sequence
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_governance_kernel::events::EventStatus;
+    use icn_identity::{IdentityId, IdentityScope};
+
+    fn event(id: &str, event_type: GovernanceEventType, timestamp: u64, proposal: Option<&str>) -> SourceEvent {
+        let event = GovernanceEvent {
+            id: id.to_string(),
+            event_type,
+            timestamp,
+            issuer: IdentityId("did:icn:alice".to_string()),
+            scope: IdentityScope::Federation,
+            organization: None,
+            proposal_cid: proposal.map(str::to_string),
+            status: EventStatus::Success,
+            data: serde_json::json!({}),
+        };
+        let credential = event.to_credential("did:icn:kernel");
+        SourceEvent { event, credential: Some(credential) }
+    }
+
+    #[test]
+    fn test_histories_interleave_with_provenance() {
+        let fed_a = "did:icn:fed-a".to_string();
+        let fed_b = "did:icn:fed-b".to_string();
+        let log_a = vec![
+            event("a0", GovernanceEventType::ProposalCreated, 100, Some("p-a")),
+            event("a1", GovernanceEventType::VoteCast, 300, Some("p-a")),
+        ];
+        let log_b = vec![
+            event("b0", GovernanceEventType::ConfigUpdated, 100, None),
+            event("b1", GovernanceEventType::ProposalCreated, 200, Some("p-b")),
+        ];
+
+        let mut history = MergedEventHistory::new(&"did:icn:fed-ab".to_string(), "merge-1");
+        assert_eq!(history.import(&fed_a, &log_a).unwrap(), 2);
+        history.import(&fed_b, &log_b).unwrap();
+        assert!(history.import(&fed_a, &log_a).is_err());
+
+        let order: Vec<&str> = history.events().iter().map(|e| e.event.id.as_str()).collect();
+        assert_eq!(order, ["a0", "b0", "b1", "a1"]);
+        assert_eq!(history.events()[3].sequence, 3);
+
+        let imported = history.locate(&fed_b, 1).unwrap();
+        assert_eq!(imported.event.id, "b1");
+        assert_eq!(imported.provenance.process_id, "merge-1");
+        // The attesting credential is carried over as issued
+        assert_eq!(
+            serde_json::to_value(&imported.credential).unwrap(),
+            serde_json::to_value(&log_b[1].credential).unwrap()
+        );
+
+        let created = history.query(&EventHistoryQuery {
+            event_type: Some(GovernanceEventType::ProposalCreated),
+            ..Default::default()
+        });
+        assert_eq!(created.len(), 2);
+
+        let from_a_after = history.query(&EventHistoryQuery {
+            origin_federation: Some(fed_a.clone()),
+            since: Some(150),
+            ..Default::default()
+        });
+        assert_eq!(from_a_after.len(), 1);
+        assert_eq!(from_a_after[0].event.id, "a1");
+        assert_eq!(history.query(&EventHistoryQuery { proposal_id: Some("p-b".into()), until: Some(200), ..Default::default() }).len(), 0);
+    }
+}
@@ -0,0 +1,383 @@
+//! Per-member impact preview for federation merges
+//!
+//! Before a merge completes, each member can see what it will mean for them:
+//! their DID in the merged federation, which credentials carry over, how their
+//! dues and roles change, and how their ledger balances are combined. The preview
+//! is derived from the process's trust mapping, the source and merged policies,
+//! and the union of the two ledgers. Impacts serialize to JSON so the gateway and
+//! CLI can return them as-is.
+
+use crate::economics::union_ledgers_impl;
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::{MergeProcess, TrustMapping};
+use icn_economics::Ledger;
+use icn_identity::Did;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Policy key holding the periodic membership dues of a federation
+pub const MEMBERSHIP_DUES_POLICY_KEY: &str = "membership_dues";
+
+/// Which source federation a member belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MemberOrigin {
+    /// Member of federation A only
+    FederationA,
+    /// Member of federation B only
+    FederationB,
+    /// Member of both source federations
+    Both,
+}
+
+/// A credential a member currently holds in a source federation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeldCredential {
+    /// Credential type
+    pub credential_type: String,
+    /// Issuer of the credential
+    pub issuer: Did,
+}
+
+/// What happens to one of the member's credentials
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialImpact {
+    /// The credential held today
+    pub current: HeldCredential,
+    /// The credential recognized in the merged federation, if the trust mapping covers it
+    pub mapped: Option<HeldCredential>,
+    /// Validation rules attached to the mapping
+    pub rules: HashMap<String, String>,
+}
+
+impl CredentialImpact {
+    /// Whether the credential is still recognized after the merge
+    pub fn carried_over(&self) -> bool {
+        self.mapped.is_some()
+    }
+}
+
+/// Change to a member's dues
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuesChange {
+    /// Dues owed in the source federation(s) today, summed if the member is in both
+    pub before: Option<u64>,
+    /// Dues owed in the merged federation
+    pub after: Option<u64>,
+}
+
+impl DuesChange {
+    /// Signed difference between the merged dues and today's dues
+    pub fn delta(&self) -> i128 {
+        self.after.unwrap_or(0) as i128 - self.before.unwrap_or(0) as i128
+    }
+}
+
+/// Change to a member's roles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleMapping {
+    /// Roles held in the source federation(s)
+    pub before: Vec<String>,
+    /// Roles assigned by the trust mapping in the merged federation
+    pub after: Vec<String>,
+    /// Roles gained by the merge
+    pub added: Vec<String>,
+    /// Roles lost by the merge
+    pub removed: Vec<String>,
+}
+
+/// How a member's balances are combined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceAdjustment {
+    /// Balance in federation A's ledger
+    pub balance_a: Option<u64>,
+    /// Balance in federation B's ledger
+    pub balance_b: Option<u64>,
+    /// Balance in the unioned ledger
+    pub merged_balance: u64,
+}
+
+/// Summary of what a merge means for a single member
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberMergeImpact {
+    /// Merge process the preview was computed for
+    pub process_id: String,
+    /// The member's current DID
+    pub member_did: Did,
+    /// The member's DID in the merged federation
+    pub new_did: Did,
+    /// The merged federation
+    pub new_federation_id: Did,
+    /// Which source federation(s) the member belongs to
+    pub origin: MemberOrigin,
+    /// Credential changes
+    pub credentials: Vec<CredentialImpact>,
+    /// Dues change
+    pub dues: DuesChange,
+    /// Role changes
+    pub roles: RoleMapping,
+    /// Balance adjustments
+    pub balance: BalanceAdjustment,
+}
+
+/// Source-side state a merge process needs for impact previews
+#[derive(Debug, Clone)]
+pub struct MergeImpactContext {
+    /// Merge process id
+    pub process_id: String,
+    /// The merged federation
+    pub new_federation_id: Did,
+    /// Trust mapping of the merge process
+    pub trust_mapping: TrustMapping,
+    /// Merged governance policy
+    pub merged_policy: HashMap<String, String>,
+    /// Members of federation A
+    pub members_a: Vec<Did>,
+    /// Members of federation B
+    pub members_b: Vec<Did>,
+    /// Governance policy of federation A
+    pub policy_a: HashMap<String, String>,
+    /// Governance policy of federation B
+    pub policy_b: HashMap<String, String>,
+    /// Roles members hold in the source federations
+    pub current_roles: HashMap<Did, Vec<String>>,
+    /// Credentials members hold in the source federations
+    pub credentials: HashMap<Did, Vec<HeldCredential>>,
+    /// Ledger of federation A
+    pub ledger_a: Ledger,
+    /// Ledger of federation B
+    pub ledger_b: Ledger,
+}
+
+impl MergeImpactContext {
+    /// Start a context from a merge process. Source-side state is filled in by the caller.
+    pub fn new(process: &MergeProcess) -> Self {
+        Self {
+            process_id: process.id.clone(),
+            new_federation_id: process.new_federation_id.clone(),
+            trust_mapping: process.trust_mapping.clone(),
+            merged_policy: process.merged_policy.clone(),
+            members_a: Vec::new(),
+            members_b: Vec::new(),
+            policy_a: HashMap::new(),
+            policy_b: HashMap::new(),
+            current_roles: HashMap::new(),
+            credentials: HashMap::new(),
+            ledger_a: Ledger::new(),
+            ledger_b: Ledger::new(),
+        }
+    }
+}
+
+/// Merge processes that are open for member impact previews
+#[derive(Debug, Default)]
+pub struct MergeProcessRegistry {
+    processes: HashMap<String, MergeImpactContext>,
+}
+
+impl MergeProcessRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a merge process, replacing any previous context for the same id
+    pub fn register(&mut self, context: MergeImpactContext) {
+        self.processes.insert(context.process_id.clone(), context);
+    }
+
+    /// Remove a merge process
+    pub fn remove(&mut self, process_id: &str) -> Option<MergeImpactContext> {
+        self.processes.remove(process_id)
+    }
+
+    /// Get the context for a merge process
+    pub fn get(&self, process_id: &str) -> Option<&MergeImpactContext> {
+        self.processes.get(process_id)
+    }
+
+    /// Summarize what the merge process means for a member
+    pub fn member_merge_impact(&self, process_id: &str, member_did: &Did) -> LifecycleResult<MemberMergeImpact> {
+        let context = self.processes.get(process_id)
+            .ok_or_else(|| LifecycleError::ProcessNotFound(process_id.to_string()))?;
+
+        compute_member_merge_impact(context, member_did)
+    }
+}
+
+/// Compute the merge impact for a single member
+pub fn compute_member_merge_impact(context: &MergeImpactContext, member_did: &Did) -> LifecycleResult<MemberMergeImpact> {
+    let in_a = context.members_a.contains(member_did);
+    let in_b = context.members_b.contains(member_did);
+    let origin = match (in_a, in_b) {
+        (true, true) => MemberOrigin::Both,
+        (true, false) => MemberOrigin::FederationA,
+        (false, true) => MemberOrigin::FederationB,
+        (false, false) => {
+            return Err(LifecycleError::IdentityError(format!(
+                "{} is not a member of either federation in merge process {}",
+                member_did, context.process_id
+            )));
+        }
+    };
+
+    let mapping = &context.trust_mapping;
+    let new_did = mapping.did_mappings.get(member_did)
+        .cloned()
+        .unwrap_or_else(|| member_did.clone());
+
+    let credentials = context.credentials.get(member_did)
+        .map(|held| held.iter().map(|credential| map_credential(context, credential)).collect())
+        .unwrap_or_default();
+
+    let dues = DuesChange {
+        before: match origin {
+            MemberOrigin::FederationA => parse_dues(&context.policy_a)?,
+            MemberOrigin::FederationB => parse_dues(&context.policy_b)?,
+            MemberOrigin::Both => match (parse_dues(&context.policy_a)?, parse_dues(&context.policy_b)?) {
+                (None, None) => None,
+                (a, b) => Some(a.unwrap_or(0).saturating_add(b.unwrap_or(0))),
+            },
+        },
+        after: parse_dues(&context.merged_policy)?,
+    };
+
+    let before_roles = context.current_roles.get(member_did).cloned().unwrap_or_default();
+    let after_roles = mapping.role_assignments.get(&new_did)
+        .or_else(|| mapping.role_assignments.get(member_did))
+        .cloned()
+        .unwrap_or_default();
+    let roles = RoleMapping {
+        added: after_roles.iter().filter(|r| !before_roles.contains(r)).cloned().collect(),
+        removed: before_roles.iter().filter(|r| !after_roles.contains(r)).cloned().collect(),
+        before: before_roles,
+        after: after_roles,
+    };
+
+    let merged_ledger = union_ledgers_impl(&context.ledger_a, &context.ledger_b)?;
+    let balance = BalanceAdjustment {
+        balance_a: context.ledger_a.accounts().get(member_did).copied(),
+        balance_b: context.ledger_b.accounts().get(member_did).copied(),
+        merged_balance: merged_ledger.accounts().get(member_did).copied().unwrap_or(0),
+    };
+
+    Ok(MemberMergeImpact {
+        process_id: context.process_id.clone(),
+        member_did: member_did.clone(),
+        new_did,
+        new_federation_id: context.new_federation_id.clone(),
+        origin,
+        credentials,
+        dues,
+        roles,
+        balance,
+    })
+}
+
+fn map_credential(context: &MergeImpactContext, credential: &HeldCredential) -> CredentialImpact {
+    let validation = context.trust_mapping.credential_validations.iter()
+        .find(|v| v.source_type == credential.credential_type && v.source_issuer == credential.issuer);
+
+    CredentialImpact {
+        current: credential.clone(),
+        mapped: validation.map(|v| HeldCredential {
+            credential_type: v.target_type.clone(),
+            issuer: v.target_issuer.clone(),
+        }),
+        rules: validation.map(|v| v.rules.clone()).unwrap_or_default(),
+    }
+}
+
+fn parse_dues(policy: &HashMap<String, String>) -> LifecycleResult<Option<u64>> {
+    policy.get(MEMBERSHIP_DUES_POLICY_KEY)
+        .map(|value| value.trim().parse::<u64>().map_err(|e| {
+            LifecycleError::InvalidProposal(format!("Invalid {} policy value '{}': {}", MEMBERSHIP_DUES_POLICY_KEY, value, e))
+        }))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CredentialValidation;
+
+    fn test_context() -> MergeImpactContext {
+        let mut did_mappings = HashMap::new();
+        did_mappings.insert("did:icn:b:carol".to_string(), "did:icn:ab:carol".to_string());
+
+        let mut role_assignments = HashMap::new();
+        role_assignments.insert("did:icn:ab:carol".to_string(), vec!["member".to_string(), "treasurer".to_string()]);
+
+        let trust_mapping = TrustMapping {
+            did_mappings,
+            role_assignments,
+            credential_validations: vec![CredentialValidation {
+                source_type: "MembershipCredential".to_string(),
+                target_type: "FederationMembershipCredential".to_string(),
+                source_issuer: "did:icn:b".to_string(),
+                target_issuer: "did:icn:ab".to_string(),
+                rules: HashMap::new(),
+            }],
+        };
+
+        let mut merged_policy = HashMap::new();
+        merged_policy.insert(MEMBERSHIP_DUES_POLICY_KEY.to_string(), "15".to_string());
+        let mut policy_b = HashMap::new();
+        policy_b.insert(MEMBERSHIP_DUES_POLICY_KEY.to_string(), "10".to_string());
+
+        let mut current_roles = HashMap::new();
+        current_roles.insert("did:icn:b:carol".to_string(), vec!["member".to_string(), "steward".to_string()]);
+
+        let mut credentials = HashMap::new();
+        credentials.insert("did:icn:b:carol".to_string(), vec![
+            HeldCredential { credential_type: "MembershipCredential".to_string(), issuer: "did:icn:b".to_string() },
+            HeldCredential { credential_type: "WorkshopCredential".to_string(), issuer: "did:icn:b".to_string() },
+        ]);
+
+        let mut ledger_a = Ledger::new();
+        ledger_a.create_account("did:icn:b:carol").unwrap();
+        ledger_a.credit("did:icn:b:carol", 20).unwrap();
+        let mut ledger_b = Ledger::new();
+        ledger_b.create_account("did:icn:b:carol").unwrap();
+        ledger_b.credit("did:icn:b:carol", 30).unwrap();
+
+        MergeImpactContext {
+            process_id: "merge-1".to_string(),
+            new_federation_id: "did:icn:ab".to_string(),
+            trust_mapping,
+            merged_policy,
+            members_a: vec![],
+            members_b: vec!["did:icn:b:carol".to_string()],
+            policy_a: HashMap::new(),
+            policy_b,
+            current_roles,
+            credentials,
+            ledger_a,
+            ledger_b,
+        }
+    }
+
+    #[test]
+    fn test_member_merge_impact() {
+        let mut registry = MergeProcessRegistry::new();
+        registry.register(test_context());
+
+        let impact = registry.member_merge_impact("merge-1", &"did:icn:b:carol".to_string()).unwrap();
+        assert_eq!(impact.new_did, "did:icn:ab:carol");
+        assert_eq!(impact.origin, MemberOrigin::FederationB);
+
+        assert_eq!(impact.credentials.len(), 2);
+        assert!(impact.credentials[0].carried_over());
+        assert!(!impact.credentials[1].carried_over());
+
+        assert_eq!(impact.dues.delta(), 5);
+        assert_eq!(impact.roles.added, vec!["treasurer".to_string()]);
+        assert_eq!(impact.roles.removed, vec!["steward".to_string()]);
+        assert_eq!(impact.balance.merged_balance, 50);
+
+        assert!(matches!(
+            registry.member_merge_impact("merge-2", &"did:icn:b:carol".to_string()),
+            Err(LifecycleError::ProcessNotFound(_))
+        ));
+        assert!(registry.member_merge_impact("merge-1", &"did:icn:x:dave".to_string()).is_err());
+    }
+}
@@ -0,0 +1,645 @@
+//! Importing records of co-ops that join ICN from other tools
+//!
+//! A co-op adopting ICN, or founding a federation with others, usually brings a member
+//! list and balances kept in a spreadsheet or on another platform. An
+//! [`ImportAdapter`] reads one such export into [`ExternalRecord`]s:
+//!
+//! - [`CsvImportAdapter`] reads a spreadsheet with one member per row, with
+//!   configurable column names
+//! - [`OpenCollectiveImportAdapter`] reads an Open Collective transactions export,
+//!   turning each contributor into a member credited with what they contributed
+//!
+//! [`plan_import`] validates the records and maps them onto an [`ImportBatch`]: roster
+//! entries under the members' DIDs, a ledger with their opening balances, and a
+//! membership credential for each member issued by the federation. Every problem found
+//! lands in the [`ImportReport`] with the line it came from. [`run_import`] hands the
+//! batch to an [`ImportSink`] (the bootstrap or adoption flow), but only if the report
+//! has no errors and the import isn't a dry run, so a batch is applied whole or not at
+//! all.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use async_trait::async_trait;
+use icn_economics::Ledger;
+use icn_identity::{Did, IdentityId, VerifiableCredential};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::info;
+
+/// Credential type issued to imported members
+pub const IMPORTED_MEMBERSHIP_CREDENTIAL: &str = "MembershipCredential";
+
+/// A member as recorded by the source system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalMember {
+    /// The member's identifier in the source system
+    pub external_id: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// DID the member already holds, if the source records one
+    pub did: Option<Did>,
+    pub roles: Vec<String>,
+}
+
+/// An amount held by or owed to a member in the source system, in minor units
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalBalance {
+    pub external_id: String,
+    pub amount: i64,
+    pub currency: Option<String>,
+}
+
+/// A record read from an external export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExternalRecord {
+    Member(ExternalMember),
+    Balance(ExternalBalance),
+}
+
+/// A record together with the line of the export it was read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceRecord {
+    pub line: usize,
+    pub record: ExternalRecord,
+}
+
+/// How serious a problem found during an import is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IssueSeverity {
+    /// The import can't be applied until it is fixed
+    Error,
+    /// Worth a look, but doesn't block the import
+    Warning,
+}
+
+/// A problem found in an export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportIssue {
+    /// Line of the export, if the problem belongs to one
+    pub line: Option<usize>,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+/// What an import found and did
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub source: String,
+    pub dry_run: bool,
+    pub records_read: usize,
+    pub members: usize,
+    pub balances: usize,
+    pub credentials: usize,
+    /// Sum of the opening balances, in minor units
+    pub total_balance: u64,
+    pub issues: Vec<ImportIssue>,
+    /// Whether the batch was handed to the sink
+    pub applied: bool,
+}
+
+impl ImportReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|issue| issue.severity == IssueSeverity::Error)
+    }
+
+    fn error(&mut self, line: Option<usize>, message: impl Into<String>) {
+        self.issues.push(ImportIssue { line, severity: IssueSeverity::Error, message: message.into() });
+    }
+
+    fn warning(&mut self, line: Option<usize>, message: impl Into<String>) {
+        self.issues.push(ImportIssue { line, severity: IssueSeverity::Warning, message: message.into() });
+    }
+}
+
+/// Reads an export from an external system
+pub trait ImportAdapter {
+    /// Name of the source system, for the report
+    fn source(&self) -> &str;
+
+    /// Read the export. Rows that can't be read are reported and skipped; an export
+    /// that can't be read at all is an error.
+    fn read(&self, input: &str, report: &mut ImportReport) -> LifecycleResult<Vec<SourceRecord>>;
+}
+
+/// Column names of a member spreadsheet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvColumns {
+    pub external_id: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub did: Option<String>,
+    /// Column listing roles separated by `;`
+    pub roles: Option<String>,
+    /// Column holding the member's balance in minor units
+    pub balance: Option<String>,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            external_id: "id".to_string(),
+            name: Some("name".to_string()),
+            email: Some("email".to_string()),
+            did: Some("did".to_string()),
+            roles: Some("roles".to_string()),
+            balance: Some("balance".to_string()),
+        }
+    }
+}
+
+/// Reads a spreadsheet with one member per row
+#[derive(Debug, Clone, Default)]
+pub struct CsvImportAdapter {
+    pub columns: CsvColumns,
+    /// Currency balances are denominated in
+    pub currency: Option<String>,
+}
+
+impl ImportAdapter for CsvImportAdapter {
+    fn source(&self) -> &str {
+        "csv"
+    }
+
+    fn read(&self, input: &str, report: &mut ImportReport) -> LifecycleResult<Vec<SourceRecord>> {
+        let table = CsvTable::parse(input)?;
+        let id_column = table.require(&self.columns.external_id)?;
+        // Optional columns missing from the file are left empty
+        let optional = |name: &Option<String>| name.as_ref().and_then(|name| table.column(name));
+        let (name_column, email_column, did_column) =
+            (optional(&self.columns.name), optional(&self.columns.email), optional(&self.columns.did));
+        let (roles_column, balance_column) = (optional(&self.columns.roles), optional(&self.columns.balance));
+
+        let mut records = Vec::new();
+        for (line, row) in table.rows() {
+            let external_id = row[id_column].clone();
+            if external_id.is_empty() {
+                report.error(Some(line), format!("Missing {}", self.columns.external_id));
+                continue;
+            }
+            let cell = |column: Option<usize>| column.map(|c| row[c].clone()).filter(|value| !value.is_empty());
+
+            records.push(SourceRecord {
+                line,
+                record: ExternalRecord::Member(ExternalMember {
+                    external_id: external_id.clone(),
+                    name: cell(name_column),
+                    email: cell(email_column),
+                    did: cell(did_column),
+                    roles: cell(roles_column)
+                        .map(|roles| roles.split(';').map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect())
+                        .unwrap_or_default(),
+                }),
+            });
+
+            if let Some(balance) = cell(balance_column) {
+                match balance.trim().parse::<i64>() {
+                    Ok(amount) => records.push(SourceRecord {
+                        line,
+                        record: ExternalRecord::Balance(ExternalBalance {
+                            external_id,
+                            amount,
+                            currency: self.currency.clone(),
+                        }),
+                    }),
+                    Err(_) => report.error(Some(line), format!("Invalid balance '{}'", balance)),
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// Reads an Open Collective transactions export
+///
+/// Every account that contributed to the collective becomes a member with the
+/// `backer` role, and is credited with its contributions net of refunds. Amounts are
+/// read in the export's currency and converted to minor units.
+#[derive(Debug, Clone, Default)]
+pub struct OpenCollectiveImportAdapter;
+
+impl ImportAdapter for OpenCollectiveImportAdapter {
+    fn source(&self) -> &str {
+        "opencollective"
+    }
+
+    fn read(&self, input: &str, report: &mut ImportReport) -> LifecycleResult<Vec<SourceRecord>> {
+        let table = CsvTable::parse(input)?;
+        let type_column = table.require("type")?;
+        let kind_column = table.require("kind")?;
+        let amount_column = table.require("amount")?;
+        let currency_column = table.require("currency")?;
+        let slug_column = table.require("oppositeAccountSlug")?;
+        let name_column = table.column("oppositeAccountName");
+
+        let mut members = HashSet::new();
+        let mut records = Vec::new();
+        for (line, row) in table.rows() {
+            let kind = row[kind_column].as_str();
+            if kind != "CONTRIBUTION" && kind != "ADDED_FUNDS" && kind != "REFUND" {
+                continue;
+            }
+            let slug = row[slug_column].clone();
+            if slug.is_empty() {
+                report.error(Some(line), "Contribution without a contributor account");
+                continue;
+            }
+            let amount = match parse_minor_units(&row[amount_column]) {
+                Some(amount) => amount,
+                None => {
+                    report.error(Some(line), format!("Invalid amount '{}'", row[amount_column]));
+                    continue;
+                }
+            };
+            // Amounts are signed from the collective's side: debits (refunds) reduce the balance
+            let amount = if row[type_column] == "DEBIT" { -amount.abs() } else { amount.abs() };
+
+            if members.insert(slug.clone()) {
+                records.push(SourceRecord {
+                    line,
+                    record: ExternalRecord::Member(ExternalMember {
+                        external_id: slug.clone(),
+                        name: name_column.map(|c| row[c].clone()).filter(|name| !name.is_empty()),
+                        email: None,
+                        did: None,
+                        roles: vec!["backer".to_string()],
+                    }),
+                });
+            }
+            records.push(SourceRecord {
+                line,
+                record: ExternalRecord::Balance(ExternalBalance {
+                    external_id: slug,
+                    amount,
+                    currency: Some(row[currency_column].clone()).filter(|c| !c.is_empty()),
+                }),
+            });
+        }
+        Ok(records)
+    }
+}
+
+/// How records are mapped into a federation
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Federation the records are imported into
+    pub federation_id: Did,
+    /// Issuer of the membership credentials
+    pub issuer: IdentityId,
+    /// DIDs of members whose source records carry none, by external ID
+    pub known_dids: HashMap<String, Did>,
+    /// Currency the federation's ledger is kept in; balances in others are rejected
+    pub currency: Option<String>,
+    /// Roles given to every imported member in addition to their own
+    pub default_roles: Vec<String>,
+    /// Validate and report without applying anything
+    pub dry_run: bool,
+}
+
+impl ImportOptions {
+    pub fn new(federation_id: impl Into<Did>, issuer: IdentityId) -> Self {
+        Self {
+            federation_id: federation_id.into(),
+            issuer,
+            known_dids: HashMap::new(),
+            currency: None,
+            default_roles: vec!["member".to_string()],
+            dry_run: false,
+        }
+    }
+
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+}
+
+/// A member as they will appear on the federation's roster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub did: Did,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub roles: Vec<String>,
+    /// Where the member was imported from
+    pub source: String,
+    pub external_id: String,
+}
+
+/// Everything an import adds to a federation
+#[derive(Debug)]
+pub struct ImportBatch {
+    pub roster: Vec<RosterEntry>,
+    pub ledger: Ledger,
+    pub credentials: Vec<VerifiableCredential>,
+}
+
+/// Applies an import batch, e.g., as part of bootstrapping a federation
+#[async_trait]
+pub trait ImportSink: Send {
+    async fn apply(&mut self, batch: &ImportBatch) -> LifecycleResult<()>;
+}
+
+/// Validate records read by `adapter` and map them onto a batch
+pub fn plan_import(
+    adapter: &dyn ImportAdapter,
+    input: &str,
+    options: &ImportOptions,
+) -> LifecycleResult<(ImportBatch, ImportReport)> {
+    let mut report = ImportReport {
+        source: adapter.source().to_string(),
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    let records = adapter.read(input, &mut report)?;
+    report.records_read = records.len();
+
+    // Members first, so balances can refer to members listed further down
+    let mut roster: Vec<RosterEntry> = Vec::new();
+    let mut did_of: HashMap<String, Did> = HashMap::new();
+    let mut dids = HashSet::new();
+    let mut emails: HashMap<String, String> = HashMap::new();
+    for SourceRecord { line, record } in &records {
+        let member = match record {
+            ExternalRecord::Member(member) => member,
+            ExternalRecord::Balance(_) => continue,
+        };
+        if did_of.contains_key(&member.external_id) {
+            report.error(Some(*line), format!("Member {} is listed twice", member.external_id));
+            continue;
+        }
+        let did = match member.did.clone().or_else(|| options.known_dids.get(&member.external_id).cloned()) {
+            Some(did) if did.starts_with("did:") => did,
+            Some(did) => {
+                report.error(Some(*line), format!("Member {} has an invalid DID '{}'", member.external_id, did));
+                continue;
+            }
+            None => {
+                report.error(Some(*line), format!("Member {} has no DID", member.external_id));
+                continue;
+            }
+        };
+        if !dids.insert(did.clone()) {
+            report.error(Some(*line), format!("DID {} belongs to more than one member", did));
+            continue;
+        }
+        if let Some(email) = &member.email {
+            if !email.contains('@') {
+                report.warning(Some(*line), format!("Member {} has an invalid email '{}'", member.external_id, email));
+            } else if let Some(other) = emails.insert(email.to_lowercase(), member.external_id.clone()) {
+                report.warning(Some(*line), format!("Members {} and {} share the email {}", other, member.external_id, email));
+            }
+        }
+
+        let mut roles = options.default_roles.clone();
+        for role in &member.roles {
+            if !roles.contains(role) {
+                roles.push(role.clone());
+            }
+        }
+        did_of.insert(member.external_id.clone(), did.clone());
+        roster.push(RosterEntry {
+            did,
+            name: member.name.clone(),
+            email: member.email.clone(),
+            roles,
+            source: report.source.clone(),
+            external_id: member.external_id.clone(),
+        });
+    }
+
+    let mut balances: BTreeMap<Did, i64> = BTreeMap::new();
+    for SourceRecord { line, record } in &records {
+        let balance = match record {
+            ExternalRecord::Balance(balance) => balance,
+            ExternalRecord::Member(_) => continue,
+        };
+        let did = match did_of.get(&balance.external_id) {
+            Some(did) => did,
+            None => {
+                report.error(Some(*line), format!("Balance for unknown member {}", balance.external_id));
+                continue;
+            }
+        };
+        if let (Some(expected), Some(currency)) = (&options.currency, &balance.currency) {
+            if !expected.eq_ignore_ascii_case(currency) {
+                report.error(Some(*line), format!("Balance in {} but the ledger is kept in {}", currency, expected));
+                continue;
+            }
+        }
+        *balances.entry(did.clone()).or_insert(0) += balance.amount;
+    }
+
+    let mut ledger = Ledger::new();
+    for entry in &roster {
+        ledger.create_account(&entry.did).map_err(|e| {
+            LifecycleError::LedgerOperationFailed(format!("Failed to create account for {}: {}", entry.did, e))
+        })?;
+    }
+    for (did, amount) in &balances {
+        if *amount < 0 {
+            report.error(None, format!("Member {} would open with a negative balance of {}", did, amount));
+            continue;
+        }
+        if *amount > 0 {
+            ledger.credit(did, *amount as u64).map_err(|e| {
+                LifecycleError::LedgerOperationFailed(format!("Failed to credit {}: {}", did, e))
+            })?;
+            report.total_balance += *amount as u64;
+        }
+    }
+    report.balances = balances.len();
+
+    let federation = IdentityId::new(options.federation_id.clone());
+    let credentials: Vec<VerifiableCredential> = roster.iter()
+        .map(|entry| VerifiableCredential::new(
+            vec!["VerifiableCredential".to_string(), IMPORTED_MEMBERSHIP_CREDENTIAL.to_string()],
+            &options.issuer,
+            &IdentityId::new(entry.did.clone()),
+            serde_json::json!({
+                "federation": federation.0,
+                "roles": entry.roles,
+                "importedFrom": entry.source,
+                "externalId": entry.external_id,
+            }),
+        ))
+        .collect();
+
+    report.members = roster.len();
+    report.credentials = credentials.len();
+    Ok((ImportBatch { roster, ledger, credentials }, report))
+}
+
+/// Plan an import and, unless it is a dry run or found errors, apply it to `sink`
+pub async fn run_import(
+    adapter: &dyn ImportAdapter,
+    input: &str,
+    options: &ImportOptions,
+    sink: &mut dyn ImportSink,
+) -> LifecycleResult<ImportReport> {
+    let (batch, mut report) = plan_import(adapter, input, options)?;
+    if options.dry_run || report.has_errors() {
+        info!(
+            "Import from {} not applied ({} members, {} issues{})",
+            report.source, report.members, report.issues.len(), if options.dry_run { ", dry run" } else { "" }
+        );
+        return Ok(report);
+    }
+
+    sink.apply(&batch).await?;
+    report.applied = true;
+    info!("Imported {} members from {} into {}", report.members, report.source, options.federation_id);
+    Ok(report)
+}
+
+/// Parse a decimal amount such as `-12.5` into minor units (hundredths)
+fn parse_minor_units(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() || fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let cents = whole.parse::<i64>().ok()?.checked_mul(100)?
+        .checked_add(format!("{:0<2}", fraction).parse::<i64>().ok()?)?;
+    Some(if negative { -cents } else { cents })
+}
+
+/// A CSV document with a header row
+struct CsvTable {
+    header: Vec<String>,
+    /// Rows with the line they start on
+    rows: Vec<(usize, Vec<String>)>,
+}
+
+impl CsvTable {
+    /// Parse RFC 4180 CSV: quoted fields may contain commas, quotes (doubled) and newlines
+    fn parse(input: &str) -> LifecycleResult<Self> {
+        let mut rows = Vec::new();
+        let (mut row, mut field) = (Vec::new(), String::new());
+        let (mut in_quotes, mut line, mut row_line) = (false, 1, 1);
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                },
+                '"' if in_quotes => in_quotes = false,
+                '"' if field.is_empty() => in_quotes = true,
+                ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+                '\r' if !in_quotes => {},
+                '\n' if !in_quotes => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push((row_line, std::mem::take(&mut row)));
+                    line += 1;
+                    row_line = line;
+                },
+                '\n' => {
+                    field.push(c);
+                    line += 1;
+                },
+                _ => field.push(c),
+            }
+        }
+        if in_quotes {
+            return Err(LifecycleError::InvalidProposal(format!("Unterminated quoted field starting on line {}", row_line)));
+        }
+        if !field.is_empty() || !row.is_empty() {
+            row.push(field);
+            rows.push((row_line, row));
+        }
+
+        rows.retain(|(_, row)| !(row.len() == 1 && row[0].trim().is_empty()));
+        if rows.is_empty() {
+            return Err(LifecycleError::InvalidProposal("Import file is empty".to_string()));
+        }
+        let (_, header) = rows.remove(0);
+        let header: Vec<String> = header.into_iter().map(|h| h.trim().to_string()).collect();
+        for (line, row) in &mut rows {
+            if row.len() != header.len() {
+                return Err(LifecycleError::InvalidProposal(format!(
+                    "Line {} has {} fields, the header has {}", line, row.len(), header.len()
+                )));
+            }
+            for value in row.iter_mut() {
+                *value = value.trim().to_string();
+            }
+        }
+        Ok(Self { header, rows })
+    }
+
+    fn column(&self, name: &str) -> Option<usize> {
+        self.header.iter().position(|h| h.eq_ignore_ascii_case(name))
+    }
+
+    fn require(&self, name: &str) -> LifecycleResult<usize> {
+        self.column(name)
+            .ok_or_else(|| LifecycleError::InvalidProposal(format!("Import file has no '{}' column", name)))
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (usize, &Vec<String>)> {
+        self.rows.iter().map(|(line, row)| (*line, row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        applied: Vec<RosterEntry>,
+    }
+
+    #[async_trait]
+    impl ImportSink for RecordingSink {
+        async fn apply(&mut self, batch: &ImportBatch) -> LifecycleResult<()> {
+            self.applied.extend(batch.roster.iter().cloned());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spreadsheet_and_open_collective_imports() {
+        let csv = "id,name,email,did,roles,balance\n\
+                   1,Ana,ana@example.coop,did:icn:ana,treasurer,1200\n\
+                   2,\"Ben, Jr.\",ana@example.coop,,,300\n\
+                   3,Cy,cy@example.coop,,,\n";
+        let mut options = ImportOptions::new("did:icn:fed", IdentityId::new("did:icn:fed"));
+        options.known_dids.insert("2".to_string(), "did:icn:ben".to_string());
+        let mut sink = RecordingSink::default();
+
+        // Cy has no DID, so nothing is applied
+        let report = run_import(&CsvImportAdapter::default(), csv, &options, &mut sink).await.unwrap();
+        assert!(report.has_errors() && !report.applied);
+        assert_eq!(report.issues.iter().find(|i| i.severity == IssueSeverity::Error).unwrap().line, Some(4));
+        assert!(report.issues.iter().any(|i| i.severity == IssueSeverity::Warning && i.message.contains("share the email")));
+
+        options.known_dids.insert("3".to_string(), "did:icn:cy".to_string());
+        let dry = run_import(&CsvImportAdapter::default(), csv, &options.clone().dry_run(), &mut sink).await.unwrap();
+        assert!(!dry.has_errors() && !dry.applied && sink.applied.is_empty());
+        assert_eq!((dry.members, dry.credentials, dry.total_balance), (3, 3, 1_500));
+
+        let (batch, _) = plan_import(&CsvImportAdapter::default(), csv, &options).unwrap();
+        assert_eq!(batch.roster[0].roles, vec!["member".to_string(), "treasurer".to_string()]);
+        assert_eq!(batch.roster[1].name.as_deref(), Some("Ben, Jr."));
+        assert!(run_import(&CsvImportAdapter::default(), csv, &options, &mut sink).await.unwrap().applied);
+        assert_eq!(sink.applied.len(), 3);
+
+        // Contributions net of refunds become opening balances in minor units
+        let transactions = "datetime,type,kind,amount,currency,oppositeAccountSlug,oppositeAccountName\n\
+                            2026-01-03,CREDIT,CONTRIBUTION,25.00,EUR,dana,Dana\n\
+                            2026-02-03,CREDIT,CONTRIBUTION,25.5,EUR,dana,Dana\n\
+                            2026-02-10,DEBIT,REFUND,-5.00,EUR,dana,Dana\n\
+                            2026-02-11,DEBIT,EXPENSE,-80.00,EUR,printer,Print Shop\n";
+        options.known_dids.insert("dana".to_string(), "did:icn:dana".to_string());
+        options.currency = Some("EUR".to_string());
+        let (batch, report) = plan_import(&OpenCollectiveImportAdapter, transactions, &options).unwrap();
+        assert!(!report.has_errors());
+        assert_eq!(batch.roster.len(), 1);
+        assert_eq!(batch.roster[0].roles, vec!["member".to_string(), "backer".to_string()]);
+        assert_eq!(report.total_balance, 4_550);
+    }
+}
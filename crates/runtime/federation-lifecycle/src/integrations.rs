@@ -0,0 +1,503 @@
+//! Notifying downstream integrations after a merge or split
+//!
+//! Payment processors, UIs and partner APIs are bound to a federation DID. When that
+//! federation merges or splits, they need to learn the DID that replaced it and the
+//! trust bundle to verify it with, and they need to consent to follow it. The
+//! [`IntegrationRegistry`] records which integrations are bound to which federation.
+//! Once a process has executed, [`IntegrationRegistry::notify_merge`] and
+//! [`IntegrationRegistry::notify_split`] send every affected integration a
+//! [`LifecycleNotice`] through an [`IntegrationNotifier`]. An integration re-consents by
+//! acknowledging the notice for the successor it follows, which rebinds it.
+//!
+//! Delivery failures don't fail the process; they're recorded and can be retried with
+//! [`IntegrationRegistry::redeliver`]. The [`ReconciliationReport`] for a process lists
+//! the integrations that haven't acknowledged yet.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::{MergeProcess, MergeStatus, SplitProcess, SplitStatus};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use icn_identity::Did;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// A downstream system bound to a federation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Integration {
+    /// Integration identifier
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Where notices are delivered (a webhook URL, queue name, ...)
+    pub endpoint: String,
+    /// Federation the integration currently follows
+    pub federation_id: Did,
+    /// When the integration was registered
+    pub registered_at: DateTime<Utc>,
+    /// When the integration last consented to the federation it follows
+    pub consented_at: DateTime<Utc>,
+}
+
+/// The lifecycle change a notice announces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LifecycleChange {
+    Merge,
+    Split,
+}
+
+/// A federation that replaced the one an integration was bound to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuccessorFederation {
+    /// DID of the new federation
+    pub federation_id: Did,
+    /// The new federation's trust bundle
+    pub trust_bundle: serde_json::Value,
+    /// Hex SHA-256 of the serialized trust bundle, so integrations can pin it
+    pub trust_bundle_hash: String,
+}
+
+impl SuccessorFederation {
+    fn new<T: Serialize>(federation_id: &Did, bundle: &T) -> LifecycleResult<Self> {
+        let bytes = serde_json::to_vec(bundle)
+            .map_err(|e| LifecycleError::BundleSerializationError(e.to_string()))?;
+        let trust_bundle = serde_json::from_slice(&bytes)
+            .map_err(|e| LifecycleError::BundleSerializationError(e.to_string()))?;
+        let trust_bundle_hash = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+
+        Ok(Self { federation_id: federation_id.clone(), trust_bundle, trust_bundle_hash })
+    }
+}
+
+/// What an integration is told after a merge or split
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleNotice {
+    /// Merge or split process the notice is about
+    pub process_id: String,
+    /// Whether the federations merged or split
+    pub change: LifecycleChange,
+    /// Federations that no longer exist
+    pub previous_federations: Vec<Did>,
+    /// Federations that replaced them; a split has one per resulting federation
+    pub successors: Vec<SuccessorFederation>,
+    /// When the notice was issued
+    pub issued_at: DateTime<Utc>,
+}
+
+impl LifecycleNotice {
+    /// The successor with the given DID
+    pub fn successor(&self, federation_id: &Did) -> Option<&SuccessorFederation> {
+        self.successors.iter().find(|s| &s.federation_id == federation_id)
+    }
+}
+
+/// Where an integration stands on a notice
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoticeStatus {
+    /// Delivered, not yet acknowledged
+    Delivered,
+    /// The last delivery attempt failed
+    DeliveryFailed(String),
+    /// The integration re-consented to follow a successor
+    Acknowledged { federation_id: Did, at: DateTime<Utc> },
+}
+
+/// Delivery and acknowledgement of a notice to one integration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoticeDelivery {
+    /// Integration the notice was sent to
+    pub integration_id: String,
+    /// Current status
+    pub status: NoticeStatus,
+    /// Delivery attempts made
+    pub attempts: u32,
+    /// When delivery was last attempted
+    pub last_attempt: DateTime<Utc>,
+}
+
+/// Delivers lifecycle notices to integrations
+#[async_trait]
+pub trait IntegrationNotifier: Send + Sync {
+    /// Deliver a notice to an integration's endpoint
+    async fn notify(&self, integration: &Integration, notice: &LifecycleNotice) -> LifecycleResult<()>;
+}
+
+/// An integration that hasn't re-consented after a process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnacknowledgedIntegration {
+    pub integration_id: String,
+    pub name: String,
+    pub endpoint: String,
+    /// Federation the integration is still bound to
+    pub federation_id: Did,
+    pub status: NoticeStatus,
+    pub attempts: u32,
+}
+
+/// Where a process stands with the integrations it affected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub process_id: String,
+    pub change: LifecycleChange,
+    /// Integrations the process affected
+    pub notified: usize,
+    /// Integrations that re-consented, with the federation each now follows
+    pub acknowledged: Vec<(String, Did)>,
+    /// Integrations still waiting to re-consent, sorted by ID
+    pub unacknowledged: Vec<UnacknowledgedIntegration>,
+}
+
+impl ReconciliationReport {
+    /// Whether every affected integration has re-consented
+    pub fn is_reconciled(&self) -> bool {
+        self.unacknowledged.is_empty()
+    }
+}
+
+/// Integrations bound to federations, and the notices sent to them
+#[derive(Debug, Default)]
+pub struct IntegrationRegistry {
+    integrations: HashMap<String, Integration>,
+    notices: HashMap<String, LifecycleNotice>,
+    deliveries: HashMap<String, Vec<NoticeDelivery>>,
+}
+
+impl IntegrationRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind an integration to a federation. Returns the integration ID.
+    pub fn register(&mut self, name: &str, endpoint: &str, federation_id: &Did) -> String {
+        let now = Utc::now();
+        let id = uuid::Uuid::new_v4().to_string();
+        self.integrations.insert(id.clone(), Integration {
+            id: id.clone(),
+            name: name.to_string(),
+            endpoint: endpoint.to_string(),
+            federation_id: federation_id.clone(),
+            registered_at: now,
+            consented_at: now,
+        });
+        id
+    }
+
+    /// Remove an integration
+    pub fn deregister(&mut self, integration_id: &str) -> Option<Integration> {
+        self.integrations.remove(integration_id)
+    }
+
+    /// Get an integration
+    pub fn get(&self, integration_id: &str) -> Option<&Integration> {
+        self.integrations.get(integration_id)
+    }
+
+    /// Integrations bound to a federation, sorted by ID
+    pub fn integrations_for(&self, federation_id: &Did) -> Vec<&Integration> {
+        let mut bound: Vec<&Integration> = self.integrations.values()
+            .filter(|i| &i.federation_id == federation_id)
+            .collect();
+        bound.sort_by(|a, b| a.id.cmp(&b.id));
+        bound
+    }
+
+    /// The notice sent for a process
+    pub fn notice(&self, process_id: &str) -> Option<&LifecycleNotice> {
+        self.notices.get(process_id)
+    }
+
+    /// Tell the integrations bound to either source federation about a completed merge
+    pub async fn notify_merge(
+        &mut self,
+        process: &MergeProcess,
+        notifier: &dyn IntegrationNotifier,
+    ) -> LifecycleResult<ReconciliationReport> {
+        if process.status != MergeStatus::Completed {
+            return Err(LifecycleError::InvalidFederationState(format!(
+                "Merge process {} has not completed", process.id
+            )));
+        }
+
+        let notice = LifecycleNotice {
+            process_id: process.id.clone(),
+            change: LifecycleChange::Merge,
+            previous_federations: vec![process.federation_a_id.clone(), process.federation_b_id.clone()],
+            successors: vec![SuccessorFederation::new(&process.new_federation_id, &process.merged_bundle)?],
+            issued_at: Utc::now(),
+        };
+        self.send_notice(notice, notifier).await
+    }
+
+    /// Tell the integrations bound to the original federation about a completed split.
+    /// Each integration acknowledges the resulting federation it follows.
+    pub async fn notify_split(
+        &mut self,
+        process: &SplitProcess,
+        notifier: &dyn IntegrationNotifier,
+    ) -> LifecycleResult<ReconciliationReport> {
+        if process.status != SplitStatus::Completed {
+            return Err(LifecycleError::InvalidFederationState(format!(
+                "Split process {} has not completed", process.id
+            )));
+        }
+
+        let notice = LifecycleNotice {
+            process_id: process.id.clone(),
+            change: LifecycleChange::Split,
+            previous_federations: vec![process.original_federation_id.clone()],
+            successors: vec![
+                SuccessorFederation::new(&process.federation_a_id, &process.bundle_a)?,
+                SuccessorFederation::new(&process.federation_b_id, &process.bundle_b)?,
+            ],
+            issued_at: Utc::now(),
+        };
+        self.send_notice(notice, notifier).await
+    }
+
+    async fn send_notice(
+        &mut self,
+        notice: LifecycleNotice,
+        notifier: &dyn IntegrationNotifier,
+    ) -> LifecycleResult<ReconciliationReport> {
+        if self.notices.contains_key(&notice.process_id) {
+            return Err(LifecycleError::ProcessAlreadyInProgress(format!(
+                "Integrations were already notified of process {}", notice.process_id
+            )));
+        }
+
+        let mut affected: Vec<String> = self.integrations.values()
+            .filter(|i| notice.previous_federations.contains(&i.federation_id))
+            .map(|i| i.id.clone())
+            .collect();
+        affected.sort();
+
+        let mut deliveries = Vec::with_capacity(affected.len());
+        for integration_id in affected {
+            let status = self.deliver(&integration_id, &notice, notifier).await;
+            deliveries.push(NoticeDelivery { integration_id, status, attempts: 1, last_attempt: Utc::now() });
+        }
+
+        info!("Notified {} integrations of process {}", deliveries.len(), notice.process_id);
+        let process_id = notice.process_id.clone();
+        self.deliveries.insert(process_id.clone(), deliveries);
+        self.notices.insert(process_id.clone(), notice);
+        self.reconciliation_report(&process_id)
+    }
+
+    async fn deliver(&self, integration_id: &str, notice: &LifecycleNotice, notifier: &dyn IntegrationNotifier) -> NoticeStatus {
+        let integration = match self.integrations.get(integration_id) {
+            Some(integration) => integration,
+            None => return NoticeStatus::DeliveryFailed("Integration was deregistered".to_string()),
+        };
+        match notifier.notify(integration, notice).await {
+            Ok(()) => NoticeStatus::Delivered,
+            Err(e) => {
+                warn!("Failed to notify integration {} of process {}: {}", integration_id, notice.process_id, e);
+                NoticeStatus::DeliveryFailed(e.to_string())
+            }
+        }
+    }
+
+    /// Retry delivery to the integrations whose notice for a process failed.
+    /// Returns the updated report.
+    pub async fn redeliver(
+        &mut self,
+        process_id: &str,
+        notifier: &dyn IntegrationNotifier,
+    ) -> LifecycleResult<ReconciliationReport> {
+        let notice = self.notices.get(process_id).cloned()
+            .ok_or_else(|| LifecycleError::ProcessNotFound(process_id.to_string()))?;
+        let mut deliveries = self.deliveries.remove(process_id).unwrap_or_default();
+
+        for delivery in deliveries.iter_mut() {
+            if matches!(delivery.status, NoticeStatus::DeliveryFailed(_)) {
+                delivery.status = self.deliver(&delivery.integration_id, &notice, notifier).await;
+                delivery.attempts += 1;
+                delivery.last_attempt = Utc::now();
+            }
+        }
+
+        self.deliveries.insert(process_id.to_string(), deliveries);
+        self.reconciliation_report(process_id)
+    }
+
+    /// Record that an integration re-consented to follow `federation_id`, one of the
+    /// process's successors, and rebind it there
+    pub fn acknowledge(&mut self, process_id: &str, integration_id: &str, federation_id: &Did) -> LifecycleResult<()> {
+        let notice = self.notices.get(process_id)
+            .ok_or_else(|| LifecycleError::ProcessNotFound(process_id.to_string()))?;
+        if notice.successor(federation_id).is_none() {
+            return Err(LifecycleError::InvalidFederationState(format!(
+                "{} is not a successor of process {}", federation_id, process_id
+            )));
+        }
+
+        let delivery = self.deliveries.get_mut(process_id)
+            .and_then(|deliveries| deliveries.iter_mut().find(|d| d.integration_id == integration_id))
+            .ok_or_else(|| LifecycleError::AuthorizationFailed(format!(
+                "Integration {} was not notified of process {}", integration_id, process_id
+            )))?;
+        let integration = self.integrations.get_mut(integration_id)
+            .ok_or_else(|| LifecycleError::InvalidFederationState(format!(
+                "Integration {} is not registered", integration_id
+            )))?;
+
+        let now = Utc::now();
+        delivery.status = NoticeStatus::Acknowledged { federation_id: federation_id.clone(), at: now };
+        integration.federation_id = federation_id.clone();
+        integration.consented_at = now;
+        Ok(())
+    }
+
+    /// Which integrations a process affected have and haven't re-consented
+    pub fn reconciliation_report(&self, process_id: &str) -> LifecycleResult<ReconciliationReport> {
+        let notice = self.notices.get(process_id)
+            .ok_or_else(|| LifecycleError::ProcessNotFound(process_id.to_string()))?;
+        let deliveries = self.deliveries.get(process_id).map(Vec::as_slice).unwrap_or_default();
+
+        let mut acknowledged = Vec::new();
+        let mut unacknowledged = Vec::new();
+        for delivery in deliveries {
+            if let NoticeStatus::Acknowledged { federation_id, .. } = &delivery.status {
+                acknowledged.push((delivery.integration_id.clone(), federation_id.clone()));
+                continue;
+            }
+            let integration = self.integrations.get(&delivery.integration_id);
+            unacknowledged.push(UnacknowledgedIntegration {
+                integration_id: delivery.integration_id.clone(),
+                name: integration.map(|i| i.name.clone()).unwrap_or_default(),
+                endpoint: integration.map(|i| i.endpoint.clone()).unwrap_or_default(),
+                federation_id: integration.map(|i| i.federation_id.clone()).unwrap_or_default(),
+                status: delivery.status.clone(),
+                attempts: delivery.attempts,
+            });
+        }
+
+        Ok(ReconciliationReport {
+            process_id: process_id.to_string(),
+            change: notice.change,
+            notified: deliveries.len(),
+            acknowledged,
+            unacknowledged,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LineageAttestation, LineageAttestationType, MergeProposal, PolicyConflictReport, PolicyMergeStrategy,
+        PreMergeBundle, QuorumConfig, TrustMapping,
+    };
+    use icn_identity::QuorumProof;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FlakyNotifier {
+        failing: Mutex<Vec<String>>,
+        delivered: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl IntegrationNotifier for FlakyNotifier {
+        async fn notify(&self, integration: &Integration, notice: &LifecycleNotice) -> LifecycleResult<()> {
+            if self.failing.lock().unwrap().contains(&integration.endpoint) {
+                return Err(LifecycleError::NetworkError(format!("{} unreachable", integration.endpoint)));
+            }
+            self.delivered.lock().unwrap().push((integration.endpoint.clone(), notice.process_id.clone()));
+            Ok(())
+        }
+    }
+
+    fn merge() -> MergeProcess {
+        MergeProcess {
+            id: "merge-1".to_string(),
+            federation_a_id: "did:icn:fed-a".to_string(),
+            federation_b_id: "did:icn:fed-b".to_string(),
+            additional_federation_ids: Vec::new(),
+            new_federation_id: "did:icn:fed-ab".to_string(),
+            merge_proposal: MergeProposal {
+                src_fed_a: "did:icn:fed-a".to_string(),
+                src_fed_b: "did:icn:fed-b".to_string(),
+                new_meta_cid: cid::Cid::default(),
+                quorum_cfg: QuorumConfig { threshold: 1, authorized_signers: vec![], weights: None },
+                challenge_window_secs: 0,
+                approval_a: None,
+                approval_b: None,
+                stages: Vec::new(),
+                policy_merge_strategy: PolicyMergeStrategy::default(),
+                additional_sources: Vec::new(),
+            },
+            trust_mapping: TrustMapping {
+                did_mappings: HashMap::new(),
+                role_assignments: HashMap::new(),
+                credential_validations: vec![],
+            },
+            merged_policy: HashMap::new(),
+            policy_conflicts: PolicyConflictReport::default(),
+            merged_bundle: PreMergeBundle {
+                dag_roots: vec![],
+                metadata: HashMap::new(),
+                lineage: LineageAttestation {
+                    parents: vec!["did:icn:fed-a".to_string(), "did:icn:fed-b".to_string()],
+                    children: vec!["did:icn:fed-ab".to_string()],
+                    typ: LineageAttestationType::Merge,
+                    proof: QuorumProof::default(),
+                    timestamp: Utc::now(),
+                    metadata: HashMap::new(),
+                },
+                proofs: vec![],
+            },
+            status: MergeStatus::Completed,
+            start_time: Utc::now(),
+            completion_time: Some(Utc::now()),
+            completed_stages: Vec::new(),
+            checkpoint_votes: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_notifies_and_tracks_reconsent() {
+        let mut registry = IntegrationRegistry::new();
+        let payments = registry.register("payments", "https://pay.example/hook", &"did:icn:fed-a".to_string());
+        let ui = registry.register("member-ui", "https://ui.example/hook", &"did:icn:fed-b".to_string());
+        let other = registry.register("other", "https://other.example/hook", &"did:icn:fed-c".to_string());
+
+        let notifier = FlakyNotifier::default();
+        notifier.failing.lock().unwrap().push("https://ui.example/hook".to_string());
+
+        let mut unfinished = merge();
+        unfinished.status = MergeStatus::Executing;
+        assert!(registry.notify_merge(&unfinished, &notifier).await.is_err());
+
+        let report = registry.notify_merge(&merge(), &notifier).await.unwrap();
+        assert_eq!(report.notified, 2);
+        assert_eq!(report.unacknowledged.len(), 2);
+        assert!(registry.notify_merge(&merge(), &notifier).await.is_err());
+
+        // Only a successor of the process can be acknowledged
+        assert!(registry.acknowledge("merge-1", &payments, &"did:icn:fed-c".to_string()).is_err());
+        assert!(registry.acknowledge("merge-1", &other, &"did:icn:fed-ab".to_string()).is_err());
+        registry.acknowledge("merge-1", &payments, &"did:icn:fed-ab".to_string()).unwrap();
+        assert_eq!(registry.get(&payments).unwrap().federation_id, "did:icn:fed-ab");
+
+        let report = registry.reconciliation_report("merge-1").unwrap();
+        assert_eq!(report.acknowledged, vec![(payments.clone(), "did:icn:fed-ab".to_string())]);
+        assert_eq!(report.unacknowledged.len(), 1);
+        assert_eq!(report.unacknowledged[0].integration_id, ui);
+        assert!(matches!(report.unacknowledged[0].status, NoticeStatus::DeliveryFailed(_)));
+
+        notifier.failing.lock().unwrap().clear();
+        let report = registry.redeliver("merge-1", &notifier).await.unwrap();
+        assert_eq!(report.unacknowledged[0].status, NoticeStatus::Delivered);
+        assert_eq!(report.unacknowledged[0].attempts, 2);
+
+        registry.acknowledge("merge-1", &ui, &"did:icn:fed-ab".to_string()).unwrap();
+        assert!(registry.reconciliation_report("merge-1").unwrap().is_reconciled());
+        assert_eq!(registry.integrations_for(&"did:icn:fed-ab".to_string()).len(), 2);
+        assert_eq!(registry.get(&other).unwrap().federation_id, "did:icn:fed-c");
+    }
+}
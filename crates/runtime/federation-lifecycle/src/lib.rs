@@ -8,102 +8,188 @@ pub mod error;
 pub mod bundle;
 pub mod executor;
 pub mod economics;
+pub mod delta;
+pub mod impact;
+pub mod carve;
+pub mod alias;
+pub mod staged;
+pub mod integrations;
+pub mod history;
+pub mod summary;
+pub mod dedup;
+pub mod import;
+pub mod split_phases;
+pub mod escrow;
 
 pub use types::{
     LineageAttestation, LineageAttestationType, MergeProposal, SplitProposal,
     PreMergeBundle, SplitBundle, QuorumConfig, PartitionMap, ResourceAllocation,
     MergeProcess, SplitProcess, MergeStatus, SplitStatus, TrustMapping, CredentialValidation,
+    MergeStage, MergeStageSpec, StageRecord, CheckpointVote,
+    PolicyMergeStrategy, PolicyResolution, PolicyConflict, PolicyConflictReport, MergeSource,
+    SplitPhase, SplitPhaseRecord, SplitCheckpoint,
 };
 pub use error::{LifecycleError, LifecycleResult};
 pub use bundle::{
     create_trust_mapping, create_merged_governance_policy,
     create_merged_trust_bundle, create_split_trust_bundle,
+    create_merged_trust_bundle_from_chains, create_multi_trust_mapping,
+    create_multi_merged_governance_policy, create_multi_merged_trust_bundle,
+};
+pub use delta::{
+    FullTrustBundle, DeltaOperation, TrustBundleDelta, TrustBundleChain,
+    DEFAULT_CONSOLIDATION_THRESHOLD,
+};
+pub use executor::{
+    execute_merge, execute_merge_with_rollback, execute_split, rollback_merge, CompensatingAction, CompensationEntry,
+    CompensationLog, CompensationStore, InMemoryCompensationStore, MergeCompensator, MergeRollback,
+};
+pub use staged::{
+    MergeStageRunner, stage_plan, checkpoint_tally, execute_staged_merge, record_checkpoint_vote,
+};
+pub use economics::{
+    union_ledgers_impl, shard_ledger_impl, shard_ledger_with_rounding, create_transfer_plan,
+    union_capital_accounts_impl, shard_capital_accounts,
+};
+pub use impact::{
+    MemberMergeImpact, MergeImpactContext, MergeProcessRegistry, MemberOrigin, HeldCredential,
+    CredentialImpact, DuesChange, RoleMapping, BalanceAdjustment, compute_member_merge_impact,
+    MEMBERSHIP_DUES_POLICY_KEY,
+};
+pub use alias::{AliasEntry, AliasKind, AliasService, AliasStore, FileAliasStore, InMemoryAliasStore};
+pub use integrations::{
+    Integration, IntegrationNotifier, IntegrationRegistry, LifecycleChange, LifecycleNotice,
+    NoticeDelivery, NoticeStatus, ReconciliationReport, SuccessorFederation, UnacknowledgedIntegration,
+};
+pub use carve::{
+    CarveManifest, CarvedNode, NodeLinks, carve_scoped_dag, seed_nodes, reachable_subgraph,
+    parents_first_order, CARVED_FROM_TAG_PREFIX,
+};
+pub use history::{EventHistoryQuery, EventProvenance, MergedEvent, MergedEventHistory, SourceEvent};
+pub use summary::{
+    EconomicAdjustment, KeyDate, LifecycleSummary, PolicyChange, SummaryKind, SUMMARY_CID_METADATA_KEY,
+};
+pub use dedup::{
+    DeclaredAlias, DuplicateCandidate, DuplicateDetector, DuplicateReview, DuplicateSignal, IdentityConsolidation,
+    IdentityEvidence, MemberContact, DEFAULT_DUPLICATE_THRESHOLD,
+};
+pub use import::{
+    CsvColumns, CsvImportAdapter, ExternalBalance, ExternalMember, ExternalRecord, ImportAdapter, ImportBatch,
+    ImportIssue, ImportOptions, ImportReport, ImportSink, IssueSeverity, OpenCollectiveImportAdapter, RosterEntry,
+    SourceRecord, plan_import, run_import, IMPORTED_MEMBERSHIP_CREDENTIAL,
+};
+pub use split_phases::{
+    FileSplitCheckpointStore, InMemorySplitCheckpointStore, SplitCheckpointStore, SplitExecutor, SplitPhaseRunner,
+};
+pub use escrow::{
+    DisputeEscrow, DisputeOutcome, EscrowAction, EscrowAuditEntry, EscrowAward, EscrowParties, EscrowStatus,
+    EscrowedAsset, EscrowedAssetKind, DEFAULT_ESCROW_THRESHOLD, ESCROW_AUDIT_CID_METADATA_KEY,
 };
-pub use executor::{execute_merge, execute_split};
-pub use economics::{union_ledgers_impl, shard_ledger_impl, create_transfer_plan};
 
 /// Creates a federation merge process from two source federations
 pub async fn initiate_federation_merge(
     federation_a: &icn_federation::Federation,
     federation_b: &icn_federation::Federation,
     merge_proposal: &MergeProposal,
+) -> LifecycleResult<MergeProcess> {
+    initiate_multi_federation_merge(&[federation_a, federation_b], merge_proposal).await
+}
+
+/// Creates a federation merge process from any number of source federations, given in
+/// the order of the proposal's [`MergeProposal::sources`]. Each source's approval is
+/// verified against its own members, and the sources combine into one trust bundle.
+pub async fn initiate_multi_federation_merge(
+    federations: &[&icn_federation::Federation],
+    merge_proposal: &MergeProposal,
 ) -> LifecycleResult<MergeProcess> {
     // Re-export of the core implementation from bundle.rs
-    use crate::bundle::create_trust_mapping;
-    use crate::bundle::create_merged_governance_policy;
-    use crate::bundle::create_merged_trust_bundle;
+    use crate::bundle::create_multi_trust_mapping;
+    use crate::bundle::create_multi_merged_governance_policy;
+    use crate::bundle::create_multi_merged_trust_bundle;
     use uuid::Uuid;
     use chrono::Utc;
 
-    // Verify federation approvals
-    verify_federation_approval(federation_a, merge_proposal)?;
-    verify_federation_approval(federation_b, merge_proposal)?;
+    let sources = merge_proposal.sources();
+    if federations.len() != sources.len() {
+        return Err(LifecycleError::InvalidProposal(format!(
+            "Merge proposal names {} source federations but {} were given", sources.len(), federations.len()
+        )));
+    }
+    for (federation, source) in federations.iter().zip(&sources) {
+        if federation.id() != &source.federation {
+            return Err(LifecycleError::InvalidProposal(format!(
+                "Expected source federation {}, got {}", source.federation, federation.id()
+            )));
+        }
+    }
+    
+    // Verify each source federation's approval
+    let payload = merge_proposal.approval_payload();
+    for (federation, source) in federations.iter().zip(&sources) {
+        verify_source_approval(federation, source, &payload).await?;
+    }
     
     // Create trust mapping
-    let federation_a_members = federation_a.members().iter().map(|m| m.did().clone()).collect::<Vec<_>>();
-    let federation_b_members = federation_b.members().iter().map(|m| m.did().clone()).collect::<Vec<_>>();
+    let source_members: Vec<Vec<_>> = federations.iter()
+        .map(|federation| federation.members().iter().map(|m| m.did().clone()).collect())
+        .collect();
+    let member_slices: Vec<&[_]> = source_members.iter().map(|members| members.as_slice()).collect();
     
-    let trust_mapping = create_trust_mapping(
-        &federation_a_members,
-        &federation_b_members,
+    let trust_mapping = create_multi_trust_mapping(
+        &member_slices,
         &merge_proposal.src_fed_a,
     )?;
     
     // Create merged governance policy
-    let merged_policy = create_merged_governance_policy(
-        &federation_a.policies(),
-        &federation_b.policies(),
+    let source_policies: Vec<_> = federations.iter().map(|federation| federation.policies().clone()).collect();
+    let (merged_policy, policy_conflicts) = create_multi_merged_governance_policy(
+        &source_policies.iter().collect::<Vec<_>>(),
+        &merge_proposal.policy_merge_strategy,
     )?;
     
     // Get active trust bundles
-    let bundle_a = PreMergeBundle {
-        dag_roots: vec![federation_a.genesis_cid()],
-        metadata: federation_a.metadata().clone(),
-        lineage: LineageAttestation {
-            parents: vec![federation_a.id().clone()],
-            children: vec![merge_proposal.src_fed_a.clone()],
-            typ: LineageAttestationType::Merge,
-            proof: merge_proposal.approval_a.clone().unwrap_or_default(),
-            timestamp: Utc::now(),
-            metadata: std::collections::HashMap::new(),
-        },
-        proofs: vec![merge_proposal.approval_a.clone().unwrap_or_default()],
-    };
-    
-    let bundle_b = PreMergeBundle {
-        dag_roots: vec![federation_b.genesis_cid()],
-        metadata: federation_b.metadata().clone(),
-        lineage: LineageAttestation {
-            parents: vec![federation_b.id().clone()],
-            children: vec![merge_proposal.src_fed_b.clone()],
-            typ: LineageAttestationType::Merge,
-            proof: merge_proposal.approval_b.clone().unwrap_or_default(),
-            timestamp: Utc::now(),
-            metadata: std::collections::HashMap::new(),
-        },
-        proofs: vec![merge_proposal.approval_b.clone().unwrap_or_default()],
-    };
+    let source_bundles: Vec<PreMergeBundle> = federations.iter().zip(&sources)
+        .map(|(federation, source)| {
+            let approval = source.approval.clone().unwrap_or_default();
+            PreMergeBundle {
+                dag_roots: vec![federation.genesis_cid()],
+                metadata: federation.metadata().clone(),
+                lineage: LineageAttestation {
+                    parents: vec![federation.id().clone()],
+                    children: vec![source.federation.clone()],
+                    typ: LineageAttestationType::Merge,
+                    proof: approval.clone(),
+                    timestamp: Utc::now(),
+                    metadata: std::collections::HashMap::new(),
+                },
+                proofs: vec![approval],
+            }
+        })
+        .collect();
     
     // Create merged trust bundle
-    let merged_bundle = create_merged_trust_bundle(
-        &bundle_a,
-        &bundle_b,
+    let merged_bundle = create_multi_merged_trust_bundle(
+        &source_bundles.iter().collect::<Vec<_>>(),
         &merge_proposal.src_fed_a,
     )?;
     
     // Create merge process
     let merge_process = MergeProcess {
         id: Uuid::new_v4().to_string(),
-        federation_a_id: federation_a.id().clone(),
-        federation_b_id: federation_b.id().clone(),
+        federation_a_id: federations[0].id().clone(),
+        federation_b_id: federations[1].id().clone(),
+        additional_federation_ids: federations[2..].iter().map(|federation| federation.id().clone()).collect(),
         new_federation_id: merge_proposal.src_fed_a.clone(),
         merge_proposal: merge_proposal.clone(),
         trust_mapping,
         merged_policy,
+        policy_conflicts,
         merged_bundle,
         status: MergeStatus::Initiated,
         start_time: Utc::now(),
         completion_time: None,
+        completed_stages: Vec::new(),
+        checkpoint_votes: Vec::new(),
     };
     
     Ok(merge_process)
@@ -161,6 +247,7 @@ pub async fn initiate_federation_split(
             metadata: std::collections::HashMap::new(),
         },
         proofs: vec![split_proposal.approval.clone().unwrap_or_default()],
+        carve_manifests: Vec::new(),
     };
     
     // Create individual bundles for each federation
@@ -192,6 +279,7 @@ pub async fn initiate_federation_split(
         status: SplitStatus::Initiated,
         start_time: Utc::now(),
         completion_time: None,
+        completed_phases: Vec::new(),
     };
     
     Ok(split_process)
@@ -209,6 +297,30 @@ fn verify_federation_approval(
     Ok(())
 }
 
+/// Verify that a source federation approved a merge: its approval must meet its quorum
+/// with signatures from its own members over the proposal's approval payload
+async fn verify_source_approval(
+    federation: &icn_federation::Federation,
+    source: &MergeSource,
+    payload: &[u8],
+) -> LifecycleResult<()> {
+    let approval = source.approval.as_ref().ok_or_else(|| {
+        LifecycleError::QuorumNotMet(format!("Federation {} has not approved the merge", source.federation))
+    })?;
+    
+    let members: Vec<String> = federation.members().iter().map(|m| m.did().to_string()).collect();
+    let approved = approval.verify(payload, &members).await.map_err(|e| {
+        LifecycleError::VerificationFailed(format!("Approval from {} failed to verify: {}", source.federation, e))
+    })?;
+    if !approved {
+        return Err(LifecycleError::QuorumNotMet(format!(
+            "Approval from {} does not meet its quorum", source.federation
+        )));
+    }
+    
+    Ok(())
+}
+
 /// Extract and validate partition map from a split proposal
 fn get_partition_map(
     split_proposal: &SplitProposal,
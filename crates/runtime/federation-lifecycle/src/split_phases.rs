@@ -0,0 +1,335 @@
+//! Phased federation splits with checkpoints
+//!
+//! [`execute_split`](crate::execute_split) runs a split in one go, so a crash halfway
+//! leaves nothing to tell which parts were done. A [`SplitExecutor`] runs the split in
+//! phases instead (trust bundle split, ledger shard, DAG anchor) and saves a
+//! [`SplitCheckpoint`] to its [`SplitCheckpointStore`] after each phase completes.
+//!
+//! After an interruption, [`SplitProcess::resume_from_checkpoint`] restores the
+//! completed phases from the store, and running the executor again continues with the
+//! first phase that hadn't completed. A phase that was interrupted before its
+//! checkpoint was saved runs again, so runners should make each phase safe to repeat.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::{SplitCheckpoint, SplitPhase, SplitPhaseRecord, SplitProcess, SplitStatus};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Executes the work of each phase of a split
+#[async_trait]
+pub trait SplitPhaseRunner: Send {
+    /// Execute a phase, returning what it produced (e.g., CIDs of anchored state)
+    async fn run_phase(&mut self, process: &SplitProcess, phase: SplitPhase) -> LifecycleResult<HashMap<String, String>>;
+}
+
+/// Where split checkpoints are kept
+pub trait SplitCheckpointStore: Send + Sync {
+    /// Load the checkpoint of a split process, if one was saved
+    fn load(&self, process_id: &str) -> LifecycleResult<Option<SplitCheckpoint>>;
+
+    /// Replace the checkpoint of a split process
+    fn save(&self, checkpoint: &SplitCheckpoint) -> LifecycleResult<()>;
+}
+
+/// Checkpoint store kept in memory, for tests and single-process deployments
+#[derive(Debug, Default)]
+pub struct InMemorySplitCheckpointStore {
+    checkpoints: Mutex<HashMap<String, SplitCheckpoint>>,
+}
+
+impl SplitCheckpointStore for InMemorySplitCheckpointStore {
+    fn load(&self, process_id: &str) -> LifecycleResult<Option<SplitCheckpoint>> {
+        let checkpoints = self.checkpoints.lock()
+            .map_err(|_| LifecycleError::StorageError("Checkpoint store lock poisoned".to_string()))?;
+        Ok(checkpoints.get(process_id).cloned())
+    }
+
+    fn save(&self, checkpoint: &SplitCheckpoint) -> LifecycleResult<()> {
+        let mut checkpoints = self.checkpoints.lock()
+            .map_err(|_| LifecycleError::StorageError("Checkpoint store lock poisoned".to_string()))?;
+        checkpoints.insert(checkpoint.process_id.clone(), checkpoint.clone());
+        Ok(())
+    }
+}
+
+/// Checkpoint store keeping one JSON file per split process in a directory
+#[derive(Debug, Clone)]
+pub struct FileSplitCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileSplitCheckpointStore {
+    /// Keep checkpoints in the directory at `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, process_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", process_id))
+    }
+}
+
+impl SplitCheckpointStore for FileSplitCheckpointStore {
+    fn load(&self, process_id: &str) -> LifecycleResult<Option<SplitCheckpoint>> {
+        let path = self.path(process_id);
+        match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| LifecycleError::StorageError(format!(
+                    "Failed to parse split checkpoint {}: {}", path.display(), e
+                ))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(LifecycleError::StorageError(format!(
+                "Failed to read split checkpoint {}: {}", path.display(), e
+            ))),
+        }
+    }
+
+    fn save(&self, checkpoint: &SplitCheckpoint) -> LifecycleResult<()> {
+        let data = serde_json::to_vec(checkpoint)
+            .map_err(|e| LifecycleError::StorageError(format!("Failed to serialize split checkpoint: {}", e)))?;
+
+        // Write to a temporary file first so a crash can't leave a partial checkpoint
+        let path = self.path(&checkpoint.process_id);
+        let tmp = path.with_extension("tmp");
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(&tmp, data))
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|e| LifecycleError::StorageError(format!(
+                "Failed to write split checkpoint {}: {}", path.display(), e
+            )))
+    }
+}
+
+impl SplitProcess {
+    /// The first phase that hasn't completed, or `None` once every phase has
+    pub fn next_phase(&self) -> Option<SplitPhase> {
+        SplitPhase::ORDER.iter()
+            .copied()
+            .find(|phase| !self.completed_phases.iter().any(|record| record.phase == *phase))
+    }
+
+    /// Restore the phases completed before an interruption from the last saved
+    /// checkpoint. Returns the phase the split resumes from, or `None` if every phase
+    /// had completed.
+    pub fn resume_from_checkpoint(&mut self, store: &impl SplitCheckpointStore) -> LifecycleResult<Option<SplitPhase>> {
+        match self.status {
+            SplitStatus::Completed | SplitStatus::Cancelled => {
+                return Err(LifecycleError::InvalidFederationState(
+                    format!("Split {} can't resume from {:?}", self.id, self.status)
+                ));
+            }
+            _ => {}
+        }
+
+        if let Some(checkpoint) = store.load(&self.id)? {
+            self.completed_phases = checkpoint.completed_phases;
+        }
+
+        let next = self.next_phase();
+        match next {
+            Some(phase) => info!("Split {} resumes from {:?}", self.id, phase),
+            None => {
+                self.status = SplitStatus::Completed;
+                self.completion_time.get_or_insert_with(Utc::now);
+            }
+        }
+        Ok(next)
+    }
+}
+
+/// Runs the phases of a split, saving a checkpoint after each one
+pub struct SplitExecutor<R, C> {
+    runner: R,
+    store: C,
+}
+
+impl<R: SplitPhaseRunner, C: SplitCheckpointStore> SplitExecutor<R, C> {
+    /// Create an executor running phases with `runner` and keeping checkpoints in `store`
+    pub fn new(runner: R, store: C) -> Self {
+        Self { runner, store }
+    }
+
+    /// The executor's checkpoint store
+    pub fn store(&self) -> &C {
+        &self.store
+    }
+
+    /// Run one phase of a split and save a checkpoint once it completes. The phases
+    /// before it must have completed and the phase itself must not have.
+    pub async fn run_phase(&mut self, process: &mut SplitProcess, phase: SplitPhase) -> LifecycleResult<SplitPhaseRecord> {
+        match process.status {
+            SplitStatus::Completed | SplitStatus::Cancelled => {
+                return Err(LifecycleError::InvalidFederationState(
+                    format!("Split {} can't execute from {:?}", process.id, process.status)
+                ));
+            }
+            _ => {}
+        }
+
+        let window_end = process.start_time + Duration::seconds(process.split_proposal.challenge_window_secs as i64);
+        if Utc::now() < window_end {
+            return Err(LifecycleError::ChallengeWindowActive(window_end.to_rfc3339()));
+        }
+
+        match process.next_phase() {
+            Some(next) if next == phase => {}
+            Some(next) => {
+                return Err(LifecycleError::InvalidFederationState(
+                    format!("Split {} must run {:?} before {:?}", process.id, next, phase)
+                ));
+            }
+            None => {
+                return Err(LifecycleError::InvalidFederationState(
+                    format!("Split {} has already run every phase", process.id)
+                ));
+            }
+        }
+
+        process.status = SplitStatus::Executing;
+        let outputs = match self.runner.run_phase(process, phase).await {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                process.status = SplitStatus::Failed;
+                return Err(e);
+            }
+        };
+
+        let record = SplitPhaseRecord {
+            phase,
+            completed_at: Utc::now(),
+            outputs,
+        };
+        process.completed_phases.push(record.clone());
+        self.store.save(&SplitCheckpoint {
+            process_id: process.id.clone(),
+            completed_phases: process.completed_phases.clone(),
+            saved_at: Utc::now(),
+        })?;
+        info!("Split {} completed {:?}", process.id, phase);
+
+        if process.next_phase().is_none() {
+            process.status = SplitStatus::Completed;
+            process.completion_time = Some(Utc::now());
+        }
+        Ok(record)
+    }
+
+    /// Run every phase that hasn't completed, in order. Returns the resulting status.
+    pub async fn run(&mut self, process: &mut SplitProcess) -> LifecycleResult<SplitStatus> {
+        while let Some(phase) = process.next_phase() {
+            self.run_phase(process, phase).await?;
+        }
+        Ok(process.status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LineageAttestation, LineageAttestationType, PartitionMap, QuorumConfig, SplitBundle,
+        SplitProposal, TrustMapping,
+    };
+    use cid::Cid;
+    use icn_identity::QuorumProof;
+
+    /// Runs phases in order, failing the first time it reaches `fail_at`
+    struct FlakyRunner {
+        ran: Vec<SplitPhase>,
+        fail_at: Option<SplitPhase>,
+    }
+
+    #[async_trait]
+    impl SplitPhaseRunner for FlakyRunner {
+        async fn run_phase(&mut self, _process: &SplitProcess, phase: SplitPhase) -> LifecycleResult<HashMap<String, String>> {
+            if self.fail_at == Some(phase) {
+                self.fail_at = None;
+                return Err(LifecycleError::LedgerOperationFailed("node crashed".to_string()));
+            }
+            self.ran.push(phase);
+            Ok(HashMap::from([("phase".to_string(), format!("{:?}", phase))]))
+        }
+    }
+
+    fn split_process() -> SplitProcess {
+        let bundle = SplitBundle::new(
+            Cid::default(),
+            PartitionMap {
+                members_a: vec!["did:icn:alice".to_string()],
+                members_b: vec!["did:icn:bob".to_string()],
+                resources_a: HashMap::new(),
+                resources_b: HashMap::new(),
+                ledger_a: HashMap::new(),
+                ledger_b: HashMap::new(),
+            },
+            LineageAttestation {
+                parents: vec!["did:icn:fed".to_string()],
+                children: vec!["did:icn:fed-a".to_string(), "did:icn:fed-b".to_string()],
+                typ: LineageAttestationType::Split,
+                proof: QuorumProof::default(),
+                timestamp: Utc::now(),
+                metadata: HashMap::new(),
+            },
+            vec![],
+        );
+        let empty_mapping = || TrustMapping {
+            did_mappings: HashMap::new(),
+            role_assignments: HashMap::new(),
+            credential_validations: vec![],
+        };
+
+        SplitProcess {
+            id: "split-1".to_string(),
+            original_federation_id: "did:icn:fed".to_string(),
+            federation_a_id: "did:icn:fed-a".to_string(),
+            federation_b_id: "did:icn:fed-b".to_string(),
+            split_proposal: SplitProposal {
+                parent_fed: "did:icn:fed".to_string(),
+                partition_map_cid: Cid::default(),
+                quorum_cfg: QuorumConfig { threshold: 1, authorized_signers: vec![], weights: None },
+                challenge_window_secs: 0,
+                approval: None,
+                federation_a_id: None,
+                federation_b_id: None,
+            },
+            trust_mapping_a: empty_mapping(),
+            trust_mapping_b: empty_mapping(),
+            policy_a: HashMap::new(),
+            policy_b: HashMap::new(),
+            bundle_a: bundle.clone(),
+            bundle_b: bundle,
+            status: SplitStatus::Initiated,
+            start_time: Utc::now(),
+            completion_time: None,
+            completed_phases: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_split_resumes_from_last_checkpoint() {
+        let runner = FlakyRunner { ran: vec![], fail_at: Some(SplitPhase::LedgerShard) };
+        let mut executor = SplitExecutor::new(runner, InMemorySplitCheckpointStore::default());
+        let mut process = split_process();
+
+        // Phases can't run out of order
+        assert!(executor.run_phase(&mut process, SplitPhase::DagAnchor).await.is_err());
+
+        assert!(executor.run(&mut process).await.is_err());
+        assert_eq!(process.status, SplitStatus::Failed);
+
+        // A fresh copy of the process, as after a restart, picks up the saved checkpoint
+        let mut restarted = split_process();
+        let resume_at = restarted.resume_from_checkpoint(executor.store()).unwrap();
+        assert_eq!(resume_at, Some(SplitPhase::LedgerShard));
+        assert_eq!(restarted.completed_phases.len(), 1);
+
+        assert_eq!(executor.run(&mut restarted).await.unwrap(), SplitStatus::Completed);
+        assert_eq!(executor.runner.ran, SplitPhase::ORDER.to_vec());
+        assert_eq!(executor.store().load("split-1").unwrap().unwrap().completed_phases.len(), 3);
+    }
+}
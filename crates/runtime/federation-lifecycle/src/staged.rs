@@ -0,0 +1,291 @@
+//! Staged merge execution with checkpoint votes
+//!
+//! Large merges can execute in stages (trust first, governance second, economics
+//! last) instead of all at once. A merge proposal lists its stages; a stage marked
+//! with a checkpoint only executes once the proposal's authorized signers ratify it.
+//!
+//! [`execute_staged_merge`] runs stages in order until it reaches a checkpoint that
+//! hasn't been approved, where the process pauses in
+//! [`MergeStatus::AwaitingCheckpoint`]. Signers vote with [`record_checkpoint_vote`];
+//! once approvals reach the proposal's quorum threshold, calling
+//! [`execute_staged_merge`] again resumes from the paused stage. A checkpoint that
+//! can no longer reach the threshold is rejected, and the stages already executed
+//! stay executed.
+
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::types::{CheckpointVote, MergeProcess, MergeProposal, MergeStage, MergeStageSpec, MergeStatus, StageRecord};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use icn_identity::Did;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Executes the work of each stage of a merge
+#[async_trait]
+pub trait MergeStageRunner: Send {
+    /// Execute a stage, returning what it produced (e.g., CIDs of anchored state)
+    async fn run_stage(&mut self, process: &MergeProcess, stage: MergeStage) -> LifecycleResult<HashMap<String, String>>;
+}
+
+/// The stages of a merge in execution order. Proposals without stages execute every
+/// stage without checkpoints.
+pub fn stage_plan(proposal: &MergeProposal) -> LifecycleResult<Vec<MergeStageSpec>> {
+    if proposal.stages.is_empty() {
+        return Ok(MergeStage::ORDER.iter()
+            .map(|&stage| MergeStageSpec { stage, checkpoint: false })
+            .collect());
+    }
+
+    let mut plan = proposal.stages.clone();
+    plan.sort_by_key(|spec| spec.stage);
+    if plan.windows(2).any(|pair| pair[0].stage == pair[1].stage) {
+        return Err(LifecycleError::InvalidProposal("Merge stages must not repeat".to_string()));
+    }
+    Ok(plan)
+}
+
+/// Weight of a signer's votes under the proposal's quorum configuration
+fn voter_weight(proposal: &MergeProposal, voter: &Did) -> u32 {
+    proposal.quorum_cfg.weights.as_ref()
+        .and_then(|weights| weights.get(voter).copied())
+        .unwrap_or(1)
+}
+
+/// Approving and rejecting weight of the votes on a stage's checkpoint
+pub fn checkpoint_tally(process: &MergeProcess, stage: MergeStage) -> (u32, u32) {
+    process.checkpoint_votes.iter()
+        .filter(|vote| vote.stage == stage)
+        .fold((0, 0), |(approve, reject), vote| {
+            let weight = voter_weight(&process.merge_proposal, &vote.voter);
+            if vote.approve { (approve + weight, reject) } else { (approve, reject + weight) }
+        })
+}
+
+fn checkpoint_approved(process: &MergeProcess, stage: MergeStage) -> bool {
+    checkpoint_tally(process, stage).0 >= process.merge_proposal.quorum_cfg.threshold
+}
+
+/// Execute the stages of a merge in order, pausing at the first checkpoint that hasn't
+/// been approved. Returns the resulting status.
+pub async fn execute_staged_merge(
+    process: &mut MergeProcess,
+    runner: &mut impl MergeStageRunner,
+) -> LifecycleResult<MergeStatus> {
+    match process.status {
+        MergeStatus::Completed | MergeStatus::Cancelled | MergeStatus::Failed | MergeStatus::RolledBack
+        | MergeStatus::CheckpointRejected(_) => {
+            return Err(LifecycleError::InvalidFederationState(
+                format!("Merge {} can't execute from {:?}", process.id, process.status)
+            ));
+        }
+        _ => {}
+    }
+
+    let window_end = process.start_time + Duration::seconds(process.merge_proposal.challenge_window_secs as i64);
+    if Utc::now() < window_end {
+        return Err(LifecycleError::ChallengeWindowActive(window_end.to_rfc3339()));
+    }
+
+    for spec in stage_plan(&process.merge_proposal)? {
+        if process.completed_stages.iter().any(|record| record.stage == spec.stage) {
+            continue;
+        }
+        if spec.checkpoint && !checkpoint_approved(process, spec.stage) {
+            info!("Merge {} paused before {:?} until its checkpoint is ratified", process.id, spec.stage);
+            process.status = MergeStatus::AwaitingCheckpoint(spec.stage);
+            return Ok(process.status);
+        }
+
+        process.status = MergeStatus::Executing;
+        match runner.run_stage(process, spec.stage).await {
+            Ok(outputs) => process.completed_stages.push(StageRecord {
+                stage: spec.stage,
+                completed_at: Utc::now(),
+                outputs,
+            }),
+            Err(e) => {
+                process.status = MergeStatus::Failed;
+                return Err(e);
+            }
+        }
+    }
+
+    process.status = MergeStatus::Completed;
+    process.completion_time = Some(Utc::now());
+    Ok(process.status)
+}
+
+/// Record an authorized signer's vote on the checkpoint the merge is paused at.
+/// Returns the resulting status: still awaiting the checkpoint, ready to resume
+/// (`Executing`), or rejected.
+pub fn record_checkpoint_vote(
+    process: &mut MergeProcess,
+    voter: &Did,
+    approve: bool,
+) -> LifecycleResult<MergeStatus> {
+    let stage = match process.status {
+        MergeStatus::AwaitingCheckpoint(stage) => stage,
+        status => {
+            return Err(LifecycleError::InvalidFederationState(
+                format!("Merge {} is not awaiting a checkpoint ({:?})", process.id, status)
+            ));
+        }
+    };
+
+    let proposal = &process.merge_proposal;
+    if !proposal.quorum_cfg.authorized_signers.contains(voter) {
+        return Err(LifecycleError::AuthorizationFailed(
+            format!("{} is not an authorized signer of merge {}", voter, process.id)
+        ));
+    }
+    if process.checkpoint_votes.iter().any(|vote| vote.stage == stage && &vote.voter == voter) {
+        return Err(LifecycleError::InvalidProposal(
+            format!("{} already voted on the {:?} checkpoint", voter, stage)
+        ));
+    }
+
+    process.checkpoint_votes.push(CheckpointVote {
+        stage,
+        voter: voter.clone(),
+        approve,
+        cast_at: Utc::now(),
+    });
+
+    let threshold = process.merge_proposal.quorum_cfg.threshold;
+    let total_weight: u32 = process.merge_proposal.quorum_cfg.authorized_signers.iter()
+        .map(|signer| voter_weight(&process.merge_proposal, signer))
+        .sum();
+    let (approving, rejecting) = checkpoint_tally(process, stage);
+
+    if approving >= threshold {
+        process.status = MergeStatus::Executing;
+    } else if total_weight.saturating_sub(rejecting) < threshold {
+        process.status = MergeStatus::CheckpointRejected(stage);
+    }
+    Ok(process.status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LineageAttestation, LineageAttestationType, PolicyConflictReport, PolicyMergeStrategy, PreMergeBundle, QuorumConfig,
+        TrustMapping,
+    };
+    use icn_identity::QuorumProof;
+
+    #[derive(Default)]
+    struct RecordingRunner {
+        ran: Vec<MergeStage>,
+    }
+
+    #[async_trait]
+    impl MergeStageRunner for RecordingRunner {
+        async fn run_stage(&mut self, _process: &MergeProcess, stage: MergeStage) -> LifecycleResult<HashMap<String, String>> {
+            self.ran.push(stage);
+            Ok(HashMap::from([("stage".to_string(), format!("{:?}", stage))]))
+        }
+    }
+
+    fn process(stages: Vec<MergeStageSpec>) -> MergeProcess {
+        let proposal = MergeProposal {
+            src_fed_a: "did:icn:fed-a".to_string(),
+            src_fed_b: "did:icn:fed-b".to_string(),
+            new_meta_cid: cid::Cid::default(),
+            quorum_cfg: QuorumConfig {
+                threshold: 2,
+                authorized_signers: vec!["did:icn:s1".to_string(), "did:icn:s2".to_string(), "did:icn:s3".to_string()],
+                weights: None,
+            },
+            challenge_window_secs: 0,
+            approval_a: None,
+            approval_b: None,
+            stages,
+            policy_merge_strategy: PolicyMergeStrategy::default(),
+            additional_sources: Vec::new(),
+        };
+        MergeProcess {
+            id: "merge-1".to_string(),
+            federation_a_id: proposal.src_fed_a.clone(),
+            federation_b_id: proposal.src_fed_b.clone(),
+            additional_federation_ids: Vec::new(),
+            new_federation_id: "did:icn:fed-ab".to_string(),
+            merge_proposal: proposal,
+            trust_mapping: TrustMapping {
+                did_mappings: HashMap::new(),
+                role_assignments: HashMap::new(),
+                credential_validations: vec![],
+            },
+            merged_policy: HashMap::new(),
+            policy_conflicts: PolicyConflictReport::default(),
+            merged_bundle: PreMergeBundle {
+                dag_roots: vec![],
+                metadata: HashMap::new(),
+                lineage: LineageAttestation {
+                    parents: vec!["did:icn:fed-a".to_string(), "did:icn:fed-b".to_string()],
+                    children: vec!["did:icn:fed-ab".to_string()],
+                    typ: LineageAttestationType::Merge,
+                    proof: QuorumProof::default(),
+                    timestamp: Utc::now(),
+                    metadata: HashMap::new(),
+                },
+                proofs: vec![],
+            },
+            status: MergeStatus::Initiated,
+            start_time: Utc::now(),
+            completion_time: None,
+            completed_stages: Vec::new(),
+            checkpoint_votes: Vec::new(),
+        }
+    }
+
+    fn checkpointed() -> Vec<MergeStageSpec> {
+        vec![
+            MergeStageSpec { stage: MergeStage::Economics, checkpoint: true },
+            MergeStageSpec { stage: MergeStage::Trust, checkpoint: false },
+            MergeStageSpec { stage: MergeStage::Governance, checkpoint: true },
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_merge_pauses_at_checkpoints_until_ratified() {
+        let mut process = process(checkpointed());
+        let mut runner = RecordingRunner::default();
+
+        let status = execute_staged_merge(&mut process, &mut runner).await.unwrap();
+        assert_eq!(status, MergeStatus::AwaitingCheckpoint(MergeStage::Governance));
+        assert_eq!(runner.ran, vec![MergeStage::Trust]);
+
+        assert!(record_checkpoint_vote(&mut process, &"did:icn:outsider".to_string(), true).is_err());
+        let s1 = "did:icn:s1".to_string();
+        assert_eq!(record_checkpoint_vote(&mut process, &s1, true).unwrap(), MergeStatus::AwaitingCheckpoint(MergeStage::Governance));
+        assert!(record_checkpoint_vote(&mut process, &s1, true).is_err());
+        assert_eq!(record_checkpoint_vote(&mut process, &"did:icn:s2".to_string(), true).unwrap(), MergeStatus::Executing);
+
+        let status = execute_staged_merge(&mut process, &mut runner).await.unwrap();
+        assert_eq!(status, MergeStatus::AwaitingCheckpoint(MergeStage::Economics));
+        assert_eq!(runner.ran, vec![MergeStage::Trust, MergeStage::Governance]);
+
+        record_checkpoint_vote(&mut process, &"did:icn:s2".to_string(), true).unwrap();
+        record_checkpoint_vote(&mut process, &"did:icn:s3".to_string(), true).unwrap();
+        assert_eq!(execute_staged_merge(&mut process, &mut runner).await.unwrap(), MergeStatus::Completed);
+        assert_eq!(runner.ran, MergeStage::ORDER.to_vec());
+        assert_eq!(process.completed_stages.len(), 3);
+        assert!(process.completion_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_checkpoint_keeps_earlier_stages() {
+        let mut process = process(checkpointed());
+        let mut runner = RecordingRunner::default();
+
+        execute_staged_merge(&mut process, &mut runner).await.unwrap();
+        record_checkpoint_vote(&mut process, &"did:icn:s1".to_string(), false).unwrap();
+        let status = record_checkpoint_vote(&mut process, &"did:icn:s2".to_string(), false).unwrap();
+        assert_eq!(status, MergeStatus::CheckpointRejected(MergeStage::Governance));
+
+        assert!(execute_staged_merge(&mut process, &mut runner).await.is_err());
+        assert_eq!(process.completed_stages.len(), 1);
+        assert_eq!(process.completed_stages[0].stage, MergeStage::Trust);
+    }
+}
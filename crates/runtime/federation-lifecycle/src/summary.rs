@@ -0,0 +1,460 @@
+//! Member-facing summaries of merges and splits
+//!
+//! Members vote on a merge or split without reading trust mappings or partition
+//! maps. A [`LifecycleSummary`] states in plain language what the process changes,
+//! the policy highlights (a diff of each source federation's governance policy
+//! against the policy that replaces it), the economic adjustments and the key dates.
+//! [`LifecycleSummary::to_markdown`] renders it as a document that can be published
+//! as-is or converted to PDF.
+//!
+//! A summary is content-addressed like the lifecycle bundles: its CID is computed
+//! over its DAG-CBOR encoding, and [`LifecycleSummary::anchor`] records that CID in a
+//! lineage attestation, which the executor anchors in the DAG. The exact summary
+//! members voted on can later be checked against the lineage with
+//! [`LifecycleSummary::is_anchored_in`].
+
+use crate::economics::union_ledgers_impl;
+use crate::error::{LifecycleError, LifecycleResult};
+use crate::impact::MergeImpactContext;
+use crate::types::{LineageAttestation, MergeProcess, SplitProcess};
+use chrono::{DateTime, Duration, Utc};
+use cid::Cid;
+use icn_identity::Did;
+use multihash::{Code, MultihashDigest};
+use serde::{Deserialize, Serialize};
+use serde_ipld_dagcbor as cbor;
+use std::collections::{BTreeSet, HashMap};
+
+/// Lineage metadata key holding the CID of the summary members voted on
+pub const SUMMARY_CID_METADATA_KEY: &str = "summary_cid";
+
+/// Kind of process a summary describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SummaryKind {
+    Merge,
+    Split,
+}
+
+/// A governance policy value that changes for the members of a federation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyChange {
+    /// Federation whose policy applies today
+    pub from_federation: Did,
+    /// Federation whose policy applies afterwards
+    pub to_federation: Did,
+    pub key: String,
+    /// Value today, if the policy is set
+    pub before: Option<String>,
+    /// Value afterwards, if the policy is set
+    pub after: Option<String>,
+}
+
+/// A change to a federation's economic state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EconomicAdjustment {
+    /// Federation the adjustment applies to
+    pub federation: Did,
+    /// What is adjusted (e.g., "Member balances")
+    pub item: String,
+    /// Amount today, if the federation exists today
+    pub before: Option<u64>,
+    pub after: u64,
+}
+
+/// A date members should know about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyDate {
+    pub label: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Member-facing summary of a merge or split
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleSummary {
+    pub kind: SummaryKind,
+    pub process_id: String,
+    pub title: String,
+    pub source_federations: Vec<Did>,
+    pub resulting_federations: Vec<Did>,
+    /// What the process changes, one statement each
+    pub changes: Vec<String>,
+    pub policy_changes: Vec<PolicyChange>,
+    pub economic_adjustments: Vec<EconomicAdjustment>,
+    /// Key dates in chronological order
+    pub key_dates: Vec<KeyDate>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Diff a policy against the policy that replaces it, in key order
+fn diff_policies(
+    from_federation: &Did,
+    before: &HashMap<String, String>,
+    to_federation: &Did,
+    after: &HashMap<String, String>,
+) -> Vec<PolicyChange> {
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .map(|key| PolicyChange {
+            from_federation: from_federation.clone(),
+            to_federation: to_federation.clone(),
+            key: key.clone(),
+            before: before.get(key).cloned(),
+            after: after.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Dates common to merges and splits, in chronological order
+fn key_dates(
+    start_time: DateTime<Utc>,
+    challenge_window_secs: u64,
+    mut others: Vec<KeyDate>,
+    completion_time: Option<DateTime<Utc>>,
+) -> Vec<KeyDate> {
+    let mut dates = vec![
+        KeyDate { label: "Process initiated".to_string(), at: start_time },
+        KeyDate {
+            label: "Challenge window closes".to_string(),
+            at: start_time + Duration::seconds(challenge_window_secs as i64),
+        },
+    ];
+    dates.append(&mut others);
+    if let Some(completed) = completion_time {
+        dates.push(KeyDate { label: "Process completed".to_string(), at: completed });
+    }
+    dates.sort_by_key(|date| date.at);
+    dates
+}
+
+impl LifecycleSummary {
+    /// Summarize a merge. The context supplies the source federations' members,
+    /// policies and ledgers.
+    pub fn for_merge(process: &MergeProcess, context: &MergeImpactContext) -> LifecycleResult<Self> {
+        if context.process_id != process.id {
+            return Err(LifecycleError::InvalidProposal(format!(
+                "Impact context is for process {}, not {}", context.process_id, process.id
+            )));
+        }
+        let (a, b, new) = (&process.federation_a_id, &process.federation_b_id, &process.new_federation_id);
+
+        let mut changes = vec![format!("{} and {} merge into {}.", a, b, new)];
+        let in_both = context.members_a.iter().filter(|m| context.members_b.contains(m)).count();
+        changes.push(format!(
+            "{} members of {} and {} members of {} join {}; {} belong to both.",
+            context.members_a.len(), a, context.members_b.len(), b, new, in_both
+        ));
+        if !process.trust_mapping.did_mappings.is_empty() {
+            changes.push(format!(
+                "{} members receive a new DID in {}.", process.trust_mapping.did_mappings.len(), new
+            ));
+        }
+        let mut recognized: Vec<String> = process.trust_mapping.credential_validations.iter()
+            .map(|v| format!(
+                "{} credentials issued by {} are recognized as {} issued by {}.",
+                v.source_type, v.source_issuer, v.target_type, v.target_issuer
+            ))
+            .collect();
+        recognized.sort();
+        changes.extend(recognized);
+        if !process.merge_proposal.stages.is_empty() {
+            let stages: Vec<String> = crate::staged::stage_plan(&process.merge_proposal)?.iter()
+                .map(|spec| if spec.checkpoint {
+                    format!("{:?} (after a ratification vote)", spec.stage)
+                } else {
+                    format!("{:?}", spec.stage)
+                })
+                .collect();
+            changes.push(format!("The merge executes in stages: {}.", stages.join(", ")));
+        }
+
+        let mut policy_changes = diff_policies(a, &context.policy_a, new, &process.merged_policy);
+        policy_changes.extend(diff_policies(b, &context.policy_b, new, &process.merged_policy));
+
+        let merged_ledger = union_ledgers_impl(&context.ledger_a, &context.ledger_b)?;
+        let total = |accounts: &HashMap<Did, u64>| accounts.values().sum::<u64>();
+        let economic_adjustments = vec![
+            EconomicAdjustment {
+                federation: new.clone(),
+                item: "Member accounts".to_string(),
+                before: Some((context.ledger_a.accounts().len() + context.ledger_b.accounts().len()) as u64),
+                after: merged_ledger.accounts().len() as u64,
+            },
+            EconomicAdjustment {
+                federation: new.clone(),
+                item: "Member balances".to_string(),
+                before: Some(total(context.ledger_a.accounts()) + total(context.ledger_b.accounts())),
+                after: total(merged_ledger.accounts()),
+            },
+        ];
+
+        let stage_dates = process.completed_stages.iter()
+            .map(|record| KeyDate { label: format!("{:?} stage executed", record.stage), at: record.completed_at })
+            .collect();
+
+        Ok(Self {
+            kind: SummaryKind::Merge,
+            process_id: process.id.clone(),
+            title: format!("Merge of {} and {} into {}", a, b, new),
+            source_federations: vec![a.clone(), b.clone()],
+            resulting_federations: vec![new.clone()],
+            changes,
+            policy_changes,
+            economic_adjustments,
+            key_dates: key_dates(
+                process.start_time,
+                process.merge_proposal.challenge_window_secs,
+                stage_dates,
+                process.completion_time,
+            ),
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Summarize a split, given the governance policy of the federation being split
+    pub fn for_split(process: &SplitProcess, parent_policy: &HashMap<String, String>) -> LifecycleResult<Self> {
+        let (parent, a, b) = (&process.original_federation_id, &process.federation_a_id, &process.federation_b_id);
+        let partition = &process.bundle_a.partition_map;
+
+        let mut changes = vec![
+            format!("{} splits into {} and {}.", parent, a, b),
+            format!(
+                "{} members move to {} and {} members move to {}.",
+                partition.members_a.len(), a, partition.members_b.len(), b
+            ),
+        ];
+        if !process.bundle_a.carve_manifests.is_empty() || !process.bundle_b.carve_manifests.is_empty() {
+            changes.push(format!("The governance history of {} is carried into both federations.", parent));
+        }
+
+        let mut policy_changes = diff_policies(parent, parent_policy, a, &process.policy_a);
+        policy_changes.extend(diff_policies(parent, parent_policy, b, &process.policy_b));
+
+        let mut economic_adjustments = Vec::new();
+        for (federation, members, ledger, resources) in [
+            (a, &partition.members_a, &partition.ledger_a, &partition.resources_a),
+            (b, &partition.members_b, &partition.ledger_b, &partition.resources_b),
+        ] {
+            economic_adjustments.push(EconomicAdjustment {
+                federation: federation.clone(),
+                item: "Members".to_string(),
+                before: None,
+                after: members.len() as u64,
+            });
+            economic_adjustments.push(EconomicAdjustment {
+                federation: federation.clone(),
+                item: "Member balances".to_string(),
+                before: None,
+                after: ledger.values().sum(),
+            });
+            let mut allocations: Vec<_> = resources.values().collect();
+            allocations.sort_by(|x, y| x.resource_id.cmp(&y.resource_id));
+            economic_adjustments.extend(allocations.into_iter().map(|allocation| EconomicAdjustment {
+                federation: federation.clone(),
+                item: format!("Resource {}", allocation.resource_id),
+                before: None,
+                after: allocation.amount,
+            }));
+        }
+
+        Ok(Self {
+            kind: SummaryKind::Split,
+            process_id: process.id.clone(),
+            title: format!("Split of {} into {} and {}", parent, a, b),
+            source_federations: vec![parent.clone()],
+            resulting_federations: vec![a.clone(), b.clone()],
+            changes,
+            policy_changes,
+            economic_adjustments,
+            key_dates: key_dates(
+                process.start_time,
+                process.split_proposal.challenge_window_secs,
+                Vec::new(),
+                process.completion_time,
+            ),
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Serialize the summary to CBOR bytes
+    pub fn to_cbor(&self) -> LifecycleResult<Vec<u8>> {
+        cbor::to_vec(self).map_err(|e| {
+            LifecycleError::BundleSerializationError(format!("Failed to serialize summary: {}", e))
+        })
+    }
+
+    /// Calculate the CID for this summary
+    pub fn calculate_cid(&self) -> LifecycleResult<Cid> {
+        let cbor_bytes = self.to_cbor()?;
+        let hash = Code::Sha2_256.digest(&cbor_bytes);
+        Ok(Cid::new_v1(0x71, hash))
+    }
+
+    /// Record the summary's CID in a lineage attestation, so anchoring the lineage
+    /// preserves which summary was voted on. Returns the CID.
+    pub fn anchor(&self, lineage: &mut LineageAttestation) -> LifecycleResult<Cid> {
+        let cid = self.calculate_cid()?;
+        lineage.metadata.insert(SUMMARY_CID_METADATA_KEY.to_string(), cid.to_string());
+        Ok(cid)
+    }
+
+    /// Whether this is the summary recorded in a lineage attestation
+    pub fn is_anchored_in(&self, lineage: &LineageAttestation) -> LifecycleResult<bool> {
+        let cid = self.calculate_cid()?;
+        Ok(lineage.metadata.get(SUMMARY_CID_METADATA_KEY) == Some(&cid.to_string()))
+    }
+
+    /// Render the summary as a Markdown document, ending with its CID
+    pub fn to_markdown(&self) -> LifecycleResult<String> {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "_not set_".to_string());
+        let mut doc = format!("# {}\n\n", self.title);
+        doc.push_str(&format!(
+            "Process `{}`, summary generated {}.\n\n", self.process_id, self.generated_at.to_rfc3339()
+        ));
+
+        doc.push_str("## What changes\n\n");
+        for change in &self.changes {
+            doc.push_str(&format!("- {}\n", change));
+        }
+
+        doc.push_str("\n## Policy highlights\n\n");
+        if self.policy_changes.is_empty() {
+            doc.push_str("No governance policy changes.\n");
+        } else {
+            doc.push_str("| Members of | Policy | Before | After (in) |\n|---|---|---|---|\n");
+            for change in &self.policy_changes {
+                doc.push_str(&format!(
+                    "| {} | {} | {} | {} ({}) |\n",
+                    change.from_federation, change.key, optional(&change.before),
+                    optional(&change.after), change.to_federation
+                ));
+            }
+        }
+
+        doc.push_str("\n## Economic adjustments\n\n");
+        if self.economic_adjustments.is_empty() {
+            doc.push_str("No economic adjustments.\n");
+        } else {
+            doc.push_str("| Federation | Item | Before | After |\n|---|---|---|---|\n");
+            for adjustment in &self.economic_adjustments {
+                doc.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    adjustment.federation, adjustment.item,
+                    adjustment.before.map_or_else(|| "-".to_string(), |v| v.to_string()), adjustment.after
+                ));
+            }
+        }
+
+        doc.push_str("\n## Key dates\n\n");
+        for date in &self.key_dates {
+            doc.push_str(&format!("- **{}**: {}\n", date.label, date.at.to_rfc3339()));
+        }
+
+        doc.push_str(&format!("\n---\n\nSummary CID: `{}`\n", self.calculate_cid()?));
+        Ok(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LineageAttestationType, PartitionMap, QuorumConfig, ResourceAllocation, SplitBundle,
+        SplitProposal, SplitStatus, TrustMapping,
+    };
+    use icn_identity::QuorumProof;
+
+    fn split_process() -> SplitProcess {
+        let partition_map = PartitionMap {
+            members_a: vec!["did:icn:alice".to_string(), "did:icn:bob".to_string()],
+            members_b: vec!["did:icn:carol".to_string()],
+            resources_a: HashMap::from([("compute".to_string(), ResourceAllocation {
+                resource_id: "compute".to_string(),
+                amount: 600,
+                metadata: None,
+            })]),
+            resources_b: HashMap::new(),
+            ledger_a: HashMap::from([("did:icn:alice".to_string(), 40), ("did:icn:bob".to_string(), 60)]),
+            ledger_b: HashMap::from([("did:icn:carol".to_string(), 25)]),
+        };
+        let bundle = SplitBundle::new(
+            Cid::default(),
+            partition_map,
+            LineageAttestation {
+                parents: vec!["did:icn:fed".to_string()],
+                children: vec!["did:icn:fed-a".to_string(), "did:icn:fed-b".to_string()],
+                typ: LineageAttestationType::Split,
+                proof: QuorumProof::default(),
+                timestamp: Utc::now(),
+                metadata: HashMap::new(),
+            },
+            vec![],
+        );
+        let empty_mapping = || TrustMapping {
+            did_mappings: HashMap::new(),
+            role_assignments: HashMap::new(),
+            credential_validations: vec![],
+        };
+
+        SplitProcess {
+            id: "split-1".to_string(),
+            original_federation_id: "did:icn:fed".to_string(),
+            federation_a_id: "did:icn:fed-a".to_string(),
+            federation_b_id: "did:icn:fed-b".to_string(),
+            split_proposal: SplitProposal {
+                parent_fed: "did:icn:fed".to_string(),
+                partition_map_cid: Cid::default(),
+                quorum_cfg: QuorumConfig { threshold: 1, authorized_signers: vec![], weights: None },
+                challenge_window_secs: 86_400,
+                approval: None,
+                federation_a_id: None,
+                federation_b_id: None,
+            },
+            trust_mapping_a: empty_mapping(),
+            trust_mapping_b: empty_mapping(),
+            policy_a: HashMap::from([("membership_dues".to_string(), "10".to_string())]),
+            policy_b: HashMap::from([("membership_dues".to_string(), "15".to_string())]),
+            bundle_a: bundle.clone(),
+            bundle_b: bundle,
+            status: SplitStatus::Initiated,
+            start_time: Utc::now(),
+            completion_time: None,
+            completed_phases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_split_summary_is_anchored_by_cid() {
+        let mut process = split_process();
+        let parent_policy = HashMap::from([("membership_dues".to_string(), "10".to_string())]);
+        let summary = LifecycleSummary::for_split(&process, &parent_policy).unwrap();
+
+        // Only federation B's dues change
+        assert_eq!(summary.policy_changes, vec![PolicyChange {
+            from_federation: "did:icn:fed".to_string(),
+            to_federation: "did:icn:fed-b".to_string(),
+            key: "membership_dues".to_string(),
+            before: Some("10".to_string()),
+            after: Some("15".to_string()),
+        }]);
+        let balances: Vec<u64> = summary.economic_adjustments.iter()
+            .filter(|adjustment| adjustment.item == "Member balances")
+            .map(|adjustment| adjustment.after)
+            .collect();
+        assert_eq!(balances, vec![100, 25]);
+        assert_eq!(summary.key_dates[1].at - summary.key_dates[0].at, Duration::days(1));
+
+        let markdown = summary.to_markdown().unwrap();
+        assert!(markdown.starts_with("# Split of did:icn:fed into did:icn:fed-a and did:icn:fed-b"));
+        assert!(markdown.contains("| Members of | Policy | Before | After (in) |"));
+
+        let cid = summary.anchor(&mut process.bundle_a.lineage).unwrap();
+        assert!(markdown.contains(&cid.to_string()));
+        assert!(summary.is_anchored_in(&process.bundle_a.lineage).unwrap());
+
+        // An edited summary no longer matches what was anchored
+        let mut edited = summary.clone();
+        edited.changes.push("Dues are waived.".to_string());
+        assert!(!edited.is_anchored_in(&process.bundle_a.lineage).unwrap());
+    }
+}
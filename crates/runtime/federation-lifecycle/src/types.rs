@@ -1,3 +1,4 @@
+use crate::carve::CarveManifest;
 use cid::Cid;
 use icn_identity::{Did, QuorumProof};
 use serde::{Deserialize, Serialize};
@@ -28,6 +29,202 @@ pub struct MergeProposal {
     /// Approval proof from federation B
     #[serde(skip_serializing_if = "Option::is_none")]
     pub approval_b: Option<QuorumProof>,
+    
+    /// Stages to execute the merge in, with the checkpoints between them. Without
+    /// stages the merge executes in one step.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<MergeStageSpec>,
+    
+    /// How governance policy fields the two federations set differently are resolved
+    #[serde(default)]
+    pub policy_merge_strategy: PolicyMergeStrategy,
+    
+    /// Source federations beyond A and B, for merges of three or more federations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_sources: Vec<MergeSource>,
+}
+
+/// A source federation of a merge and its approval of the merge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeSource {
+    /// Source federation DID
+    pub federation: Did,
+    
+    /// Approval proof from the federation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval: Option<QuorumProof>,
+}
+
+impl MergeProposal {
+    /// Every source federation of the merge, A and B first
+    pub fn sources(&self) -> Vec<MergeSource> {
+        let mut sources = vec![
+            MergeSource { federation: self.src_fed_a.clone(), approval: self.approval_a.clone() },
+            MergeSource { federation: self.src_fed_b.clone(), approval: self.approval_b.clone() },
+        ];
+        sources.extend(self.additional_sources.iter().cloned());
+        sources
+    }
+    
+    /// Bytes each source federation's signers sign to approve the merge: the source
+    /// federations in order and the new federation's metadata
+    pub fn approval_payload(&self) -> Vec<u8> {
+        let sources: Vec<Did> = self.sources().into_iter().map(|source| source.federation).collect();
+        format!("federation-merge:{}:{}", sources.join(","), self.new_meta_cid).into_bytes()
+    }
+}
+
+/// How a governance policy field set differently by the two merging federations is resolved
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PolicyMergeStrategy {
+    /// The stricter value wins: the higher quorum, majority or threshold, or the lower
+    /// `max_*` or `*_limit` value
+    #[default]
+    StricterWins,
+    
+    /// The looser value wins
+    LooserWins,
+    
+    /// Conflicting fields are left out of the merged policy until the new federation
+    /// votes on them
+    RequireNewVote,
+    
+    /// The federations agreed the values of conflicting fields up front; conflicting
+    /// fields without an agreed value are put to a vote
+    Custom(HashMap<String, String>),
+}
+
+/// How one conflicting policy field was resolved
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PolicyResolution {
+    /// Federation A's value was kept
+    TookA,
+    
+    /// Federation B's value was kept
+    TookB,
+    
+    /// The value agreed under a custom strategy was used
+    Agreed,
+    
+    /// The field awaits a vote of the new federation
+    PendingVote,
+}
+
+/// A policy field the two merging federations set differently
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PolicyConflict {
+    /// Policy field
+    pub field: String,
+    
+    /// Federation A's value
+    pub value_a: String,
+    
+    /// Federation B's value
+    pub value_b: String,
+    
+    /// How the conflict was resolved
+    pub resolution: PolicyResolution,
+    
+    /// Value in the merged policy, unless the field awaits a vote
+    pub merged_value: Option<String>,
+}
+
+/// Every policy field that diverged between the merging federations, and how each was resolved
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PolicyConflictReport {
+    /// Strategy the conflicts were resolved with
+    pub strategy: PolicyMergeStrategy,
+    
+    /// Conflicting fields, ordered by name
+    pub conflicts: Vec<PolicyConflict>,
+}
+
+impl PolicyConflictReport {
+    /// Whether the policies agreed on every field they both set
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+    
+    /// Fields the new federation still has to vote on
+    pub fn pending_vote(&self) -> Vec<&str> {
+        self.conflicts.iter()
+            .filter(|c| c.resolution == PolicyResolution::PendingVote)
+            .map(|c| c.field.as_str())
+            .collect()
+    }
+}
+
+/// A part of a merge that can execute on its own
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MergeStage {
+    /// Trust mapping and the merged trust bundle
+    Trust,
+    
+    /// Merged governance policy
+    Governance,
+    
+    /// Ledgers, capital accounts and other economic state
+    Economics,
+}
+
+impl MergeStage {
+    /// All stages, in execution order
+    pub const ORDER: [MergeStage; 3] = [MergeStage::Trust, MergeStage::Governance, MergeStage::Economics];
+}
+
+/// A stage of a staged merge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MergeStageSpec {
+    /// The stage
+    pub stage: MergeStage,
+    
+    /// Whether a ratification vote must approve the stage before it executes
+    pub checkpoint: bool,
+}
+
+/// A phase of a federation split, in execution order
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SplitPhase {
+    /// Trust mappings and the trust bundles of the resulting federations
+    TrustBundleSplit,
+    
+    /// Sharding the parent's ledger between the resulting federations
+    LedgerShard,
+    
+    /// Anchoring the genesis, lineage and bridge nodes to the DAG
+    DagAnchor,
+}
+
+impl SplitPhase {
+    /// All phases, in execution order
+    pub const ORDER: [SplitPhase; 3] = [SplitPhase::TrustBundleSplit, SplitPhase::LedgerShard, SplitPhase::DagAnchor];
+}
+
+/// A completed phase of a split
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SplitPhaseRecord {
+    /// The phase
+    pub phase: SplitPhase,
+    
+    /// When the phase finished executing
+    pub completed_at: chrono::DateTime<chrono::Utc>,
+    
+    /// What the phase produced (e.g., CIDs of anchored state)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub outputs: HashMap<String, String>,
+}
+
+/// Progress of a split persisted after each phase, so an interrupted split can resume
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SplitCheckpoint {
+    /// The split process
+    pub process_id: String,
+    
+    /// Phases completed so far, in execution order
+    pub completed_phases: Vec<SplitPhaseRecord>,
+    
+    /// When the checkpoint was saved
+    pub saved_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// Proposal to split a federation into two new ones
@@ -66,6 +263,9 @@ pub enum LineageAttestationType {
     
     /// Attests that a federation split
     Split,
+    
+    /// Attests that a merge was attempted and rolled back; the parents continue unchanged
+    AbortedMerge,
 }
 
 /// Attestation documenting the lineage relationship between federations
@@ -153,6 +353,12 @@ pub enum MergeStatus {
     /// Process is being executed
     Executing,
     
+    /// Execution is paused until a ratification vote approves the next stage
+    AwaitingCheckpoint(MergeStage),
+    
+    /// A ratification vote rejected the next stage; earlier stages stay executed
+    CheckpointRejected(MergeStage),
+    
     /// Process has completed successfully
     Completed,
     
@@ -161,6 +367,9 @@ pub enum MergeStatus {
     
     /// Process has failed
     Failed,
+    
+    /// A failed merge's completed steps were undone with `rollback_merge`
+    RolledBack,
 }
 
 /// Process status for federation split operations
@@ -197,6 +406,10 @@ pub struct MergeProcess {
     /// Second source federation DID
     pub federation_b_id: Did,
     
+    /// Further source federation DIDs, for merges of three or more federations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_federation_ids: Vec<Did>,
+    
     /// New federation DID
     pub new_federation_id: Did,
     
@@ -209,6 +422,10 @@ pub struct MergeProcess {
     /// Merged governance policy
     pub merged_policy: HashMap<String, String>,
     
+    /// Policy fields that diverged between the source federations, and how each was resolved
+    #[serde(default, skip_serializing_if = "PolicyConflictReport::is_empty")]
+    pub policy_conflicts: PolicyConflictReport,
+    
     /// Merged trust bundle
     pub merged_bundle: PreMergeBundle,
     
@@ -220,6 +437,53 @@ pub struct MergeProcess {
     
     /// Process completion time
     pub completion_time: Option<chrono::DateTime<chrono::Utc>>,
+    
+    /// Stages executed so far, for staged merges
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed_stages: Vec<StageRecord>,
+    
+    /// Ratification votes cast at checkpoints, for staged merges
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checkpoint_votes: Vec<CheckpointVote>,
+}
+
+impl MergeProcess {
+    /// Every source federation of the merge, A and B first
+    pub fn source_federation_ids(&self) -> Vec<Did> {
+        let mut ids = vec![self.federation_a_id.clone(), self.federation_b_id.clone()];
+        ids.extend(self.additional_federation_ids.iter().cloned());
+        ids
+    }
+}
+
+/// A stage of a staged merge that has executed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StageRecord {
+    /// The stage
+    pub stage: MergeStage,
+    
+    /// When the stage finished executing
+    pub completed_at: chrono::DateTime<chrono::Utc>,
+    
+    /// What the stage produced (e.g., CIDs of anchored state)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub outputs: HashMap<String, String>,
+}
+
+/// A signer's ratification vote on executing a stage
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckpointVote {
+    /// Stage the vote is about
+    pub stage: MergeStage,
+    
+    /// Voting signer
+    pub voter: Did,
+    
+    /// Whether the signer approves executing the stage
+    pub approve: bool,
+    
+    /// When the vote was cast
+    pub cast_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// Tracks the state of a federation split process
@@ -266,6 +530,10 @@ pub struct SplitProcess {
     
     /// Process completion time
     pub completion_time: Option<chrono::DateTime<chrono::Utc>>,
+    
+    /// Phases of the split executed so far, in execution order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed_phases: Vec<SplitPhaseRecord>,
 }
 
 /// Trust mapping between federation entities
@@ -330,4 +598,8 @@ pub struct SplitBundle {
     
     /// Cryptographic proofs
     pub proofs: Vec<QuorumProof>,
+    
+    /// Manifests of the DAG history carved out for the resulting federations
+    #[serde(default)]
+    pub carve_manifests: Vec<CarveManifest>,
 } 
\ No newline at end of file
@@ -0,0 +1,1280 @@
+/*!
+# Action Plugins
+
+Every DSL action the compiler understands is an [`ActionPlugin`]. A plugin validates
+the action's DSL input, chooses which parameters go into the module's data section,
+declares the host functions it needs beyond the base imports and emits the body of
+the module's `invoke` function.
+
+Plugins are kept in an [`ActionRegistry`]. The built-in actions are registered by
+default; downstream crates add their own actions with
+[`CclCompiler::register_action`](crate::CclCompiler::register_action) instead of
+forking the compiler.
+*/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use wasm_encoder::ValType;
+use icn_core_vm::{AbiValType, HostAbiManifest};
+use icn_governance_kernel::config::GovernanceConfig;
+
+use crate::{decompile, reproducible, CompilerError, CompilerResult, Diagnostic, DiagnosticCode, Locale};
+use crate::memory_layout::{self, MemoryLayout, RegionKind};
+
+/// Host functions every module imports, in function index order
+pub const BASE_IMPORTS: [&str; 10] = [
+    "host_log_message",
+    "host_storage_get",
+    "host_storage_put",
+    "host_get_caller_did",
+    "host_get_caller_scope",
+    "host_check_resource_authorization",
+    "host_record_resource_usage",
+    "host_anchor_to_dag",
+    "host_mint_token",
+    "host_transfer_resource",
+];
+
+/// Module all base imports come from
+pub const BASE_IMPORT_MODULE: &str = "env";
+
+/// A host function an action imports in addition to the base imports
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostImport {
+    pub module: String,
+    pub name: String,
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+impl HostImport {
+    /// Bind a host function of this runtime build with the signature the runtime's ABI
+    /// manifest gives it
+    pub fn bind(name: &str) -> CompilerResult<Self> {
+        Self::bind_from(icn_core_vm::host_abi_manifest(), name, icn_core_vm::HOST_ABI_VERSION)
+    }
+
+    /// Bind a host function from a runtime's ABI manifest, for a module that targets
+    /// `abi_version`. Functions newer than that revision can't be bound.
+    pub fn bind_from(manifest: &HostAbiManifest, name: &str, abi_version: u32) -> CompilerResult<Self> {
+        let function = manifest.get(name).ok_or_else(|| CompilerError::WasmGenerationError(format!(
+            "Host function {} is not exposed by runtime {}", name, manifest.runtime_version
+        )))?;
+        if function.since > abi_version {
+            return Err(CompilerError::WasmGenerationError(format!(
+                "Host function {} needs host ABI {}, but the module targets {}", name, function.since, abi_version
+            )));
+        }
+        let val_types = |types: &[AbiValType]| types.iter()
+            .map(|ty| match ty {
+                AbiValType::I32 => Ok(ValType::I32),
+                AbiValType::I64 => Ok(ValType::I64),
+                AbiValType::F32 => Ok(ValType::F32),
+                AbiValType::F64 => Ok(ValType::F64),
+                AbiValType::V128 => Ok(ValType::V128),
+                other => Err(CompilerError::WasmGenerationError(format!(
+                    "Host function {} takes a {:?}, which bindings don't support", name, other
+                ))),
+            })
+            .collect::<CompilerResult<Vec<_>>>();
+
+        Ok(Self {
+            module: function.module.clone(),
+            name: function.name.clone(),
+            params: val_types(&function.params)?,
+            results: val_types(&function.results)?,
+        })
+    }
+}
+
+/// What an action's `invoke` body can see while it is generated
+pub struct ActionContext<'a> {
+    pub action: &'a str,
+    pub template_type: &'a str,
+    /// The CCL config the module is compiled from; None when a body is generated only
+    /// to see which host functions it calls
+    pub ccl_config: Option<&'a GovernanceConfig>,
+    pub dsl_input: &'a JsonValue,
+    /// Offset and length of each laid out parameter in the data section
+    pub(crate) params: &'a HashMap<String, (usize, usize)>,
+    /// Every region of the module's linear memory
+    pub(crate) memory: &'a MemoryLayout,
+    /// Function index of each import, keyed by "module::name"
+    pub(crate) imports: &'a HashMap<String, u32>,
+}
+
+impl ActionContext<'_> {
+    /// Offset and length of a laid out parameter, or (0, 0) if it wasn't laid out
+    pub fn param(&self, name: &str) -> (i32, i32) {
+        self.params.get(name)
+            .map(|(offset, len)| (*offset as i32, *len as i32))
+            .unwrap_or((0, 0))
+    }
+
+    /// Offset and length of a message or scratch buffer, or (0, 0) if there is none
+    pub fn region(&self, name: &str) -> (i32, i32) {
+        self.memory.region(RegionKind::Message, name)
+            .or_else(|| self.memory.region(RegionKind::Scratch, name))
+            .map(|r| (r.offset as i32, r.len as i32))
+            .unwrap_or((0, 0))
+    }
+
+    /// An integer DSL parameter, or 0 if it is missing
+    pub fn int_param(&self, name: &str) -> i32 {
+        self.dsl_input.get(name)
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0) as i32
+    }
+
+    /// Function index of an imported host function
+    pub fn import_index(&self, module: &str, name: &str) -> Option<u32> {
+        self.imports.get(&format!("{}::{}", module, name)).copied()
+    }
+}
+
+/// A DSL action the compiler can turn into a WASM module
+pub trait ActionPlugin: Send + Sync {
+    /// The value of the DSL `action` field this plugin handles
+    fn name(&self) -> &str;
+
+    /// Whether the action may be used under the given CCL template
+    fn supports_template(&self, _template_type: &str) -> bool {
+        true
+    }
+
+    /// Check the DSL input has what the action needs
+    fn validate(&self, _dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        Ok(())
+    }
+
+    /// Named parameter bytes to place in the data section's input region
+    fn layout(&self, dsl_input: &JsonValue) -> Vec<(String, Vec<u8>)> {
+        default_layout(dsl_input)
+    }
+
+    /// Buffers the `invoke` body writes into, as name and size in bytes
+    fn scratch(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
+    /// Host functions to import beyond [`BASE_IMPORTS`]; runtime host functions are
+    /// best declared with [`HostImport::bind`]
+    fn imports(&self) -> Vec<HostImport> {
+        Vec::new()
+    }
+
+    /// Custom sections the action embeds in the module, as name and contents. Unlike
+    /// the debug sections they are emitted whatever the options.
+    fn custom_sections(&self, _dsl_input: &JsonValue) -> CompilerResult<Vec<(String, Vec<u8>)>> {
+        Ok(Vec::new())
+    }
+
+    /// The body of the module's `invoke` function, of type (i32, i32) -> i32
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function>;
+}
+
+/// Lay out every string parameter, and array parameters as JSON, in DSL order
+pub fn default_layout(dsl_input: &JsonValue) -> Vec<(String, Vec<u8>)> {
+    let mut items = Vec::new();
+    if let Some(obj) = dsl_input.as_object() {
+        for (key, value) in obj {
+            // The action itself isn't a parameter
+            if key == "action" {
+                continue;
+            }
+
+            if let Some(value_str) = value.as_str() {
+                items.push((key.clone(), value_str.as_bytes().to_vec()));
+            } else if value.is_array() {
+                // TODO: Handle arrays more efficiently
+                let json_str = serde_json::to_string(value).unwrap_or_default();
+                items.push((key.clone(), json_str.into_bytes()));
+            }
+            // Numbers are emitted as constants, other types are skipped
+        }
+    }
+    items
+}
+
+/// The actions a compiler can generate modules for
+#[derive(Clone)]
+pub struct ActionRegistry {
+    plugins: HashMap<String, Arc<dyn ActionPlugin>>,
+}
+
+impl Default for ActionRegistry {
+    /// A registry holding the built-in actions
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Arc::new(ProposeMembership));
+        registry.register(Arc::new(ProposeBudget));
+        registry.register(Arc::new(LogCallerInfo));
+        registry.register(Arc::new(PerformMeteredAction));
+        registry.register(Arc::new(AnchorData));
+        registry.register(Arc::new(MintToken));
+        registry.register(Arc::new(TransferResource));
+        registry.register(Arc::new(ScheduleRecurring));
+        registry.register(Arc::new(CastVote));
+        registry.register(Arc::new(TallyVotes));
+        registry
+    }
+}
+
+impl ActionRegistry {
+    /// A registry without any actions
+    pub fn empty() -> Self {
+        Self { plugins: HashMap::new() }
+    }
+
+    /// Add an action, replacing any registered under the same name
+    pub fn register(&mut self, plugin: Arc<dyn ActionPlugin>) {
+        self.plugins.insert(plugin.name().to_string(), plugin);
+    }
+
+    /// The plugin for an action
+    pub fn get(&self, action: &str) -> Option<Arc<dyn ActionPlugin>> {
+        self.plugins.get(action).cloned()
+    }
+
+    /// Names of all registered actions, sorted
+    pub fn actions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.plugins.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+fn require_field(dsl: &Map<String, JsonValue>, action: &str, field: &str) -> CompilerResult<()> {
+    require_fields(dsl, action, &[field])
+}
+
+/// Check every field is present, reporting all that are missing at once
+fn require_fields(dsl: &Map<String, JsonValue>, action: &str, fields: &[&str]) -> CompilerResult<()> {
+    let diagnostics: Vec<Diagnostic> = fields.iter()
+        .filter(|field| !dsl.contains_key(**field))
+        .map(|field| Diagnostic::missing_field(action, field))
+        .collect();
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(CompilerError::Invalid { diagnostics, locale: Locale::default() })
+    }
+}
+
+/// Most built-in actions are only defined for cooperative bylaws
+fn only_for_coop_bylaws(template_type: &str) -> bool {
+    template_type == "coop_bylaws"
+}
+
+struct ProposeMembership;
+
+impl ActionPlugin for ProposeMembership {
+    fn name(&self) -> &str {
+        "propose_membership"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_field(dsl, self.name(), "applicant_did")
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        Ok(default_body(&StatusMessages::of(ctx)))
+    }
+}
+
+struct ProposeBudget;
+
+impl ActionPlugin for ProposeBudget {
+    fn name(&self) -> &str {
+        "propose_budget"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["amount", "category"])
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        Ok(default_body(&StatusMessages::of(ctx)))
+    }
+}
+
+struct LogCallerInfo;
+
+impl ActionPlugin for LogCallerInfo {
+    fn name(&self) -> &str {
+        "log_caller_info"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        template_type == "coop_bylaws" || template_type == "community_charter"
+    }
+
+    fn scratch(&self) -> Vec<(String, usize)> {
+        vec![(CALLER_DID_BUFFER.to_string(), 100)]
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        Ok(log_caller_info_body(&StatusMessages::of(ctx), ctx.region(CALLER_DID_BUFFER)))
+    }
+}
+
+struct PerformMeteredAction;
+
+impl ActionPlugin for PerformMeteredAction {
+    fn name(&self) -> &str {
+        "perform_metered_action"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["resource_type", "amount"])
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        Ok(perform_metered_action_body(&StatusMessages::of(ctx), ctx.int_param("resource_type"), ctx.int_param("amount")))
+    }
+}
+
+struct AnchorData;
+
+impl ActionPlugin for AnchorData {
+    fn name(&self) -> &str {
+        "anchor_data"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["key", "value"])
+        // parents is optional, so no validation needed
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        let (key_offset, key_len) = ctx.param("key");
+        let (value_offset, value_len) = ctx.param("value");
+        Ok(anchor_data_body(&StatusMessages::of(ctx), key_offset, key_len, value_offset, value_len))
+    }
+}
+
+struct MintToken;
+
+impl ActionPlugin for MintToken {
+    fn name(&self) -> &str {
+        "mint_token"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["resource_type", "recipient", "amount"])
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        let (recipient_offset, recipient_len) = ctx.param("recipient");
+        Ok(mint_token_body(&StatusMessages::of(ctx), ctx.int_param("resource_type"), recipient_offset, recipient_len, ctx.int_param("amount")))
+    }
+}
+
+struct TransferResource;
+
+impl ActionPlugin for TransferResource {
+    fn name(&self) -> &str {
+        "transfer_resource"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["from", "to", "amount", "resource_type"])
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        let (from_offset, from_len) = ctx.param("from");
+        let (to_offset, to_len) = ctx.param("to");
+        Ok(transfer_resource_body(
+            &StatusMessages::of(ctx),
+            from_offset, from_len,
+            to_offset, to_len,
+            ctx.int_param("resource_type"), ctx.int_param("amount")
+        ))
+    }
+}
+
+/// The recurrence a `schedule_recurring` module registers, as embedded in its
+/// `icn-schedule` section
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringSchedule {
+    /// Five-field cron expression: minute, hour, day of month, month, day of week
+    pub schedule: String,
+    /// DSL input of the action to run each time the schedule fires
+    pub target_action: JsonValue,
+}
+
+/// Host function schedule_recurring registers its timer with
+const REGISTER_TIMER: &str = "host_register_timer";
+
+struct ScheduleRecurring;
+
+impl ScheduleRecurring {
+    /// The recurrence in a validated DSL input
+    fn recurrence(dsl_input: &JsonValue) -> RecurringSchedule {
+        RecurringSchedule {
+            schedule: dsl_input.get("schedule").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            target_action: dsl_input.get("target_action").cloned().unwrap_or(JsonValue::Null),
+        }
+    }
+}
+
+impl ActionPlugin for ScheduleRecurring {
+    fn name(&self) -> &str {
+        "schedule_recurring"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["schedule", "target_action"])?;
+
+        let schedule = dsl["schedule"].as_str().ok_or_else(|| CompilerError::invalid(
+            Diagnostic::new(DiagnosticCode::SchemaInvalidType)
+                .arg("path", "schedule").arg("expected", "string").arg("actual", json_type(&dsl["schedule"]))
+                .at("/schedule")
+        ))?;
+        icn_core_vm::timers::validate_schedule(schedule).map_err(|detail| CompilerError::invalid(
+            Diagnostic::new(DiagnosticCode::InvalidSchedule)
+                .arg("action", self.name()).arg("schedule", schedule).arg("detail", detail)
+                .at("/schedule")
+        ))?;
+
+        // The target is the DSL input of another action
+        let target = dsl["target_action"].as_object().ok_or_else(|| CompilerError::invalid(
+            Diagnostic::new(DiagnosticCode::SchemaInvalidType)
+                .arg("path", "target_action").arg("expected", "object").arg("actual", json_type(&dsl["target_action"]))
+                .at("/target_action")
+        ))?;
+        if !target.get("action").is_some_and(JsonValue::is_string) {
+            return Err(CompilerError::invalid(Diagnostic::missing_field(self.name(), "target_action.action").at("/target_action")));
+        }
+        Ok(())
+    }
+
+    fn layout(&self, dsl_input: &JsonValue) -> Vec<(String, Vec<u8>)> {
+        let recurrence = Self::recurrence(dsl_input);
+        let payload = reproducible::canonical_json(&recurrence.target_action).unwrap_or_default();
+        vec![
+            ("schedule".to_string(), recurrence.schedule.into_bytes()),
+            ("target_action".to_string(), payload),
+        ]
+    }
+
+    fn imports(&self) -> Vec<HostImport> {
+        // Bound from the runtime's manifest; if it has no timers, emitting the body fails
+        HostImport::bind(REGISTER_TIMER).into_iter().collect()
+    }
+
+    fn custom_sections(&self, dsl_input: &JsonValue) -> CompilerResult<Vec<(String, Vec<u8>)>> {
+        let section = reproducible::canonical_json(&Self::recurrence(dsl_input))
+            .map_err(|e| CompilerError::General(format!("Failed to serialize schedule: {}", e)))?;
+        Ok(vec![(decompile::SCHEDULE_SECTION.to_string(), section)])
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        let register_timer = ctx.import_index(BASE_IMPORT_MODULE, REGISTER_TIMER).ok_or_else(|| {
+            CompilerError::WasmGenerationError(format!("{} is not imported", REGISTER_TIMER))
+        })?;
+        let (schedule_offset, schedule_len) = ctx.param("schedule");
+        let (payload_offset, payload_len) = ctx.param("target_action");
+        Ok(host_call_body(
+            &StatusMessages::of(ctx),
+            register_timer,
+            &[schedule_offset, schedule_len, payload_offset, payload_len],
+        ))
+    }
+}
+
+/// Host function cast_vote records a proposal vote with
+const CAST_VOTE: &str = "host_cast_vote";
+
+/// Host function cast_vote records a participatory budget vote with
+const BUDGET_VOTE: &str = "economics_budget_vote";
+
+/// Vote choices, in the order of the codes the vote host functions take
+const VOTE_CHOICES: [&str; 3] = ["for", "against", "abstain"];
+
+struct CastVote;
+
+impl CastVote {
+    /// Weight of the vote under the config's participation scoring: 1 unless votes
+    /// are weighted, otherwise the DSL weight capped at the config's maximum
+    fn weight(ctx: &ActionContext<'_>) -> i32 {
+        let participation = ctx.ccl_config
+            .and_then(|config| config.membership.as_ref())
+            .and_then(|membership| membership.participation.as_ref());
+        match participation {
+            Some(p) if p.weights_votes == Some(true) => {
+                let max = p.max_vote_weight.unwrap_or(1).max(1).min(i32::MAX as u64) as i32;
+                ctx.int_param("weight").clamp(1, max)
+            }
+            _ => 1,
+        }
+    }
+}
+
+impl ActionPlugin for CastVote {
+    fn name(&self) -> &str {
+        "cast_vote"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_fields(dsl, self.name(), &["proposal_id", "choice"])?;
+
+        let choice = dsl["choice"].as_str().ok_or_else(|| CompilerError::invalid(
+            Diagnostic::new(DiagnosticCode::SchemaInvalidType)
+                .arg("path", "choice").arg("expected", "string").arg("actual", json_type(&dsl["choice"]))
+                .at("/choice")
+        ))?;
+        if !VOTE_CHOICES.contains(&choice) {
+            return Err(CompilerError::invalid(
+                Diagnostic::new(DiagnosticCode::SchemaInvalidValue)
+                    .arg("path", "choice")
+                    .at("/choice")
+            ));
+        }
+        if let Some(weight) = dsl.get("weight") {
+            if !weight.as_i64().is_some_and(|w| w >= 1) {
+                return Err(CompilerError::invalid(
+                    Diagnostic::new(DiagnosticCode::SchemaViolation)
+                        .arg("path", "weight").arg("detail", format!("expected an integer of at least 1, got {}", weight))
+                        .at("/weight")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn imports(&self) -> Vec<HostImport> {
+        // Budget votes go through the budget's own tally
+        HostImport::bind(CAST_VOTE).into_iter()
+            .chain(HostImport::bind(BUDGET_VOTE))
+            .collect()
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        let choice = ctx.dsl_input.get("choice").and_then(|v| v.as_str())
+            .and_then(|choice| VOTE_CHOICES.iter().position(|c| *c == choice))
+            .unwrap_or_default() as i32;
+        let (proposal_offset, proposal_len) = ctx.param("proposal_id");
+
+        let (function, args) = if ctx.dsl_input.get("budget_id").is_some() {
+            let (budget_offset, budget_len) = ctx.param("budget_id");
+            (BUDGET_VOTE, vec![budget_offset, budget_len, proposal_offset, proposal_len, choice, Self::weight(ctx)])
+        } else {
+            (CAST_VOTE, vec![proposal_offset, proposal_len, choice])
+        };
+        let index = ctx.import_index(BASE_IMPORT_MODULE, function).ok_or_else(|| {
+            CompilerError::WasmGenerationError(format!("{} is not imported", function))
+        })?;
+        Ok(host_call_body(&StatusMessages::of(ctx), index, &args))
+    }
+}
+
+/// Thresholds are compiled in as basis points, so the body needs no floats
+const BASIS_POINTS: i64 = 10_000;
+
+/// Returned by a tally_votes module invoked with less than a [`TallyInput`]
+pub const TALLY_INPUT_INVALID: i32 = -1;
+
+/// The counts a `tally_votes` module is invoked with, as four little-endian u32s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TallyInput {
+    pub votes_for: u32,
+    pub votes_against: u32,
+    pub votes_abstain: u32,
+    /// Members eligible to vote, against whom quorum is measured
+    pub eligible: u32,
+}
+
+impl TallyInput {
+    /// Length of the encoded input
+    pub const LEN: usize = 16;
+
+    /// The input as the module reads it
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0u8; Self::LEN];
+        for (i, count) in [self.votes_for, self.votes_against, self.votes_abstain, self.eligible].iter().enumerate() {
+            bytes[i * 4..i * 4 + 4].copy_from_slice(&count.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Quorum and majority a tally_votes module checks, in basis points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VotingThresholds {
+    quorum_bps: i64,
+    majority_bps: i64,
+}
+
+impl VotingThresholds {
+    /// The config's quorum and majority, adjusted by the proposal type's modifiers
+    fn from_config(config: Option<&GovernanceConfig>, proposal_type: Option<&str>) -> Self {
+        let governance = config.and_then(|c| c.governance.as_ref());
+        let kind = proposal_type.and_then(|name| {
+            config.and_then(|c| c.proposals.as_ref())
+                .and_then(|p| p.types.as_ref())
+                .and_then(|types| types.iter().find(|t| t.name == name))
+        });
+
+        let quorum = governance.and_then(|g| g.quorum).unwrap_or(0.0)
+            * kind.and_then(|t| t.quorum_modifier).unwrap_or(1.0);
+        let majority = governance.and_then(|g| g.majority).unwrap_or(0.5)
+            * kind.and_then(|t| t.majority_modifier).unwrap_or(1.0);
+        let bps = |share: f64| ((share * BASIS_POINTS as f64).round() as i64).clamp(0, BASIS_POINTS);
+        Self { quorum_bps: bps(quorum), majority_bps: bps(majority) }
+    }
+}
+
+struct TallyVotes;
+
+impl ActionPlugin for TallyVotes {
+    fn name(&self) -> &str {
+        "tally_votes"
+    }
+
+    fn supports_template(&self, template_type: &str) -> bool {
+        only_for_coop_bylaws(template_type)
+    }
+
+    fn validate(&self, dsl: &Map<String, JsonValue>) -> CompilerResult<()> {
+        require_field(dsl, self.name(), "proposal_id")?;
+        if let Some(kind) = dsl.get("proposal_type") {
+            if !kind.is_string() {
+                return Err(CompilerError::invalid(
+                    Diagnostic::new(DiagnosticCode::SchemaInvalidType)
+                        .arg("path", "proposal_type").arg("expected", "string").arg("actual", json_type(kind))
+                        .at("/proposal_type")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        let proposal_type = ctx.dsl_input.get("proposal_type").and_then(|v| v.as_str());
+        let thresholds = VotingThresholds::from_config(ctx.ccl_config, proposal_type);
+        Ok(tally_votes_body(&StatusMessages::of(ctx), thresholds))
+    }
+}
+
+/// Name of a JSON value's type, for diagnostics
+fn json_type(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+/// Fallback for actions without a plugin: logs and returns success
+pub(crate) struct DefaultAction;
+
+impl ActionPlugin for DefaultAction {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+        Ok(default_body(&StatusMessages::of(ctx)))
+    }
+}
+
+/// Offset and length of each status message a built-in body logs
+struct StatusMessages {
+    debug: (i32, i32),
+    success: (i32, i32),
+    error: (i32, i32),
+}
+
+impl StatusMessages {
+    fn of(ctx: &ActionContext<'_>) -> Self {
+        Self {
+            debug: ctx.region(memory_layout::DEBUG_MESSAGE),
+            success: ctx.region(memory_layout::SUCCESS_MESSAGE),
+            error: ctx.region(memory_layout::ERROR_MESSAGE),
+        }
+    }
+}
+
+/// Buffer log_caller_info reads the caller's DID into
+const CALLER_DID_BUFFER: &str = "caller_did";
+
+/// Generate a WASM function body for the log_caller_info action
+fn log_caller_info_body(messages: &StatusMessages, did_buffer: (i32, i32)) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        // Local 1: Temporary variable for results
+        // Local 2: String length
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Initialize return value to 0 (success)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Call host_get_caller_did to get the caller's DID
+    func.instruction(&wasm_encoder::Instruction::I32Const(did_buffer.0)); // Output buffer
+    func.instruction(&wasm_encoder::Instruction::I32Const(did_buffer.1)); // Buffer size
+    func.instruction(&wasm_encoder::Instruction::Call(4)); // host_get_caller_did
+    func.instruction(&wasm_encoder::Instruction::LocalSet(2)); // Save the returned length
+    
+    // Check if we got a valid result (length > 0)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(2));
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::I32GtS());
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Log the DID
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
+    func.instruction(&wasm_encoder::Instruction::I32Const(did_buffer.0)); // DID buffer
+    func.instruction(&wasm_encoder::Instruction::LocalGet(2)); // DID length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
+    
+    // End if
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Call host_get_caller_scope to get the caller's scope
+    func.instruction(&wasm_encoder::Instruction::Call(5)); // host_get_caller_scope
+    func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Save the result
+    
+    // Log the scope value
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.0)); // Debug message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
+    
+    // Return success
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body for the perform_metered_action action
+fn perform_metered_action_body(messages: &StatusMessages, resource_type: i32, amount: i32) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        // Local 1: Result of authorization check
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Initialize return value to -1 (error by default)
+    func.instruction(&wasm_encoder::Instruction::I32Const(-1));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Log start of metered action
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.0)); // Debug message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
+    
+    // Check resource authorization
+    func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
+    func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(6)); // host_check_resource_authorization
+    
+    // Store the result in local 1
+    func.instruction(&wasm_encoder::Instruction::LocalSet(1));
+    
+    // Check if authorized (value in local 1)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(1));
+    
+    // If-else block
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // If branch (authorized)
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.0)); // Success message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // Record resource usage
+    func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
+    func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(7)); // Call host_record_resource_usage
+    
+    // Set return value to success (0)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Else branch (not authorized)
+    func.instruction(&wasm_encoder::Instruction::Else);
+    
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // End if-else
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Return status
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body for the anchor_data action
+fn anchor_data_body(messages: &StatusMessages, key_offset: i32, key_len: i32, value_offset: i32, value_len: i32) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        // Local 1: Result of anchor operation
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Initialize return value to -1 (error by default)
+    func.instruction(&wasm_encoder::Instruction::I32Const(-1));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Log start of anchor operation
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.0)); // Debug message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
+    
+    // First check authorization for DAG anchoring (compute resource)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Resource type (Compute)
+    func.instruction(&wasm_encoder::Instruction::I32Const(100)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(6)); // host_check_resource_authorization
+    
+    // If authorization check passes
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Anchor to DAG
+    func.instruction(&wasm_encoder::Instruction::I32Const(key_offset)); // Key pointer
+    func.instruction(&wasm_encoder::Instruction::I32Const(key_len)); // Key length
+    func.instruction(&wasm_encoder::Instruction::I32Const(value_offset)); // Value pointer
+    func.instruction(&wasm_encoder::Instruction::I32Const(value_len)); // Value length
+    func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Parent pointer (none)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Parent count (none)
+    func.instruction(&wasm_encoder::Instruction::Call(8)); // host_anchor_to_dag
+    func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
+    
+    // Check if anchor succeeded (result > 0)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(1));
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::I32GtS());
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Success branch
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.0)); // Success message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // Record resource usage
+    func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Resource type (Compute)
+    func.instruction(&wasm_encoder::Instruction::I32Const(50)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(7)); // Call host_record_resource_usage
+    
+    // Set return value to success (0)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Else branch (anchor failed)
+    func.instruction(&wasm_encoder::Instruction::Else);
+    
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // End if-else (anchor result)
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // End if (authorization check)
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Return status
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body for the mint_token action
+fn mint_token_body(messages: &StatusMessages, resource_type: i32, recipient_offset: i32, recipient_len: i32, amount: i32) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        // Local 1: Result of mint operation
+        // Local 2: Caller scope (to verify Guardian status)
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Initialize return value to -1 (error by default)
+    func.instruction(&wasm_encoder::Instruction::I32Const(-1));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Get caller scope to check if Guardian
+    func.instruction(&wasm_encoder::Instruction::Call(5)); // host_get_caller_scope
+    func.instruction(&wasm_encoder::Instruction::LocalSet(2));
+    
+    // Check if caller has Guardian scope (scope value is 3)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(2));
+    func.instruction(&wasm_encoder::Instruction::I32Const(3));
+    func.instruction(&wasm_encoder::Instruction::I32Eq());
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Caller is Guardian, proceed with mint operation
+    func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
+    func.instruction(&wasm_encoder::Instruction::I32Const(recipient_offset)); // Recipient pointer
+    func.instruction(&wasm_encoder::Instruction::I32Const(recipient_len)); // Recipient length
+    func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(9)); // host_mint_token
+    func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
+    
+    // Check if mint succeeded (result > 0)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(1));
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::I32GtS());
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Success branch
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.0)); // Success message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // Set return value to success (0)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Else branch (mint failed)
+    func.instruction(&wasm_encoder::Instruction::Else);
+    
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // End if-else (mint result)
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Else branch (not a Guardian)
+    func.instruction(&wasm_encoder::Instruction::Else);
+    
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // End if-else (Guardian check)
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Return status
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body for the transfer_resource action
+fn transfer_resource_body(messages: &StatusMessages, from_offset: i32, from_len: i32, to_offset: i32, to_len: i32, resource_type: i32, amount: i32) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        // Local 1: Result of transfer operation
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Initialize return value to -1 (error by default)
+    func.instruction(&wasm_encoder::Instruction::I32Const(-1));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // First check authorization for resource usage
+    func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
+    func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(6)); // host_check_resource_authorization
+    
+    // If authorization check passes
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Perform the transfer
+    func.instruction(&wasm_encoder::Instruction::I32Const(from_offset)); // From pointer
+    func.instruction(&wasm_encoder::Instruction::I32Const(from_len)); // From length
+    func.instruction(&wasm_encoder::Instruction::I32Const(to_offset)); // To pointer
+    func.instruction(&wasm_encoder::Instruction::I32Const(to_len)); // To length
+    func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
+    func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(10)); // host_transfer_resource
+    func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
+    
+    // Check if transfer succeeded (result > 0)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(1));
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::I32GtS());
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Success branch
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.0)); // Success message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // Record resource usage
+    func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Resource type (Compute)
+    func.instruction(&wasm_encoder::Instruction::I32Const(20)); // Amount
+    func.instruction(&wasm_encoder::Instruction::Call(7)); // Call host_record_resource_usage
+    
+    // Set return value to success (0)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Else branch (transfer failed)
+    func.instruction(&wasm_encoder::Instruction::Else);
+    
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // End if-else (transfer result)
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // End if (authorization check)
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Return status
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body that calls one host function with constant arguments.
+/// A non-negative result is success and returns 0; a negative one is returned as is.
+fn host_call_body(messages: &StatusMessages, function: u32, args: &[i32]) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        // Local 1: Result of the host call
+        wasm_encoder::ValType::I32,
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Call the host function
+    for arg in args {
+        func.instruction(&wasm_encoder::Instruction::I32Const(*arg));
+    }
+    func.instruction(&wasm_encoder::Instruction::Call(function));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
+    
+    // Check if the call succeeded (result >= 0)
+    func.instruction(&wasm_encoder::Instruction::LocalGet(1));
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::I32GeS);
+    func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
+    
+    // Success branch
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.0)); // Success message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.success.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // Set return value to success (0)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Else branch (call refused)
+    func.instruction(&wasm_encoder::Instruction::Else);
+    
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
+    
+    // Return the host's error code
+    func.instruction(&wasm_encoder::Instruction::LocalGet(1));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // End if-else
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    // Return status
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body for the tally_votes action. The body reads the
+/// [`TallyInput`] it is invoked with and returns 1 if the proposal passes, 0 if it
+/// fails and [`TALLY_INPUT_INVALID`] if the input is too short.
+fn tally_votes_body(messages: &StatusMessages, thresholds: VotingThresholds) -> wasm_encoder::Function {
+    use wasm_encoder::Instruction;
+    
+    // Parameters 0 and 1: input pointer and length
+    const INPUT: u32 = 0;
+    const INPUT_LEN: u32 = 1;
+    // Local 2: Return value
+    // Locals 3-6: votes for, against, abstaining and eligible voters
+    const RESULT: u32 = 2;
+    const FOR: u32 = 3;
+    const AGAINST: u32 = 4;
+    const ABSTAIN: u32 = 5;
+    const ELIGIBLE: u32 = 6;
+    let mut func = wasm_encoder::Function::new([
+        (1, wasm_encoder::ValType::I32),
+        (4, wasm_encoder::ValType::I64),
+    ]);
+    
+    // Log that we're tallying
+    func.instruction(&Instruction::I32Const(1)); // Log level INFO
+    func.instruction(&Instruction::I32Const(messages.debug.0)); // Debug message
+    func.instruction(&Instruction::I32Const(messages.debug.1)); // Message length
+    func.instruction(&Instruction::Call(0)); // host_log_message
+    
+    // Refuse input too short to hold the tally
+    func.instruction(&Instruction::LocalGet(INPUT_LEN));
+    func.instruction(&Instruction::I32Const(TallyInput::LEN as i32));
+    func.instruction(&Instruction::I32LtS);
+    func.instruction(&Instruction::If(wasm_encoder::BlockType::Empty));
+    func.instruction(&Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&Instruction::I32Const(messages.error.0)); // Error message
+    func.instruction(&Instruction::I32Const(messages.error.1)); // Message length
+    func.instruction(&Instruction::Call(0)); // Call host_log_message
+    func.instruction(&Instruction::I32Const(TALLY_INPUT_INVALID));
+    func.instruction(&Instruction::Return);
+    func.instruction(&Instruction::End);
+    
+    // Load the four counts, widened so products can't overflow
+    for (i, local) in [FOR, AGAINST, ABSTAIN, ELIGIBLE].into_iter().enumerate() {
+        func.instruction(&Instruction::LocalGet(INPUT));
+        func.instruction(&Instruction::I32Load(wasm_encoder::MemArg { offset: (i * 4) as u64, align: 2, memory_index: 0 }));
+        func.instruction(&Instruction::I64ExtendI32U);
+        func.instruction(&Instruction::LocalSet(local));
+    }
+    
+    // Quorum: turnout, abstentions included, is at least the quorum share of the
+    // eligible voters
+    func.instruction(&Instruction::LocalGet(FOR));
+    func.instruction(&Instruction::LocalGet(AGAINST));
+    func.instruction(&Instruction::I64Add);
+    func.instruction(&Instruction::LocalGet(ABSTAIN));
+    func.instruction(&Instruction::I64Add);
+    func.instruction(&Instruction::I64Const(BASIS_POINTS));
+    func.instruction(&Instruction::I64Mul);
+    func.instruction(&Instruction::I64Const(thresholds.quorum_bps));
+    func.instruction(&Instruction::LocalGet(ELIGIBLE));
+    func.instruction(&Instruction::I64Mul);
+    func.instruction(&Instruction::I64GeS);
+    if thresholds.quorum_bps > 0 {
+        // Without eligible voters only a zero quorum is met
+        func.instruction(&Instruction::LocalGet(ELIGIBLE));
+        func.instruction(&Instruction::I64Const(0));
+        func.instruction(&Instruction::I64Ne);
+        func.instruction(&Instruction::I32And);
+    }
+    
+    // Majority: some votes were decisive...
+    func.instruction(&Instruction::LocalGet(FOR));
+    func.instruction(&Instruction::LocalGet(AGAINST));
+    func.instruction(&Instruction::I64Add);
+    func.instruction(&Instruction::I64Const(0));
+    func.instruction(&Instruction::I64Ne);
+    func.instruction(&Instruction::I32And);
+    
+    // ...and the votes for reach the majority share of them. A majority of one half
+    // needs strictly more than half.
+    func.instruction(&Instruction::LocalGet(FOR));
+    func.instruction(&Instruction::I64Const(BASIS_POINTS));
+    func.instruction(&Instruction::I64Mul);
+    func.instruction(&Instruction::I64Const(thresholds.majority_bps.max(BASIS_POINTS / 2)));
+    func.instruction(&Instruction::LocalGet(FOR));
+    func.instruction(&Instruction::LocalGet(AGAINST));
+    func.instruction(&Instruction::I64Add);
+    func.instruction(&Instruction::I64Mul);
+    if thresholds.majority_bps <= BASIS_POINTS / 2 {
+        func.instruction(&Instruction::I64GtS);
+    } else {
+        func.instruction(&Instruction::I64GeS);
+    }
+    func.instruction(&Instruction::I32And);
+    func.instruction(&Instruction::LocalSet(RESULT));
+    
+    // Log the outcome
+    func.instruction(&Instruction::I32Const(1)); // Log level (INFO)
+    func.instruction(&Instruction::I32Const(messages.success.0)); // Success message
+    func.instruction(&Instruction::I32Const(messages.success.1)); // Message length
+    func.instruction(&Instruction::Call(0)); // Call host_log_message
+    
+    // Return the outcome
+    func.instruction(&Instruction::LocalGet(RESULT));
+    func.instruction(&Instruction::End);
+    
+    func
+}
+
+/// Generate a WASM function body for the default (fallback) function
+fn default_body(messages: &StatusMessages) -> wasm_encoder::Function {
+    let mut func = wasm_encoder::Function::new([
+        // Local variables: 
+        // Local 0: Return value
+        wasm_encoder::ValType::I32,
+    ]);
+    
+    // Initialize return value to 0 (success)
+    func.instruction(&wasm_encoder::Instruction::I32Const(0));
+    func.instruction(&wasm_encoder::Instruction::LocalSet(0));
+    
+    // Log that we're executing the action
+    func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.0)); // Debug message
+    func.instruction(&wasm_encoder::Instruction::I32Const(messages.debug.1)); // Message length
+    func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
+    
+    // Return success
+    func.instruction(&wasm_encoder::Instruction::LocalGet(0));
+    func.instruction(&wasm_encoder::Instruction::End);
+    
+    func
+}
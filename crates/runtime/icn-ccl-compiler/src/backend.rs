@@ -0,0 +1,165 @@
+/*!
+# Codegen Backends
+
+Validation, metadata and optimization are the same whatever the module is generated
+with; the last step, turning a validated CCL config and DSL input into module bytes, is
+done by a [`CodegenBackend`]. The built-in backend encodes core modules directly with
+`wasm_encoder` and is what [`CompilationOptions::backend`] selects when left unset.
+Other backends, such as the Rust template backend behind the `templating` feature or a
+backend for tests, are registered on the compiler with
+[`CclCompiler::register_backend`](crate::CclCompiler::register_backend) and selected by
+name.
+
+Only backends that produce binary core modules go through the optimization pipeline.
+*/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::Value as JsonValue;
+
+use crate::{CclCompiler, CompilationOptions, CompilerError, CompilerResult, GovernanceConfig};
+
+/// Name of the built-in `wasm_encoder` backend
+pub const DEFAULT_BACKEND: &str = "wasm-encoder";
+
+/// Generates a module from a validated CCL config and DSL input
+pub trait CodegenBackend: Send + Sync {
+    /// Name the backend is selected by in [`CompilationOptions::backend`]
+    fn name(&self) -> &str;
+
+    /// Generate the module
+    fn generate(
+        &self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<Vec<u8>>;
+
+    /// Whether the output is a binary core module the optimizer can rewrite
+    fn produces_core_module(&self) -> bool {
+        true
+    }
+}
+
+/// The backends a compiler can generate modules with, besides the built-in one
+#[derive(Clone)]
+pub struct BackendRegistry {
+    backends: HashMap<String, Arc<dyn CodegenBackend>>,
+}
+
+impl Default for BackendRegistry {
+    /// A registry holding the backends enabled by crate features
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::empty();
+        #[cfg(feature = "templating")]
+        registry.register(Arc::new(RustTemplateBackend));
+        registry
+    }
+}
+
+impl BackendRegistry {
+    /// A registry without any backends
+    pub fn empty() -> Self {
+        Self { backends: HashMap::new() }
+    }
+
+    /// Add a backend, replacing any registered under the same name
+    pub fn register(&mut self, backend: Arc<dyn CodegenBackend>) {
+        self.backends.insert(backend.name().to_string(), backend);
+    }
+
+    /// The backend registered under a name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn CodegenBackend>> {
+        self.backends.get(name).cloned()
+    }
+
+    /// Names of all registered backends, sorted
+    pub fn backends(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.backends.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// The compiler is its own built-in backend, since generating a module needs its
+/// action plugins
+impl CodegenBackend for CclCompiler {
+    fn name(&self) -> &str {
+        DEFAULT_BACKEND
+    }
+
+    fn generate(
+        &self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<Vec<u8>> {
+        self.generate_wasm_module(ccl_config, dsl_input, options)
+    }
+}
+
+impl CclCompiler {
+    /// Register a codegen backend, replacing any backend of the same name
+    pub fn register_backend(&mut self, backend: Arc<dyn CodegenBackend>) {
+        self.backends.register(backend);
+    }
+
+    /// The backends this compiler can generate modules with, besides the built-in one
+    pub fn backends(&self) -> &BackendRegistry {
+        &self.backends
+    }
+
+    /// Generate a module with the backend the options select. Returns the module and
+    /// whether it is a core module the optimizer can rewrite.
+    pub(crate) fn generate_with_backend(
+        &self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<(Vec<u8>, bool)> {
+        match options.backend.as_deref() {
+            None | Some(DEFAULT_BACKEND) => Ok((self.generate(ccl_config, dsl_input, options)?, true)),
+            Some(name) => {
+                let backend = self.backends.get(name).ok_or_else(|| {
+                    let mut available = vec![DEFAULT_BACKEND.to_string()];
+                    available.extend(self.backends.backends());
+                    CompilerError::General(format!(
+                        "Unknown codegen backend '{}'; available: {}", name, available.join(", ")
+                    ))
+                })?;
+                tracing::debug!("Generating module with the {} backend", name);
+                Ok((backend.generate(ccl_config, dsl_input, options)?, backend.produces_core_module()))
+            }
+        }
+    }
+}
+
+/// Builds modules from Rust templates compiled for `wasm32-wasi`
+#[cfg(feature = "templating")]
+pub struct RustTemplateBackend;
+
+#[cfg(feature = "templating")]
+impl CodegenBackend for RustTemplateBackend {
+    fn name(&self) -> &str {
+        "rust-template"
+    }
+
+    fn generate(
+        &self,
+        _ccl_config: &GovernanceConfig,
+        _dsl_input: &JsonValue,
+        _options: &CompilationOptions,
+    ) -> CompilerResult<Vec<u8>> {
+        // This is a placeholder for the templating approach
+        // In a real implementation, this would:
+        // 1. Select a Rust template based on the CCL template type
+        // 2. Fill in the template with the DSL input values
+        // 3. Compile the Rust code to wasm32-wasi
+        // 4. Return the compiled WASM bytes
+
+        Err(CompilerError::General(
+            "Templated WASM generation not yet implemented".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,111 @@
+/*!
+# Batch Compilation
+
+Bootstrapping a federation compiles hundreds of proposal modules at once.
+[`CclCompiler::compile_batch`] runs the jobs in parallel on the rayon pool it's called
+from (the global pool unless the call is wrapped in `ThreadPool::install`). Each worker
+compiles with its own copy of the compiler, but the copies share one cache of compiled
+JSON schemas, so each schema file is read and compiled once per batch.
+
+A job that fails, or panics, fails alone: every job gets its own result, in the order
+the jobs were given.
+*/
+
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
+use serde_json::Value as JsonValue;
+
+use crate::{CclCompiler, CompilationOptions, CompilerError, CompilerResult, GovernanceConfig, OptimizationStats};
+
+/// One module to compile in a batch
+#[derive(Debug, Clone)]
+pub struct CompileJob {
+    /// Caller's name for the job, echoed in its result
+    pub id: String,
+    pub ccl_config: GovernanceConfig,
+    pub dsl_input: JsonValue,
+    pub options: Option<CompilationOptions>,
+}
+
+impl CompileJob {
+    pub fn new(id: impl Into<String>, ccl_config: GovernanceConfig, dsl_input: JsonValue) -> Self {
+        Self { id: id.into(), ccl_config, dsl_input, options: None }
+    }
+
+    /// Compile the job with these options instead of the defaults
+    pub fn with_options(mut self, options: CompilationOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+}
+
+/// The outcome of one job in a batch
+#[derive(Debug)]
+pub struct CompileResult {
+    /// The job's ID
+    pub id: String,
+    /// The compiled module, or why the job failed
+    pub result: CompilerResult<Vec<u8>>,
+    /// What the optimizer did, if the module was optimized
+    pub optimization: Option<OptimizationStats>,
+    /// How long the job took
+    pub duration: Duration,
+}
+
+impl CompileResult {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+impl CclCompiler {
+    /// A compiler for one batch worker: the same actions and backends, and a schema
+    /// manager sharing this compiler's schema cache
+    fn batch_worker(&self) -> CclCompiler {
+        CclCompiler {
+            schema_manager: self.schema_manager.clone(),
+            actions: self.actions.clone(),
+            last_optimization: None,
+            backends: self.backends.clone(),
+        }
+    }
+
+    /// Compile many modules in parallel, sharing compiled schemas between them.
+    ///
+    /// Returns one result per job, in job order. Failing jobs don't stop the others.
+    pub fn compile_batch(&self, jobs: Vec<CompileJob>) -> Vec<CompileResult> {
+        let started = Instant::now();
+        let job_count = jobs.len();
+
+        let results: Vec<CompileResult> = jobs.into_par_iter()
+            .map_init(|| self.batch_worker(), |compiler, job| {
+                let job_started = Instant::now();
+                let CompileJob { id, ccl_config, dsl_input, options } = job;
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    compiler.compile_to_wasm(&ccl_config, &dsl_input, options)
+                }))
+                .unwrap_or_else(|payload| Err(CompilerError::General(format!(
+                    "Compilation panicked: {}", panic_message(payload.as_ref())
+                ))));
+                let optimization = match &result {
+                    Ok(_) => compiler.last_optimization.take(),
+                    Err(_) => None,
+                };
+                CompileResult { id, result, optimization, duration: job_started.elapsed() }
+            })
+            .collect();
+
+        let failed = results.iter().filter(|r| !r.is_ok()).count();
+        tracing::info!(
+            "Compiled batch of {} modules in {:?} ({} failed)", job_count, started.elapsed(), failed
+        );
+        results
+    }
+}
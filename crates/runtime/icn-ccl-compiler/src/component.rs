@@ -0,0 +1,289 @@
+/*!
+# Component Model Backend (experimental)
+
+Compiles a CCL configuration and DSL input into a WebAssembly component targeting the
+`ccl-module` world defined by the runtime (`icn_core_vm::component::CCL_WIT`) instead of
+a core module with the pointer-and-length host ABI. The component exports
+`run(proposal) -> result<execution-result, execution-error>` and logs through the
+structured `host` interface; the canonical ABI takes care of moving records and strings
+across the boundary.
+
+The backend builds a core module that follows the canonical ABI for the world (a
+bump-allocating `cabi_realloc`, a `run` export returning a pointer to its result),
+embeds the world's type information and wraps it with `wit-component`. It doesn't use
+action plugins yet: every component reports the action and the DSL's string fields as
+outputs, together with the ID of the proposal it ran for.
+*/
+
+use std::path::Path;
+use serde_json::Value as JsonValue;
+use wasm_encoder::{
+    CodeSection, ConstExpr, DataSection, EntityType, ExportKind, ExportSection, Function,
+    FunctionSection, GlobalSection, GlobalType, ImportSection, Instruction, MemArg,
+    MemorySection, MemoryType, Module, TypeSection, ValType,
+};
+use wit_component::{ComponentEncoder, StringEncoding};
+use wit_parser::{Resolve, UnresolvedPackage};
+use icn_core_vm::component::{CCL_WIT, CCL_WORLD};
+
+use crate::{CclCompiler, CompilationOptions, CompilerError, CompilerResult, GovernanceConfig, MemoryLimits};
+use crate::fit_memory_limits;
+
+/// Core module name the world's `host` interface is imported under
+const HOST_IMPORT_MODULE: &str = "icn:ccl/host@0.1.0";
+
+/// `log-level` case used for the start-of-execution message
+const LOG_LEVEL_INFO: i32 = 1;
+
+/// `execution-error` case for invalid input
+const INVALID_INPUT_CASE: u8 = 1;
+
+/// Where string data starts in linear memory
+const STRINGS_OFFSET: usize = 1024;
+
+/// Size of a `tuple<string, string>` in linear memory
+const TUPLE_SIZE: usize = 16;
+
+/// Static data of a compiled component, laid out in linear memory
+struct ComponentLayout {
+    data: Vec<u8>,
+    log_message: (usize, usize),
+    tuples_offset: usize,
+    ok_offset: usize,
+    err_offset: usize,
+    heap_start: usize,
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+/// Append a string, returning its offset and length
+fn push_str(data: &mut Vec<u8>, s: &str) -> (usize, usize) {
+    let offset = data.len();
+    data.extend_from_slice(s.as_bytes());
+    (offset, s.len())
+}
+
+fn put_u32(data: &mut Vec<u8>, offset: usize, value: u32) {
+    if data.len() < offset + 4 {
+        data.resize(offset + 4, 0);
+    }
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Lay out the log message, the `outputs` list and both `run` results
+fn layout(ccl_config: &GovernanceConfig, action: &str, dsl_input: &JsonValue) -> ComponentLayout {
+    let mut data = vec![0u8; STRINGS_OFFSET];
+
+    let log_message = push_str(&mut data, &format!("Executing {} for template {}", action, ccl_config.template_type));
+    let result_message = push_str(&mut data, &format!("Executed {}", action));
+    let error_message = push_str(&mut data, "Proposal has no id");
+
+    // The first output's value is the proposal ID, filled in when `run` is called
+    let mut outputs = vec![(push_str(&mut data, "proposal_id"), (0, 0))];
+    outputs.push((push_str(&mut data, "action"), push_str(&mut data, action)));
+    if let Some(fields) = dsl_input.as_object() {
+        for (key, value) in fields.iter().filter(|(key, _)| key.as_str() != "action") {
+            if let Some(value) = value.as_str() {
+                outputs.push((push_str(&mut data, key), push_str(&mut data, value)));
+            }
+        }
+    }
+
+    let tuples_offset = align_to(data.len(), 8);
+    for (i, ((key_ptr, key_len), (value_ptr, value_len))) in outputs.iter().enumerate() {
+        let at = tuples_offset + i * TUPLE_SIZE;
+        put_u32(&mut data, at, *key_ptr as u32);
+        put_u32(&mut data, at + 4, *key_len as u32);
+        put_u32(&mut data, at + 8, *value_ptr as u32);
+        put_u32(&mut data, at + 12, *value_len as u32);
+    }
+
+    // result::ok(execution-result { code: 0, message, outputs })
+    let ok_offset = align_to(data.len(), 8);
+    put_u32(&mut data, ok_offset, 0);
+    put_u32(&mut data, ok_offset + 4, 0);
+    put_u32(&mut data, ok_offset + 8, result_message.0 as u32);
+    put_u32(&mut data, ok_offset + 12, result_message.1 as u32);
+    put_u32(&mut data, ok_offset + 16, tuples_offset as u32);
+    put_u32(&mut data, ok_offset + 20, outputs.len() as u32);
+
+    // result::err(execution-error::invalid-input(message))
+    let err_offset = align_to(data.len(), 8);
+    put_u32(&mut data, err_offset, 1);
+    put_u32(&mut data, err_offset + 4, INVALID_INPUT_CASE as u32);
+    put_u32(&mut data, err_offset + 8, error_message.0 as u32);
+    put_u32(&mut data, err_offset + 12, error_message.1 as u32);
+
+    let heap_start = align_to(data.len(), 16);
+    ComponentLayout { data, log_message, tuples_offset, ok_offset, err_offset, heap_start }
+}
+
+fn mem_arg(offset: usize) -> MemArg {
+    MemArg { offset: offset as u64, align: 2, memory_index: 0 }
+}
+
+/// `cabi_realloc(old_ptr, old_size, align, new_size) -> ptr`, a bump allocator that
+/// grows memory as needed. The canonical ABI only allocates fresh blocks when lowering
+/// arguments, so old blocks never need copying.
+fn realloc_body() -> Function {
+    // Locals 4..=6: ptr, end, pages to grow
+    let mut func = Function::new([(3, ValType::I32)]);
+    func.instruction(&Instruction::GlobalGet(0))
+        .instruction(&Instruction::LocalGet(2))
+        .instruction(&Instruction::I32Add)
+        .instruction(&Instruction::I32Const(1))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::I32Const(0))
+        .instruction(&Instruction::LocalGet(2))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::I32And)
+        .instruction(&Instruction::LocalSet(4))
+        .instruction(&Instruction::LocalGet(4))
+        .instruction(&Instruction::LocalGet(3))
+        .instruction(&Instruction::I32Add)
+        .instruction(&Instruction::LocalTee(5))
+        .instruction(&Instruction::GlobalSet(0))
+        .instruction(&Instruction::LocalGet(5))
+        .instruction(&Instruction::I32Const(65_535))
+        .instruction(&Instruction::I32Add)
+        .instruction(&Instruction::I32Const(16))
+        .instruction(&Instruction::I32ShrU)
+        .instruction(&Instruction::MemorySize(0))
+        .instruction(&Instruction::I32Sub)
+        .instruction(&Instruction::LocalTee(6))
+        .instruction(&Instruction::I32Const(0))
+        .instruction(&Instruction::I32GtS)
+        .instruction(&Instruction::If(wasm_encoder::BlockType::Empty))
+        .instruction(&Instruction::LocalGet(6))
+        .instruction(&Instruction::MemoryGrow(0))
+        .instruction(&Instruction::Drop)
+        .instruction(&Instruction::End)
+        .instruction(&Instruction::LocalGet(4))
+        .instruction(&Instruction::End);
+    func
+}
+
+/// `run(id, scope-id, action, params) -> result-ptr`, with each string and list
+/// flattened to a pointer and a length
+fn run_body(layout: &ComponentLayout) -> Function {
+    let mut func = Function::new([]);
+    func.instruction(&Instruction::I32Const(LOG_LEVEL_INFO))
+        .instruction(&Instruction::I32Const(layout.log_message.0 as i32))
+        .instruction(&Instruction::I32Const(layout.log_message.1 as i32))
+        .instruction(&Instruction::Call(0))
+        // A proposal without an ID is invalid input
+        .instruction(&Instruction::LocalGet(1))
+        .instruction(&Instruction::I32Eqz)
+        .instruction(&Instruction::If(wasm_encoder::BlockType::Empty))
+        .instruction(&Instruction::I32Const(layout.err_offset as i32))
+        .instruction(&Instruction::Return)
+        .instruction(&Instruction::End)
+        // Report the proposal ID as the first output
+        .instruction(&Instruction::I32Const(layout.tuples_offset as i32))
+        .instruction(&Instruction::LocalGet(0))
+        .instruction(&Instruction::I32Store(mem_arg(8)))
+        .instruction(&Instruction::I32Const(layout.tuples_offset as i32))
+        .instruction(&Instruction::LocalGet(1))
+        .instruction(&Instruction::I32Store(mem_arg(12)))
+        .instruction(&Instruction::I32Const(layout.ok_offset as i32))
+        .instruction(&Instruction::End);
+    func
+}
+
+/// Build the core module the component wraps
+fn core_module(layout: &ComponentLayout, memory_limits: &MemoryLimits) -> Vec<u8> {
+    let mut module = Module::new();
+
+    let mut types = TypeSection::new();
+    // Type 0: host log(level, message_ptr, message_len)
+    types.function(vec![ValType::I32; 3], vec![]);
+    // Type 1: cabi_realloc(old_ptr, old_size, align, new_size) -> ptr
+    types.function(vec![ValType::I32; 4], vec![ValType::I32]);
+    // Type 2: run(id, scope-id, action, params) -> result ptr
+    types.function(vec![ValType::I32; 8], vec![ValType::I32]);
+    module.section(&types);
+
+    let mut imports = ImportSection::new();
+    imports.import(HOST_IMPORT_MODULE, "log", EntityType::Function(0));
+    module.section(&imports);
+
+    let mut functions = FunctionSection::new();
+    functions.function(1);
+    functions.function(2);
+    module.section(&functions);
+
+    let mut memory = MemorySection::new();
+    memory.memory(MemoryType {
+        minimum: memory_limits.min_pages as u64,
+        maximum: memory_limits.max_pages.map(u64::from),
+        memory64: false,
+        shared: false,
+    });
+    module.section(&memory);
+
+    let mut globals = GlobalSection::new();
+    globals.global(
+        GlobalType { val_type: ValType::I32, mutable: true },
+        &ConstExpr::i32_const(layout.heap_start as i32),
+    );
+    module.section(&globals);
+
+    let mut exports = ExportSection::new();
+    exports.export("memory", ExportKind::Memory, 0);
+    exports.export("cabi_realloc", ExportKind::Func, 1);
+    exports.export("run", ExportKind::Func, 2);
+    module.section(&exports);
+
+    let mut code = CodeSection::new();
+    code.function(&realloc_body());
+    code.function(&run_body(layout));
+    module.section(&code);
+
+    let mut data = DataSection::new();
+    data.active(0, &ConstExpr::i32_const(0), layout.data.iter().copied());
+    module.section(&data);
+
+    module.finish()
+}
+
+impl CclCompiler {
+    /// Compile a CCL configuration and DSL input into a component targeting the
+    /// runtime's `ccl-module` world
+    pub fn compile_to_component(
+        &mut self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: Option<CompilationOptions>,
+    ) -> CompilerResult<Vec<u8>> {
+        let options = options.unwrap_or_default();
+        self.validate_input(ccl_config, dsl_input, &options)
+            .map_err(|e| e.localized(options.locale))?;
+        let action = self.extract_action_from_dsl(dsl_input)?;
+
+        let layout = layout(ccl_config, &action, dsl_input);
+
+        // Leave a page past the static data for arguments lowered into the heap
+        let default_limits = MemoryLimits::default();
+        let requested_limits = options.memory_limits.as_ref().unwrap_or(&default_limits);
+        let memory_limits = fit_memory_limits(requested_limits, layout.heap_start + crate::WASM_PAGE_SIZE)?;
+
+        let mut core = core_module(&layout, &memory_limits);
+
+        let mut resolve = Resolve::default();
+        let package = UnresolvedPackage::parse(Path::new("ccl.wit"), CCL_WIT)
+            .and_then(|package| resolve.push(package))
+            .map_err(|e| CompilerError::WasmGenerationError(format!("Invalid CCL WIT: {}", e)))?;
+        let world = resolve.select_world(package, Some(CCL_WORLD))
+            .map_err(|e| CompilerError::WasmGenerationError(format!("Missing CCL world: {}", e)))?;
+
+        wit_component::embed_component_metadata(&mut core, &resolve, world, StringEncoding::UTF8)
+            .map_err(|e| CompilerError::WasmGenerationError(format!("Failed to embed component types: {}", e)))?;
+
+        ComponentEncoder::default()
+            .module(&core)
+            .and_then(|encoder| encoder.validate(true).encode())
+            .map_err(|e| CompilerError::WasmGenerationError(format!("Failed to encode component: {}", e)))
+    }
+}
@@ -0,0 +1,281 @@
+/*!
+# Decompilation
+
+Auditors need to see what a deployed governance module actually does.
+[`CclCompiler::decompile`] reads a compiled basic module back: the metadata, CCL config
+and DSL input custom sections when debug info was embedded, the host functions it
+imports and the ones its code actually calls.
+
+The metadata records a SHA-256 hash of each source section. Decompiling checks the
+embedded config and DSL input against those hashes, so a module whose sources were
+edited after compilation is refused rather than misreported.
+
+Without debug info the action can't be read from the module, so it's inferred: every
+registered action that imports at least what the module imports, and whose generated
+body calls the same host functions, is a candidate. Built-in actions that share a body
+can't be told apart this way and are all reported.
+*/
+
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use wasmparser::{Operator, Parser, Payload, TypeRef};
+
+use crate::{
+    actions, assemble_module, ActionContext, RecurringSchedule, CclCompiler, CompilerError, CompilerResult,
+    EncodedSection, GovernanceConfig, MemoryLayout, MetadataInfo,
+};
+
+/// Custom section holding the module's [`MetadataInfo`]
+pub const METADATA_SECTION: &str = "icn-ccl-metadata";
+
+/// Name the metadata section had in modules compiled before it was renamed
+pub const LEGACY_METADATA_SECTION: &str = "icn-metadata";
+
+/// Custom section holding the CCL config the module was compiled from
+pub const CONFIG_SECTION: &str = "icn-ccl-config";
+
+/// Custom section holding the DSL input the module was compiled from
+pub const DSL_SECTION: &str = "icn-dsl-input";
+
+/// Custom section holding the recurrence a `schedule_recurring` module registers
+pub const SCHEDULE_SECTION: &str = "icn-schedule";
+
+/// Hash of a custom section's contents as recorded in the metadata
+pub fn section_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// A host function a module imports
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ImportedFunction {
+    pub module: String,
+    pub name: String,
+}
+
+/// How the action a module performs was determined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecompiledAction {
+    /// Read from the module's metadata or DSL input
+    Embedded(String),
+    /// Inferred from the module's imports and calls; every action that fits, sorted
+    Inferred { candidates: Vec<String> },
+}
+
+impl DecompiledAction {
+    /// The action, if it's known or only one action fits
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            DecompiledAction::Embedded(action) => Some(action),
+            DecompiledAction::Inferred { candidates } if candidates.len() == 1 => Some(&candidates[0]),
+            DecompiledAction::Inferred { .. } => None,
+        }
+    }
+}
+
+/// What a compiled module was built from and what it does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecompiledModule {
+    /// The embedded metadata, if debug info was included
+    pub metadata: Option<MetadataInfo>,
+    pub ccl_config: Option<GovernanceConfig>,
+    pub dsl_input: Option<JsonValue>,
+    /// The recurrence the module registers, if it schedules one
+    pub schedule: Option<RecurringSchedule>,
+    /// Whether the embedded sources were checked against hashes in the metadata.
+    /// False when there were no hashes to check, as in modules without debug info.
+    pub sources_verified: bool,
+    /// Every host function the module imports, in function index order
+    pub imports: Vec<ImportedFunction>,
+    /// Imported host functions some function body calls, sorted
+    pub called_imports: Vec<ImportedFunction>,
+    pub action: DecompiledAction,
+}
+
+impl DecompiledModule {
+    /// Where the module's data and buffers sit, if the metadata recorded it
+    pub fn memory_layout(&self) -> Option<&MemoryLayout> {
+        self.metadata.as_ref().and_then(|m| m.memory_layout.as_ref())
+    }
+}
+
+fn parse_error(e: wasmparser::BinaryReaderError) -> CompilerError {
+    CompilerError::DecompileError(format!("Failed to parse module: {}", e))
+}
+
+/// The parts of a module decompilation reads
+#[derive(Default)]
+struct ParsedModule {
+    custom_sections: HashMap<String, Vec<u8>>,
+    imports: Vec<ImportedFunction>,
+    /// Function index of every call instruction
+    calls: BTreeSet<u32>,
+}
+
+fn parse_module(wasm_bytes: &[u8]) -> CompilerResult<ParsedModule> {
+    let mut parsed = ParsedModule::default();
+    for payload in Parser::new(0).parse_all(wasm_bytes) {
+        match payload.map_err(parse_error)? {
+            Payload::CustomSection(section) => {
+                if parsed.custom_sections.insert(section.name().to_string(), section.data().to_vec()).is_some() {
+                    return Err(CompilerError::DecompileError(format!(
+                        "Custom section '{}' appears more than once", section.name()
+                    )));
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(parse_error)?;
+                    // Only function imports take function indices
+                    if let TypeRef::Func(_) = import.ty {
+                        parsed.imports.push(ImportedFunction {
+                            module: import.module.to_string(),
+                            name: import.name.to_string(),
+                        });
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut operators = body.get_operators_reader().map_err(parse_error)?;
+                while !operators.eof() {
+                    if let Operator::Call { function_index } = operators.read().map_err(parse_error)? {
+                        parsed.calls.insert(function_index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// Imports called through the given function indices
+fn called_imports(imports: &[ImportedFunction], calls: &BTreeSet<u32>) -> BTreeSet<ImportedFunction> {
+    calls.iter()
+        .filter_map(|index| imports.get(*index as usize).cloned())
+        .collect()
+}
+
+/// Check the embedded sources against the hashes recorded in the metadata
+fn verify_sources(metadata: &MetadataInfo, sections: &HashMap<String, Vec<u8>>) -> CompilerResult<bool> {
+    if metadata.source_hashes.is_empty() {
+        return Ok(false);
+    }
+    for name in [CONFIG_SECTION, DSL_SECTION] {
+        match (metadata.source_hashes.get(name), sections.get(name)) {
+            (Some(expected), Some(bytes)) if *expected == section_hash(bytes) => {}
+            (Some(_), Some(_)) => {
+                return Err(CompilerError::DecompileError(format!(
+                    "Section '{}' doesn't match the hash recorded in the metadata", name
+                )));
+            }
+            (Some(_), None) => {
+                return Err(CompilerError::DecompileError(format!(
+                    "Section '{}' is recorded in the metadata but missing", name
+                )));
+            }
+            (None, Some(_)) => {
+                return Err(CompilerError::DecompileError(format!(
+                    "Section '{}' has no hash in the metadata", name
+                )));
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(true)
+}
+
+fn parse_section<T: serde::de::DeserializeOwned>(sections: &HashMap<String, Vec<u8>>, name: &str) -> CompilerResult<Option<T>> {
+    sections.get(name)
+        .map(|bytes| serde_json::from_slice(bytes).map_err(|e| CompilerError::DecompileError(format!(
+            "Section '{}' is not valid: {}", name, e
+        ))))
+        .transpose()
+}
+
+impl CclCompiler {
+    /// Recover the CCL config, DSL input and metadata a module was compiled from, with
+    /// the host functions it imports and calls.
+    ///
+    /// Embedded sources are checked against the hashes in the metadata. If the module
+    /// was compiled without debug info the action is inferred from the registered
+    /// actions instead.
+    pub fn decompile(&self, wasm_bytes: &[u8]) -> CompilerResult<DecompiledModule> {
+        let parsed = parse_module(wasm_bytes)?;
+        let sections = &parsed.custom_sections;
+
+        let metadata: Option<MetadataInfo> = match parse_section(sections, METADATA_SECTION)? {
+            Some(metadata) => Some(metadata),
+            None => parse_section(sections, LEGACY_METADATA_SECTION)?,
+        };
+        let sources_verified = match &metadata {
+            Some(metadata) => verify_sources(metadata, sections)?,
+            None => false,
+        };
+        let ccl_config: Option<GovernanceConfig> = parse_section(sections, CONFIG_SECTION)?;
+        let dsl_input: Option<JsonValue> = parse_section(sections, DSL_SECTION)?;
+        let schedule: Option<RecurringSchedule> = parse_section(sections, SCHEDULE_SECTION)?;
+
+        let called = called_imports(&parsed.imports, &parsed.calls);
+        let embedded = metadata.as_ref().map(|m| m.action.clone())
+            .or_else(|| dsl_input.as_ref()
+                .and_then(|dsl| dsl.get("action"))
+                .and_then(|action| action.as_str())
+                .map(str::to_string));
+        let action = match embedded {
+            Some(action) => DecompiledAction::Embedded(action),
+            None => DecompiledAction::Inferred { candidates: self.infer_actions(&parsed.imports, &called)? },
+        };
+
+        Ok(DecompiledModule {
+            metadata,
+            ccl_config,
+            dsl_input,
+            schedule,
+            sources_verified,
+            imports: parsed.imports,
+            called_imports: called.into_iter().collect(),
+            action,
+        })
+    }
+
+    /// Registered actions that would import `imports` and call `called`
+    fn infer_actions(&self, imports: &[ImportedFunction], called: &BTreeSet<ImportedFunction>) -> CompilerResult<Vec<String>> {
+        let mut candidates = Vec::new();
+        for name in self.actions.actions() {
+            let Some(plugin) = self.actions.get(&name) else { continue };
+
+            // The action's import table, as the compiler would lay it out. An optimized
+            // module may have had unused imports stripped, so it only needs a subset.
+            let extra_imports = crate::extra_imports(plugin.as_ref());
+            let expected: Vec<ImportedFunction> = actions::BASE_IMPORTS.iter()
+                .map(|name| ImportedFunction { module: actions::BASE_IMPORT_MODULE.to_string(), name: name.to_string() })
+                .chain(extra_imports.iter().map(|import| ImportedFunction { module: import.module.clone(), name: import.name.clone() }))
+                .collect();
+            if !imports.iter().all(|import| expected.contains(import)) {
+                continue;
+            }
+
+            // Generate the action's body against an empty layout and compare its calls
+            let dsl_input = serde_json::json!({ "action": name });
+            let context = ActionContext {
+                action: &name,
+                template_type: "",
+                ccl_config: None,
+                dsl_input: &dsl_input,
+                params: &HashMap::new(),
+                memory: &MemoryLayout::default(),
+                imports: &crate::import_indices(&extra_imports),
+            };
+            let Ok(body) = plugin.emit_body(&context) else { continue };
+            let mut code = wasm_encoder::CodeSection::new();
+            code.function(&body);
+            let probe = parse_module(&assemble_module(&[EncodedSection::of(&code)]))?;
+            if called_imports(&expected, &probe.calls) == *called {
+                candidates.push(name);
+            }
+        }
+        Ok(candidates)
+    }
+}
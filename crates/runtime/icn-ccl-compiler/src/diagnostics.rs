@@ -0,0 +1,224 @@
+/*!
+# Validation Diagnostics
+
+DSL validation failures are reported as [`Diagnostic`]s rather than English sentences.
+A diagnostic carries a stable code, the arguments its message needs and, for schema
+failures, the path of the offending value. Its message is rendered from a per-locale
+catalog when it is displayed, so the same failure reads in the locale set with
+[`CompilationOptions::locale`](crate::CompilationOptions::locale).
+
+Front-ends that render their own feedback can ignore the messages and work from the
+codes and arguments: [`Diagnostic::to_json`] gives both.
+*/
+
+use std::collections::BTreeMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Locale validation messages are rendered in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "es")]
+    Spanish,
+}
+
+impl Locale {
+    /// The locale for a language tag such as `es` or `es-MX`; only the primary language is used
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    /// The locale's language tag
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Spanish => "es",
+        }
+    }
+}
+
+/// Kinds of validation failure, each with a stable code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticCode {
+    /// The DSL input isn't a JSON object
+    NotAnObject,
+    /// The DSL input has no `action` field (args: `template`)
+    MissingAction,
+    /// The action isn't known for the template (args: `action`, `template`)
+    UnknownAction,
+    /// The action needs a field the input doesn't have (args: `action`, `field`)
+    MissingField,
+    /// The CCL template type isn't supported (args: `template`, `version`)
+    UnsupportedTemplate,
+    /// A recurrence schedule isn't a valid cron expression (args: `action`, `schedule`, `detail`)
+    InvalidSchedule,
+    /// A property required by the schema is missing (args: `property`)
+    SchemaMissingProperty,
+    /// A value has the wrong type (args: `path`, `expected`, `actual`)
+    SchemaInvalidType,
+    /// A value isn't one of the allowed values (args: `path`)
+    SchemaInvalidValue,
+    /// A string is shorter than allowed (args: `path`, `limit`)
+    SchemaTooShort,
+    /// A string is longer than allowed (args: `path`, `limit`)
+    SchemaTooLong,
+    /// A string doesn't match the schema's pattern (args: `path`)
+    SchemaPatternMismatch,
+    /// Any other schema violation (args: `path`, `detail`)
+    SchemaViolation,
+}
+
+impl DiagnosticCode {
+    /// The stable code front-ends key translations and help pages by
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticCode::NotAnObject => "CCL-V001",
+            DiagnosticCode::MissingAction => "CCL-V002",
+            DiagnosticCode::UnknownAction => "CCL-V003",
+            DiagnosticCode::MissingField => "CCL-V004",
+            DiagnosticCode::UnsupportedTemplate => "CCL-V005",
+            DiagnosticCode::InvalidSchedule => "CCL-V006",
+            DiagnosticCode::SchemaMissingProperty => "CCL-S001",
+            DiagnosticCode::SchemaInvalidType => "CCL-S002",
+            DiagnosticCode::SchemaInvalidValue => "CCL-S003",
+            DiagnosticCode::SchemaTooShort => "CCL-S004",
+            DiagnosticCode::SchemaTooLong => "CCL-S005",
+            DiagnosticCode::SchemaPatternMismatch => "CCL-S006",
+            DiagnosticCode::SchemaViolation => "CCL-S099",
+        }
+    }
+
+    /// The message template for the code in a locale, with `{arg}` placeholders
+    fn template(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::English => match self {
+                DiagnosticCode::NotAnObject => "DSL input must be a JSON object",
+                DiagnosticCode::MissingAction => "DSL input for {template} must contain 'action' field",
+                DiagnosticCode::UnknownAction => "Unknown action '{action}' for {template}",
+                DiagnosticCode::MissingField => "{action} requires '{field}' field",
+                DiagnosticCode::UnsupportedTemplate => "Unsupported template type: {template}:{version}",
+                DiagnosticCode::InvalidSchedule => "{action} has an invalid schedule '{schedule}': {detail}",
+                DiagnosticCode::SchemaMissingProperty => "Missing required property: '{property}'",
+                DiagnosticCode::SchemaInvalidType => "Invalid type for '{path}': expected {expected}, got {actual}",
+                DiagnosticCode::SchemaInvalidValue => "Invalid value for '{path}': must be one of the allowed values",
+                DiagnosticCode::SchemaTooShort => "'{path}' is too short: minimum length is {limit}",
+                DiagnosticCode::SchemaTooLong => "'{path}' is too long: maximum length is {limit}",
+                DiagnosticCode::SchemaPatternMismatch => "'{path}' does not match the required pattern",
+                DiagnosticCode::SchemaViolation => "Validation error at '{path}': {detail}",
+            },
+            Locale::Spanish => match self {
+                DiagnosticCode::NotAnObject => "La entrada DSL debe ser un objeto JSON",
+                DiagnosticCode::MissingAction => "La entrada DSL para {template} debe contener el campo 'action'",
+                DiagnosticCode::UnknownAction => "Acción desconocida '{action}' para {template}",
+                DiagnosticCode::MissingField => "{action} requiere el campo '{field}'",
+                DiagnosticCode::UnsupportedTemplate => "Tipo de plantilla no admitido: {template}:{version}",
+                DiagnosticCode::InvalidSchedule => "{action} tiene una programación no válida '{schedule}': {detail}",
+                DiagnosticCode::SchemaMissingProperty => "Falta la propiedad obligatoria: '{property}'",
+                DiagnosticCode::SchemaInvalidType => "Tipo no válido para '{path}': se esperaba {expected}, se recibió {actual}",
+                DiagnosticCode::SchemaInvalidValue => "Valor no válido para '{path}': debe ser uno de los valores permitidos",
+                DiagnosticCode::SchemaTooShort => "'{path}' es demasiado corto: la longitud mínima es {limit}",
+                DiagnosticCode::SchemaTooLong => "'{path}' es demasiado largo: la longitud máxima es {limit}",
+                DiagnosticCode::SchemaPatternMismatch => "'{path}' no coincide con el patrón requerido",
+                DiagnosticCode::SchemaViolation => "Error de validación en '{path}': {detail}",
+            },
+        }
+    }
+}
+
+/// One validation failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    /// Values substituted into the message
+    pub args: BTreeMap<String, String>,
+    /// JSON pointer to the offending value, for schema failures
+    pub path: Option<String>,
+}
+
+impl Diagnostic {
+    /// A diagnostic without arguments
+    pub fn new(code: DiagnosticCode) -> Self {
+        Self { code, args: BTreeMap::new(), path: None }
+    }
+
+    /// Add a message argument
+    pub fn arg(mut self, name: &str, value: impl ToString) -> Self {
+        self.args.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the path of the offending value
+    pub fn at(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// An action needs a field the input doesn't have
+    pub fn missing_field(action: &str, field: &str) -> Self {
+        Self::new(DiagnosticCode::MissingField).arg("action", action).arg("field", field)
+    }
+
+    /// The message in a locale
+    pub fn message(&self, locale: Locale) -> String {
+        let mut message = self.code.template(locale).to_string();
+        for (name, value) in &self.args {
+            message = message.replace(&format!("{{{}}}", name), value);
+        }
+        message
+    }
+
+    /// Machine-readable form: code, message in `locale`, arguments and path
+    pub fn to_json(&self, locale: Locale) -> JsonValue {
+        serde_json::json!({
+            "code": self.code.as_str(),
+            "message": self.message(locale),
+            "locale": locale.tag(),
+            "args": self.args,
+            "path": self.path,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(Locale::English))
+    }
+}
+
+/// Messages of several diagnostics in a locale, joined for display
+pub fn render_messages(diagnostics: &[Diagnostic], locale: &Locale) -> String {
+    diagnostics.iter()
+        .map(|d| d.message(*locale))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_render_per_locale() {
+        let diagnostic = Diagnostic::missing_field("mint_token", "recipient");
+        assert_eq!(diagnostic.message(Locale::English), "mint_token requires 'recipient' field");
+        assert_eq!(diagnostic.message(Locale::Spanish), "mint_token requiere el campo 'recipient'");
+
+        let json = diagnostic.to_json(Locale::Spanish);
+        assert_eq!(json["code"], "CCL-V004");
+        assert_eq!(json["locale"], "es");
+        assert_eq!(json["args"]["field"], "recipient");
+
+        assert_eq!(Locale::from_tag("es-MX"), Some(Locale::Spanish));
+        assert_eq!(Locale::from_tag("EN"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("fr"), None);
+    }
+}
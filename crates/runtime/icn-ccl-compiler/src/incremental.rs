@@ -0,0 +1,368 @@
+/*!
+# Incremental Compilation
+
+Recompiling a proposal after a small DSL edit usually leaves most of its module
+unchanged: the memory, type and import sections depend only on the action's host
+imports, and an edit to one parameter only touches the data section. The
+[`IncrementalCompiler`] splits compilation into stages, one per module section (plus
+input validation), and caches each stage's encoded output keyed by a SHA-256 hash of
+the inputs that stage reads. On the next compilation only stages whose inputs changed
+are recomputed; the module is then reassembled from cached and fresh sections.
+
+The `icn-ccl-metadata` section records the compilation time and is always
+recomputed. The module layout (data offsets, memory size, import indices) is cheap
+to derive and is recomputed every time, since every stage key is built from it.
+Optimization works on the assembled module and runs after every compilation that
+asks for it.
+
+Reused stages count towards [`CompilerMetrics::time_saved`], measured as the time the
+stage originally took to compile.
+*/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use icn_governance_kernel::config::GovernanceConfig;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    assemble_module, optimize_if_enabled, ActionPlugin, CclCompiler, CompilationOptions, CompilerError,
+    CompilerResult, EncodedSection, ModuleLayout, ModuleStage, OptimizationStats,
+};
+
+/// A unit of work the incremental compiler can skip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompileStage {
+    /// Validation of the DSL input against the configuration and schema
+    Validation,
+    /// Encoding of one module section
+    Section(ModuleStage),
+}
+
+/// What happened to a stage during a compilation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageOutcome {
+    /// The stage was recomputed, taking the given time
+    Compiled(Duration),
+    /// The stage's cached output was reused, saving the time it originally took
+    Reused(Duration),
+}
+
+/// Per-stage outcomes of one compilation
+#[derive(Debug, Clone, Default)]
+pub struct CompilationReport {
+    /// Outcome of every stage, in compilation order
+    pub stages: Vec<(CompileStage, StageOutcome)>,
+
+    /// Wall-clock time of the whole compilation
+    pub total_time: Duration,
+
+    /// What the optimizer did, if the module was optimized
+    pub optimization: Option<OptimizationStats>,
+}
+
+impl CompilationReport {
+    /// Stages that were recomputed
+    pub fn compiled(&self) -> Vec<CompileStage> {
+        self.stages.iter()
+            .filter(|(_, outcome)| matches!(outcome, StageOutcome::Compiled(_)))
+            .map(|(stage, _)| *stage)
+            .collect()
+    }
+
+    /// Stages whose cached output was reused
+    pub fn reused(&self) -> Vec<CompileStage> {
+        self.stages.iter()
+            .filter(|(_, outcome)| matches!(outcome, StageOutcome::Reused(_)))
+            .map(|(stage, _)| *stage)
+            .collect()
+    }
+
+    /// Compile time saved by reused stages
+    pub fn time_saved(&self) -> Duration {
+        self.stages.iter()
+            .filter_map(|(_, outcome)| match outcome {
+                StageOutcome::Reused(saved) => Some(*saved),
+                StageOutcome::Compiled(_) => None,
+            })
+            .sum()
+    }
+}
+
+/// Running totals over every compilation by an [`IncrementalCompiler`]
+#[derive(Debug, Clone, Default)]
+pub struct CompilerMetrics {
+    /// Number of successful compilations
+    pub compilations: u64,
+
+    /// Stages recomputed across all compilations
+    pub stages_compiled: u64,
+
+    /// Stages reused from the cache across all compilations
+    pub stages_reused: u64,
+
+    /// Wall-clock time spent compiling
+    pub compile_time: Duration,
+
+    /// Time reused stages originally took to compile
+    pub time_saved: Duration,
+
+    /// Report of the latest successful compilation
+    pub last_report: Option<CompilationReport>,
+}
+
+impl CompilerMetrics {
+    fn record(&mut self, report: CompilationReport) {
+        self.compilations += 1;
+        self.stages_compiled += report.compiled().len() as u64;
+        self.stages_reused += report.reused().len() as u64;
+        self.compile_time += report.total_time;
+        self.time_saved += report.time_saved();
+        self.last_report = Some(report);
+    }
+}
+
+/// Cached output of a stage
+#[derive(Debug, Clone)]
+struct CachedArtifact {
+    sections: Vec<EncodedSection>,
+    compile_time: Duration,
+}
+
+/// Content hash of a stage's inputs
+type StageKey = (CompileStage, [u8; 32]);
+
+/// Hash a stage's inputs; each part is length-prefixed so parts can't run together
+fn stage_hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+/// The inputs a section stage reads, flattened to bytes
+fn section_inputs(
+    stage: ModuleStage,
+    layout: &ModuleLayout,
+    ccl_config: &GovernanceConfig,
+    dsl_input: &JsonValue,
+) -> CompilerResult<Vec<Vec<u8>>> {
+    let inputs = match stage {
+        ModuleStage::Memory => vec![format!("{:?}", (layout.memory_limits.min_pages, layout.memory_limits.max_pages)).into_bytes()],
+        ModuleStage::Types | ModuleStage::Imports => vec![format!("{:?}", layout.extra_imports).into_bytes()],
+        ModuleStage::Functions => vec![],
+        ModuleStage::Exports => vec![layout.import_count.to_le_bytes().to_vec()],
+        ModuleStage::Data => layout.data_items.iter()
+            .flat_map(|(offset, bytes)| [offset.to_le_bytes().to_vec(), bytes.clone()])
+            .collect(),
+        ModuleStage::Code => {
+            let mut params: Vec<_> = layout.param_offsets.iter().collect();
+            params.sort();
+            let mut imports: Vec<_> = layout.import_indices.iter().collect();
+            imports.sort();
+            vec![
+                layout.action.as_bytes().to_vec(),
+                to_json(ccl_config)?,
+                to_json(dsl_input)?,
+                format!("{:?}", params).into_bytes(),
+                format!("{:?}", layout.memory.regions).into_bytes(),
+                format!("{:?}", imports).into_bytes(),
+            ]
+        }
+        ModuleStage::ActionSections => layout.action_sections.iter()
+            .flat_map(|(name, bytes)| [name.as_bytes().to_vec(), bytes.clone()])
+            .collect(),
+        ModuleStage::Metadata => unreachable!("metadata is never cached"),
+        ModuleStage::Sources => vec![to_json(ccl_config)?, to_json(dsl_input)?],
+    };
+    Ok(inputs)
+}
+
+/// Serialize a stage input
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> CompilerResult<Vec<u8>> {
+    serde_json::to_vec(value)
+        .map_err(|e| CompilerError::General(format!("Failed to serialize stage input: {}", e)))
+}
+
+/// A compiler that reuses unchanged stages from its previous compilation
+pub struct IncrementalCompiler {
+    compiler: CclCompiler,
+
+    /// Artifacts used by the latest compilation
+    cache: HashMap<StageKey, CachedArtifact>,
+
+    metrics: CompilerMetrics,
+}
+
+impl IncrementalCompiler {
+    /// Wrap a compiler with an empty cache
+    pub fn new(compiler: CclCompiler) -> Self {
+        Self {
+            compiler,
+            cache: HashMap::new(),
+            metrics: CompilerMetrics::default(),
+        }
+    }
+
+    /// Register a custom action. Cached artifacts may come from the action being
+    /// replaced, so the cache is cleared.
+    pub fn register_action(&mut self, plugin: Arc<dyn ActionPlugin>) {
+        self.compiler.register_action(plugin);
+        self.invalidate();
+    }
+
+    /// Drop every cached artifact
+    pub fn invalidate(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Number of cached artifacts
+    pub fn cached_artifacts(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Compilation metrics so far
+    pub fn metrics(&self) -> &CompilerMetrics {
+        &self.metrics
+    }
+
+    /// The wrapped compiler
+    pub fn compiler(&self) -> &CclCompiler {
+        &self.compiler
+    }
+
+    /// Compile a CCL configuration and DSL input into a WASM module, recomputing only
+    /// the stages whose inputs changed since the previous compilation. The module is
+    /// byte-for-byte what [`CclCompiler::compile_to_wasm`] produces.
+    pub fn compile_to_wasm(
+        &mut self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: Option<CompilationOptions>,
+    ) -> CompilerResult<Vec<u8>> {
+        let started = Instant::now();
+        let options = options.unwrap_or_default();
+
+        // Templated modules aren't built from sections and aren't cached
+        #[cfg(feature = "templating")]
+        if self.compiler.extract_action_from_dsl(dsl_input).ok().as_deref() == Some("use_template") {
+            return self.compiler.compile_to_wasm(ccl_config, dsl_input, Some(options));
+        }
+
+        // Nor are modules from other codegen backends
+        if options.backend.as_deref().is_some_and(|name| name != crate::backend::DEFAULT_BACKEND) {
+            return self.compiler.compile_to_wasm(ccl_config, dsl_input, Some(options));
+        }
+
+        let mut report = CompilationReport::default();
+        let mut used = HashMap::new();
+
+        // Validation
+        let validation_key = self.validation_key(ccl_config, dsl_input, &options)?;
+        match validation_key.and_then(|key| self.cache.get(&key).map(|artifact| (key, artifact.clone()))) {
+            Some((key, artifact)) => {
+                report.stages.push((CompileStage::Validation, StageOutcome::Reused(artifact.compile_time)));
+                used.insert(key, artifact);
+            }
+            None => {
+                let stage_started = Instant::now();
+                self.compiler.validate_input(ccl_config, dsl_input, &options)
+                    .map_err(|e| e.localized(options.locale))?;
+                let compile_time = stage_started.elapsed();
+                report.stages.push((CompileStage::Validation, StageOutcome::Compiled(compile_time)));
+                if let Some(key) = validation_key {
+                    used.insert(key, CachedArtifact { sections: Vec::new(), compile_time });
+                }
+            }
+        }
+
+        // Sections
+        let layout = self.compiler.layout_basic_module(ccl_config, dsl_input, &options)?;
+        let mut sections = Vec::new();
+        for stage in layout.stages(&options) {
+            let key = match stage {
+                ModuleStage::Metadata => None,
+                _ => {
+                    let inputs = section_inputs(stage, &layout, ccl_config, dsl_input)?;
+                    let parts: Vec<&[u8]> = inputs.iter().map(Vec::as_slice).collect();
+                    Some((CompileStage::Section(stage), stage_hash(&parts)))
+                }
+            };
+
+            match key.and_then(|key| self.cache.get(&key).map(|artifact| (key, artifact.clone()))) {
+                Some((key, artifact)) => {
+                    report.stages.push((CompileStage::Section(stage), StageOutcome::Reused(artifact.compile_time)));
+                    sections.extend(artifact.sections.iter().cloned());
+                    used.insert(key, artifact);
+                }
+                None => {
+                    let stage_started = Instant::now();
+                    let encoded = self.compiler.encode_stage(stage, &layout, ccl_config, dsl_input, &options)?;
+                    let compile_time = stage_started.elapsed();
+                    report.stages.push((CompileStage::Section(stage), StageOutcome::Compiled(compile_time)));
+                    sections.extend(encoded.iter().cloned());
+                    if let Some(key) = key {
+                        used.insert(key, CachedArtifact { sections: encoded, compile_time });
+                    }
+                }
+            }
+        }
+
+        // The optimizer runs over the whole module, so it isn't cached
+        let (module, optimization) = optimize_if_enabled(assemble_module(&sections), &options)?;
+        report.optimization = optimization;
+
+        // Keep only what this compilation used, so the cache tracks the latest input
+        self.cache = used;
+        report.total_time = started.elapsed();
+        tracing::debug!(
+            "Incremental compilation reused {} of {} stages, saving {:?}",
+            report.reused().len(), report.stages.len(), report.time_saved()
+        );
+        self.metrics.record(report);
+
+        Ok(module)
+    }
+
+    /// Key of the validation stage, or `None` if its inputs can't be read
+    fn validation_key(
+        &self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<Option<StageKey>> {
+        // A custom schema is part of the input; if it can't be read, validation runs
+        // and reports the error
+        let schema = match (&options.schema_path, options.validate_schema) {
+            (Some(path), true) => match std::fs::read(path) {
+                Ok(contents) => contents,
+                Err(_) => return Ok(None),
+            },
+            _ => Vec::new(),
+        };
+
+        let hash = stage_hash(&[
+            &to_json(ccl_config)?,
+            &to_json(dsl_input)?,
+            &[options.validate_schema as u8],
+            &schema,
+        ]);
+        Ok(Some((CompileStage::Validation, hash)))
+    }
+}
+
+impl From<CclCompiler> for IncrementalCompiler {
+    fn from(compiler: CclCompiler) -> Self {
+        Self::new(compiler)
+    }
+}
+
+impl Default for IncrementalCompiler {
+    fn default() -> Self {
+        Self::new(CclCompiler::new())
+    }
+}
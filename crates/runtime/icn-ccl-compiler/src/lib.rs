@@ -17,6 +17,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 use wasm_encoder::{
     CodeSection, EntityType, ExportSection, FunctionSection, ImportSection, Module, TypeSection,
@@ -30,6 +31,50 @@ pub use icn_governance_kernel::config;
 mod schema;
 pub use schema::SchemaManager;
 
+// Localized validation diagnostics
+pub mod diagnostics;
+pub use diagnostics::{Diagnostic, DiagnosticCode, Locale};
+
+// Action plugins
+pub mod actions;
+pub use actions::{ActionContext, ActionPlugin, ActionRegistry, HostImport, RecurringSchedule, TallyInput, TALLY_INPUT_INVALID};
+
+// Linear memory layout of generated modules
+pub mod memory_layout;
+pub use memory_layout::{MemoryLayout, MemoryLayoutPlanner, MemoryRegion, RegionKind};
+
+// Optimization passes over generated modules
+pub mod optimize;
+pub use optimize::{OptimizationConfig, OptimizationStats};
+
+// Reading compiled modules back for audits
+pub mod decompile;
+pub use decompile::{DecompiledAction, DecompiledModule, ImportedFunction};
+
+// Reproducible builds
+pub mod reproducible;
+pub use reproducible::ReproducibilityReport;
+
+// Pluggable module generation
+pub mod backend;
+pub use backend::{BackendRegistry, CodegenBackend};
+
+// Many modules compiled in parallel
+pub mod batch;
+pub use batch::{CompileJob, CompileResult};
+
+// Kernel compilation hook
+mod proposal_compiler;
+pub use proposal_compiler::CclProposalCompiler;
+
+// Incremental recompilation
+pub mod incremental;
+pub use incremental::{CompilerMetrics, IncrementalCompiler};
+
+// Experimental component-model backend
+#[cfg(feature = "component-model")]
+pub mod component;
+
 // Integration tests
 #[cfg(test)]
 mod tests;
@@ -57,6 +102,14 @@ pub struct MetadataInfo {
     
     /// Additional metadata fields
     pub additional_data: HashMap<String, String>,
+
+    /// Where the module's data and buffers sit in linear memory
+    #[serde(default)]
+    pub memory_layout: Option<MemoryLayout>,
+
+    /// SHA-256 of each embedded source section, keyed by section name
+    #[serde(default)]
+    pub source_hashes: HashMap<String, String>,
 }
 
 /// Errors that can occur during CCL compilation
@@ -82,6 +135,24 @@ pub enum CompilerError {
     #[error("Schema validation error: {0}")]
     SchemaError(String),
 
+    /// The DSL input failed validation; displayed in `locale`
+    #[error("DSL validation failed: {}", diagnostics::render_messages(.diagnostics, .locale))]
+    Invalid {
+        diagnostics: Vec<Diagnostic>,
+        locale: Locale,
+    },
+
+    /// Data segments don't fit within the maximum memory size
+    #[error("Data segments need {required_pages} memory pages but at most {max_pages} are allowed")]
+    MemoryBudgetExceeded {
+        required_pages: u32,
+        max_pages: u32,
+    },
+
+    /// A compiled module couldn't be read back
+    #[error("Decompilation error: {0}")]
+    DecompileError(String),
+
     /// General compilation error
     #[error("Compilation error: {0}")]
     General(String),
@@ -99,6 +170,42 @@ impl From<anyhow::Error> for CompilerError {
     }
 }
 
+impl CompilerError {
+    /// A validation failure with a single diagnostic
+    pub fn invalid(diagnostic: Diagnostic) -> Self {
+        CompilerError::Invalid { diagnostics: vec![diagnostic], locale: Locale::default() }
+    }
+
+    /// The structured diagnostics of a validation failure; empty for other errors
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            CompilerError::Invalid { diagnostics, .. } => diagnostics,
+            _ => &[],
+        }
+    }
+
+    /// Display a validation failure in `locale`; other errors are unchanged
+    pub fn localized(self, locale: Locale) -> Self {
+        match self {
+            CompilerError::Invalid { diagnostics, .. } => CompilerError::Invalid { diagnostics, locale },
+            other => other,
+        }
+    }
+
+    /// Machine-readable form of the error, with each diagnostic's code, arguments and
+    /// message for front-ends
+    pub fn to_json(&self) -> JsonValue {
+        let locale = match self {
+            CompilerError::Invalid { locale, .. } => *locale,
+            _ => Locale::default(),
+        };
+        serde_json::json!({
+            "error": self.to_string(),
+            "diagnostics": self.diagnostics().iter().map(|d| d.to_json(locale)).collect::<Vec<_>>(),
+        })
+    }
+}
+
 /// Result type for compiler operations
 pub type CompilerResult<T> = Result<T, CompilerError>;
 
@@ -110,6 +217,21 @@ pub struct CompilationOptions {
     
     /// Whether to optimize the generated WASM
     pub optimize: bool,
+
+    /// Which optimization passes run when `optimize` is set
+    #[serde(default)]
+    pub optimization: OptimizationConfig,
+
+    /// Whether to produce the same bytes every time the same CCL config and DSL
+    /// input are compiled. The compilation timestamp is taken from the DSL input's
+    /// `timestamp` field instead of the clock.
+    #[serde(default)]
+    pub deterministic: bool,
+
+    /// Name of the codegen backend to generate the module with; the built-in
+    /// `wasm_encoder` backend if None
+    #[serde(default)]
+    pub backend: Option<String>,
     
     /// Memory limits in pages (64KB per page)
     pub memory_limits: Option<MemoryLimits>,
@@ -128,6 +250,10 @@ pub struct CompilationOptions {
     
     /// Whether to validate DSL input against schema
     pub validate_schema: bool,
+    
+    /// Locale validation errors are displayed in
+    #[serde(default)]
+    pub locale: Locale,
 }
 
 impl Default for CompilationOptions {
@@ -135,12 +261,16 @@ impl Default for CompilationOptions {
         Self {
             include_debug_info: false,
             optimize: true,
+            optimization: OptimizationConfig::default(),
+            deterministic: false,
+            backend: None,
             memory_limits: Some(MemoryLimits::default()),
             additional_metadata: None,
             caller_did: None,
             execution_id: None,
             schema_path: None,
             validate_schema: true,
+            locale: Locale::default(),
         }
     }
 }
@@ -164,11 +294,168 @@ impl Default for MemoryLimits {
     }
 }
 
+/// WASM page size in bytes
+const WASM_PAGE_SIZE: usize = 65_536;
+
+/// Raise the minimum memory size so `footprint` bytes of data fit, without going past
+/// the maximum
+fn fit_memory_limits(limits: &MemoryLimits, footprint: usize) -> CompilerResult<MemoryLimits> {
+    let required = footprint.div_ceil(WASM_PAGE_SIZE);
+    let required_pages = u32::try_from(required).unwrap_or(u32::MAX);
+
+    if let Some(max_pages) = limits.max_pages {
+        if required_pages > max_pages {
+            return Err(CompilerError::MemoryBudgetExceeded { required_pages, max_pages });
+        }
+    }
+
+    Ok(MemoryLimits {
+        min_pages: limits.min_pages.max(required_pages),
+        max_pages: limits.max_pages,
+    })
+}
+
+/// Stages that each contribute sections to a basic module, in section order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ModuleStage {
+    Memory,
+    Types,
+    Imports,
+    Functions,
+    Exports,
+    Data,
+    Code,
+    /// Custom sections the action embeds, such as `icn-schedule`
+    ActionSections,
+    /// The `icn-ccl-metadata` custom section
+    Metadata,
+    /// The `icn-ccl-config` and `icn-dsl-input` custom sections
+    Sources,
+}
+
+/// A section as it appears in an encoded module: its ID and its length-prefixed contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EncodedSection {
+    pub id: u8,
+    pub bytes: Vec<u8>,
+}
+
+impl EncodedSection {
+    fn of(section: &impl wasm_encoder::Section) -> Self {
+        let mut bytes = Vec::new();
+        section.encode(&mut bytes);
+        Self { id: section.id(), bytes }
+    }
+}
+
+/// Assemble a module from its encoded sections, in order
+pub(crate) fn assemble_module(sections: &[EncodedSection]) -> Vec<u8> {
+    let mut module = Module::new().finish();
+    for section in sections {
+        module.push(section.id);
+        module.extend_from_slice(&section.bytes);
+    }
+    module
+}
+
+/// Everything about a basic module that is decided before any section is encoded
+pub(crate) struct ModuleLayout {
+    pub(crate) action: String,
+    pub(crate) plugin: Arc<dyn ActionPlugin>,
+    /// Data segments as (offset, bytes)
+    pub(crate) data_items: Vec<(usize, Vec<u8>)>,
+    /// Every region of linear memory the module uses
+    pub(crate) memory: MemoryLayout,
+    /// Where each parameter was placed, as (offset, length)
+    pub(crate) param_offsets: HashMap<String, (usize, usize)>,
+    pub(crate) memory_limits: MemoryLimits,
+    /// The action's host imports beyond the base imports
+    pub(crate) extra_imports: Vec<actions::HostImport>,
+    /// Function index of every import, keyed `module::name`
+    pub(crate) import_indices: HashMap<String, u32>,
+    pub(crate) import_count: u32,
+    /// Custom sections the action embeds, as (name, contents)
+    pub(crate) action_sections: Vec<(String, Vec<u8>)>,
+}
+
+impl ModuleLayout {
+    /// The stages that make up the module, in section order
+    pub(crate) fn stages(&self, options: &CompilationOptions) -> Vec<ModuleStage> {
+        let mut stages = vec![
+            ModuleStage::Memory,
+            ModuleStage::Types,
+            ModuleStage::Imports,
+            ModuleStage::Functions,
+            ModuleStage::Exports,
+            ModuleStage::Data,
+            ModuleStage::Code,
+        ];
+        if !self.action_sections.is_empty() {
+            stages.push(ModuleStage::ActionSections);
+        }
+        if options.include_debug_info {
+            stages.push(ModuleStage::Metadata);
+            stages.push(ModuleStage::Sources);
+        }
+        stages
+    }
+}
+
+/// An action's host imports beyond the base imports
+pub(crate) fn extra_imports(plugin: &dyn ActionPlugin) -> Vec<actions::HostImport> {
+    plugin.imports().into_iter()
+        .filter(|import| import.module != actions::BASE_IMPORT_MODULE || !actions::BASE_IMPORTS.contains(&import.name.as_str()))
+        .collect()
+}
+
+/// Function index of every import, keyed `module::name`, with the base imports first
+pub(crate) fn import_indices(extra_imports: &[actions::HostImport]) -> HashMap<String, u32> {
+    let mut import_indices: HashMap<String, u32> = actions::BASE_IMPORTS.iter()
+        .enumerate()
+        .map(|(i, name)| (format!("{}::{}", actions::BASE_IMPORT_MODULE, name), i as u32))
+        .collect();
+    for (i, import) in extra_imports.iter().enumerate() {
+        import_indices.insert(format!("{}::{}", import.module, import.name), (actions::BASE_IMPORTS.len() + i) as u32);
+    }
+    import_indices
+}
+
+/// The CCL config and DSL input custom sections, as (name, contents)
+fn source_sections(ccl_config: &GovernanceConfig, dsl_input: &JsonValue) -> CompilerResult<Vec<(&'static str, Vec<u8>)>> {
+    let ccl_json = reproducible::canonical_json(ccl_config)
+        .map_err(|e| CompilerError::General(format!("Failed to serialize CCL config: {}", e)))?;
+    let dsl_json = reproducible::canonical_json(dsl_input)
+        .map_err(|e| CompilerError::General(format!("Failed to serialize DSL input: {}", e)))?;
+    Ok(vec![(decompile::CONFIG_SECTION, ccl_json), (decompile::DSL_SECTION, dsl_json)])
+}
+
+/// Run the optimization pipeline over a module if the options ask for it
+pub(crate) fn optimize_if_enabled(
+    wasm_bytes: Vec<u8>,
+    options: &CompilationOptions,
+) -> CompilerResult<(Vec<u8>, Option<OptimizationStats>)> {
+    if !options.optimize {
+        return Ok((wasm_bytes, None));
+    }
+    let (optimized, stats) = optimize::optimize_module(&wasm_bytes, &options.optimization)?;
+    tracing::debug!("Optimized module from {} to {} bytes", stats.size_before, stats.size_after);
+    Ok((optimized, Some(stats)))
+}
+
 /// Main compiler interface
 #[derive(Default)]
 pub struct CclCompiler {
     /// Schema manager for validating DSL inputs
     schema_manager: Option<SchemaManager>,
+    
+    /// Actions the compiler can generate modules for
+    actions: ActionRegistry,
+
+    /// What the optimizer did in the latest compilation
+    last_optimization: Option<OptimizationStats>,
+
+    /// Codegen backends besides the built-in one
+    backends: BackendRegistry,
 }
 
 impl CclCompiler {
@@ -176,6 +463,9 @@ impl CclCompiler {
     pub fn new() -> Self {
         Self {
             schema_manager: Some(SchemaManager::new()),
+            actions: ActionRegistry::default(),
+            last_optimization: None,
+            backends: BackendRegistry::default(),
         }
     }
     
@@ -183,8 +473,27 @@ impl CclCompiler {
     pub fn with_schema_dir<P: AsRef<Path>>(schema_dir: P) -> Self {
         Self {
             schema_manager: Some(SchemaManager::with_schema_dir(schema_dir)),
+            actions: ActionRegistry::default(),
+            last_optimization: None,
+            backends: BackendRegistry::default(),
         }
     }
+    
+    /// Register a custom action, replacing any action of the same name
+    pub fn register_action(&mut self, plugin: Arc<dyn ActionPlugin>) {
+        self.actions.register(plugin);
+    }
+    
+    /// The actions this compiler can generate modules for
+    pub fn actions(&self) -> &ActionRegistry {
+        &self.actions
+    }
+
+    /// Module sizes before and after optimization in the latest compilation, and what
+    /// each pass removed; None if it wasn't optimized
+    pub fn last_optimization_stats(&self) -> Option<&OptimizationStats> {
+        self.last_optimization.as_ref()
+    }
 
     /// Compile a CCL configuration and DSL input into a WASM module
     ///
@@ -204,6 +513,28 @@ impl CclCompiler {
         // Use default options if none provided
         let options = options.unwrap_or_default();
         
+        self.validate_input(ccl_config, dsl_input, &options)
+            .map_err(|e| e.localized(options.locale))?;
+
+        // Generate WASM using the appropriate backend
+        let (wasm_bytes, core_module) = self.generate_with_backend(ccl_config, dsl_input, &options)?;
+        if !core_module {
+            self.last_optimization = None;
+            return Ok(wasm_bytes);
+        }
+
+        let (wasm_bytes, stats) = optimize_if_enabled(wasm_bytes, &options)?;
+        self.last_optimization = stats;
+        Ok(wasm_bytes)
+    }
+    
+    /// Validate a DSL input for a CCL configuration, against its JSON schema if enabled
+    fn validate_input(
+        &mut self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<()> {
         // Extract template type and action
         let template_type = &ccl_config.template_type;
         let action = match self.extract_action_from_dsl(dsl_input) {
@@ -224,69 +555,42 @@ impl CclCompiler {
             self.validate_dsl_for_template(ccl_config, dsl_input, true)?;
         }
 
-        // Generate WASM using the appropriate backend
-        let wasm_bytes = self.generate_wasm_module(ccl_config, dsl_input, &options)?;
-
-        Ok(wasm_bytes)
+        Ok(())
     }
     
     /// Validate DSL input against a JSON schema
     fn validate_against_schema(
-        &mut self, 
+        &self, 
         template_type: &str, 
         action: &str, 
         dsl_input: &JsonValue,
         custom_schema_path: Option<&Path>
     ) -> CompilerResult<()> {
-        // If we have a custom schema path, load and validate directly
+        // If we have a custom schema path, validate against it, compiled once and cached
         if let Some(schema_path) = custom_schema_path {
-            if let Some(_schema_manager) = &mut self.schema_manager {
-                // Load and compile the schema
-                let schema_content = std::fs::read_to_string(schema_path)
-                    .map_err(|e| CompilerError::SchemaError(format!(
-                        "Failed to read schema file {}: {}", schema_path.display(), e
-                    )))?;
-                
-                // Parse the schema
-                let schema_value: JsonValue = serde_json::from_str(&schema_content)
-                    .map_err(|e| CompilerError::SchemaError(format!(
-                        "Failed to parse schema file {}: {}", schema_path.display(), e
-                    )))?;
-                
-                // Compile the schema
-                let schema = jsonschema::JSONSchema::compile(&schema_value)
-                    .map_err(|e| CompilerError::SchemaError(format!(
-                        "Failed to compile schema from {}: {}", schema_path.display(), e
-                    )))?;
-                
-                // Validate the DSL input
-                let validation_result = schema.validate(dsl_input);
-                if let Err(errors) = validation_result {
-                    // Format validation errors
-                    let error_messages: Vec<String> = errors
-                        .into_iter()
-                        .map(|err| schema::format_validation_error(&err, dsl_input))
-                        .collect();
-                    
-                    return Err(CompilerError::SchemaError(format!(
-                        "DSL validation failed: {}",
-                        error_messages.join("; ")
-                    )));
-                }
-                
-                return Ok(());
+            if let Some(schema_manager) = &self.schema_manager {
+                return schema_manager.validate_dsl_with_schema_file(schema_path, dsl_input)
+                    .map_err(|e| match e {
+                        CompilerError::Invalid { .. } => e,
+                        CompilerError::ValidationError(msg) => CompilerError::SchemaError(msg),
+                        other => other,
+                    });
             }
         }
         
         // Otherwise use the schema manager
-        if let Some(schema_manager) = &mut self.schema_manager {
+        if let Some(schema_manager) = &self.schema_manager {
             match schema_manager.validate_dsl_for_action(action, dsl_input) {
                 Ok(()) => return Ok(()),
                 Err(CompilerError::ValidationError(msg)) if msg.contains("No schema registered") || msg.contains("Schema file not found") => {
                     // Fall back to template validation
                     schema_manager.validate_dsl_for_template(template_type, dsl_input)
-                        .map_err(|e| CompilerError::SchemaError(e.to_string()))?;
+                        .map_err(|e| match e {
+                            CompilerError::Invalid { .. } => e,
+                            other => CompilerError::SchemaError(other.to_string()),
+                        })?;
                 }
+                Err(e @ CompilerError::Invalid { .. }) => return Err(e),
                 Err(e) => return Err(CompilerError::SchemaError(e.to_string())),
             }
         } else {
@@ -313,17 +617,15 @@ impl CclCompiler {
             "coop_bylaws" => {
                 // For cooperative bylaws, we expect specific fields in the DSL input
                 if !dsl_input.is_object() {
-                    return Err(CompilerError::DslError(
-                        "DSL input must be a JSON object".to_string(),
-                    ));
+                    return Err(CompilerError::invalid(Diagnostic::new(DiagnosticCode::NotAnObject)));
                 }
 
                 let dsl_obj = dsl_input.as_object().unwrap();
 
                 // Check for required fields based on template type
                 if !dsl_obj.contains_key("action") {
-                    return Err(CompilerError::DslError(
-                        "DSL input for cooperative bylaws must contain 'action' field".to_string(),
+                    return Err(CompilerError::invalid(
+                        Diagnostic::new(DiagnosticCode::MissingAction).arg("template", template_type),
                     ));
                 }
 
@@ -332,114 +634,31 @@ impl CclCompiler {
                     return Ok(());
                 }
 
-                // Specific checks based on action type
+                // Action-specific checks come from the action's plugin
                 let action = dsl_obj.get("action").unwrap().as_str().unwrap_or("");
-                match action {
-                    "propose_membership" => {
-                        if !dsl_obj.contains_key("applicant_did") {
-                            return Err(CompilerError::DslError(
-                                "Membership proposal requires 'applicant_did' field".to_string(),
-                            ));
-                        }
-                    }
-                    "propose_budget" => {
-                        if !dsl_obj.contains_key("amount") || !dsl_obj.contains_key("category") {
-                            return Err(CompilerError::DslError(
-                                "Budget proposal requires 'amount' and 'category' fields"
-                                    .to_string(),
-                            ));
-                        }
-                    }
-                    "log_caller_info" => {
-                        // This action doesn't require additional fields
-                    }
-                    "perform_metered_action" => {
-                        if !dsl_obj.contains_key("resource_type") {
-                            return Err(CompilerError::DslError(
-                                "perform_metered_action requires 'resource_type' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("amount") {
-                            return Err(CompilerError::DslError(
-                                "perform_metered_action requires 'amount' field".to_string(),
-                            ));
-                        }
-                    }
-                    "anchor_data" => {
-                        if !dsl_obj.contains_key("key") {
-                            return Err(CompilerError::DslError(
-                                "anchor_data requires 'key' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("value") {
-                            return Err(CompilerError::DslError(
-                                "anchor_data requires 'value' field".to_string(),
-                            ));
-                        }
-                        // parents is optional, so no validation needed
-                    }
-                    "mint_token" => {
-                        if !dsl_obj.contains_key("resource_type") {
-                            return Err(CompilerError::DslError(
-                                "mint_token requires 'resource_type' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("recipient") {
-                            return Err(CompilerError::DslError(
-                                "mint_token requires 'recipient' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("amount") {
-                            return Err(CompilerError::DslError(
-                                "mint_token requires 'amount' field".to_string(),
-                            ));
-                        }
-                    }
-                    "transfer_resource" => {
-                        if !dsl_obj.contains_key("from") {
-                            return Err(CompilerError::DslError(
-                                "transfer_resource requires 'from' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("to") {
-                            return Err(CompilerError::DslError(
-                                "transfer_resource requires 'to' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("amount") {
-                            return Err(CompilerError::DslError(
-                                "transfer_resource requires 'amount' field".to_string(),
-                            ));
-                        }
-                        if !dsl_obj.contains_key("resource_type") {
-                            return Err(CompilerError::DslError(
-                                "transfer_resource requires 'resource_type' field".to_string(),
-                            ));
-                        }
-                    }
-                    // Add more action-specific validations as needed
+                match self.actions.get(action) {
+                    Some(plugin) if plugin.supports_template(template_type) => plugin.validate(dsl_obj)?,
                     _ => {
-                        return Err(CompilerError::DslError(format!(
-                            "Unknown action '{}' for cooperative bylaws",
-                            action
-                        )));
+                        return Err(CompilerError::invalid(
+                            Diagnostic::new(DiagnosticCode::UnknownAction)
+                                .arg("action", action)
+                                .arg("template", template_type),
+                        ));
                     }
                 }
             }
             "community_charter" => {
                 // Similar validations for community charter
                 if !dsl_input.is_object() {
-                    return Err(CompilerError::DslError(
-                        "DSL input must be a JSON object".to_string(),
-                    ));
+                    return Err(CompilerError::invalid(Diagnostic::new(DiagnosticCode::NotAnObject)));
                 }
 
                 let dsl_obj = dsl_input.as_object().unwrap();
 
                 // Check for required fields based on template type
                 if !dsl_obj.contains_key("action") {
-                    return Err(CompilerError::DslError(
-                        "DSL input for community charter must contain 'action' field".to_string(),
+                    return Err(CompilerError::invalid(
+                        Diagnostic::new(DiagnosticCode::MissingAction).arg("template", template_type),
                     ));
                 }
 
@@ -448,44 +667,44 @@ impl CclCompiler {
                     return Ok(());
                 }
 
-                // Specific checks based on action type
+                // Action-specific checks come from the action's plugin
                 let action = dsl_obj.get("action").unwrap().as_str().unwrap_or("");
-                match action {
-                    "log_caller_info" => {
-                        // This action doesn't require additional fields
-                    }
+                match self.actions.get(action) {
+                    Some(plugin) if plugin.supports_template(template_type) => plugin.validate(dsl_obj)?,
                     _ => {
-                        return Err(CompilerError::DslError(format!(
-                            "Unknown action '{}' for community charter",
-                            action
-                        )));
+                        return Err(CompilerError::invalid(
+                            Diagnostic::new(DiagnosticCode::UnknownAction)
+                                .arg("action", action)
+                                .arg("template", template_type),
+                        ));
                     }
                 }
             }
             "budget_proposal" => {
                 // Validations for budget proposals
                 if !dsl_input.is_object() {
-                    return Err(CompilerError::DslError(
-                        "DSL input must be a JSON object".to_string(),
-                    ));
+                    return Err(CompilerError::invalid(Diagnostic::new(DiagnosticCode::NotAnObject)));
                 }
 
                 let dsl_obj = dsl_input.as_object().unwrap();
 
                 // Check for required fields
-                if !dsl_obj.contains_key("amount") || !dsl_obj.contains_key("purpose") {
-                    return Err(CompilerError::DslError(
-                        "Budget proposal requires 'amount' and 'purpose' fields".to_string(),
-                    ));
+                let missing: Vec<Diagnostic> = ["amount", "purpose"].iter()
+                    .filter(|field| !dsl_obj.contains_key(**field))
+                    .map(|field| Diagnostic::missing_field(template_type, field))
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(CompilerError::Invalid { diagnostics: missing, locale: Locale::default() });
                 }
             }
             // Add more template type validations as needed
             _ => {
                 if !skip_strict_validation {
-                    return Err(CompilerError::ValidationError(format!(
-                        "Unsupported template type: {}:{}",
-                        template_type, template_version
-                    )));
+                    return Err(CompilerError::invalid(
+                        Diagnostic::new(DiagnosticCode::UnsupportedTemplate)
+                            .arg("template", template_type)
+                            .arg("version", template_version),
+                    ));
                 }
             }
         }
@@ -515,11 +734,15 @@ impl CclCompiler {
         // Extract the action from DSL input
         let action = self.extract_action_from_dsl(dsl_input).unwrap_or_else(|_| "unknown".to_string());
         
-        // Get current timestamp
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| CompilerError::General(format!("Failed to get system time: {}", e)))?
-            .as_secs() as i64;
+        // Get current timestamp, or the DSL input's when the build must be reproducible
+        let timestamp = if options.deterministic {
+            reproducible::dsl_timestamp(dsl_input)?
+        } else {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_err(|e| CompilerError::General(format!("Failed to get system time: {}", e)))?
+                .as_secs() as i64
+        };
         
         // Create additional data hashmap
         let mut additional_data = options.additional_metadata.clone().unwrap_or_default();
@@ -545,6 +768,8 @@ impl CclCompiler {
             compilation_timestamp: timestamp,
             execution_id: options.execution_id.clone(),
             additional_data,
+            memory_layout: None,
+            source_hashes: HashMap::new(),
         };
         
         Ok(metadata)
@@ -565,7 +790,7 @@ impl CclCompiler {
             #[cfg(feature = "templating")]
             "use_template" => {
                 // If the action is "use_template", use the template approach
-                return self.generate_templated_wasm(ccl_config, dsl_input, options);
+                return backend::RustTemplateBackend.generate(ccl_config, dsl_input, options);
             }
             // For all other actions, generate bytecode directly
             _ => {
@@ -584,759 +809,302 @@ impl CclCompiler {
         dsl_input: &JsonValue,
         options: &CompilationOptions,
     ) -> CompilerResult<Vec<u8>> {
-        // Extract action and parameters
-        let action = self.extract_action_from_dsl(dsl_input)?;
-        
-        // Create a new WASM module with basic host imports
-        let mut module = Module::new();
-        
-        // Define memory section with default limits
-        let memory_limits = options.memory_limits.as_ref().unwrap_or(&MemoryLimits::default());
-        let memory = wasm_encoder::MemorySection::new().entry(
-            wasm_encoder::MemoryType {
-                minimum: memory_limits.min_pages,
-                maximum: memory_limits.max_pages,
-                memory64: false,
-                shared: false,
-            }
-        );
-        
-        // Add memory section to module
-        module.section(&memory);
-        
-        // Define type section (function signatures)
-        let mut types = TypeSection::new();
+        let layout = self.layout_basic_module(ccl_config, dsl_input, options)?;
         
-        // Type 0: () -> () for _start function
-        types.function(vec![], vec![]);
-        
-        // Type 1: (i32, i32) -> i32 for host_log_message function
-        types.function(vec![ValType::I32, ValType::I32, ValType::I32], vec![]);
-        
-        // Type 2: (i32, i32) -> i32 for invoke function (our main entry point)
-        types.function(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
-        
-        // Type 3: (i32, i32, i32, i32) -> i32 for host_storage_get
-        types.function(
-            vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32],
-            vec![ValType::I32],
-        );
-        
-        // Type 4: (i32, i32, i32, i32) -> i32 for host_storage_put
-        types.function(
-            vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32],
-            vec![ValType::I32],
-        );
-        
-        // Type 5: (i32, i32) -> i32 for host_get_caller_did
-        types.function(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
-        
-        // Type 6: () -> i32 for host_get_caller_scope
-        types.function(vec![], vec![ValType::I32]);
-        
-        // Type 7: (i32, i32) -> i32 for host_check_resource_authorization
-        types.function(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
-        
-        // Type 8: (i32, i32) -> () for host_record_resource_usage
-        types.function(vec![ValType::I32, ValType::I32], vec![]);
-        
-        // Type 9: (i32, i32, i32, i32, i32, i32) -> i32 for host_anchor_to_dag
-        types.function(
-            vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32],
-            vec![ValType::I32],
-        );
-        
-        // Type 10: (i32, i32, i32, i32) -> i32 for host_mint_token
-        types.function(
-            vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32], 
-            vec![ValType::I32]
-        );
-        
-        // Type 11: (i32, i32, i32, i32, i32, i32) -> i32 for host_transfer_resource
-        types.function(
-            vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32],
-            vec![ValType::I32],
-        );
-        
-        // Add the type section to the module
-        module.section(&types);
-        
-        // Define import section (host functions we'll use)
-        let mut imports = ImportSection::new();
-        
-        // Import host_log_message from env
-        imports.import(
-            "env",
-            "host_log_message",
-            EntityType::Function(1), // Using type index 1 (log message function)
-        );
-        
-        // Import host_storage_get from env
-        imports.import("env", "host_storage_get", EntityType::Function(3));
-        
-        // Import host_storage_put from env
-        imports.import("env", "host_storage_put", EntityType::Function(4));
-        
-        // Import host_get_caller_did from env
-        imports.import("env", "host_get_caller_did", EntityType::Function(5));
-        
-        // Import host_get_caller_scope from env
-        imports.import("env", "host_get_caller_scope", EntityType::Function(6));
-        
-        // Import host_check_resource_authorization from env
-        imports.import("env", "host_check_resource_authorization", EntityType::Function(7));
-        
-        // Import host_record_resource_usage from env
-        imports.import("env", "host_record_resource_usage", EntityType::Function(8));
-        
-        // Import host_anchor_to_dag from env
-        imports.import("env", "host_anchor_to_dag", EntityType::Function(9));
-        
-        // Import host_mint_token from env
-        imports.import("env", "host_mint_token", EntityType::Function(10));
-        
-        // Import host_transfer_resource from env
-        imports.import("env", "host_transfer_resource", EntityType::Function(11));
-        
-        // Add import section to module
-        module.section(&imports);
-        
-        // Define function section (indices of our functions' signatures)
-        let mut functions = FunctionSection::new();
-        
-        // Function 10: _start function (type 0)
-        functions.function(0);
-        
-        // Function 11: invoke function (type 2)
-        functions.function(2);
-        
-        // Add function section to module
-        module.section(&functions);
-        
-        // Define export section (functions we export)
-        let mut exports = ExportSection::new();
-        
-        // Export memory
-        exports.export("memory", wasm_encoder::ExportKind::Memory, 0);
-        
-        // Export _start function
-        exports.export("_start", wasm_encoder::ExportKind::Func, 10);
-        
-        // Export invoke function
-        exports.export("invoke", wasm_encoder::ExportKind::Func, 11);
-        
-        // Add export section to module
-        module.section(&exports);
+        let mut sections = Vec::new();
+        for stage in layout.stages(options) {
+            sections.extend(self.encode_stage(stage, &layout, ccl_config, dsl_input, options)?);
+        }
         
-        // Extract parameters we'll need for data section
-        let mut data_items = vec![];
+        // Return the compiled WASM module
+        Ok(assemble_module(&sections))
+    }
+    
+    /// Lay out a basic module: its data, memory size and host imports
+    pub(crate) fn layout_basic_module(
+        &self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<ModuleLayout> {
+        // Extract action and parameters
+        let action = self.extract_action_from_dsl(dsl_input)?;
         
-        // Some common messages in our data section
-        let mut data_offset = 0;
+        // The action's plugin decides which parameters go in the data section
+        let plugin = self.actions.get(&action)
+            .unwrap_or_else(|| Arc::new(actions::DefaultAction));
         
-        // Add a debugging message
+        // Give every message, parameter and buffer a region sized to fit it
+        let mut planner = MemoryLayoutPlanner::new();
         let debug_msg = format!("Executing {} for template {}", action, ccl_config.template_type);
-        data_items.push((data_offset, debug_msg.into_bytes()));
-        data_offset += debug_msg.len();
-        
-        // Memory layout:
-        // 0 - 1000: Debug and status messages
-        // 1000 - 2000: Input parameters
-        // 2000 - 3000: Result buffers
-        // 4000+: Dynamic memory allocation
+        planner.place(RegionKind::Message, memory_layout::DEBUG_MESSAGE, debug_msg.into_bytes())?;
+        planner.place(RegionKind::Message, memory_layout::SUCCESS_MESSAGE, b"Operation completed successfully".to_vec())?;
+        planner.place(RegionKind::Message, memory_layout::ERROR_MESSAGE, b"Operation failed".to_vec())?;
         
-        // Reset offset for our input data
-        data_offset = 1000;
-        
-        // Allocate space for parameters and extract values
         let mut param_offsets = HashMap::new();
-        
-        // Extract and store all String parameters
-        if let Some(obj) = dsl_input.as_object() {
-            for (key, value) in obj {
-                // Skip the action since we've already processed it
-                if key == "action" {
-                    continue;
-                }
-                
-                // Handle different parameter types
-                if let Some(value_str) = value.as_str() {
-                    // Store string values in data section
-                    let bytes = value_str.as_bytes();
-                    data_items.push((data_offset, bytes.to_vec()));
-                    param_offsets.insert(key.clone(), (data_offset, bytes.len()));
-                    data_offset += bytes.len() + 1; // +1 for null terminator
-                } else if value.is_number() {
-                    // We'll handle numeric values directly in the code section
-                    // For now just record their existence
-                    param_offsets.insert(key.clone(), (0, 0));
-                } else if let Some(values) = value.as_array() {
-                    // Handle arrays by converting to JSON string for now
-                    // TODO: Handle arrays more efficiently
-                    let json_str = serde_json::to_string(value).unwrap_or_default();
-                    let bytes = json_str.as_bytes();
-                    data_items.push((data_offset, bytes.to_vec()));
-                    param_offsets.insert(key.clone(), (data_offset, bytes.len()));
-                    data_offset += bytes.len() + 1;
-                }
-                // Skip other types for now
-            }
+        for (key, bytes) in plugin.layout(dsl_input) {
+            let region = planner.place(RegionKind::Param, &key, bytes)?;
+            param_offsets.insert(key, region);
         }
+        for (name, len) in plugin.scratch() {
+            planner.reserve(&name, len)?;
+        }
+        let (memory, data_items) = planner.finish();
         
-        // Add a success message
-        let success_msg = "Operation completed successfully";
-        data_items.push((2000, success_msg.as_bytes().to_vec()));
-        
-        // Add an error message
-        let error_msg = "Operation failed";
-        data_items.push((2050, error_msg.as_bytes().to_vec()));
+        // Size memory so the whole layout fits
+        let default_limits = MemoryLimits::default();
+        let requested_limits = options.memory_limits.as_ref().unwrap_or(&default_limits);
+        let memory_limits = fit_memory_limits(requested_limits, memory.footprint())?;
         
-        // Add data section to module
-        let mut data_section = wasm_encoder::DataSection::new();
-        for (offset, bytes) in data_items {
-            data_section.active(0, &wasm_encoder::ConstExpr::i32_const(offset as i32), bytes);
-        }
-        module.section(&data_section);
+        // The action's own host imports, after the base imports
+        let extra_imports = extra_imports(plugin.as_ref());
         
-        // Create code section with our function bodies
-        let mut code_section = CodeSection::new();
+        // Our own functions come after the imports
+        let import_count = (actions::BASE_IMPORTS.len() + extra_imports.len()) as u32;
+        let import_indices = import_indices(&extra_imports);
         
-        // Define _start function (just calls invoke with default parameters)
-        let mut start_func = wasm_encoder::Function::new([]);
-        start_func.instruction(&wasm_encoder::Instruction::End);
-        code_section.function(&start_func);
+        // Sections the action embeds whatever the options
+        let action_sections = plugin.custom_sections(dsl_input)?;
         
-        // Define invoke function based on action
-        let invoke_func = match action.as_str() {
-            "log_caller_info" => self.generate_log_caller_info_function(),
-            "perform_metered_action" => {
-                let resource_type = dsl_input.get("resource_type")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
+        Ok(ModuleLayout {
+            action,
+            plugin,
+            data_items,
+            memory,
+            param_offsets,
+            memory_limits,
+            extra_imports,
+            import_indices,
+            import_count,
+            action_sections,
+        })
+    }
+    
+    /// Encode the sections a stage contributes to a basic module
+    pub(crate) fn encode_stage(
+        &self,
+        stage: ModuleStage,
+        layout: &ModuleLayout,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        options: &CompilationOptions,
+    ) -> CompilerResult<Vec<EncodedSection>> {
+        let section = match stage {
+            ModuleStage::Memory => {
+                let memory = wasm_encoder::MemorySection::new().entry(
+                    wasm_encoder::MemoryType {
+                        minimum: layout.memory_limits.min_pages,
+                        maximum: layout.memory_limits.max_pages,
+                        memory64: false,
+                        shared: false,
+                    }
+                );
+                EncodedSection::of(&memory)
+            }
+            ModuleStage::Types => {
+                // Define type section (function signatures)
+                let mut types = TypeSection::new();
                 
-                let amount = dsl_input.get("amount")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
+                // Type 0: () -> () for _start function
+                types.function(vec![], vec![]);
                 
-                self.generate_perform_metered_action_function(resource_type, amount)
-            },
-            "anchor_data" => {
-                let key_offset = param_offsets.get("key")
-                    .map(|(offset, _)| *offset as i32)
-                    .unwrap_or(0);
+                // Type 1: (i32, i32) -> i32 for host_log_message function
+                types.function(vec![ValType::I32, ValType::I32, ValType::I32], vec![]);
                 
-                let key_len = param_offsets.get("key")
-                    .map(|(_, len)| *len as i32)
-                    .unwrap_or(0);
+                // Type 2: (i32, i32) -> i32 for invoke function (our main entry point)
+                types.function(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
                 
-                let value_offset = param_offsets.get("value")
-                    .map(|(offset, _)| *offset as i32)
-                    .unwrap_or(0);
+                // Type 3: (i32, i32, i32, i32) -> i32 for host_storage_get
+                types.function(
+                    vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+                    vec![ValType::I32],
+                );
                 
-                let value_len = param_offsets.get("value")
-                    .map(|(_, len)| *len as i32)
-                    .unwrap_or(0);
+                // Type 4: (i32, i32, i32, i32) -> i32 for host_storage_put
+                types.function(
+                    vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+                    vec![ValType::I32],
+                );
                 
-                self.generate_anchor_data_function(key_offset, key_len, value_offset, value_len)
-            },
-            "mint_token" => {
-                let resource_type = dsl_input.get("resource_type")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
+                // Type 5: (i32, i32) -> i32 for host_get_caller_did
+                types.function(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
                 
-                let recipient_offset = param_offsets.get("recipient")
-                    .map(|(offset, _)| *offset as i32)
-                    .unwrap_or(0);
+                // Type 6: () -> i32 for host_get_caller_scope
+                types.function(vec![], vec![ValType::I32]);
                 
-                let recipient_len = param_offsets.get("recipient")
-                    .map(|(_, len)| *len as i32)
-                    .unwrap_or(0);
+                // Type 7: (i32, i32) -> i32 for host_check_resource_authorization
+                types.function(vec![ValType::I32, ValType::I32], vec![ValType::I32]);
                 
-                let amount = dsl_input.get("amount")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
+                // Type 8: (i32, i32) -> () for host_record_resource_usage
+                types.function(vec![ValType::I32, ValType::I32], vec![]);
                 
-                self.generate_mint_token_function(resource_type, recipient_offset, recipient_len, amount)
-            },
-            "transfer_resource" => {
-                let from_offset = param_offsets.get("from")
-                    .map(|(offset, _)| *offset as i32)
-                    .unwrap_or(0);
+                // Type 9: (i32, i32, i32, i32, i32, i32) -> i32 for host_anchor_to_dag
+                types.function(
+                    vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+                    vec![ValType::I32],
+                );
                 
-                let from_len = param_offsets.get("from")
-                    .map(|(_, len)| *len as i32)
-                    .unwrap_or(0);
+                // Type 10: (i32, i32, i32, i32) -> i32 for host_mint_token
+                types.function(
+                    vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32], 
+                    vec![ValType::I32]
+                );
                 
-                let to_offset = param_offsets.get("to")
-                    .map(|(offset, _)| *offset as i32)
-                    .unwrap_or(0);
+                // Type 11: (i32, i32, i32, i32, i32, i32) -> i32 for host_transfer_resource
+                types.function(
+                    vec![ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32, ValType::I32],
+                    vec![ValType::I32],
+                );
                 
-                let to_len = param_offsets.get("to")
-                    .map(|(_, len)| *len as i32)
-                    .unwrap_or(0);
+                // Types 12+: the action's own host imports
+                for import in &layout.extra_imports {
+                    types.function(import.params.clone(), import.results.clone());
+                }
                 
-                let resource_type = dsl_input.get("resource_type")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
+                EncodedSection::of(&types)
+            }
+            ModuleStage::Imports => {
+                // Define import section (host functions we'll use)
+                let mut imports = ImportSection::new();
                 
-                let amount = dsl_input.get("amount")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as i32;
+                // Import host_log_message from env
+                imports.import(
+                    "env",
+                    "host_log_message",
+                    EntityType::Function(1), // Using type index 1 (log message function)
+                );
                 
-                self.generate_transfer_resource_function(
-                    from_offset, from_len, 
-                    to_offset, to_len,
-                    resource_type, amount
-                )
-            },
-            _ => {
-                // Default function that just logs and returns success
-                self.generate_default_function(&action)
+                // Import host_storage_get from env
+                imports.import("env", "host_storage_get", EntityType::Function(3));
+                
+                // Import host_storage_put from env
+                imports.import("env", "host_storage_put", EntityType::Function(4));
+                
+                // Import host_get_caller_did from env
+                imports.import("env", "host_get_caller_did", EntityType::Function(5));
+                
+                // Import host_get_caller_scope from env
+                imports.import("env", "host_get_caller_scope", EntityType::Function(6));
+                
+                // Import host_check_resource_authorization from env
+                imports.import("env", "host_check_resource_authorization", EntityType::Function(7));
+                
+                // Import host_record_resource_usage from env
+                imports.import("env", "host_record_resource_usage", EntityType::Function(8));
+                
+                // Import host_anchor_to_dag from env
+                imports.import("env", "host_anchor_to_dag", EntityType::Function(9));
+                
+                // Import host_mint_token from env
+                imports.import("env", "host_mint_token", EntityType::Function(10));
+                
+                // Import host_transfer_resource from env
+                imports.import("env", "host_transfer_resource", EntityType::Function(11));
+                
+                // Import the action's own host functions after the base imports
+                for (i, import) in layout.extra_imports.iter().enumerate() {
+                    imports.import(&import.module, &import.name, EntityType::Function(12 + i as u32));
+                }
+                
+                EncodedSection::of(&imports)
             }
-        };
-        
-        // Add the invoke function to code section
-        code_section.function(&invoke_func);
-        
-        // Add code section to module
-        module.section(&code_section);
-        
-        // Add metadata if enabled
-        if options.include_debug_info {
-            // Create metadata info
-            let metadata = self.create_metadata(ccl_config, dsl_input, options)?;
-            let metadata_json = serde_json::to_string(&metadata)
-                .map_err(|e| CompilerError::General(format!("Failed to serialize metadata: {}", e)))?;
-            
-            // Add custom section with metadata
-            let custom_section = wasm_encoder::CustomSection {
-                name: std::borrow::Cow::Borrowed("icn-ccl-metadata"),
-                data: std::borrow::Cow::Borrowed(metadata_json.as_bytes()),
-            };
-            module.section(&custom_section);
-            
-            // Also add the raw CCL config and DSL input for debugging
-            let ccl_json = serde_json::to_string(ccl_config)
-                .map_err(|e| CompilerError::General(format!("Failed to serialize CCL config: {}", e)))?;
-            let dsl_json = serde_json::to_string(dsl_input)
-                .map_err(|e| CompilerError::General(format!("Failed to serialize DSL input: {}", e)))?;
+            ModuleStage::Functions => {
+                // Define function section (indices of our functions' signatures)
+                let mut functions = FunctionSection::new();
                 
-            // Add CCL config in a custom section
-            let ccl_section = wasm_encoder::CustomSection {
-                name: std::borrow::Cow::Borrowed("icn-ccl-config"),
-                data: std::borrow::Cow::Borrowed(ccl_json.as_bytes()),
-            };
-            module.section(&ccl_section);
-            
-            // Add DSL input in a custom section
-            let dsl_section = wasm_encoder::CustomSection {
-                name: std::borrow::Cow::Borrowed("icn-dsl-input"),
-                data: std::borrow::Cow::Borrowed(dsl_json.as_bytes()),
-            };
-            module.section(&dsl_section);
-        }
-        
-        // Return the compiled WASM module
-        Ok(module.finish())
-    }
-    
-    /// Generate a WASM function body for the log_caller_info action
-    fn generate_log_caller_info_function(&self) -> wasm_encoder::Function {
-        let mut func = wasm_encoder::Function::new([
-            // Local variables: 
-            // Local 0: Return value
-            // Local 1: Temporary variable for results
-            // Local 2: String length
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-        ]);
-        
-        // Initialize return value to 0 (success)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Call host_get_caller_did to get the caller's DID
-        func.instruction(&wasm_encoder::Instruction::I32Const(2000)); // Output buffer
-        func.instruction(&wasm_encoder::Instruction::I32Const(100)); // Buffer size
-        func.instruction(&wasm_encoder::Instruction::Call(4)); // host_get_caller_did
-        func.instruction(&wasm_encoder::Instruction::LocalSet(2)); // Save the returned length
-        
-        // Check if we got a valid result (length > 0)
-        func.instruction(&wasm_encoder::Instruction::LocalGet(2));
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::I32GtS());
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Log the DID
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
-        func.instruction(&wasm_encoder::Instruction::I32Const(2000)); // DID buffer
-        func.instruction(&wasm_encoder::Instruction::LocalGet(2)); // DID length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
-        
-        // End if
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // Call host_get_caller_scope to get the caller's scope
-        func.instruction(&wasm_encoder::Instruction::Call(5)); // host_get_caller_scope
-        func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Save the result
-        
-        // Log the scope value
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Debug message
-        func.instruction(&wasm_encoder::Instruction::I32Const(20)); // Message length (approximate)
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
-        
-        // Return success
-        func.instruction(&wasm_encoder::Instruction::LocalGet(0));
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        func
-    }
-    
-    /// Generate a WASM function body for the perform_metered_action action
-    fn generate_perform_metered_action_function(&self, resource_type: i32, amount: i32) -> wasm_encoder::Function {
-        let mut func = wasm_encoder::Function::new([
-            // Local variables: 
-            // Local 0: Return value
-            // Local 1: Result of authorization check
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-        ]);
-        
-        // Initialize return value to -1 (error by default)
-        func.instruction(&wasm_encoder::Instruction::I32Const(-1));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Log start of metered action
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Debug message
-        func.instruction(&wasm_encoder::Instruction::I32Const(20)); // Message length (approximate)
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
-        
-        // Check resource authorization
-        func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
-        func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(6)); // host_check_resource_authorization
-        
-        // Store the result in local 1
-        func.instruction(&wasm_encoder::Instruction::LocalSet(1));
-        
-        // Check if authorized (value in local 1)
-        func.instruction(&wasm_encoder::Instruction::LocalGet(1));
-        
-        // If-else block
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // If branch (authorized)
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2000)); // Success message
-        func.instruction(&wasm_encoder::Instruction::I32Const(30)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // Record resource usage
-        func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
-        func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(7)); // Call host_record_resource_usage
-        
-        // Set return value to success (0)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Else branch (not authorized)
-        func.instruction(&wasm_encoder::Instruction::Else);
-        
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2050)); // Error message
-        func.instruction(&wasm_encoder::Instruction::I32Const(16)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // End if-else
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // Return status
-        func.instruction(&wasm_encoder::Instruction::LocalGet(0));
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        func
-    }
-    
-    /// Generate a WASM function body for the anchor_data action
-    fn generate_anchor_data_function(&self, key_offset: i32, key_len: i32, value_offset: i32, value_len: i32) -> wasm_encoder::Function {
-        let mut func = wasm_encoder::Function::new([
-            // Local variables: 
-            // Local 0: Return value
-            // Local 1: Result of anchor operation
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-        ]);
-        
-        // Initialize return value to -1 (error by default)
-        func.instruction(&wasm_encoder::Instruction::I32Const(-1));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Log start of anchor operation
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Debug message
-        func.instruction(&wasm_encoder::Instruction::I32Const(20)); // Message length (approximate)
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
-        
-        // First check authorization for DAG anchoring (compute resource)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Resource type (Compute)
-        func.instruction(&wasm_encoder::Instruction::I32Const(100)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(6)); // host_check_resource_authorization
-        
-        // If authorization check passes
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Anchor to DAG
-        func.instruction(&wasm_encoder::Instruction::I32Const(key_offset)); // Key pointer
-        func.instruction(&wasm_encoder::Instruction::I32Const(key_len)); // Key length
-        func.instruction(&wasm_encoder::Instruction::I32Const(value_offset)); // Value pointer
-        func.instruction(&wasm_encoder::Instruction::I32Const(value_len)); // Value length
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Parent pointer (none)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Parent count (none)
-        func.instruction(&wasm_encoder::Instruction::Call(8)); // host_anchor_to_dag
-        func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
-        
-        // Check if anchor succeeded (result > 0)
-        func.instruction(&wasm_encoder::Instruction::LocalGet(1));
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::I32GtS());
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Success branch
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2000)); // Success message
-        func.instruction(&wasm_encoder::Instruction::I32Const(30)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // Record resource usage
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Resource type (Compute)
-        func.instruction(&wasm_encoder::Instruction::I32Const(50)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(7)); // Call host_record_resource_usage
-        
-        // Set return value to success (0)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Else branch (anchor failed)
-        func.instruction(&wasm_encoder::Instruction::Else);
-        
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2050)); // Error message
-        func.instruction(&wasm_encoder::Instruction::I32Const(16)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // End if-else (anchor result)
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // End if (authorization check)
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // Return status
-        func.instruction(&wasm_encoder::Instruction::LocalGet(0));
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        func
-    }
-    
-    /// Generate a WASM function body for the mint_token action
-    fn generate_mint_token_function(&self, resource_type: i32, recipient_offset: i32, recipient_len: i32, amount: i32) -> wasm_encoder::Function {
-        let mut func = wasm_encoder::Function::new([
-            // Local variables: 
-            // Local 0: Return value
-            // Local 1: Result of mint operation
-            // Local 2: Caller scope (to verify Guardian status)
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-        ]);
-        
-        // Initialize return value to -1 (error by default)
-        func.instruction(&wasm_encoder::Instruction::I32Const(-1));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Get caller scope to check if Guardian
-        func.instruction(&wasm_encoder::Instruction::Call(5)); // host_get_caller_scope
-        func.instruction(&wasm_encoder::Instruction::LocalSet(2));
-        
-        // Check if caller has Guardian scope (scope value is 3)
-        func.instruction(&wasm_encoder::Instruction::LocalGet(2));
-        func.instruction(&wasm_encoder::Instruction::I32Const(3));
-        func.instruction(&wasm_encoder::Instruction::I32Eq());
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Caller is Guardian, proceed with mint operation
-        func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
-        func.instruction(&wasm_encoder::Instruction::I32Const(recipient_offset)); // Recipient pointer
-        func.instruction(&wasm_encoder::Instruction::I32Const(recipient_len)); // Recipient length
-        func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(9)); // host_mint_token
-        func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
-        
-        // Check if mint succeeded (result > 0)
-        func.instruction(&wasm_encoder::Instruction::LocalGet(1));
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::I32GtS());
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Success branch
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2000)); // Success message
-        func.instruction(&wasm_encoder::Instruction::I32Const(30)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // Set return value to success (0)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Else branch (mint failed)
-        func.instruction(&wasm_encoder::Instruction::Else);
-        
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2050)); // Error message
-        func.instruction(&wasm_encoder::Instruction::I32Const(16)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // End if-else (mint result)
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // Else branch (not a Guardian)
-        func.instruction(&wasm_encoder::Instruction::Else);
-        
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2050)); // Error message
-        func.instruction(&wasm_encoder::Instruction::I32Const(16)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // End if-else (Guardian check)
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // Return status
-        func.instruction(&wasm_encoder::Instruction::LocalGet(0));
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        func
-    }
-    
-    /// Generate a WASM function body for the transfer_resource action
-    fn generate_transfer_resource_function(&self, from_offset: i32, from_len: i32, to_offset: i32, to_len: i32, resource_type: i32, amount: i32) -> wasm_encoder::Function {
-        let mut func = wasm_encoder::Function::new([
-            // Local variables: 
-            // Local 0: Return value
-            // Local 1: Result of transfer operation
-            wasm_encoder::ValType::I32,
-            wasm_encoder::ValType::I32,
-        ]);
-        
-        // Initialize return value to -1 (error by default)
-        func.instruction(&wasm_encoder::Instruction::I32Const(-1));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // First check authorization for resource usage
-        func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
-        func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(6)); // host_check_resource_authorization
-        
-        // If authorization check passes
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Perform the transfer
-        func.instruction(&wasm_encoder::Instruction::I32Const(from_offset)); // From pointer
-        func.instruction(&wasm_encoder::Instruction::I32Const(from_len)); // From length
-        func.instruction(&wasm_encoder::Instruction::I32Const(to_offset)); // To pointer
-        func.instruction(&wasm_encoder::Instruction::I32Const(to_len)); // To length
-        func.instruction(&wasm_encoder::Instruction::I32Const(resource_type)); // Resource type
-        func.instruction(&wasm_encoder::Instruction::I32Const(amount)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(10)); // host_transfer_resource
-        func.instruction(&wasm_encoder::Instruction::LocalSet(1)); // Store result
-        
-        // Check if transfer succeeded (result > 0)
-        func.instruction(&wasm_encoder::Instruction::LocalGet(1));
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::I32GtS());
-        func.instruction(&wasm_encoder::Instruction::If(wasm_encoder::BlockType::Empty));
-        
-        // Success branch
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2000)); // Success message
-        func.instruction(&wasm_encoder::Instruction::I32Const(30)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // Record resource usage
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Resource type (Compute)
-        func.instruction(&wasm_encoder::Instruction::I32Const(20)); // Amount
-        func.instruction(&wasm_encoder::Instruction::Call(7)); // Call host_record_resource_usage
-        
-        // Set return value to success (0)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Else branch (transfer failed)
-        func.instruction(&wasm_encoder::Instruction::Else);
-        
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level (INFO)
-        func.instruction(&wasm_encoder::Instruction::I32Const(2050)); // Error message
-        func.instruction(&wasm_encoder::Instruction::I32Const(16)); // Message length
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // Call host_log_message
-        
-        // End if-else (transfer result)
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // End if (authorization check)
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        // Return status
-        func.instruction(&wasm_encoder::Instruction::LocalGet(0));
-        func.instruction(&wasm_encoder::Instruction::End);
-        
-        func
-    }
-    
-    /// Generate a WASM function body for the default (fallback) function
-    fn generate_default_function(&self, action: &str) -> wasm_encoder::Function {
-        let mut func = wasm_encoder::Function::new([
-            // Local variables: 
-            // Local 0: Return value
-            wasm_encoder::ValType::I32,
-        ]);
-        
-        // Initialize return value to 0 (success)
-        func.instruction(&wasm_encoder::Instruction::I32Const(0));
-        func.instruction(&wasm_encoder::Instruction::LocalSet(0));
-        
-        // Log that we're executing the action
-        func.instruction(&wasm_encoder::Instruction::I32Const(1)); // Log level INFO
-        func.instruction(&wasm_encoder::Instruction::I32Const(0)); // Debug message
-        func.instruction(&wasm_encoder::Instruction::I32Const(20)); // Message length (approximate)
-        func.instruction(&wasm_encoder::Instruction::Call(0)); // host_log_message
-        
-        // Return success
-        func.instruction(&wasm_encoder::Instruction::LocalGet(0));
-        func.instruction(&wasm_encoder::Instruction::End);
+                // _start function (type 0)
+                functions.function(0);
+                
+                // invoke function (type 2)
+                functions.function(2);
+                
+                EncodedSection::of(&functions)
+            }
+            ModuleStage::Exports => {
+                // Define export section (functions we export)
+                let mut exports = ExportSection::new();
+                
+                // Export memory
+                exports.export("memory", wasm_encoder::ExportKind::Memory, 0);
+                
+                // Export _start function
+                exports.export("_start", wasm_encoder::ExportKind::Func, layout.import_count);
+                
+                // Export invoke function
+                exports.export("invoke", wasm_encoder::ExportKind::Func, layout.import_count + 1);
+                
+                EncodedSection::of(&exports)
+            }
+            ModuleStage::Data => {
+                let mut data_section = wasm_encoder::DataSection::new();
+                for (offset, bytes) in &layout.data_items {
+                    data_section.active(0, &wasm_encoder::ConstExpr::i32_const(*offset as i32), bytes.iter().copied());
+                }
+                EncodedSection::of(&data_section)
+            }
+            ModuleStage::Code => {
+                // Create code section with our function bodies
+                let mut code_section = CodeSection::new();
+                
+                // Define _start function (just calls invoke with default parameters)
+                let mut start_func = wasm_encoder::Function::new([]);
+                start_func.instruction(&wasm_encoder::Instruction::End);
+                code_section.function(&start_func);
+                
+                // Define invoke function based on action
+                let context = actions::ActionContext {
+                    action: &layout.action,
+                    template_type: &ccl_config.template_type,
+                    ccl_config: Some(ccl_config),
+                    dsl_input,
+                    params: &layout.param_offsets,
+                    memory: &layout.memory,
+                    imports: &layout.import_indices,
+                };
+                let invoke_func = layout.plugin.emit_body(&context)?;
+                
+                // Add the invoke function to code section
+                code_section.function(&invoke_func);
+                
+                EncodedSection::of(&code_section)
+            }
+            ModuleStage::ActionSections => {
+                return Ok(layout.action_sections.iter()
+                    .map(|(name, bytes)| EncodedSection::of(&wasm_encoder::CustomSection {
+                        name: std::borrow::Cow::Borrowed(name),
+                        data: std::borrow::Cow::Borrowed(bytes),
+                    }))
+                    .collect());
+            }
+            ModuleStage::Metadata => {
+                // Create metadata info
+                let mut metadata = self.create_metadata(ccl_config, dsl_input, options)?;
+                metadata.memory_layout = Some(layout.memory.clone());
+                metadata.source_hashes = source_sections(ccl_config, dsl_input)?.iter()
+                    .map(|(name, bytes)| (name.to_string(), decompile::section_hash(bytes)))
+                    .collect();
+                let metadata_json = reproducible::canonical_json(&metadata)
+                    .map_err(|e| CompilerError::General(format!("Failed to serialize metadata: {}", e)))?;
+                
+                // Add custom section with metadata
+                let custom_section = wasm_encoder::CustomSection {
+                    name: std::borrow::Cow::Borrowed(decompile::METADATA_SECTION),
+                    data: std::borrow::Cow::Borrowed(&metadata_json),
+                };
+                EncodedSection::of(&custom_section)
+            }
+            ModuleStage::Sources => {
+                // Also add the raw CCL config and DSL input for debugging
+                return Ok(source_sections(ccl_config, dsl_input)?.into_iter()
+                    .map(|(name, bytes)| EncodedSection::of(&wasm_encoder::CustomSection {
+                        name: std::borrow::Cow::Borrowed(name),
+                        data: std::borrow::Cow::Owned(bytes),
+                    }))
+                    .collect());
+            }
+        };
         
-        func
-    }
-
-    /// Generate a more complex WASM module with actual business logic
-    #[cfg(feature = "templating")]
-    fn generate_templated_wasm(
-        &self,
-        ccl_config: &GovernanceConfig,
-        dsl_input: &JsonValue,
-        options: &CompilationOptions,
-    ) -> CompilerResult<Vec<u8>> {
-        // This is a placeholder for the templating approach
-        // In a real implementation, this would:
-        // 1. Select a Rust template based on the CCL template type
-        // 2. Fill in the template with the DSL input values
-        // 3. Compile the Rust code to WASM
-        // 4. Return the compiled WASM bytes
-
-        Err(CompilerError::General(
-            "Templated WASM generation not yet implemented".to_string(),
-        ))
+        Ok(vec![section])
     }
 }
 
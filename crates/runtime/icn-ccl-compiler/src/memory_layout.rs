@@ -0,0 +1,124 @@
+/*!
+# Memory Layout
+
+The static data of a basic module (its status messages and the parameters its action
+lays out) and the buffers its `invoke` body writes into at run time are placed by a
+[`MemoryLayoutPlanner`]. Each region gets its own aligned range, sized from what
+actually goes in it, so regions can't overlap however large the DSL input grows.
+
+The planned [`MemoryLayout`] is recorded in the module's metadata section. Everything
+from its `heap_start` on is free for dynamic allocation.
+*/
+
+use serde::{Deserialize, Serialize};
+use crate::{CompilerError, CompilerResult};
+
+/// Region holding the message logged when the action starts
+pub const DEBUG_MESSAGE: &str = "debug_message";
+
+/// Region holding the message logged when the action succeeds
+pub const SUCCESS_MESSAGE: &str = "success_message";
+
+/// Region holding the message logged when the action fails
+pub const ERROR_MESSAGE: &str = "error_message";
+
+/// Every region starts at a multiple of this
+pub const REGION_ALIGN: usize = 8;
+
+/// Offsets are emitted as `i32.const`, so no region may reach past this
+const MAX_ADDRESS: usize = i32::MAX as usize;
+
+/// What a region of memory holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RegionKind {
+    /// A message the module logs
+    Message,
+    /// A DSL parameter laid out by the action's plugin
+    Param,
+    /// A buffer the module fills at run time; no data segment writes it
+    Scratch,
+}
+
+/// A named range of linear memory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub kind: RegionKind,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// The regions of a module's linear memory, in address order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryLayout {
+    pub regions: Vec<MemoryRegion>,
+    /// First address past every region, aligned
+    pub heap_start: usize,
+}
+
+impl MemoryLayout {
+    /// The region of a kind with a name
+    pub fn region(&self, kind: RegionKind, name: &str) -> Option<&MemoryRegion> {
+        self.regions.iter().find(|r| r.kind == kind && r.name == name)
+    }
+
+    /// Bytes of memory the layout spans, counted from address 0
+    pub fn footprint(&self) -> usize {
+        self.heap_start
+    }
+}
+
+/// Assigns regions of linear memory one after another
+#[derive(Debug, Default)]
+pub struct MemoryLayoutPlanner {
+    layout: MemoryLayout,
+    /// Data segments as (offset, bytes)
+    data: Vec<(usize, Vec<u8>)>,
+}
+
+impl MemoryLayoutPlanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn allocate(&mut self, kind: RegionKind, name: &str, len: usize) -> CompilerResult<usize> {
+        if self.layout.region(kind, name).is_some() {
+            return Err(CompilerError::WasmGenerationError(format!(
+                "{:?} region '{}' is laid out twice", kind, name
+            )));
+        }
+
+        let offset = self.layout.heap_start;
+        let end = offset.checked_add(len)
+            .and_then(|end| end.checked_add(REGION_ALIGN - 1))
+            .map(|end| end / REGION_ALIGN * REGION_ALIGN)
+            .filter(|end| *end <= MAX_ADDRESS)
+            .ok_or_else(|| CompilerError::WasmGenerationError(format!(
+                "{:?} region '{}' of {} bytes doesn't fit in 32-bit linear memory", kind, name, len
+            )))?;
+
+        self.layout.regions.push(MemoryRegion { name: name.to_string(), kind, offset, len });
+        self.layout.heap_start = end;
+        Ok(offset)
+    }
+
+    /// Place bytes in a region of their own, written by a data segment. Returns the
+    /// region's offset and length.
+    pub fn place(&mut self, kind: RegionKind, name: &str, bytes: Vec<u8>) -> CompilerResult<(usize, usize)> {
+        let len = bytes.len();
+        let offset = self.allocate(kind, name, len)?;
+        self.data.push((offset, bytes));
+        Ok((offset, len))
+    }
+
+    /// Reserve a scratch buffer of `len` bytes. Returns its offset and length.
+    pub fn reserve(&mut self, name: &str, len: usize) -> CompilerResult<(usize, usize)> {
+        let offset = self.allocate(RegionKind::Scratch, name, len)?;
+        Ok((offset, len))
+    }
+
+    /// The planned layout and the data segments that fill it
+    pub fn finish(self) -> (MemoryLayout, Vec<(usize, Vec<u8>)>) {
+        (self.layout, self.data)
+    }
+}
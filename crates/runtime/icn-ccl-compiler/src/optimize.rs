@@ -0,0 +1,499 @@
+/*!
+# Module Optimization
+
+When [`CompilationOptions::optimize`](crate::CompilationOptions::optimize) is set, the
+assembled module goes through a pipeline of passes, each of which can be turned off
+in [`OptimizationConfig`]:
+
+- **Constant folding** replaces `i32` arithmetic, bitwise and comparison instructions
+  whose operands are both constants with the result, and drops constants that are
+  immediately discarded. Division and remainder can trap, so they're never folded.
+- **Unused import stripping** removes host imports no function calls or exports, and
+  renumbers calls and exports to match.
+- **Dead data-segment elimination** removes active data segments that no `i32.const`
+  in any function body points into. Plugins address their parameters through
+  constants taken from the layout, so a segment nothing points at is never read. The
+  check is conservative: a constant that only happens to fall inside a segment keeps it.
+
+The passes only rewrite the sections they need to, and only modules built from the
+instructions basic modules use. A module with anything else (tables, a start
+function, passive data, instructions the pipeline doesn't know) is left as it is. The
+sizes before and after, and what each pass removed, are reported in
+[`OptimizationStats`].
+*/
+
+use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
+use wasm_encoder::{BlockType, Instruction, MemArg, ValType};
+use wasmparser::Operator;
+
+use crate::{assemble_module, CompilerError, CompilerResult, EncodedSection};
+
+/// Which optimization passes run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptimizationConfig {
+    /// Remove data segments nothing points into
+    pub eliminate_dead_data: bool,
+
+    /// Remove host imports nothing calls
+    pub strip_unused_imports: bool,
+
+    /// Fold constant `i32` expressions in function bodies
+    pub fold_constants: bool,
+}
+
+impl Default for OptimizationConfig {
+    fn default() -> Self {
+        Self {
+            eliminate_dead_data: true,
+            strip_unused_imports: true,
+            fold_constants: true,
+        }
+    }
+}
+
+/// What an optimization run did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptimizationStats {
+    /// Module size in bytes before optimization
+    pub size_before: usize,
+
+    /// Module size in bytes after optimization
+    pub size_after: usize,
+
+    pub data_segments_removed: usize,
+    pub imports_removed: usize,
+    pub constants_folded: usize,
+}
+
+impl OptimizationStats {
+    /// Bytes the passes saved
+    pub fn bytes_saved(&self) -> usize {
+        self.size_before.saturating_sub(self.size_after)
+    }
+}
+
+const IMPORT_SECTION: u8 = 2;
+const EXPORT_SECTION: u8 = 7;
+const START_SECTION: u8 = 8;
+const ELEMENT_SECTION: u8 = 9;
+const CODE_SECTION: u8 = 10;
+const DATA_SECTION: u8 = 11;
+const DATA_COUNT_SECTION: u8 = 12;
+
+/// Split a module into its sections as (ID, contents)
+fn split_sections(wasm: &[u8]) -> CompilerResult<Vec<(u8, &[u8])>> {
+    let malformed = || CompilerError::WasmGenerationError("Malformed module".to_string());
+    if wasm.len() < 8 || wasm[0..4] != *b"\0asm" {
+        return Err(malformed());
+    }
+
+    let mut sections = Vec::new();
+    let mut pos = 8;
+    while pos < wasm.len() {
+        let id = wasm[pos];
+        pos += 1;
+
+        // Section size, as unsigned LEB128
+        let mut size: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *wasm.get(pos).ok_or_else(malformed)?;
+            pos += 1;
+            size |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            if shift > 28 {
+                return Err(malformed());
+            }
+        }
+
+        let end = pos.checked_add(size).filter(|end| *end <= wasm.len()).ok_or_else(malformed)?;
+        sections.push((id, &wasm[pos..end]));
+        pos = end;
+    }
+    Ok(sections)
+}
+
+fn parse_error(e: wasmparser::BinaryReaderError) -> CompilerError {
+    CompilerError::WasmGenerationError(format!("Failed to parse module for optimization: {}", e))
+}
+
+/// Raised inside the pipeline when a module uses something the passes don't handle
+struct Unsupported;
+
+/// A function body as locals and instructions
+struct Body {
+    locals: Vec<(u32, ValType)>,
+    instructions: Vec<Instruction<'static>>,
+}
+
+fn val_type(ty: wasmparser::ValType) -> Result<ValType, Unsupported> {
+    match ty {
+        wasmparser::ValType::I32 => Ok(ValType::I32),
+        wasmparser::ValType::I64 => Ok(ValType::I64),
+        wasmparser::ValType::F32 => Ok(ValType::F32),
+        wasmparser::ValType::F64 => Ok(ValType::F64),
+        _ => Err(Unsupported),
+    }
+}
+
+fn block_type(ty: wasmparser::BlockType) -> Result<BlockType, Unsupported> {
+    match ty {
+        wasmparser::BlockType::Empty => Ok(BlockType::Empty),
+        wasmparser::BlockType::Type(ty) => Ok(BlockType::Result(val_type(ty)?)),
+        wasmparser::BlockType::FuncType(index) => Ok(BlockType::FunctionType(index)),
+    }
+}
+
+fn mem_arg(arg: wasmparser::MemArg) -> MemArg {
+    MemArg { offset: arg.offset, align: arg.align as u32, memory_index: arg.memory }
+}
+
+/// The instructions basic modules are built from
+fn instruction(op: Operator<'_>) -> Result<Instruction<'static>, Unsupported> {
+    Ok(match op {
+        Operator::Unreachable => Instruction::Unreachable,
+        Operator::Nop => Instruction::Nop,
+        Operator::Block { blockty } => Instruction::Block(block_type(blockty)?),
+        Operator::Loop { blockty } => Instruction::Loop(block_type(blockty)?),
+        Operator::If { blockty } => Instruction::If(block_type(blockty)?),
+        Operator::Else => Instruction::Else,
+        Operator::End => Instruction::End,
+        Operator::Br { relative_depth } => Instruction::Br(relative_depth),
+        Operator::BrIf { relative_depth } => Instruction::BrIf(relative_depth),
+        Operator::Return => Instruction::Return,
+        Operator::Call { function_index } => Instruction::Call(function_index),
+        Operator::Drop => Instruction::Drop,
+        Operator::Select => Instruction::Select,
+        Operator::LocalGet { local_index } => Instruction::LocalGet(local_index),
+        Operator::LocalSet { local_index } => Instruction::LocalSet(local_index),
+        Operator::LocalTee { local_index } => Instruction::LocalTee(local_index),
+        Operator::GlobalGet { global_index } => Instruction::GlobalGet(global_index),
+        Operator::GlobalSet { global_index } => Instruction::GlobalSet(global_index),
+        Operator::I32Load { memarg } => Instruction::I32Load(mem_arg(memarg)),
+        Operator::I32Load8U { memarg } => Instruction::I32Load8U(mem_arg(memarg)),
+        Operator::I32Store { memarg } => Instruction::I32Store(mem_arg(memarg)),
+        Operator::I32Store8 { memarg } => Instruction::I32Store8(mem_arg(memarg)),
+        Operator::MemorySize { mem, .. } => Instruction::MemorySize(mem),
+        Operator::MemoryGrow { mem, .. } => Instruction::MemoryGrow(mem),
+        Operator::I32Const { value } => Instruction::I32Const(value),
+        Operator::I64Const { value } => Instruction::I64Const(value),
+        Operator::I32Eqz => Instruction::I32Eqz,
+        Operator::I32Eq => Instruction::I32Eq,
+        Operator::I32Ne => Instruction::I32Ne,
+        Operator::I32LtS => Instruction::I32LtS,
+        Operator::I32LtU => Instruction::I32LtU,
+        Operator::I32GtS => Instruction::I32GtS,
+        Operator::I32GtU => Instruction::I32GtU,
+        Operator::I32LeS => Instruction::I32LeS,
+        Operator::I32LeU => Instruction::I32LeU,
+        Operator::I32GeS => Instruction::I32GeS,
+        Operator::I32GeU => Instruction::I32GeU,
+        Operator::I32Add => Instruction::I32Add,
+        Operator::I32Sub => Instruction::I32Sub,
+        Operator::I32Mul => Instruction::I32Mul,
+        Operator::I32DivS => Instruction::I32DivS,
+        Operator::I32DivU => Instruction::I32DivU,
+        Operator::I32RemS => Instruction::I32RemS,
+        Operator::I32RemU => Instruction::I32RemU,
+        Operator::I32And => Instruction::I32And,
+        Operator::I32Or => Instruction::I32Or,
+        Operator::I32Xor => Instruction::I32Xor,
+        Operator::I32Shl => Instruction::I32Shl,
+        Operator::I32ShrS => Instruction::I32ShrS,
+        Operator::I32ShrU => Instruction::I32ShrU,
+        Operator::I64Ne => Instruction::I64Ne,
+        Operator::I64GtS => Instruction::I64GtS,
+        Operator::I64GeS => Instruction::I64GeS,
+        Operator::I64Add => Instruction::I64Add,
+        Operator::I64Mul => Instruction::I64Mul,
+        Operator::I64ExtendI32U => Instruction::I64ExtendI32U,
+        _ => return Err(Unsupported),
+    })
+}
+
+/// Result of an `i32` binary instruction on two constants, if it can't trap
+fn fold_binary(op: &Instruction<'_>, a: i32, b: i32) -> Option<i32> {
+    let (ua, ub) = (a as u32, b as u32);
+    Some(match op {
+        Instruction::I32Add => a.wrapping_add(b),
+        Instruction::I32Sub => a.wrapping_sub(b),
+        Instruction::I32Mul => a.wrapping_mul(b),
+        Instruction::I32And => a & b,
+        Instruction::I32Or => a | b,
+        Instruction::I32Xor => a ^ b,
+        Instruction::I32Shl => a.wrapping_shl(ub),
+        Instruction::I32ShrS => a.wrapping_shr(ub),
+        Instruction::I32ShrU => ua.wrapping_shr(ub) as i32,
+        Instruction::I32Eq => (a == b) as i32,
+        Instruction::I32Ne => (a != b) as i32,
+        Instruction::I32LtS => (a < b) as i32,
+        Instruction::I32LtU => (ua < ub) as i32,
+        Instruction::I32GtS => (a > b) as i32,
+        Instruction::I32GtU => (ua > ub) as i32,
+        Instruction::I32LeS => (a <= b) as i32,
+        Instruction::I32LeU => (ua <= ub) as i32,
+        Instruction::I32GeS => (a >= b) as i32,
+        Instruction::I32GeU => (ua >= ub) as i32,
+        _ => return None,
+    })
+}
+
+/// Fold constant expressions in a body. Returns the number of instructions folded away.
+fn fold_constants(body: &mut Body) -> usize {
+    let mut folded = 0;
+    let mut out: Vec<Instruction<'static>> = Vec::with_capacity(body.instructions.len());
+    for instruction in body.instructions.drain(..) {
+        let top = match out.last() {
+            Some(Instruction::I32Const(value)) => Some(*value),
+            _ => None,
+        };
+        let below = match out.len().checked_sub(2).map(|i| &out[i]) {
+            Some(Instruction::I32Const(value)) => Some(*value),
+            _ => None,
+        };
+
+        match (&instruction, below, top) {
+            (Instruction::I32Eqz, _, Some(value)) => {
+                out.pop();
+                out.push(Instruction::I32Const((value == 0) as i32));
+                folded += 1;
+            }
+            (Instruction::Drop, _, Some(_)) => {
+                out.pop();
+                folded += 2;
+            }
+            (op, Some(a), Some(b)) => match fold_binary(op, a, b) {
+                Some(result) => {
+                    out.truncate(out.len() - 2);
+                    out.push(Instruction::I32Const(result));
+                    folded += 2;
+                }
+                None => out.push(instruction),
+            },
+            _ => out.push(instruction),
+        }
+    }
+    body.instructions = out;
+    folded
+}
+
+/// The parts of a module the passes rewrite
+struct Module<'a> {
+    sections: Vec<(u8, &'a [u8])>,
+    /// Imports as (module, name, type index)
+    imports: Vec<(String, String, u32)>,
+    /// Exports as (name, kind, index)
+    exports: Vec<(String, wasm_encoder::ExportKind, u32)>,
+    /// Active data segments in memory 0 as (offset, bytes)
+    data: Vec<(i32, Vec<u8>)>,
+    bodies: Vec<Body>,
+}
+
+fn parse(wasm: &[u8]) -> CompilerResult<Result<Module<'_>, Unsupported>> {
+    let sections = split_sections(wasm)?;
+    let mut module = Module { sections: sections.clone(), imports: Vec::new(), exports: Vec::new(), data: Vec::new(), bodies: Vec::new() };
+
+    for (id, contents) in sections {
+        match id {
+            START_SECTION | ELEMENT_SECTION | DATA_COUNT_SECTION => return Ok(Err(Unsupported)),
+            IMPORT_SECTION => {
+                for import in wasmparser::ImportSectionReader::new(contents, 0).map_err(parse_error)? {
+                    let import = import.map_err(parse_error)?;
+                    match import.ty {
+                        wasmparser::TypeRef::Func(ty) => module.imports.push((import.module.to_string(), import.name.to_string(), ty)),
+                        _ => return Ok(Err(Unsupported)),
+                    }
+                }
+            }
+            EXPORT_SECTION => {
+                for export in wasmparser::ExportSectionReader::new(contents, 0).map_err(parse_error)? {
+                    let export = export.map_err(parse_error)?;
+                    let kind = match export.kind {
+                        wasmparser::ExternalKind::Func => wasm_encoder::ExportKind::Func,
+                        wasmparser::ExternalKind::Memory => wasm_encoder::ExportKind::Memory,
+                        wasmparser::ExternalKind::Global => wasm_encoder::ExportKind::Global,
+                        _ => return Ok(Err(Unsupported)),
+                    };
+                    module.exports.push((export.name.to_string(), kind, export.index));
+                }
+            }
+            DATA_SECTION => {
+                for data in wasmparser::DataSectionReader::new(contents, 0).map_err(parse_error)? {
+                    let data = data.map_err(parse_error)?;
+                    let wasmparser::DataKind::Active { memory_index: 0, offset_expr } = data.kind else {
+                        return Ok(Err(Unsupported));
+                    };
+                    let mut offset = offset_expr.get_operators_reader();
+                    let (Ok(Operator::I32Const { value }), Ok(Operator::End)) = (offset.read(), offset.read()) else {
+                        return Ok(Err(Unsupported));
+                    };
+                    module.data.push((value, data.data.to_vec()));
+                }
+            }
+            CODE_SECTION => {
+                for body in wasmparser::CodeSectionReader::new(contents, 0).map_err(parse_error)? {
+                    let body = body.map_err(parse_error)?;
+                    let mut locals = Vec::new();
+                    let mut locals_reader = body.get_locals_reader().map_err(parse_error)?;
+                    for _ in 0..locals_reader.get_count() {
+                        let (count, ty) = locals_reader.read().map_err(parse_error)?;
+                        match val_type(ty) {
+                            Ok(ty) => locals.push((count, ty)),
+                            Err(unsupported) => return Ok(Err(unsupported)),
+                        }
+                    }
+                    let mut instructions = Vec::new();
+                    let mut operators = body.get_operators_reader().map_err(parse_error)?;
+                    while !operators.eof() {
+                        match instruction(operators.read().map_err(parse_error)?) {
+                            Ok(instruction) => instructions.push(instruction),
+                            Err(unsupported) => return Ok(Err(unsupported)),
+                        }
+                    }
+                    module.bodies.push(Body { locals, instructions });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(Ok(module))
+}
+
+/// Strip imports nothing calls or exports. Returns the number removed.
+fn strip_unused_imports(module: &mut Module<'_>) -> usize {
+    let mut used: BTreeSet<u32> = module.bodies.iter()
+        .flat_map(|body| body.instructions.iter())
+        .filter_map(|instruction| match instruction {
+            Instruction::Call(index) => Some(*index),
+            _ => None,
+        })
+        .collect();
+    used.extend(module.exports.iter()
+        .filter(|(_, kind, _)| *kind == wasm_encoder::ExportKind::Func)
+        .map(|(_, _, index)| *index));
+
+    // New index of every function, imports first
+    let import_count = module.imports.len() as u32;
+    let mut removed = 0;
+    let mut remap = Vec::new();
+    for index in 0..import_count {
+        if used.contains(&index) {
+            remap.push(index - removed);
+        } else {
+            remap.push(u32::MAX);
+            removed += 1;
+        }
+    }
+    if removed == 0 {
+        return 0;
+    }
+    let new_index = |index: u32| if index < import_count { remap[index as usize] } else { index - removed };
+
+    let mut kept = remap.iter();
+    module.imports.retain(|_| *kept.next().expect("one entry per import") != u32::MAX);
+    for body in &mut module.bodies {
+        for instruction in &mut body.instructions {
+            if let Instruction::Call(index) = instruction {
+                *index = new_index(*index);
+            }
+        }
+    }
+    for (_, kind, index) in &mut module.exports {
+        if *kind == wasm_encoder::ExportKind::Func {
+            *index = new_index(*index);
+        }
+    }
+    removed as usize
+}
+
+/// Drop data segments no constant points into. Returns the number removed.
+fn eliminate_dead_data(module: &mut Module<'_>) -> usize {
+    let constants: BTreeSet<i64> = module.bodies.iter()
+        .flat_map(|body| body.instructions.iter())
+        .filter_map(|instruction| match instruction {
+            Instruction::I32Const(value) => Some(*value as u32 as i64),
+            _ => None,
+        })
+        .collect();
+
+    let before = module.data.len();
+    module.data.retain(|(offset, bytes)| {
+        let start = *offset as u32 as i64;
+        constants.range(start..start + bytes.len() as i64).next().is_some()
+    });
+    before - module.data.len()
+}
+
+fn encode(module: &Module<'_>) -> Vec<u8> {
+    let mut sections = Vec::new();
+    for (id, contents) in &module.sections {
+        let section = match *id {
+            IMPORT_SECTION => {
+                let mut imports = wasm_encoder::ImportSection::new();
+                for (module, name, ty) in &module.imports {
+                    imports.import(module, name, wasm_encoder::EntityType::Function(*ty));
+                }
+                EncodedSection::of(&imports)
+            }
+            EXPORT_SECTION => {
+                let mut exports = wasm_encoder::ExportSection::new();
+                for (name, kind, index) in &module.exports {
+                    exports.export(name, *kind, *index);
+                }
+                EncodedSection::of(&exports)
+            }
+            DATA_SECTION => {
+                let mut data = wasm_encoder::DataSection::new();
+                for (offset, bytes) in &module.data {
+                    data.active(0, &wasm_encoder::ConstExpr::i32_const(*offset), bytes.iter().copied());
+                }
+                EncodedSection::of(&data)
+            }
+            CODE_SECTION => {
+                let mut code = wasm_encoder::CodeSection::new();
+                for body in &module.bodies {
+                    let mut function = wasm_encoder::Function::new(body.locals.iter().copied());
+                    for instruction in &body.instructions {
+                        function.instruction(instruction);
+                    }
+                    code.function(&function);
+                }
+                EncodedSection::of(&code)
+            }
+            id => EncodedSection::of(&wasm_encoder::RawSection { id, data: contents }),
+        };
+        sections.push(section);
+    }
+    assemble_module(&sections)
+}
+
+/// Run the enabled passes over a module
+pub fn optimize_module(wasm: &[u8], config: &OptimizationConfig) -> CompilerResult<(Vec<u8>, OptimizationStats)> {
+    let mut stats = OptimizationStats { size_before: wasm.len(), size_after: wasm.len(), ..Default::default() };
+    let mut module = match parse(wasm)? {
+        Ok(module) => module,
+        Err(Unsupported) => {
+            tracing::debug!("Module uses features the optimizer doesn't handle; leaving it as is");
+            return Ok((wasm.to_vec(), stats));
+        }
+    };
+
+    // Folding first, so the other passes see the constants that remain
+    if config.fold_constants {
+        stats.constants_folded = module.bodies.iter_mut().map(fold_constants).sum();
+    }
+    if config.strip_unused_imports {
+        stats.imports_removed = strip_unused_imports(&mut module);
+    }
+    if config.eliminate_dead_data {
+        stats.data_segments_removed = eliminate_dead_data(&mut module);
+    }
+
+    let optimized = encode(&module);
+    stats.size_after = optimized.len();
+    Ok((optimized, stats))
+}
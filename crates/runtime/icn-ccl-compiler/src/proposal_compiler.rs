@@ -0,0 +1,45 @@
+use std::sync::Mutex;
+
+use icn_governance_kernel::config::GovernanceConfig;
+use icn_governance_kernel::ProposalCompiler;
+use serde_json::Value as JsonValue;
+
+use crate::{CclCompiler, CompilationOptions, CompilerError};
+
+/// Adapter that lets the governance kernel compile proposal `ccl_code` on activation.
+///
+/// The proposal's code is the DSL input for the action it performs. It is compiled
+/// against the governance configuration of the proposal's scope.
+pub struct CclProposalCompiler {
+    compiler: Mutex<CclCompiler>,
+    options: CompilationOptions,
+}
+
+impl CclProposalCompiler {
+    /// Create an adapter with default compilation options
+    pub fn new(compiler: CclCompiler) -> Self {
+        Self::with_options(compiler, CompilationOptions::default())
+    }
+
+    /// Create an adapter with the given compilation options
+    pub fn with_options(compiler: CclCompiler, options: CompilationOptions) -> Self {
+        Self {
+            compiler: Mutex::new(compiler),
+            options,
+        }
+    }
+}
+
+impl ProposalCompiler for CclProposalCompiler {
+    fn compile(&self, ccl_code: &str, scope_config: &GovernanceConfig) -> Result<Vec<u8>, String> {
+        let dsl_input: JsonValue = serde_json::from_str(ccl_code)
+            .map_err(|e| CompilerError::DslError(format!("Proposal code is not valid DSL input: {}", e)).to_string())?;
+
+        let mut compiler = self.compiler.lock()
+            .map_err(|_| CompilerError::General("Compiler lock poisoned".to_string()).to_string())?;
+
+        compiler
+            .compile_to_wasm(scope_config, &dsl_input, Some(self.options.clone()))
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,103 @@
+/*!
+# Reproducible Builds
+
+A federation voting on a proposal should be able to check that the WASM it's asked to
+run is what the proposal's CCL config and DSL input compile to, without trusting
+whoever compiled it. That needs the compiler to be a pure function of its inputs.
+
+With [`CompilationOptions::deterministic`](crate::CompilationOptions) set, nothing in
+the module comes from the clock: the compilation timestamp is read from the DSL
+input's `timestamp` field, either seconds since the Unix epoch or an RFC 3339 string,
+and is 0 when the field is absent. Every JSON section the compiler embeds is written
+with its object keys sorted, so map iteration order never reaches the output.
+
+[`CclCompiler::verify_reproducible`] recompiles the sources in deterministic mode and
+compares the module's hash with the one the proposal claims.
+*/
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+
+use crate::{CclCompiler, CompilationOptions, CompilerError, CompilerResult, GovernanceConfig};
+
+/// DSL input field a deterministic build takes its timestamp from
+pub const TIMESTAMP_FIELD: &str = "timestamp";
+
+/// SHA-256 of a compiled module, as lowercase hex
+pub fn module_hash(wasm_bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(wasm_bytes))
+}
+
+/// JSON bytes with object keys sorted at every level. Going through [`JsonValue`]
+/// sorts them, since its maps are ordered by key.
+pub(crate) fn canonical_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&serde_json::to_value(value)?)
+}
+
+/// The compilation timestamp a deterministic build records for a DSL input
+pub fn dsl_timestamp(dsl_input: &JsonValue) -> CompilerResult<i64> {
+    match dsl_input.get(TIMESTAMP_FIELD) {
+        None | Some(JsonValue::Null) => Ok(0),
+        Some(JsonValue::Number(n)) => n.as_i64().ok_or_else(|| CompilerError::DslError(format!(
+            "'{}' must be whole seconds since the Unix epoch, got {}", TIMESTAMP_FIELD, n
+        ))),
+        Some(JsonValue::String(s)) => chrono::DateTime::parse_from_rfc3339(s)
+            .map(|t| t.timestamp())
+            .map_err(|e| CompilerError::DslError(format!(
+                "'{}' is not an RFC 3339 date: {}", TIMESTAMP_FIELD, e
+            ))),
+        Some(other) => Err(CompilerError::DslError(format!(
+            "'{}' must be a number or an RFC 3339 string, got {}", TIMESTAMP_FIELD, other
+        ))),
+    }
+}
+
+/// Outcome of recompiling a module's sources and comparing hashes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReproducibilityReport {
+    /// The hash the module was claimed to have
+    pub expected_hash: String,
+    /// The hash of the module the sources compile to
+    pub actual_hash: String,
+    /// Size of the recompiled module in bytes
+    pub module_size: usize,
+}
+
+impl ReproducibilityReport {
+    /// Whether the sources compile to the claimed module
+    pub fn matches(&self) -> bool {
+        self.expected_hash.eq_ignore_ascii_case(&self.actual_hash)
+    }
+}
+
+impl CclCompiler {
+    /// Recompile a CCL config and DSL input and check the module hashes to
+    /// `expected_hash`, as returned by [`module_hash`].
+    ///
+    /// The options must match the ones the module was built with; deterministic mode
+    /// is switched on regardless. A mismatch is reported, not returned as an error.
+    pub fn verify_reproducible(
+        &mut self,
+        ccl_config: &GovernanceConfig,
+        dsl_input: &JsonValue,
+        expected_hash: &str,
+        options: Option<CompilationOptions>,
+    ) -> CompilerResult<ReproducibilityReport> {
+        let options = CompilationOptions { deterministic: true, ..options.unwrap_or_default() };
+        let wasm_bytes = self.compile_to_wasm(ccl_config, dsl_input, Some(options))?;
+
+        let report = ReproducibilityReport {
+            expected_hash: expected_hash.to_string(),
+            actual_hash: module_hash(&wasm_bytes),
+            module_size: wasm_bytes.len(),
+        };
+        if !report.matches() {
+            tracing::warn!(
+                "Module built from the given sources hashes to {}, expected {}",
+                report.actual_hash, report.expected_hash
+            );
+        }
+        Ok(report)
+    }
+}
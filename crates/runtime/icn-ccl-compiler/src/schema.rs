@@ -4,11 +4,19 @@ use serde_json::Value as JsonValue;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::CompilerError;
+use crate::diagnostics::{Diagnostic, DiagnosticCode, Locale};
 
-/// Schema manager for validating DSL inputs against JSON schemas
+/// Compiled schemas keyed by file path, shared between clones of a [`SchemaManager`]
+type SchemaCache = Arc<RwLock<HashMap<String, Arc<JSONSchema>>>>;
+
+/// Schema manager for validating DSL inputs against JSON schemas.
+///
+/// Clones share the cache of compiled schemas, so compilers running side by side
+/// compile each schema file once.
+#[derive(Clone)]
 pub struct SchemaManager {
     /// Base directory for schema files
     schema_dir: PathBuf,
@@ -20,7 +28,7 @@ pub struct SchemaManager {
     template_schemas: HashMap<String, String>,
     
     /// Cache of compiled schemas
-    schema_cache: HashMap<String, Arc<JSONSchema>>,
+    schema_cache: SchemaCache,
 }
 
 impl SchemaManager {
@@ -37,7 +45,7 @@ impl SchemaManager {
             schema_dir: schema_dir.as_ref().to_path_buf(),
             action_schemas: HashMap::new(),
             template_schemas: HashMap::new(),
-            schema_cache: HashMap::new(),
+            schema_cache: SchemaCache::default(),
         };
         
         // Register default schemas
@@ -81,11 +89,19 @@ impl SchemaManager {
         })
     }
     
+    /// Number of compiled schemas in the cache
+    pub fn cached_schemas(&self) -> usize {
+        self.schema_cache.read().map(|cache| cache.len()).unwrap_or(0)
+    }
+    
     /// Load and compile a JSON schema from a file
-    fn load_schema(&mut self, schema_path: &Path) -> Result<Arc<JSONSchema>, CompilerError> {
+    fn load_schema(&self, schema_path: &Path) -> Result<Arc<JSONSchema>, CompilerError> {
         // Check if we already have this schema cached
         let path_str = schema_path.to_string_lossy().to_string();
-        if let Some(schema) = self.schema_cache.get(&path_str) {
+        if let Some(schema) = self.schema_cache.read()
+            .map_err(|_| CompilerError::General("Schema cache lock poisoned".to_string()))?
+            .get(&path_str)
+        {
             return Ok(schema.clone());
         }
         
@@ -107,15 +123,29 @@ impl SchemaManager {
                 "Failed to compile schema from {}: {}", schema_path.display(), e
             )))?;
         
-        // Cache the compiled schema
-        let schema_arc = Arc::new(schema);
-        self.schema_cache.insert(path_str, schema_arc.clone());
+        // Cache the compiled schema; another compiler may have cached it meanwhile
+        let mut cache = self.schema_cache.write()
+            .map_err(|_| CompilerError::General("Schema cache lock poisoned".to_string()))?;
+        Ok(cache.entry(path_str).or_insert_with(|| Arc::new(schema)).clone())
+    }
+    
+    /// Validate a DSL input against the schema in a given file
+    pub fn validate_dsl_with_schema_file(&self, schema_path: &Path, dsl_input: &JsonValue) -> Result<(), CompilerError> {
+        let schema = self.load_schema(schema_path)?;
+        if let Err(errors) = schema.validate(dsl_input) {
+            let diagnostics = errors
+                .into_iter()
+                .map(|err| validation_diagnostic(&err, dsl_input))
+                .collect();
+            
+            return Err(CompilerError::Invalid { diagnostics, locale: Locale::default() });
+        }
         
-        Ok(schema_arc)
+        Ok(())
     }
     
     /// Validate a DSL input against a schema for a specific action
-    pub fn validate_dsl_for_action(&mut self, action: &str, dsl_input: &JsonValue) -> Result<(), CompilerError> {
+    pub fn validate_dsl_for_action(&self, action: &str, dsl_input: &JsonValue) -> Result<(), CompilerError> {
         // Get the schema path for this action
         let schema_path = self.get_schema_path_for_action(action)
             .ok_or_else(|| CompilerError::ValidationError(format!(
@@ -135,24 +165,19 @@ impl SchemaManager {
         // Validate the DSL input against the schema
         let validation_result = schema.validate(dsl_input);
         if let Err(errors) = validation_result {
-            // Format validation errors
-            let error_messages: Vec<String> = errors
+            let diagnostics = errors
                 .into_iter()
-                .map(|err| format_validation_error(&err, dsl_input))
+                .map(|err| validation_diagnostic(&err, dsl_input))
                 .collect();
             
-            return Err(CompilerError::ValidationError(format!(
-                "DSL validation failed for action '{}': {}",
-                action,
-                error_messages.join("; ")
-            )));
+            return Err(CompilerError::Invalid { diagnostics, locale: Locale::default() });
         }
         
         Ok(())
     }
     
     /// Validate a DSL input against a schema for a specific template
-    pub fn validate_dsl_for_template(&mut self, template: &str, dsl_input: &JsonValue) -> Result<(), CompilerError> {
+    pub fn validate_dsl_for_template(&self, template: &str, dsl_input: &JsonValue) -> Result<(), CompilerError> {
         // Extract the action from the DSL input
         let action = extract_action(dsl_input)
             .ok_or_else(|| CompilerError::ValidationError(
@@ -187,17 +212,12 @@ impl SchemaManager {
         // Validate the DSL input against the schema
         let validation_result = schema.validate(dsl_input);
         if let Err(errors) = validation_result {
-            // Format validation errors
-            let error_messages: Vec<String> = errors
+            let diagnostics = errors
                 .into_iter()
-                .map(|err| format_validation_error(&err, dsl_input))
+                .map(|err| validation_diagnostic(&err, dsl_input))
                 .collect();
             
-            return Err(CompilerError::ValidationError(format!(
-                "DSL validation failed for template '{}': {}",
-                template,
-                error_messages.join("; ")
-            )));
+            return Err(CompilerError::Invalid { diagnostics, locale: Locale::default() });
         }
         
         Ok(())
@@ -211,42 +231,39 @@ fn extract_action(dsl_input: &JsonValue) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-/// Format a validation error in a user-friendly way
-pub fn format_validation_error(err: &ValidationError, instance: &JsonValue) -> String {
+/// The diagnostic for a schema validation error
+pub fn validation_diagnostic(err: &ValidationError, instance: &JsonValue) -> Diagnostic {
     let path = err.instance_path.to_string();
     let path_display = if path.is_empty() { "root" } else { &path };
     
-    // Extract the property name from the path
-    let _property = path.split('/')
-        .last()
-        .unwrap_or("unknown");
-    
-    // Format based on error type
-    match &err.kind {
+    // Map the error type to a diagnostic code
+    let diagnostic = match &err.kind {
         ValidationErrorKind::Required { property } => {
-            format!("Missing required property: '{}'", property)
+            let property = property.as_str().map(|p| p.to_string()).unwrap_or_else(|| property.to_string());
+            Diagnostic::new(DiagnosticCode::SchemaMissingProperty).arg("property", property)
         }
         ValidationErrorKind::Type { .. } => {
-            let _value = instance.pointer(err.instance_path.to_string().as_str());
-            format!("Invalid type for '{}': expected {}, got {}",
-                    path_display, err.schema_path, _value.map_or("null".to_string(), |v| format!("{:?}", v)))
+            let value = instance.pointer(path.as_str());
+            Diagnostic::new(DiagnosticCode::SchemaInvalidType)
+                .arg("path", path_display)
+                .arg("expected", &err.schema_path)
+                .arg("actual", value.map_or("null".to_string(), |v| v.to_string()))
         }
         ValidationErrorKind::Enum { .. } => {
-            let _value = instance.pointer(err.instance_path.to_string().as_str());
-            format!("Invalid value for '{}': must be one of the allowed values",
-                    path_display)
+            Diagnostic::new(DiagnosticCode::SchemaInvalidValue).arg("path", path_display)
         }
         ValidationErrorKind::MinLength { limit, .. } => {
-            format!("'{}' is too short: minimum length is {}", path_display, limit)
+            Diagnostic::new(DiagnosticCode::SchemaTooShort).arg("path", path_display).arg("limit", limit)
         }
         ValidationErrorKind::MaxLength { limit, .. } => {
-            format!("'{}' is too long: maximum length is {}", path_display, limit)
+            Diagnostic::new(DiagnosticCode::SchemaTooLong).arg("path", path_display).arg("limit", limit)
         }
         ValidationErrorKind::Pattern { .. } => {
-            format!("'{}' does not match the required pattern", path_display)
+            Diagnostic::new(DiagnosticCode::SchemaPatternMismatch).arg("path", path_display)
         }
         _ => {
-            format!("Validation error at '{}': {}", path_display, err.to_string())
+            Diagnostic::new(DiagnosticCode::SchemaViolation).arg("path", path_display).arg("detail", err)
         }
-    }
-} 
\ No newline at end of file
+    };
+    diagnostic.at(path)
+}
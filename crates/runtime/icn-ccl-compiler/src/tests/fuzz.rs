@@ -0,0 +1,139 @@
+// Randomized codegen harness
+//
+// Generates random DSL inputs that are valid for the built-in actions (required fields
+// present, with random strings, numbers, arrays and extra fields mixed in), compiles
+// each, and checks the module validates and instantiates. Instantiation places the data
+// segments in memory, so it also catches memory sized too small for the parameters.
+//
+// The run is reproducible from its seed. `CCL_FUZZ_SEED` and `CCL_FUZZ_ITERATIONS`
+// override the defaults, e.g. for a longer run before a release.
+
+use super::golden::golden_config;
+use crate::{CclCompiler, CompilationOptions, MemoryLimits};
+use serde_json::{Map, Value as JsonValue};
+
+const DEFAULT_SEED: u64 = 0x1c4_c0de;
+const DEFAULT_ITERATIONS: usize = 256;
+
+/// Fields each built-in action requires, and whether they are numeric
+const ACTIONS: &[(&str, &[(&str, bool)])] = &[
+    ("propose_membership", &[("applicant_did", false)]),
+    ("propose_budget", &[("amount", true), ("category", false)]),
+    ("log_caller_info", &[]),
+    ("perform_metered_action", &[("resource_type", true), ("amount", true)]),
+    ("anchor_data", &[("key", false), ("value", false)]),
+    ("mint_token", &[("resource_type", false), ("recipient", false), ("amount", true)]),
+    ("transfer_resource", &[("from", false), ("to", false), ("amount", true), ("resource_type", false)]),
+];
+
+/// SplitMix64, so runs don't depend on an RNG crate's output staying the same
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn string(&mut self) -> String {
+        const ALPHABET: &[char] = &['a', 'z', 'A', '0', '9', ':', '/', '-', '_', ' ', '"', '\\', 'é', 'ß', '✓', '\0'];
+        let len = match self.below(10) {
+            0 => 0,
+            1 => 1_000 + self.below(4_000) as usize,
+            _ => self.below(48) as usize,
+        };
+        (0..len).map(|_| ALPHABET[self.below(ALPHABET.len() as u64) as usize]).collect()
+    }
+
+    fn number(&mut self) -> JsonValue {
+        match self.below(4) {
+            0 => JsonValue::from(0),
+            1 => JsonValue::from(self.below(1_000)),
+            2 => JsonValue::from(i32::MAX as i64 + self.below(1_000) as i64),
+            _ => JsonValue::from(-(self.below(1_000) as i64)),
+        }
+    }
+
+    fn value(&mut self) -> JsonValue {
+        match self.below(6) {
+            0 => JsonValue::Null,
+            1 => JsonValue::Bool(self.below(2) == 0),
+            2 => self.number(),
+            3 => JsonValue::Array((0..self.below(5)).map(|_| JsonValue::String(self.string())).collect()),
+            _ => JsonValue::String(self.string()),
+        }
+    }
+}
+
+fn random_dsl(rng: &mut SplitMix) -> JsonValue {
+    let (action, required) = ACTIONS[rng.below(ACTIONS.len() as u64) as usize];
+    let mut dsl = Map::new();
+    dsl.insert("action".to_string(), JsonValue::from(action));
+    for (field, numeric) in required {
+        let value = if *numeric { rng.number() } else { JsonValue::String(rng.string()) };
+        dsl.insert(field.to_string(), value);
+    }
+    for i in 0..rng.below(4) {
+        dsl.insert(format!("extra_{}", i), rng.value());
+    }
+    JsonValue::Object(dsl)
+}
+
+fn random_options(rng: &mut SplitMix) -> CompilationOptions {
+    CompilationOptions {
+        include_debug_info: rng.below(2) == 0,
+        optimize: rng.below(2) == 0,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
+        memory_limits: Some(MemoryLimits { min_pages: 1 + rng.below(2) as u32, max_pages: Some(16) }),
+        additional_metadata: None,
+        caller_did: (rng.below(2) == 0).then(|| "did:icn:fuzz".to_string()),
+        execution_id: None,
+        schema_path: None,
+        validate_schema: false,
+        locale: Default::default(),
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+#[test]
+fn test_random_dsl_inputs_produce_valid_modules() {
+    let seed = env_or("CCL_FUZZ_SEED", DEFAULT_SEED);
+    let iterations = env_or("CCL_FUZZ_ITERATIONS", DEFAULT_ITERATIONS);
+    let mut rng = SplitMix(seed);
+
+    let config = golden_config();
+    let engine = wasmtime::Engine::default();
+    let mut compiler = CclCompiler::new();
+
+    for iteration in 0..iterations {
+        let dsl = random_dsl(&mut rng);
+        let options = random_options(&mut rng);
+        let context = || format!("seed {:#x}, iteration {}, input {}", seed, iteration, dsl);
+
+        let wasm = compiler.compile_to_wasm(&config, &dsl, Some(options))
+            .unwrap_or_else(|e| panic!("Compilation failed ({}): {:?}", context(), e));
+
+        wasmparser::Validator::new().validate_all(&wasm)
+            .unwrap_or_else(|e| panic!("Invalid module ({}): {}", context(), e));
+
+        let module = wasmtime::Module::new(&engine, &wasm)
+            .unwrap_or_else(|e| panic!("Module rejected ({}): {}", context(), e));
+        let mut linker = wasmtime::Linker::new(&engine);
+        linker.define_unknown_imports_as_traps(&module).unwrap();
+        let mut store = wasmtime::Store::new(&engine, ());
+        linker.instantiate(&mut store, &module)
+            .unwrap_or_else(|e| panic!("Instantiation failed ({}): {}", context(), e));
+    }
+}
@@ -0,0 +1,239 @@
+// Golden snapshot tests for generated modules
+//
+// Each case compiles a fixed DSL input and compares a structural summary of the module
+// (imports and exports with their types, memory limits, every instruction of every
+// function body, data segments and custom sections) with the snapshot in
+// `tests/golden/<case>.snap`. The summary leaves out the compilation timestamp and puts
+// JSON keys in a stable order, so only real codegen changes show up.
+//
+// Run with `UPDATE_GOLDEN=1` to rewrite the snapshots after an intended change. A
+// missing snapshot is recorded on first run, except under `CI`, where it is an error.
+
+use crate::{CclCompiler, CompilationOptions};
+use icn_governance_kernel::config::GovernanceConfig;
+use icn_identity::IdentityScope;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use wasmparser::{DataKind, Parser, Payload};
+
+pub(crate) fn golden_config() -> GovernanceConfig {
+    GovernanceConfig {
+        template_type: "coop_bylaws".to_string(),
+        template_version: "v1".to_string(),
+        governing_scope: IdentityScope::Cooperative,
+        identity: None,
+        governance: None,
+        membership: None,
+        proposals: None,
+        working_groups: None,
+        dispute_resolution: None,
+        economic_model: None,
+    }
+}
+
+fn golden_options() -> CompilationOptions {
+    CompilationOptions {
+        include_debug_info: true,
+        optimize: false,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
+        memory_limits: None,
+        additional_metadata: None,
+        caller_did: Some("did:icn:golden".to_string()),
+        execution_id: Some("golden-execution".to_string()),
+        schema_path: None,
+        validate_schema: false,
+        locale: Default::default(),
+    }
+}
+
+/// JSON with object keys sorted at every level
+fn canonical_json(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => {
+            let sorted: BTreeMap<&String, JsonValue> = map.iter()
+                .map(|(key, value)| (key, canonical_json(value)))
+                .collect();
+            serde_json::to_value(sorted).unwrap()
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(canonical_json).collect()),
+        other => other.clone(),
+    }
+}
+
+fn describe_custom_section(name: &str, data: &[u8]) -> String {
+    match serde_json::from_slice::<JsonValue>(data) {
+        Ok(mut json) => {
+            if name == "icn-ccl-metadata" {
+                if let Some(obj) = json.as_object_mut() {
+                    obj.insert("compilation_timestamp".to_string(), JsonValue::from(0));
+                }
+            }
+            serde_json::to_string(&canonical_json(&json)).unwrap()
+        }
+        Err(_) => format!("{} bytes", data.len()),
+    }
+}
+
+/// A line-per-item description of a module's structure
+pub(crate) fn summarize_module(wasm: &[u8]) -> String {
+    let mut out = String::new();
+
+    let engine = wasmtime::Engine::default();
+    let module = wasmtime::Module::new(&engine, wasm).expect("module should compile");
+    for import in module.imports() {
+        writeln!(out, "import {}::{} {:?}", import.module(), import.name(), import.ty()).unwrap();
+    }
+    for export in module.exports() {
+        writeln!(out, "export {} {:?}", export.name(), export.ty()).unwrap();
+    }
+
+    let mut function = 0;
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.expect("module should parse") {
+            Payload::CodeSectionEntry(body) => {
+                writeln!(out, "func {}", function).unwrap();
+                let mut locals = body.get_locals_reader().unwrap();
+                for _ in 0..locals.get_count() {
+                    let (count, ty) = locals.read().unwrap();
+                    writeln!(out, "  local {} x {:?}", count, ty).unwrap();
+                }
+                let mut operators = body.get_operators_reader().unwrap();
+                while !operators.eof() {
+                    writeln!(out, "  {:?}", operators.read().unwrap()).unwrap();
+                }
+                function += 1;
+            }
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    let data = data.unwrap();
+                    let offset = match data.kind {
+                        DataKind::Active { offset_expr, .. } => {
+                            format!("{:?}", offset_expr.get_operators_reader().read().unwrap())
+                        }
+                        DataKind::Passive => "passive".to_string(),
+                    };
+                    writeln!(out, "data {} {:?}", offset, String::from_utf8_lossy(data.data)).unwrap();
+                }
+            }
+            Payload::CustomSection(section) => {
+                writeln!(out, "custom {} {}", section.name(), describe_custom_section(section.name(), section.data())).unwrap();
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+fn golden_path(case: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.snap", case))
+}
+
+fn assert_golden(case: &str, dsl: JsonValue) {
+    let wasm = CclCompiler::new()
+        .compile_to_wasm(&golden_config(), &dsl, Some(golden_options()))
+        .unwrap_or_else(|e| panic!("{} failed to compile: {:?}", case, e));
+    let actual = summarize_module(&wasm);
+    let path = golden_path(case);
+
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let expected = match std::fs::read_to_string(&path) {
+        Ok(expected) if !update => expected,
+        Ok(_) | Err(_) if update || std::env::var_os("CI").is_none() => {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &actual).unwrap();
+            println!("Recorded golden snapshot {}", path.display());
+            return;
+        }
+        Ok(_) | Err(_) => panic!("Missing golden snapshot {}; run with UPDATE_GOLDEN=1", path.display()),
+    };
+
+    if expected != actual {
+        let mismatch = expected.lines().zip(actual.lines())
+            .enumerate()
+            .find(|(_, (expected, actual))| expected != actual)
+            .map(|(line, (expected, actual))| format!("line {}:\n  expected: {}\n  actual:   {}", line + 1, expected, actual))
+            .unwrap_or_else(|| format!(
+                "expected {} lines, got {}", expected.lines().count(), actual.lines().count()
+            ));
+        panic!("{} differs from {} at {}\nRun with UPDATE_GOLDEN=1 if the change is intended", case, path.display(), mismatch);
+    }
+}
+
+#[test]
+fn test_golden_propose_membership() {
+    assert_golden("propose_membership", serde_json::json!({
+        "action": "propose_membership",
+        "applicant_did": "did:icn:applicant",
+        "name": "Alice Johnson",
+        "skills": ["bookkeeping", "facilitation"]
+    }));
+}
+
+#[test]
+fn test_golden_propose_budget() {
+    assert_golden("propose_budget", serde_json::json!({
+        "action": "propose_budget",
+        "amount": 5000,
+        "category": "development",
+        "purpose": "Website"
+    }));
+}
+
+#[test]
+fn test_golden_log_caller_info() {
+    assert_golden("log_caller_info", serde_json::json!({ "action": "log_caller_info" }));
+}
+
+#[test]
+fn test_golden_perform_metered_action() {
+    assert_golden("perform_metered_action", serde_json::json!({
+        "action": "perform_metered_action",
+        "resource_type": 1,
+        "amount": 250
+    }));
+}
+
+#[test]
+fn test_golden_anchor_data() {
+    assert_golden("anchor_data", serde_json::json!({
+        "action": "anchor_data",
+        "key": "minutes/2026-10",
+        "value": "Approved the budget"
+    }));
+}
+
+#[test]
+fn test_golden_mint_token() {
+    assert_golden("mint_token", serde_json::json!({
+        "action": "mint_token",
+        "resource_type": "compute",
+        "recipient": "did:icn:recipient",
+        "amount": 10
+    }));
+}
+
+#[test]
+fn test_golden_transfer_resource() {
+    assert_golden("transfer_resource", serde_json::json!({
+        "action": "transfer_resource",
+        "from": "did:icn:sender",
+        "to": "did:icn:recipient",
+        "amount": 3,
+        "resource_type": "storage"
+    }));
+}
+
+#[test]
+fn test_golden_summary_ignores_timestamp() {
+    let dsl = serde_json::json!({ "action": "log_caller_info" });
+    let mut compiler = CclCompiler::new();
+    let first = compiler.compile_to_wasm(&golden_config(), &dsl, Some(golden_options())).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let second = compiler.compile_to_wasm(&golden_config(), &dsl, Some(golden_options())).unwrap();
+    assert_eq!(summarize_module(&first), summarize_module(&second));
+}
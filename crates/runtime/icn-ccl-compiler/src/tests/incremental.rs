@@ -0,0 +1,49 @@
+// Incremental recompilation against full compilation
+
+use crate::incremental::{CompileStage, IncrementalCompiler};
+use crate::{CclCompiler, CompilationOptions, ModuleStage};
+use super::golden::golden_config;
+
+fn options() -> CompilationOptions {
+    CompilationOptions {
+        include_debug_info: false,
+        validate_schema: false,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_incremental_matches_full_compilation() {
+    let config = golden_config();
+    let mut full = CclCompiler::new();
+    let mut incremental = IncrementalCompiler::new(CclCompiler::new());
+
+    let first = serde_json::json!({ "action": "anchor_data", "key": "minutes", "value": "2025-01-01" });
+    let edited = serde_json::json!({ "action": "anchor_data", "key": "minutes", "value": "2025-02-01" });
+
+    for dsl in [&first, &edited] {
+        let expected = full.compile_to_wasm(&config, dsl, Some(options())).unwrap();
+        let actual = incremental.compile_to_wasm(&config, dsl, Some(options())).unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    // Changing one value only touches validation, data and the DSL-dependent code
+    let report = incremental.metrics().last_report.clone().unwrap();
+    assert_eq!(
+        report.compiled(),
+        vec![CompileStage::Validation, CompileStage::Section(ModuleStage::Data), CompileStage::Section(ModuleStage::Code)]
+    );
+    assert!(report.reused().contains(&CompileStage::Section(ModuleStage::Types)));
+
+    // Compiling the same input again reuses everything
+    incremental.compile_to_wasm(&config, &edited, Some(options())).unwrap();
+    let metrics = incremental.metrics();
+    assert_eq!(metrics.compilations, 3);
+    assert!(metrics.last_report.as_ref().unwrap().compiled().is_empty());
+    assert_eq!(metrics.stages_reused, 5 + 8);
+    assert!(metrics.time_saved > std::time::Duration::ZERO);
+
+    // Registering an action drops the cache
+    incremental.register_action(std::sync::Arc::new(crate::actions::DefaultAction));
+    assert_eq!(incremental.cached_artifacts(), 0);
+}
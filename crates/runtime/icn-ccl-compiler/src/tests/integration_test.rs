@@ -66,6 +66,9 @@ mod integration_tests {
                     majority: Some(0.67),
                     term_length: Some(365),
                     roles: None,
+                    invariants: None,
+                    member_classes: None,
+                    calendar: None,
                 }),
                 membership: None,
                 proposals: None,
@@ -138,6 +141,9 @@ mod integration_tests {
         let options = CompilationOptions {
             include_debug_info: true, // Include debug info for testing
             optimize: true,
+            optimization: Default::default(),
+            deterministic: false,
+            backend: None,
             memory_limits: None, // Use default limits
             additional_metadata: Some({
                 let mut map = std::collections::HashMap::new();
@@ -151,6 +157,7 @@ mod integration_tests {
                 .as_secs())),
             schema_path: None,
             validate_schema: false, // Skip schema validation for integration test
+            locale: Default::default(),
         };
         
         // Create compiler and compile to WASM
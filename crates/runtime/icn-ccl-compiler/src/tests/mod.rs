@@ -4,6 +4,15 @@ mod integration_test;
 // Unit tests for the CCL compiler
 mod unit_tests;
 
+// Golden snapshots of generated modules
+mod golden;
+
+// Randomized DSL inputs checked for valid modules
+mod fuzz;
+
+// Incremental recompilation against full compilation
+mod incremental;
+
 // Unit tests for specific compiler functionality can be added here later 
 
 use super::*;
@@ -80,6 +89,9 @@ fn test_generate_wasm_module() {
     let options = CompilationOptions {
         include_debug_info: true,
         optimize: false,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         memory_limits: Some(MemoryLimits {
             min_pages: 1,
             max_pages: Some(10),
@@ -91,6 +103,7 @@ fn test_generate_wasm_module() {
         execution_id: Some("test-execution-001".to_string()),
         schema_path: None,
         validate_schema: false,
+        locale: Default::default(),
     };
     
     // Create compiler and compile WASM
@@ -122,7 +135,11 @@ fn test_wasm_with_different_actions() {
     let options = CompilationOptions {
         include_debug_info: false,
         optimize: false,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         validate_schema: false, // Turn off schema validation for this test
+        locale: Default::default(),
         memory_limits: None,
         additional_metadata: None,
         caller_did: None,
@@ -168,6 +185,9 @@ fn test_metadata_embedding() {
     let options = CompilationOptions {
         include_debug_info: true,
         optimize: false,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         additional_metadata: Some([
             ("custom_field".to_string(), "custom_value".to_string())
         ].into_iter().collect()),
@@ -175,6 +195,7 @@ fn test_metadata_embedding() {
         execution_id: Some(test_exec_id.to_string()),
         schema_path: None,
         validate_schema: false,
+        locale: Default::default(),
         memory_limits: None,
     };
     
@@ -208,4 +229,409 @@ fn test_metadata_embedding() {
     assert_eq!(metadata.execution_id, Some(test_exec_id.to_string()));
     assert!(metadata.additional_data.contains_key("custom_field"));
     assert_eq!(metadata.additional_data.get("custom_field").unwrap(), "custom_value");
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_memory_limits_fit_data_segments() {
+    let limits = MemoryLimits { min_pages: 1, max_pages: Some(16) };
+
+    // Small payloads keep the requested minimum
+    assert_eq!(fit_memory_limits(&limits, 4000).unwrap().min_pages, 1);
+
+    // Larger payloads raise the minimum within the maximum
+    assert_eq!(fit_memory_limits(&limits, 3 * WASM_PAGE_SIZE + 1).unwrap().min_pages, 4);
+
+    // Payloads past the maximum are rejected with the page count they need
+    match fit_memory_limits(&limits, 20 * WASM_PAGE_SIZE) {
+        Err(CompilerError::MemoryBudgetExceeded { required_pages, max_pages }) => {
+            assert_eq!(required_pages, 20);
+            assert_eq!(max_pages, 16);
+        }
+        other => panic!("Expected MemoryBudgetExceeded, got {:?}", other),
+    }
+
+    // Unbounded memory accepts any payload
+    let unbounded = MemoryLimits { min_pages: 1, max_pages: None };
+    assert_eq!(fit_memory_limits(&unbounded, 20 * WASM_PAGE_SIZE).unwrap().min_pages, 20);
+}
+
+#[test]
+fn test_oversized_dsl_payload_is_rejected() {
+    let config = create_test_governance_config();
+    let dsl = serde_json::json!({
+        "action": "anchor_data",
+        "key": "large_document",
+        "value": "x".repeat(2 * 1024 * 1024)
+    });
+
+    let options = CompilationOptions {
+        validate_schema: false,
+        ..Default::default()
+    };
+
+    let mut compiler = CclCompiler::new();
+    let result = compiler.compile_to_wasm(&config, &dsl, Some(options));
+    assert!(
+        matches!(result, Err(CompilerError::MemoryBudgetExceeded { max_pages: 16, .. })),
+        "Expected MemoryBudgetExceeded, got {:?}", result.map(|b| b.len())
+    );
+}
+
+#[test]
+fn test_memory_layout_grows_with_payloads() {
+    let config = create_test_governance_config();
+    let dsl = serde_json::json!({
+        "action": "anchor_data",
+        "key": "minutes",
+        "value": "x".repeat(6000)
+    });
+    let options = CompilationOptions { validate_schema: false, ..Default::default() };
+    let wasm_bytes = CclCompiler::new().compile_to_wasm(&config, &dsl, Some(options)).unwrap();
+
+    let mut segments = Vec::new();
+    let mut metadata = None;
+    for payload in Parser::new(0).parse_all(&wasm_bytes) {
+        match payload.unwrap() {
+            Payload::DataSection(reader) => {
+                for data in reader {
+                    let data = data.unwrap();
+                    if let wasmparser::DataKind::Active { offset_expr, .. } = data.kind {
+                        if let Ok(wasmparser::Operator::I32Const { value }) = offset_expr.get_operators_reader().read() {
+                            segments.push((value as usize, data.data.len()));
+                        }
+                    }
+                }
+            }
+            Payload::CustomSection(section) if section.name() == "icn-ccl-metadata" => {
+                metadata = Some(serde_json::from_slice::<MetadataInfo>(section.data()).unwrap());
+            }
+            _ => {}
+        }
+    }
+
+    // The value no longer runs into the status messages that used to sit at fixed offsets
+    segments.sort();
+    assert_eq!(segments.len(), 5);
+    assert!(segments.windows(2).all(|pair| pair[0].0 + pair[0].1 <= pair[1].0));
+
+    // The metadata records where each region went
+    let layout = metadata.unwrap().memory_layout.unwrap();
+    let value = layout.region(RegionKind::Param, "value").unwrap();
+    assert_eq!(value.len, 6000);
+    assert!(layout.region(RegionKind::Message, memory_layout::SUCCESS_MESSAGE).unwrap().offset < value.offset);
+    assert!(layout.heap_start >= value.offset + value.len);
+
+    // Scratch buffers get regions but no data
+    let dsl = serde_json::json!({ "action": "log_caller_info" });
+    let module = CclCompiler::new().layout_basic_module(&config, &dsl, &CompilationOptions::default()).unwrap();
+    let buffer = module.memory.region(RegionKind::Scratch, "caller_did").unwrap();
+    assert_eq!(buffer.len, 100);
+    assert!(module.data_items.iter().all(|(offset, bytes)| offset + bytes.len() <= buffer.offset));
+}
+
+#[test]
+fn test_decompile_recovers_sources_and_infers_actions() {
+    let config = create_test_governance_config();
+    let dsl = serde_json::json!({
+        "action": "mint_token",
+        "resource_type": 1,
+        "recipient": "did:icn:bob",
+        "amount": 25
+    });
+    let compiler = CclCompiler::new();
+    let debug_options = CompilationOptions { include_debug_info: true, validate_schema: false, ..Default::default() };
+    let wasm_bytes = CclCompiler::new().compile_to_wasm(&config, &dsl, Some(debug_options)).unwrap();
+
+    let decompiled = compiler.decompile(&wasm_bytes).unwrap();
+    assert!(decompiled.sources_verified);
+    assert_eq!(decompiled.action, DecompiledAction::Embedded("mint_token".to_string()));
+    assert_eq!(decompiled.ccl_config, Some(config.clone()));
+    assert_eq!(decompiled.dsl_input, Some(dsl.clone()));
+    assert!(decompiled.memory_layout().is_some());
+    // Optimization stripped the imports nothing calls
+    assert_eq!(decompiled.imports.len(), decompiled.called_imports.len());
+    assert!(decompiled.called_imports.iter().any(|import| import.name == "host_mint_token"));
+    assert!(!decompiled.called_imports.iter().any(|import| import.name == "host_transfer_resource"));
+
+    // Editing the embedded DSL input after compilation is caught
+    let needle = b"did:icn:bob";
+    let mut tampered = wasm_bytes.clone();
+    for start in 0..tampered.len() - needle.len() {
+        if &tampered[start..start + needle.len()] == needle {
+            tampered[start..start + needle.len()].copy_from_slice(b"did:icn:eve");
+        }
+    }
+    assert!(matches!(compiler.decompile(&tampered), Err(CompilerError::DecompileError(_))));
+
+    // Without debug info the action is inferred from what the module calls
+    let options = CompilationOptions { include_debug_info: false, validate_schema: false, ..Default::default() };
+    let wasm_bytes = CclCompiler::new().compile_to_wasm(&config, &dsl, Some(options.clone())).unwrap();
+    let decompiled = compiler.decompile(&wasm_bytes).unwrap();
+    assert!(decompiled.metadata.is_none() && !decompiled.sources_verified);
+    assert_eq!(decompiled.action.name(), Some("mint_token"));
+
+    // Actions with the same body can only be narrowed down
+    let wasm_bytes = CclCompiler::new().compile_to_wasm(&config, &create_test_membership_dsl(), Some(options)).unwrap();
+    let DecompiledAction::Inferred { candidates } = compiler.decompile(&wasm_bytes).unwrap().action else {
+        panic!("action should be inferred");
+    };
+    assert!(candidates.contains(&"propose_membership".to_string()));
+    assert!(candidates.contains(&"propose_budget".to_string()));
+}
+
+#[test]
+fn test_deterministic_builds_are_reproducible() {
+    let config = create_test_governance_config();
+    let dsl = serde_json::json!({
+        "action": "mint_token",
+        "resource_type": 1,
+        "recipient": "did:icn:bob",
+        "amount": 25,
+        "timestamp": "2025-03-01T12:00:00Z"
+    });
+    let additional_metadata: std::collections::HashMap<String, String> = (0..16)
+        .map(|i| (format!("key_{}", i), format!("value_{}", i)))
+        .collect();
+    let options = CompilationOptions {
+        include_debug_info: true,
+        validate_schema: false,
+        deterministic: true,
+        additional_metadata: Some(additional_metadata),
+        ..Default::default()
+    };
+
+    // Separate compilers, so nothing is shared between the builds
+    let first = CclCompiler::new().compile_to_wasm(&config, &dsl, Some(options.clone())).unwrap();
+    let second = CclCompiler::new().compile_to_wasm(&config, &dsl, Some(options.clone())).unwrap();
+    assert_eq!(first, second);
+
+    let metadata = CclCompiler::new().decompile(&first).unwrap().metadata.unwrap();
+    assert_eq!(metadata.compilation_timestamp, 1_740_830_400);
+
+    let expected_hash = reproducible::module_hash(&first);
+    let report = CclCompiler::new()
+        .verify_reproducible(&config, &dsl, &expected_hash.to_uppercase(), Some(options.clone()))
+        .unwrap();
+    assert!(report.matches());
+    assert_eq!(report.module_size, first.len());
+
+    // A module built from different sources doesn't verify
+    let mut altered = dsl.clone();
+    altered["amount"] = serde_json::json!(26);
+    let report = CclCompiler::new()
+        .verify_reproducible(&config, &altered, &expected_hash, Some(options.clone()))
+        .unwrap();
+    assert!(!report.matches());
+
+    // Nor does one compiled with a different timestamp
+    altered = dsl.clone();
+    altered["timestamp"] = serde_json::json!(0);
+    let report = CclCompiler::new()
+        .verify_reproducible(&config, &altered, &expected_hash, Some(options))
+        .unwrap();
+    assert!(!report.matches());
+
+    assert!(reproducible::dsl_timestamp(&serde_json::json!({ "timestamp": true })).is_err());
+}
+
+#[test]
+fn test_compile_batch_shares_schemas_and_isolates_failures() {
+    let schema_dir = tempfile::tempdir().unwrap();
+    std::fs::write(schema_dir.path().join("propose_join.schema.json"), r#"{
+        "type": "object",
+        "properties": { "applicant_did": { "type": "string" } },
+        "required": ["action", "applicant_did"]
+    }"#).unwrap();
+
+    let config = create_test_governance_config();
+    let options = CompilationOptions { deterministic: true, ..Default::default() };
+    let mut jobs: Vec<CompileJob> = (0..8)
+        .map(|i| CompileJob::new(
+            format!("member-{}", i),
+            config.clone(),
+            serde_json::json!({
+                "action": "propose_membership",
+                "applicant_did": format!("did:icn:applicant{}", i),
+                "name": format!("Applicant {}", i)
+            }),
+        ).with_options(options.clone()))
+        .collect();
+    // Fails schema validation, without taking the rest of the batch down
+    jobs.insert(3, CompileJob::new(
+        "missing-applicant",
+        config.clone(),
+        serde_json::json!({ "action": "propose_membership", "name": "Nobody" }),
+    ).with_options(options.clone()));
+
+    let compiler = CclCompiler::with_schema_dir(schema_dir.path());
+    let results = compiler.compile_batch(jobs.clone());
+
+    assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+        jobs.iter().map(|j| j.id.as_str()).collect::<Vec<_>>());
+    assert!(matches!(results[3].result, Err(CompilerError::Invalid { .. })));
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 8);
+    assert!(results[0].optimization.is_some());
+
+    // Every worker used the one compiled schema
+    assert_eq!(compiler.schema_manager.as_ref().unwrap().cached_schemas(), 1);
+
+    // The batch produces what compiling each job alone would
+    let mut single = CclCompiler::with_schema_dir(schema_dir.path());
+    let alone = single.compile_to_wasm(&jobs[5].ccl_config, &jobs[5].dsl_input, jobs[5].options.clone()).unwrap();
+    assert_eq!(results[5].result.as_ref().unwrap(), &alone);
+}
+
+#[test]
+fn test_optimization_passes_shrink_the_module() {
+    use wasm_encoder::Instruction;
+
+    /// Logs a message whose length is computed from constants, then discards a constant
+    struct ComputedLength;
+
+    impl ActionPlugin for ComputedLength {
+        fn name(&self) -> &str {
+            "computed_length"
+        }
+
+        fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+            let (offset, len) = ctx.region(memory_layout::DEBUG_MESSAGE);
+            let mut func = wasm_encoder::Function::new([]);
+            func.instruction(&Instruction::I32Const(1));
+            func.instruction(&Instruction::I32Const(offset));
+            func.instruction(&Instruction::I32Const(len - 4));
+            func.instruction(&Instruction::I32Const(4));
+            func.instruction(&Instruction::I32Add);
+            func.instruction(&Instruction::Call(0));
+            func.instruction(&Instruction::I32Const(7));
+            func.instruction(&Instruction::Drop);
+            func.instruction(&Instruction::I32Const(0));
+            func.instruction(&Instruction::End);
+            Ok(func)
+        }
+    }
+
+    let config = create_test_governance_config();
+    let plain = CompilationOptions { optimize: false, validate_schema: false, ..Default::default() };
+    let optimized = CompilationOptions { validate_schema: false, ..Default::default() };
+    let mut compiler = CclCompiler::new();
+    compiler.register_action(std::sync::Arc::new(ComputedLength));
+
+    // The membership body only logs its debug message, so every other message, every
+    // parameter and every import but host_log_message go
+    let dsl = create_test_membership_dsl();
+    let unoptimized = compiler.compile_to_wasm(&config, &dsl, Some(plain.clone())).unwrap();
+    assert!(compiler.last_optimization_stats().is_none());
+    let wasm_bytes = compiler.compile_to_wasm(&config, &dsl, Some(optimized.clone())).unwrap();
+    wasmparser::validate(&wasm_bytes).expect("Optimized module should be valid");
+    let stats = compiler.last_optimization_stats().unwrap().clone();
+    assert_eq!(stats.size_before, unoptimized.len());
+    assert_eq!(stats.size_after, wasm_bytes.len());
+    assert!(stats.bytes_saved() > 0);
+    assert_eq!(stats.imports_removed, actions::BASE_IMPORTS.len() - 1);
+    assert_eq!(stats.data_segments_removed, 6);
+
+    // Constant expressions are folded, and each pass can be turned off
+    let dsl = serde_json::json!({ "action": "computed_length" });
+    compiler.compile_to_wasm(&config, &dsl, Some(optimized.clone())).unwrap();
+    assert_eq!(compiler.last_optimization_stats().unwrap().constants_folded, 4);
+    let no_folding = CompilationOptions {
+        optimization: OptimizationConfig { fold_constants: false, ..Default::default() },
+        deterministic: false,
+        ..optimized
+    };
+    let wasm_bytes = compiler.compile_to_wasm(&config, &dsl, Some(no_folding)).unwrap();
+    wasmparser::validate(&wasm_bytes).expect("Optimized module should be valid");
+    let stats = compiler.last_optimization_stats().unwrap();
+    assert_eq!(stats.constants_folded, 0);
+    assert_eq!(stats.imports_removed, actions::BASE_IMPORTS.len() - 1);
+}
+
+#[test]
+fn test_codegen_backend_is_selected_by_name() {
+    /// Emits the action as a WAT module exporting `invoke`
+    struct WatBackend;
+
+    impl CodegenBackend for WatBackend {
+        fn name(&self) -> &str {
+            "wat"
+        }
+
+        fn generate(&self, ccl_config: &GovernanceConfig, dsl_input: &serde_json::Value, _options: &CompilationOptions) -> CompilerResult<Vec<u8>> {
+            let action = dsl_input["action"].as_str().unwrap_or("unknown");
+            Ok(format!(
+                "(module\n  (memory (export \"memory\") 1)\n  (data (i32.const 0) \"{} for {}\")\n  (func (export \"invoke\") (result i32) i32.const 0))\n",
+                action, ccl_config.template_type
+            ).into_bytes())
+        }
+
+        fn produces_core_module(&self) -> bool {
+            false
+        }
+    }
+
+    let config = create_test_governance_config();
+    let dsl = create_test_membership_dsl();
+    let mut compiler = CclCompiler::new();
+    compiler.register_backend(std::sync::Arc::new(WatBackend));
+    assert_eq!(compiler.backends().backends(), vec!["wat".to_string()]);
+
+    // The built-in backend is used unless another is named, and can be named too
+    let default = compiler.compile_to_wasm(&config, &dsl, None).unwrap();
+    let named = CompilationOptions { backend: Some(backend::DEFAULT_BACKEND.to_string()), ..Default::default() };
+    assert_eq!(compiler.compile_to_wasm(&config, &dsl, Some(named)).unwrap().len(), default.len());
+    wasmparser::validate(&default).expect("Built-in backend output should be valid");
+
+    // Other backends still get validated input, but their output isn't optimized
+    let wat = CompilationOptions { backend: Some("wat".to_string()), ..Default::default() };
+    let text = compiler.compile_to_wasm(&config, &dsl, Some(wat.clone())).unwrap();
+    assert!(compiler.last_optimization_stats().is_none());
+    assert!(String::from_utf8(text.clone()).unwrap().contains("propose_membership for coop_bylaws"));
+    wasmtime::Module::new(&wasmtime::Engine::default(), &text).expect("WAT output should compile");
+    let invalid = serde_json::json!({ "action": "propose_membership" });
+    assert!(compiler.compile_to_wasm(&config, &invalid, Some(wat)).is_err());
+
+    let unknown = CompilationOptions { backend: Some("llvm".to_string()), ..Default::default() };
+    let err = compiler.compile_to_wasm(&config, &dsl, Some(unknown)).unwrap_err();
+    assert!(err.to_string().contains("wasm-encoder, wat"));
+}
+
+#[cfg(feature = "component-model")]
+#[test]
+fn test_component_exchanges_structured_data() {
+    use icn_core_vm::component::{ComponentError, ComponentProposal, execute_component};
+    use icn_core_vm::ConcreteHostEnvironment;
+    use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+    use icn_storage::InMemoryStorageManager;
+    use std::sync::Arc;
+
+    let config = create_test_governance_config();
+    let options = CompilationOptions { validate_schema: false, ..Default::default() };
+    let component = CclCompiler::new()
+        .compile_to_component(&config, &create_test_membership_dsl(), Some(options))
+        .unwrap();
+
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    let host_env = ConcreteHostEnvironment::new(icn_core_vm::VMContext::default(), storage.clone(), identity_manager, None, storage);
+
+    let mut proposal = ComponentProposal {
+        id: "proposal:admit-alice".to_string(),
+        scope_id: "coop-1".to_string(),
+        action: "propose_membership".to_string(),
+        params: vec![("applicant_did".to_string(), "did:icn:applicant123".to_string())],
+    };
+    let execution = execute_component(&component, &proposal, None, &host_env).unwrap();
+    let result = execution.outcome.unwrap();
+    assert_eq!(result.code, 0);
+    assert_eq!(result.message, "Executed propose_membership");
+    assert_eq!(result.outputs[0], ("proposal_id".to_string(), "proposal:admit-alice".to_string()));
+    assert!(result.outputs.contains(&("name".to_string(), "Alice Johnson".to_string())));
+    assert_eq!(execution.guest_log.entries.len(), 1);
+
+    proposal.id.clear();
+    let execution = execute_component(&component, &proposal, None, &host_env).unwrap();
+    assert!(matches!(execution.outcome, Err(ComponentError::InvalidInput(_))));
+}
+
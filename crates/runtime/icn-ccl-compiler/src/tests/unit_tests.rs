@@ -22,6 +22,9 @@ fn create_test_ccl_config() -> GovernanceConfig {
             majority: Some(0.67),
             term_length: Some(365),
             roles: None,
+            invariants: None,
+            member_classes: None,
+            calendar: None,
         }),
         membership: None,
         proposals: None,
@@ -125,12 +128,16 @@ fn test_metadata_embedding() {
     let options = CompilationOptions {
         include_debug_info: true,
         optimize: true,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         memory_limits: None,
         additional_metadata: Some(additional_metadata),
         caller_did: Some("did:icn:test:caller123".to_string()),
         execution_id: Some("test-exec-001".to_string()),
         schema_path: None,
         validate_schema: false,
+        locale: Default::default(),
     };
     
     // Compile with metadata
@@ -164,12 +171,16 @@ fn test_create_metadata() {
     let options = CompilationOptions {
         include_debug_info: true,
         optimize: true,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         memory_limits: None,
         additional_metadata: Some(additional_metadata),
         caller_did: Some("did:icn:test:caller123".to_string()),
         execution_id: Some("test-exec-001".to_string()),
         schema_path: None,
         validate_schema: false,
+        locale: Default::default(),
     };
     
     // Create the metadata
@@ -235,12 +246,16 @@ fn test_schema_validation_valid_input() {
     let options = CompilationOptions {
         include_debug_info: false,
         optimize: true,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         memory_limits: None,
         additional_metadata: None,
         caller_did: None,
         execution_id: None,
         schema_path: Some(schema_path),
         validate_schema: true,
+        locale: Default::default(),
     };
     
     // Compilation should succeed with valid input
@@ -289,12 +304,16 @@ fn test_schema_validation_invalid_input() {
     let options = CompilationOptions {
         include_debug_info: false,
         optimize: true,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         memory_limits: None,
         additional_metadata: None,
         caller_did: None,
         execution_id: None,
         schema_path: Some(schema_path),
         validate_schema: true,
+        locale: Default::default(),
     };
     
     // Compilation should fail with invalid input
@@ -354,12 +373,16 @@ fn test_schema_validation_with_custom_schema() {
     let valid_options = CompilationOptions {
         include_debug_info: false,
         optimize: true,
+        optimization: Default::default(),
+        deterministic: false,
+        backend: None,
         memory_limits: None,
         additional_metadata: None,
         caller_did: None,
         execution_id: None,
         schema_path: Some(schema_path.clone()),
         validate_schema: true,
+        locale: Default::default(),
     };
     
     // Test with valid input
@@ -907,4 +930,275 @@ fn test_dag_anchor_wasm_generation() {
     assert!(has_export_section, "WASM module should have an export section");
     assert!(has_code_section, "WASM module should have a code section");
     assert!(has_data_section, "WASM module should have a data section");
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_proposal_compiler_adapter() {
+    use crate::CclProposalCompiler;
+    use icn_governance_kernel::ProposalCompiler;
+
+    let options = CompilationOptions {
+        validate_schema: false,
+        ..CompilationOptions::default()
+    };
+    let adapter = CclProposalCompiler::with_options(CclCompiler::new(), options);
+    let ccl_config = create_test_ccl_config();
+
+    // Proposal code is compiled against the scope's config
+    let ccl_code = create_test_dsl_input().to_string();
+    let wasm_bytes = adapter.compile(&ccl_code, &ccl_config).expect("Proposal code should compile");
+    assert_eq!(&wasm_bytes[0..4], &[0x00, 0x61, 0x73, 0x6d]);
+
+    // Code that isn't valid DSL input is rejected
+    assert!(adapter.compile("not json", &ccl_config).is_err());
+}
+
+#[test]
+fn test_custom_action_plugin() {
+    use crate::{ActionContext, ActionPlugin, CompilerError, CompilerResult, HostImport};
+    use std::sync::Arc;
+    use wasm_encoder::{Instruction, ValType};
+
+    /// Records a notice through a host function the base imports don't include
+    struct PostNotice;
+
+    impl ActionPlugin for PostNotice {
+        fn name(&self) -> &str {
+            "post_notice"
+        }
+
+        fn validate(&self, dsl: &serde_json::Map<String, JsonValue>) -> CompilerResult<()> {
+            if dsl.contains_key("notice") {
+                Ok(())
+            } else {
+                Err(CompilerError::DslError("post_notice requires 'notice' field".to_string()))
+            }
+        }
+
+        fn imports(&self) -> Vec<HostImport> {
+            vec![HostImport {
+                module: "env".to_string(),
+                name: "host_post_notice".to_string(),
+                params: vec![ValType::I32, ValType::I32],
+                results: vec![ValType::I32],
+            }]
+        }
+
+        fn emit_body(&self, ctx: &ActionContext<'_>) -> CompilerResult<wasm_encoder::Function> {
+            let post = ctx.import_index("env", "host_post_notice")
+                .ok_or_else(|| CompilerError::WasmGenerationError("host_post_notice not imported".to_string()))?;
+            let (offset, len) = ctx.param("notice");
+
+            let mut func = wasm_encoder::Function::new([]);
+            func.instruction(&Instruction::I32Const(offset));
+            func.instruction(&Instruction::I32Const(len));
+            func.instruction(&Instruction::Call(post));
+            func.instruction(&Instruction::End);
+            Ok(func)
+        }
+    }
+
+    let mut compiler = CclCompiler::new();
+    let ccl_config = create_test_ccl_config();
+    let dsl_input = serde_json::json!({ "action": "post_notice", "notice": "Assembly on Friday" });
+
+    // Unknown until registered
+    assert!(compiler.validate_dsl_for_template(&ccl_config, &dsl_input, false).is_err());
+    compiler.register_action(Arc::new(PostNotice));
+    assert!(compiler.validate_dsl_for_template(&ccl_config, &dsl_input, false).is_ok());
+    assert!(compiler.validate_dsl_for_template(&ccl_config, &serde_json::json!({ "action": "post_notice" }), false).is_err());
+
+    let options = CompilationOptions { validate_schema: false, ..CompilationOptions::default() };
+    let wasm_bytes = compiler.generate_wasm_module(&ccl_config, &dsl_input, &options)
+        .expect("WASM generation should succeed");
+    wasmparser::validate(&wasm_bytes).expect("Generated module should be valid");
+
+    // The extra import follows the base imports and the exports shift past it
+    let mut imports = Vec::new();
+    let mut invoke_index = None;
+    let mut calls = Vec::new();
+    for payload in Parser::new(0).parse_all(&wasm_bytes) {
+        match payload.expect("Should parse WASM payload") {
+            Payload::ImportSection(section) => {
+                for import in section {
+                    imports.push(import.expect("Should parse import").name.to_string());
+                }
+            }
+            Payload::ExportSection(section) => {
+                for export in section {
+                    let export = export.expect("Should parse export");
+                    if export.name == "invoke" {
+                        invoke_index = Some(export.index);
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut operators = body.get_operators_reader().expect("Should read operators");
+                while !operators.eof() {
+                    if let Ok(Operator::Call { function_index }) = operators.read() {
+                        calls.push(function_index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(imports.len(), 11);
+    assert_eq!(imports.last().map(String::as_str), Some("host_post_notice"));
+    assert_eq!(invoke_index, Some(12));
+    assert_eq!(calls, vec![10]);
+}
+
+#[test]
+fn test_validation_errors_are_localized() {
+    use crate::{CompilerError, DiagnosticCode, Locale};
+
+    let compiler = CclCompiler::new();
+    let ccl_config = create_test_ccl_config();
+    let dsl_input = serde_json::json!({ "action": "transfer_resource", "from": "did:icn:alice", "amount": 5 });
+
+    let err = compiler.validate_dsl_for_template(&ccl_config, &dsl_input, false)
+        .expect_err("Missing fields should fail validation")
+        .localized(Locale::Spanish);
+
+    // Every missing field is reported, with a stable code
+    let fields: Vec<&str> = err.diagnostics().iter().map(|d| d.args["field"].as_str()).collect();
+    assert_eq!(fields, vec!["to", "resource_type"]);
+    assert!(err.diagnostics().iter().all(|d| d.code == DiagnosticCode::MissingField));
+    assert_eq!(err.to_string(), "DSL validation failed: transfer_resource requiere el campo 'to'; transfer_resource requiere el campo 'resource_type'");
+
+    let json = err.to_json();
+    assert_eq!(json["diagnostics"][0]["code"], "CCL-V004");
+    assert_eq!(json["diagnostics"][0]["locale"], "es");
+
+    // Errors other than validation failures carry no diagnostics
+    assert!(CompilerError::General("boom".to_string()).diagnostics().is_empty());
+}
+
+#[test]
+fn test_host_imports_bind_from_runtime_manifest() {
+    use crate::{CompilerError, HostImport};
+    use icn_core_vm::{host_abi_manifest, HOST_ABI_VERSION};
+    use wasm_encoder::ValType;
+
+    let context = HostImport::bind("host_get_execution_context").unwrap();
+    assert_eq!(context.module, "env");
+    assert_eq!(context.params, vec![ValType::I32, ValType::I32]);
+    assert_eq!(context.results, vec![ValType::I32]);
+
+    // Not exposed by the runtime, or newer than the ABI the module targets
+    assert!(matches!(HostImport::bind("host_post_notice"), Err(CompilerError::WasmGenerationError(_))));
+    let since = host_abi_manifest().get("host_get_execution_context").unwrap().since;
+    assert!(HostImport::bind_from(host_abi_manifest(), "host_get_execution_context", since - 1).is_err());
+    assert!(HostImport::bind_from(host_abi_manifest(), "host_get_execution_context", HOST_ABI_VERSION).is_ok());
+}
+
+#[test]
+fn test_schedule_recurring_embeds_schedule_and_imports_timer() {
+    use crate::{DiagnosticCode, RecurringSchedule};
+
+    let compiler = CclCompiler::new();
+    let ccl_config = create_test_ccl_config();
+    let target = serde_json::json!({ "action": "propose_budget", "amount": 25, "category": "dues" });
+    let dsl_input = serde_json::json!({
+        "action": "schedule_recurring",
+        "schedule": "0 9 1 * *",
+        "target_action": target,
+    });
+
+    // The schedule section is embedded without debug info
+    let wasm = compiler.compile_to_wasm(&ccl_config, &dsl_input, None).unwrap();
+    let decompiled = compiler.decompile(&wasm).unwrap();
+    assert_eq!(decompiled.schedule, Some(RecurringSchedule { schedule: "0 9 1 * *".to_string(), target_action: target }));
+    assert!(decompiled.metadata.is_none());
+
+    let timer = decompiled.imports.iter().position(|import| import.name == "host_register_timer").unwrap();
+    assert!(decompiled.called_imports.contains(&decompiled.imports[timer]));
+    assert_eq!(decompiled.action.name(), Some("schedule_recurring"));
+
+    // Schedules and targets are checked before anything is generated
+    let bad_schedule = serde_json::json!({ "action": "schedule_recurring", "schedule": "0 9 1 *", "target_action": { "action": "propose_budget" } });
+    let err = compiler.validate_dsl_for_template(&ccl_config, &bad_schedule, false).unwrap_err();
+    assert_eq!(err.diagnostics()[0].code, DiagnosticCode::InvalidSchedule);
+    assert_eq!(err.to_json()["diagnostics"][0]["code"], "CCL-V006");
+
+    let no_target = serde_json::json!({ "action": "schedule_recurring", "schedule": "0 9 1 * *", "target_action": { "amount": 25 } });
+    let err = compiler.validate_dsl_for_template(&ccl_config, &no_target, false).unwrap_err();
+    assert_eq!(err.diagnostics()[0].args["field"], "target_action.action");
+}
+
+#[test]
+fn test_vote_actions_apply_config_weights_and_thresholds() {
+    use crate::{DiagnosticCode, TallyInput, TALLY_INPUT_INVALID};
+    use icn_governance_kernel::config::{MembershipRules, ParticipationScoring};
+
+    let compiler = CclCompiler::new();
+    let mut ccl_config = create_test_ccl_config();
+    ccl_config.membership = Some(MembershipRules {
+        onboarding: None,
+        dues: None,
+        offboarding: None,
+        participation: Some(ParticipationScoring { weights_votes: Some(true), max_vote_weight: Some(3), ..Default::default() }),
+    });
+
+    // Budget votes carry the weight, capped at the config's maximum
+    let budget_vote = serde_json::json!({ "action": "cast_vote", "proposal_id": "prop-1", "budget_id": "budget-1", "choice": "against", "weight": 5 });
+    let wasm = compiler.compile_to_wasm(&ccl_config, &budget_vote, None).unwrap();
+    let decompiled = compiler.decompile(&wasm).unwrap();
+    let budget = decompiled.imports.iter().position(|import| import.name == "economics_budget_vote").unwrap();
+    assert!(decompiled.called_imports.contains(&decompiled.imports[budget]));
+    // invoke is the last function body
+    let mut invoke_consts = Vec::new();
+    for payload in Parser::new(0).parse_all(&wasm) {
+        if let Payload::CodeSectionEntry(func_body) = payload.expect("Should parse payload") {
+            invoke_consts.clear();
+            let mut operators = func_body.get_operators_reader().expect("Should read operators");
+            while !operators.eof() {
+                if let Operator::I32Const { value } = operators.read().expect("Should read operator") {
+                    invoke_consts.push(value);
+                }
+            }
+        }
+    }
+    assert!(invoke_consts.windows(2).any(|w| w == [1, 3]), "choice 1 with weight 3 in {:?}", invoke_consts);
+
+    let proposal_vote = serde_json::json!({ "action": "cast_vote", "proposal_id": "prop-1", "choice": "for" });
+    let decompiled = compiler.decompile(&compiler.compile_to_wasm(&ccl_config, &proposal_vote, None).unwrap()).unwrap();
+    let cast = decompiled.imports.iter().position(|import| import.name == "host_cast_vote").unwrap();
+    assert!(decompiled.called_imports.contains(&decompiled.imports[cast]));
+
+    let bad_choice = serde_json::json!({ "action": "cast_vote", "proposal_id": "prop-1", "choice": "maybe" });
+    let err = compiler.validate_dsl_for_template(&ccl_config, &bad_choice, false).unwrap_err();
+    assert_eq!(err.diagnostics()[0].code, DiagnosticCode::SchemaInvalidValue);
+    let bad_weight = serde_json::json!({ "action": "cast_vote", "proposal_id": "prop-1", "choice": "for", "weight": 0 });
+    assert!(compiler.validate_dsl_for_template(&ccl_config, &bad_weight, false).is_err());
+
+    // The tally checks the config's 75% quorum and 67% majority inside the module
+    let tally = serde_json::json!({ "action": "tally_votes", "proposal_id": "prop-1" });
+    let wasm = compiler.compile_to_wasm(&ccl_config, &tally, None).unwrap();
+    let engine = wasmtime::Engine::default();
+    let module = wasmtime::Module::new(&engine, &wasm).unwrap();
+    let mut linker = wasmtime::Linker::new(&engine);
+    linker.func_wrap("env", "host_log_message", |_: i32, _: i32, _: i32| {}).unwrap();
+    linker.define_unknown_imports_as_traps(&module).unwrap();
+    let mut store = wasmtime::Store::new(&engine, ());
+    let instance = linker.instantiate(&mut store, &module).unwrap();
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let invoke = instance.get_typed_func::<(i32, i32), i32>(&mut store, "invoke").unwrap();
+    // Past the module's data
+    let input_ptr = 60_000;
+
+    let mut run = |input: TallyInput, len: i32| {
+        memory.write(&mut store, input_ptr, &input.to_bytes()).unwrap();
+        invoke.call(&mut store, (input_ptr as i32, len)).unwrap()
+    };
+    let tally = |votes_for, votes_against, votes_abstain, eligible| TallyInput { votes_for, votes_against, votes_abstain, eligible };
+    assert_eq!(run(tally(7, 2, 1, 12), 16), 1);
+    // 67% majority missed
+    assert_eq!(run(tally(6, 3, 1, 12), 16), 0);
+    // Quorum missed
+    assert_eq!(run(tally(7, 1, 0, 12), 16), 0);
+    // Abstentions count toward quorum but not majority
+    assert_eq!(run(tally(0, 0, 10, 12), 16), 0);
+    assert_eq!(run(tally(7, 2, 1, 0), 16), 0);
+    assert_eq!(run(tally(7, 2, 1, 12), 12), TALLY_INPUT_INVALID);
+}
@@ -0,0 +1,50 @@
+//! Copying guest memory out against reading it in place.
+//!
+//! Host calls either copy guest bytes into a `Vec` before using them or borrow
+//! them as a view of linear memory. Each benchmark reads a range of a guest memory
+//! and hashes it, the way a read-only host call consumes its input. The size where
+//! the copy starts to cost noticeably more than the view is what
+//! `mem_helpers::ZERO_COPY_THRESHOLD` is set from.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sha2::{Digest, Sha256};
+use wasmtime::{Engine, Memory, MemoryType, Store};
+
+const SIZES: &[usize] = &[256, 1024, 4 * 1024, 16 * 1024, 64 * 1024, 256 * 1024, 1024 * 1024];
+
+fn guest_memory() -> (Store<()>, Memory) {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    // 32 pages (2 MiB), enough for the largest read
+    let memory = Memory::new(&mut store, MemoryType::new(32, None)).unwrap();
+    memory.data_mut(&mut store).iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+    (store, memory)
+}
+
+fn bench_reads(c: &mut Criterion) {
+    let (mut store, memory) = guest_memory();
+    let mut group = c.benchmark_group("guest_read");
+
+    for &size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("copy", size), &size, |b, &size| {
+            b.iter(|| {
+                let bytes = memory.data(&store)[64..64 + size].to_vec();
+                black_box(Sha256::digest(&bytes))
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("view", size), &size, |b, &size| {
+            b.iter(|| {
+                let (data, _) = memory.data_and_store_mut(&mut store);
+                black_box(Sha256::digest(&data[64..64 + size]))
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_reads);
+criterion_main!(benches);
@@ -0,0 +1,230 @@
+/*!
+# Host ABI Manifest
+
+Which host functions a runtime build exposes, and with which signatures, is decided by
+whatever [`register_host_functions`](crate::host_abi::register_host_functions) links. The
+manifest reads that back from the linker itself, so it can't drift from what modules are
+actually linked against, and adds for each function the capability group it belongs to
+and the ABI revision it first appeared in.
+
+Operators fetch the manifest through the gateway, which serves [`HostAbiManifest::to_json`]
+as is. The CCL compiler's binding generator reads it to declare its imports with the
+signatures the runtime expects, and can refuse to bind functions newer than the ABI
+revision a module targets.
+*/
+
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use serde::{Serialize, Deserialize};
+use wasmtime::{Engine, Extern, Linker, Store, ValType};
+
+use crate::{ConcreteHostEnvironment, VMContext, VmError};
+
+/// Current revision of the host ABI. Bumped whenever a host function is added or its
+/// signature changes.
+pub const HOST_ABI_VERSION: u32 = 5;
+
+/// What a host function gives a module access to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostCapability {
+    /// Key-value storage
+    Storage,
+    /// Guest logging
+    Logging,
+    /// DAG nodes and anchoring
+    Dag,
+    /// Resource authorization and usage metering
+    Resources,
+    /// Token minting and resource transfers
+    Tokens,
+    /// Mesh job escrow
+    Escrow,
+    /// Mesh policy governance
+    MeshPolicy,
+    /// Participatory budgets
+    Budgets,
+    /// Windowed reads of input blobs
+    BlobInput,
+    /// Proposal creation and voting
+    Governance,
+    /// The execution's own context
+    Context,
+    /// Recurring timers
+    Timers,
+    /// Linked, but missing from the manifest's classification
+    Unclassified,
+}
+
+/// A wasm value type in a host function signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AbiValType {
+    I32,
+    I64,
+    F32,
+    F64,
+    V128,
+    Funcref,
+    Externref,
+}
+
+impl From<ValType> for AbiValType {
+    fn from(ty: ValType) -> Self {
+        match ty {
+            ValType::I32 => AbiValType::I32,
+            ValType::I64 => AbiValType::I64,
+            ValType::F32 => AbiValType::F32,
+            ValType::F64 => AbiValType::F64,
+            ValType::V128 => AbiValType::V128,
+            ValType::FuncRef => AbiValType::Funcref,
+            ValType::ExternRef => AbiValType::Externref,
+        }
+    }
+}
+
+/// One host function as modules import it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostFunctionDescriptor {
+    /// Import module, e.g. `env`
+    pub module: String,
+    pub name: String,
+    pub params: Vec<AbiValType>,
+    pub results: Vec<AbiValType>,
+    pub capability: HostCapability,
+    /// ABI revision the function first appeared in
+    pub since: u32,
+}
+
+/// Every host function a runtime build exposes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostAbiManifest {
+    pub abi_version: u32,
+    /// Version of the `icn-core-vm` crate the runtime was built with
+    pub runtime_version: String,
+    /// Sorted by module, then name
+    pub functions: Vec<HostFunctionDescriptor>,
+}
+
+impl HostAbiManifest {
+    /// The function imported as `name` from any module
+    pub fn get(&self, name: &str) -> Option<&HostFunctionDescriptor> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    /// The functions giving access to a capability
+    pub fn functions_in(&self, capability: HostCapability) -> impl Iterator<Item = &HostFunctionDescriptor> {
+        self.functions.iter().filter(move |f| f.capability == capability)
+    }
+
+    /// The functions a module targeting `abi_version` may import
+    pub fn available_in(&self, abi_version: u32) -> impl Iterator<Item = &HostFunctionDescriptor> {
+        self.functions.iter().filter(move |f| f.since <= abi_version)
+    }
+
+    /// The manifest as served by the gateway
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("manifest serializes to JSON")
+    }
+}
+
+/// Capability and first ABI revision of each host function
+const CLASSIFICATION: &[(&str, HostCapability, u32)] = &[
+    ("get_value", HostCapability::Storage, 1),
+    ("set_value", HostCapability::Storage, 1),
+    ("delete_value", HostCapability::Storage, 1),
+    ("create_sub_dag", HostCapability::Dag, 1),
+    ("store_dag_node", HostCapability::Dag, 1),
+    ("get_dag_node", HostCapability::Dag, 1),
+    ("contains_dag_node", HostCapability::Dag, 1),
+    ("anchor_to_dag", HostCapability::Dag, 1),
+    ("check_resource_authorization", HostCapability::Resources, 1),
+    ("record_resource_usage", HostCapability::Resources, 1),
+    ("economics_check_resource_authorization", HostCapability::Resources, 1),
+    ("economics_record_resource_usage", HostCapability::Resources, 1),
+    ("mint_token", HostCapability::Tokens, 1),
+    ("transfer_resource", HostCapability::Tokens, 1),
+    ("economics_budget_allocate", HostCapability::Budgets, 1),
+    ("economics_budget_query_balance", HostCapability::Budgets, 1),
+    ("economics_budget_vote", HostCapability::Budgets, 1),
+    ("economics_budget_tally_votes", HostCapability::Budgets, 1),
+    ("economics_budget_finalize_proposal", HostCapability::Budgets, 1),
+    ("host_lock_tokens", HostCapability::Escrow, 2),
+    ("host_release_tokens", HostCapability::Escrow, 2),
+    ("host_refund_tokens", HostCapability::Escrow, 2),
+    ("host_get_active_mesh_policy_cid", HostCapability::MeshPolicy, 2),
+    ("host_load_mesh_policy", HostCapability::MeshPolicy, 2),
+    ("host_update_mesh_policy", HostCapability::MeshPolicy, 2),
+    ("host_activate_mesh_policy", HostCapability::MeshPolicy, 2),
+    ("host_record_policy_vote", HostCapability::MeshPolicy, 2),
+    ("host_input_blob_count", HostCapability::BlobInput, 3),
+    ("host_input_blob_cid", HostCapability::BlobInput, 3),
+    ("host_blob_size", HostCapability::BlobInput, 3),
+    ("host_blob_read_at", HostCapability::BlobInput, 3),
+    ("host_log", HostCapability::Logging, 3),
+    ("host_log_message", HostCapability::Logging, 3),
+    ("host_create_proposal", HostCapability::Governance, 4),
+    ("host_cast_vote", HostCapability::Governance, 4),
+    ("host_get_execution_context", HostCapability::Context, 4),
+    ("host_register_timer", HostCapability::Timers, 5),
+];
+
+fn classify(name: &str) -> (HostCapability, u32) {
+    CLASSIFICATION.iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, capability, since)| (*capability, *since))
+        .unwrap_or((HostCapability::Unclassified, HOST_ABI_VERSION))
+}
+
+/// Read the manifest back from a linker holding the runtime's host functions
+fn build_manifest() -> Result<HostAbiManifest, VmError> {
+    let storage = Arc::new(icn_storage::InMemoryStorageManager::new());
+    let identity_manager = Arc::new(icn_identity::ConcreteIdentityManager::new(
+        Arc::new(icn_identity::InMemoryKeyStorage::default()),
+        Arc::new(icn_identity::InMemoryMetadataStorage::default()),
+    ));
+    let env = ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage);
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, env);
+    let mut linker = Linker::new(&engine);
+    crate::host_abi::register_host_functions(&mut linker)?;
+
+    let definitions: Vec<(String, String, Extern)> = linker.iter(&mut store)
+        .map(|(module, name, item)| (module.to_string(), name.to_string(), item))
+        .collect();
+    let mut functions: Vec<HostFunctionDescriptor> = definitions.into_iter()
+        .filter_map(|(module, name, item)| match item {
+            Extern::Func(func) => Some((module, name, func.ty(&store))),
+            _ => None,
+        })
+        .map(|(module, name, ty)| {
+            let (capability, since) = classify(&name);
+            HostFunctionDescriptor {
+                params: ty.params().map(AbiValType::from).collect(),
+                results: ty.results().map(AbiValType::from).collect(),
+                module,
+                name,
+                capability,
+                since,
+            }
+        })
+        .collect();
+    functions.sort_by(|a, b| (&a.module, &a.name).cmp(&(&b.module, &b.name)));
+
+    Ok(HostAbiManifest {
+        abi_version: HOST_ABI_VERSION,
+        runtime_version: env!("CARGO_PKG_VERSION").to_string(),
+        functions,
+    })
+}
+
+static MANIFEST: Lazy<HostAbiManifest> = Lazy::new(|| {
+    build_manifest().expect("host functions register on a fresh linker")
+});
+
+/// The host functions this runtime build exposes, with their signatures, capability
+/// groups and the ABI revision each first appeared in
+pub fn host_abi_manifest() -> &'static HostAbiManifest {
+    &MANIFEST
+}
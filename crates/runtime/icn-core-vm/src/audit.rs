@@ -0,0 +1,171 @@
+/*!
+# Syscall Audit Log
+
+When a host environment has a [`SyscallAuditPolicy`], every host call the module makes
+is recorded: the call's name, a SHA-256 digest of its arguments, the result code it
+returned (or that it trapped) and the fuel consumed when it was called. After the run
+the log is anchored to the DAG under `audit:syscalls:<execution-id>`, next to the
+execution's receipt, so a misbehaving governance module can be reconstructed after
+the fact.
+
+Calls named in the policy's redaction rules are still recorded, but without the
+argument digest, so sensitive payloads can't be confirmed by guessing them.
+
+Recording works by re-registering every function of the linker for the execution's
+store behind a wrapper, so host functions themselves don't need to know about it.
+*/
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use wasmtime::{Caller, Engine, Extern, Linker, Store, Val};
+
+use crate::{ConcreteHostEnvironment, VmError};
+
+/// What to record about a host call's arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArgumentCapture {
+    /// Record a digest of the arguments
+    Digest,
+    /// Record that the call happened, but nothing about its arguments
+    Redact,
+}
+
+/// Which host calls are audited and how
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyscallAuditPolicy {
+    /// Capture for calls without a specific rule
+    pub default_capture: ArgumentCapture,
+    /// Per-call capture rules, keyed by host function name
+    pub rules: HashMap<String, ArgumentCapture>,
+}
+
+impl Default for SyscallAuditPolicy {
+    fn default() -> Self {
+        Self {
+            default_capture: ArgumentCapture::Digest,
+            rules: HashMap::new(),
+        }
+    }
+}
+
+impl SyscallAuditPolicy {
+    /// Redact the arguments of a host call
+    pub fn redact(mut self, name: &str) -> Self {
+        self.rules.insert(name.to_string(), ArgumentCapture::Redact);
+        self
+    }
+
+    /// How a host call's arguments are captured
+    pub fn capture_for(&self, name: &str) -> ArgumentCapture {
+        self.rules.get(name).copied().unwrap_or(self.default_capture)
+    }
+}
+
+/// One host call made during an execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallRecord {
+    /// Position of the call in the execution, starting at 0
+    pub sequence: u64,
+    /// Import module and name (e.g., "env::anchor_to_dag")
+    pub name: String,
+    /// Hex-encoded SHA-256 of the arguments, unless redacted
+    pub arg_digest: Option<String>,
+    pub redacted: bool,
+    /// First return value, if the call returned one
+    pub result_code: Option<i64>,
+    /// Whether the call trapped instead of returning
+    pub trapped: bool,
+    /// Fuel consumed by the execution when the call was made
+    pub fuel_at_call: u64,
+}
+
+/// The audit log of one execution, as anchored to the DAG
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallAuditLog {
+    pub execution_id: String,
+    pub caller_did: String,
+    pub records: Vec<SyscallRecord>,
+}
+
+/// DAG anchor key of an execution's audit log
+pub fn audit_anchor_key(execution_id: &str) -> String {
+    format!("audit:syscalls:{}", execution_id)
+}
+
+/// Digest of a host call's arguments
+pub fn argument_digest(params: &[Val]) -> String {
+    let mut hasher = Sha256::new();
+    for param in params {
+        let (tag, bits): (u8, u64) = match param {
+            Val::I32(v) => (0, *v as u32 as u64),
+            Val::I64(v) => (1, *v as u64),
+            Val::F32(v) => (2, *v as u64),
+            Val::F64(v) => (3, *v),
+            _ => (4, 0),
+        };
+        hasher.update([tag]);
+        hasher.update(bits.to_le_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn result_code(results: &[Val]) -> Option<i64> {
+    match results.first() {
+        Some(Val::I32(v)) => Some(*v as i64),
+        Some(Val::I64(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+/// A linker whose functions record every call into the host environment's audit log.
+///
+/// The functions of `linker` are instantiated into `store` and wrapped, so the returned
+/// linker can only be used with that store.
+pub(crate) fn audited_linker(
+    engine: &Engine,
+    linker: &Linker<ConcreteHostEnvironment>,
+    store: &mut Store<ConcreteHostEnvironment>,
+    policy: &SyscallAuditPolicy,
+) -> Result<Linker<ConcreteHostEnvironment>, VmError> {
+    let definitions: Vec<(String, String, Extern)> = linker.iter(&mut *store)
+        .map(|(module, name, item)| (module.to_string(), name.to_string(), item))
+        .collect();
+
+    let mut audited = Linker::new(engine);
+    for (module, name, item) in definitions {
+        let func = match item {
+            Extern::Func(func) => func,
+            other => {
+                audited.define(&mut *store, &module, &name, other)
+                    .map_err(|e| VmError::EngineCreationFailed(format!("Failed to define {}::{}: {}", module, name, e)))?;
+                continue;
+            }
+        };
+
+        let ty = func.ty(&*store);
+        let capture = policy.capture_for(&name);
+        let label = format!("{}::{}", module, name);
+        audited.func_new(&module, &name, ty, move |mut caller: Caller<'_, ConcreteHostEnvironment>, params, results| {
+            let fuel_at_call = caller.fuel_consumed().unwrap_or(0);
+            let outcome = func.call(&mut caller, params, results);
+
+            let (arg_digest, redacted) = match capture {
+                ArgumentCapture::Digest => (Some(argument_digest(params)), false),
+                ArgumentCapture::Redact => (None, true),
+            };
+            caller.data().record_syscall(SyscallRecord {
+                sequence: 0, // Assigned by the log
+                name: label.clone(),
+                arg_digest,
+                redacted,
+                result_code: if outcome.is_ok() { result_code(results) } else { None },
+                trapped: outcome.is_err(),
+                fuel_at_call,
+            });
+            outcome
+        }).map_err(|e| VmError::EngineCreationFailed(format!("Failed to wrap {}::{}: {}", module, name, e)))?;
+    }
+
+    Ok(audited)
+}
@@ -0,0 +1,243 @@
+/*!
+# Blob Input Passing
+
+Large inputs (e.g. full member rosters) are not copied into the module's data section or
+passed as invoke parameters. Instead the host attaches one or more blob CIDs to the
+execution, and the module streams the content in windows:
+
+1. `host_input_blob_count() -> i32` returns the number of attached input blobs.
+2. `host_input_blob_cid(index, out_ptr, out_max_len) -> i32` writes the CID string of the
+   input at `index` and returns its length.
+3. `host_blob_size(cid_ptr, cid_len) -> i64` returns the blob size in bytes.
+4. `host_blob_read_at(cid_ptr, cid_len, offset, len, out_ptr) -> i32` copies up to `len`
+   bytes starting at `offset` into guest memory and returns the number of bytes read
+   (0 at end of blob).
+
+Negative return values follow the host ABI error codes. Only the attached input blobs
+can be read; any other CID is refused, and the call is still charged its base cost.
+
+Storage only hands out whole blobs, so a blob is loaded once and its windows are served
+from memory. Loaded blobs are kept in a [`BlobCache`] that belongs to a single execution
+and holds at most [`MAX_BLOB_CACHE_BYTES`]; the least recently read blobs are evicted
+first, and a blob larger than the whole cache is read through without being kept.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use wasmtime::{Caller, Linker, Trap};
+use tracing::*;
+use cid::Cid;
+use crate::{ConcreteHostEnvironment, InternalHostError};
+use crate::host_abi::{map_abi_error_to_wasm, map_internal_error_to_wasm, map_vm_error_to_wasm};
+use crate::cid_utils;
+use crate::pricing::HostCallClass;
+
+/// Maximum number of bytes a single `host_blob_read_at` call may return
+pub const MAX_BLOB_READ_CHUNK: u32 = 1024 * 1024; // 1 MB
+
+/// Base compute cost of a blob read call
+const BLOB_READ_BASE_COST: u64 = 50;
+
+/// Compute cost charged per 64 bytes copied into guest memory
+const BLOB_READ_COST_PER_WORD: u64 = 1;
+
+/// Bytes of blob content one execution keeps loaded for windowed reads
+pub const MAX_BLOB_CACHE_BYTES: usize = 64 * 1024 * 1024; // 64 MB
+
+/// Blobs an execution has loaded for windowed reads, bounded by total size
+#[derive(Debug)]
+pub struct BlobCache {
+    capacity: usize,
+    size: usize,
+    entries: HashMap<Cid, Arc<Vec<u8>>>,
+    /// Cached CIDs, least recently read first
+    order: VecDeque<Cid>,
+}
+
+impl Default for BlobCache {
+    fn default() -> Self {
+        Self::new(MAX_BLOB_CACHE_BYTES)
+    }
+}
+
+impl BlobCache {
+    /// An empty cache holding at most `capacity` bytes
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// A cached blob, marked as the most recently read
+    pub fn get(&mut self, cid: &Cid) -> Option<Arc<Vec<u8>>> {
+        let data = self.entries.get(cid)?.clone();
+        self.order.retain(|c| c != cid);
+        self.order.push_back(*cid);
+        Some(data)
+    }
+
+    /// Keep a loaded blob, evicting the least recently read ones to make room. Blobs
+    /// larger than the whole cache aren't kept.
+    pub fn insert(&mut self, cid: Cid, data: Arc<Vec<u8>>) {
+        if data.len() > self.capacity || self.entries.contains_key(&cid) {
+            return;
+        }
+        while self.size + data.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(evicted) => {
+                    if let Some(old) = self.entries.remove(&evicted) {
+                        self.size -= old.len();
+                    }
+                }
+                None => break,
+            }
+        }
+        self.size += data.len();
+        self.entries.insert(cid, data);
+        self.order.push_back(cid);
+    }
+
+    /// Total bytes of the cached blobs
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// Return the window of `data` starting at `offset` and at most `len` bytes long.
+/// Offsets past the end yield an empty window.
+pub fn read_window(data: &[u8], offset: u64, len: u32) -> &[u8] {
+    if offset >= data.len() as u64 {
+        return &[];
+    }
+    let start = offset as usize;
+    let end = start.saturating_add(len as usize).min(data.len());
+    &data[start..end]
+}
+
+/// Compute cost of copying `len` bytes out of a blob
+pub fn blob_read_cost(len: usize) -> u64 {
+    BLOB_READ_BASE_COST + (len as u64).div_ceil(64) * BLOB_READ_COST_PER_WORD
+}
+
+fn host_input_blob_count_wrapper(caller: Caller<'_, ConcreteHostEnvironment>) -> Result<i32, Trap> {
+    Ok(caller.data().input_blob_cids().len() as i32)
+}
+
+fn host_input_blob_cid_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    index: i32,
+    out_ptr: i32,
+    out_max_len: i32,
+) -> Result<i32, Trap> {
+    let cids = caller.data().input_blob_cids();
+    let cid = match usize::try_from(index).ok().and_then(|i| cids.get(i)) {
+        Some(cid) => cid_utils::cid_to_wasm_string(cid),
+        None => return Ok(map_internal_error_to_wasm(InternalHostError::InvalidInput(
+            format!("No input blob at index {}", index)
+        ))),
+    };
+
+    if cid.len() > out_max_len.max(0) as usize {
+        return Ok(map_abi_error_to_wasm(anyhow::anyhow!(
+            "Output buffer too small: required {}, max {}", cid.len(), out_max_len
+        )));
+    }
+
+    match crate::mem_helpers::write_memory_bytes(&mut caller, out_ptr, cid.as_bytes()) {
+        Ok(()) => Ok(cid.len() as i32),
+        Err(e) => Ok(map_abi_error_to_wasm(e)),
+    }
+}
+
+fn host_blob_size_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    cid_ptr: i32,
+    cid_len: i32,
+) -> Result<i64, Trap> {
+    let cid = match cid_utils::read_cid_from_wasm_memory(&mut caller, cid_ptr, cid_len) {
+        Ok(cid) => cid,
+        Err(e) => return Ok(map_vm_error_to_wasm(e) as i64),
+    };
+
+    if let Err(e) = caller.data().record_host_call(HostCallClass::BlobRead, BLOB_READ_BASE_COST) {
+        return Ok(map_vm_error_to_wasm(e) as i64);
+    }
+
+    let host_env = caller.data().clone();
+    let result = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(host_env.load_blob_cached(&cid))
+    });
+
+    match result {
+        Ok(data) => Ok(data.len() as i64),
+        Err(e) => Ok(map_internal_error_to_wasm(e) as i64),
+    }
+}
+
+fn host_blob_read_at_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    cid_ptr: i32,
+    cid_len: i32,
+    offset: i64,
+    len: i32,
+    out_ptr: i32,
+) -> Result<i32, Trap> {
+    debug!(offset, len, "Host ABI: host_blob_read_at called");
+
+    if offset < 0 || len < 0 || len as u32 > MAX_BLOB_READ_CHUNK {
+        return Ok(map_internal_error_to_wasm(InternalHostError::InvalidInput(format!(
+            "Invalid blob read window: offset={}, len={} (max chunk {})", offset, len, MAX_BLOB_READ_CHUNK
+        ))));
+    }
+
+    let cid = match cid_utils::read_cid_from_wasm_memory(&mut caller, cid_ptr, cid_len) {
+        Ok(cid) => cid,
+        Err(e) => return Ok(map_vm_error_to_wasm(e)),
+    };
+
+    // The base cost is due even if the blob can't be read
+    if let Err(e) = caller.data().record_host_call(HostCallClass::BlobRead, BLOB_READ_BASE_COST) {
+        return Ok(map_vm_error_to_wasm(e));
+    }
+
+    let host_env = caller.data().clone();
+    let result = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(host_env.load_blob_cached(&cid))
+    });
+
+    let data = match result {
+        Ok(data) => data,
+        Err(e) => return Ok(map_internal_error_to_wasm(e)),
+    };
+
+    let window = read_window(&data, offset as u64, len as u32);
+
+    // Charge for the bytes actually copied, not the requested window
+    let copy_cost = blob_read_cost(window.len()) - BLOB_READ_BASE_COST;
+    if copy_cost > 0 {
+        if let Err(e) = caller.data().record_host_call(HostCallClass::BlobRead, copy_cost) {
+            return Ok(map_vm_error_to_wasm(e));
+        }
+    }
+
+    if window.is_empty() {
+        return Ok(0);
+    }
+
+    match crate::mem_helpers::write_memory_bytes(&mut caller, out_ptr, window) {
+        Ok(()) => Ok(window.len() as i32),
+        Err(e) => Ok(map_abi_error_to_wasm(e)),
+    }
+}
+
+/// Register blob input host functions
+pub fn register_blob_input_functions(linker: &mut Linker<ConcreteHostEnvironment>) -> Result<(), wasmtime::Error> {
+    linker.func_wrap("env", "host_input_blob_count", host_input_blob_count_wrapper)?;
+    linker.func_wrap("env", "host_input_blob_cid", host_input_blob_cid_wrapper)?;
+    linker.func_wrap("env", "host_blob_size", host_blob_size_wrapper)?;
+    linker.func_wrap("env", "host_blob_read_at", host_blob_read_at_wrapper)?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+/*!
+# Component Model Execution (experimental)
+
+Modules built against the core-module ABI exchange data through pointers and lengths
+into linear memory. The `ccl-module` world in `wit/ccl.wit` describes the same
+contract as typed records instead: a module exports
+`run: func(proposal: proposal) -> result<execution-result, execution-error>` and
+imports a structured `host` interface, and wasmtime's canonical ABI handles the
+lifting and lowering.
+
+The WIT is always available as [`CCL_WIT`] so the compiler can target it. Executing
+components requires the `component-model` feature. Component execution is metered
+with fuel at the base price; federation instruction pricing only applies to core
+modules for now.
+*/
+
+/// WIT package describing the interface between compiled CCL components and the runtime
+pub const CCL_WIT: &str = include_str!("../wit/ccl.wit");
+
+/// Name of the world compiled CCL components target
+pub const CCL_WORLD: &str = "ccl-module";
+
+#[cfg(feature = "component-model")]
+pub use execution::*;
+
+#[cfg(feature = "component-model")]
+mod execution {
+    use std::collections::HashMap;
+    use wasmtime::{Config, Engine, Store};
+    use wasmtime::component::{Component, Linker};
+    use tracing::*;
+
+    use crate::{ConcreteHostEnvironment, GuestLog, GuestLogLevel, ResourceType, VMContext, VmError};
+    use crate::pricing::{self, UNIT_MULTIPLIER};
+
+    mod bindings {
+        wasmtime::component::bindgen!({
+            path: "wit/ccl.wit",
+            world: "ccl-module",
+        });
+    }
+
+    use bindings::icn::ccl::host::{Host, LogLevel};
+    pub use bindings::icn::ccl::types::{
+        Proposal as ComponentProposal,
+        ExecutionResult as ComponentResult,
+        ExecutionError as ComponentError,
+    };
+
+    /// Store state of a component execution
+    struct ComponentState {
+        host_env: ConcreteHostEnvironment,
+    }
+
+    impl Host for ComponentState {
+        fn log(&mut self, level: LogLevel, message: String) -> wasmtime::Result<()> {
+            let level = match level {
+                LogLevel::Debug => GuestLogLevel::Debug,
+                LogLevel::Info => GuestLogLevel::Info,
+                LogLevel::Warn => GuestLogLevel::Warn,
+                LogLevel::Error => GuestLogLevel::Error,
+            };
+            self.host_env.guest_log.record(level, &message, None);
+            Ok(())
+        }
+    }
+
+    /// Result of executing a component
+    #[derive(Debug, Clone)]
+    pub struct ComponentExecution {
+        /// What the component's `run` export returned
+        pub outcome: Result<ComponentResult, ComponentError>,
+
+        /// Resources consumed during execution
+        pub resource_usage: HashMap<ResourceType, u64>,
+
+        /// Log lines written by the component
+        pub guest_log: GuestLog,
+    }
+
+    /// Create an engine that can compile components
+    pub fn create_component_engine() -> Result<Engine, VmError> {
+        let mut config = Config::new();
+        config.wasm_component_model(true);
+        config.consume_fuel(true);
+
+        Engine::new(&config)
+            .map_err(|e| VmError::EngineCreationFailed(e.to_string()))
+    }
+
+    /// Execute a component targeting the `ccl-module` world for a proposal
+    pub fn execute_component(
+        component_bytes: &[u8],
+        proposal: &ComponentProposal,
+        context: Option<VMContext>,
+        host_env: &ConcreteHostEnvironment,
+    ) -> Result<ComponentExecution, VmError> {
+        let context = context.unwrap_or_default();
+        let engine = create_component_engine()?;
+
+        let component = Component::new(&engine, component_bytes)
+            .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+
+        let mut linker = Linker::new(&engine);
+        bindings::CclModule::add_to_linker(&mut linker, |state: &mut ComponentState| state)
+            .map_err(|e| VmError::InitializationError(e.to_string()))?;
+
+        let mut store = Store::new(&engine, ComponentState { host_env: host_env.clone() });
+
+        let compute_limit = context.authorization_for(ResourceType::Compute)
+            .map_err(|e| VmError::Unauthorized(e.to_string()))?
+            .map_or(1_000_000, |auth| auth.limit);
+        store.add_fuel(pricing::fuel_budget(compute_limit, UNIT_MULTIPLIER))
+            .map_err(|e| VmError::FuelAllocationFailed(e.to_string()))?;
+
+        let (module, _instance) = bindings::CclModule::instantiate(&mut store, &component, &linker)
+            .map_err(|e| VmError::InstantiationFailed(e.to_string()))?;
+
+        debug!(proposal = %proposal.id, action = %proposal.action, "Executing component");
+        let outcome = module.call_run(&mut store, proposal);
+        store.data().host_env.guest_log.close();
+
+        // A run that trapped still pays for the fuel it burned
+        let fuel_consumed = store.fuel_consumed().unwrap_or(0);
+        let host_env = &store.data().host_env;
+        host_env.settle_compute_usage(pricing::compute_for_fuel(fuel_consumed, UNIT_MULTIPLIER));
+
+        let outcome = outcome.map_err(|e| {
+            if e.to_string().contains("out of fuel") {
+                VmError::ResourceLimitExceeded("Execution exceeded fuel limit".to_string())
+            } else {
+                VmError::ExecutionError(e.to_string())
+            }
+        })?;
+
+        Ok(ComponentExecution {
+            outcome,
+            resource_usage: host_env.get_resource_usage(),
+            guest_log: host_env.guest_log(),
+        })
+    }
+}
@@ -0,0 +1,392 @@
+/*!
+# Differential Testing Between VM Builds
+
+Runtime upgrades risk changing how governance modules execute. The differential harness
+runs a corpus of compiled modules, each with its execution context, against two VM
+builds and compares the resulting receipts field by field.
+
+A build is anything implementing [`VmBuild`]. [`InProcessBuild`] is the VM compiled into
+the running binary. [`ExternalBuild`] runs another build's binary, which is called as
+`<program> <args...> <module-path> <context-path>` and must print a
+[`DifferentialReceipt`] as JSON on stdout.
+
+## Corpus layout
+
+A corpus is a directory of `.wasm` or `.wat` modules. A module can have a context file
+with the same stem and a `.json` extension, holding a [`CaseContext`].
+
+## What is compared
+
+- The return code, or the kind of error when execution fails. Error messages are kept in
+  the receipt for diagnosis but are not compared, since they carry engine-specific text.
+- Every resource usage counter.
+- Whether a DAG anchor was created. Anchor CIDs cover wall-clock timestamps, so only
+  their presence is compared.
+*/
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+
+use crate::{ConcreteHostEnvironment, IdentityContext, VMContext, VmError, VmExecutionResult, execute_wasm};
+use crate::resources::{ResourceAuthorization, ResourceType};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage, KeyPair};
+use icn_storage::InMemoryStorageManager;
+
+/// A resource authorization in a case context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseAuthorization {
+    /// Resource type: `compute`, `storage`, `network` or `token`
+    pub resource: String,
+    pub limit: u64,
+}
+
+/// Execution context for a corpus module
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaseContext {
+    /// Caller DID; defaults to `did:icn:differential`
+    pub caller_did: Option<String>,
+    pub authorizations: Vec<CaseAuthorization>,
+    pub proposal_id: Option<String>,
+    pub federation_scope: Option<String>,
+}
+
+impl CaseContext {
+    /// Resource authorizations for the VM context
+    pub fn resource_authorizations(&self) -> Result<Vec<ResourceAuthorization>, VmError> {
+        self.authorizations.iter()
+            .map(|auth| {
+                let resource_type = match auth.resource.to_lowercase().as_str() {
+                    "compute" => ResourceType::Compute,
+                    "storage" => ResourceType::Storage,
+                    "network" => ResourceType::Network,
+                    "token" => ResourceType::Token,
+                    other => return Err(VmError::InitializationError(format!("Unknown resource type {}", other))),
+                };
+                Ok(ResourceAuthorization::new(resource_type, auth.limit, None, "Differential test case".to_string()))
+            })
+            .collect()
+    }
+}
+
+/// A compiled module and the context it runs in
+#[derive(Debug, Clone)]
+pub struct DifferentialCase {
+    pub name: String,
+    pub wasm: Vec<u8>,
+    pub context: CaseContext,
+}
+
+/// The comparable outcome of running a case on one build
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DifferentialReceipt {
+    /// Return code, if execution completed
+    pub code: Option<i32>,
+    /// Kind of error, if execution failed (e.g. `ResourceLimitExceeded`)
+    pub error_kind: Option<String>,
+    /// Error message, if execution failed; not compared
+    pub error: Option<String>,
+    /// Resource usage by resource type
+    pub resource_usage: BTreeMap<String, u64>,
+    pub dag_anchor_cid: Option<String>,
+}
+
+impl DifferentialReceipt {
+    /// Receipt for the result of an execution
+    pub fn from_result(result: &Result<VmExecutionResult, VmError>) -> Self {
+        match result {
+            Ok(result) => Self {
+                code: Some(result.code),
+                resource_usage: result.resource_usage.iter()
+                    .map(|(resource, amount)| (resource.to_string(), *amount))
+                    .collect(),
+                dag_anchor_cid: result.dag_anchor_cid.clone(),
+                ..Default::default()
+            },
+            Err(e) => Self {
+                error_kind: Some(error_kind(e).to_string()),
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Name of a VM error's variant
+pub fn error_kind(error: &VmError) -> &'static str {
+    match error {
+        VmError::InitializationError(_) => "InitializationError",
+        VmError::ExecutionError(_) => "ExecutionError",
+        VmError::ResourceLimitExceeded(_) => "ResourceLimitExceeded",
+        VmError::Unauthorized(_) => "Unauthorized",
+        VmError::MemoryError(_) => "MemoryError",
+        VmError::HostFunctionError(_) => "HostFunctionError",
+        VmError::EngineCreationFailed(_) => "EngineCreationFailed",
+        VmError::ModuleCreationFailed(_) => "ModuleCreationFailed",
+        VmError::FuelAllocationFailed(_) => "FuelAllocationFailed",
+        VmError::InstantiationFailed(_) => "InstantiationFailed",
+        VmError::EntryPointNotFound(_) => "EntryPointNotFound",
+    }
+}
+
+/// A receipt field on which two builds disagree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDifference {
+    /// Field name, e.g. `code` or `resource_usage.compute`
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// Compare two receipts field by field
+pub fn compare_receipts(left: &DifferentialReceipt, right: &DifferentialReceipt) -> Vec<FieldDifference> {
+    fn show<T: std::fmt::Debug>(value: &Option<T>) -> String {
+        value.as_ref().map(|v| format!("{:?}", v)).unwrap_or_else(|| "none".to_string())
+    }
+
+    let mut differences = Vec::new();
+    let mut check = |field: String, left: String, right: String| {
+        if left != right {
+            differences.push(FieldDifference { field, left, right });
+        }
+    };
+
+    check("code".to_string(), show(&left.code), show(&right.code));
+    check("error_kind".to_string(), show(&left.error_kind), show(&right.error_kind));
+
+    let resources: BTreeSet<&String> = left.resource_usage.keys().chain(right.resource_usage.keys()).collect();
+    for resource in resources {
+        check(
+            format!("resource_usage.{}", resource),
+            left.resource_usage.get(resource).copied().unwrap_or(0).to_string(),
+            right.resource_usage.get(resource).copied().unwrap_or(0).to_string(),
+        );
+    }
+
+    check(
+        "dag_anchor_cid".to_string(),
+        if left.dag_anchor_cid.is_some() { "present" } else { "none" }.to_string(),
+        if right.dag_anchor_cid.is_some() { "present" } else { "none" }.to_string(),
+    );
+
+    differences
+}
+
+/// A VM build that can run differential cases
+#[async_trait::async_trait]
+pub trait VmBuild: Send + Sync {
+    /// Name shown in reports
+    fn name(&self) -> &str;
+
+    /// Run a case. Execution failures are reported in the receipt; an `Err` means the
+    /// build itself couldn't be run.
+    async fn execute(&self, case: &DifferentialCase) -> Result<DifferentialReceipt, VmError>;
+}
+
+/// The VM compiled into this binary, run with fresh in-memory storage for every case
+pub struct InProcessBuild {
+    name: String,
+}
+
+impl InProcessBuild {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+impl Default for InProcessBuild {
+    fn default() -> Self {
+        Self::new(concat!("icn-core-vm ", env!("CARGO_PKG_VERSION")))
+    }
+}
+
+#[async_trait::async_trait]
+impl VmBuild for InProcessBuild {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn execute(&self, case: &DifferentialCase) -> Result<DifferentialReceipt, VmError> {
+        let caller_did = case.context.caller_did.as_deref().unwrap_or("did:icn:differential");
+        let identity = Arc::new(IdentityContext::new(KeyPair::generate_random(), caller_did));
+        let vm_context = VMContext::new(identity, case.context.resource_authorizations()?);
+
+        let storage = Arc::new(InMemoryStorageManager::new());
+        let identity_manager = Arc::new(ConcreteIdentityManager::new(
+            Arc::new(InMemoryKeyStorage::default()),
+            Arc::new(InMemoryMetadataStorage::default()),
+        ));
+        let host_env = ConcreteHostEnvironment::new(
+            vm_context.clone(),
+            storage.clone(),
+            identity_manager,
+            case.context.federation_scope.clone(),
+            storage,
+        );
+
+        let result = execute_wasm(
+            &case.wasm,
+            Some(vm_context),
+            &host_env,
+            case.context.proposal_id.as_deref(),
+            case.context.federation_scope.as_deref(),
+        ).await;
+
+        Ok(DifferentialReceipt::from_result(&result))
+    }
+}
+
+/// Another VM build, run as a separate program
+pub struct ExternalBuild {
+    name: String,
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl ExternalBuild {
+    pub fn new(name: impl Into<String>, program: impl Into<PathBuf>, args: Vec<String>) -> Self {
+        Self { name: name.into(), program: program.into(), args }
+    }
+}
+
+#[async_trait::async_trait]
+impl VmBuild for ExternalBuild {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn execute(&self, case: &DifferentialCase) -> Result<DifferentialReceipt, VmError> {
+        let work_dir = std::env::temp_dir().join(format!("icn-vm-diff-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&work_dir)
+            .map_err(|e| VmError::InitializationError(format!("Failed to create work directory: {}", e)))?;
+
+        let module_path = work_dir.join("module.wasm");
+        let context_path = work_dir.join("context.json");
+        let context_json = serde_json::to_vec(&case.context)
+            .map_err(|e| VmError::InitializationError(format!("Failed to serialize case context: {}", e)))?;
+        let written = fs::write(&module_path, &case.wasm).and_then(|_| fs::write(&context_path, context_json));
+
+        let program = self.program.clone();
+        let args = self.args.clone();
+        let output = match written {
+            Ok(()) => tokio::task::spawn_blocking(move || {
+                std::process::Command::new(program)
+                    .args(args)
+                    .arg(&module_path)
+                    .arg(&context_path)
+                    .output()
+            })
+            .await
+            .map_err(|e| VmError::InitializationError(format!("Build {} did not finish: {}", self.name, e)))
+            .and_then(|r| r.map_err(|e| VmError::InitializationError(format!("Failed to run build {}: {}", self.name, e)))),
+            Err(e) => Err(VmError::InitializationError(format!("Failed to write case files: {}", e))),
+        };
+        let _ = fs::remove_dir_all(&work_dir);
+        let output = output?;
+
+        if !output.status.success() {
+            return Err(VmError::InitializationError(format!(
+                "Build {} exited with {}: {}", self.name, output.status, String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| VmError::InitializationError(format!("Build {} printed an invalid receipt: {}", self.name, e)))
+    }
+}
+
+/// Comparison of one case across the two builds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseReport {
+    pub case: String,
+    pub left: DifferentialReceipt,
+    pub right: DifferentialReceipt,
+    pub differences: Vec<FieldDifference>,
+}
+
+/// Outcome of running a corpus against two builds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferentialReport {
+    pub left_build: String,
+    pub right_build: String,
+    pub cases: Vec<CaseReport>,
+}
+
+impl DifferentialReport {
+    /// Whether the builds agreed on every case
+    pub fn is_clean(&self) -> bool {
+        self.cases.iter().all(|c| c.differences.is_empty())
+    }
+
+    /// Cases on which the builds disagreed
+    pub fn divergent_cases(&self) -> impl Iterator<Item = &CaseReport> {
+        self.cases.iter().filter(|c| !c.differences.is_empty())
+    }
+}
+
+/// Run every case on both builds and compare the receipts
+pub async fn run_differential(
+    corpus: &[DifferentialCase],
+    left: &dyn VmBuild,
+    right: &dyn VmBuild,
+) -> Result<DifferentialReport, VmError> {
+    let mut cases = Vec::with_capacity(corpus.len());
+    for case in corpus {
+        let left_receipt = left.execute(case).await?;
+        let right_receipt = right.execute(case).await?;
+        let differences = compare_receipts(&left_receipt, &right_receipt);
+        if !differences.is_empty() {
+            tracing::warn!("Builds diverge on {}: {} field(s) differ", case.name, differences.len());
+        }
+        cases.push(CaseReport { case: case.name.clone(), left: left_receipt, right: right_receipt, differences });
+    }
+
+    Ok(DifferentialReport {
+        left_build: left.name().to_string(),
+        right_build: right.name().to_string(),
+        cases,
+    })
+}
+
+/// Load a single module and its optional context file
+pub fn load_case(module_path: &Path) -> Result<DifferentialCase, VmError> {
+    let name = module_path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .ok_or_else(|| VmError::InitializationError(format!("Invalid module path {}", module_path.display())))?;
+
+    let wasm = match module_path.extension().and_then(|e| e.to_str()) {
+        Some("wat") => wat::parse_file(module_path)
+            .map_err(|e| VmError::ModuleCreationFailed(format!("{}: {}", module_path.display(), e)))?,
+        _ => fs::read(module_path)
+            .map_err(|e| VmError::InitializationError(format!("Failed to read {}: {}", module_path.display(), e)))?,
+    };
+
+    let context_path = module_path.with_extension("json");
+    let context = if context_path.exists() {
+        let bytes = fs::read(&context_path)
+            .map_err(|e| VmError::InitializationError(format!("Failed to read {}: {}", context_path.display(), e)))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| VmError::InitializationError(format!("Invalid context {}: {}", context_path.display(), e)))?
+    } else {
+        CaseContext::default()
+    };
+
+    Ok(DifferentialCase { name, wasm, context })
+}
+
+/// Load every module in a corpus directory, ordered by name
+pub fn load_corpus(dir: &Path) -> Result<Vec<DifferentialCase>, VmError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| VmError::InitializationError(format!("Failed to read corpus {}: {}", dir.display(), e)))?;
+
+    let mut module_paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("wasm") | Some("wat")))
+        .collect();
+    module_paths.sort();
+
+    module_paths.iter().map(|path| load_case(path)).collect()
+}
@@ -2,7 +2,7 @@ use anyhow::Error as AnyhowError;
 use wasmtime::Linker;
 use crate::ConcreteHostEnvironment;
 use crate::mem_helpers::{read_memory_string};
-use crate::host_abi::create_trap;
+use crate::host_abi::{create_trap, map_internal_error_to_wasm};
 use crate::resources::ResourceType;
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -44,12 +44,10 @@ pub fn register_economics_functions(linker: &mut Linker<ConcreteHostEnvironment>
         
         // Check if the amount is below the authorized limit
         let host_env = caller.data();
-        let auth_limits = host_env.vm_context.resource_authorizations();
-        
-        let authorized = auth_limits.iter()
-            .find(|auth| auth.resource_type == res_type)
-            .map(|auth| auth.limit >= amount as u64)
-            .unwrap_or(false);
+        let authorized = match host_env.vm_context.authorization_for(res_type) {
+            Ok(auth) => auth.map(|auth| auth.limit >= amount as u64).unwrap_or(false),
+            Err(e) => return Ok(map_internal_error_to_wasm(e)),
+        };
         
         // Return 1 for authorized, 0 for not authorized
         Ok(if authorized { 1 } else { 0 })
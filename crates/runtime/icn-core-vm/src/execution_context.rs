@@ -0,0 +1,72 @@
+/*!
+# Execution Context Introspection
+
+A module can ask which execution it is part of, so governance logic can reference its
+own provenance (e.g., tag what it anchors with the proposal that ran it) without being
+recompiled for every execution.
+
+`host_get_execution_context(out_ptr, max_len) -> i32` writes the context as a DAG-CBOR
+map into guest memory and returns its length:
+
+- `proposal_cid`: the proposal the module executes on behalf of, or null
+- `scope`: the federation scope of the execution, or the tenant's scope without one
+- `execution_id`: the ID of this execution
+- `timestamp`: when the execution started (Unix timestamp)
+
+If the context doesn't fit in `max_len` bytes nothing is written and the host ABI's
+argument error code is returned. Reading the context is always allowed and free.
+*/
+
+use serde::{Serialize, Deserialize};
+use wasmtime::{Caller, Linker, Trap};
+use tracing::*;
+use crate::{ConcreteHostEnvironment, InternalHostError};
+use crate::host_abi::{map_abi_error_to_wasm, map_internal_error_to_wasm};
+
+/// The part of an execution's context a module can read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestExecutionContext {
+    pub proposal_cid: Option<String>,
+    pub scope: String,
+    pub execution_id: String,
+    pub timestamp: i64,
+}
+
+impl GuestExecutionContext {
+    /// The context as the module receives it
+    pub fn to_cbor(&self) -> Result<Vec<u8>, InternalHostError> {
+        serde_ipld_dagcbor::to_vec(self)
+            .map_err(|e| InternalHostError::CodecError(format!("Failed to encode execution context: {}", e)))
+    }
+}
+
+fn host_get_execution_context_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    out_ptr: i32,
+    max_len: i32,
+) -> Result<i32, Trap> {
+    let context = caller.data().execution_context();
+    debug!(execution_id = %context.execution_id, "Host ABI: host_get_execution_context called");
+
+    let bytes = match context.to_cbor() {
+        Ok(bytes) => bytes,
+        Err(e) => return Ok(map_internal_error_to_wasm(e)),
+    };
+
+    if bytes.len() > max_len.max(0) as usize {
+        return Ok(map_abi_error_to_wasm(anyhow::anyhow!(
+            "Output buffer too small: required {}, max {}", bytes.len(), max_len
+        )));
+    }
+
+    match crate::mem_helpers::write_memory_bytes(&mut caller, out_ptr, &bytes) {
+        Ok(()) => Ok(bytes.len() as i32),
+        Err(e) => Ok(map_abi_error_to_wasm(e)),
+    }
+}
+
+/// Register execution context host functions
+pub fn register_execution_context_functions(linker: &mut Linker<ConcreteHostEnvironment>) -> Result<(), wasmtime::Error> {
+    linker.func_wrap("env", "host_get_execution_context", host_get_execution_context_wrapper)?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+/*!
+# Execution Policy
+
+Permission checks at proposal creation say who may submit a module, not what the
+module may do once it runs. An [`ExecutionPolicy`] closes that gap: it lists the host
+functions the acting identity's roles allow in the execution's scope, and every other
+host function traps with [`HostActionDenied`] when the module calls it, even though
+the module imported it. The governance kernel derives the policy from the roles it
+has verified for the proposer; the VM only enforces it.
+
+Actions are host function names (e.g., `anchor_to_dag`); `*` allows every function.
+Logging and reading the execution context are always allowed. Gating works by
+re-registering the denied functions of the linker for the execution's store behind a
+trapping stub, the same way the syscall audit wraps them, so denied calls still show
+up in the audit log as trapped.
+*/
+
+use std::collections::BTreeSet;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use wasmtime::{Caller, Engine, Extern, Linker, Store};
+
+use crate::{ConcreteHostEnvironment, VmError};
+
+/// Allows every host function
+pub const ALLOW_ALL: &str = "*";
+
+/// Host functions a policy can't deny
+pub const ALWAYS_ALLOWED: &[&str] = &["host_log", "host_log_message", "host_get_execution_context"];
+
+/// The host functions an execution may call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionPolicy {
+    /// Scope the policy was derived in
+    pub scope: String,
+    /// Identity the module executes on behalf of
+    pub actor_did: String,
+    /// Roles the allowed actions come from
+    pub roles: Vec<String>,
+    /// Allowed host function names, or `*`
+    pub allowed_actions: BTreeSet<String>,
+}
+
+impl ExecutionPolicy {
+    /// A policy allowing only the given actions
+    pub fn new(scope: &str, actor_did: &str, roles: Vec<String>, allowed_actions: BTreeSet<String>) -> Self {
+        Self {
+            scope: scope.to_string(),
+            actor_did: actor_did.to_string(),
+            roles,
+            allowed_actions,
+        }
+    }
+
+    /// A policy allowing every host function
+    pub fn unrestricted(scope: &str, actor_did: &str) -> Self {
+        Self::new(scope, actor_did, Vec::new(), BTreeSet::from([ALLOW_ALL.to_string()]))
+    }
+
+    /// Whether the policy allows every host function
+    pub fn is_unrestricted(&self) -> bool {
+        self.allowed_actions.contains(ALLOW_ALL)
+    }
+
+    /// Whether the module may call a host function
+    pub fn allows(&self, action: &str) -> bool {
+        self.is_unrestricted()
+            || ALWAYS_ALLOWED.contains(&action)
+            || self.allowed_actions.contains(action)
+    }
+}
+
+/// A host call the execution policy doesn't allow
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("host call {action} is not allowed for {actor_did} (roles: {roles:?}) in scope {scope}")]
+pub struct HostActionDenied {
+    pub action: String,
+    pub actor_did: String,
+    pub roles: Vec<String>,
+    pub scope: String,
+}
+
+/// A linker whose functions the policy denies trap with [`HostActionDenied`].
+///
+/// The functions of `linker` are instantiated into `store`, so the returned linker can
+/// only be used with that store.
+pub(crate) fn gated_linker(
+    engine: &Engine,
+    linker: &Linker<ConcreteHostEnvironment>,
+    store: &mut Store<ConcreteHostEnvironment>,
+    policy: &ExecutionPolicy,
+) -> Result<Linker<ConcreteHostEnvironment>, VmError> {
+    let definitions: Vec<(String, String, Extern)> = linker.iter(&mut *store)
+        .map(|(module, name, item)| (module.to_string(), name.to_string(), item))
+        .collect();
+
+    let mut gated = Linker::new(engine);
+    for (module, name, item) in definitions {
+        let func = match item {
+            Extern::Func(func) if !policy.allows(&name) => func,
+            other => {
+                gated.define(&mut *store, &module, &name, other)
+                    .map_err(|e| VmError::EngineCreationFailed(format!("Failed to define {}::{}: {}", module, name, e)))?;
+                continue;
+            }
+        };
+
+        let ty = func.ty(&*store);
+        let denied = HostActionDenied {
+            action: name.clone(),
+            actor_did: policy.actor_did.clone(),
+            roles: policy.roles.clone(),
+            scope: policy.scope.clone(),
+        };
+        gated.func_new(&module, &name, ty, move |_caller: Caller<'_, ConcreteHostEnvironment>, _params, _results| {
+            Err(denied.clone().into())
+        }).map_err(|e| VmError::EngineCreationFailed(format!("Failed to gate {}::{}: {}", module, name, e)))?;
+    }
+
+    Ok(gated)
+}
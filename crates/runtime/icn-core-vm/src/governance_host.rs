@@ -0,0 +1,211 @@
+/*!
+# Governance Host API
+
+Compiled workflows can create follow-up proposals and cast votes through two host
+functions:
+
+- `env::host_create_proposal(req_ptr, req_len, out_ptr, out_cap) -> i32` takes a
+  JSON-encoded [`ProposalDraft`] and writes the new proposal's ID to `out_ptr`,
+  returning the number of bytes written.
+- `env::host_cast_vote(id_ptr, id_len, choice) -> i32` votes on a proposal with a
+  [`VoteChoice`] code (0 = for, 1 = against, 2 = abstain), returning 0.
+
+Both act as the identity the module executes on behalf of. The VM doesn't decide
+whether that identity may create proposals or vote: the host environment forwards
+the request to a [`GovernanceHost`] (the governance kernel), which applies the same
+permission checks as its external API. Negative return codes report why a request
+was refused; see the `GOVERNANCE_*` constants.
+*/
+
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use wasmtime::{Caller, Linker, Trap};
+use tracing::*;
+use icn_identity::IdentityScope;
+use crate::{ConcreteHostEnvironment, InternalHostError};
+use crate::host_abi::{map_abi_error_to_wasm, map_internal_error_to_wasm, map_vm_error_to_wasm};
+use crate::mem_helpers::{read_memory_string, with_guest_bytes, write_memory_bytes};
+use crate::pricing::HostCallClass;
+
+/// Base compute cost of a governance host call
+const GOVERNANCE_CALL_COST: u64 = 500;
+
+/// The caller isn't allowed to perform the request
+pub const GOVERNANCE_UNAUTHORIZED: i32 = -8;
+
+/// No governance host is attached to the execution
+pub const GOVERNANCE_UNAVAILABLE: i32 = -9;
+
+/// The governance host refused or failed the request
+pub const GOVERNANCE_REJECTED: i32 = -10;
+
+/// A proposal created by a module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalDraft {
+    pub scope: IdentityScope,
+    pub scope_id: String,
+    pub title: String,
+    pub description: String,
+
+    /// Proposal type from the scope's proposal process
+    #[serde(default)]
+    pub proposal_type: Option<String>,
+
+    /// CCL the proposal executes if it passes
+    #[serde(default)]
+    pub ccl_code: Option<String>,
+
+    /// Voting period end (Unix timestamp)
+    #[serde(default)]
+    pub voting_end_time: Option<i64>,
+
+    /// Proposal whose execution created this one, if any
+    #[serde(default)]
+    pub parent_proposal_id: Option<String>,
+}
+
+/// A vote cast by a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoteChoice {
+    For,
+    Against,
+    Abstain,
+}
+
+impl VoteChoice {
+    /// Choice for the code a module passes to `host_cast_vote`
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(VoteChoice::For),
+            1 => Some(VoteChoice::Against),
+            2 => Some(VoteChoice::Abstain),
+            _ => None,
+        }
+    }
+}
+
+/// Why a governance request from a module was refused
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GovernanceHostError {
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Request rejected: {0}")]
+    Rejected(String),
+}
+
+impl GovernanceHostError {
+    /// Code returned to the module
+    pub fn code(&self) -> i32 {
+        match self {
+            GovernanceHostError::Unauthorized(_) => GOVERNANCE_UNAUTHORIZED,
+            GovernanceHostError::Rejected(_) => GOVERNANCE_REJECTED,
+        }
+    }
+}
+
+/// Governance operations available to modules, implemented by the governance kernel
+#[async_trait]
+pub trait GovernanceHost: Send + Sync {
+    /// Create a proposal on behalf of `caller`. Returns the proposal ID.
+    async fn create_proposal(&self, caller: &str, draft: ProposalDraft) -> Result<String, GovernanceHostError>;
+
+    /// Vote on a proposal on behalf of `caller`
+    async fn cast_vote(&self, caller: &str, proposal_id: &str, choice: VoteChoice) -> Result<(), GovernanceHostError>;
+}
+
+fn host_create_proposal_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    req_ptr: i32,
+    req_len: i32,
+    out_ptr: i32,
+    out_cap: i32,
+) -> Result<i32, Trap> {
+    let draft: ProposalDraft = match with_guest_bytes(&mut caller, req_ptr, req_len, |request, _| serde_json::from_slice(request)) {
+        Ok(Ok(draft)) => draft,
+        Ok(Err(e)) => return Ok(map_internal_error_to_wasm(InternalHostError::InvalidInput(
+            format!("Invalid proposal draft: {}", e)
+        ))),
+        Err(e) => return Ok(map_abi_error_to_wasm(e)),
+    };
+
+    if let Err(e) = caller.data().record_host_call(HostCallClass::Governance, GOVERNANCE_CALL_COST) {
+        return Ok(map_vm_error_to_wasm(e));
+    }
+
+    let governance = match caller.data().governance_host() {
+        Some(governance) => governance,
+        None => return Ok(GOVERNANCE_UNAVAILABLE),
+    };
+    let caller_did = caller.data().caller_did().to_string();
+    debug!(caller = %caller_did, title = %draft.title, "host_create_proposal called");
+
+    let result = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(governance.create_proposal(&caller_did, draft))
+    });
+    let proposal_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            warn!(caller = %caller_did, error = %e, "Module proposal refused");
+            return Ok(e.code());
+        }
+    };
+
+    if proposal_id.len() > out_cap.max(0) as usize {
+        return Ok(map_abi_error_to_wasm(anyhow::anyhow!(
+            "Output buffer too small: required {}, max {}", proposal_id.len(), out_cap
+        )));
+    }
+    match write_memory_bytes(&mut caller, out_ptr, proposal_id.as_bytes()) {
+        Ok(()) => Ok(proposal_id.len() as i32),
+        Err(e) => Ok(map_abi_error_to_wasm(e)),
+    }
+}
+
+fn host_cast_vote_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    id_ptr: i32,
+    id_len: i32,
+    choice: i32,
+) -> Result<i32, Trap> {
+    let proposal_id = match read_memory_string(&mut caller, id_ptr, id_len) {
+        Ok(id) => id,
+        Err(e) => return Ok(map_abi_error_to_wasm(e)),
+    };
+    let choice = match VoteChoice::from_code(choice) {
+        Some(choice) => choice,
+        None => return Ok(map_internal_error_to_wasm(InternalHostError::InvalidInput(
+            format!("Invalid vote choice: {}", choice)
+        ))),
+    };
+
+    if let Err(e) = caller.data().record_host_call(HostCallClass::Governance, GOVERNANCE_CALL_COST) {
+        return Ok(map_vm_error_to_wasm(e));
+    }
+
+    let governance = match caller.data().governance_host() {
+        Some(governance) => governance,
+        None => return Ok(GOVERNANCE_UNAVAILABLE),
+    };
+    let caller_did = caller.data().caller_did().to_string();
+    debug!(caller = %caller_did, proposal = %proposal_id, ?choice, "host_cast_vote called");
+
+    let result = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(governance.cast_vote(&caller_did, &proposal_id, choice))
+    });
+    match result {
+        Ok(()) => Ok(0),
+        Err(e) => {
+            warn!(caller = %caller_did, error = %e, "Module vote refused");
+            Ok(e.code())
+        }
+    }
+}
+
+/// Register governance host functions
+pub fn register_governance_functions(linker: &mut Linker<ConcreteHostEnvironment>) -> Result<(), wasmtime::Error> {
+    linker.func_wrap("env", "host_create_proposal", host_create_proposal_wrapper)?;
+    linker.func_wrap("env", "host_cast_vote", host_cast_vote_wrapper)?;
+    Ok(())
+}
@@ -0,0 +1,201 @@
+/*!
+# Guest Log Capture
+
+Modules log through `env::host_log` (level, pointer, length) or the CCL compiler's
+`env::host_log_message` (pointer, length; logged at Info). Every line is captured as a
+[`GuestLogEntry`] with its level, a timestamp and the code offset of the call in the
+module, and is still mirrored to the host's tracing output.
+
+Capture is bounded by [`GuestLogLimits`]: lines below the minimum level are skipped,
+over-long messages are cut, and once the line or byte limit is reached a single
+truncation marker is appended and further lines are only counted. Long-running
+executions can be followed live with [`GuestLogCapture::subscribe`], which replays
+the lines captured so far and then streams new ones until the execution finishes.
+*/
+
+use std::sync::Mutex;
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+
+/// Severity of a guest log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GuestLogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl GuestLogLevel {
+    /// Level for the code a module passes to `host_log`; unknown codes log at Info
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => GuestLogLevel::Debug,
+            2 => GuestLogLevel::Warn,
+            3 => GuestLogLevel::Error,
+            _ => GuestLogLevel::Info,
+        }
+    }
+}
+
+/// One captured log line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestLogEntry {
+    /// Position of the line in the log, starting at 0
+    pub sequence: u64,
+    pub level: GuestLogLevel,
+    /// When the line was logged (Unix milliseconds)
+    pub timestamp_ms: i64,
+    /// Code offset in the module of the call that logged the line, if known
+    pub source_offset: Option<usize>,
+    pub message: String,
+    /// Whether the message was cut to fit, or this is the truncation marker
+    pub truncated: bool,
+}
+
+/// Bounds on what one execution may log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestLogLimits {
+    /// Lines below this level are skipped
+    pub min_level: GuestLogLevel,
+    /// Lines kept before the log is truncated
+    pub max_lines: usize,
+    /// Message bytes kept before the log is truncated
+    pub max_bytes: usize,
+    /// Longest message kept; longer ones are cut
+    pub max_message_bytes: usize,
+}
+
+impl Default for GuestLogLimits {
+    fn default() -> Self {
+        Self {
+            min_level: GuestLogLevel::Debug,
+            max_lines: 1_000,
+            max_bytes: 64 * 1024,
+            max_message_bytes: 4 * 1024,
+        }
+    }
+}
+
+/// Message of the marker appended when the log reaches its limits
+pub const TRUNCATION_MARKER: &str = "[guest log truncated: line or byte limit reached]";
+
+/// The log captured for an execution
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuestLog {
+    pub entries: Vec<GuestLogEntry>,
+    /// Lines dropped after the log was truncated
+    pub dropped: u64,
+    pub truncated: bool,
+}
+
+#[derive(Default)]
+struct CaptureState {
+    log: GuestLog,
+    bytes: usize,
+    subscribers: Vec<mpsc::UnboundedSender<GuestLogEntry>>,
+}
+
+impl CaptureState {
+    fn push(&mut self, entry: GuestLogEntry) {
+        self.subscribers.retain(|tx| tx.send(entry.clone()).is_ok());
+        self.log.entries.push(entry);
+    }
+}
+
+/// Captures the log lines of executions under a set of limits
+#[derive(Default)]
+pub struct GuestLogCapture {
+    limits: GuestLogLimits,
+    state: Mutex<CaptureState>,
+}
+
+impl GuestLogCapture {
+    /// Capture logs under the given limits
+    pub fn new(limits: GuestLogLimits) -> Self {
+        Self { limits, state: Mutex::new(CaptureState::default()) }
+    }
+
+    /// The limits logs are captured under
+    pub fn limits(&self) -> &GuestLogLimits {
+        &self.limits
+    }
+
+    /// Capture a line. Returns whether it was kept.
+    pub fn record(&self, level: GuestLogLevel, message: &str, source_offset: Option<usize>) -> bool {
+        if level < self.limits.min_level {
+            return false;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if state.log.truncated {
+            state.log.dropped += 1;
+            return false;
+        }
+
+        let timestamp_ms = chrono::Utc::now().timestamp_millis();
+        let sequence = state.log.entries.len() as u64;
+
+        if state.log.entries.len() >= self.limits.max_lines
+            || state.bytes.saturating_add(message.len().min(self.limits.max_message_bytes)) > self.limits.max_bytes
+        {
+            state.log.truncated = true;
+            state.log.dropped += 1;
+            state.push(GuestLogEntry {
+                sequence,
+                level: GuestLogLevel::Warn,
+                timestamp_ms,
+                source_offset,
+                message: TRUNCATION_MARKER.to_string(),
+                truncated: true,
+            });
+            return false;
+        }
+
+        let (message, truncated) = truncate_message(message, self.limits.max_message_bytes);
+        state.bytes += message.len();
+        state.push(GuestLogEntry {
+            sequence,
+            level,
+            timestamp_ms,
+            source_offset,
+            message: message.to_string(),
+            truncated,
+        });
+        true
+    }
+
+    /// Follow the log: the lines captured so far, then new lines as they are logged.
+    /// The stream ends when the execution finishes.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<GuestLogEntry> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut state = self.state.lock().unwrap();
+        for entry in &state.log.entries {
+            let _ = tx.send(entry.clone());
+        }
+        state.subscribers.push(tx);
+        rx
+    }
+
+    /// The lines captured so far
+    pub fn snapshot(&self) -> GuestLog {
+        self.state.lock().unwrap().log.clone()
+    }
+
+    /// End the streams of current subscribers
+    pub fn close(&self) {
+        self.state.lock().unwrap().subscribers.clear();
+    }
+}
+
+/// Cut a message to at most `max_bytes`, on a character boundary
+fn truncate_message(message: &str, max_bytes: usize) -> (&str, bool) {
+    if message.len() <= max_bytes {
+        return (message, false);
+    }
+    let mut end = max_bytes;
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&message[..end], true)
+}
@@ -12,7 +12,9 @@ use crate::{
     ConcreteHostEnvironment, VmError, ResourceType,
     InternalHostError
 };
-use crate::mem_helpers::{read_memory_string, write_memory_string, read_memory_bytes, safe_check_bounds};
+use crate::mem_helpers::{read_memory_string, write_memory_string, read_memory_bytes, checked_range, with_guest_bytes, with_guest_str};
+use crate::pricing::HostCallClass;
+use crate::guest_log::GuestLogLevel;
 use wasmtime::{Caller, Linker, Memory, Trap, WasmBacktrace};
 use tracing::*;
 use anyhow::{anyhow, Error};
@@ -35,7 +37,7 @@ const MAX_ALLOCATION_SIZE: u32 = 1024 * 1024 * 10; // 10 MB
 type HostAbiResult<T> = Result<T, Error>;
 
 /// Maps InternalHostError to a negative i32 code for WASM return.
-fn map_internal_error_to_wasm(err: InternalHostError) -> i32 {
+pub(crate) fn map_internal_error_to_wasm(err: InternalHostError) -> i32 {
     error!(error = %err, "Internal host error during ABI call");
     match err {
         InternalHostError::IdentityError(_) => -1,
@@ -45,19 +47,20 @@ fn map_internal_error_to_wasm(err: InternalHostError) -> i32 {
         InternalHostError::InvalidInput(_) => -5,
         InternalHostError::ConfigurationError(_) => -6,
         InternalHostError::VmError(_) => -7,
+        InternalHostError::UnauthorizedAccess(_) => -8,
         InternalHostError::Other(_) => -99, // Generic internal error
     }
 }
 
 /// Function to map anyhow::Error from helpers to i32 WASM error code
 /// Using a distinct code for memory/ABI argument errors
-fn map_abi_error_to_wasm(err: Error) -> i32 {
+pub(crate) fn map_abi_error_to_wasm(err: Error) -> i32 {
     error!(error = %err, "Host ABI argument/memory error");
     -101 // Example: Generic ABI error code
 }
 
 /// Function to map VmError (e.g., resource limit) to i32 WASM error code
-fn map_vm_error_to_wasm(err: VmError) -> i32 {
+pub(crate) fn map_vm_error_to_wasm(err: VmError) -> i32 {
     error!(error = %err, "Host resource/VM error");
     match err {
         VmError::ResourceLimitExceeded(_) => -102,
@@ -69,8 +72,8 @@ fn map_vm_error_to_wasm(err: VmError) -> i32 {
 fn check_compute(caller: &mut Caller<'_, ConcreteHostEnvironment>, cost: u64) -> Result<(), i32> {
      let env = caller.data();
      let current = env.get_compute_consumed();
-     let limit = env.vm_context.resource_authorizations().iter()
-         .find(|auth| auth.resource_type == ResourceType::Compute)
+     let limit = env.vm_context.authorization_for(ResourceType::Compute)
+         .map_err(map_internal_error_to_wasm)?
          .map_or(0, |auth| auth.limit);
 
      if current.saturating_add(cost) > limit {
@@ -90,9 +93,9 @@ fn safe_read_bytes(
     let memory = caller.get_export("memory")
         .and_then(|exp| exp.into_memory())
         .ok_or_else(|| anyhow!("Memory export not found"))?;
-    safe_check_bounds(&memory, caller, ptr, len)?;
     let data = memory.data(caller);
-    Ok(data[ptr as usize..(ptr + len) as usize].to_vec())
+    let range = checked_range(data.len(), ptr as usize, len as usize)?;
+    Ok(data[range].to_vec())
 }
 
 /// Safely read string, returning HostAbiResult
@@ -101,6 +104,9 @@ fn safe_read_string(
     ptr: u32,
     len: u32,
 ) -> HostAbiResult<String> {
+    if len as usize > MAX_STRING_LENGTH {
+        return Err(anyhow!("String too long: len={}, max={}", len, MAX_STRING_LENGTH));
+    }
     let bytes = safe_read_bytes(caller, ptr, len)?;
     String::from_utf8(bytes).map_err(|e| anyhow!("Invalid UTF-8 sequence: {}", e))
 }
@@ -122,9 +128,9 @@ fn safe_write_bytes(
         .ok_or_else(|| anyhow!("Memory export not found"))?;
 
     // Check bounds *before* writing
-    safe_check_bounds(&memory, caller, out_ptr, bytes_len as u32)?;
+    let range = checked_range(memory.data_size(&*caller), out_ptr as usize, bytes_len)?;
 
-    memory.write(caller, out_ptr as usize, bytes)
+    memory.write(caller, range.start, bytes)
         .map_err(|e| anyhow!("Memory write failed: {}", e))?;
 
     Ok(bytes_len)
@@ -148,28 +154,32 @@ fn read_vec_of_bytes(
     count: u32,
     lens_ptr: u32,
 ) -> HostAbiResult<(Vec<Vec<u8>>, u64)> { // Return cost as well
-    let mut vecs = Vec::with_capacity(count as usize);
-    let mut cost = 0u64;
     let memory = caller.get_export("memory")
         .and_then(|exp| exp.into_memory())
         .ok_or_else(|| anyhow!("Memory export not found"))?;
 
-    // Check bounds for reading the pointer array and length array
-    safe_check_bounds(&memory, caller, ptr_ptr, count * 4)?;
-    safe_check_bounds(&memory, caller, lens_ptr, count * 4)?;
+    // Check bounds for reading the pointer array and length array before allocating
+    // anything for `count` entries
+    let table_len = (count as usize).checked_mul(4)
+        .ok_or_else(|| anyhow!("Pointer table would overflow: count={}", count))?;
+    let mem_size = memory.data_size(&*caller);
+    let ptrs = checked_range(mem_size, ptr_ptr as usize, table_len)?;
+    let lens = checked_range(mem_size, lens_ptr as usize, table_len)?;
 
-    let data = memory.data(caller);
+    let mut vecs = Vec::with_capacity(count as usize);
+    let mut cost = 0u64;
+    for i in 0..count as usize {
+        let data = memory.data(&*caller);
 
-    for i in 0..count {
         // Read the pointer to the i-th byte vector
-        let current_ptr_offset = (ptr_ptr + i * 4) as usize;
+        let current_ptr_offset = ptrs.start + i * 4;
         let current_ptr_bytes: [u8; 4] = data[current_ptr_offset..current_ptr_offset + 4]
             .try_into()
             .map_err(|_| anyhow!("Failed to read pointer bytes"))?;
         let current_ptr = u32::from_le_bytes(current_ptr_bytes);
 
         // Read the length of the i-th byte vector
-        let current_len_offset = (lens_ptr + i * 4) as usize;
+        let current_len_offset = lens.start + i * 4;
         let current_len_bytes: [u8; 4] = data[current_len_offset..current_len_offset + 4]
             .try_into()
             .map_err(|_| anyhow!("Failed to read length bytes"))?;
@@ -177,7 +187,7 @@ fn read_vec_of_bytes(
 
         // Read the actual bytes using safe_read_bytes (which does its own bounds check)
         let bytes = safe_read_bytes(caller, current_ptr, current_len)?;
-        cost += current_len as u64;
+        cost = cost.saturating_add(current_len as u64);
         vecs.push(bytes);
     }
 
@@ -417,10 +427,10 @@ fn host_check_resource_authorization_wrapper(
     let env = caller.data();
     
     // Check if the caller has authorization for this resource usage
-    let authorized = env.vm_context.resource_authorizations().iter()
-        .find(|auth| auth.resource_type == res_type)
-        .map(|auth| auth.limit >= amount as u64)
-        .unwrap_or(false);
+    let authorized = match env.vm_context.authorization_for(res_type) {
+        Ok(auth) => auth.map(|auth| auth.limit >= amount as u64).unwrap_or(false),
+        Err(e) => return Ok(map_internal_error_to_wasm(e)),
+    };
     
     // Return 1 for authorized, 0 for not authorized
     Ok(if authorized { 1 } else { 0 })
@@ -473,33 +483,49 @@ fn host_anchor_to_dag_wrapper(
 ) -> Result<i32, Trap> {
     debug!(ptr, len, "host_anchor_to_dag called");
     
-    // Read the anchor payload from memory
-    let anchor_bytes = match safe_read_bytes(&mut caller, ptr as u32, len as u32) {
-        Ok(bytes) => bytes,
-        Err(e) => return Ok(map_abi_error_to_wasm(e)),
-    };
+    // Record compute cost for this operation
+    if let Err(e) = caller.data().record_host_call(HostCallClass::Dag, 100 + (len.max(0) as u64) / 10) {
+        return Ok(map_vm_error_to_wasm(e));
+    }
     
-    // Convert bytes to string (assuming JSON or similar text format)
-    let anchor_str = match String::from_utf8(anchor_bytes) {
-        Ok(s) => s,
+    // Anchor the payload (JSON or similar text) straight from guest memory
+    let handle = tokio::runtime::Handle::current();
+    let result = with_guest_str(&mut caller, ptr, len, |anchor_str, env| {
+        handle.block_on(env.anchor_metadata_to_dag(anchor_str))
+    });
+    
+    match result {
+        Ok(Ok(_)) => Ok(0), // Success
+        Ok(Err(e)) => Ok(map_internal_error_to_wasm(e)),
         Err(e) => {
-            error!("Invalid UTF-8 in anchor payload: {}", e);
-            return Ok(map_abi_error_to_wasm(anyhow::anyhow!("Invalid UTF-8 in anchor payload")));
+            error!("Invalid anchor payload: {}", e);
+            Ok(map_abi_error_to_wasm(e))
         }
-    };
-    
-    // Record compute cost for this operation
-    let env = caller.data();
-    if let Err(e) = env.record_compute_usage(100 + (len as u64) / 10) {
+    }
+}
+
+/// Capture a log line from the module. Returns 0 if the line was kept, 1 if it was
+/// skipped or dropped by the log limits.
+fn host_log(
+    caller: &mut Caller<'_, ConcreteHostEnvironment>,
+    level: GuestLogLevel,
+    msg_ptr: i32,
+    msg_len: i32,
+) -> Result<i32, Trap> {
+    if let Err(e) = caller.data().record_host_call(HostCallClass::Logging, 10 + (msg_len.max(0) as u64) / 100) {
         return Ok(map_vm_error_to_wasm(e));
     }
     
-    // Call the host environment to anchor the data
-    let handle = tokio::runtime::Handle::current();
+    // Offset of the logging call in the module's code
+    let source_offset = WasmBacktrace::capture(&*caller)
+        .frames()
+        .first()
+        .and_then(|frame| frame.module_offset());
     
-    match handle.block_on(env.anchor_metadata_to_dag(&anchor_str)) {
-        Ok(_) => Ok(0), // Success
-        Err(e) => Ok(map_internal_error_to_wasm(e)),
+    match with_guest_str(caller, msg_ptr, msg_len, |message, env| env.record_guest_log(level, message, source_offset)) {
+        Ok(true) => Ok(0),
+        Ok(false) => Ok(1),
+        Err(e) => Ok(map_abi_error_to_wasm(e)),
     }
 }
 
@@ -699,25 +725,20 @@ fn host_store_dag_node_wrapper(
 ) -> Result<i32, Trap> {
     debug!("host_store_dag_node called with ptr: {}, len: {}", ptr, len);
     
-    // Read the serialized DagNode from WASM memory
-    let node_bytes = match safe_read_bytes(&mut caller, ptr, len) {
-        Ok(bytes) => bytes,
-        Err(e) => return Ok(map_abi_error_to_wasm(e)),
-    };
-    
     // Record compute cost for this operation
-    if let Err(code) = check_compute(&mut caller, 1000 + (node_bytes.len() as u64) / 10) {
+    if let Err(code) = check_compute(&mut caller, 1000 + (len as u64) / 10) {
         return Ok(code);
     }
     
-    // Deserialize the node
+    // Deserialize the node from WASM memory
     let codec = dag_storage_codec();
-    let node: DagNode = match codec.decode(&node_bytes) {
-        Ok(node) => node,
-        Err(e) => {
+    let node: DagNode = match with_guest_bytes(&mut caller, ptr as i32, len as i32, |node_bytes, _| codec.decode(node_bytes)) {
+        Ok(Ok(node)) => node,
+        Ok(Err(e)) => {
             error!("Failed to deserialize DagNode: {}", e);
             return Ok(-4); // Codec error
         }
+        Err(e) => return Ok(map_abi_error_to_wasm(e)),
     };
     
     // Call the host environment to store the node
@@ -864,7 +885,7 @@ fn host_get_execution_receipts_wrapper(
     
     // Record compute cost for this operation
     let env = caller.data();
-    if let Err(e) = env.record_compute_usage(200 + (scope_len as u64) / 10) {
+    if let Err(e) = env.record_host_call(HostCallClass::StorageRead, 200 + (scope_len as u64) / 10) {
         return Ok(map_vm_error_to_wasm(e));
     }
     
@@ -1335,6 +1356,24 @@ pub fn register_host_functions(
         }
     ).map_err(|e| VmError::EngineCreationFailed(format!("Failed to register set_value: {}", e)))?;
     
+    // Guest logging
+    linker.func_wrap(
+        "env", 
+        "host_log", 
+        |mut caller: Caller<'_, ConcreteHostEnvironment>, level: i32, msg_ptr: i32, msg_len: i32| -> Result<i32, Trap> {
+            host_log(&mut caller, GuestLogLevel::from_code(level), msg_ptr, msg_len)
+        }
+    ).map_err(|e| VmError::EngineCreationFailed(format!("Failed to register host_log: {}", e)))?;
+    
+    // The CCL compiler's log import carries no level
+    linker.func_wrap(
+        "env", 
+        "host_log_message", 
+        |mut caller: Caller<'_, ConcreteHostEnvironment>, msg_ptr: i32, msg_len: i32| -> Result<i32, Trap> {
+            host_log(&mut caller, GuestLogLevel::Info, msg_ptr, msg_len)
+        }
+    ).map_err(|e| VmError::EngineCreationFailed(format!("Failed to register host_log_message: {}", e)))?;
+    
     // Register other host functions similarly
     // host_delete_value
     linker.func_wrap(
@@ -1475,5 +1514,21 @@ pub fn register_host_functions(
     crate::economics_helpers::register_economics_functions(linker)
         .map_err(|e| VmError::EngineCreationFailed(format!("Failed to register economics functions: {}", e)))?;
     
+    // Blob input streaming
+    crate::blob_input::register_blob_input_functions(linker)
+        .map_err(|e| VmError::EngineCreationFailed(format!("Failed to register blob input functions: {}", e)))?;
+    
+    // Proposal creation and voting
+    crate::governance_host::register_governance_functions(linker)
+        .map_err(|e| VmError::EngineCreationFailed(format!("Failed to register governance functions: {}", e)))?;
+    
+    // Execution context introspection
+    crate::execution_context::register_execution_context_functions(linker)
+        .map_err(|e| VmError::EngineCreationFailed(format!("Failed to register execution context functions: {}", e)))?;
+    
+    // Recurring timers
+    crate::timers::register_timer_functions(linker)
+        .map_err(|e| VmError::EngineCreationFailed(format!("Failed to register timer functions: {}", e)))?;
+    
     Ok(())
 } 
\ No newline at end of file
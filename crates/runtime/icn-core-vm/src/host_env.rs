@@ -0,0 +1,207 @@
+/*!
+# Pluggable Host Environments
+
+[`execute_wasm`](crate::execute_wasm) runs modules against the [`ConcreteHostEnvironment`](crate::ConcreteHostEnvironment)
+and its full host ABI, which needs storage, identity and DAG backends behind it.
+[`execute_wasm_with_host`] runs a module against any [`HostEnvironment`] instead:
+every function the module imports is routed to [`HostEnvironment::call_host`] with the
+call's name and parameters and a view of the module's exported memory. That makes it
+cheap to unit-test a compiled module against the ABI, e.g. with the scriptable
+[`MockHostEnvironment`](crate::mock_host::MockHostEnvironment).
+
+Execution is metered like `execute_wasm`: the module gets the fuel of its Compute
+authorization (one unit per instruction) and runs from `main`, `_start` or `__main`.
+*/
+
+use wasmtime::{Caller, ExternType, Linker, Module, Store, Val, ValType};
+
+use crate::{pool, scheduler, ModuleLimits, ResourceType, VMContext, VmError};
+
+/// Fuel for modules without a Compute authorization
+const DEFAULT_FUEL: u64 = 1_000_000;
+
+/// A call from a module to one of its imported functions
+pub struct HostCall<'a> {
+    /// Import module (e.g., "env")
+    pub module: &'a str,
+    /// Import name (e.g., "host_log_message")
+    pub name: &'a str,
+    pub params: &'a [Val],
+    /// Types of the results the call must return
+    pub result_types: &'a [ValType],
+    memory: Option<&'a mut [u8]>,
+}
+
+impl<'a> HostCall<'a> {
+    /// An i32 parameter, by position
+    pub fn i32_param(&self, index: usize) -> Option<i32> {
+        self.params.get(index).and_then(|v| v.i32())
+    }
+
+    /// An i64 parameter, by position
+    pub fn i64_param(&self, index: usize) -> Option<i64> {
+        self.params.get(index).and_then(|v| v.i64())
+    }
+
+    fn range(&self, ptr: i32, len: usize) -> Result<std::ops::Range<usize>, VmError> {
+        let memory_len = self.memory.as_ref().map_or(0, |m| m.len());
+        let start = u32::try_from(ptr)
+            .map_err(|_| VmError::MemoryError(format!("Negative guest pointer {}", ptr)))? as usize;
+        start.checked_add(len)
+            .filter(|end| *end <= memory_len)
+            .map(|end| start..end)
+            .ok_or_else(|| VmError::MemoryError(format!(
+                "Guest range {}+{} is outside memory of {} bytes", ptr, len, memory_len
+            )))
+    }
+
+    /// Guest memory at `ptr..ptr + len`
+    pub fn read_bytes(&self, ptr: i32, len: i32) -> Result<&[u8], VmError> {
+        let len = usize::try_from(len)
+            .map_err(|_| VmError::MemoryError(format!("Negative guest length {}", len)))?;
+        let range = self.range(ptr, len)?;
+        Ok(&self.memory.as_deref().unwrap_or_default()[range])
+    }
+
+    /// Guest memory at `ptr..ptr + len` as UTF-8
+    pub fn read_str(&self, ptr: i32, len: i32) -> Result<&str, VmError> {
+        std::str::from_utf8(self.read_bytes(ptr, len)?)
+            .map_err(|e| VmError::MemoryError(format!("Invalid UTF-8 in guest memory: {}", e)))
+    }
+
+    /// Copy `bytes` into guest memory at `ptr`
+    pub fn write_bytes(&mut self, ptr: i32, bytes: &[u8]) -> Result<(), VmError> {
+        let range = self.range(ptr, bytes.len())?;
+        match self.memory.as_deref_mut() {
+            Some(memory) => memory[range].copy_from_slice(bytes),
+            None if bytes.is_empty() => {}
+            None => return Err(VmError::MemoryError("Module exports no memory".to_string())),
+        }
+        Ok(())
+    }
+
+    /// Zero values for every result, for calls that just need to return something
+    pub fn default_results(&self) -> Vec<Val> {
+        self.result_types.iter().map(zero_value).collect()
+    }
+}
+
+/// The zero value of a type
+pub fn zero_value(ty: &ValType) -> Val {
+    match ty {
+        ValType::I32 => Val::I32(0),
+        ValType::I64 => Val::I64(0),
+        ValType::F32 => Val::F32(0),
+        ValType::F64 => Val::F64(0),
+        ValType::V128 => Val::V128(0u128.into()),
+        ValType::FuncRef => Val::FuncRef(None),
+        ValType::ExternRef => Val::ExternRef(None),
+    }
+}
+
+/// Answers the host calls of a module run by [`execute_wasm_with_host`]
+pub trait HostEnvironment: Send + 'static {
+    /// Handle one call. The returned values must match `call.result_types`; an error
+    /// traps the module.
+    fn call_host(&mut self, call: &mut HostCall<'_>) -> Result<Vec<Val>, VmError>;
+}
+
+/// The result of running a module against a [`HostEnvironment`]
+pub struct HostedExecution<H> {
+    /// Return code of the entry point, or why the module failed to complete
+    pub outcome: Result<i32, VmError>,
+    pub fuel_consumed: u64,
+    /// The host after the run, for inspecting what the module did
+    pub host: H,
+}
+
+fn dispatch<H: HostEnvironment>(
+    mut caller: Caller<'_, H>,
+    module: &str,
+    name: &str,
+    params: &[Val],
+    result_types: &[ValType],
+    results: &mut [Val],
+) -> anyhow::Result<()> {
+    let memory = caller.get_export("memory").and_then(|export| export.into_memory());
+    let (memory, host) = match memory {
+        Some(memory) => {
+            let (data, host) = memory.data_and_store_mut(&mut caller);
+            (Some(data), host)
+        }
+        None => (None, caller.data_mut()),
+    };
+
+    let mut call = HostCall { module, name, params, result_types, memory };
+    let values = host.call_host(&mut call)
+        .map_err(|e| anyhow::anyhow!("{}::{} failed: {}", module, name, e))?;
+    if values.len() != results.len() {
+        return Err(anyhow::anyhow!(
+            "{}::{} returned {} values, expected {}", module, name, values.len(), results.len()
+        ));
+    }
+    for (slot, value) in results.iter_mut().zip(values) {
+        *slot = value;
+    }
+    Ok(())
+}
+
+/// Execute a WASM module with every import answered by `host`. Fails without running
+/// the module if it can't be compiled or instantiated, e.g. when it imports anything
+/// other than functions.
+pub async fn execute_wasm_with_host<H: HostEnvironment>(
+    wasm_bytes: &[u8],
+    context: Option<VMContext>,
+    host: H,
+) -> Result<HostedExecution<H>, VmError> {
+    let context = context.unwrap_or_default();
+    let engine = pool::create_engine()?;
+    ModuleLimits::default().validate(wasm_bytes)?;
+    let module = Module::new(&engine, wasm_bytes)
+        .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+
+    let mut linker: Linker<H> = Linker::new(&engine);
+    for import in module.imports() {
+        let (module_name, name) = (import.module().to_string(), import.name().to_string());
+        let ty = match import.ty() {
+            ExternType::Func(ty) => ty,
+            other => return Err(VmError::InstantiationFailed(format!(
+                "{}::{} is not a function import: {:?}", module_name, name, other
+            ))),
+        };
+        let result_types: Vec<ValType> = ty.results().collect();
+        let (call_module, call_name) = (module_name.clone(), name.clone());
+        linker.func_new(&module_name, &name, ty, move |caller: Caller<'_, H>, params, results| {
+            dispatch(caller, &call_module, &call_name, params, &result_types, results)
+        }).map_err(|e| VmError::EngineCreationFailed(format!("Failed to define {}::{}: {}", module_name, name, e)))?;
+    }
+
+    let mut store = Store::new(&engine, host);
+    let fuel_limit = context.authorization_for(ResourceType::Compute)
+        .map_err(|e| VmError::Unauthorized(e.to_string()))?
+        .map_or(DEFAULT_FUEL, |auth| auth.limit);
+    store.add_fuel(fuel_limit)
+        .map_err(|e| VmError::FuelAllocationFailed(e.to_string()))?;
+    store.fuel_async_yield_interval(Some(scheduler::DEFAULT_YIELD_INTERVAL))
+        .map_err(|e| VmError::FuelAllocationFailed(e.to_string()))?;
+
+    let instance = linker.instantiate_async(&mut store, &module).await
+        .map_err(|e| VmError::InstantiationFailed(e.to_string()))?;
+    let main_func = instance.get_typed_func::<(), i32>(&mut store, "main")
+        .or_else(|_| instance.get_typed_func::<(), i32>(&mut store, "_start"))
+        .or_else(|_| instance.get_typed_func::<(), i32>(&mut store, "__main"))
+        .map_err(|_| VmError::EntryPointNotFound("No main/_start/__main function found".to_string()))?;
+
+    let outcome = main_func.call_async(&mut store, ()).await
+        .map_err(|e| if e.to_string().contains("out of fuel") {
+            VmError::ResourceLimitExceeded("Execution exceeded fuel limit".to_string())
+        } else {
+            VmError::ExecutionError(format!("{:#}", e))
+        });
+
+    Ok(HostedExecution {
+        outcome,
+        fuel_consumed: store.fuel_consumed().unwrap_or(0),
+        host: store.into_data(),
+    })
+}
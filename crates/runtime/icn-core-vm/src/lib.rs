@@ -14,7 +14,25 @@ pub mod blob_storage;
 pub mod cid_utils;
 pub mod dag_helpers;
 pub mod economics_helpers;
+pub mod blob_input;
+pub mod pricing;
 pub mod monitor;
+pub mod pool;
+pub mod differential;
+pub mod audit;
+pub mod guest_log;
+pub mod execution_policy;
+pub mod governance_host;
+pub mod component;
+pub mod scheduler;
+pub mod host_env;
+pub mod mock_host;
+pub mod result_store;
+pub mod module_limits;
+pub mod execution_context;
+pub mod metrics;
+pub mod abi_manifest;
+pub mod timers;
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
@@ -38,7 +56,29 @@ use icn_models::{dag_storage_codec, DagNode, DagNodeBuilder, DagNodeMetadata, Ic
 use libipld::codec::{Decode, Encode};
 use thiserror::Error;
 
-pub use resources::{ResourceType, ResourceAuthorization, ResourceConsumption};
+pub use resources::{ResourceType, ResourceAuthorization, ResourceConsumption, AuthorizationTenant};
+pub use pricing::{FuelPricingTable, FuelPricingRegistry, HostCallClass, InstructionGroup};
+pub use blob_input::BlobCache;
+pub use module_limits::{ModuleLimits, ModuleTables};
+pub use execution_context::GuestExecutionContext;
+pub use timers::{TimerRegistration, MAX_TIMERS_PER_EXECUTION, TIMER_LIMIT_REACHED};
+pub use metrics::{VmMetrics, VmScopeMetrics};
+pub use abi_manifest::{
+    AbiValType, HostAbiManifest, HostCapability, HostFunctionDescriptor, host_abi_manifest, HOST_ABI_VERSION,
+};
+pub use pool::{WarmPool, WarmPoolConfig, WarmPoolStats, execute_wasm_pooled};
+pub use differential::{
+    DifferentialCase, DifferentialReceipt, DifferentialReport, VmBuild, InProcessBuild, ExternalBuild,
+    run_differential, load_corpus, compare_receipts,
+};
+pub use audit::{ArgumentCapture, SyscallAuditLog, SyscallAuditPolicy, SyscallRecord};
+pub use guest_log::{GuestLog, GuestLogCapture, GuestLogEntry, GuestLogLevel, GuestLogLimits};
+pub use execution_policy::{ExecutionPolicy, HostActionDenied};
+pub use governance_host::{GovernanceHost, GovernanceHostError, ProposalDraft, VoteChoice as HostVoteChoice};
+pub use scheduler::{FairScheduler, SchedulerConfig, SchedulingTicket, ScopeUsage};
+pub use host_env::{HostCall, HostEnvironment, HostedExecution, execute_wasm_with_host};
+pub use mock_host::{MockHostEnvironment, MockResponse, RecordedCall};
+pub use result_store::{ExecutionResultQuery, ExecutionResultStore, StoredExecutionResult};
 
 // Re-export credentials module functionality
 pub use credentials::{
@@ -78,26 +118,78 @@ impl IdentityContext {
 }
 
 /// VM context containing identity and resource authorization information
+///
+/// Authorizations are bound to the context's tenant, its caller and scope, when the
+/// context is created. Authorizations already bound to another tenant keep their
+/// binding, and consuming them fails with [`InternalHostError::UnauthorizedAccess`].
 #[derive(Clone)]
 pub struct VMContext {
     identity_context: Arc<IdentityContext>,
     resource_authorizations: Vec<ResourceAuthorization>,
+    tenant: AuthorizationTenant,
     execution_id: String, // Add execution_id field
 }
 
 impl VMContext {
-    /// Create a new VM context
+    /// Create a new VM context acting in the caller's own scope
     pub fn new(
         identity_context: Arc<IdentityContext>,
         resource_authorizations: Vec<ResourceAuthorization>,
     ) -> Self {
+        let scope = identity_context.did().to_string();
+        Self::with_scope(identity_context, scope, resource_authorizations)
+    }
+
+    /// Create a new VM context acting in the given scope
+    pub fn with_scope(
+        identity_context: Arc<IdentityContext>,
+        scope: impl Into<String>,
+        resource_authorizations: Vec<ResourceAuthorization>,
+    ) -> Self {
+        let tenant = AuthorizationTenant {
+            caller_did: identity_context.did().to_string(),
+            scope: scope.into(),
+        };
+        let resource_authorizations = resource_authorizations.into_iter()
+            .map(|auth| match auth.tenant {
+                Some(_) => auth,
+                None => auth.bound_to(tenant.clone()),
+            })
+            .collect();
         Self {
             identity_context,
             resource_authorizations,
+            tenant,
             execution_id: uuid::Uuid::new_v4().to_string(), // Generate a unique execution ID
         }
     }
 
+    /// Get the caller and scope this context's authorizations are bound to
+    pub fn tenant(&self) -> &AuthorizationTenant {
+        &self.tenant
+    }
+
+    /// Get the context's authorization for a resource type. Fails if the only
+    /// authorization for it belongs to another tenant.
+    pub fn authorization_for(&self, resource_type: ResourceType) -> Result<Option<&ResourceAuthorization>, InternalHostError> {
+        let mut matching = self.resource_authorizations.iter()
+            .filter(|auth| auth.resource_type == resource_type)
+            .peekable();
+        let foreign = match matching.peek() {
+            Some(auth) => (*auth).clone(),
+            None => return Ok(None),
+        };
+        match matching.find(|auth| auth.belongs_to(&self.tenant)) {
+            Some(auth) => Ok(Some(auth)),
+            None => Err(InternalHostError::UnauthorizedAccess(format!(
+                "{} authorization for {} can't be consumed by {}",
+                resource_type,
+                foreign.tenant.map_or_else(|| "no tenant".to_string(), |t| t.to_string()),
+                self.tenant
+            ))),
+        }
+    }
+
     /// Get the caller DID from the identity context
     pub fn caller_did(&self) -> &str {
         self.identity_context.did()
@@ -122,6 +214,10 @@ impl Default for VMContext {
                 did: "did:icn:anonymous".to_string(),
             }),
             resource_authorizations: Vec::new(),
+            tenant: AuthorizationTenant {
+                caller_did: "did:icn:anonymous".to_string(),
+                scope: "did:icn:anonymous".to_string(),
+            },
             execution_id: uuid::Uuid::new_v4().to_string(), // Generate a unique execution ID for default context
         }
     }
@@ -243,6 +339,8 @@ pub enum InternalHostError {
     ConfigurationError(String),
     #[error("Virtual machine error: {0}")]
     VmError(String),
+    #[error("Unauthorized access: {0}")]
+    UnauthorizedAccess(String),
     #[error("Generic internal error: {0}")]
     Other(String),
 }
@@ -265,9 +363,6 @@ impl From<StorageError> for InternalHostError {
     }
 }
 
-/// Host environment trait for VM execution (keep existing, maybe add new methods later)
-// pub trait HostEnvironment { ... }
-
 /// Concrete implementation of the host environment
 #[derive(Clone)]
 pub struct ConcreteHostEnvironment {
@@ -286,6 +381,51 @@ pub struct ConcreteHostEnvironment {
     
     /// DAG storage manager for WASM host ABI functions
     pub dag_storage: Arc<dyn DagStorageManager + Send + Sync>,
+    
+    /// Blob CIDs attached as inputs to this execution
+    input_blobs: Arc<RwLock<Vec<Cid>>>,
+    
+    /// Blobs already loaded for windowed reads; replaced at the start of each execution
+    blob_cache: Arc<RwLock<BlobCache>>,
+    
+    /// Fuel pricing tables, per federation
+    fuel_pricing: Arc<FuelPricingRegistry>,
+    
+    /// Federation whose pricing table applies to this execution
+    pricing_federation: Option<String>,
+    
+    /// Bounds on the tables a module may declare
+    module_limits: ModuleLimits,
+    
+    /// Store limits that keep tables within `module_limits` as they grow
+    table_limits: wasmtime::StoreLimits,
+    
+    /// Host call auditing, if enabled for this execution
+    syscall_audit: Option<Arc<SyscallAuditPolicy>>,
+    
+    /// Host calls recorded during execution
+    syscall_log: Arc<RwLock<Vec<SyscallRecord>>>,
+    
+    /// Log lines written by the module
+    guest_log: Arc<GuestLogCapture>,
+    
+    /// Host functions the acting identity's roles allow, if execution is gated
+    execution_policy: Option<Arc<ExecutionPolicy>>,
+    
+    /// Governance kernel modules create proposals and vote through
+    governance: Option<Arc<dyn governance_host::GovernanceHost>>,
+    
+    /// Scheduler this execution is accounted to, and the scope it's charged to
+    scheduling: Option<(FairScheduler, String)>,
+    
+    /// Context the module can read, set when an execution starts
+    execution_context: Option<GuestExecutionContext>,
+    
+    /// Recurring timers the module registered
+    timers: Arc<RwLock<Vec<TimerRegistration>>>,
+    
+    /// Prometheus metrics executions are recorded in, if exported
+    vm_metrics: Option<VmMetrics>,
 }
 
 impl ConcreteHostEnvironment {
@@ -301,15 +441,238 @@ impl ConcreteHostEnvironment {
             vm_context,
             storage_manager,
             identity_manager,
+            pricing_federation: parent_federation_did.clone(),
             parent_federation_did,
             consumed_resources: Arc::new(RwLock::new(HashMap::new())),
             last_created_entity_info: None, // Initialize as None
             resource_usage: Arc::new(RwLock::new(HashMap::new())),
             last_anchor_cid: Arc::new(RwLock::new(None)),
             dag_storage,
+            input_blobs: Arc::new(RwLock::new(Vec::new())),
+            blob_cache: Arc::new(RwLock::new(BlobCache::default())),
+            fuel_pricing: Arc::new(FuelPricingRegistry::default()),
+            module_limits: ModuleLimits::default(),
+            table_limits: ModuleLimits::default().store_limits(),
+            syscall_audit: None,
+            syscall_log: Arc::new(RwLock::new(Vec::new())),
+            guest_log: Arc::new(GuestLogCapture::default()),
+            execution_policy: None,
+            governance: None,
+            scheduling: None,
+            execution_context: None,
+            timers: Arc::new(RwLock::new(Vec::new())),
+            vm_metrics: None,
+        }
+    }
+    
+    /// Capture the module's log lines under the given limits
+    pub fn with_guest_log_limits(mut self, limits: GuestLogLimits) -> Self {
+        self.guest_log = Arc::new(GuestLogCapture::new(limits));
+        self
+    }
+    
+    /// Get the module's log lines captured so far
+    pub fn guest_log(&self) -> GuestLog {
+        self.guest_log.snapshot()
+    }
+    
+    /// Follow the module's log lines live while it executes
+    pub fn subscribe_guest_log(&self) -> tokio::sync::mpsc::UnboundedReceiver<GuestLogEntry> {
+        self.guest_log.subscribe()
+    }
+    
+    /// Capture a line logged by the module and mirror it to the host's tracing output.
+    /// Returns whether the line was kept.
+    pub(crate) fn record_guest_log(&self, level: GuestLogLevel, message: &str, source_offset: Option<usize>) -> bool {
+        let caller = self.caller_did();
+        match level {
+            GuestLogLevel::Debug => debug!(caller = %caller, "guest: {}", message),
+            GuestLogLevel::Info => info!(caller = %caller, "guest: {}", message),
+            GuestLogLevel::Warn => warn!(caller = %caller, "guest: {}", message),
+            GuestLogLevel::Error => error!(caller = %caller, "guest: {}", message),
+        }
+        self.guest_log.record(level, message, source_offset)
+    }
+    
+    /// Get the recurring timers the module registered so far
+    pub fn registered_timers(&self) -> Vec<TimerRegistration> {
+        self.timers.read().unwrap().clone()
+    }
+    
+    /// Record a timer the module registered. Returns its index within the execution,
+    /// or None once the execution registered as many timers as it may.
+    pub(crate) fn record_timer(&self, schedule: String, payload: serde_json::Value) -> Option<usize> {
+        let mut timers = self.timers.write().unwrap();
+        if timers.len() >= MAX_TIMERS_PER_EXECUTION {
+            warn!(caller = %self.caller_did(), "Timer limit reached");
+            return None;
+        }
+        timers.push(TimerRegistration {
+            schedule,
+            payload,
+            registered_by: self.caller_did().to_string(),
+            execution_id: self.vm_context.execution_id().to_string(),
+        });
+        Some(timers.len() - 1)
+    }
+    
+    /// Only allow the host calls the given policy allows
+    pub fn with_execution_policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.execution_policy = Some(Arc::new(policy));
+        self
+    }
+    
+    /// Get the execution policy, if execution is gated
+    pub fn execution_policy(&self) -> Option<&ExecutionPolicy> {
+        self.execution_policy.as_deref()
+    }
+    
+    /// Let the module create proposals and vote through the given governance host
+    pub fn with_governance_host(mut self, governance: Arc<dyn governance_host::GovernanceHost>) -> Self {
+        self.governance = Some(governance);
+        self
+    }
+    
+    /// Get the governance host, if one is attached
+    pub fn governance_host(&self) -> Option<Arc<dyn governance_host::GovernanceHost>> {
+        self.governance.clone()
+    }
+    
+    /// Account this execution to `scope` on a scheduler shared with other executions
+    pub fn with_fair_scheduler(mut self, scheduler: FairScheduler, scope: impl Into<String>) -> Self {
+        self.scheduling = Some((scheduler, scope.into()));
+        self
+    }
+    
+    /// Record executions, fuel and host call latency in the given metrics
+    pub fn with_vm_metrics(mut self, metrics: VmMetrics) -> Self {
+        self.vm_metrics = Some(metrics);
+        self
+    }
+    
+    /// Get the metrics executions are recorded in, if exported
+    pub fn vm_metrics(&self) -> Option<&VmMetrics> {
+        self.vm_metrics.as_ref()
+    }
+    
+    /// Record every host call made during execution under the given policy
+    pub fn with_syscall_audit(mut self, policy: SyscallAuditPolicy) -> Self {
+        self.syscall_audit = Some(Arc::new(policy));
+        self
+    }
+    
+    /// Get the host call audit policy, if auditing is enabled
+    pub fn syscall_audit_policy(&self) -> Option<&SyscallAuditPolicy> {
+        self.syscall_audit.as_deref()
+    }
+    
+    /// Append a host call to the audit log, numbering it in call order
+    pub(crate) fn record_syscall(&self, mut record: SyscallRecord) {
+        let mut log = self.syscall_log.write().unwrap();
+        record.sequence = log.len() as u64;
+        log.push(record);
+    }
+    
+    /// Get the host calls recorded so far
+    pub fn syscall_audit_log(&self) -> SyscallAuditLog {
+        SyscallAuditLog {
+            execution_id: self.vm_context.execution_id().to_string(),
+            caller_did: self.caller_did().to_string(),
+            records: self.syscall_log.read().unwrap().clone(),
         }
     }
     
+    /// Anchor the audit log to the DAG next to the execution receipt.
+    /// Returns the anchor CID, or None when auditing is disabled.
+    pub async fn persist_syscall_audit_log(&self) -> Result<Option<String>, InternalHostError> {
+        if self.syscall_audit.is_none() {
+            return Ok(None);
+        }
+        
+        let log = self.syscall_audit_log();
+        let data = serde_json::to_vec(&log)
+            .map_err(|e| InternalHostError::CodecError(format!("Failed to serialize syscall audit log: {}", e)))?;
+        let cid = self.anchor_to_dag(&audit::audit_anchor_key(&log.execution_id), data).await?;
+        Ok(Some(cid))
+    }
+    
+    /// Use the given fuel pricing registry for host call and instruction charges
+    pub fn with_fuel_pricing(mut self, registry: FuelPricingRegistry) -> Self {
+        self.fuel_pricing = Arc::new(registry);
+        self
+    }
+    
+    /// Select the federation whose pricing table applies to this execution
+    pub fn set_pricing_federation(&mut self, federation_id: Option<String>) {
+        self.pricing_federation = federation_id;
+    }
+    
+    /// Get the fuel pricing table that applies to this execution
+    pub fn fuel_pricing(&self) -> &FuelPricingTable {
+        self.fuel_pricing.table_for(self.pricing_federation.as_deref())
+    }
+    
+    /// Bound the tables of executed modules by the given limits
+    pub fn with_module_limits(mut self, limits: ModuleLimits) -> Self {
+        self.table_limits = limits.store_limits();
+        self.module_limits = limits;
+        self
+    }
+    
+    /// Get the bounds on the tables of executed modules
+    pub fn module_limits(&self) -> &ModuleLimits {
+        &self.module_limits
+    }
+    
+    /// Get the context the executing module can read. Outside an execution it is
+    /// derived from the VM context.
+    pub fn execution_context(&self) -> GuestExecutionContext {
+        self.execution_context.clone().unwrap_or_else(|| GuestExecutionContext {
+            proposal_cid: None,
+            scope: self.vm_context.tenant().scope.clone(),
+            execution_id: self.vm_context.execution_id().to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+        })
+    }
+    
+    /// Attach a blob CID as an input to this execution
+    pub fn add_input_blob(&self, cid: Cid) {
+        self.input_blobs.write().unwrap().push(cid);
+    }
+    
+    /// Get the blob CIDs attached as inputs to this execution
+    pub fn input_blob_cids(&self) -> Vec<Cid> {
+        self.input_blobs.read().unwrap().clone()
+    }
+    
+    /// Load an input blob for windowed reads, caching it so repeated reads don't hit
+    /// storage. Blobs that weren't attached with [`Self::add_input_blob`] are refused.
+    pub async fn load_blob_cached(&self, cid: &Cid) -> Result<Arc<Vec<u8>>, InternalHostError> {
+        if !self.input_blobs.read().unwrap().contains(cid) {
+            return Err(InternalHostError::UnauthorizedAccess(format!(
+                "Blob {} is not an input of this execution", cid
+            )));
+        }
+        
+        if let Some(data) = self.blob_cache.write().unwrap().get(cid) {
+            return Ok(data);
+        }
+        
+        let data = self.storage_manager.get_blob(cid)
+            .await
+            .map_err(|e| InternalHostError::StorageError(e.to_string()))?
+            .ok_or_else(|| InternalHostError::StorageError(format!("Blob not found: {}", cid)))?;
+        
+        let data = Arc::new(data);
+        self.blob_cache.write().unwrap().insert(*cid, data.clone());
+        Ok(data)
+    }
+    
+    /// Bytes of blob content currently cached for windowed reads
+    pub fn cached_blob_bytes(&self) -> usize {
+        self.blob_cache.read().unwrap().size()
+    }
+    
     /// Get the amount of compute resources consumed
     pub fn get_compute_consumed(&self) -> u64 {
         self.consumed_resources.read().unwrap()
@@ -342,9 +705,9 @@ impl ConcreteHostEnvironment {
             ))
         })?;
         
-        // Check authorization limits
-        let auth_limit = self.vm_context.resource_authorizations().iter()
-            .find(|auth| auth.resource_type == resource_type)
+        // Check authorization limits; another tenant's authorization can't be consumed
+        let auth_limit = self.vm_context.authorization_for(resource_type)
+            .map_err(|e| VmError::Unauthorized(e.to_string()))?
             .map(|auth| auth.limit)
             .unwrap_or(u64::MAX);
         
@@ -371,6 +734,28 @@ impl ConcreteHostEnvironment {
         self.record_resource_consumption(ResourceType::Compute, amount)
     }
 
+    /// Charge compute for work that already happened. Unlike [`Self::record_compute_usage`]
+    /// this can't fail: the charge is capped at what remains of the Compute authorization,
+    /// and the part left uncharged is returned.
+    pub fn settle_compute_usage(&self, amount: u64) -> u64 {
+        let used = self.resource_usage.read().unwrap()
+            .get(&ResourceType::Compute).copied().unwrap_or(0);
+        let limit = self.vm_context.authorization_for(ResourceType::Compute)
+            .ok()
+            .flatten()
+            .map_or(u64::MAX, |auth| auth.limit);
+        let charged = amount.min(limit.saturating_sub(used));
+        if self.record_compute_usage(charged).is_err() {
+            return amount;
+        }
+        amount - charged
+    }
+
+    /// Record the compute cost of a host call, priced by its class
+    pub fn record_host_call(&self, class: HostCallClass, base_cost: u64) -> Result<(), VmError> {
+        self.record_compute_usage(self.fuel_pricing().host_call_cost(class, base_cost))
+    }
+
     /// Record consumption of storage resources
     pub fn record_storage_usage(&self, amount: u64) -> Result<(), VmError> {
         self.record_resource_consumption(ResourceType::Storage, amount)
@@ -403,7 +788,7 @@ impl ConcreteHostEnvironment {
     ) -> Result<String, InternalHostError> { // Returns only DID string now
         // --- Basic Compute Cost ---
         // Record some base cost for this complex operation
-        let _ = self.record_compute_usage(5000); // Adjust cost as needed
+        let _ = self.record_host_call(HostCallClass::Dag, 5000); // Adjust cost as needed
 
         // 1. Generate new DID and Keypair
         let (new_did_key_str, _public_jwk) = self
@@ -414,7 +799,7 @@ impl ConcreteHostEnvironment {
         tracing::info!(new_did = %new_did_key_str, "Generated new DID for sub-entity");
 
         // Record cost associated with key generation
-        let _ = self.record_compute_usage(1000); // Cost for crypto op
+        let _ = self.record_host_call(HostCallClass::Crypto, 1000); // Cost for crypto op
 
         // 2. Deserialize/Prepare Genesis Payload
         // Create a codec for serialization/deserialization
@@ -423,7 +808,7 @@ impl ConcreteHostEnvironment {
             .map_err(|e| InternalHostError::CodecError(e.to_string()))?;
         
         // Record cost for decoding
-        let _ = self.record_compute_usage((genesis_payload_bytes.len() / 100) as u64);
+        let _ = self.record_host_call(HostCallClass::Dag, (genesis_payload_bytes.len() / 100) as u64);
 
         // 3. Construct Genesis DagNode
         //    The issuer ('iss') of the genesis node is the *new* entity's DID.
@@ -503,7 +888,7 @@ impl ConcreteHostEnvironment {
         metadata_bytes: Vec<u8>,
     ) -> Result<Cid, InternalHostError> { // Returns the CID of the stored node
         // Record base compute cost
-        self.record_compute_usage(2000)?; // Base cost for storing
+        self.record_host_call(HostCallClass::Dag, 2000)?; // Base cost for storing
 
         // Create a codec for serialization
         let codec = dag_storage_codec();
@@ -571,7 +956,7 @@ impl ConcreteHostEnvironment {
         cid_bytes: Vec<u8>,
     ) -> Result<Option<Vec<u8>>, InternalHostError> { // Returns CBOR bytes of the node
         // Record base compute cost
-        self.record_compute_usage(500)?;
+        self.record_host_call(HostCallClass::Dag, 500)?;
 
         let cid = Cid::read_bytes(std::io::Cursor::new(cid_bytes))
             .map_err(|e| InternalHostError::InvalidInput(format!("Invalid CID bytes: {}", e)))?;
@@ -594,7 +979,7 @@ impl ConcreteHostEnvironment {
         cid_bytes: Vec<u8>,
     ) -> Result<bool, InternalHostError> {
         // Record base compute cost
-        self.record_compute_usage(200)?; // Cheaper than get
+        self.record_host_call(HostCallClass::Dag, 200)?; // Cheaper than get
 
         let cid = Cid::read_bytes(std::io::Cursor::new(cid_bytes))
             .map_err(|e| InternalHostError::InvalidInput(format!("Invalid CID bytes: {}", e)))?;
@@ -847,7 +1232,7 @@ impl ConcreteHostEnvironment {
     /// Store a key-value pair in storage
     pub fn set_value(&self, key: &str, value: Vec<u8>) -> Result<(), InternalHostError> {
         // Record storage usage
-        self.record_compute_usage(100)?;
+        self.record_host_call(HostCallClass::StorageWrite, 100)?;
         self.record_storage_usage(value.len() as u64)?;
         
         // In a real implementation, this would store the value in a storage system
@@ -860,7 +1245,7 @@ impl ConcreteHostEnvironment {
     /// Delete a value from storage
     pub fn delete_value(&self, key: &str) -> Result<(), InternalHostError> {
         // Record compute usage
-        self.record_compute_usage(50)?;
+        self.record_host_call(HostCallClass::StorageWrite, 50)?;
         
         // In a real implementation, this would delete from storage
         // For now, we just log it
@@ -872,7 +1257,7 @@ impl ConcreteHostEnvironment {
     /// Store a DAG node using the DagStorageManager
     pub async fn store_node(&self, node: DagNode) -> Result<(), InternalHostError> {
         // Record base compute cost for storing a node
-        self.record_compute_usage(500)?;
+        self.record_host_call(HostCallClass::Dag, 500)?;
         
         // Get the entity DID - here we use the caller's DID as the entity owner
         let entity_did = self.vm_context.caller_did();
@@ -912,7 +1297,7 @@ impl ConcreteHostEnvironment {
     /// Retrieve a DAG node by its CID
     pub async fn get_node(&self, cid: &Cid) -> Result<Option<DagNode>, InternalHostError> {
         // Record base compute cost for retrieving a node
-        self.record_compute_usage(200)?;
+        self.record_host_call(HostCallClass::Dag, 200)?;
         
         // Get the entity DID - here we use the caller's DID 
         let entity_did = self.vm_context.caller_did();
@@ -934,7 +1319,7 @@ impl ConcreteHostEnvironment {
     /// Check if a DAG node exists by its CID
     pub async fn contains_node(&self, cid: &Cid) -> Result<bool, InternalHostError> {
         // Record base compute cost for checking node existence
-        self.record_compute_usage(100)?;
+        self.record_host_call(HostCallClass::Dag, 100)?;
         
         // Get the entity DID - here we use the caller's DID
         let entity_did = self.vm_context.caller_did();
@@ -971,7 +1356,7 @@ impl ConcreteHostEnvironment {
         let key = format!("{}:{}:{}", scope, anchor_type, timestamp);
         
         // Record compute usage
-        self.record_compute_usage(500)?;
+        self.record_host_call(HostCallClass::Dag, 500)?;
         
         // Record storage usage (approximate size of the payload)
         let payload_size = anchor_json.len() as u64;
@@ -1013,6 +1398,15 @@ pub struct VmExecutionResult {
     
     /// CID of the last DAG anchor created during execution, if any
     pub dag_anchor_cid: Option<String>,
+    
+    /// CID of the anchored host call audit log, if auditing was enabled
+    pub syscall_audit_cid: Option<String>,
+    
+    /// Log lines written by the module
+    pub guest_log: GuestLog,
+    
+    /// Recurring timers the module registered, for the host to install
+    pub registered_timers: Vec<TimerRegistration>,
 }
 
 /// Execute a WASM module in a sandboxed environment
@@ -1023,54 +1417,183 @@ pub async fn execute_wasm(
     proposal_id: Option<&str>,
     federation_scope: Option<&str>,
 ) -> Result<VmExecutionResult, VmError> {
+    // Create a new Wasmtime engine and register the host functions
+    let engine = pool::create_engine()?;
+    let mut linker = wasmtime::Linker::new(&engine);
+    host_abi::register_host_functions(&mut linker)?;
+    
+    run_module(&engine, &linker, None, wasm_bytes, context, host_env, proposal_id, federation_scope).await
+}
+
+/// Execute a WASM module with an engine and a linker that has the host functions registered,
+/// reusing compiled modules from `module_cache` if one is given
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn run_module(
+    engine: &Engine,
+    linker: &wasmtime::Linker<ConcreteHostEnvironment>,
+    module_cache: Option<&pool::ModuleCache>,
+    wasm_bytes: &[u8],
+    context: Option<VMContext>,
+    host_env: &ConcreteHostEnvironment,
+    proposal_id: Option<&str>,
+    federation_scope: Option<&str>,
+) -> Result<VmExecutionResult, VmError> {
+    // A context for another tenant would draw on authorizations the host doesn't charge
+    if let Some(context) = &context {
+        if context.tenant() != host_env.vm_context.tenant() {
+            return Err(VmError::Unauthorized(format!(
+                "Execution context of {} can't run in the host environment of {}",
+                context.tenant(), host_env.vm_context.tenant()
+            )));
+        }
+    }
+    let execution_id = context.as_ref()
+        .map_or_else(|| host_env.vm_context.execution_id(), |c| c.execution_id())
+        .to_string();
     let context = context.unwrap_or_default();
+    let scope = federation_scope.map_or_else(|| host_env.vm_context.tenant().scope.clone(), str::to_string);
     
-    // Create a new Wasmtime engine with appropriate config
-    let mut config = Config::new();
-    config.wasm_bulk_memory(true);
-    config.wasm_reference_types(true);
-    config.async_support(true);
-    config.consume_fuel(true);
+    // Count modules refused before they run under their own outcome
+    let vm_metrics = host_env.vm_metrics.clone();
+    let rejected = |e: VmError| {
+        if let Some(metrics) = &vm_metrics {
+            metrics.record_execution(&scope, metrics::OUTCOME_REJECTED, 0);
+        }
+        e
+    };
     
-    let engine = Engine::new(&config)
-        .map_err(|e| VmError::EngineCreationFailed(e.to_string()))?;
+    // Bound the module's tables before compiling it
+    host_env.module_limits().validate(wasm_bytes).map_err(rejected)?;
     
-    // Set up the WASM module
-    let module = Module::new(&engine, wasm_bytes)
-        .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+    // Set up the WASM module, compiling it only if the slot hasn't already
+    let module = match module_cache {
+        Some(cache) => {
+            let (module, hit) = cache.get_or_compile(engine, wasm_bytes).map_err(rejected)?;
+            if let Some(metrics) = &vm_metrics {
+                metrics.record_module_cache(&scope, hit);
+            }
+            module
+        }
+        None => Module::new(engine, wasm_bytes)
+            .map_err(|e| rejected(VmError::ModuleCreationFailed(e.to_string())))?,
+    };
         
     // Clone the host environment for the store
     let mut host_env = host_env.clone();
     
+    // Blobs loaded by an earlier execution don't stay in memory for this one
+    host_env.blob_cache = Arc::new(RwLock::new(BlobCache::default()));
+    if let Some(scope) = federation_scope {
+        host_env.set_pricing_federation(Some(scope.to_string()));
+    }
+    
+    // Let the module read which execution it is part of
+    host_env.execution_context = Some(GuestExecutionContext {
+        proposal_cid: proposal_id.map(str::to_string),
+        scope: scope.clone(),
+        execution_id,
+        timestamp: chrono::Utc::now().timestamp(),
+    });
+    
+    // Price the module's instructions with the federation's table
+    let module_multiplier = host_env.fuel_pricing().module_multiplier(wasm_bytes).map_err(rejected)?;
+    
+    // Wait for the scope's turn if the execution is scheduled
+    let ticket = match &host_env.scheduling {
+        Some((scheduler, scope)) => Some(scheduler.admit(scope).await),
+        None => None,
+    };
+    let yield_interval = ticket.as_ref().map_or(scheduler::DEFAULT_YIELD_INTERVAL, |t| t.yield_interval());
+    
     // Create a store with the host environment
-    let mut store = Store::new(&engine, host_env);
+    let mut store = Store::new(engine, host_env);
     
-    // Allocate fuel for the execution (1,000,000 units as default)
-    let fuel_limit = context.resource_authorizations()
-        .iter()
-        .find(|auth| auth.resource_type == ResourceType::Compute)
+    // Keep tables that grow at run time within the limits
+    store.limiter(|env| &mut env.table_limits);
+    
+    // Allocate fuel for the execution (1,000,000 compute units as default)
+    let compute_limit = context.authorization_for(ResourceType::Compute)
+        .map_err(|e| VmError::Unauthorized(e.to_string()))?
         .map_or(1_000_000, |auth| auth.limit);
+    let fuel_limit = pricing::fuel_budget(compute_limit, module_multiplier);
         
     store.add_fuel(fuel_limit)
         .map_err(|e| VmError::FuelAllocationFailed(e.to_string()))?;
     
-    // Create import object with host functions
-    let mut linker = host_abi::create_import_object(&mut store);
+    // Give the thread back to the executor every slice so long modules can't starve others
+    store.fuel_async_yield_interval(Some(yield_interval))
+        .map_err(|e| VmError::FuelAllocationFailed(e.to_string()))?;
+    
+    // Stub out the host calls the acting identity's roles don't allow
+    let gated_linker = match store.data().execution_policy.clone() {
+        Some(policy) if !policy.is_unrestricted() => Some(execution_policy::gated_linker(engine, linker, &mut store, &policy)?),
+        _ => None,
+    };
+    let linker = gated_linker.as_ref().unwrap_or(linker);
+    
+    // Time host calls when metrics are exported
+    let timed_linker = match &vm_metrics {
+        Some(metrics) => Some(metrics::timed_linker(engine, linker, &mut store, metrics, &scope)?),
+        None => None,
+    };
+    let linker = timed_linker.as_ref().unwrap_or(linker);
+    
+    // Route host calls through the audit log when auditing is enabled
+    let audited_linker = match store.data().syscall_audit.clone() {
+        Some(policy) => Some(audit::audited_linker(engine, linker, &mut store, &policy)?),
+        None => None,
+    };
+    let linker = audited_linker.as_ref().unwrap_or(linker);
     
     // Instantiate the module
-    let instance = linker.instantiate(&mut store, &module)
-        .map_err(|e| VmError::InstantiationFailed(e.to_string()))?;
+    let instance = linker.instantiate_async(&mut store, &module).await
+        .map_err(|e| rejected(VmError::InstantiationFailed(e.to_string())))?;
         
     // Check for a "main" export
     let main_func = instance.get_typed_func::<(), i32>(&mut store, "main")
         .or_else(|_| instance.get_typed_func::<(), i32>(&mut store, "_start"))
         .or_else(|_| instance.get_typed_func::<(), i32>(&mut store, "__main"))
-        .map_err(|_| VmError::EntryPointNotFound("No main/_start/__main function found".to_string()))?;
+        .map_err(|_| rejected(VmError::EntryPointNotFound("No main/_start/__main function found".to_string())))?;
         
     // Execute the function
-    let return_code = main_func.call(&mut store, ())
+    let outcome = main_func.call_async(&mut store, ()).await;
+    
+    // Charge the scope for the fuel used, whether or not the module trapped
+    let fuel_consumed = store.fuel_consumed().unwrap_or(0);
+    if let Some(ticket) = ticket {
+        ticket.finish(fuel_consumed);
+    }
+    
+    if let Some(metrics) = &vm_metrics {
+        let label = match &outcome {
+            Ok(_) => metrics::OUTCOME_SUCCESS,
+            Err(e) if e.downcast_ref::<HostActionDenied>().is_some() => metrics::OUTCOME_DENIED,
+            Err(e) if e.to_string().contains("out of fuel") => metrics::OUTCOME_OUT_OF_FUEL,
+            Err(_) => metrics::OUTCOME_TRAPPED,
+        };
+        metrics.record_execution(&scope, label, fuel_consumed);
+    }
+    
+    // The execution is over, so end any live log streams
+    store.data().guest_log.close();
+    
+    // Keep the audit log even when the module traps; that's when it matters most
+    let syscall_audit_cid = store.data().persist_syscall_audit_log().await
+        .map_err(|e| VmError::HostFunctionError(format!("Failed to persist syscall audit log: {}", e)))?;
+    
+    // Charge the instructions executed against the Compute authorization, including
+    // those of a run that trapped or ran out of fuel. Host side effects are already
+    // committed by now, so settling can't fail the execution.
+    let uncharged = store.data().settle_compute_usage(pricing::compute_for_fuel(fuel_consumed, module_multiplier));
+    if uncharged > 0 {
+        warn!(uncharged, "Instruction charges exceeded the remaining Compute authorization");
+    }
+    
+    let return_code = outcome
         .map_err(|e| {
-            if e.to_string().contains("out of fuel") {
+            if let Some(denied) = e.downcast_ref::<HostActionDenied>() {
+                VmError::Unauthorized(denied.to_string())
+            } else if e.to_string().contains("out of fuel") {
                 VmError::ResourceLimitExceeded("Execution exceeded fuel limit".to_string())
             } else {
                 VmError::ExecutionError(e.to_string())
@@ -1087,6 +1610,9 @@ pub async fn execute_wasm(
         code: return_code,
         resource_usage,
         dag_anchor_cid,
+        syscall_audit_cid,
+        guest_log: store.data().guest_log(),
+        registered_timers: store.data().registered_timers(),
     })
 }
 
@@ -11,19 +11,109 @@ pub fn get_memory(caller: &mut Caller<'_, ConcreteHostEnvironment>) -> Result<Me
         .ok_or_else(|| anyhow::anyhow!("Failed to find memory export"))
 }
 
-/// Safely check memory bounds without accessing the memory
-pub fn safe_check_bounds(memory: &Memory, caller: &Caller<'_, ConcreteHostEnvironment>, ptr: u32, len: u32) -> Result<(), anyhow::Error> {
-    let mem_size = memory.data_size(caller);
-    let end_ptr = ptr.checked_add(len).ok_or_else(|| anyhow::anyhow!("Memory access would overflow"))?;
-    
-    if end_ptr as usize > mem_size {
+/// Longest guest memory range a single host call parameter may span (16 MiB). Longer
+/// lengths are refused before memory is touched, so a guest can't make the host copy,
+/// allocate or hash more than this for one argument, whatever its memory size.
+pub const MAX_HOST_PARAM_LEN: usize = 16 * 1024 * 1024;
+
+/// Byte range `ptr..ptr + len` of a guest memory `mem_size` bytes long, refusing lengths
+/// over [`MAX_HOST_PARAM_LEN`], ranges whose end overflows and ranges past the end of memory
+pub fn checked_range(mem_size: usize, ptr: usize, len: usize) -> Result<std::ops::Range<usize>, anyhow::Error> {
+    if len > MAX_HOST_PARAM_LEN {
+        return Err(anyhow::anyhow!(
+            "Memory access too long: len={}, max={}", len, MAX_HOST_PARAM_LEN
+        ));
+    }
+
+    let end = ptr.checked_add(len).ok_or_else(|| anyhow::anyhow!("Memory access would overflow"))?;
+    if end > mem_size {
         return Err(anyhow::anyhow!(
             "Memory access out of bounds: ptr={}, len={}, end={}, mem_size={}",
-            ptr, len, end_ptr, mem_size
+            ptr, len, end, mem_size
         ));
     }
-    
-    Ok(())
+
+    Ok(ptr..end)
+}
+
+/// Safely check memory bounds without accessing the memory
+pub fn safe_check_bounds(memory: &Memory, caller: &Caller<'_, ConcreteHostEnvironment>, ptr: u32, len: u32) -> Result<(), anyhow::Error> {
+    checked_range(memory.data_size(caller), ptr as usize, len as usize).map(|_| ())
+}
+
+/// Reads at least this many bytes long are served as borrowed views of guest memory
+/// by [`with_guest_bytes`]; shorter reads are copied out. Below this size the copy
+/// costs less than the host call around it, and the copied bytes leave the store free
+/// for the rest of the call. `benches/memory_views.rs` measures copying against
+/// borrowing across read sizes; re-run it before moving the threshold.
+pub const ZERO_COPY_THRESHOLD: usize = 16 * 1024;
+
+/// Bounds-checked byte range of a guest memory read from the `i32` arguments of a host call
+pub fn guest_range(mem_size: usize, ptr: i32, len: i32) -> Result<std::ops::Range<usize>, anyhow::Error> {
+    if ptr < 0 || len < 0 {
+        return Err(anyhow::anyhow!("Invalid memory parameters"));
+    }
+
+    checked_range(mem_size, ptr as usize, len as usize)
+}
+
+/// Run `f` over `len` bytes of guest memory at `ptr` without copying them. The view
+/// only lives for the duration of `f`, which also gets the host environment, so a
+/// read-only host call can decode, hash or verify guest data in place.
+pub fn with_memory_view<R>(
+    caller: &mut Caller<'_, ConcreteHostEnvironment>,
+    ptr: i32,
+    len: i32,
+    f: impl FnOnce(&[u8], &ConcreteHostEnvironment) -> R,
+) -> Result<R, anyhow::Error> {
+    let memory = get_memory(caller)?;
+    let (data, env) = memory.data_and_store_mut(caller.as_context_mut());
+    let range = guest_range(data.len(), ptr, len)?;
+    Ok(f(&data[range], env))
+}
+
+/// [`with_memory_view`] for UTF-8 text, validated in place
+pub fn with_memory_str<R>(
+    caller: &mut Caller<'_, ConcreteHostEnvironment>,
+    ptr: i32,
+    len: i32,
+    f: impl FnOnce(&str, &ConcreteHostEnvironment) -> R,
+) -> Result<R, anyhow::Error> {
+    with_memory_view(caller, ptr, len, |bytes, env| {
+        std::str::from_utf8(bytes)
+            .map(|text| f(text, env))
+            .map_err(|e| anyhow::anyhow!("Invalid UTF-8 string: {}", e))
+    })?
+}
+
+/// Run `f` over guest bytes for a read-only host call, borrowing them in place when
+/// the read reaches [`ZERO_COPY_THRESHOLD`] and copying them out otherwise
+pub fn with_guest_bytes<R>(
+    caller: &mut Caller<'_, ConcreteHostEnvironment>,
+    ptr: i32,
+    len: i32,
+    f: impl FnOnce(&[u8], &ConcreteHostEnvironment) -> R,
+) -> Result<R, anyhow::Error> {
+    if len >= 0 && len as usize >= ZERO_COPY_THRESHOLD {
+        return with_memory_view(caller, ptr, len, f);
+    }
+
+    let bytes = read_memory_bytes(caller, ptr, len)?;
+    Ok(f(&bytes, caller.data()))
+}
+
+/// [`with_guest_bytes`] for UTF-8 text
+pub fn with_guest_str<R>(
+    caller: &mut Caller<'_, ConcreteHostEnvironment>,
+    ptr: i32,
+    len: i32,
+    f: impl FnOnce(&str, &ConcreteHostEnvironment) -> R,
+) -> Result<R, anyhow::Error> {
+    with_guest_bytes(caller, ptr, len, |bytes, env| {
+        std::str::from_utf8(bytes)
+            .map(|text| f(text, env))
+            .map_err(|e| anyhow::anyhow!("Invalid UTF-8 string: {}", e))
+    })?
 }
 
 /// Read a string from WASM memory
@@ -34,23 +124,15 @@ pub fn read_memory_string<'a>(caller: &mut Caller<'a, ConcreteHostEnvironment>,
     
     let memory = get_memory(caller)?;
     let data = memory.data(caller.as_context_mut());
+    let range = guest_range(data.len(), ptr, len)?;
     
-    let start = ptr as usize;
-    let end = start + len as usize;
-    
-    if end > data.len() {
-        return Err(anyhow::anyhow!(
-            "Memory access out of bounds: offset={}, size={}, mem_size={}",
-            start, len, data.len()
-        ));
-    }
-    
-    let bytes = &data[start..end];
+    let bytes = &data[range];
     String::from_utf8(bytes.to_vec())
         .map_err(|e| anyhow::anyhow!("Invalid UTF-8 string: {}", e))
 }
 
-/// Read raw bytes from WASM memory
+/// Copy raw bytes out of WASM memory. Host calls that only read the bytes should use
+/// [`with_guest_bytes`] instead; this is for calls that keep them.
 pub fn read_memory_bytes<'a>(caller: &mut Caller<'a, ConcreteHostEnvironment>, ptr: i32, len: i32) -> Result<Vec<u8>, anyhow::Error> {
     if ptr < 0 || len < 0 {
         return Err(anyhow::anyhow!("Invalid memory parameters"));
@@ -58,18 +140,8 @@ pub fn read_memory_bytes<'a>(caller: &mut Caller<'a, ConcreteHostEnvironment>, p
     
     let memory = get_memory(caller)?;
     let data = memory.data(caller.as_context_mut());
-    
-    let start = ptr as usize;
-    let end = start + len as usize;
-    
-    if end > data.len() {
-        return Err(anyhow::anyhow!(
-            "Memory access out of bounds: offset={}, size={}, mem_size={}",
-            start, len, data.len()
-        ));
-    }
-    
-    Ok(data[start..end].to_vec())
+    let range = guest_range(data.len(), ptr, len)?;
+    Ok(data[range].to_vec())
 }
 
 /// Write bytes to WASM memory
@@ -79,17 +151,11 @@ pub fn write_memory_bytes<'a>(caller: &mut Caller<'a, ConcreteHostEnvironment>,
     }
     
     let memory = get_memory(caller)?;
-    let start = ptr as usize;
-    
     let mem_size = memory.data_size(caller.as_context_mut());
-    if start + bytes.len() > mem_size {
-        return Err(anyhow::anyhow!(
-            "Memory write out of bounds: offset={}, size={}, mem_size={}",
-            start, bytes.len(), mem_size
-        ));
-    }
+    let range = checked_range(mem_size, ptr as usize, bytes.len())
+        .map_err(|e| anyhow::anyhow!("Memory write refused: {}", e))?;
     
-    memory.write(caller.as_context_mut(), start, bytes)
+    memory.write(caller.as_context_mut(), range.start, bytes)
         .map_err(|e| anyhow::anyhow!("Memory write failed: {}", e))
 }
 
@@ -109,16 +175,7 @@ pub fn write_memory_u64(caller: &mut wasmtime::Caller<'_, ConcreteHostEnvironmen
         return Err(anyhow::anyhow!("Invalid memory pointer"));
     }
     
-    let memory = get_memory(caller)?;
-    
-    // Write the u64 value as 8 bytes in little-endian order
-    memory.write(
-        caller, 
-        ptr as usize, 
-        &value.to_le_bytes(),
-    ).map_err(|_| anyhow::anyhow!("Failed to write to guest memory"))?;
-    
-    Ok(())
+    write_memory_bytes(caller, ptr, &value.to_le_bytes())
 }
 
 /// Try to allocate memory in the WASM guest
@@ -126,6 +183,9 @@ pub fn try_allocate_guest_memory<'a>(caller: &mut Caller<'a, ConcreteHostEnviron
     if size < 0 {
         return Err(anyhow::anyhow!("Cannot allocate negative memory size"));
     }
+    if size as usize > MAX_HOST_PARAM_LEN {
+        return Err(anyhow::anyhow!("Allocation too large: size={}, max={}", size, MAX_HOST_PARAM_LEN));
+    }
     
     if let Some(alloc) = caller.get_export("alloc") {
         if let Some(alloc_func) = alloc.into_func() {
@@ -148,14 +208,15 @@ pub fn write_memory_string<'a>(
     max_len: u32,
 ) -> Result<u32, anyhow::Error> {
     let bytes = value.as_bytes();
-    let write_len = std::cmp::min(bytes.len(), max_len as usize) as u32;
+    let write_len = std::cmp::min(bytes.len(), max_len as usize);
+    let range = checked_range(memory.data_size(caller.as_context_mut()), ptr as usize, write_len)?;
     
     // Write the string data
     memory.write(
         caller.as_context_mut(),
-        ptr as usize,
-        &bytes[0..write_len as usize],
+        range.start,
+        &bytes[..write_len],
     )?;
     
-    Ok(write_len)
+    Ok(write_len as u32)
 } 
\ No newline at end of file
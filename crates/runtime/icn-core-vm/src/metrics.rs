@@ -0,0 +1,225 @@
+/*!
+# Execution Metrics
+
+Operators need to see what the VM is doing on their node. A host environment with
+[`VmMetrics`] attached records, per scope:
+
+- `icn_vm_executions_total{scope, outcome}`: executions by how they ended
+  (`success`, `trapped`, `out_of_fuel`, `denied` or `rejected` before they started)
+- `icn_vm_fuel_consumed{scope}`: fuel consumed per execution
+- `icn_vm_host_call_seconds{scope, function}`: latency of each host function
+- `icn_vm_module_cache_requests_total{scope, result}`: compiled module cache `hit`s
+  and `miss`es on warm pool slots
+- `icn_vm_queue_depth{scope, queue}`: executions waiting for the fair scheduler
+
+The scope is the execution's federation scope, or the tenant's scope without one.
+Metrics live in their own Prometheus registry, which the node serves for scraping with
+[`VmMetrics::gather_text`]. [`VmMetrics::scope_summary`] condenses a scope's metrics
+into the execution success rate and cache hit rate the federation health score uses.
+
+Host call latency is measured by re-registering every function of the linker behind a
+timing wrapper, as the syscall audit log does, so host functions don't need to know
+about it.
+*/
+
+use std::time::{Duration, Instant};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use wasmtime::{Caller, Engine, Extern, Linker, Store};
+
+use crate::{ConcreteHostEnvironment, VmError};
+
+/// Outcome label of an execution that returned normally
+pub const OUTCOME_SUCCESS: &str = "success";
+/// Outcome label of an execution that trapped
+pub const OUTCOME_TRAPPED: &str = "trapped";
+/// Outcome label of an execution that ran out of fuel
+pub const OUTCOME_OUT_OF_FUEL: &str = "out_of_fuel";
+/// Outcome label of an execution stopped by its execution policy
+pub const OUTCOME_DENIED: &str = "denied";
+/// Outcome label of an execution whose module was refused before it ran
+pub const OUTCOME_REJECTED: &str = "rejected";
+
+const OUTCOMES: [&str; 5] = [OUTCOME_SUCCESS, OUTCOME_TRAPPED, OUTCOME_OUT_OF_FUEL, OUTCOME_DENIED, OUTCOME_REJECTED];
+
+/// Queue label of executions waiting for the fair scheduler
+pub const SCHEDULER_QUEUE: &str = "scheduler";
+
+/// Prometheus metrics of the executions on a node
+#[derive(Clone)]
+pub struct VmMetrics {
+    registry: Registry,
+    executions: IntCounterVec,
+    fuel_consumed: HistogramVec,
+    host_call_seconds: HistogramVec,
+    module_cache: IntCounterVec,
+    queue_depth: IntGaugeVec,
+}
+
+/// A scope's metrics condensed for health reporting
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VmScopeMetrics {
+    pub executions: u64,
+    pub successful: u64,
+    pub fuel_consumed: f64,
+    pub module_cache_hits: u64,
+    pub module_cache_misses: u64,
+    pub queue_depth: i64,
+}
+
+impl VmScopeMetrics {
+    /// Fraction of executions that succeeded, if there were any
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.executions > 0).then(|| self.successful as f64 / self.executions as f64)
+    }
+
+    /// Fraction of module lookups served from the cache, if there were any
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let lookups = self.module_cache_hits + self.module_cache_misses;
+        (lookups > 0).then(|| self.module_cache_hits as f64 / lookups as f64)
+    }
+}
+
+fn metric_error(e: prometheus::Error) -> VmError {
+    VmError::InitializationError(format!("Failed to register VM metrics: {}", e))
+}
+
+impl VmMetrics {
+    /// Create the metrics in a registry of their own
+    pub fn new() -> Result<Self, VmError> {
+        Self::with_registry(Registry::new())
+    }
+
+    /// Create the metrics in an existing registry, e.g. the node's
+    pub fn with_registry(registry: Registry) -> Result<Self, VmError> {
+        let executions = IntCounterVec::new(
+            Opts::new("icn_vm_executions_total", "Executions by outcome"),
+            &["scope", "outcome"],
+        ).map_err(metric_error)?;
+        let fuel_consumed = HistogramVec::new(
+            HistogramOpts::new("icn_vm_fuel_consumed", "Fuel consumed per execution")
+                .buckets(prometheus::exponential_buckets(1_000.0, 4.0, 10).map_err(metric_error)?),
+            &["scope"],
+        ).map_err(metric_error)?;
+        let host_call_seconds = HistogramVec::new(
+            HistogramOpts::new("icn_vm_host_call_seconds", "Host call latency by function")
+                .buckets(vec![0.00001, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0]),
+            &["scope", "function"],
+        ).map_err(metric_error)?;
+        let module_cache = IntCounterVec::new(
+            Opts::new("icn_vm_module_cache_requests_total", "Compiled module cache lookups by result"),
+            &["scope", "result"],
+        ).map_err(metric_error)?;
+        let queue_depth = IntGaugeVec::new(
+            Opts::new("icn_vm_queue_depth", "Executions waiting to run"),
+            &["scope", "queue"],
+        ).map_err(metric_error)?;
+
+        registry.register(Box::new(executions.clone())).map_err(metric_error)?;
+        registry.register(Box::new(fuel_consumed.clone())).map_err(metric_error)?;
+        registry.register(Box::new(host_call_seconds.clone())).map_err(metric_error)?;
+        registry.register(Box::new(module_cache.clone())).map_err(metric_error)?;
+        registry.register(Box::new(queue_depth.clone())).map_err(metric_error)?;
+
+        Ok(Self { registry, executions, fuel_consumed, host_call_seconds, module_cache, queue_depth })
+    }
+
+    /// The registry the metrics are registered in
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// The metrics in the Prometheus text exposition format, for scraping
+    pub fn gather_text(&self) -> Result<String, VmError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| VmError::InitializationError(format!("Failed to encode VM metrics: {}", e)))?;
+        String::from_utf8(buffer)
+            .map_err(|e| VmError::InitializationError(format!("Failed to encode VM metrics: {}", e)))
+    }
+
+    /// Record how an execution ended and the fuel it consumed
+    pub fn record_execution(&self, scope: &str, outcome: &str, fuel_consumed: u64) {
+        self.executions.with_label_values(&[scope, outcome]).inc();
+        if outcome != OUTCOME_REJECTED {
+            self.fuel_consumed.with_label_values(&[scope]).observe(fuel_consumed as f64);
+        }
+    }
+
+    /// Record how long a host call took
+    pub fn observe_host_call(&self, scope: &str, function: &str, elapsed: Duration) {
+        self.host_call_seconds.with_label_values(&[scope, function]).observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a compiled module cache lookup
+    pub fn record_module_cache(&self, scope: &str, hit: bool) {
+        self.module_cache.with_label_values(&[scope, if hit { "hit" } else { "miss" }]).inc();
+    }
+
+    /// Set how many executions are waiting in a queue
+    pub fn set_queue_depth(&self, scope: &str, queue: &str, depth: usize) {
+        self.queue_depth.with_label_values(&[scope, queue]).set(depth as i64);
+    }
+
+    /// A scope's metrics condensed for health reporting
+    pub fn scope_summary(&self, scope: &str) -> VmScopeMetrics {
+        let executions_with = |outcome: &str| self.executions.get_metric_with_label_values(&[scope, outcome])
+            .map_or(0, |counter| counter.get());
+        let cache = |result: &str| self.module_cache.get_metric_with_label_values(&[scope, result])
+            .map_or(0, |counter| counter.get());
+
+        VmScopeMetrics {
+            executions: OUTCOMES.iter().map(|outcome| executions_with(outcome)).sum(),
+            successful: executions_with(OUTCOME_SUCCESS),
+            fuel_consumed: self.fuel_consumed.get_metric_with_label_values(&[scope])
+                .map_or(0.0, |histogram| histogram.get_sample_sum()),
+            module_cache_hits: cache("hit"),
+            module_cache_misses: cache("miss"),
+            queue_depth: self.queue_depth.get_metric_with_label_values(&[scope, SCHEDULER_QUEUE])
+                .map_or(0, |gauge| gauge.get()),
+        }
+    }
+}
+
+impl std::fmt::Debug for VmMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VmMetrics").finish_non_exhaustive()
+    }
+}
+
+/// A linker whose functions time each call into `metrics` under `scope`
+pub(crate) fn timed_linker(
+    engine: &Engine,
+    linker: &Linker<ConcreteHostEnvironment>,
+    store: &mut Store<ConcreteHostEnvironment>,
+    metrics: &VmMetrics,
+    scope: &str,
+) -> Result<Linker<ConcreteHostEnvironment>, VmError> {
+    let definitions: Vec<(String, String, Extern)> = linker.iter(&mut *store)
+        .map(|(module, name, item)| (module.to_string(), name.to_string(), item))
+        .collect();
+
+    let mut timed = Linker::new(engine);
+    for (module, name, item) in definitions {
+        let func = match item {
+            Extern::Func(func) => func,
+            other => {
+                timed.define(&mut *store, &module, &name, other)
+                    .map_err(|e| VmError::EngineCreationFailed(format!("Failed to define {}::{}: {}", module, name, e)))?;
+                continue;
+            }
+        };
+
+        let ty = func.ty(&*store);
+        let histogram = metrics.host_call_seconds.with_label_values(&[scope, &name]);
+        timed.func_new(&module, &name, ty, move |mut caller: Caller<'_, ConcreteHostEnvironment>, params, results| {
+            let started = Instant::now();
+            let outcome = func.call(&mut caller, params, results);
+            histogram.observe(started.elapsed().as_secs_f64());
+            outcome
+        }).map_err(|e| VmError::EngineCreationFailed(format!("Failed to wrap {}::{}: {}", module, name, e)))?;
+    }
+
+    Ok(timed)
+}
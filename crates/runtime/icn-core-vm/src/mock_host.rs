@@ -0,0 +1,173 @@
+/*!
+# Mock Host Environment
+
+A [`HostEnvironment`] for unit-testing modules without a storage or identity stack.
+Responses are scripted per import name (the import module is ignored): a fixed set of
+return values, bytes written to guest memory, a trap, or a handler closure. One-shot
+responses queued with [`MockHostEnvironment::respond_once`] are used first, in order,
+before the standing response set with [`MockHostEnvironment::respond`]. Unscripted calls
+return zeros, unless the mock is strict, in which case they trap.
+
+Every call is recorded with its parameters. Calls registered with
+[`MockHostEnvironment::capture`] also record the guest bytes a pointer and length
+parameter refer to, so tests can check what a module logged or stored.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use wasmtime::Val;
+
+use crate::host_env::{HostCall, HostEnvironment};
+use crate::VmError;
+
+/// Scripted handler for a host call
+pub type MockHandler = Box<dyn FnMut(&mut HostCall<'_>) -> Result<Vec<Val>, VmError> + Send>;
+
+/// How the mock answers a call
+pub enum MockResponse {
+    /// Return these values
+    Values(Vec<Val>),
+    /// Write `bytes` at the address in parameter `ptr_param` and return their length
+    /// as an i32
+    WriteBytes { ptr_param: usize, bytes: Vec<u8> },
+    /// Trap the module with this message
+    Trap(String),
+    /// Let a closure answer the call
+    Handler(MockHandler),
+}
+
+impl MockResponse {
+    /// Return a single i32
+    pub fn i32(value: i32) -> Self {
+        MockResponse::Values(vec![Val::I32(value)])
+    }
+
+    /// Answer with a closure
+    pub fn handler(f: impl FnMut(&mut HostCall<'_>) -> Result<Vec<Val>, VmError> + Send + 'static) -> Self {
+        MockResponse::Handler(Box::new(f))
+    }
+
+    fn answer(&mut self, call: &mut HostCall<'_>) -> Result<Vec<Val>, VmError> {
+        match self {
+            MockResponse::Values(values) => Ok(values.clone()),
+            MockResponse::WriteBytes { ptr_param, bytes } => {
+                let ptr = call.i32_param(*ptr_param).ok_or_else(|| VmError::HostFunctionError(format!(
+                    "{} has no i32 parameter {}", call.name, ptr_param
+                )))?;
+                call.write_bytes(ptr, bytes)?;
+                Ok(vec![Val::I32(bytes.len() as i32)])
+            }
+            MockResponse::Trap(message) => Err(VmError::HostFunctionError(message.clone())),
+            MockResponse::Handler(handler) => handler(call),
+        }
+    }
+}
+
+/// A host call the module made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedCall {
+    pub module: String,
+    pub name: String,
+    /// Integer parameters as i64; floats as their bit patterns, references as 0
+    pub params: Vec<i64>,
+    /// Guest bytes captured for the call, see [`MockHostEnvironment::capture`]
+    pub data: Option<Vec<u8>>,
+}
+
+impl RecordedCall {
+    /// The captured bytes as UTF-8, if any
+    pub fn data_str(&self) -> Option<&str> {
+        self.data.as_deref().and_then(|data| std::str::from_utf8(data).ok())
+    }
+}
+
+fn param_bits(value: &Val) -> i64 {
+    match value {
+        Val::I32(v) => *v as i64,
+        Val::I64(v) => *v,
+        Val::F32(bits) => *bits as i64,
+        Val::F64(bits) => *bits as i64,
+        _ => 0,
+    }
+}
+
+/// A scriptable, recording [`HostEnvironment`]
+#[derive(Default)]
+pub struct MockHostEnvironment {
+    responses: HashMap<String, MockResponse>,
+    queued: HashMap<String, VecDeque<MockResponse>>,
+    captures: HashMap<String, (usize, usize)>,
+    strict: bool,
+    calls: Vec<RecordedCall>,
+}
+
+impl MockHostEnvironment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trap on calls without a scripted response instead of returning zeros
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Answer every call to `name` with `response`
+    pub fn respond(mut self, name: &str, response: MockResponse) -> Self {
+        self.responses.insert(name.to_string(), response);
+        self
+    }
+
+    /// Answer the next call to `name` with `response`; queued responses are used in order
+    pub fn respond_once(mut self, name: &str, response: MockResponse) -> Self {
+        self.queued.entry(name.to_string()).or_default().push_back(response);
+        self
+    }
+
+    /// Record the guest bytes at parameters `ptr_param` and `len_param` of calls to `name`
+    pub fn capture(mut self, name: &str, ptr_param: usize, len_param: usize) -> Self {
+        self.captures.insert(name.to_string(), (ptr_param, len_param));
+        self
+    }
+
+    /// Every call, in the order the module made them
+    pub fn calls(&self) -> &[RecordedCall] {
+        &self.calls
+    }
+
+    /// The calls to one import
+    pub fn calls_to(&self, name: &str) -> Vec<&RecordedCall> {
+        self.calls.iter().filter(|call| call.name == name).collect()
+    }
+}
+
+impl HostEnvironment for MockHostEnvironment {
+    fn call_host(&mut self, call: &mut HostCall<'_>) -> Result<Vec<Val>, VmError> {
+        let data = match self.captures.get(call.name) {
+            Some((ptr_param, len_param)) => {
+                let (ptr, len) = (call.i32_param(*ptr_param), call.i32_param(*len_param));
+                match (ptr, len) {
+                    (Some(ptr), Some(len)) => Some(call.read_bytes(ptr, len)?.to_vec()),
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+        self.calls.push(RecordedCall {
+            module: call.module.to_string(),
+            name: call.name.to_string(),
+            params: call.params.iter().map(param_bits).collect(),
+            data,
+        });
+
+        if let Some(mut response) = self.queued.get_mut(call.name).and_then(|queue| queue.pop_front()) {
+            return response.answer(call);
+        }
+        match self.responses.get_mut(call.name) {
+            Some(response) => response.answer(call),
+            None if self.strict => Err(VmError::HostFunctionError(format!(
+                "No scripted response for {}::{}", call.module, call.name
+            ))),
+            None => Ok(call.default_results()),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+/*!
+# Module Limits
+
+Modules built by standard toolchains don't only make direct calls. Rust lowers trait
+object methods and function pointers to `call_indirect` through a funcref table that
+element segments fill at instantiation. The VM runs such modules, but a table is host
+memory the module sizes itself, so tables are bounded before a module is
+instantiated:
+
+- at most `max_tables` tables, imported or defined
+- no table's initial size, or declared maximum, above `max_table_elements`
+- at most `max_element_segments` element segments
+
+A table declared without a maximum can still grow at run time with `table.grow`; the
+store's limiter refuses growth past `max_table_elements`, so the instruction returns
+-1 as it does for any failed growth.
+*/
+
+use serde::{Serialize, Deserialize};
+use wasmparser::{Operator, Parser, Payload, TableType, TypeRef};
+use crate::VmError;
+
+/// Bounds on the tables a module may declare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleLimits {
+    /// Tables a module may import or define
+    pub max_tables: u32,
+    /// Elements any one table may hold
+    pub max_table_elements: u32,
+    /// Element segments a module may declare
+    pub max_element_segments: u32,
+}
+
+impl Default for ModuleLimits {
+    fn default() -> Self {
+        Self {
+            max_tables: 4,
+            max_table_elements: 10_000,
+            max_element_segments: 1_000,
+        }
+    }
+}
+
+/// A table as declared by a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub initial: u32,
+    pub maximum: Option<u32>,
+    /// Whether the table is imported rather than defined by the module
+    pub imported: bool,
+}
+
+/// What a module declares for indirect calls
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleTables {
+    /// Tables in index order, imported tables first
+    pub tables: Vec<TableInfo>,
+    pub element_segments: u32,
+    /// `call_indirect` and `return_call_indirect` instructions in the module's code
+    pub indirect_calls: u64,
+}
+
+impl ModuleLimits {
+    /// Check a module's tables against the limits. Accepts the binary or text format.
+    pub fn validate(&self, wasm_bytes: &[u8]) -> Result<ModuleTables, VmError> {
+        let binary = wat::parse_bytes(wasm_bytes)
+            .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+        let parse_error = |e: wasmparser::BinaryReaderError| VmError::ModuleCreationFailed(e.to_string());
+
+        let mut summary = ModuleTables::default();
+        for payload in Parser::new(0).parse_all(&binary) {
+            match payload.map_err(parse_error)? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if let TypeRef::Table(ty) = import.map_err(parse_error)?.ty {
+                            summary.tables.push(self.check_table(&ty, true, summary.tables.len())?);
+                        }
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        let table = table.map_err(parse_error)?;
+                        summary.tables.push(self.check_table(&table.ty, false, summary.tables.len())?);
+                    }
+                }
+                Payload::ElementSection(reader) => {
+                    summary.element_segments += reader.count();
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let mut reader = body.get_operators_reader().map_err(parse_error)?;
+                    while !reader.eof() {
+                        if matches!(
+                            reader.read().map_err(parse_error)?,
+                            Operator::CallIndirect { .. } | Operator::ReturnCallIndirect { .. }
+                        ) {
+                            summary.indirect_calls += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if summary.element_segments > self.max_element_segments {
+            return Err(VmError::ResourceLimitExceeded(format!(
+                "Module declares {} element segments, more than the limit of {}",
+                summary.element_segments, self.max_element_segments
+            )));
+        }
+
+        Ok(summary)
+    }
+
+    /// Check one table, the `index`th the module declares
+    fn check_table(&self, ty: &TableType, imported: bool, index: usize) -> Result<TableInfo, VmError> {
+        if index as u64 >= self.max_tables as u64 {
+            return Err(VmError::ResourceLimitExceeded(format!(
+                "Module declares more than {} tables", self.max_tables
+            )));
+        }
+
+        let largest = ty.maximum.unwrap_or(ty.initial);
+        if largest > self.max_table_elements {
+            return Err(VmError::ResourceLimitExceeded(format!(
+                "Table {} may hold {} elements, more than the limit of {}",
+                index, largest, self.max_table_elements
+            )));
+        }
+
+        Ok(TableInfo { initial: ty.initial, maximum: ty.maximum, imported })
+    }
+
+    /// Store limits that stop tables growing past the limits at run time
+    pub fn store_limits(&self) -> wasmtime::StoreLimits {
+        wasmtime::StoreLimitsBuilder::new()
+            .tables(self.max_tables as usize)
+            .table_elements(self.max_table_elements)
+            .build()
+    }
+}
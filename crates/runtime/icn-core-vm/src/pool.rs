@@ -0,0 +1,247 @@
+/*!
+# Warm Execution Pool
+
+Creating an engine and registering every host function on a fresh linker dominates
+setup time for short governance actions. The warm pool keeps engines and linkers ready
+so an execution only has to compile its module and create a store.
+
+Slots never hold per-execution state. Each lease gets a fresh `Store`, built from a
+clone of the caller's host environment, with its own fuel budget. The store is dropped
+when the execution ends, so memory, globals, fuel and resource accounting cannot leak
+between executions that reuse the same slot.
+
+Compiled modules are the exception: they're immutable, so each slot keeps the last few
+it compiled, keyed by the SHA-256 of their bytes, and a module executed again on the
+same slot skips compilation.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use sha2::{Digest, Sha256};
+use wasmtime::{Config, Engine, Linker, Module};
+
+use crate::{ConcreteHostEnvironment, VMContext, VmError, VmExecutionResult};
+use crate::host_abi::register_host_functions;
+
+/// Configuration for the warm pool
+#[derive(Debug, Clone)]
+pub struct WarmPoolConfig {
+    /// Number of slots created up front
+    pub size: usize,
+    /// Maximum number of idle slots kept; extra slots are dropped when returned
+    pub max_idle: usize,
+}
+
+impl Default for WarmPoolConfig {
+    fn default() -> Self {
+        Self { size: 4, max_idle: 8 }
+    }
+}
+
+/// Counters describing how the pool has been used
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarmPoolStats {
+    /// Leases served from a pre-initialized slot
+    pub warm_leases: u64,
+    /// Leases that had to create a slot because the pool was empty
+    pub cold_starts: u64,
+    /// Slots currently idle in the pool
+    pub idle: usize,
+}
+
+/// Create an engine configured the way the VM executes modules
+pub fn create_engine() -> Result<Engine, VmError> {
+    let mut config = Config::new();
+    config.wasm_bulk_memory(true);
+    config.wasm_reference_types(true);
+    config.async_support(true);
+    config.consume_fuel(true);
+
+    // Sandbox hardening: no shared or 64-bit memories, whose pointers the host
+    // helpers don't handle, and a guard region before every linear memory as well
+    // as after it, so a miscompiled negative offset traps instead of reaching host memory
+    config.wasm_threads(false);
+    config.wasm_memory64(false);
+    config.guard_before_linear_memory(true);
+
+    Engine::new(&config)
+        .map_err(|e| VmError::EngineCreationFailed(e.to_string()))
+}
+
+/// Compiled modules a slot keeps
+const MODULE_CACHE_CAPACITY: usize = 32;
+
+/// Compiled modules keyed by the SHA-256 of their bytes, oldest evicted first
+#[derive(Default)]
+pub(crate) struct ModuleCache {
+    entries: Mutex<(HashMap<[u8; 32], Module>, VecDeque<[u8; 32]>)>,
+}
+
+impl ModuleCache {
+    /// The compiled module for `wasm_bytes`, and whether it came from the cache
+    pub(crate) fn get_or_compile(&self, engine: &Engine, wasm_bytes: &[u8]) -> Result<(Module, bool), VmError> {
+        let key: [u8; 32] = Sha256::digest(wasm_bytes).into();
+        if let Some(module) = self.entries.lock().ok().and_then(|entries| entries.0.get(&key).cloned()) {
+            return Ok((module, true));
+        }
+
+        let module = Module::new(engine, wasm_bytes)
+            .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+        if let Ok(mut entries) = self.entries.lock() {
+            let (modules, order) = &mut *entries;
+            if modules.insert(key, module.clone()).is_none() {
+                order.push_back(key);
+            }
+            while order.len() > MODULE_CACHE_CAPACITY {
+                if let Some(oldest) = order.pop_front() {
+                    modules.remove(&oldest);
+                }
+            }
+        }
+        Ok((module, false))
+    }
+}
+
+/// An engine with a linker that already has every host function registered
+pub struct WarmSlot {
+    engine: Engine,
+    linker: Linker<ConcreteHostEnvironment>,
+    modules: ModuleCache,
+}
+
+impl WarmSlot {
+    /// Create and initialize a slot
+    pub fn new() -> Result<Self, VmError> {
+        let engine = create_engine()?;
+        let mut linker = Linker::new(&engine);
+        register_host_functions(&mut linker)?;
+        Ok(Self { engine, linker, modules: ModuleCache::default() })
+    }
+
+    /// The slot's engine
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// The slot's linker
+    pub fn linker(&self) -> &Linker<ConcreteHostEnvironment> {
+        &self.linker
+    }
+}
+
+struct PoolInner {
+    idle: Mutex<Vec<WarmSlot>>,
+    stats: Mutex<WarmPoolStats>,
+    max_idle: usize,
+}
+
+/// Pool of pre-initialized execution slots
+#[derive(Clone)]
+pub struct WarmPool {
+    inner: Arc<PoolInner>,
+}
+
+impl WarmPool {
+    /// Create a pool and initialize its slots
+    pub fn new(config: WarmPoolConfig) -> Result<Self, VmError> {
+        let mut slots = Vec::with_capacity(config.size);
+        for _ in 0..config.size {
+            slots.push(WarmSlot::new()?);
+        }
+
+        let stats = WarmPoolStats { idle: slots.len(), ..Default::default() };
+        Ok(Self {
+            inner: Arc::new(PoolInner {
+                idle: Mutex::new(slots),
+                stats: Mutex::new(stats),
+                max_idle: config.max_idle.max(config.size),
+            }),
+        })
+    }
+
+    /// Lease a slot. If none are idle a new one is created, so a lease never waits.
+    pub fn lease(&self) -> Result<WarmLease, VmError> {
+        let slot = self.inner.idle.lock()
+            .map_err(|_| VmError::InitializationError("Warm pool lock poisoned".to_string()))?
+            .pop();
+
+        let slot = match slot {
+            Some(slot) => {
+                self.record(|stats| stats.warm_leases += 1);
+                slot
+            }
+            None => {
+                self.record(|stats| stats.cold_starts += 1);
+                WarmSlot::new()?
+            }
+        };
+
+        refresh_idle(&self.inner);
+        Ok(WarmLease { slot: Some(slot), pool: self.inner.clone() })
+    }
+
+    /// Current usage counters
+    pub fn stats(&self) -> WarmPoolStats {
+        self.inner.stats.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn record(&self, update: impl FnOnce(&mut WarmPoolStats)) {
+        if let Ok(mut stats) = self.inner.stats.lock() {
+            update(&mut stats);
+        }
+    }
+}
+
+fn refresh_idle(inner: &PoolInner) {
+    let idle = inner.idle.lock().map(|slots| slots.len()).unwrap_or(0);
+    if let Ok(mut stats) = inner.stats.lock() {
+        stats.idle = idle;
+    }
+}
+
+/// A slot leased from the pool; it goes back to the pool when dropped
+pub struct WarmLease {
+    slot: Option<WarmSlot>,
+    pool: Arc<PoolInner>,
+}
+
+impl WarmLease {
+    /// The leased slot
+    pub fn slot(&self) -> &WarmSlot {
+        self.slot.as_ref().expect("slot is present until the lease is dropped")
+    }
+}
+
+impl Drop for WarmLease {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            if let Ok(mut idle) = self.pool.idle.lock() {
+                if idle.len() < self.pool.max_idle {
+                    idle.push(slot);
+                }
+            }
+            refresh_idle(&self.pool);
+        }
+    }
+}
+
+/// Execute a WASM module on a slot leased from the warm pool.
+///
+/// Behaves like [`crate::execute_wasm`], but skips engine creation and host
+/// function registration, and compilation if the slot has run the module before.
+pub async fn execute_wasm_pooled(
+    pool: &WarmPool,
+    wasm_bytes: &[u8],
+    context: Option<VMContext>,
+    host_env: &ConcreteHostEnvironment,
+    proposal_id: Option<&str>,
+    federation_scope: Option<&str>,
+) -> Result<VmExecutionResult, VmError> {
+    let setup_started = Instant::now();
+    let lease = pool.lease()?;
+    tracing::debug!("Leased warm slot in {:?}", setup_started.elapsed());
+
+    let slot = lease.slot();
+    crate::run_module(slot.engine(), slot.linker(), Some(&slot.modules), wasm_bytes, context, host_env, proposal_id, federation_scope).await
+}
@@ -0,0 +1,292 @@
+/*!
+# Fuel Pricing
+
+By default every wasm instruction costs one unit of fuel. Instructions and host calls
+do not cost the same to run, though; storage calls in particular cost far more than
+arithmetic. This module maps host call classes and instruction groups to multipliers,
+and a federation can configure its own table. Hosts price everything at 1x until they
+opt in with `ConcreteHostEnvironment::with_fuel_pricing`; [`FuelPricingTable::default`] is the
+suggested tiered table to opt in with.
+
+Multipliers are expressed in percent (`100` = 1x) so that charging stays integral and
+deterministic across nodes.
+
+- Host calls: each host call's base cost is scaled by the multiplier for its class
+  before it is charged against the Compute authorization.
+- Instructions: wasmtime charges one fuel per instruction, so the module's code is
+  scanned once to get a blended multiplier, weighted by how often each instruction
+  group appears. The fuel budget is derived from the Compute authorization using this
+  blended multiplier. Fuel actually consumed is converted back to compute units when
+  the execution completes.
+*/
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use wasmparser::{Operator, Parser, Payload};
+use crate::VmError;
+
+/// Multiplier that leaves a cost unchanged
+pub const UNIT_MULTIPLIER: u32 = 100;
+
+/// Class of host call, for pricing purposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HostCallClass {
+    /// Key-value and blob reads
+    StorageRead,
+    /// Key-value and blob writes
+    StorageWrite,
+    /// DAG node storage, lookup and anchoring
+    Dag,
+    /// DID generation and other identity operations
+    Identity,
+    /// Signing and verification
+    Crypto,
+    /// Token and budget operations
+    Economics,
+    /// Windowed reads of input blobs
+    BlobRead,
+    /// Logging and other diagnostics
+    Logging,
+    /// Proposal creation and voting
+    Governance,
+}
+
+/// Group of wasm instructions, for pricing purposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InstructionGroup {
+    /// Arithmetic, comparison and conversion
+    Numeric,
+    /// Locals, globals and other variable access
+    Variable,
+    /// Linear memory loads, stores and bulk operations
+    Memory,
+    /// Blocks, branches and other control flow
+    Control,
+    /// Direct and indirect calls
+    Call,
+}
+
+impl InstructionGroup {
+    /// Classify a wasm operator
+    pub fn of(op: &Operator<'_>) -> Self {
+        match op {
+            Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } => InstructionGroup::Call,
+
+            Operator::Unreachable
+            | Operator::Nop
+            | Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Drop
+            | Operator::Select
+            | Operator::TypedSelect { .. } => InstructionGroup::Control,
+
+            Operator::LocalGet { .. }
+            | Operator::LocalSet { .. }
+            | Operator::LocalTee { .. }
+            | Operator::GlobalGet { .. }
+            | Operator::GlobalSet { .. } => InstructionGroup::Variable,
+
+            Operator::I32Load { .. }
+            | Operator::I64Load { .. }
+            | Operator::F32Load { .. }
+            | Operator::F64Load { .. }
+            | Operator::I32Load8S { .. }
+            | Operator::I32Load8U { .. }
+            | Operator::I32Load16S { .. }
+            | Operator::I32Load16U { .. }
+            | Operator::I64Load8S { .. }
+            | Operator::I64Load8U { .. }
+            | Operator::I64Load16S { .. }
+            | Operator::I64Load16U { .. }
+            | Operator::I64Load32S { .. }
+            | Operator::I64Load32U { .. }
+            | Operator::I32Store { .. }
+            | Operator::I64Store { .. }
+            | Operator::F32Store { .. }
+            | Operator::F64Store { .. }
+            | Operator::I32Store8 { .. }
+            | Operator::I32Store16 { .. }
+            | Operator::I64Store8 { .. }
+            | Operator::I64Store16 { .. }
+            | Operator::I64Store32 { .. }
+            | Operator::MemorySize { .. }
+            | Operator::MemoryGrow { .. }
+            | Operator::MemoryCopy { .. }
+            | Operator::MemoryFill { .. }
+            | Operator::MemoryInit { .. }
+            | Operator::DataDrop { .. } => InstructionGroup::Memory,
+
+            _ => InstructionGroup::Numeric,
+        }
+    }
+}
+
+/// Fuel multipliers for host call classes and instruction groups
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuelPricingTable {
+    /// Multiplier per host call class, in percent
+    pub host_calls: HashMap<HostCallClass, u32>,
+    /// Multiplier per instruction group, in percent
+    pub instructions: HashMap<InstructionGroup, u32>,
+}
+
+impl Default for FuelPricingTable {
+    fn default() -> Self {
+        let host_calls = HashMap::from([
+            (HostCallClass::StorageRead, 200),
+            (HostCallClass::StorageWrite, 400),
+            (HostCallClass::Dag, 300),
+            (HostCallClass::Identity, 200),
+            (HostCallClass::Crypto, 300),
+            (HostCallClass::Economics, 150),
+            (HostCallClass::BlobRead, 150),
+            (HostCallClass::Logging, UNIT_MULTIPLIER),
+            (HostCallClass::Governance, 300),
+        ]);
+        let instructions = HashMap::from([
+            (InstructionGroup::Numeric, UNIT_MULTIPLIER),
+            (InstructionGroup::Variable, UNIT_MULTIPLIER),
+            (InstructionGroup::Memory, 200),
+            (InstructionGroup::Control, UNIT_MULTIPLIER),
+            (InstructionGroup::Call, 300),
+        ]);
+        Self { host_calls, instructions }
+    }
+}
+
+impl FuelPricingTable {
+    /// A table where every multiplier is 1x
+    pub fn flat() -> Self {
+        Self {
+            host_calls: HashMap::new(),
+            instructions: HashMap::new(),
+        }
+    }
+
+    /// Multiplier for a host call class (1x if not configured)
+    pub fn host_call_multiplier(&self, class: HostCallClass) -> u32 {
+        self.host_calls.get(&class).copied().unwrap_or(UNIT_MULTIPLIER)
+    }
+
+    /// Multiplier for an instruction group (1x if not configured)
+    pub fn instruction_multiplier(&self, group: InstructionGroup) -> u32 {
+        self.instructions.get(&group).copied().unwrap_or(UNIT_MULTIPLIER)
+    }
+
+    /// Compute cost of a host call with the given base cost
+    pub fn host_call_cost(&self, class: HostCallClass, base_cost: u64) -> u64 {
+        apply_multiplier(base_cost, self.host_call_multiplier(class))
+    }
+
+    /// Blended instruction multiplier for a module, weighted by how often each
+    /// instruction group occurs in its code
+    pub fn module_multiplier(&self, wasm_bytes: &[u8]) -> Result<u32, VmError> {
+        let counts = count_instruction_groups(wasm_bytes)?;
+        let total: u64 = counts.values().sum();
+        if total == 0 {
+            return Ok(UNIT_MULTIPLIER);
+        }
+
+        let weighted: u64 = counts.iter()
+            .map(|(group, count)| count * self.instruction_multiplier(*group) as u64)
+            .sum();
+        Ok(weighted.div_ceil(total).max(1) as u32)
+    }
+
+    /// Validate the table. Zero multipliers would make work free.
+    pub fn validate(&self) -> Result<(), VmError> {
+        if self.host_calls.values().chain(self.instructions.values()).any(|m| *m == 0) {
+            return Err(VmError::InitializationError("Fuel multipliers must be greater than zero".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Per-federation fuel pricing tables with a fallback default
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuelPricingRegistry {
+    /// Table used for federations without their own configuration
+    pub default_table: FuelPricingTable,
+    /// Tables configured by individual federations, keyed by federation ID
+    pub federations: HashMap<String, FuelPricingTable>,
+}
+
+impl Default for FuelPricingRegistry {
+    /// Every multiplier 1x, so charges only change for federations that opt in
+    fn default() -> Self {
+        Self::new(FuelPricingTable::flat())
+    }
+}
+
+impl FuelPricingRegistry {
+    /// Create a registry with the given default table
+    pub fn new(default_table: FuelPricingTable) -> Self {
+        Self {
+            default_table,
+            federations: HashMap::new(),
+        }
+    }
+
+    /// Configure the table for a federation
+    pub fn set_federation_table(&mut self, federation_id: &str, table: FuelPricingTable) -> Result<(), VmError> {
+        table.validate()?;
+        self.federations.insert(federation_id.to_string(), table);
+        Ok(())
+    }
+
+    /// Get the table that applies to a federation
+    pub fn table_for(&self, federation_id: Option<&str>) -> &FuelPricingTable {
+        federation_id
+            .and_then(|id| self.federations.get(id))
+            .unwrap_or(&self.default_table)
+    }
+}
+
+/// Fuel to allocate for a Compute authorization, given a module's blended multiplier
+pub fn fuel_budget(compute_limit: u64, module_multiplier: u32) -> u64 {
+    let multiplier = module_multiplier.max(1) as u128;
+    ((compute_limit as u128 * UNIT_MULTIPLIER as u128) / multiplier).min(u64::MAX as u128) as u64
+}
+
+/// Compute units charged for the fuel a module consumed
+pub fn compute_for_fuel(fuel_consumed: u64, module_multiplier: u32) -> u64 {
+    apply_multiplier(fuel_consumed, module_multiplier)
+}
+
+/// Count the instructions of each group in a module's function bodies. Accepts the
+/// binary or text format.
+pub fn count_instruction_groups(wasm_bytes: &[u8]) -> Result<HashMap<InstructionGroup, u64>, VmError> {
+    let binary = wat::parse_bytes(wasm_bytes)
+        .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+    let mut counts = HashMap::new();
+
+    for payload in Parser::new(0).parse_all(&binary) {
+        let payload = payload.map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+        if let Payload::CodeSectionEntry(body) = payload {
+            let mut reader = body.get_operators_reader()
+                .map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+            while !reader.eof() {
+                let op = reader.read().map_err(|e| VmError::ModuleCreationFailed(e.to_string()))?;
+                *counts.entry(InstructionGroup::of(&op)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Scale a cost by a percent multiplier, rounding up
+fn apply_multiplier(cost: u64, multiplier: u32) -> u64 {
+    let scaled = (cost as u128 * multiplier as u128).div_ceil(UNIT_MULTIPLIER as u128);
+    scaled.min(u64::MAX as u128) as u64
+}
@@ -30,6 +30,21 @@ impl fmt::Display for ResourceType {
     }
 }
 
+/// The caller and scope an authorization was granted to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthorizationTenant {
+    /// DID of the caller the authorization belongs to
+    pub caller_did: String,
+    /// Scope the caller acts in
+    pub scope: String,
+}
+
+impl fmt::Display for AuthorizationTenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}", self.caller_did, self.scope)
+    }
+}
+
 /// Authorization for a specific resource type
 #[derive(Debug, Clone)]
 pub struct ResourceAuthorization {
@@ -41,6 +56,8 @@ pub struct ResourceAuthorization {
     pub context: Option<String>,
     /// Description of what the authorization is for
     pub description: String,
+    /// Tenant the authorization is bound to, set when it's loaded into a VM context
+    pub tenant: Option<AuthorizationTenant>,
 }
 
 impl ResourceAuthorization {
@@ -56,9 +73,21 @@ impl ResourceAuthorization {
             limit,
             context,
             description,
+            tenant: None,
         }
     }
 
+    /// Bind the authorization to a tenant
+    pub fn bound_to(mut self, tenant: AuthorizationTenant) -> Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    /// Whether the authorization is bound to the given tenant
+    pub fn belongs_to(&self, tenant: &AuthorizationTenant) -> bool {
+        self.tenant.as_ref() == Some(tenant)
+    }
+
     /// Check if this authorization allows the given amount of resource to be consumed
     pub fn allows(&self, current: u64, additional: u64) -> bool {
         match current.checked_add(additional) {
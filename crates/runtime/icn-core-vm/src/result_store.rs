@@ -0,0 +1,339 @@
+/*!
+# Execution Result Store
+
+Execution results are returned to the caller and are otherwise gone once it drops them.
+The result store persists each outcome, keyed by execution ID, in a storage namespace so
+the governance kernel, the gateway and auditors can fetch it long after the execution.
+
+Alongside each result the store keeps index entries by caller, by proposal and by module
+CID, plus a timeline ordered by recording time. Queries read the most selective index that
+applies and filter the remaining fields on the stored results.
+*/
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use tokio::sync::Mutex;
+
+use crate::{InternalHostError, ResourceType, VmExecutionResult};
+use icn_models::storage::BasicStorageManager;
+
+/// Storage namespace holding results and their indexes
+pub const RESULT_NAMESPACE: &str = "execution_results";
+
+/// An execution outcome as persisted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredExecutionResult {
+    pub execution_id: String,
+
+    /// CID of the executed module
+    pub module_cid: String,
+
+    /// DID of the identity the module ran as
+    pub caller_did: String,
+
+    /// Proposal the execution carried out, if any
+    pub proposal_id: Option<String>,
+
+    /// Return code, or `None` when the execution failed before returning
+    pub code: Option<i32>,
+
+    /// Error message when the execution failed
+    pub error: Option<String>,
+
+    /// Resources consumed, by resource type name
+    pub resource_usage: BTreeMap<String, u64>,
+
+    pub dag_anchor_cid: Option<String>,
+    pub syscall_audit_cid: Option<String>,
+
+    /// CID of the execution receipt credential, if one was issued
+    pub receipt_cid: Option<String>,
+
+    /// Unix timestamp (seconds) the result was recorded at
+    pub recorded_at: i64,
+}
+
+impl StoredExecutionResult {
+    /// Capture the outcome of an execution
+    pub fn from_result(
+        execution_id: &str,
+        module_cid: &str,
+        caller_did: &str,
+        proposal_id: Option<&str>,
+        result: &Result<VmExecutionResult, crate::VmError>,
+    ) -> Self {
+        let (code, error, resource_usage, dag_anchor_cid, syscall_audit_cid) = match result {
+            Ok(r) => (Some(r.code), None, usage_by_name(&r.resource_usage), r.dag_anchor_cid.clone(), r.syscall_audit_cid.clone()),
+            Err(e) => (None, Some(e.to_string()), BTreeMap::new(), None, None),
+        };
+        Self {
+            execution_id: execution_id.to_string(),
+            module_cid: module_cid.to_string(),
+            caller_did: caller_did.to_string(),
+            proposal_id: proposal_id.map(str::to_string),
+            code,
+            error,
+            resource_usage,
+            dag_anchor_cid,
+            syscall_audit_cid,
+            receipt_cid: None,
+            recorded_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Attach the CID of the execution receipt
+    pub fn with_receipt(mut self, receipt_cid: impl Into<String>) -> Self {
+        self.receipt_cid = Some(receipt_cid.into());
+        self
+    }
+
+    /// Whether the module ran to completion with a zero return code
+    pub fn succeeded(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+fn usage_by_name(usage: &HashMap<ResourceType, u64>) -> BTreeMap<String, u64> {
+    usage.iter().map(|(resource, amount)| (resource.to_string(), *amount)).collect()
+}
+
+/// Filters for querying stored results; unset fields match every result
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionResultQuery {
+    pub caller_did: Option<String>,
+    pub proposal_id: Option<String>,
+    pub module_cid: Option<String>,
+    /// Earliest recording time, inclusive
+    pub since: Option<i64>,
+    /// Latest recording time, exclusive
+    pub until: Option<i64>,
+    /// Maximum number of results to return
+    pub limit: Option<usize>,
+}
+
+impl ExecutionResultQuery {
+    /// Whether a result passes every filter
+    pub fn matches(&self, result: &StoredExecutionResult) -> bool {
+        self.caller_did.as_ref().map_or(true, |did| &result.caller_did == did)
+            && self.proposal_id.as_ref().map_or(true, |id| result.proposal_id.as_ref() == Some(id))
+            && self.module_cid.as_ref().map_or(true, |cid| &result.module_cid == cid)
+            && self.since.map_or(true, |since| result.recorded_at >= since)
+            && self.until.map_or(true, |until| result.recorded_at < until)
+    }
+
+    /// The index key to read candidates from
+    fn index_key(&self) -> String {
+        if let Some(id) = &self.proposal_id {
+            format!("by_proposal:{}", id)
+        } else if let Some(did) = &self.caller_did {
+            format!("by_caller:{}", did)
+        } else if let Some(cid) = &self.module_cid {
+            format!("by_module:{}", cid)
+        } else {
+            TIMELINE_KEY.to_string()
+        }
+    }
+}
+
+const TIMELINE_KEY: &str = "timeline";
+
+fn result_key(execution_id: &str) -> String {
+    format!("result:{}", execution_id)
+}
+
+/// Persistent store of execution results
+pub struct ExecutionResultStore {
+    storage: Arc<dyn BasicStorageManager + Send + Sync>,
+
+    /// Serializes index updates, which are read-modify-write
+    index_lock: Mutex<()>,
+}
+
+impl ExecutionResultStore {
+    pub fn new(storage: Arc<dyn BasicStorageManager + Send + Sync>) -> Self {
+        Self { storage, index_lock: Mutex::new(()) }
+    }
+
+    /// Persist a result and index it. Recording an execution ID twice is rejected so
+    /// an outcome can't be rewritten after the fact.
+    pub async fn record(&self, result: &StoredExecutionResult) -> Result<(), InternalHostError> {
+        let _guard = self.index_lock.lock().await;
+        self.ensure_namespace().await?;
+
+        let key = result_key(&result.execution_id);
+        if self.read(&key).await?.is_some() {
+            return Err(InternalHostError::InvalidInput(format!(
+                "Result for execution {} is already recorded", result.execution_id
+            )));
+        }
+
+        let bytes = serde_json::to_vec(result)
+            .map_err(|e| InternalHostError::CodecError(format!("Failed to serialize execution result: {}", e)))?;
+        self.write(&key, &bytes).await?;
+
+        let mut index_keys = vec![
+            format!("by_caller:{}", result.caller_did),
+            format!("by_module:{}", result.module_cid),
+        ];
+        if let Some(id) = &result.proposal_id {
+            index_keys.push(format!("by_proposal:{}", id));
+        }
+        for index_key in index_keys {
+            let mut ids = self.index(&index_key).await?;
+            ids.push(result.execution_id.clone());
+            self.write_index(&index_key, &ids).await?;
+        }
+
+        // The timeline stays ordered by recording time even if results arrive late
+        let mut timeline: Vec<(i64, String)> = match self.read(TIMELINE_KEY).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| InternalHostError::CodecError(format!("Failed to decode result timeline: {}", e)))?,
+            None => Vec::new(),
+        };
+        let at = timeline.partition_point(|(recorded_at, _)| *recorded_at <= result.recorded_at);
+        timeline.insert(at, (result.recorded_at, result.execution_id.clone()));
+        let bytes = serde_json::to_vec(&timeline)
+            .map_err(|e| InternalHostError::CodecError(format!("Failed to serialize result timeline: {}", e)))?;
+        self.write(TIMELINE_KEY, &bytes).await
+    }
+
+    /// The result of an execution
+    pub async fn get(&self, execution_id: &str) -> Result<Option<StoredExecutionResult>, InternalHostError> {
+        match self.read(&result_key(execution_id)).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| InternalHostError::CodecError(format!("Failed to decode execution result: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Results passing a query, oldest first
+    pub async fn query(&self, query: &ExecutionResultQuery) -> Result<Vec<StoredExecutionResult>, InternalHostError> {
+        let index_key = query.index_key();
+        let candidates = if index_key == TIMELINE_KEY {
+            match self.read(TIMELINE_KEY).await? {
+                Some(bytes) => serde_json::from_slice::<Vec<(i64, String)>>(&bytes)
+                    .map_err(|e| InternalHostError::CodecError(format!("Failed to decode result timeline: {}", e)))?
+                    .into_iter()
+                    .map(|(_, id)| id)
+                    .collect(),
+                None => Vec::new(),
+            }
+        } else {
+            self.index(&index_key).await?
+        };
+
+        let mut results = Vec::new();
+        for id in candidates {
+            if let Some(result) = self.get(&id).await? {
+                if query.matches(&result) {
+                    results.push(result);
+                }
+            }
+        }
+        results.sort_by(|a, b| a.recorded_at.cmp(&b.recorded_at));
+        if let Some(limit) = query.limit {
+            results.truncate(limit);
+        }
+        Ok(results)
+    }
+
+    /// Results of executions run by a caller
+    pub async fn by_caller(&self, caller_did: &str) -> Result<Vec<StoredExecutionResult>, InternalHostError> {
+        self.query(&ExecutionResultQuery { caller_did: Some(caller_did.to_string()), ..Default::default() }).await
+    }
+
+    /// Results of executions carrying out a proposal
+    pub async fn by_proposal(&self, proposal_id: &str) -> Result<Vec<StoredExecutionResult>, InternalHostError> {
+        self.query(&ExecutionResultQuery { proposal_id: Some(proposal_id.to_string()), ..Default::default() }).await
+    }
+
+    /// Results recorded in `[since, until)`
+    pub async fn in_range(&self, since: i64, until: i64) -> Result<Vec<StoredExecutionResult>, InternalHostError> {
+        self.query(&ExecutionResultQuery { since: Some(since), until: Some(until), ..Default::default() }).await
+    }
+
+    async fn ensure_namespace(&self) -> Result<(), InternalHostError> {
+        let exists = self.storage.namespace_exists(RESULT_NAMESPACE).await
+            .map_err(|e| InternalHostError::StorageError(e.to_string()))?;
+        if !exists {
+            self.storage.create_namespace(RESULT_NAMESPACE).await
+                .map_err(|e| InternalHostError::StorageError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, InternalHostError> {
+        self.storage.get_from_namespace(RESULT_NAMESPACE, key).await
+            .map_err(|e| InternalHostError::StorageError(e.to_string()))
+    }
+
+    async fn write(&self, key: &str, bytes: &[u8]) -> Result<(), InternalHostError> {
+        self.storage.store_in_namespace(RESULT_NAMESPACE, key, bytes).await
+            .map_err(|e| InternalHostError::StorageError(e.to_string()))
+    }
+
+    async fn index(&self, index_key: &str) -> Result<Vec<String>, InternalHostError> {
+        match self.read(index_key).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| InternalHostError::CodecError(format!("Failed to decode result index {}: {}", index_key, e))),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn write_index(&self, index_key: &str, ids: &[String]) -> Result<(), InternalHostError> {
+        let bytes = serde_json::to_vec(ids)
+            .map_err(|e| InternalHostError::CodecError(format!("Failed to serialize result index {}: {}", index_key, e)))?;
+        self.write(index_key, &bytes).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_storage::InMemoryStorageManager;
+
+    fn result(id: &str, caller: &str, proposal: Option<&str>, recorded_at: i64) -> StoredExecutionResult {
+        let execution = VmExecutionResult {
+            code: 0,
+            resource_usage: HashMap::from([(ResourceType::Compute, 120)]),
+            dag_anchor_cid: None,
+            syscall_audit_cid: None,
+            guest_log: Default::default(),
+            registered_timers: Vec::new(),
+        };
+        let mut stored = StoredExecutionResult::from_result(id, "bafy-module", caller, proposal, &Ok(execution));
+        stored.recorded_at = recorded_at;
+        stored
+    }
+
+    #[tokio::test]
+    async fn test_results_survive_and_query() {
+        let store = ExecutionResultStore::new(Arc::new(InMemoryStorageManager::new()));
+        store.record(&result("exec-2", "did:icn:bob", Some("prop-1"), 200)).await.unwrap();
+        store.record(&result("exec-1", "did:icn:alice", Some("prop-1"), 100).with_receipt("bafy-receipt")).await.unwrap();
+        store.record(&result("exec-3", "did:icn:alice", None, 300)).await.unwrap();
+        assert!(store.record(&result("exec-3", "did:icn:alice", None, 400)).await.is_err());
+
+        let fetched = store.get("exec-1").await.unwrap().unwrap();
+        assert!(fetched.succeeded());
+        assert_eq!(fetched.receipt_cid.as_deref(), Some("bafy-receipt"));
+        assert_eq!(fetched.resource_usage.get("compute"), Some(&120));
+
+        let by_proposal: Vec<String> = store.by_proposal("prop-1").await.unwrap().into_iter().map(|r| r.execution_id).collect();
+        assert_eq!(by_proposal, ["exec-1", "exec-2"]);
+        assert_eq!(store.by_caller("did:icn:alice").await.unwrap().len(), 2);
+
+        let window: Vec<String> = store.in_range(150, 350).await.unwrap().into_iter().map(|r| r.execution_id).collect();
+        assert_eq!(window, ["exec-2", "exec-3"]);
+
+        let latest_alice = store.query(&ExecutionResultQuery {
+            caller_did: Some("did:icn:alice".into()),
+            since: Some(200),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(latest_alice.len(), 1);
+        assert_eq!(latest_alice[0].execution_id, "exec-3");
+    }
+}
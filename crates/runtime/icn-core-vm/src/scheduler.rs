@@ -0,0 +1,237 @@
+/*!
+# Cooperative Scheduling
+
+Executions run on the async executor, but a module that never calls the host never
+gives its thread back. Every execution therefore yields after consuming a fixed amount
+of fuel (wasmtime's `fuel_async_yield_interval`), so other executions on the same node
+make progress between its slices.
+
+Yielding alone shares time between executions, not between cooperatives: a scope that
+submits twenty heavy jobs gets twenty times the share of a scope that submits one. A
+[`FairScheduler`] shared by the node's executions accounts fuel per scope. It caps how
+many executions one scope may run at once, and scopes that have recently consumed more
+than their fair share get shorter slices, so they yield more often while lighter scopes
+catch up. Usage history decays with a configurable half-life, so a burst of heavy work
+only penalizes a scope for a while.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::metrics::{VmMetrics, SCHEDULER_QUEUE};
+
+/// Fuel an execution consumes between yields when no scheduler throttles it
+pub const DEFAULT_YIELD_INTERVAL: u64 = 10_000;
+
+/// Configuration for a [`FairScheduler`]
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Fuel consumed between yields by scopes within their fair share
+    pub yield_interval: u64,
+    /// Shortest slice a scope over its fair share is throttled to
+    pub min_yield_interval: u64,
+    /// Executions one scope may run at once; further executions wait
+    pub max_concurrent_per_scope: usize,
+    /// Half-life of the recent usage the fair share is computed from, in seconds
+    pub usage_half_life_secs: f64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            yield_interval: DEFAULT_YIELD_INTERVAL,
+            min_yield_interval: 1_000,
+            max_concurrent_per_scope: 4,
+            usage_half_life_secs: 60.0,
+        }
+    }
+}
+
+/// Fuel accounting for one scope
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScopeUsage {
+    /// Executions finished
+    pub executions: u64,
+    /// Fuel consumed by finished executions
+    pub fuel_consumed: u64,
+    /// Fuel consumed recently, decayed by the configured half-life
+    pub recent_fuel: f64,
+    /// Executions currently running
+    pub active: usize,
+    /// Executions waiting for one of the scope's slots
+    pub waiting: usize,
+}
+
+struct ScopeState {
+    usage: ScopeUsage,
+    permits: Arc<Semaphore>,
+}
+
+struct SchedulerInner {
+    config: SchedulerConfig,
+    scopes: Mutex<HashMap<String, ScopeState>>,
+    last_decay: Mutex<Instant>,
+}
+
+impl SchedulerInner {
+    /// Decay every scope's recent usage up to now
+    fn decay(&self, scopes: &mut HashMap<String, ScopeState>) {
+        let mut last_decay = self.last_decay.lock().unwrap();
+        let elapsed = last_decay.elapsed().as_secs_f64();
+        *last_decay = Instant::now();
+
+        if self.config.usage_half_life_secs > 0.0 {
+            let factor = 0.5f64.powf(elapsed / self.config.usage_half_life_secs);
+            for state in scopes.values_mut() {
+                state.usage.recent_fuel *= factor;
+            }
+        }
+    }
+
+    /// The slice a scope gets given every scope's recent usage
+    fn yield_interval_for(&self, scopes: &HashMap<String, ScopeState>, scope: &str) -> u64 {
+        let competing: Vec<&ScopeUsage> = scopes.values()
+            .map(|state| &state.usage)
+            .filter(|usage| usage.active > 0 || usage.recent_fuel >= 1.0)
+            .collect();
+        let recent = scopes.get(scope).map_or(0.0, |state| state.usage.recent_fuel);
+        if competing.len() < 2 || recent < 1.0 {
+            return self.config.yield_interval;
+        }
+
+        let fair_share = competing.iter().map(|usage| usage.recent_fuel).sum::<f64>() / competing.len() as f64;
+        if recent <= fair_share {
+            return self.config.yield_interval;
+        }
+
+        let scaled = (self.config.yield_interval as f64 * fair_share / recent) as u64;
+        scaled.clamp(self.config.min_yield_interval.max(1), self.config.yield_interval)
+    }
+}
+
+/// Per-scope fairness accounting shared by the executions on a node
+#[derive(Clone)]
+pub struct FairScheduler {
+    inner: Arc<SchedulerInner>,
+    metrics: Option<VmMetrics>,
+}
+
+impl FairScheduler {
+    /// Create a scheduler
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            inner: Arc::new(SchedulerInner {
+                config,
+                scopes: Mutex::new(HashMap::new()),
+                last_decay: Mutex::new(Instant::now()),
+            }),
+            metrics: None,
+        }
+    }
+
+    /// Export each scope's queue depth to the given metrics
+    pub fn with_metrics(mut self, metrics: VmMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Count an execution that starts or stops waiting for a slot
+    fn adjust_waiting(&self, scope: &str, waiting: bool) {
+        let mut scopes = self.inner.scopes.lock().unwrap();
+        if let Some(state) = scopes.get_mut(scope) {
+            state.usage.waiting = if waiting { state.usage.waiting + 1 } else { state.usage.waiting.saturating_sub(1) };
+            if let Some(metrics) = &self.metrics {
+                metrics.set_queue_depth(scope, SCHEDULER_QUEUE, state.usage.waiting);
+            }
+        }
+    }
+
+    /// Wait until `scope` may start another execution, and get the slice it runs with
+    pub async fn admit(&self, scope: &str) -> SchedulingTicket {
+        let permits = {
+            let mut scopes = self.inner.scopes.lock().unwrap();
+            scopes.entry(scope.to_string())
+                .or_insert_with(|| ScopeState {
+                    usage: ScopeUsage::default(),
+                    permits: Arc::new(Semaphore::new(self.inner.config.max_concurrent_per_scope.max(1))),
+                })
+                .permits
+                .clone()
+        };
+        self.adjust_waiting(scope, true);
+        let permit = permits.acquire_owned().await
+            .expect("scope semaphores are never closed");
+        self.adjust_waiting(scope, false);
+
+        let mut scopes = self.inner.scopes.lock().unwrap();
+        self.inner.decay(&mut scopes);
+        let yield_interval = self.inner.yield_interval_for(&scopes, scope);
+        if let Some(state) = scopes.get_mut(scope) {
+            state.usage.active += 1;
+        }
+
+        SchedulingTicket {
+            scope: scope.to_string(),
+            yield_interval,
+            inner: self.inner.clone(),
+            _permit: permit,
+        }
+    }
+
+    /// Fuel accounting for a scope
+    pub fn usage(&self, scope: &str) -> Option<ScopeUsage> {
+        let mut scopes = self.inner.scopes.lock().unwrap();
+        self.inner.decay(&mut scopes);
+        scopes.get(scope).map(|state| state.usage.clone())
+    }
+
+    /// The slice a new execution for `scope` would get right now
+    pub fn yield_interval(&self, scope: &str) -> u64 {
+        let mut scopes = self.inner.scopes.lock().unwrap();
+        self.inner.decay(&mut scopes);
+        self.inner.yield_interval_for(&scopes, scope)
+    }
+}
+
+/// An admitted execution; the scope's slot is released when the ticket is dropped
+pub struct SchedulingTicket {
+    scope: String,
+    yield_interval: u64,
+    inner: Arc<SchedulerInner>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl SchedulingTicket {
+    /// Scope the execution is accounted to
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+
+    /// Fuel the execution consumes between yields
+    pub fn yield_interval(&self) -> u64 {
+        self.yield_interval
+    }
+
+    /// Charge the fuel the execution consumed to its scope
+    pub fn finish(self, fuel_consumed: u64) {
+        let mut scopes = self.inner.scopes.lock().unwrap();
+        self.inner.decay(&mut scopes);
+        if let Some(state) = scopes.get_mut(&self.scope) {
+            state.usage.executions += 1;
+            state.usage.fuel_consumed += fuel_consumed;
+            state.usage.recent_fuel += fuel_consumed as f64;
+        }
+    }
+}
+
+impl Drop for SchedulingTicket {
+    fn drop(&mut self) {
+        if let Ok(mut scopes) = self.inner.scopes.lock() {
+            if let Some(state) = scopes.get_mut(&self.scope) {
+                state.usage.active = state.usage.active.saturating_sub(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+/*!
+# Recurring Timers
+
+Bylaws such as monthly dues assessments run on a schedule rather than on a proposal. A
+module registers the recurrence while it executes with
+
+- `env::host_register_timer(schedule_ptr, schedule_len, payload_ptr, payload_len) -> i32`
+
+where the schedule is a five-field cron expression (minute, hour, day of month, month,
+day of week) and the payload is the JSON DSL input of the action to run each time the
+timer fires. The VM only validates and collects registrations; they are returned with
+the execution result, and the host that ran the module decides whether to install them.
+
+The return value is the registration's index within the execution, or a negative code:
+the host ABI's argument error code for a malformed schedule or payload, or
+[`TIMER_LIMIT_REACHED`] once [`MAX_TIMERS_PER_EXECUTION`] timers were registered.
+*/
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+use wasmtime::{Caller, Linker, Trap};
+use tracing::*;
+use crate::{ConcreteHostEnvironment, InternalHostError};
+use crate::host_abi::{map_abi_error_to_wasm, map_internal_error_to_wasm, map_vm_error_to_wasm};
+use crate::mem_helpers::{read_memory_bytes, read_memory_string};
+use crate::pricing::HostCallClass;
+
+/// Base compute cost of registering a timer
+const TIMER_CALL_COST: u64 = 200;
+
+/// Timers one execution may register
+pub const MAX_TIMERS_PER_EXECUTION: usize = 16;
+
+/// The execution already registered [`MAX_TIMERS_PER_EXECUTION`] timers
+pub const TIMER_LIMIT_REACHED: i32 = -11;
+
+/// Name and allowed range of each cron field, in order
+const CRON_FIELDS: [(&str, u32, u32); 5] = [
+    ("minute", 0, 59),
+    ("hour", 0, 23),
+    ("day of month", 1, 31),
+    ("month", 1, 12),
+    // 7 is Sunday as well as 0
+    ("day of week", 0, 7),
+];
+
+/// Check a cron-like schedule: five whitespace-separated fields, each a comma-separated
+/// list of `*`, a value or a range `a-b`, optionally followed by a step `/n`
+pub fn validate_schedule(schedule: &str) -> Result<(), String> {
+    let fields: Vec<&str> = schedule.split_whitespace().collect();
+    if fields.len() != CRON_FIELDS.len() {
+        return Err(format!("expected {} fields, got {}", CRON_FIELDS.len(), fields.len()));
+    }
+
+    for (field, (name, min, max)) in fields.iter().zip(CRON_FIELDS) {
+        for item in field.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (item, None),
+            };
+            if let Some(step) = step {
+                match step.parse::<u32>() {
+                    Ok(step) if step > 0 => {}
+                    _ => return Err(format!("invalid step '{}' in {} field", step, name)),
+                }
+            }
+            if range == "*" {
+                continue;
+            }
+
+            let value = |v: &str| match v.parse::<u32>() {
+                Ok(v) if (min..=max).contains(&v) => Ok(v),
+                _ => Err(format!("{} must be between {} and {}, got '{}'", name, min, max, v)),
+            };
+            match range.split_once('-') {
+                Some((from, to)) => {
+                    if value(from)? > value(to)? {
+                        return Err(format!("range '{}' in {} field is reversed", range, name));
+                    }
+                }
+                None => {
+                    value(range)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A recurrence a module asked the host to install
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimerRegistration {
+    /// Cron-like schedule, as validated by [`validate_schedule`]
+    pub schedule: String,
+    /// DSL input of the action to run each time the timer fires
+    pub payload: JsonValue,
+    /// Identity the module executed on behalf of
+    pub registered_by: String,
+    /// Execution that registered the timer
+    pub execution_id: String,
+}
+
+fn host_register_timer_wrapper(
+    mut caller: Caller<'_, ConcreteHostEnvironment>,
+    schedule_ptr: i32,
+    schedule_len: i32,
+    payload_ptr: i32,
+    payload_len: i32,
+) -> Result<i32, Trap> {
+    let schedule = match read_memory_string(&mut caller, schedule_ptr, schedule_len) {
+        Ok(schedule) => schedule,
+        Err(e) => return Ok(map_abi_error_to_wasm(e)),
+    };
+    if let Err(e) = validate_schedule(&schedule) {
+        return Ok(map_internal_error_to_wasm(InternalHostError::InvalidInput(
+            format!("Invalid timer schedule '{}': {}", schedule, e)
+        )));
+    }
+    let payload: JsonValue = match read_memory_bytes(&mut caller, payload_ptr, payload_len) {
+        Ok(bytes) => match serde_json::from_slice(&bytes) {
+            Ok(payload) => payload,
+            Err(e) => return Ok(map_internal_error_to_wasm(InternalHostError::InvalidInput(
+                format!("Invalid timer payload: {}", e)
+            ))),
+        },
+        Err(e) => return Ok(map_abi_error_to_wasm(e)),
+    };
+
+    if let Err(e) = caller.data().record_host_call(HostCallClass::Governance, TIMER_CALL_COST) {
+        return Ok(map_vm_error_to_wasm(e));
+    }
+
+    debug!(caller = %caller.data().caller_did(), schedule = %schedule, "host_register_timer called");
+    match caller.data().record_timer(schedule, payload) {
+        Some(index) => Ok(index as i32),
+        None => Ok(TIMER_LIMIT_REACHED),
+    }
+}
+
+/// Register timer host functions
+pub fn register_timer_functions(linker: &mut Linker<ConcreteHostEnvironment>) -> Result<(), wasmtime::Error> {
+    linker.func_wrap("env", "host_register_timer", host_register_timer_wrapper)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_schedule() {
+        assert!(validate_schedule("0 9 1 * *").is_ok());
+        assert!(validate_schedule("*/15 8-17 * 1,4,7,10 1-5").is_ok());
+        assert!(validate_schedule("0 0 * * 7").is_ok());
+
+        assert!(validate_schedule("0 9 1 *").is_err());
+        assert!(validate_schedule("60 9 1 * *").is_err());
+        assert!(validate_schedule("0 9 0 * *").is_err());
+        assert!(validate_schedule("0 17-8 * * *").is_err());
+        assert!(validate_schedule("*/0 * * * *").is_err());
+        assert!(validate_schedule("@monthly").is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use icn_core_vm::{AbiValType, HostCapability, host_abi_manifest, HOST_ABI_VERSION};
+
+#[test]
+fn test_manifest_lists_every_linked_host_function() {
+    let manifest = host_abi_manifest();
+    assert_eq!(manifest.abi_version, HOST_ABI_VERSION);
+
+    // Every function the runtime links is classified
+    let unclassified: Vec<&str> = manifest.functions_in(HostCapability::Unclassified)
+        .map(|f| f.name.as_str())
+        .collect();
+    assert!(unclassified.is_empty(), "unclassified host functions: {:?}", unclassified);
+    assert!(manifest.functions.iter().all(|f| f.since <= HOST_ABI_VERSION));
+
+    // Signatures are the linked ones
+    let context = manifest.get("host_get_execution_context").unwrap();
+    assert_eq!(context.module, "env");
+    assert_eq!(context.params, vec![AbiValType::I32, AbiValType::I32]);
+    assert_eq!(context.results, vec![AbiValType::I32]);
+    assert_eq!(context.capability, HostCapability::Context);
+    assert!(manifest.get("host_spawn_process").is_none());
+
+    // Older ABI revisions expose fewer functions
+    assert!(manifest.available_in(1).all(|f| f.since == 1));
+    assert!(manifest.available_in(1).count() < manifest.available_in(HOST_ABI_VERSION).count());
+    assert_eq!(manifest.available_in(HOST_ABI_VERSION).count(), manifest.functions.len());
+
+    let json = manifest.to_json();
+    assert_eq!(json["abi_version"], HOST_ABI_VERSION);
+    let log = json["functions"].as_array().unwrap().iter()
+        .find(|f| f["name"] == "host_log_message")
+        .unwrap();
+    assert_eq!(log["capability"], "logging");
+    assert_eq!(log["params"], serde_json::json!(["i32", "i32"]));
+}
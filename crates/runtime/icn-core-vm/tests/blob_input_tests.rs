@@ -0,0 +1,151 @@
+use std::sync::Arc;
+use icn_core_vm::blob_input::{read_window, blob_read_cost, BlobCache, MAX_BLOB_READ_CHUNK};
+use icn_core_vm::{ConcreteHostEnvironment, VMContext, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_models::storage::BasicStorageManager;
+use icn_storage::InMemoryStorageManager;
+
+/// Streams the first input blob in 1 KB windows, checking each window's first byte
+/// against the `i % 251` pattern, and returns the bytes read. Returns -101 if the total
+/// doesn't match `host_blob_size` and -102 on a wrong byte.
+const STREAMING_MODULE: &str = r#"
+(module
+  (import "env" "host_input_blob_count" (func $count (result i32)))
+  (import "env" "host_input_blob_cid" (func $cid (param i32 i32 i32) (result i32)))
+  (import "env" "host_blob_size" (func $size (param i32 i32) (result i64)))
+  (import "env" "host_blob_read_at" (func $read (param i32 i32 i64 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "main") (result i32)
+    (local $cid_len i32)
+    (local $offset i64)
+    (local $n i32)
+    (if (i32.ne (call $count) (i32.const 1)) (then (return (i32.const -100))))
+    (local.set $cid_len (call $cid (i32.const 0) (i32.const 0) (i32.const 128)))
+    (if (i32.lt_s (local.get $cid_len) (i32.const 0)) (then (return (local.get $cid_len))))
+    (block $done
+      (loop $next
+        (local.set $n (call $read (i32.const 0) (local.get $cid_len) (local.get $offset) (i32.const 1024) (i32.const 1024)))
+        (br_if $done (i32.le_s (local.get $n) (i32.const 0)))
+        (if (i32.ne (i32.load8_u (i32.const 1024)) (i32.wrap_i64 (i64.rem_u (local.get $offset) (i64.const 251))))
+          (then (return (i32.const -102))))
+        (local.set $offset (i64.add (local.get $offset) (i64.extend_i32_u (local.get $n))))
+        (br $next)))
+    (if (i64.ne (call $size (i32.const 0) (local.get $cid_len)) (local.get $offset))
+      (then (return (i32.const -101))))
+    (i32.wrap_i64 (local.get $offset))))
+"#;
+
+#[test]
+fn test_read_window_within_bounds() {
+    let data: Vec<u8> = (0..100u8).collect();
+    
+    let window = read_window(&data, 10, 5);
+    assert_eq!(window, &[10, 11, 12, 13, 14]);
+}
+
+#[test]
+fn test_read_window_truncates_at_end() {
+    let data: Vec<u8> = (0..100u8).collect();
+    
+    // A window running past the end returns the remaining bytes
+    let window = read_window(&data, 95, 20);
+    assert_eq!(window.len(), 5);
+    assert_eq!(window[0], 95);
+    
+    // Reading at or past the end signals EOF with an empty window
+    assert!(read_window(&data, 100, 10).is_empty());
+    assert!(read_window(&data, u64::MAX, 10).is_empty());
+}
+
+#[test]
+fn test_streaming_reads_cover_whole_blob() {
+    let data: Vec<u8> = (0..=255u8).cycle().take(10_000).collect();
+    
+    // Stream the blob in fixed-size windows the way a module would
+    let mut offset = 0u64;
+    let mut reassembled = Vec::new();
+    loop {
+        let window = read_window(&data, offset, 1024);
+        if window.is_empty() {
+            break;
+        }
+        reassembled.extend_from_slice(window);
+        offset += window.len() as u64;
+    }
+    
+    assert_eq!(reassembled, data);
+}
+
+#[test]
+fn test_blob_read_cost_scales_with_bytes() {
+    assert!(blob_read_cost(0) < blob_read_cost(64));
+    assert!(blob_read_cost(64) < blob_read_cost(MAX_BLOB_READ_CHUNK as usize));
+    assert_eq!(blob_read_cost(1), blob_read_cost(64));
+}
+
+#[test]
+fn test_blob_cache_evicts_least_recently_read() {
+    let cid = |n: u8| cid::Cid::new_v1(0x55, cid::multihash::Multihash::wrap(0x12, &[n; 32]).unwrap());
+    let mut cache = BlobCache::new(100);
+
+    cache.insert(cid(1), Arc::new(vec![0; 40]));
+    cache.insert(cid(2), Arc::new(vec![0; 40]));
+    assert!(cache.get(&cid(1)).is_some());
+
+    // Making room for a third blob evicts the one read longest ago
+    cache.insert(cid(3), Arc::new(vec![0; 40]));
+    assert!(cache.get(&cid(2)).is_none());
+    assert!(cache.get(&cid(1)).is_some());
+    assert_eq!(cache.size(), 80);
+
+    // A blob larger than the whole cache is never kept
+    cache.insert(cid(4), Arc::new(vec![0; 101]));
+    assert!(cache.get(&cid(4)).is_none());
+    assert_eq!(cache.size(), 80);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_module_streams_input_blob() {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    let host_env = ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage.clone());
+
+    let roster: Vec<u8> = (0..5_000u32).map(|i| (i % 251) as u8).collect();
+    let cid = storage.store_blob(&roster).await.unwrap();
+    host_env.add_input_blob(cid);
+
+    let result = execute_wasm(STREAMING_MODULE.as_bytes(), None, &host_env, None, None).await.unwrap();
+    assert_eq!(result.code, roster.len() as i32);
+
+    // The blob was loaded for the execution only
+    assert_eq!(host_env.cached_blob_bytes(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_module_cannot_read_blobs_it_was_not_given() {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    let host_env = ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage.clone());
+
+    // Stored, but never attached to the execution
+    let secret = storage.store_blob(b"another member's records").await.unwrap().to_string();
+    let module = format!(r#"
+        (module
+          (import "env" "host_blob_size" (func $size (param i32 i32) (result i64)))
+          (import "env" "host_blob_read_at" (func $read (param i32 i32 i64 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "{cid}")
+          (func (export "main") (result i32)
+            (if (i64.ge_s (call $size (i32.const 0) (i32.const {len})) (i64.const 0)) (then (return (i32.const -200))))
+            (call $read (i32.const 0) (i32.const {len}) (i64.const 0) (i32.const 64) (i32.const 1024))))
+    "#, cid = secret, len = secret.len());
+
+    let result = execute_wasm(module.as_bytes(), None, &host_env, None, None).await.unwrap();
+    assert!(result.code < 0 && result.code != -200, "unattached blob was readable: {}", result.code);
+}
@@ -0,0 +1,12 @@
+;; Sums 1..1000 so instruction metering shows up in compute usage
+(module
+  (func (export "main") (result i32)
+    (local $i i32)
+    (local $sum i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.gt_u (local.get $i) (i32.const 1000)))
+        (local.set $sum (i32.add (local.get $sum) (local.get $i)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.const 0)))
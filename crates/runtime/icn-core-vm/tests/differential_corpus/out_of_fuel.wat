@@ -0,0 +1,6 @@
+;; Loops forever; must stop with the same error on every build
+(module
+  (func (export "main") (result i32)
+    (loop $forever
+      (br $forever))
+    (i32.const 0)))
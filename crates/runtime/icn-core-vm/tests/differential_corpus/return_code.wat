@@ -0,0 +1,4 @@
+;; Returns a fixed non-zero code after a little arithmetic
+(module
+  (func (export "main") (result i32)
+    (i32.add (i32.const 40) (i32.const 2))))
@@ -0,0 +1,4 @@
+;; Traps on its first instruction
+(module
+  (func (export "main") (result i32)
+    unreachable))
@@ -0,0 +1,63 @@
+use std::path::Path;
+use std::collections::BTreeMap;
+use icn_core_vm::differential::{
+    DifferentialReceipt, ExternalBuild, InProcessBuild, compare_receipts, load_corpus, run_differential,
+};
+
+fn corpus_dir() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/differential_corpus"))
+}
+
+#[tokio::test]
+async fn test_corpus_runs_identically_on_repeated_executions() {
+    let corpus = load_corpus(corpus_dir()).unwrap();
+    assert_eq!(corpus.len(), 4);
+
+    let report = run_differential(&corpus, &InProcessBuild::new("left"), &InProcessBuild::new("right"))
+        .await
+        .unwrap();
+    assert!(report.is_clean(), "Divergent cases: {:?}", report.divergent_cases().collect::<Vec<_>>());
+
+    let by_case: BTreeMap<_, _> = report.cases.iter().map(|c| (c.case.as_str(), &c.left)).collect();
+    assert_eq!(by_case["return_code"].code, Some(42));
+    assert!(by_case["bounded_loop"].resource_usage["compute"] > 0);
+    assert!(by_case["out_of_fuel"].error_kind.is_some());
+    assert_eq!(by_case["trap"].error_kind.as_deref(), Some("ExecutionError"));
+}
+
+#[test]
+fn test_receipts_are_compared_field_by_field() {
+    let left = DifferentialReceipt {
+        code: Some(0),
+        resource_usage: [("compute".to_string(), 120)].into_iter().collect(),
+        dag_anchor_cid: Some("bafy-left".to_string()),
+        ..Default::default()
+    };
+
+    // Anchor CIDs differ but both builds anchored, and error messages are not compared
+    let mut right = left.clone();
+    right.dag_anchor_cid = Some("bafy-right".to_string());
+    assert!(compare_receipts(&left, &right).is_empty());
+
+    right.code = Some(1);
+    right.resource_usage.insert("storage".to_string(), 8);
+    let fields: Vec<_> = compare_receipts(&left, &right).into_iter().map(|d| d.field).collect();
+    assert_eq!(fields, vec!["code", "resource_usage.storage"]);
+}
+
+/// Compares this build against a baseline binary when `ICN_VM_BASELINE` names one, e.g.
+/// `ICN_VM_BASELINE=/opt/icn/previous/icn-vm-diff ICN_VM_BASELINE_ARGS=run cargo test`
+#[tokio::test]
+async fn test_corpus_against_baseline_build() {
+    let Ok(program) = std::env::var("ICN_VM_BASELINE") else {
+        return;
+    };
+    let args = std::env::var("ICN_VM_BASELINE_ARGS")
+        .map(|a| a.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let corpus = load_corpus(corpus_dir()).unwrap();
+    let baseline = ExternalBuild::new("baseline", program, args);
+    let report = run_differential(&corpus, &baseline, &InProcessBuild::default()).await.unwrap();
+    assert!(report.is_clean(), "{}", serde_json::to_string_pretty(&report).unwrap());
+}
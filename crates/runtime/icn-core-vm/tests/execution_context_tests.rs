@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use icn_core_vm::{ConcreteHostEnvironment, GuestExecutionContext, VMContext, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Reads the execution context into a buffer of the given size and returns its length
+/// if it is a CBOR map of four entries
+fn context_module(buffer_len: u32) -> String {
+    format!(r#"
+(module
+  (import "env" "host_get_execution_context" (func $context (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "main") (result i32)
+    (local $len i32)
+    (local.set $len (call $context (i32.const 0) (i32.const {})))
+    (if (result i32) (i32.lt_s (local.get $len) (i32.const 0))
+      (then (local.get $len))
+      (else
+        (if (result i32) (i32.eq (i32.load8_u (i32.const 0)) (i32.const 0xa4))
+          (then (local.get $len))
+          (else (i32.const -1)))))))
+"#, buffer_len)
+}
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[test]
+fn test_context_round_trips_through_cbor() {
+    let context = GuestExecutionContext {
+        proposal_cid: Some("bafyproposal".to_string()),
+        scope: "did:icn:federation".to_string(),
+        execution_id: "exec-1".to_string(),
+        timestamp: 1_700_000_000,
+    };
+    let bytes = context.to_cbor().unwrap();
+    let decoded: GuestExecutionContext = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, context);
+}
+
+#[tokio::test]
+async fn test_module_reads_its_execution_context() {
+    let host_env = host_env();
+    let wasm = wat::parse_str(context_module(256)).unwrap();
+
+    let result = execute_wasm(&wasm, None, &host_env, Some("bafyproposal"), Some("did:icn:federation"))
+        .await
+        .unwrap();
+
+    // Outside an execution the context falls back to the host's own
+    let expected = GuestExecutionContext {
+        proposal_cid: Some("bafyproposal".to_string()),
+        scope: "did:icn:federation".to_string(),
+        execution_id: host_env.execution_context().execution_id,
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    assert_eq!(result.code, expected.to_cbor().unwrap().len() as i32);
+    assert_eq!(host_env.execution_context().proposal_cid, None);
+}
+
+#[tokio::test]
+async fn test_small_buffer_is_left_untouched() {
+    let host_env = host_env();
+    let wasm = wat::parse_str(context_module(8)).unwrap();
+
+    let result = execute_wasm(&wasm, None, &host_env, None, None).await.unwrap();
+    assert_eq!(result.code, -101);
+}
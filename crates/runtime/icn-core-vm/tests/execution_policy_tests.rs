@@ -0,0 +1,52 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use icn_core_vm::{ConcreteHostEnvironment, ExecutionPolicy, VMContext, VmError, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Logs a line, then writes a value to storage
+const MODULE: &str = r#"
+(module
+  (import "env" "set_value" (func $set_value (param i32 i32 i32 i32) (result i32)))
+  (import "env" "host_log_message" (func $log_message (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "key")
+  (data (i32.const 16) "value")
+  (func (export "main") (result i32)
+    (drop (call $log_message (i32.const 16) (i32.const 5)))
+    (call $set_value (i32.const 0) (i32.const 3) (i32.const 16) (i32.const 5))))
+"#;
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[tokio::test]
+async fn test_host_calls_outside_the_allowlist_are_denied() {
+    let policy = ExecutionPolicy::new("coop-1", "did:icn:member", vec!["member".to_string()], BTreeSet::new());
+    let host_env = host_env().with_execution_policy(policy);
+
+    let result = execute_wasm(MODULE.as_bytes(), None, &host_env, None, None).await;
+    match result {
+        Err(VmError::Unauthorized(message)) => assert!(message.contains("set_value")),
+        other => panic!("expected the storage write to be denied, got {:?}", other.map(|r| r.code)),
+    }
+}
+
+#[tokio::test]
+async fn test_allowed_host_calls_run() {
+    let allowed = BTreeSet::from(["set_value".to_string()]);
+    let policy = ExecutionPolicy::new("coop-1", "did:icn:steward", vec!["steward".to_string()], allowed);
+    let host_env = host_env().with_execution_policy(policy);
+
+    let result = execute_wasm(MODULE.as_bytes(), None, &host_env, None, None).await.unwrap();
+    assert_eq!(result.guest_log.entries.len(), 1);
+
+    let unrestricted = host_env.clone().with_execution_policy(ExecutionPolicy::unrestricted("coop-1", "did:icn:steward"));
+    assert!(execute_wasm(MODULE.as_bytes(), None, &unrestricted, None, None).await.is_ok());
+}
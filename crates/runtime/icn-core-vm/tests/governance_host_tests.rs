@@ -0,0 +1,81 @@
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use icn_core_vm::governance_host::{VoteChoice, GOVERNANCE_UNAUTHORIZED, GOVERNANCE_UNAVAILABLE};
+use icn_core_vm::{ConcreteHostEnvironment, GovernanceHost, GovernanceHostError, ProposalDraft, VMContext, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Creates a follow-up proposal and votes for it, returning the vote's result code
+const WORKFLOW_MODULE: &str = r#"
+(module
+  (import "env" "host_create_proposal" (func $create (param i32 i32 i32 i32) (result i32)))
+  (import "env" "host_cast_vote" (func $vote (param i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "{\"scope\":\"Cooperative\",\"scope_id\":\"coop-1\",\"title\":\"Renew lease\",\"description\":\"Follow-up\"}")
+  (func (export "main") (result i32)
+    (local $len i32)
+    (local.set $len (call $create (i32.const 0) (i32.const 91) (i32.const 256) (i32.const 64)))
+    (if (i32.lt_s (local.get $len) (i32.const 0)) (then (return (local.get $len))))
+    (call $vote (i32.const 256) (local.get $len) (i32.const 0))))
+"#;
+
+#[derive(Default)]
+struct RecordingGovernance {
+    allow: bool,
+    drafts: Mutex<Vec<(String, ProposalDraft)>>,
+    votes: Mutex<Vec<(String, String, VoteChoice)>>,
+}
+
+#[async_trait]
+impl GovernanceHost for RecordingGovernance {
+    async fn create_proposal(&self, caller: &str, draft: ProposalDraft) -> Result<String, GovernanceHostError> {
+        if !self.allow {
+            return Err(GovernanceHostError::Unauthorized("create_proposals".to_string()));
+        }
+        self.drafts.lock().unwrap().push((caller.to_string(), draft));
+        Ok("proposal:renew-lease".to_string())
+    }
+
+    async fn cast_vote(&self, caller: &str, proposal_id: &str, choice: VoteChoice) -> Result<(), GovernanceHostError> {
+        self.votes.lock().unwrap().push((caller.to_string(), proposal_id.to_string(), choice));
+        Ok(())
+    }
+}
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_module_creates_and_votes_on_proposal() {
+    let governance = Arc::new(RecordingGovernance { allow: true, ..Default::default() });
+    let host_env = host_env().with_governance_host(governance.clone());
+
+    let result = execute_wasm(WORKFLOW_MODULE.as_bytes(), None, &host_env, None, None).await.unwrap();
+    assert_eq!(result.code, 0);
+
+    let drafts = governance.drafts.lock().unwrap();
+    assert_eq!(drafts.len(), 1);
+    assert_eq!(drafts[0].1.title, "Renew lease");
+    assert_eq!(drafts[0].0, host_env.caller_did());
+
+    let votes = governance.votes.lock().unwrap();
+    assert_eq!(votes.as_slice(), &[(host_env.caller_did().to_string(), "proposal:renew-lease".to_string(), VoteChoice::For)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_refused_and_unavailable_requests_return_codes() {
+    let governance = Arc::new(RecordingGovernance::default());
+    let host_env = host_env().with_governance_host(governance.clone());
+    let result = execute_wasm(WORKFLOW_MODULE.as_bytes(), None, &host_env, None, None).await.unwrap();
+    assert_eq!(result.code, GOVERNANCE_UNAUTHORIZED);
+    assert!(governance.votes.lock().unwrap().is_empty());
+
+    let result = execute_wasm(WORKFLOW_MODULE.as_bytes(), None, &host_env(), None, None).await.unwrap();
+    assert_eq!(result.code, GOVERNANCE_UNAVAILABLE);
+}
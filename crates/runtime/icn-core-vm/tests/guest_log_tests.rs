@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use icn_core_vm::guest_log::TRUNCATION_MARKER;
+use icn_core_vm::{ConcreteHostEnvironment, GuestLogLevel, GuestLogLimits, VMContext, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Logs a debug line, an info line, a warning and an error, in that order
+const LOGGING_MODULE: &str = r#"
+(module
+  (import "env" "host_log" (func $log (param i32 i32 i32) (result i32)))
+  (import "env" "host_log_message" (func $log_message (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "starting")
+  (data (i32.const 16) "loaded config")
+  (data (i32.const 32) "quota nearly spent")
+  (data (i32.const 64) "anchor failed")
+  (func (export "main") (result i32)
+    (drop (call $log (i32.const 0) (i32.const 0) (i32.const 8)))
+    (drop (call $log_message (i32.const 16) (i32.const 13)))
+    (drop (call $log (i32.const 2) (i32.const 32) (i32.const 18)))
+    (drop (call $log (i32.const 3) (i32.const 64) (i32.const 13)))
+    (i32.const 0)))
+"#;
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[tokio::test]
+async fn test_logs_are_captured_with_levels_and_offsets() {
+    let host_env = host_env();
+    let mut stream = host_env.subscribe_guest_log();
+
+    let result = execute_wasm(LOGGING_MODULE.as_bytes(), None, &host_env, None, None)
+        .await
+        .unwrap();
+
+    let levels: Vec<_> = result.guest_log.entries.iter().map(|e| e.level).collect();
+    assert_eq!(levels, vec![GuestLogLevel::Debug, GuestLogLevel::Info, GuestLogLevel::Warn, GuestLogLevel::Error]);
+    assert_eq!(result.guest_log.entries[1].message, "loaded config");
+    assert!(result.guest_log.entries.iter().all(|e| e.source_offset.is_some() && !e.truncated));
+    assert!(!result.guest_log.truncated);
+
+    // The live stream saw every line and ended with the execution
+    let mut streamed = Vec::new();
+    while let Some(entry) = stream.recv().await {
+        streamed.push(entry);
+    }
+    assert_eq!(streamed, result.guest_log.entries);
+}
+
+#[tokio::test]
+async fn test_limits_truncate_the_log() {
+    let host_env = host_env().with_guest_log_limits(GuestLogLimits {
+        min_level: GuestLogLevel::Info,
+        max_lines: 2,
+        max_message_bytes: 5,
+        ..Default::default()
+    });
+
+    let result = execute_wasm(LOGGING_MODULE.as_bytes(), None, &host_env, None, None)
+        .await
+        .unwrap();
+    let log = result.guest_log;
+
+    // The debug line is skipped, long messages are cut and the last line is dropped
+    let messages: Vec<_> = log.entries.iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(messages, vec!["loade", "quota", TRUNCATION_MARKER]);
+    assert!(log.entries.iter().all(|e| e.truncated));
+    assert!(log.truncated);
+    assert_eq!(log.dropped, 1);
+}
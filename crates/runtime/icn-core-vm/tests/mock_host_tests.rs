@@ -0,0 +1,60 @@
+use icn_core_vm::{MockHostEnvironment, MockResponse, VmError, execute_wasm_with_host};
+
+/// Logs a message, reads a value from storage and returns its first byte plus the
+/// caller's scope
+const STORAGE_MODULE: &str = r#"
+(module
+  (import "env" "host_log_message" (func $log (param i32 i32) (result i32)))
+  (import "env" "host_storage_get" (func $get (param i32 i32 i32 i32) (result i32)))
+  (import "env" "host_get_caller_scope" (func $scope (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "reading budget")
+  (data (i32.const 32) "budget")
+  (func (export "_start") (result i32)
+    (drop (call $log (i32.const 0) (i32.const 14)))
+    (drop (call $get (i32.const 32) (i32.const 6) (i32.const 64) (i32.const 16)))
+    (i32.add (i32.load8_u (i32.const 64)) (call $scope))))
+"#;
+
+#[tokio::test]
+async fn test_scripted_responses_and_recorded_calls() {
+    let host = MockHostEnvironment::new()
+        .respond("host_get_caller_scope", MockResponse::i32(3))
+        .respond("host_storage_get", MockResponse::WriteBytes { ptr_param: 2, bytes: vec![40] })
+        .capture("host_log_message", 0, 1)
+        .capture("host_storage_get", 0, 1);
+
+    let execution = execute_wasm_with_host(STORAGE_MODULE.as_bytes(), None, host).await.unwrap();
+    assert_eq!(execution.outcome.unwrap(), 43);
+    assert!(execution.fuel_consumed > 0);
+
+    let calls = execution.host.calls();
+    let names: Vec<_> = calls.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["host_log_message", "host_storage_get", "host_get_caller_scope"]);
+    assert_eq!(calls[0].data_str(), Some("reading budget"));
+    assert_eq!(calls[1].data_str(), Some("budget"));
+    assert_eq!(calls[1].params, vec![32, 6, 64, 16]);
+}
+
+#[tokio::test]
+async fn test_queued_responses_strict_mode_and_traps() {
+    // A one-shot response is used before the standing one
+    let host = MockHostEnvironment::new()
+        .respond("host_get_caller_scope", MockResponse::i32(1))
+        .respond_once("host_get_caller_scope", MockResponse::i32(5));
+    let execution = execute_wasm_with_host(STORAGE_MODULE.as_bytes(), None, host).await.unwrap();
+    assert_eq!(execution.outcome.unwrap(), 5);
+
+    // Strict mocks trap on unscripted calls, and the calls made so far are kept
+    let execution = execute_wasm_with_host(STORAGE_MODULE.as_bytes(), None, MockHostEnvironment::new().strict())
+        .await
+        .unwrap();
+    assert!(matches!(execution.outcome, Err(VmError::ExecutionError(ref e)) if e.contains("host_log_message")));
+    assert_eq!(execution.host.calls().len(), 1);
+
+    let host = MockHostEnvironment::new()
+        .respond("host_storage_get", MockResponse::Trap("storage offline".to_string()));
+    let execution = execute_wasm_with_host(STORAGE_MODULE.as_bytes(), None, host).await.unwrap();
+    assert!(matches!(execution.outcome, Err(VmError::ExecutionError(ref e)) if e.contains("storage offline")));
+    assert!(execution.host.calls_to("host_get_caller_scope").is_empty());
+}
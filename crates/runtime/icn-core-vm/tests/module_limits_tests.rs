@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use icn_core_vm::{ConcreteHostEnvironment, ModuleLimits, VMContext, VmError, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Dispatches through a table the way Rust lowers calls on a trait object
+const DISPATCH_MODULE: &str = r#"
+(module
+  (type $op (func (param i32) (result i32)))
+  (table 2 2 funcref)
+  (elem (i32.const 0) $double $square)
+  (func $double (type $op) (i32.mul (local.get 0) (i32.const 2)))
+  (func $square (type $op) (i32.mul (local.get 0) (local.get 0)))
+  (func (export "main") (result i32)
+    (i32.add
+      (call_indirect (type $op) (i32.const 5) (i32.const 0))
+      (call_indirect (type $op) (i32.const 5) (i32.const 1)))))
+"#;
+
+/// Grows an unbounded table by 100 elements and returns the result of `table.grow`
+const GROWING_MODULE: &str = r#"
+(module
+  (table 1 funcref)
+  (func (export "main") (result i32)
+    (table.grow (ref.null func) (i32.const 100))))
+"#;
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[tokio::test]
+async fn test_indirect_calls_execute() {
+    let wasm = wat::parse_str(DISPATCH_MODULE).unwrap();
+
+    let tables = ModuleLimits::default().validate(&wasm).unwrap();
+    assert_eq!(tables.tables.len(), 1);
+    assert_eq!(tables.tables[0].maximum, Some(2));
+    assert_eq!(tables.element_segments, 1);
+    assert_eq!(tables.indirect_calls, 2);
+
+    let result = execute_wasm(&wasm, None, &host_env(), None, None).await.unwrap();
+    assert_eq!(result.code, 10 + 25);
+}
+
+#[tokio::test]
+async fn test_oversized_tables_are_rejected() {
+    let wasm = wat::parse_str(DISPATCH_MODULE).unwrap();
+    let limits = ModuleLimits { max_table_elements: 1, ..Default::default() };
+
+    let result = execute_wasm(&wasm, None, &host_env().with_module_limits(limits), None, None).await;
+    assert!(matches!(result, Err(VmError::ResourceLimitExceeded(_))), "{:?}", result.map(|r| r.code));
+
+    let no_tables = ModuleLimits { max_tables: 0, ..Default::default() };
+    assert!(no_tables.validate(&wasm).is_err());
+    let one_segment = ModuleLimits { max_element_segments: 0, ..Default::default() };
+    assert!(one_segment.validate(&wasm).is_err());
+}
+
+#[tokio::test]
+async fn test_table_growth_is_bounded() {
+    let wasm = wat::parse_str(GROWING_MODULE).unwrap();
+
+    // Within the limits the table grows and returns its old size
+    let result = execute_wasm(&wasm, None, &host_env(), None, None).await.unwrap();
+    assert_eq!(result.code, 1);
+
+    // Past them growth fails
+    let limits = ModuleLimits { max_table_elements: 10, ..Default::default() };
+    let result = execute_wasm(&wasm, None, &host_env().with_module_limits(limits), None, None).await.unwrap();
+    assert_eq!(result.code, -1);
+}
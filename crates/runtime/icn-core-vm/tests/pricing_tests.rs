@@ -0,0 +1,143 @@
+use std::sync::Arc;
+use icn_core_vm::{execute_wasm, ConcreteHostEnvironment, IdentityContext, ResourceAuthorization, ResourceType, VMContext};
+use icn_core_vm::pricing::{
+    FuelPricingTable, FuelPricingRegistry, HostCallClass, InstructionGroup,
+    count_instruction_groups, fuel_budget, compute_for_fuel, UNIT_MULTIPLIER,
+};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage, KeyPair};
+use icn_storage::InMemoryStorageManager;
+
+#[test]
+fn test_host_call_cost_scales_by_class() {
+    let table = FuelPricingTable::default();
+
+    assert_eq!(table.host_call_cost(HostCallClass::Logging, 100), 100);
+    assert_eq!(table.host_call_cost(HostCallClass::StorageWrite, 100), 400);
+
+    // Fractional results round up so calls are never free
+    let mut table = FuelPricingTable::flat();
+    table.host_calls.insert(HostCallClass::StorageRead, 150);
+    assert_eq!(table.host_call_cost(HostCallClass::StorageRead, 3), 5);
+    assert_eq!(table.host_call_cost(HostCallClass::Dag, 3), 3);
+}
+
+#[test]
+fn test_module_multiplier_weights_instruction_groups() {
+    let wasm = wat::parse_str(r#"
+        (module
+          (memory 1)
+          (func (export "main") (result i32)
+            i32.const 0
+            i32.load
+            drop
+            i32.const 1
+            i32.const 2
+            i32.add))
+    "#).unwrap();
+
+    let counts = count_instruction_groups(&wasm).unwrap();
+
+    // The text format counts the same as the binary it compiles to
+    let wat = r#"(module (func (export "main") (result i32) i32.const 1 i32.const 2 i32.add))"#;
+    assert_eq!(count_instruction_groups(wat.as_bytes()).unwrap().get(&InstructionGroup::Numeric), Some(&3));
+    assert_eq!(counts.get(&InstructionGroup::Memory), Some(&1));
+    assert_eq!(counts.get(&InstructionGroup::Numeric), Some(&4));
+
+    // A flat table leaves fuel unscaled
+    assert_eq!(FuelPricingTable::flat().module_multiplier(&wasm).unwrap(), UNIT_MULTIPLIER);
+
+    // Making memory access 10x pulls the blended multiplier up
+    let mut table = FuelPricingTable::flat();
+    table.instructions.insert(InstructionGroup::Memory, 1000);
+    let multiplier = table.module_multiplier(&wasm).unwrap();
+    assert!(multiplier > UNIT_MULTIPLIER);
+
+    // Cheaper instructions get more fuel for the same Compute authorization
+    assert!(fuel_budget(1_000_000, multiplier) < fuel_budget(1_000_000, UNIT_MULTIPLIER));
+    assert_eq!(compute_for_fuel(fuel_budget(1_000, 200), 200), 1_000);
+}
+
+#[test]
+fn test_registry_selects_federation_table() {
+    // Without opting in, nothing is scaled
+    let flat = FuelPricingRegistry::default();
+    assert_eq!(flat.table_for(None).host_call_cost(HostCallClass::StorageWrite, 10), 10);
+    assert_eq!(flat.table_for(Some("federation-a")).host_call_cost(HostCallClass::StorageWrite, 10), 10);
+
+    let mut registry = FuelPricingRegistry::new(FuelPricingTable::default());
+
+    let mut expensive = FuelPricingTable::default();
+    expensive.host_calls.insert(HostCallClass::StorageWrite, 1000);
+    registry.set_federation_table("federation-a", expensive).unwrap();
+
+    assert_eq!(registry.table_for(Some("federation-a")).host_call_cost(HostCallClass::StorageWrite, 10), 100);
+    assert_eq!(registry.table_for(Some("federation-b")).host_call_cost(HostCallClass::StorageWrite, 10), 40);
+    assert_eq!(registry.table_for(None).host_call_cost(HostCallClass::StorageWrite, 10), 40);
+
+    // Zero multipliers are rejected
+    let mut free = FuelPricingTable::flat();
+    free.instructions.insert(InstructionGroup::Call, 0);
+    assert!(registry.set_federation_table("federation-c", free).is_err());
+}
+
+fn host_env_with_compute(limit: u64) -> (ConcreteHostEnvironment, VMContext) {
+    let identity = Arc::new(IdentityContext::new(KeyPair::generate_random(), "did:icn:settler"));
+    let vm_context = VMContext::new(identity, vec![
+        ResourceAuthorization::new(ResourceType::Compute, limit, None, "Settle test".to_string()),
+    ]);
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let host_env = ConcreteHostEnvironment::new(vm_context.clone(), storage.clone(), identity_manager, None, storage);
+    (host_env, vm_context)
+}
+
+#[test]
+fn test_settling_compute_is_capped_at_the_authorization() {
+    let (host_env, _) = host_env_with_compute(100);
+
+    host_env.record_compute_usage(80).unwrap();
+    assert!(host_env.record_compute_usage(50).is_err());
+
+    // Work that already ran is charged up to the limit instead of failing
+    assert_eq!(host_env.settle_compute_usage(50), 30);
+    assert_eq!(host_env.get_compute_consumed(), 100);
+    assert_eq!(host_env.settle_compute_usage(10), 10);
+}
+
+#[tokio::test]
+async fn test_failed_runs_are_charged_for_fuel_used() {
+    // Counts to a thousand, then traps
+    let trapping = wat::parse_str(r#"
+        (module
+          (func (export "main") (result i32)
+            (local i32)
+            (loop
+              local.get 0
+              i32.const 1
+              i32.add
+              local.tee 0
+              i32.const 1000
+              i32.lt_u
+              br_if 0)
+            unreachable))
+    "#).unwrap();
+
+    let (host_env, vm_context) = host_env_with_compute(1_000_000);
+    assert!(execute_wasm(&trapping, Some(vm_context), &host_env, None, None).await.is_err());
+    assert!(host_env.get_compute_consumed() > 1_000);
+
+    // A module that never returns burns its whole authorization
+    let spinning = wat::parse_str(r#"
+        (module
+          (func (export "main") (result i32)
+            (loop (br 0))
+            i32.const 0))
+    "#).unwrap();
+
+    let (host_env, vm_context) = host_env_with_compute(10_000);
+    assert!(execute_wasm(&spinning, Some(vm_context), &host_env, None, None).await.is_err());
+    assert_eq!(host_env.get_compute_consumed(), 10_000);
+}
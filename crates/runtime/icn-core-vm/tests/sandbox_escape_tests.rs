@@ -0,0 +1,122 @@
+//! Regression tests for the ways a module could try to reach past its sandbox through
+//! host call arguments: reads and writes outside its memory, pointer math that
+//! overflows inside the host wrappers, and lengths large enough to make the host
+//! allocate or copy without limit. Every such call must fail with the host ABI's
+//! argument error code rather than trap the host or touch memory it shouldn't.
+
+use std::sync::Arc;
+use icn_core_vm::mem_helpers::{checked_range, guest_range, MAX_HOST_PARAM_LEN};
+use icn_core_vm::{ConcreteHostEnvironment, VMContext, execute_wasm};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Code host calls return for invalid pointer and length arguments
+const ABI_ERROR: i32 = -101;
+
+/// One WASM page
+const PAGE: i32 = 64 * 1024;
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+/// A module with `pages` of memory whose `main` returns the result of `call`
+fn module(pages: u32, imports: &str, call: &str) -> String {
+    format!(r#"
+(module
+  {imports}
+  (memory (export "memory") {pages})
+  (data (i32.const 0) "hello")
+  (func (export "main") (result i32)
+    {call}))
+"#)
+}
+
+const LOG_IMPORT: &str = r#"(import "env" "host_log_message" (func $log (param i32 i32) (result i32)))"#;
+
+async fn run(wat: &str) -> i32 {
+    execute_wasm(wat.as_bytes(), None, &host_env(), None, None)
+        .await
+        .expect("host calls with bad arguments must not abort the execution")
+        .code
+}
+
+async fn log_call(pages: u32, ptr: i32, len: i32) -> i32 {
+    run(&module(pages, LOG_IMPORT, &format!("(call $log (i32.const {}) (i32.const {}))", ptr, len))).await
+}
+
+#[tokio::test]
+async fn test_out_of_bounds_reads_are_refused() {
+    // A well-formed read still works
+    assert_eq!(log_call(1, 0, 5).await, 0);
+
+    // Straddling the end of memory, starting past it, and negative arguments
+    assert_eq!(log_call(1, PAGE - 2, 5).await, ABI_ERROR);
+    assert_eq!(log_call(1, PAGE, 1).await, ABI_ERROR);
+    assert_eq!(log_call(1, -1, 5).await, ABI_ERROR);
+    assert_eq!(log_call(1, 0, -1).await, ABI_ERROR);
+}
+
+#[tokio::test]
+async fn test_pointer_math_overflow_is_refused() {
+    // ptr + len overflows i32 and, once the host casts to u32, wraps back into memory
+    assert_eq!(log_call(1, i32::MAX, i32::MAX).await, ABI_ERROR);
+
+    // get_value takes its key as u32, so -1 is 0xFFFF_FFFF and ptr + len wraps to 1
+    let imports = r#"(import "env" "get_value" (func $get (param i32 i32 i32 i32) (result i32)))"#;
+    let call = "(call $get (i32.const -1) (i32.const 2) (i32.const 0) (i32.const 16))";
+    assert_eq!(run(&module(1, imports, call)).await, ABI_ERROR);
+}
+
+#[tokio::test]
+async fn test_out_of_bounds_writes_are_refused() {
+    let imports = r#"(import "env" "host_get_execution_context" (func $ctx (param i32 i32) (result i32)))"#;
+
+    // The output buffer would run off the end of memory, or starts before it
+    let call = format!("(call $ctx (i32.const {}) (i32.const 4096))", PAGE - 8);
+    assert_eq!(run(&module(1, imports, &call)).await, ABI_ERROR);
+    let call = "(call $ctx (i32.const -8) (i32.const 4096))";
+    assert_eq!(run(&module(1, imports, call)).await, ABI_ERROR);
+
+    // The same call into a buffer inside memory succeeds
+    let call = "(call $ctx (i32.const 1024) (i32.const 4096))";
+    assert!(run(&module(1, imports, call)).await > 0);
+}
+
+#[tokio::test]
+async fn test_huge_lengths_are_clamped() {
+    // Enough memory that the read is in bounds, but longer than any one parameter may be
+    let pages = (MAX_HOST_PARAM_LEN as u32 / PAGE as u32) + 4;
+    let too_long = MAX_HOST_PARAM_LEN as i32 + 1;
+    assert_eq!(log_call(pages, 0, too_long).await, ABI_ERROR);
+
+    // A parent table claiming 2^30 entries is refused before anything is allocated for it
+    let imports = r#"(import "env" "store_dag_node" (func $store
+      (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))"#;
+    let call = "(call $store
+      (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0)
+      (i32.const 0) (i32.const 0x40000000) (i32.const 0)
+      (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0)
+      (i32.const 0) (i32.const 0))";
+    assert_eq!(run(&module(1, imports, call)).await, ABI_ERROR);
+}
+
+#[test]
+fn test_memory_helpers_use_checked_arithmetic() {
+    let mem_size = PAGE as usize;
+    assert_eq!(checked_range(mem_size, 16, 8).unwrap(), 16..24);
+    assert_eq!(checked_range(mem_size, mem_size, 0).unwrap(), mem_size..mem_size);
+
+    assert!(checked_range(mem_size, mem_size - 4, 8).is_err());
+    assert!(checked_range(usize::MAX, usize::MAX - 1, 8).is_err());
+    assert!(checked_range(usize::MAX, 0, MAX_HOST_PARAM_LEN + 1).is_err());
+
+    assert!(guest_range(mem_size, -1, 1).is_err());
+    assert!(guest_range(mem_size, 0, i32::MIN).is_err());
+    assert!(guest_range(usize::MAX, i32::MAX, i32::MAX).is_err());
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use icn_core_vm::audit::argument_digest;
+use icn_core_vm::{
+    ConcreteHostEnvironment, IdentityContext, ResourceAuthorization, ResourceType, SyscallAuditPolicy, VMContext,
+    execute_wasm,
+};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage, KeyPair};
+use icn_storage::InMemoryStorageManager;
+use wasmtime::Val;
+
+/// Stores a value under "key" and reads it back
+const KV_MODULE: &str = r#"
+(module
+  (import "env" "set_value" (func $set_value (param i32 i32 i32 i32) (result i32)))
+  (import "env" "get_value" (func $get_value (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "key")
+  (data (i32.const 8) "secret")
+  (func (export "main") (result i32)
+    (drop (call $set_value (i32.const 0) (i32.const 3) (i32.const 8) (i32.const 6)))
+    (call $get_value (i32.const 0) (i32.const 3) (i32.const 32) (i32.const 16))))
+"#;
+
+fn host_env(vm_context: VMContext) -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(vm_context, storage.clone(), identity_manager, None, storage)
+}
+
+#[tokio::test]
+async fn test_host_calls_are_recorded_with_redaction() {
+    let identity = Arc::new(IdentityContext::new(KeyPair::generate_random(), "did:icn:auditor"));
+    let vm_context = VMContext::new(identity, vec![
+        ResourceAuthorization::new(ResourceType::Compute, 1_000_000, None, "Audit test".to_string()),
+        ResourceAuthorization::new(ResourceType::Storage, 10_000, None, "Audit test".to_string()),
+    ]);
+    let host_env = host_env(vm_context.clone())
+        .with_syscall_audit(SyscallAuditPolicy::default().redact("set_value"));
+
+    let result = execute_wasm(KV_MODULE.as_bytes(), Some(vm_context), &host_env, None, None)
+        .await
+        .unwrap();
+    assert!(result.syscall_audit_cid.is_some());
+
+    let log = host_env.syscall_audit_log();
+    assert_eq!(log.caller_did, "did:icn:auditor");
+    let names: Vec<_> = log.records.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, vec!["env::set_value", "env::get_value"]);
+
+    let set = &log.records[0];
+    assert!(set.redacted && set.arg_digest.is_none());
+    assert_eq!(set.result_code, Some(1));
+
+    let get = &log.records[1];
+    assert_eq!(get.sequence, 1);
+    assert_eq!(
+        get.arg_digest,
+        Some(argument_digest(&[Val::I32(0), Val::I32(3), Val::I32(32), Val::I32(16)]))
+    );
+    assert!(!get.trapped);
+    assert!(get.fuel_at_call >= set.fuel_at_call);
+}
+
+#[tokio::test]
+async fn test_auditing_is_off_by_default() {
+    let vm_context = VMContext::default();
+    let host_env = host_env(vm_context.clone());
+
+    let module = r#"(module (func (export "main") (result i32) (i32.const 7)))"#;
+    let result = execute_wasm(module.as_bytes(), Some(vm_context), &host_env, None, None)
+        .await
+        .unwrap();
+    assert_eq!(result.code, 7);
+    assert!(result.syscall_audit_cid.is_none());
+    assert!(host_env.syscall_audit_log().records.is_empty());
+}
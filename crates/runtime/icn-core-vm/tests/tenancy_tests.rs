@@ -0,0 +1,137 @@
+use std::sync::Arc;
+use icn_core_vm::{
+    AuthorizationTenant, ConcreteHostEnvironment, IdentityContext, InternalHostError, ResourceAuthorization,
+    ResourceType, VMContext, VmError, execute_wasm,
+};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage, KeyPair};
+use icn_storage::InMemoryStorageManager;
+
+/// Records 100 units of storage
+const RECORD_MODULE: &str = r#"
+(module
+  (import "env" "record_resource_usage" (func $record (param i32 i32)))
+  (func (export "main") (result i32)
+    (call $record (i32.const 1) (i32.const 100))
+    (i32.const 0)))
+"#;
+
+/// Returns whether 100 units of storage are authorized, or the host's error code
+const CHECK_MODULE: &str = r#"
+(module
+  (import "env" "check_resource_authorization" (func $check (param i32 i32) (result i32)))
+  (func (export "main") (result i32)
+    (call $check (i32.const 1) (i32.const 100))))
+"#;
+
+fn authorizations() -> Vec<ResourceAuthorization> {
+    vec![
+        ResourceAuthorization::new(ResourceType::Compute, 1_000_000, None, "Tenancy test".to_string()),
+        ResourceAuthorization::new(ResourceType::Storage, 1_000, None, "Tenancy test".to_string()),
+    ]
+}
+
+fn vm_context(did: &str, scope: &str, authorizations: Vec<ResourceAuthorization>) -> VMContext {
+    let identity = Arc::new(IdentityContext::new(KeyPair::generate_random(), did));
+    VMContext::with_scope(identity, scope, authorizations)
+}
+
+fn host_env(vm_context: VMContext) -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(vm_context, storage.clone(), identity_manager, None, storage)
+}
+
+#[test]
+fn test_authorizations_are_bound_at_load() {
+    let alice = vm_context("did:icn:alice", "coop-a", authorizations());
+    let tenant = AuthorizationTenant { caller_did: "did:icn:alice".to_string(), scope: "coop-a".to_string() };
+    assert_eq!(alice.tenant(), &tenant);
+    assert!(alice.resource_authorizations().iter().all(|auth| auth.belongs_to(&tenant)));
+    assert_eq!(alice.authorization_for(ResourceType::Storage).unwrap().unwrap().limit, 1_000);
+    assert!(alice.authorization_for(ResourceType::Network).unwrap().is_none());
+
+    // Loading alice's authorizations into another context doesn't rebind them, whether
+    // the caller or only the scope differs
+    for (did, scope) in [("did:icn:bob", "coop-a"), ("did:icn:alice", "coop-b")] {
+        let other = vm_context(did, scope, alice.resource_authorizations().to_vec());
+        assert!(matches!(
+            other.authorization_for(ResourceType::Storage),
+            Err(InternalHostError::UnauthorizedAccess(_))
+        ));
+    }
+}
+
+#[test]
+fn test_cross_tenant_consumption_is_refused() {
+    let alice = vm_context("did:icn:alice", "coop-a", authorizations());
+    let bob = host_env(vm_context("did:icn:bob", "coop-b", alice.resource_authorizations().to_vec()));
+
+    assert!(matches!(bob.record_storage_usage(100), Err(VmError::Unauthorized(_))));
+    assert_eq!(bob.get_storage_consumed(), 0);
+}
+
+#[tokio::test]
+async fn test_modules_cannot_consume_another_tenants_authorizations() {
+    let alice = vm_context("did:icn:alice", "coop-a", authorizations());
+    let bob = host_env(vm_context("did:icn:bob", "coop-b", alice.resource_authorizations().to_vec()));
+
+    // Checking reports the host's unauthorized access code instead of a yes or no
+    let wasm = wat::parse_str(CHECK_MODULE).unwrap();
+    let result = execute_wasm(&wasm, None, &bob, None, None).await.unwrap();
+    assert_eq!(result.code, -8);
+
+    // Recording traps
+    let wasm = wat::parse_str(RECORD_MODULE).unwrap();
+    assert!(execute_wasm(&wasm, None, &bob, None, None).await.is_err());
+    assert_eq!(bob.get_storage_consumed(), 0);
+}
+
+#[tokio::test]
+async fn test_context_must_match_host_tenant() {
+    let alice = vm_context("did:icn:alice", "coop-a", authorizations());
+    let bob = host_env(vm_context("did:icn:bob", "coop-b", authorizations()));
+
+    let wasm = wat::parse_str(RECORD_MODULE).unwrap();
+    let result = execute_wasm(&wasm, Some(alice), &bob, None, None).await;
+    assert!(matches!(result, Err(VmError::Unauthorized(_))));
+    assert_eq!(bob.get_storage_consumed(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_executions_stay_within_their_tenant() {
+    let wasm = Arc::new(wat::parse_str(RECORD_MODULE).unwrap());
+    let alice = host_env(vm_context("did:icn:alice", "coop-a", authorizations()));
+    let bob = host_env(vm_context("did:icn:bob", "coop-b", authorizations()));
+    let alice_authorizations = vm_context("did:icn:alice", "coop-a", authorizations()).resource_authorizations().to_vec();
+    let mallory = host_env(vm_context("did:icn:mallory", "coop-b", alice_authorizations));
+
+    let mut handles = Vec::new();
+    for i in 0..15 {
+        let env = match i % 3 {
+            0 => alice.clone(),
+            1 => bob.clone(),
+            _ => mallory.clone(),
+        };
+        let wasm = wasm.clone();
+        handles.push(tokio::spawn(async move {
+            (i % 3, execute_wasm(&wasm, None, &env, None, None).await.map(|result| result.code))
+        }));
+    }
+
+    for handle in handles {
+        let (tenant, outcome) = handle.await.unwrap();
+        if tenant == 2 {
+            assert!(outcome.is_err());
+        } else {
+            assert_eq!(outcome.unwrap(), 0);
+        }
+    }
+
+    // Each tenant's executions drew on its own authorization only
+    assert_eq!(alice.get_storage_consumed(), 500);
+    assert_eq!(bob.get_storage_consumed(), 500);
+    assert_eq!(mallory.get_storage_consumed(), 0);
+}
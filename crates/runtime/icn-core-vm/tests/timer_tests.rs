@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use icn_core_vm::{ConcreteHostEnvironment, VMContext, execute_wasm, MAX_TIMERS_PER_EXECUTION, TIMER_LIMIT_REACHED};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Registers a monthly dues assessment `count` times with a schedule of the given
+/// length, returning the last result code
+fn timer_module(schedule_len: u32, count: usize) -> String {
+    format!(r#"
+(module
+  (import "env" "host_register_timer" (func $register (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "0 9 1 * *")
+  (data (i32.const 16) "{{\"action\":\"assess_dues\",\"amount\":25}}")
+  (func (export "main") (result i32)
+    (local $code i32)
+    (local $i i32)
+    (loop $again
+      (local.set $code (call $register (i32.const 0) (i32.const {}) (i32.const 16) (i32.const 36)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $again (i32.lt_u (local.get $i) (i32.const {}))))
+    (local.get $code)))
+"#, schedule_len, count)
+}
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[tokio::test]
+async fn test_module_registers_recurring_timer() {
+    let host_env = host_env();
+    let wasm = wat::parse_str(timer_module(9, 1)).unwrap();
+
+    let result = execute_wasm(&wasm, None, &host_env, None, None).await.unwrap();
+    assert_eq!(result.code, 0);
+    assert_eq!(result.registered_timers.len(), 1);
+
+    let timer = &result.registered_timers[0];
+    assert_eq!(timer.schedule, "0 9 1 * *");
+    assert_eq!(timer.payload, serde_json::json!({"action": "assess_dues", "amount": 25}));
+    assert_eq!(timer.registered_by, host_env.caller_did());
+}
+
+#[tokio::test]
+async fn test_invalid_schedules_and_excess_timers_are_refused() {
+    // "0 9 1 *" has only four fields
+    let wasm = wat::parse_str(timer_module(7, 1)).unwrap();
+    let result = execute_wasm(&wasm, None, &host_env(), None, None).await.unwrap();
+    assert_eq!(result.code, -5);
+    assert!(result.registered_timers.is_empty());
+
+    let wasm = wat::parse_str(timer_module(9, MAX_TIMERS_PER_EXECUTION + 1)).unwrap();
+    let result = execute_wasm(&wasm, None, &host_env(), None, None).await.unwrap();
+    assert_eq!(result.code, TIMER_LIMIT_REACHED);
+    assert_eq!(result.registered_timers.len(), MAX_TIMERS_PER_EXECUTION);
+}
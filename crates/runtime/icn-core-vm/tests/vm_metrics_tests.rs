@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use icn_core_vm::{
+    ConcreteHostEnvironment, VMContext, VmMetrics, WarmPool, WarmPoolConfig, execute_wasm, execute_wasm_pooled,
+};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+const SCOPE: &str = "did:icn:federation";
+
+/// Logs a line through the host and returns 0
+const LOGGING_MODULE: &str = r#"
+(module
+  (import "env" "host_log_message" (func $log (param i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "hello")
+  (func (export "main") (result i32)
+    (drop (call $log (i32.const 0) (i32.const 5)))
+    (i32.const 0)))
+"#;
+
+const TRAPPING_MODULE: &str = r#"
+(module
+  (func (export "main") (result i32)
+    unreachable))
+"#;
+
+fn host_env(metrics: &VmMetrics) -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+        .with_vm_metrics(metrics.clone())
+}
+
+#[tokio::test]
+async fn test_executions_are_counted_by_outcome() {
+    let metrics = VmMetrics::new().unwrap();
+    let host_env = host_env(&metrics);
+
+    let logging = wat::parse_str(LOGGING_MODULE).unwrap();
+    execute_wasm(&logging, None, &host_env, None, Some(SCOPE)).await.unwrap();
+    let trapping = wat::parse_str(TRAPPING_MODULE).unwrap();
+    assert!(execute_wasm(&trapping, None, &host_env, None, Some(SCOPE)).await.is_err());
+    assert!(execute_wasm(b"not wasm", None, &host_env, None, Some(SCOPE)).await.is_err());
+
+    let summary = metrics.scope_summary(SCOPE);
+    assert_eq!(summary.executions, 3);
+    assert_eq!(summary.successful, 1);
+    assert!(summary.fuel_consumed > 0.0);
+
+    let text = metrics.gather_text().unwrap();
+    assert!(text.contains(r#"icn_vm_executions_total{outcome="trapped",scope="did:icn:federation"} 1"#));
+    assert!(text.contains(r#"icn_vm_executions_total{outcome="rejected",scope="did:icn:federation"} 1"#));
+    assert!(text.contains(r#"icn_vm_host_call_seconds_count{function="host_log_message",scope="did:icn:federation"} 1"#));
+
+    // Other scopes are reported separately
+    assert_eq!(metrics.scope_summary("did:icn:other").success_rate(), None);
+}
+
+#[tokio::test]
+async fn test_pooled_executions_hit_the_module_cache() {
+    let metrics = VmMetrics::new().unwrap();
+    let host_env = host_env(&metrics);
+    let pool = WarmPool::new(WarmPoolConfig { size: 1, max_idle: 1 }).unwrap();
+    let wasm = wat::parse_str(LOGGING_MODULE).unwrap();
+
+    for _ in 0..3 {
+        execute_wasm_pooled(&pool, &wasm, None, &host_env, None, Some(SCOPE)).await.unwrap();
+    }
+
+    let summary = metrics.scope_summary(SCOPE);
+    assert_eq!(summary.module_cache_misses, 1);
+    assert_eq!(summary.module_cache_hits, 2);
+    assert_eq!(summary.success_rate(), Some(1.0));
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use icn_core_vm::pool::{create_engine, WarmPool, WarmPoolConfig};
+use icn_core_vm::{ConcreteHostEnvironment, ResourceType, VMContext, execute_wasm_pooled};
+use icn_identity::{ConcreteIdentityManager, InMemoryKeyStorage, InMemoryMetadataStorage};
+use icn_storage::InMemoryStorageManager;
+
+/// Returns what an earlier instance would have left behind (memory byte 0 times 100
+/// plus a mutable global), then leaves state of its own behind
+const STATEFUL_MODULE: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (global $runs (mut i32) (i32.const 0))
+  (func (export "main") (result i32)
+    (local $seen i32)
+    (local.set $seen (i32.add
+      (i32.mul (i32.load8_u (i32.const 0)) (i32.const 100))
+      (global.get $runs)))
+    (i32.store8 (i32.const 0) (i32.const 7))
+    (global.set $runs (i32.add (global.get $runs) (i32.const 1)))
+    (local.get $seen)))
+"#;
+
+/// Burns fuel and grows memory, so a leaked store would show up in the next execution
+const BUSY_MODULE: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "main") (result i32)
+    (local $i i32)
+    (drop (memory.grow (i32.const 3)))
+    (loop $spin
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $spin (i32.lt_u (local.get $i) (i32.const 10000))))
+    (memory.size)))
+"#;
+
+fn host_env() -> ConcreteHostEnvironment {
+    let storage = Arc::new(InMemoryStorageManager::new());
+    let identity_manager = Arc::new(ConcreteIdentityManager::new(
+        Arc::new(InMemoryKeyStorage::default()),
+        Arc::new(InMemoryMetadataStorage::default()),
+    ));
+    ConcreteHostEnvironment::new(VMContext::default(), storage.clone(), identity_manager, None, storage)
+}
+
+#[test]
+fn test_leases_reuse_warm_slots() {
+    let pool = WarmPool::new(WarmPoolConfig { size: 2, max_idle: 2 }).unwrap();
+    assert_eq!(pool.stats().idle, 2);
+
+    {
+        let _first = pool.lease().unwrap();
+        let _second = pool.lease().unwrap();
+        assert_eq!(pool.stats().idle, 0);
+        assert_eq!(pool.stats().warm_leases, 2);
+    }
+
+    // Both slots are back once the leases are dropped
+    assert_eq!(pool.stats().idle, 2);
+    let _again = pool.lease().unwrap();
+    assert_eq!(pool.stats().warm_leases, 3);
+    assert_eq!(pool.stats().cold_starts, 0);
+}
+
+#[test]
+fn test_exhausted_pool_cold_starts_without_growing_past_max_idle() {
+    let pool = WarmPool::new(WarmPoolConfig { size: 1, max_idle: 1 }).unwrap();
+
+    {
+        let _warm = pool.lease().unwrap();
+        let cold = pool.lease().unwrap();
+        assert_eq!(pool.stats().cold_starts, 1);
+
+        // Modules compile against a cold-started slot's engine as they do a warm one
+        let wasm = wat::parse_str(r#"(module (func (export "main") (result i32) i32.const 0))"#).unwrap();
+        assert!(wasmtime::Module::new(cold.slot().engine(), &wasm).is_ok());
+    }
+
+    // Only one slot is kept idle
+    assert_eq!(pool.stats().idle, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reused_slot_starts_each_execution_clean() {
+    // A single slot, so every execution below runs on it
+    let pool = WarmPool::new(WarmPoolConfig { size: 1, max_idle: 1 }).unwrap();
+
+    let first = execute_wasm_pooled(&pool, STATEFUL_MODULE.as_bytes(), None, &host_env(), None, None).await.unwrap();
+    assert_eq!(first.code, 0);
+
+    let busy = execute_wasm_pooled(&pool, BUSY_MODULE.as_bytes(), None, &host_env(), None, None).await.unwrap();
+    assert_eq!(busy.code, 4);
+
+    // The same module on the same slot sees none of the memory or globals it left behind,
+    // and is charged from a fresh fuel budget
+    let second = execute_wasm_pooled(&pool, STATEFUL_MODULE.as_bytes(), None, &host_env(), None, None).await.unwrap();
+    assert_eq!(second.code, 0);
+    assert_eq!(
+        second.resource_usage.get(&ResourceType::Compute),
+        first.resource_usage.get(&ResourceType::Compute)
+    );
+
+    let stats = pool.stats();
+    assert_eq!(stats.warm_leases, 3);
+    assert_eq!(stats.cold_starts, 0);
+}
+
+#[test]
+fn test_warm_lease_is_cheaper_than_cold_setup() {
+    const ROUNDS: u32 = 5;
+    let pool = WarmPool::new(WarmPoolConfig { size: 1, max_idle: 1 }).unwrap();
+
+    let mut warm = Duration::ZERO;
+    let mut cold = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        let started = Instant::now();
+        let lease = pool.lease().unwrap();
+        warm += started.elapsed();
+        drop(lease);
+
+        // What execute_wasm does before it can compile the module
+        let started = Instant::now();
+        let engine = create_engine().unwrap();
+        let mut linker = wasmtime::Linker::new(&engine);
+        icn_core_vm::host_abi::register_host_functions(&mut linker).unwrap();
+        cold += started.elapsed();
+    }
+
+    assert!(warm < cold, "warm leases took {:?}, cold setup {:?}", warm / ROUNDS, cold / ROUNDS);
+}
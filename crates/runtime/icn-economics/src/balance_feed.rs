@@ -0,0 +1,249 @@
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::Stream;
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::{self, BudgetStorage};
+use crate::statements::{self, TreasuryFlow};
+
+/// Changes kept per account for subscribers resuming after a gap
+pub const REPLAY_DEPTH: usize = 256;
+
+/// An account whose balance can be watched
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BalanceAccount {
+    /// A scope's treasury journal
+    Treasury { scope_id: String },
+
+    /// One resource of a participatory budget
+    Budget { budget_id: String, resource: ResourceType },
+}
+
+/// A change to an account's balance. Sequence numbers count up by one per account, so
+/// a subscriber that sees a jump knows it missed changes and can resume from the last
+/// sequence it saw. Changes serialize to JSON for the gateway to forward over its
+/// WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub account: BalanceAccount,
+    pub sequence: u64,
+    pub previous_balance: i64,
+    pub balance: i64,
+
+    /// Journal entry or budget operation that caused the change, if known
+    pub source: Option<String>,
+    pub occurred_at: i64,
+}
+
+impl BalanceChange {
+    /// Whether this change comes directly after `last_sequence`
+    pub fn follows(&self, last_sequence: u64) -> bool {
+        self.sequence == last_sequence + 1
+    }
+}
+
+/// An account's balance at a given sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceSnapshot {
+    pub sequence: u64,
+    pub balance: i64,
+}
+
+#[derive(Default)]
+struct AccountFeed {
+    snapshot: Option<BalanceSnapshot>,
+    recent: VecDeque<BalanceChange>,
+    subscribers: Vec<UnboundedSender<BalanceChange>>,
+}
+
+/// Stream of an account's balance changes
+pub struct BalanceSubscription {
+    /// The balance when the subscription was opened, if the account has one yet
+    pub snapshot: Option<BalanceSnapshot>,
+    receiver: UnboundedReceiver<BalanceChange>,
+}
+
+impl Stream for BalanceSubscription {
+    type Item = BalanceChange;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// Publishes balance changes to subscribers, per account
+#[derive(Default)]
+pub struct BalanceFeed {
+    accounts: Mutex<HashMap<BalanceAccount, AccountFeed>>,
+}
+
+impl BalanceFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> EconomicsResult<std::sync::MutexGuard<'_, HashMap<BalanceAccount, AccountFeed>>> {
+        self.accounts.lock()
+            .map_err(|_| EconomicsError::InvalidBudget("Balance feed lock poisoned".to_string()))
+    }
+
+    /// Watch an account. With `resume_after`, the changes after that sequence are
+    /// delivered first; resuming from further back than the replay buffer reaches fails,
+    /// and the subscriber should reload the balance and subscribe afresh.
+    pub fn subscribe(&self, account: &BalanceAccount, resume_after: Option<u64>) -> EconomicsResult<BalanceSubscription> {
+        let mut accounts = self.lock()?;
+        let feed = accounts.entry(account.clone()).or_default();
+        let (sender, receiver) = mpsc::unbounded();
+
+        if let Some(after) = resume_after {
+            let current = feed.snapshot.map(|s| s.sequence).unwrap_or(0);
+            let oldest = feed.recent.front().map(|c| c.sequence).unwrap_or(current + 1);
+            if after > current || after + 1 < oldest {
+                return Err(EconomicsError::InvalidBudget(format!(
+                    "Cannot resume balance feed after sequence {} (replay covers {}..={})", after, oldest, current
+                )));
+            }
+            for change in feed.recent.iter().filter(|c| c.sequence > after) {
+                let _ = sender.unbounded_send(change.clone());
+            }
+        }
+
+        feed.subscribers.push(sender);
+        Ok(BalanceSubscription { snapshot: feed.snapshot, receiver })
+    }
+
+    /// The latest balance published for an account
+    pub fn snapshot(&self, account: &BalanceAccount) -> EconomicsResult<Option<BalanceSnapshot>> {
+        Ok(self.lock()?.get(account).and_then(|feed| feed.snapshot))
+    }
+
+    /// Publish an account's new balance. Nothing is sent when the balance is unchanged.
+    /// The first balance published for an account sets its baseline without a change.
+    pub fn publish(
+        &self,
+        account: &BalanceAccount,
+        balance: i64,
+        source: Option<String>,
+        occurred_at: i64,
+    ) -> EconomicsResult<Option<BalanceChange>> {
+        let mut accounts = self.lock()?;
+        let feed = accounts.entry(account.clone()).or_default();
+
+        let previous = match feed.snapshot {
+            None => {
+                feed.snapshot = Some(BalanceSnapshot { sequence: 0, balance });
+                return Ok(None);
+            }
+            Some(previous) if previous.balance == balance => return Ok(None),
+            Some(previous) => previous,
+        };
+
+        let change = BalanceChange {
+            account: account.clone(),
+            sequence: previous.sequence + 1,
+            previous_balance: previous.balance,
+            balance,
+            source,
+            occurred_at,
+        };
+        feed.snapshot = Some(BalanceSnapshot { sequence: change.sequence, balance });
+        feed.recent.push_back(change.clone());
+        if feed.recent.len() > REPLAY_DEPTH {
+            feed.recent.pop_front();
+        }
+        feed.subscribers.retain(|subscriber| subscriber.unbounded_send(change.clone()).is_ok());
+        Ok(Some(change))
+    }
+}
+
+/// Publish a scope's treasury balance, e.g. when its journal is opened
+pub async fn publish_treasury_balance(
+    scope_id: &str,
+    occurred_at: i64,
+    feed: &BalanceFeed,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<BalanceChange>> {
+    let journal = statements::load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+    let account = BalanceAccount::Treasury { scope_id: scope_id.to_string() };
+    feed.publish(&account, journal.balance(), None, occurred_at)
+}
+
+/// [`statements::record_treasury_entry`], publishing the treasury's new balance
+#[allow(clippy::too_many_arguments)]
+pub async fn record_treasury_entry_and_publish(
+    scope_id: &str,
+    flow: TreasuryFlow,
+    category: &str,
+    amount: u64,
+    occurred_at: i64,
+    reference: Option<String>,
+    feed: &BalanceFeed,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let account = BalanceAccount::Treasury { scope_id: scope_id.to_string() };
+    if feed.snapshot(&account)?.is_none() {
+        publish_treasury_balance(scope_id, occurred_at, feed, storage).await?;
+    }
+
+    let entry_id = statements::record_treasury_entry(scope_id, flow, category, amount, occurred_at, reference, storage).await?;
+    let journal = statements::load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+    feed.publish(&account, journal.balance(), Some(entry_id.clone()), occurred_at)?;
+    Ok(entry_id)
+}
+
+/// Publish the available balance of one resource of a budget, after an allocation or
+/// spend has been stored
+pub async fn publish_budget_balance(
+    budget_id: &str,
+    resource: &ResourceType,
+    source: Option<String>,
+    occurred_at: i64,
+    feed: &BalanceFeed,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<BalanceChange>> {
+    let available = budget_ops::query_budget_balance(budget_id, resource, storage).await?;
+    let account = BalanceAccount::Budget { budget_id: budget_id.to_string(), resource: resource.clone() };
+    feed.publish(&account, available as i64, source, occurred_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use crate::budget_ops::MockBudgetStorage;
+    use crate::statements::open_treasury_journal;
+
+    #[tokio::test]
+    async fn test_treasury_postings_stream_with_sequence_numbers() {
+        let mut storage = MockBudgetStorage::new();
+        let feed = BalanceFeed::new();
+        let account = BalanceAccount::Treasury { scope_id: "coop-1".to_string() };
+        open_treasury_journal("coop-1", "USD", 1_000, &mut storage).await.unwrap();
+        publish_treasury_balance("coop-1", 0, &feed, &storage).await.unwrap();
+
+        let mut subscription = feed.subscribe(&account, None).unwrap();
+        assert_eq!(subscription.snapshot, Some(BalanceSnapshot { sequence: 0, balance: 1_000 }));
+
+        let dues = record_treasury_entry_and_publish("coop-1", TreasuryFlow::Inflow, "dues", 300, 100, Some("pay-1".into()), &feed, &mut storage).await.unwrap();
+        // A duplicate posting leaves the balance alone and sends nothing
+        record_treasury_entry_and_publish("coop-1", TreasuryFlow::Inflow, "dues", 300, 100, Some("pay-1".into()), &feed, &mut storage).await.unwrap();
+        record_treasury_entry_and_publish("coop-1", TreasuryFlow::Outflow, "rent", 500, 200, None, &feed, &mut storage).await.unwrap();
+
+        let first = subscription.next().await.unwrap();
+        assert_eq!((first.sequence, first.previous_balance, first.balance), (1, 1_000, 1_300));
+        assert_eq!(first.source, Some(dues));
+        let second = subscription.next().await.unwrap();
+        assert!(second.follows(first.sequence));
+        assert_eq!(second.balance, 800);
+
+        // A subscriber that saw only the first change resumes from it
+        let mut resumed = feed.subscribe(&account, Some(1)).unwrap();
+        assert_eq!(resumed.next().await.unwrap(), second);
+        assert!(feed.subscribe(&account, Some(5)).is_err());
+    }
+}
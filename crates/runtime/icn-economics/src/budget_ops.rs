@@ -193,7 +193,7 @@ pub async fn load_budget(
 }
 
 /// Save a budget to storage
-async fn save_budget(
+pub(crate) async fn save_budget(
     budget: &ParticipatoryBudget,
     storage: &mut impl BudgetStorage,
 ) -> EconomicsResult<()> {
@@ -0,0 +1,575 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+use crate::patronage::PatronageShare;
+
+/// Storage key prefix for capital accounts
+const CAPITAL_ACCOUNT_KEY_PREFIX: &str = "capital::account::";
+
+/// Storage key prefix for the per-scope capital account index
+const CAPITAL_INDEX_KEY_PREFIX: &str = "capital::index::";
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u128 = 10_000;
+
+/// What a capital account entry records
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CapitalEntryKind {
+    /// Member's buy-in payment
+    BuyIn,
+
+    /// Retained patronage allocated for a period
+    Patronage { period: String },
+
+    /// Interest accrued for a period
+    Interest { period: String },
+
+    /// Redemption installment paid out to the member
+    Redemption { installment: usize },
+
+    /// Balance carried over from another scope in a merge
+    MergedFrom { scope_id: String },
+}
+
+impl CapitalEntryKind {
+    /// Whether the entry reduces the balance
+    pub fn is_debit(&self) -> bool {
+        matches!(self, CapitalEntryKind::Redemption { .. })
+    }
+}
+
+/// A single movement on a capital account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapitalEntry {
+    pub kind: CapitalEntryKind,
+    pub amount: u64,
+
+    /// When the entry was recorded (Unix timestamp)
+    pub recorded_at: i64,
+}
+
+/// Status of a capital account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapitalAccountStatus {
+    /// Member is active; the account receives allocations and interest
+    Open,
+
+    /// Member has exited; the balance is being paid out on a schedule
+    Redeeming,
+
+    /// Fully redeemed
+    Closed,
+}
+
+/// One installment of a redemption schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedemptionInstallment {
+    /// When the installment is due (Unix timestamp)
+    pub due_at: i64,
+    pub amount: u64,
+
+    /// When the installment was paid, if it has been
+    pub paid_at: Option<i64>,
+}
+
+/// Schedule for paying out a capital account after a member exits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedemptionSchedule {
+    /// When the member exited (Unix timestamp)
+    pub exited_at: i64,
+    pub installments: Vec<RedemptionInstallment>,
+}
+
+impl RedemptionSchedule {
+    /// Total still to be paid
+    pub fn outstanding(&self) -> u64 {
+        self.installments.iter()
+            .filter(|i| i.paid_at.is_none())
+            .map(|i| i.amount)
+            .sum()
+    }
+}
+
+/// A member's internal capital account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapitalAccount {
+    /// The cooperative scope the account belongs to
+    pub scope_id: String,
+    pub member_did: String,
+    pub balance: u64,
+    pub status: CapitalAccountStatus,
+    pub entries: Vec<CapitalEntry>,
+    pub redemption: Option<RedemptionSchedule>,
+
+    /// When the account was opened (Unix timestamp)
+    pub opened_at: i64,
+}
+
+impl CapitalAccount {
+    fn record(&mut self, kind: CapitalEntryKind, amount: u64, at: i64) -> EconomicsResult<()> {
+        self.balance = if kind.is_debit() {
+            self.balance.checked_sub(amount).ok_or_else(|| EconomicsError::InsufficientBalance(
+                format!("Capital account of {} holds {}, cannot debit {}", self.member_did, self.balance, amount)
+            ))?
+        } else {
+            self.balance.checked_add(amount).ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("Capital account of {} would overflow", self.member_did)
+            ))?
+        };
+        self.entries.push(CapitalEntry { kind, amount, recorded_at: at });
+        Ok(())
+    }
+
+    fn has_entry(&self, kind: &CapitalEntryKind) -> bool {
+        self.entries.iter().any(|e| &e.kind == kind)
+    }
+}
+
+/// Store a capital account
+pub async fn save_capital_account(
+    account: &CapitalAccount,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(account)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize capital account: {}", e)))?;
+
+    let key = format!("{}{}::{}", CAPITAL_ACCOUNT_KEY_PREFIX, account.scope_id, account.member_did);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a member's capital account in a scope
+pub async fn load_capital_account(
+    scope_id: &str,
+    member_did: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<CapitalAccount> {
+    let key = format!("{}{}::{}", CAPITAL_ACCOUNT_KEY_PREFIX, scope_id, member_did);
+    let data = storage.get_budget(&key).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No capital account for {} in {}", member_did, scope_id)))?;
+
+    serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize capital account: {}", e)))
+}
+
+/// List every capital account in a scope
+pub async fn list_capital_accounts(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Vec<CapitalAccount>> {
+    let mut accounts = Vec::new();
+    for member_did in load_capital_index(scope_id, storage).await? {
+        accounts.push(load_capital_account(scope_id, &member_did, storage).await?);
+    }
+    Ok(accounts)
+}
+
+/// Store a set of capital accounts and index them under their scope
+pub async fn import_capital_accounts(
+    accounts: &[CapitalAccount],
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    for account in accounts {
+        save_capital_account(account, storage).await?;
+        add_to_capital_index(&account.scope_id, &account.member_did, storage).await?;
+    }
+    Ok(())
+}
+
+/// Open a capital account with the member's initial buy-in
+pub async fn open_capital_account(
+    scope_id: &str,
+    member_did: &str,
+    buy_in: u64,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<CapitalAccount> {
+    if load_capital_index(scope_id, storage).await?.iter().any(|m| m == member_did) {
+        return Err(EconomicsError::InvalidBudget(format!("{} already has a capital account in {}", member_did, scope_id)));
+    }
+
+    let mut account = CapitalAccount {
+        scope_id: scope_id.to_string(),
+        member_did: member_did.to_string(),
+        balance: 0,
+        status: CapitalAccountStatus::Open,
+        entries: Vec::new(),
+        redemption: None,
+        opened_at: at,
+    };
+    if buy_in > 0 {
+        account.record(CapitalEntryKind::BuyIn, buy_in, at)?;
+    }
+
+    save_capital_account(&account, storage).await?;
+    add_to_capital_index(scope_id, member_did, storage).await?;
+    Ok(account)
+}
+
+/// Record a further buy-in payment (e.g., a buy-in paid in installments)
+pub async fn record_buy_in(
+    scope_id: &str,
+    member_did: &str,
+    amount: u64,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<CapitalAccount> {
+    let mut account = load_open_account(scope_id, member_did, storage).await?;
+    account.record(CapitalEntryKind::BuyIn, amount, at)?;
+    save_capital_account(&account, storage).await?;
+    Ok(account)
+}
+
+/// Credit the retained part of each member's patronage share to their capital account.
+///
+/// `retained_bps` is the fraction of each share retained as capital, in basis points;
+/// the rest is paid out as a cash dividend. Returns the amount retained per member.
+/// A period can only be allocated once.
+pub async fn allocate_patronage_to_capital(
+    scope_id: &str,
+    period: &str,
+    shares: &[PatronageShare],
+    retained_bps: u32,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<HashMap<String, u64>> {
+    if retained_bps as u128 > BASIS_POINTS {
+        return Err(EconomicsError::InvalidBudget(format!("Retained fraction {} exceeds 100%", retained_bps)));
+    }
+
+    let kind = CapitalEntryKind::Patronage { period: period.to_string() };
+    let mut accounts = Vec::with_capacity(shares.len());
+    for share in shares {
+        let account = load_open_account(scope_id, &share.member_did, storage).await?;
+        if account.has_entry(&kind) {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "Patronage for {} was already allocated to {}", period, share.member_did
+            )));
+        }
+        accounts.push((account, share.amount));
+    }
+
+    let mut retained = HashMap::new();
+    for (mut account, amount) in accounts {
+        let amount = (amount as u128 * retained_bps as u128 / BASIS_POINTS) as u64;
+        if amount > 0 {
+            account.record(kind.clone(), amount, at)?;
+            save_capital_account(&account, storage).await?;
+        }
+        retained.insert(account.member_did, amount);
+    }
+    Ok(retained)
+}
+
+/// Accrue a period's interest on every open capital account in a scope.
+///
+/// Interest is simple interest on the current balance at `rate_bps` basis points for
+/// the period, rounded down. Accounts that already accrued the period are skipped.
+/// Returns the total interest credited.
+pub async fn accrue_capital_interest(
+    scope_id: &str,
+    period: &str,
+    rate_bps: u32,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<u64> {
+    let kind = CapitalEntryKind::Interest { period: period.to_string() };
+    let mut total = 0u64;
+
+    for mut account in list_capital_accounts(scope_id, storage).await? {
+        if account.status != CapitalAccountStatus::Open || account.has_entry(&kind) {
+            continue;
+        }
+
+        let interest = (account.balance as u128 * rate_bps as u128 / BASIS_POINTS) as u64;
+        if interest == 0 {
+            continue;
+        }
+
+        account.record(kind.clone(), interest, at)?;
+        save_capital_account(&account, storage).await?;
+        total += interest;
+    }
+
+    Ok(total)
+}
+
+/// Schedule the redemption of an exiting member's capital account.
+///
+/// The balance is split into `installments` equal payments, `interval_secs` apart,
+/// starting at `first_due_at`. Any rounding remainder goes into the last installment.
+pub async fn schedule_redemption(
+    scope_id: &str,
+    member_did: &str,
+    installments: u32,
+    first_due_at: i64,
+    interval_secs: i64,
+    exited_at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<RedemptionSchedule> {
+    if installments == 0 {
+        return Err(EconomicsError::InvalidBudget("A redemption needs at least one installment".to_string()));
+    }
+    if interval_secs < 0 {
+        return Err(EconomicsError::InvalidBudget("Redemption interval must not be negative".to_string()));
+    }
+
+    let mut account = load_open_account(scope_id, member_did, storage).await?;
+
+    let count = installments as u64;
+    let base = account.balance / count;
+    let remainder = account.balance % count;
+    let schedule = RedemptionSchedule {
+        exited_at,
+        installments: (0..count)
+            .map(|i| RedemptionInstallment {
+                due_at: first_due_at + interval_secs * i as i64,
+                amount: if i == count - 1 { base + remainder } else { base },
+                paid_at: None,
+            })
+            .collect(),
+    };
+
+    account.status = if account.balance == 0 { CapitalAccountStatus::Closed } else { CapitalAccountStatus::Redeeming };
+    account.redemption = Some(schedule.clone());
+    save_capital_account(&account, storage).await?;
+    Ok(schedule)
+}
+
+/// Record payment of a redemption installment. The account closes once every
+/// installment has been paid.
+pub async fn record_redemption_payment(
+    scope_id: &str,
+    member_did: &str,
+    installment: usize,
+    paid_at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<CapitalAccount> {
+    let mut account = load_capital_account(scope_id, member_did, storage).await?;
+    if account.status != CapitalAccountStatus::Redeeming {
+        return Err(EconomicsError::InvalidBudget(format!("Capital account of {} is not being redeemed", member_did)));
+    }
+
+    let schedule = account.redemption.as_mut()
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No redemption schedule for {}", member_did)))?;
+    let entry = schedule.installments.get_mut(installment)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No redemption installment {}", installment)))?;
+    if entry.paid_at.is_some() {
+        return Err(EconomicsError::InvalidBudget(format!("Redemption installment {} was already paid", installment)));
+    }
+    entry.paid_at = Some(paid_at);
+    let amount = entry.amount;
+    let fully_paid = schedule.outstanding() == 0;
+
+    account.record(CapitalEntryKind::Redemption { installment }, amount, paid_at)?;
+    if fully_paid {
+        account.status = CapitalAccountStatus::Closed;
+    }
+
+    save_capital_account(&account, storage).await?;
+    Ok(account)
+}
+
+/// Combine the capital accounts of two merging scopes into accounts of the new scope.
+///
+/// A member with accounts on both sides gets one account holding the sum of both
+/// balances. If one side is being redeemed, its schedule is kept and the other side's
+/// balance is added to the final installment. Members being redeemed on both sides must
+/// be settled before the merge.
+pub fn union_capital_accounts(
+    accounts_a: &[CapitalAccount],
+    accounts_b: &[CapitalAccount],
+    new_scope_id: &str,
+    at: i64,
+) -> EconomicsResult<Vec<CapitalAccount>> {
+    let mut merged: Vec<CapitalAccount> = Vec::new();
+
+    for source in accounts_a.iter().chain(accounts_b.iter()) {
+        let carried = CapitalEntryKind::MergedFrom { scope_id: source.scope_id.clone() };
+
+        match merged.iter_mut().find(|a| a.member_did == source.member_did) {
+            None => {
+                let mut account = source.clone();
+                account.scope_id = new_scope_id.to_string();
+                account.entries.push(CapitalEntry { kind: carried, amount: source.balance, recorded_at: at });
+                merged.push(account);
+            }
+            Some(existing) => {
+                match (existing.redemption.is_some(), source.redemption.is_some()) {
+                    (true, true) => return Err(EconomicsError::InvalidBudget(format!(
+                        "{} is being redeemed in both scopes; settle one schedule before merging", source.member_did
+                    ))),
+                    (false, true) => {
+                        existing.redemption = source.redemption.clone();
+                        existing.status = source.status;
+                    }
+                    _ => {}
+                }
+
+                existing.balance = existing.balance.checked_add(source.balance).ok_or_else(|| {
+                    EconomicsError::InvalidBudget(format!("Capital account of {} would overflow", source.member_did))
+                })?;
+                existing.opened_at = existing.opened_at.min(source.opened_at);
+                existing.entries.extend(source.entries.iter().cloned());
+                existing.entries.push(CapitalEntry { kind: carried, amount: source.balance, recorded_at: at });
+
+                if let Some(last) = existing.redemption.as_mut().and_then(|s| s.installments.iter_mut().rev().find(|i| i.paid_at.is_none())) {
+                    let added = if source.redemption.is_some() { existing.balance - source.balance } else { source.balance };
+                    last.amount += added;
+                }
+            }
+        }
+    }
+
+    merged.sort_by(|a, b| a.member_did.cmp(&b.member_did));
+    Ok(merged)
+}
+
+/// Allocate a splitting scope's capital accounts to the two resulting scopes.
+///
+/// Each account follows its member. Every account holder must be assigned to exactly
+/// one side.
+pub fn split_capital_accounts(
+    accounts: &[CapitalAccount],
+    scope_a: &str,
+    members_a: &[String],
+    scope_b: &str,
+    members_b: &[String],
+) -> EconomicsResult<(Vec<CapitalAccount>, Vec<CapitalAccount>)> {
+    let mut side_a = Vec::new();
+    let mut side_b = Vec::new();
+
+    for account in accounts {
+        let in_a = members_a.contains(&account.member_did);
+        let in_b = members_b.contains(&account.member_did);
+        let (side, scope) = match (in_a, in_b) {
+            (true, false) => (&mut side_a, scope_a),
+            (false, true) => (&mut side_b, scope_b),
+            (true, true) => return Err(EconomicsError::InvalidBudget(format!(
+                "{} is assigned to both sides of the split", account.member_did
+            ))),
+            (false, false) => return Err(EconomicsError::InvalidBudget(format!(
+                "{} holds a capital account but is not assigned to either side of the split", account.member_did
+            ))),
+        };
+
+        let mut moved = account.clone();
+        moved.scope_id = scope.to_string();
+        side.push(moved);
+    }
+
+    Ok((side_a, side_b))
+}
+
+/// Total capital held across a set of accounts
+pub fn total_capital(accounts: &[CapitalAccount]) -> u64 {
+    accounts.iter().map(|a| a.balance).sum()
+}
+
+async fn load_open_account(
+    scope_id: &str,
+    member_did: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<CapitalAccount> {
+    let account = load_capital_account(scope_id, member_did, storage).await?;
+    if account.status != CapitalAccountStatus::Open {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Capital account of {} is {:?}", member_did, account.status
+        )));
+    }
+    Ok(account)
+}
+
+async fn load_capital_index(scope_id: &str, storage: &impl BudgetStorage) -> EconomicsResult<Vec<String>> {
+    let key = format!("{}{}", CAPITAL_INDEX_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize capital index: {}", e))),
+        None => Ok(Vec::new()),
+    }
+}
+
+async fn add_to_capital_index(scope_id: &str, member_did: &str, storage: &mut impl BudgetStorage) -> EconomicsResult<()> {
+    let mut index = load_capital_index(scope_id, storage).await?;
+    if !index.iter().any(|m| m == member_did) {
+        index.push(member_did.to_string());
+    }
+
+    let data = serde_json::to_vec(&index)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize capital index: {}", e)))?;
+    storage.store_budget(&format!("{}{}", CAPITAL_INDEX_KEY_PREFIX, scope_id), data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    fn share(did: &str, amount: u64) -> PatronageShare {
+        PatronageShare { member_did: did.to_string(), fraction: 0.0, amount }
+    }
+
+    #[tokio::test]
+    async fn test_capital_account_lifecycle() {
+        let mut storage = MockBudgetStorage::new();
+        open_capital_account("did:icn:coop", "did:icn:alice", 1000, 0, &mut storage).await.unwrap();
+        open_capital_account("did:icn:coop", "did:icn:bob", 500, 0, &mut storage).await.unwrap();
+        assert!(open_capital_account("did:icn:coop", "did:icn:bob", 500, 0, &mut storage).await.is_err());
+
+        // 70% of patronage is retained as capital
+        let retained = allocate_patronage_to_capital(
+            "did:icn:coop", "2024", &[share("did:icn:alice", 200), share("did:icn:bob", 100)], 7000, 10, &mut storage,
+        ).await.unwrap();
+        assert_eq!(retained["did:icn:alice"], 140);
+        assert!(allocate_patronage_to_capital(
+            "did:icn:coop", "2024", &[share("did:icn:alice", 200)], 7000, 10, &mut storage,
+        ).await.is_err());
+
+        // 5% interest, accrued once per period
+        assert_eq!(accrue_capital_interest("did:icn:coop", "2024", 500, 20, &mut storage).await.unwrap(), 57 + 28);
+        assert_eq!(accrue_capital_interest("did:icn:coop", "2024", 500, 20, &mut storage).await.unwrap(), 0);
+
+        let bob = load_capital_account("did:icn:coop", "did:icn:bob", &storage).await.unwrap();
+        assert_eq!(bob.balance, 500 + 70 + 28);
+
+        // Bob exits and is paid out over three installments
+        let schedule = schedule_redemption("did:icn:coop", "did:icn:bob", 3, 100, 50, 90, &mut storage).await.unwrap();
+        assert_eq!(schedule.installments.iter().map(|i| i.amount).collect::<Vec<_>>(), vec![199, 199, 200]);
+        assert_eq!(accrue_capital_interest("did:icn:coop", "2025", 500, 30, &mut storage).await.unwrap(), 59);
+
+        for installment in 0..3 {
+            record_redemption_payment("did:icn:coop", "did:icn:bob", installment, 100 + 50 * installment as i64, &mut storage).await.unwrap();
+        }
+        let bob = load_capital_account("did:icn:coop", "did:icn:bob", &storage).await.unwrap();
+        assert_eq!(bob.balance, 0);
+        assert_eq!(bob.status, CapitalAccountStatus::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_capital_accounts_follow_merges_and_splits() {
+        let mut storage = MockBudgetStorage::new();
+        open_capital_account("did:icn:coop-a", "did:icn:alice", 1000, 0, &mut storage).await.unwrap();
+        open_capital_account("did:icn:coop-a", "did:icn:bob", 300, 0, &mut storage).await.unwrap();
+        open_capital_account("did:icn:coop-b", "did:icn:bob", 200, 5, &mut storage).await.unwrap();
+
+        let a = list_capital_accounts("did:icn:coop-a", &storage).await.unwrap();
+        let b = list_capital_accounts("did:icn:coop-b", &storage).await.unwrap();
+        let merged = union_capital_accounts(&a, &b, "did:icn:coop-ab", 50).unwrap();
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(total_capital(&merged), total_capital(&a) + total_capital(&b));
+        assert_eq!(merged[1].member_did, "did:icn:bob");
+        assert_eq!(merged[1].balance, 500);
+        assert_eq!(merged[1].scope_id, "did:icn:coop-ab");
+
+        let (left, right) = split_capital_accounts(
+            &merged,
+            "did:icn:coop-a2", &["did:icn:alice".to_string()],
+            "did:icn:coop-b2", &["did:icn:bob".to_string()],
+        ).unwrap();
+        assert_eq!(total_capital(&left), 1000);
+        assert_eq!(right[0].scope_id, "did:icn:coop-b2");
+
+        assert!(split_capital_accounts(&merged, "x", &[], "y", &["did:icn:bob".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use async_trait::async_trait;
+use cid::Cid;
+use sha2::{Sha256, Digest};
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+use crate::statements::{self, TreasuryEntry, TreasuryFlow};
+use crate::idempotency::{self, DEFAULT_DEDUP_WINDOW_SECS};
+
+/// Storage key prefix for the chargeback ledger of a paying scope
+const CHARGEBACK_KEY_PREFIX: &str = "treasury::chargebacks::";
+
+/// Storage key prefix for the amounts frozen in a receiving scope's treasury
+const FROZEN_KEY_PREFIX: &str = "treasury::frozen::";
+
+/// Raw multicodec, for rulings anchored by the hash of their bytes
+const RAW_CODEC: u64 = 0x55;
+
+/// Category of the compensating journal entries a reversal posts
+pub const CHARGEBACK_CATEGORY: &str = "chargeback";
+
+/// How long after a transfer it may be flagged, unless a scope sets its own window (30 days)
+pub const DEFAULT_DISPUTE_WINDOW_SECS: i64 = 30 * 86_400;
+
+/// One side of an internal transfer: an entry in a scope's treasury journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferLeg {
+    pub scope_id: String,
+    pub entry_id: String,
+}
+
+/// How a flagged transfer was decided
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChargebackRuling {
+    /// Move the given amount back from the payee to the payer
+    Reverse { amount: u64 },
+    /// The transfer stands
+    Deny,
+}
+
+/// Where a flagged transfer stands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChargebackStatus {
+    /// The disputed amount is frozen while the dispute is heard
+    Open,
+    /// The ruling was carried out
+    Decided(ChargebackRuling),
+}
+
+/// A step in a dispute's trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChargebackStep {
+    /// What happened (e.g., "flagged", "frozen", "reversed")
+    pub action: String,
+    pub actor: String,
+    pub at: i64,
+    pub detail: Option<String>,
+}
+
+/// A mistaken or fraudulent internal transfer being contested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferDispute {
+    pub id: String,
+
+    /// The outflow from the paying scope
+    pub payer: TransferLeg,
+
+    /// The inflow to the receiving scope
+    pub payee: TransferLeg,
+
+    /// Amount contested and frozen in the payee's treasury
+    pub amount: u64,
+
+    pub reason: String,
+    pub raised_by: String,
+    pub raised_at: i64,
+    pub status: ChargebackStatus,
+
+    /// Journal entries posted to carry out a reversal, payee's first
+    pub compensating_entries: Vec<String>,
+
+    pub trail: Vec<ChargebackStep>,
+
+    /// CID the decided dispute was anchored under
+    pub anchor_cid: Option<String>,
+
+    /// Idempotency key of the call that carried out the ruling
+    #[serde(default)]
+    pub ruling_key: Option<String>,
+}
+
+/// Every transfer a paying scope has contested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChargebackLedger {
+    pub scope_id: String,
+    pub disputes: Vec<TransferDispute>,
+}
+
+impl ChargebackLedger {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            disputes: Vec::new(),
+        }
+    }
+
+    /// The dispute over a payer's journal entry, if it was flagged
+    pub fn dispute_for_entry(&self, entry_id: &str) -> Option<&TransferDispute> {
+        self.disputes.iter().find(|d| d.payer.entry_id == entry_id)
+    }
+}
+
+/// Notified when a transfer is flagged, e.g. to open a dispute resolution process
+#[async_trait]
+pub trait ChargebackHook: Send + Sync {
+    async fn chargeback_raised(&self, dispute: &TransferDispute) -> EconomicsResult<()>;
+}
+
+/// Save a paying scope's chargeback ledger
+pub async fn save_chargeback_ledger(
+    ledger: &ChargebackLedger,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(ledger)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize chargeback ledger: {}", e)))?;
+
+    let key = format!("{}{}", CHARGEBACK_KEY_PREFIX, ledger.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a paying scope's chargeback ledger, or an empty one
+pub async fn load_chargeback_ledger(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<ChargebackLedger> {
+    let key = format!("{}{}", CHARGEBACK_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize chargeback ledger: {}", e))),
+        None => Ok(ChargebackLedger::new(scope_id)),
+    }
+}
+
+/// Amounts frozen in a scope's treasury, by dispute ID
+async fn load_freezes(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<BTreeMap<String, u64>> {
+    let key = format!("{}{}", FROZEN_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize frozen amounts: {}", e))),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+async fn save_freezes(
+    scope_id: &str,
+    freezes: &BTreeMap<String, u64>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(freezes)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize frozen amounts: {}", e)))?;
+    storage.store_budget(&format!("{}{}", FROZEN_KEY_PREFIX, scope_id), data).await
+}
+
+/// Total amount frozen in a scope's treasury by open disputes
+pub async fn frozen_amount(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<u64> {
+    Ok(load_freezes(scope_id, storage).await?.values().sum())
+}
+
+/// A scope's treasury balance less what open disputes have frozen
+pub async fn spendable_treasury_balance(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<i64> {
+    let journal = statements::load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+    Ok(journal.balance() - frozen_amount(scope_id, storage).await? as i64)
+}
+
+async fn load_leg(
+    leg: &TransferLeg,
+    flow: TreasuryFlow,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<TreasuryEntry> {
+    let journal = statements::load_treasury_journal(&leg.scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", leg.scope_id)))?;
+    let entry = journal.entries.into_iter()
+        .find(|e| e.id == leg.entry_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury entry {} in {}", leg.entry_id, leg.scope_id)))?;
+    if entry.flow != flow {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Treasury entry {} of {} is not an {:?}", leg.entry_id, leg.scope_id, flow
+        )));
+    }
+    Ok(entry)
+}
+
+/// Flag an internal transfer as mistaken or fraudulent. The payer's outflow must be
+/// flagged within `window_secs` of when it happened, and each transfer can be disputed
+/// once. The contested amount is frozen in the payee's treasury until the dispute is
+/// decided. Returns the dispute ID.
+#[allow(clippy::too_many_arguments)]
+pub async fn flag_transfer(
+    payer: &TransferLeg,
+    payee: &TransferLeg,
+    amount: u64,
+    reason: &str,
+    raised_by: &str,
+    at: i64,
+    window_secs: i64,
+    hook: Option<&dyn ChargebackHook>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    if payer.scope_id == payee.scope_id {
+        return Err(EconomicsError::InvalidBudget("A transfer can't be disputed between a scope and itself".to_string()));
+    }
+    let outflow = load_leg(payer, TreasuryFlow::Outflow, storage).await?;
+    let inflow = load_leg(payee, TreasuryFlow::Inflow, storage).await?;
+    if amount == 0 || amount > outflow.amount.min(inflow.amount) {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Disputed amount must be between 1 and the transferred {}", outflow.amount.min(inflow.amount)
+        )));
+    }
+    if at - outflow.occurred_at > window_secs {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Transfer {} can no longer be disputed; the window closed at {}", payer.entry_id, outflow.occurred_at + window_secs
+        )));
+    }
+
+    let mut ledger = load_chargeback_ledger(&payer.scope_id, storage).await?;
+    if ledger.dispute_for_entry(&payer.entry_id).is_some() {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Transfer {} of {} was already disputed", payer.entry_id, payer.scope_id
+        )));
+    }
+
+    let dispute = TransferDispute {
+        id: Uuid::new_v4().to_string(),
+        payer: payer.clone(),
+        payee: payee.clone(),
+        amount,
+        reason: reason.to_string(),
+        raised_by: raised_by.to_string(),
+        raised_at: at,
+        status: ChargebackStatus::Open,
+        compensating_entries: Vec::new(),
+        trail: vec![
+            ChargebackStep { action: "flagged".to_string(), actor: raised_by.to_string(), at, detail: Some(reason.to_string()) },
+            ChargebackStep { action: "frozen".to_string(), actor: raised_by.to_string(), at, detail: Some(format!("{} in {}", amount, payee.scope_id)) },
+        ],
+        anchor_cid: None,
+        ruling_key: None,
+    };
+
+    let mut freezes = load_freezes(&payee.scope_id, storage).await?;
+    freezes.insert(dispute.id.clone(), amount);
+    save_freezes(&payee.scope_id, &freezes, storage).await?;
+
+    ledger.disputes.push(dispute.clone());
+    save_chargeback_ledger(&ledger, storage).await?;
+
+    if let Some(hook) = hook {
+        hook.chargeback_raised(&dispute).await?;
+    }
+    Ok(dispute.id)
+}
+
+/// CID of a decided dispute's JSON bytes
+pub fn chargeback_cid(dispute: &TransferDispute) -> EconomicsResult<Cid> {
+    let mut unanchored = dispute.clone();
+    unanchored.anchor_cid = None;
+    let data = serde_json::to_vec(&unanchored)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize transfer dispute: {}", e)))?;
+    let digest = Sha256::digest(&data);
+    let hash = cid::multihash::Multihash::wrap(0x12, digest.as_slice())
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to hash transfer dispute: {}", e)))?;
+    Ok(Cid::new_v1(RAW_CODEC, hash))
+}
+
+/// Carry out the ruling on an open dispute. The frozen amount is released; a reversal
+/// posts a compensating outflow from the payee and inflow to the payer. The decided
+/// dispute, with its full trail, is anchored under its CID. A retried call with the
+/// same `idempotency_key` returns the decided dispute instead of ruling again.
+pub async fn rule_on_chargeback(
+    payer_scope_id: &str,
+    dispute_id: &str,
+    ruling: ChargebackRuling,
+    ruled_by: &str,
+    idempotency_key: &str,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<TransferDispute> {
+    let request_hash = idempotency::request_fingerprint(&(dispute_id, &ruling, ruled_by))?;
+    if let Some(decided) = idempotency::replay_result(
+        payer_scope_id, idempotency_key, "rule_on_chargeback", &request_hash, at, storage,
+    ).await? {
+        return Ok(decided);
+    }
+
+    let mut ledger = load_chargeback_ledger(payer_scope_id, storage).await?;
+    let mut dispute = ledger.disputes.iter()
+        .find(|d| d.id == dispute_id)
+        .cloned()
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No transfer dispute {} in {}", dispute_id, payer_scope_id)))?;
+    if dispute.status != ChargebackStatus::Open {
+        return Err(EconomicsError::InvalidBudget(format!("Transfer dispute {} was already decided", dispute_id)));
+    }
+    if let ChargebackRuling::Reverse { amount } = ruling {
+        if amount == 0 || amount > dispute.amount {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "A reversal must be between 1 and the disputed {}", dispute.amount
+            )));
+        }
+    }
+
+    let mut freezes = load_freezes(&dispute.payee.scope_id, storage).await?;
+    freezes.remove(dispute_id);
+    save_freezes(&dispute.payee.scope_id, &freezes, storage).await?;
+    dispute.trail.push(ChargebackStep {
+        action: "released".to_string(),
+        actor: ruled_by.to_string(),
+        at,
+        detail: Some(format!("{} in {}", dispute.amount, dispute.payee.scope_id)),
+    });
+
+    if let ChargebackRuling::Reverse { amount } = ruling {
+        // References make the postings idempotent if a ruling is retried after a failure
+        let debit = statements::record_keyed_treasury_entry(
+            &dispute.payee.scope_id, TreasuryFlow::Outflow, CHARGEBACK_CATEGORY, amount, at,
+            Some(format!("chargeback:{}:debit", dispute_id)), Some(idempotency_key), storage,
+        ).await?;
+        let credit = statements::record_keyed_treasury_entry(
+            &dispute.payer.scope_id, TreasuryFlow::Inflow, CHARGEBACK_CATEGORY, amount, at,
+            Some(format!("chargeback:{}:credit", dispute_id)), Some(idempotency_key), storage,
+        ).await?;
+        dispute.compensating_entries = vec![debit, credit];
+        dispute.trail.push(ChargebackStep {
+            action: "reversed".to_string(),
+            actor: ruled_by.to_string(),
+            at,
+            detail: Some(format!("{} from {} to {}", amount, dispute.payee.scope_id, dispute.payer.scope_id)),
+        });
+    } else {
+        dispute.trail.push(ChargebackStep { action: "denied".to_string(), actor: ruled_by.to_string(), at, detail: None });
+    }
+    dispute.status = ChargebackStatus::Decided(ruling);
+    dispute.ruling_key = Some(idempotency_key.to_string());
+
+    let cid = chargeback_cid(&dispute)?;
+    let data = serde_json::to_vec(&dispute)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize transfer dispute: {}", e)))?;
+    storage.put_with_key(cid, data).await?;
+    dispute.anchor_cid = Some(cid.to_string());
+
+    if let Some(stored) = ledger.disputes.iter_mut().find(|d| d.id == dispute_id) {
+        *stored = dispute.clone();
+    }
+    save_chargeback_ledger(&ledger, storage).await?;
+    idempotency::remember_result(
+        payer_scope_id, idempotency_key, "rule_on_chargeback", &request_hash, &dispute, at, DEFAULT_DEDUP_WINDOW_SECS, storage,
+    ).await?;
+    Ok(dispute)
+}
+
+/// A decided dispute by the CID it was anchored under, checked against its hash
+pub async fn get_anchored_chargeback(
+    cid: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<TransferDispute>> {
+    let cid = Cid::try_from(cid)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Invalid chargeback CID {}: {}", cid, e)))?;
+    let data = match storage.get_by_cid(&cid).await? {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+    let mut dispute: TransferDispute = serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize transfer dispute: {}", e)))?;
+
+    if chargeback_cid(&dispute)? != cid {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Transfer dispute {} does not match its hash", cid)
+        ));
+    }
+    dispute.anchor_cid = Some(cid.to_string());
+    Ok(Some(dispute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::budget_ops::MockBudgetStorage;
+    use crate::statements::{open_treasury_journal, record_treasury_entry, load_treasury_journal};
+
+    #[derive(Default)]
+    struct RecordingHook {
+        raised: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ChargebackHook for RecordingHook {
+        async fn chargeback_raised(&self, dispute: &TransferDispute) -> EconomicsResult<()> {
+            self.raised.lock().unwrap().push(dispute.id.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mistaken_transfer_frozen_and_reversed() {
+        let mut storage = MockBudgetStorage::new();
+        open_treasury_journal("coop-1", "USD", 1_000, &mut storage).await.unwrap();
+        open_treasury_journal("coop-2", "USD", 0, &mut storage).await.unwrap();
+
+        let t0 = 1_790_000_000;
+        let out = record_treasury_entry("coop-1", TreasuryFlow::Outflow, "transfers", 400, t0, Some("xfer-1:out".into()), &mut storage).await.unwrap();
+        let inn = record_treasury_entry("coop-2", TreasuryFlow::Inflow, "transfers", 400, t0, Some("xfer-1:in".into()), &mut storage).await.unwrap();
+        let payer = TransferLeg { scope_id: "coop-1".into(), entry_id: out };
+        let payee = TransferLeg { scope_id: "coop-2".into(), entry_id: inn };
+
+        // Outside the window, for more than was sent, or with the legs swapped
+        assert!(flag_transfer(&payer, &payee, 400, "typo", "did:icn:alice", t0 + DEFAULT_DISPUTE_WINDOW_SECS + 1, DEFAULT_DISPUTE_WINDOW_SECS, None, &mut storage).await.is_err());
+        assert!(flag_transfer(&payer, &payee, 500, "typo", "did:icn:alice", t0 + 60, DEFAULT_DISPUTE_WINDOW_SECS, None, &mut storage).await.is_err());
+        assert!(flag_transfer(&payee, &payer, 400, "typo", "did:icn:alice", t0 + 60, DEFAULT_DISPUTE_WINDOW_SECS, None, &mut storage).await.is_err());
+
+        let hook = RecordingHook::default();
+        let dispute = flag_transfer(&payer, &payee, 300, "Sent to the wrong co-op", "did:icn:alice", t0 + 60, DEFAULT_DISPUTE_WINDOW_SECS, Some(&hook), &mut storage).await.unwrap();
+        assert_eq!(hook.raised.lock().unwrap().as_slice(), [dispute.clone()]);
+        assert_eq!(spendable_treasury_balance("coop-2", &storage).await.unwrap(), 100);
+        assert!(flag_transfer(&payer, &payee, 100, "again", "did:icn:alice", t0 + 90, DEFAULT_DISPUTE_WINDOW_SECS, None, &mut storage).await.is_err());
+
+        assert!(rule_on_chargeback("coop-1", &dispute, ChargebackRuling::Reverse { amount: 301 }, "did:icn:panel", "ruling-1", t0 + 120, &mut storage).await.is_err());
+        let decided = rule_on_chargeback("coop-1", &dispute, ChargebackRuling::Reverse { amount: 300 }, "did:icn:panel", "ruling-1", t0 + 120, &mut storage).await.unwrap();
+        assert_eq!(frozen_amount("coop-2", &storage).await.unwrap(), 0);
+        assert_eq!(load_treasury_journal("coop-1", &storage).await.unwrap().unwrap().balance(), 900);
+        assert_eq!(load_treasury_journal("coop-2", &storage).await.unwrap().unwrap().balance(), 100);
+        assert_eq!(decided.compensating_entries.len(), 2);
+        let journal = load_treasury_journal("coop-2", &storage).await.unwrap().unwrap();
+        let debit = journal.entries.iter().find(|e| e.id == decided.compensating_entries[0]).unwrap();
+        assert_eq!(debit.idempotency_key.as_deref(), Some("ruling-1"));
+        assert_eq!(decided.trail.iter().map(|s| s.action.as_str()).collect::<Vec<_>>(), ["flagged", "frozen", "released", "reversed"]);
+
+        let anchored = get_anchored_chargeback(decided.anchor_cid.as_ref().unwrap(), &storage).await.unwrap();
+        assert_eq!(anchored, Some(decided.clone()));
+        assert!(rule_on_chargeback("coop-1", &dispute, ChargebackRuling::Deny, "did:icn:panel", "ruling-2", t0 + 180, &mut storage).await.is_err());
+
+        // A retried ruling returns the decided dispute without reversing twice
+        let retried = rule_on_chargeback("coop-1", &dispute, ChargebackRuling::Reverse { amount: 300 }, "did:icn:panel", "ruling-1", t0 + 180, &mut storage).await.unwrap();
+        assert_eq!(retried, decided);
+        assert_eq!(load_treasury_journal("coop-1", &storage).await.unwrap().unwrap().balance(), 900);
+    }
+}
@@ -0,0 +1,501 @@
+use std::collections::{BTreeMap, HashMap};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+use crate::rounding::RoundingPolicy;
+use crate::statements::StatementPeriod;
+
+/// Storage key prefix for cost allocation ledgers
+const ALLOCATION_KEY_PREFIX: &str = "costs::ledger::";
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u64 = 10_000;
+
+/// How a shared expense is split between the member co-ops of a federation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AllocationKey {
+    /// In proportion to each co-op's member count
+    PerCapita,
+
+    /// In proportion to each co-op's metered use of a resource
+    Usage { meter: String },
+
+    /// Fixed shares agreed between the co-ops, in basis points
+    Negotiated { shares_bps: BTreeMap<String, u64> },
+}
+
+impl AllocationKey {
+    /// Build a key from its entry in the `cost_allocation` section of an economic model.
+    ///
+    /// Accepts `"per_capita"`, `"usage(meter=storage_gb)"` or
+    /// `"negotiated(did:icn:coop-a=6000, did:icn:coop-b=4000)"`.
+    pub fn from_economic_model(spec: &str) -> EconomicsResult<Self> {
+        let spec = spec.trim();
+        let (method, params) = match spec.find('(') {
+            Some(open) if spec.ends_with(')') => (spec[..open].trim(), &spec[open + 1..spec.len() - 1]),
+            Some(_) => return Err(EconomicsError::InvalidBudget(
+                format!("Malformed allocation key: {}", spec)
+            )),
+            None => (spec, ""),
+        };
+
+        let mut params_map = BTreeMap::new();
+        for param in params.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = param.rsplit_once('=').ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("Malformed allocation key parameter: {}", param.trim())
+            ))?;
+            params_map.insert(key.trim().to_string(), value.trim().to_string());
+        }
+
+        match method {
+            "per_capita" if params_map.is_empty() => Ok(AllocationKey::PerCapita),
+            "usage" => match params_map.get("meter") {
+                Some(meter) if params_map.len() == 1 && !meter.is_empty() => Ok(AllocationKey::Usage { meter: meter.clone() }),
+                _ => Err(EconomicsError::InvalidBudget(
+                    format!("Usage allocation key needs exactly one meter: {}", spec)
+                )),
+            },
+            "negotiated" => {
+                let mut shares_bps = BTreeMap::new();
+                for (member, value) in params_map {
+                    let share = value.parse::<u64>().map_err(|_| EconomicsError::InvalidBudget(
+                        format!("Invalid negotiated share for {}: {}", member, value)
+                    ))?;
+                    shares_bps.insert(member, share);
+                }
+                let total: u64 = shares_bps.values().sum();
+                if total != BASIS_POINTS {
+                    return Err(EconomicsError::InvalidBudget(
+                        format!("Negotiated shares add up to {} bps instead of 10000", total)
+                    ));
+                }
+                Ok(AllocationKey::Negotiated { shares_bps })
+            }
+            other => Err(EconomicsError::InvalidBudget(
+                format!("Unknown allocation key: {}", other)
+            )),
+        }
+    }
+
+    /// Each participant's weight under this key, in the order of `participants`
+    pub fn weights(&self, participants: &[String], inputs: &AllocationInputs) -> EconomicsResult<Vec<u64>> {
+        let weights = match self {
+            AllocationKey::PerCapita => participants.iter()
+                .map(|p| inputs.member_counts.get(p).copied().unwrap_or(0))
+                .collect(),
+            AllocationKey::Usage { meter } => {
+                let readings = inputs.usage.get(meter).ok_or_else(|| EconomicsError::InvalidBudget(
+                    format!("No metering readings for {}", meter)
+                ))?;
+                participants.iter()
+                    .map(|p| readings.get(p).copied().unwrap_or(0))
+                    .collect()
+            }
+            AllocationKey::Negotiated { shares_bps } => {
+                if let Some(outsider) = shares_bps.keys().find(|m| !participants.contains(m)) {
+                    return Err(EconomicsError::InvalidBudget(
+                        format!("Negotiated share for {}, which is not a participant", outsider)
+                    ));
+                }
+                participants.iter()
+                    .map(|p| shares_bps.get(p).copied().unwrap_or(0))
+                    .collect()
+            }
+        };
+        Ok(weights)
+    }
+}
+
+/// What per-capita and usage-based keys are computed from for a period
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AllocationInputs {
+    /// Members of each co-op
+    pub member_counts: HashMap<String, u64>,
+
+    /// Metered units per meter, per co-op
+    pub usage: HashMap<String, HashMap<String, u64>>,
+}
+
+/// Split an amount in proportion to `weights`. Shares are rounded down and the
+/// remainder goes to the largest fractions, earlier participants first, so the shares
+/// always add up to the amount.
+pub fn split_amount(amount: u64, weights: &[u64]) -> EconomicsResult<Vec<u64>> {
+    RoundingPolicy::default().split(amount, weights).map(|split| split.shares)
+}
+
+/// A federation-level expense to split between the member co-ops
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedExpense {
+    pub id: String,
+    pub description: String,
+    pub amount: u64,
+
+    /// Name of the allocation key the expense is split by
+    pub key: String,
+}
+
+/// A co-op's share of one expense
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostShareLine {
+    pub expense_id: String,
+    pub description: String,
+    pub key: String,
+    pub amount: u64,
+}
+
+/// Where a co-op's invoice stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostInvoiceStatus {
+    Issued,
+    /// The co-op contested its share; the dispute is open
+    Disputed,
+    /// A dispute was decided
+    Resolved,
+}
+
+/// A co-op's share of a period's shared expenses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostInvoice {
+    pub id: String,
+
+    /// The co-op billed
+    pub member: String,
+
+    pub period: StatementPeriod,
+    pub lines: Vec<CostShareLine>,
+
+    /// Amount owed after any dispute adjustment
+    pub total: u64,
+
+    pub status: CostInvoiceStatus,
+}
+
+/// How a dispute was decided
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostDisputeOutcome {
+    /// The share stands
+    Upheld,
+    /// The co-op owes the given total instead
+    Adjusted { total: u64 },
+}
+
+/// A co-op contesting its share
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostDispute {
+    pub id: String,
+    pub invoice_id: String,
+    pub raised_by: String,
+    pub reason: String,
+    pub raised_at: i64,
+    pub outcome: Option<CostDisputeOutcome>,
+    pub decided_at: Option<i64>,
+}
+
+/// What rounding left of an expense once it was split, carried by the federation's
+/// reserve rather than billed to any co-op
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostResidue {
+    pub expense_id: String,
+    pub period: StatementPeriod,
+    pub amount: u64,
+}
+
+/// Shared cost invoices and disputes of a federation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostAllocationLedger {
+    pub scope_id: String,
+    pub invoices: Vec<CostInvoice>,
+    pub disputes: Vec<CostDispute>,
+
+    /// Rounding residue of each split that left one
+    #[serde(default)]
+    pub residues: Vec<CostResidue>,
+}
+
+impl CostAllocationLedger {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            invoices: Vec::new(),
+            disputes: Vec::new(),
+            residues: Vec::new(),
+        }
+    }
+
+    /// Invoices billed for a period
+    pub fn invoices_for(&self, period: StatementPeriod) -> Vec<&CostInvoice> {
+        self.invoices.iter().filter(|i| i.period == period).collect()
+    }
+
+    fn invoice_mut(&mut self, invoice_id: &str) -> EconomicsResult<&mut CostInvoice> {
+        let scope_id = self.scope_id.clone();
+        self.invoices.iter_mut()
+            .find(|i| i.id == invoice_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(format!("No cost invoice {} in {}", invoice_id, scope_id)))
+    }
+}
+
+/// Notified when a co-op contests its share, e.g. to open a dispute resolution process
+#[async_trait]
+pub trait CostDisputeHook: Send + Sync {
+    async fn dispute_raised(&self, invoice: &CostInvoice, dispute: &CostDispute) -> EconomicsResult<()>;
+}
+
+/// Save a federation's cost allocation ledger
+pub async fn save_cost_allocation_ledger(
+    ledger: &CostAllocationLedger,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(ledger)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize cost allocation ledger: {}", e)))?;
+
+    let key = format!("{}{}", ALLOCATION_KEY_PREFIX, ledger.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a federation's cost allocation ledger, or an empty one
+pub async fn load_cost_allocation_ledger(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<CostAllocationLedger> {
+    let key = format!("{}{}", ALLOCATION_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize cost allocation ledger: {}", e))),
+        None => Ok(CostAllocationLedger::new(scope_id)),
+    }
+}
+
+/// Split a period's shared expenses between the participating co-ops by their
+/// allocation keys and bill each co-op one invoice. A period is allocated once.
+///
+/// Shares are rounded under `rounding`; any residue it leaves is recorded on the ledger,
+/// so the invoices and residues of a period always add up to its expenses.
+#[allow(clippy::too_many_arguments)]
+pub async fn allocate_shared_costs(
+    scope_id: &str,
+    period: StatementPeriod,
+    expenses: &[SharedExpense],
+    keys: &HashMap<String, AllocationKey>,
+    participants: &[String],
+    inputs: &AllocationInputs,
+    rounding: &RoundingPolicy,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<Vec<CostInvoice>> {
+    let mut ledger = load_cost_allocation_ledger(scope_id, storage).await?;
+    if !ledger.invoices_for(period).is_empty() {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Shared costs for {} were already allocated in {}", period, scope_id)
+        ));
+    }
+    if participants.is_empty() {
+        return Err(EconomicsError::InvalidBudget("Shared costs need at least one participating co-op".to_string()));
+    }
+
+    let mut lines: Vec<Vec<CostShareLine>> = vec![Vec::new(); participants.len()];
+    for expense in expenses {
+        let key = keys.get(&expense.key).ok_or_else(|| EconomicsError::InvalidBudget(
+            format!("Expense {} uses unknown allocation key {}", expense.id, expense.key)
+        ))?;
+        let split = rounding.split(expense.amount, &key.weights(participants, inputs)?)?;
+        if split.residue > 0 {
+            ledger.residues.push(CostResidue { expense_id: expense.id.clone(), period, amount: split.residue });
+        }
+        for (i, amount) in split.shares.into_iter().enumerate() {
+            lines[i].push(CostShareLine {
+                expense_id: expense.id.clone(),
+                description: expense.description.clone(),
+                key: expense.key.clone(),
+                amount,
+            });
+        }
+    }
+
+    let invoices: Vec<CostInvoice> = participants.iter().zip(lines)
+        .map(|(member, lines)| CostInvoice {
+            id: Uuid::new_v4().to_string(),
+            member: member.clone(),
+            period,
+            total: lines.iter().map(|l| l.amount).sum(),
+            lines,
+            status: CostInvoiceStatus::Issued,
+        })
+        .collect();
+
+    ledger.invoices.extend(invoices.iter().cloned());
+    save_cost_allocation_ledger(&ledger, storage).await?;
+    Ok(invoices)
+}
+
+/// Contest a co-op's share. Only the billed co-op can contest, and only one dispute per
+/// invoice may be open. Returns the dispute ID.
+pub async fn dispute_cost_share(
+    scope_id: &str,
+    invoice_id: &str,
+    member: &str,
+    reason: &str,
+    at: i64,
+    hook: Option<&dyn CostDisputeHook>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut ledger = load_cost_allocation_ledger(scope_id, storage).await?;
+    let invoice = ledger.invoice_mut(invoice_id)?;
+    if invoice.member != member {
+        return Err(EconomicsError::Unauthorized(
+            format!("Cost invoice {} was not billed to {}", invoice_id, member)
+        ));
+    }
+    if invoice.status == CostInvoiceStatus::Disputed {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Cost invoice {} already has an open dispute", invoice_id)
+        ));
+    }
+
+    invoice.status = CostInvoiceStatus::Disputed;
+    let invoice = invoice.clone();
+    let dispute = CostDispute {
+        id: Uuid::new_v4().to_string(),
+        invoice_id: invoice_id.to_string(),
+        raised_by: member.to_string(),
+        reason: reason.to_string(),
+        raised_at: at,
+        outcome: None,
+        decided_at: None,
+    };
+    ledger.disputes.push(dispute.clone());
+    save_cost_allocation_ledger(&ledger, storage).await?;
+
+    if let Some(hook) = hook {
+        hook.dispute_raised(&invoice, &dispute).await?;
+    }
+    Ok(dispute.id)
+}
+
+/// Decide an open dispute. An adjustment replaces what the co-op owes.
+pub async fn resolve_cost_dispute(
+    scope_id: &str,
+    dispute_id: &str,
+    outcome: CostDisputeOutcome,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<CostInvoice> {
+    let mut ledger = load_cost_allocation_ledger(scope_id, storage).await?;
+    let dispute = ledger.disputes.iter_mut()
+        .find(|d| d.id == dispute_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No cost dispute {} in {}", dispute_id, scope_id)))?;
+    if dispute.outcome.is_some() {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Cost dispute {} was already decided", dispute_id)
+        ));
+    }
+
+    dispute.outcome = Some(outcome.clone());
+    dispute.decided_at = Some(at);
+    let invoice_id = dispute.invoice_id.clone();
+
+    let invoice = ledger.invoice_mut(&invoice_id)?;
+    if let CostDisputeOutcome::Adjusted { total } = outcome {
+        invoice.total = total;
+    }
+    invoice.status = CostInvoiceStatus::Resolved;
+    let invoice = invoice.clone();
+
+    save_cost_allocation_ledger(&ledger, storage).await?;
+    Ok(invoice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::budget_ops::MockBudgetStorage;
+
+    #[derive(Default)]
+    struct RecordingHook {
+        raised: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl CostDisputeHook for RecordingHook {
+        async fn dispute_raised(&self, invoice: &CostInvoice, _dispute: &CostDispute) -> EconomicsResult<()> {
+            self.raised.lock().unwrap().push(invoice.member.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_costs_split_by_key_and_contested() {
+        let mut storage = MockBudgetStorage::new();
+        let coops = vec!["did:icn:bakery".to_string(), "did:icn:press".to_string(), "did:icn:farm".to_string()];
+
+        let mut keys = HashMap::new();
+        keys.insert("insurance".to_string(), AllocationKey::from_economic_model("per_capita").unwrap());
+        keys.insert("hosting".to_string(), AllocationKey::from_economic_model("usage(meter=storage_gb)").unwrap());
+        keys.insert("legal".to_string(), AllocationKey::from_economic_model(
+            "negotiated(did:icn:bakery=5000, did:icn:press=3000, did:icn:farm=2000)"
+        ).unwrap());
+        assert!(AllocationKey::from_economic_model("negotiated(did:icn:bakery=6000)").is_err());
+
+        let inputs = AllocationInputs {
+            member_counts: coops.iter().map(|c| (c.clone(), 1)).collect(),
+            usage: HashMap::from([("storage_gb".to_string(), HashMap::from([
+                ("did:icn:bakery".to_string(), 10),
+                ("did:icn:press".to_string(), 30),
+            ]))]),
+        };
+
+        let expenses = vec![
+            SharedExpense { id: "ins".into(), description: "Liability insurance".into(), amount: 100, key: "insurance".into() },
+            SharedExpense { id: "host".into(), description: "Hosting".into(), amount: 400, key: "hosting".into() },
+            SharedExpense { id: "law".into(), description: "Legal retainer".into(), amount: 1_000, key: "legal".into() },
+        ];
+        let period = StatementPeriod::new(2026, 9).unwrap();
+        let invoices = allocate_shared_costs("fed-1", period, &expenses, &keys, &coops, &inputs, &RoundingPolicy::default(), &mut storage).await.unwrap();
+
+        // 100 split three ways leaves one unit for the first co-op
+        assert_eq!(invoices[0].lines[0].amount, 34);
+        assert_eq!(invoices[0].total, 34 + 100 + 500);
+        assert_eq!(invoices[1].total, 33 + 300 + 300);
+        // The farm stores nothing, so it pays no hosting
+        assert_eq!(invoices[2].lines[1].amount, 0);
+        assert_eq!(invoices[2].total, 33 + 200);
+        assert_eq!(invoices.iter().map(|i| i.total).sum::<u64>(), 1_500);
+        assert!(allocate_shared_costs("fed-1", period, &expenses, &keys, &coops, &inputs, &RoundingPolicy::default(), &mut storage).await.is_err());
+
+        let hook = RecordingHook::default();
+        let press_invoice = &invoices[1].id;
+        assert!(dispute_cost_share("fed-1", press_invoice, "did:icn:farm", "Not ours", 1, Some(&hook), &mut storage).await.is_err());
+        let dispute = dispute_cost_share("fed-1", press_invoice, "did:icn:press", "Meter double-counted backups", 1, Some(&hook), &mut storage).await.unwrap();
+        assert_eq!(hook.raised.lock().unwrap().as_slice(), ["did:icn:press".to_string()]);
+
+        let resolved = resolve_cost_dispute("fed-1", &dispute, CostDisputeOutcome::Adjusted { total: 500 }, 2, &mut storage).await.unwrap();
+        assert_eq!(resolved.total, 500);
+        assert_eq!(resolved.status, CostInvoiceStatus::Resolved);
+        assert!(resolve_cost_dispute("fed-1", &dispute, CostDisputeOutcome::Upheld, 3, &mut storage).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rounding_residue_is_carried_by_the_reserve() {
+        let mut storage = MockBudgetStorage::new();
+        let coops = vec!["did:icn:bakery".to_string(), "did:icn:press".to_string(), "did:icn:farm".to_string()];
+        let keys = HashMap::from([("insurance".to_string(), AllocationKey::PerCapita)]);
+        let inputs = AllocationInputs {
+            member_counts: coops.iter().map(|c| (c.clone(), 1)).collect(),
+            ..Default::default()
+        };
+        let expenses = vec![
+            SharedExpense { id: "ins".into(), description: "Liability insurance".into(), amount: 1_000, key: "insurance".into() },
+        ];
+        let rounding = RoundingPolicy::from_economic_model("half_even(unit=10, residue=reserve)").unwrap();
+        let period = StatementPeriod::new(2026, 9).unwrap();
+
+        let invoices = allocate_shared_costs("fed-1", period, &expenses, &keys, &coops, &inputs, &rounding, &mut storage).await.unwrap();
+        assert!(invoices.iter().all(|i| i.total == 330));
+
+        let ledger = load_cost_allocation_ledger("fed-1", &storage).await.unwrap();
+        assert_eq!(ledger.residues, vec![CostResidue { expense_id: "ins".into(), period, amount: 10 }]);
+        assert_eq!(invoices.iter().map(|i| i.total).sum::<u64>() + ledger.residues[0].amount, 1_000);
+    }
+}
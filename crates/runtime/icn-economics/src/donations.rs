@@ -0,0 +1,479 @@
+use std::collections::BTreeMap;
+use cid::Cid;
+use uuid::Uuid;
+use sha2::{Sha256, Digest};
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+
+/// Storage key prefix for donation ledgers
+const DONATION_KEY_PREFIX: &str = "donations::ledger::";
+
+/// Raw multicodec, for receipts anchored by the hash of their bytes
+const RAW_CODEC: u64 = 0x55;
+
+/// A fund of a scope that accepts donations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DonationFund {
+    pub fund_id: String,
+    pub name: String,
+
+    /// Currency donations to this fund are made in (e.g., "USD")
+    pub currency: String,
+
+    /// Purpose donations to this fund are restricted to, for restricted funds
+    pub restricted_purpose: Option<String>,
+
+    pub accepting: bool,
+}
+
+/// Who made a donation. External contributors don't need a DID; every field is optional
+/// and anonymous donors are kept out of per-donor reporting.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contributor {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub organization: Option<String>,
+
+    /// Tax identifier, for funders who need a receipt they can file
+    pub tax_id: Option<String>,
+
+    /// DID, when the contributor has one
+    pub did: Option<String>,
+
+    #[serde(default)]
+    pub anonymous: bool,
+}
+
+impl Contributor {
+    /// Key donations of the same donor are grouped under in reports; None for
+    /// anonymous or unidentified donors
+    fn donor_key(&self) -> Option<String> {
+        if self.anonymous {
+            return None;
+        }
+        self.did.clone()
+            .or_else(|| self.tax_id.clone())
+            .or_else(|| self.email.as_ref().map(|e| e.trim().to_lowercase()))
+            .or_else(|| self.organization.clone())
+            .or_else(|| self.name.clone())
+    }
+
+    /// Name shown on the donor's receipt
+    fn display_name(&self) -> String {
+        if self.anonymous {
+            return "Anonymous".to_string();
+        }
+        self.organization.clone()
+            .or_else(|| self.name.clone())
+            .unwrap_or_else(|| "Anonymous".to_string())
+    }
+}
+
+/// A donation as submitted through the gateway's donation endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DonationIntake {
+    pub scope_id: String,
+    pub fund_id: String,
+    pub amount: u64,
+    pub currency: String,
+    pub contributor: Contributor,
+
+    /// Reference of the payment at the processor; resubmitting the same reference
+    /// returns the original acknowledgement
+    pub payment_reference: String,
+
+    /// When the payment was received (Unix timestamp)
+    pub received_at: i64,
+}
+
+/// A recorded donation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Donation {
+    pub id: String,
+    pub fund_id: String,
+    pub amount: u64,
+    pub currency: String,
+    pub contributor: Contributor,
+    pub payment_reference: String,
+    pub received_at: i64,
+    pub receipt_cid: String,
+}
+
+/// Acknowledgement receipt of a donation. Its CID is the hash of its JSON bytes, so a
+/// donor or auditor holding the receipt can check it against the scope's records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DonationReceipt {
+    pub donation_id: String,
+    pub scope_id: String,
+    pub fund_id: String,
+    pub fund_name: String,
+    pub amount: u64,
+    pub currency: String,
+    pub donor: String,
+    pub donor_tax_id: Option<String>,
+    pub restricted_purpose: Option<String>,
+    pub received_at: i64,
+    pub issued_at: i64,
+}
+
+/// What the gateway returns to the contributor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DonationAcknowledgement {
+    pub donation_id: String,
+    pub receipt_cid: String,
+    pub receipt: DonationReceipt,
+}
+
+/// Funds and donations of a scope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DonationLedger {
+    pub scope_id: String,
+    pub funds: Vec<DonationFund>,
+    pub donations: Vec<Donation>,
+}
+
+impl DonationLedger {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            funds: Vec::new(),
+            donations: Vec::new(),
+        }
+    }
+
+    fn fund(&self, fund_id: &str) -> EconomicsResult<&DonationFund> {
+        self.funds.iter()
+            .find(|f| f.fund_id == fund_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("No donation fund {} in {}", fund_id, self.scope_id)
+            ))
+    }
+
+    /// Total donated to a fund
+    pub fn fund_total(&self, fund_id: &str) -> u64 {
+        self.donations.iter()
+            .filter(|d| d.fund_id == fund_id)
+            .map(|d| d.amount)
+            .sum()
+    }
+}
+
+/// One identified donor's giving in a reporting period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DonorSummary {
+    pub donor: String,
+    pub tax_id: Option<String>,
+    pub total_by_fund: BTreeMap<String, u64>,
+    pub receipt_cids: Vec<String>,
+}
+
+/// Donations of a period, for tax and funder compliance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DonationReport {
+    pub scope_id: String,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub total_by_fund: BTreeMap<String, u64>,
+    pub donation_count: usize,
+    pub donors: Vec<DonorSummary>,
+
+    /// Given anonymously or without identifying details
+    pub unidentified_total: u64,
+
+    /// Receipt CIDs of every donation in the period
+    pub receipt_cids: Vec<String>,
+}
+
+/// Store a donation ledger
+pub async fn save_donation_ledger(
+    ledger: &DonationLedger,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(ledger)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize donation ledger: {}", e)))?;
+
+    let key = format!("{}{}", DONATION_KEY_PREFIX, ledger.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's donation ledger; scopes without funds get an empty ledger
+pub async fn load_donation_ledger(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<DonationLedger> {
+    let key = format!("{}{}", DONATION_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize donation ledger: {}", e))),
+        None => Ok(DonationLedger::new(scope_id)),
+    }
+}
+
+/// Open a fund for donations
+pub async fn open_donation_fund(
+    scope_id: &str,
+    fund: DonationFund,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let mut ledger = load_donation_ledger(scope_id, storage).await?;
+    if ledger.funds.iter().any(|f| f.fund_id == fund.fund_id) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Donation fund {} already exists in {}", fund.fund_id, scope_id)
+        ));
+    }
+
+    ledger.funds.push(fund);
+    save_donation_ledger(&ledger, storage).await
+}
+
+/// Stop or resume accepting donations to a fund
+pub async fn set_fund_accepting(
+    scope_id: &str,
+    fund_id: &str,
+    accepting: bool,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let mut ledger = load_donation_ledger(scope_id, storage).await?;
+    ledger.fund(fund_id)?;
+    if let Some(fund) = ledger.funds.iter_mut().find(|f| f.fund_id == fund_id) {
+        fund.accepting = accepting;
+    }
+    save_donation_ledger(&ledger, storage).await
+}
+
+/// CID of a receipt's JSON bytes
+pub fn receipt_cid(receipt: &DonationReceipt) -> EconomicsResult<Cid> {
+    let data = serde_json::to_vec(receipt)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize donation receipt: {}", e)))?;
+    let digest = Sha256::digest(&data);
+    let hash = cid::multihash::Multihash::wrap(0x12, digest.as_slice())
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to hash donation receipt: {}", e)))?;
+    Ok(Cid::new_v1(RAW_CODEC, hash))
+}
+
+/// Record a donation from the gateway and issue its acknowledgement receipt.
+///
+/// The receipt is stored under the CID of its bytes before the donation is recorded, so
+/// every recorded donation has an anchored receipt. Resubmitting a payment reference
+/// (e.g., a gateway retry) returns the original acknowledgement.
+pub async fn receive_donation(
+    intake: DonationIntake,
+    issued_at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<DonationAcknowledgement> {
+    let mut ledger = load_donation_ledger(&intake.scope_id, storage).await?;
+
+    if let Some(existing) = ledger.donations.iter().find(|d| d.payment_reference == intake.payment_reference) {
+        let cid = Cid::try_from(existing.receipt_cid.as_str())
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Invalid receipt CID: {}", e)))?;
+        let receipt = load_donation_receipt(&cid, storage).await?;
+        return Ok(DonationAcknowledgement {
+            donation_id: existing.id.clone(),
+            receipt_cid: existing.receipt_cid.clone(),
+            receipt,
+        });
+    }
+
+    if intake.amount == 0 {
+        return Err(EconomicsError::InvalidBudget("Donation amount must be positive".to_string()));
+    }
+    let fund = ledger.fund(&intake.fund_id)?.clone();
+    if !fund.accepting {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Donation fund {} is not accepting donations", fund.fund_id)
+        ));
+    }
+    if fund.currency != intake.currency {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Donation fund {} accepts {}, not {}", fund.fund_id, fund.currency, intake.currency)
+        ));
+    }
+
+    let donation_id = Uuid::new_v4().to_string();
+    let receipt = DonationReceipt {
+        donation_id: donation_id.clone(),
+        scope_id: intake.scope_id.clone(),
+        fund_id: fund.fund_id.clone(),
+        fund_name: fund.name.clone(),
+        amount: intake.amount,
+        currency: intake.currency.clone(),
+        donor: intake.contributor.display_name(),
+        donor_tax_id: if intake.contributor.anonymous { None } else { intake.contributor.tax_id.clone() },
+        restricted_purpose: fund.restricted_purpose.clone(),
+        received_at: intake.received_at,
+        issued_at,
+    };
+
+    let cid = receipt_cid(&receipt)?;
+    let data = serde_json::to_vec(&receipt)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize donation receipt: {}", e)))?;
+    storage.put_with_key(cid, data).await?;
+
+    ledger.donations.push(Donation {
+        id: donation_id.clone(),
+        fund_id: fund.fund_id,
+        amount: intake.amount,
+        currency: intake.currency,
+        contributor: intake.contributor,
+        payment_reference: intake.payment_reference,
+        received_at: intake.received_at,
+        receipt_cid: cid.to_string(),
+    });
+    save_donation_ledger(&ledger, storage).await?;
+
+    Ok(DonationAcknowledgement {
+        donation_id,
+        receipt_cid: cid.to_string(),
+        receipt,
+    })
+}
+
+/// Load an anchored receipt, checking that it still hashes to its CID
+pub async fn load_donation_receipt(
+    cid: &Cid,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<DonationReceipt> {
+    let data = storage.get_by_cid(cid).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No donation receipt {}", cid)))?;
+    let receipt: DonationReceipt = serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize donation receipt: {}", e)))?;
+
+    if receipt_cid(&receipt)? != *cid {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Donation receipt {} does not match its hash", cid)
+        ));
+    }
+    Ok(receipt)
+}
+
+/// Report the donations received in `[period_start, period_end)`
+pub async fn donation_report(
+    scope_id: &str,
+    period_start: i64,
+    period_end: i64,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<DonationReport> {
+    let ledger = load_donation_ledger(scope_id, storage).await?;
+
+    let mut total_by_fund = BTreeMap::new();
+    let mut donors: BTreeMap<String, DonorSummary> = BTreeMap::new();
+    let mut unidentified_total = 0;
+    let mut receipt_cids = Vec::new();
+    let mut donation_count = 0;
+
+    for donation in ledger.donations.iter()
+        .filter(|d| d.received_at >= period_start && d.received_at < period_end)
+    {
+        donation_count += 1;
+        *total_by_fund.entry(donation.fund_id.clone()).or_insert(0) += donation.amount;
+        receipt_cids.push(donation.receipt_cid.clone());
+
+        match donation.contributor.donor_key() {
+            Some(key) => {
+                let summary = donors.entry(key).or_insert_with(|| DonorSummary {
+                    donor: donation.contributor.display_name(),
+                    tax_id: donation.contributor.tax_id.clone(),
+                    total_by_fund: BTreeMap::new(),
+                    receipt_cids: Vec::new(),
+                });
+                *summary.total_by_fund.entry(donation.fund_id.clone()).or_insert(0) += donation.amount;
+                summary.receipt_cids.push(donation.receipt_cid.clone());
+            }
+            None => unidentified_total += donation.amount,
+        }
+    }
+
+    Ok(DonationReport {
+        scope_id: scope_id.to_string(),
+        period_start,
+        period_end,
+        total_by_fund,
+        donation_count,
+        donors: donors.into_values().collect(),
+        unidentified_total,
+        receipt_cids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    const SCOPE: &str = "did:icn:coop";
+
+    fn fund() -> DonationFund {
+        DonationFund {
+            fund_id: "solidarity".to_string(),
+            name: "Solidarity Fund".to_string(),
+            currency: "USD".to_string(),
+            restricted_purpose: Some("Member hardship grants".to_string()),
+            accepting: true,
+        }
+    }
+
+    fn intake(reference: &str, amount: u64, contributor: Contributor) -> DonationIntake {
+        DonationIntake {
+            scope_id: SCOPE.to_string(),
+            fund_id: "solidarity".to_string(),
+            amount,
+            currency: "USD".to_string(),
+            contributor,
+            payment_reference: reference.to_string(),
+            received_at: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_donation_is_recorded_with_anchored_receipt() {
+        let mut storage = MockBudgetStorage::new();
+        open_donation_fund(SCOPE, fund(), &mut storage).await.unwrap();
+
+        let donor = Contributor {
+            name: Some("Dana".to_string()),
+            email: Some("dana@example.org".to_string()),
+            tax_id: Some("12-3456789".to_string()),
+            ..Default::default()
+        };
+        let ack = receive_donation(intake("pay-1", 250, donor.clone()), 110, &mut storage).await.unwrap();
+        assert_eq!(ack.receipt.donor, "Dana");
+        assert_eq!(ack.receipt_cid, receipt_cid(&ack.receipt).unwrap().to_string());
+
+        let cid = Cid::try_from(ack.receipt_cid.as_str()).unwrap();
+        assert_eq!(load_donation_receipt(&cid, &storage).await.unwrap(), ack.receipt);
+
+        // A gateway retry doesn't record the donation twice
+        let retry = receive_donation(intake("pay-1", 250, donor), 120, &mut storage).await.unwrap();
+        assert_eq!(retry, ack);
+        assert_eq!(load_donation_ledger(SCOPE, &storage).await.unwrap().fund_total("solidarity"), 250);
+
+        let mut wrong_currency = intake("pay-2", 10, Contributor::default());
+        wrong_currency.currency = "EUR".to_string();
+        assert!(receive_donation(wrong_currency, 110, &mut storage).await.is_err());
+
+        set_fund_accepting(SCOPE, "solidarity", false, &mut storage).await.unwrap();
+        assert!(receive_donation(intake("pay-3", 10, Contributor::default()), 110, &mut storage).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_groups_identified_donors() {
+        let mut storage = MockBudgetStorage::new();
+        open_donation_fund(SCOPE, fund(), &mut storage).await.unwrap();
+
+        let donor = |email: &str| Contributor { email: Some(email.to_string()), name: Some(email.to_string()), ..Default::default() };
+        receive_donation(intake("pay-1", 100, donor("ana@example.org")), 0, &mut storage).await.unwrap();
+        receive_donation(intake("pay-2", 50, donor("ANA@example.org")), 0, &mut storage).await.unwrap();
+        receive_donation(intake("pay-3", 30, Contributor { anonymous: true, ..donor("ben@example.org") }), 0, &mut storage).await.unwrap();
+
+        let report = donation_report(SCOPE, 0, 1_000, &storage).await.unwrap();
+        assert_eq!(report.donation_count, 3);
+        assert_eq!(report.total_by_fund.get("solidarity"), Some(&180));
+        assert_eq!(report.donors.len(), 1);
+        assert_eq!(report.donors[0].total_by_fund.get("solidarity"), Some(&150));
+        assert_eq!(report.unidentified_total, 30);
+        assert_eq!(report.receipt_cids.len(), 3);
+
+        assert_eq!(donation_report(SCOPE, 1_000, 2_000, &storage).await.unwrap().donation_count, 0);
+    }
+}
@@ -0,0 +1,535 @@
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+use crate::rounding::RoundingPolicy;
+
+/// Storage key prefix for dues ledgers
+const DUES_KEY_PREFIX: &str = "dues::ledger::";
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u128 = 10_000;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Late-payment rules from the `late_policy` of a scope's membership dues
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuesPolicy {
+    /// Days after the due date before a payment counts as late
+    pub grace_period_days: u64,
+
+    /// Flat fee assessed on a late invoice
+    pub late_fee: u64,
+
+    /// Fee assessed on a late invoice as a share of the amount owed, in basis points
+    pub late_fee_bps: u64,
+
+    /// Days after the grace period before a member with a late invoice loses their
+    /// vote. Late members keep voting if unset.
+    pub suspend_voting_after_days: Option<u64>,
+
+    /// Approvals a hardship waiver needs
+    pub waiver_approvals: usize,
+
+    /// How the proportional part of a late fee is rounded
+    #[serde(default)]
+    pub rounding: RoundingPolicy,
+}
+
+impl Default for DuesPolicy {
+    fn default() -> Self {
+        Self {
+            grace_period_days: 0,
+            late_fee: 0,
+            late_fee_bps: 0,
+            suspend_voting_after_days: None,
+            waiver_approvals: 1,
+            rounding: RoundingPolicy::default(),
+        }
+    }
+}
+
+impl DuesPolicy {
+    /// Build a policy from the fields of a membership dues late policy
+    pub fn from_membership_dues(
+        grace_period_days: Option<u64>,
+        late_fee: Option<u64>,
+        late_fee_bps: Option<u64>,
+        suspend_voting_after_days: Option<u64>,
+        waiver_approvals: Option<u64>,
+    ) -> EconomicsResult<Self> {
+        let late_fee_bps = late_fee_bps.unwrap_or(0);
+        if late_fee_bps as u128 > BASIS_POINTS {
+            return Err(EconomicsError::InvalidBudget(
+                format!("Late fee of {} bps is above 100% of the dues owed", late_fee_bps)
+            ));
+        }
+        if waiver_approvals == Some(0) {
+            return Err(EconomicsError::InvalidBudget(
+                "Hardship waivers need at least one approval".to_string()
+            ));
+        }
+
+        Ok(Self {
+            grace_period_days: grace_period_days.unwrap_or(0),
+            late_fee: late_fee.unwrap_or(0),
+            late_fee_bps,
+            suspend_voting_after_days,
+            waiver_approvals: waiver_approvals.unwrap_or(1) as usize,
+            rounding: RoundingPolicy::default(),
+        })
+    }
+
+    /// Round late fees under the economic model's rounding policy
+    pub fn with_rounding(mut self, rounding: RoundingPolicy) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// When an invoice due at `due_at` becomes late
+    pub fn late_from(&self, due_at: i64) -> i64 {
+        due_at.saturating_add((self.grace_period_days as i64).saturating_mul(SECONDS_PER_DAY))
+    }
+
+    /// Fee for a late invoice with `outstanding` left to pay
+    pub fn fee_for(&self, outstanding: u64) -> u64 {
+        let proportional = self.rounding.apply_bps(outstanding, self.late_fee_bps);
+        self.late_fee.saturating_add(proportional)
+    }
+}
+
+/// Dues billed to a member for one period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuesInvoice {
+    pub id: String,
+    pub member: String,
+
+    /// Billing period label (e.g., "2025-03")
+    pub period: String,
+    pub amount: u64,
+
+    /// When the dues are due (Unix timestamp)
+    pub due_at: i64,
+    pub paid: u64,
+
+    /// Late fee assessed on the invoice, if any
+    pub late_fee: u64,
+    pub fee_assessed_at: Option<i64>,
+
+    /// Whether an approved hardship waiver forgave what was left to pay
+    pub waived: bool,
+}
+
+impl DuesInvoice {
+    /// Amount still owed, including any late fee
+    pub fn outstanding(&self) -> u64 {
+        if self.waived {
+            return 0;
+        }
+        self.amount.saturating_add(self.late_fee).saturating_sub(self.paid)
+    }
+}
+
+/// Where a hardship waiver is in its approval flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WaiverStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A member's request to have an invoice forgiven
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaiverRequest {
+    pub id: String,
+    pub invoice_id: String,
+    pub member: String,
+    pub reason: String,
+    pub requested_at: i64,
+    pub approvals: Vec<String>,
+    pub status: WaiverStatus,
+
+    /// Reviewer who rejected the request, if it was rejected
+    pub rejected_by: Option<String>,
+    pub decided_at: Option<i64>,
+}
+
+/// A member's dues standing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DuesStanding {
+    /// Nothing overdue
+    Good,
+    /// Overdue, but within the grace period
+    Grace,
+    /// Past the grace period
+    Late,
+    /// Late long enough that the member's vote is suspended
+    Suspended,
+}
+
+impl DuesStanding {
+    /// Whether a member in this standing may vote
+    pub fn can_vote(&self) -> bool {
+        *self != DuesStanding::Suspended
+    }
+}
+
+/// Invoices and waiver requests of a scope's members
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuesLedger {
+    pub scope_id: String,
+    pub invoices: Vec<DuesInvoice>,
+    pub waivers: Vec<WaiverRequest>,
+}
+
+impl DuesLedger {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            invoices: Vec::new(),
+            waivers: Vec::new(),
+        }
+    }
+
+    fn invoice_mut(&mut self, invoice_id: &str) -> EconomicsResult<&mut DuesInvoice> {
+        let scope_id = self.scope_id.clone();
+        self.invoices.iter_mut()
+            .find(|i| i.id == invoice_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("No dues invoice {} in {}", invoice_id, scope_id)
+            ))
+    }
+
+    fn has_pending_waiver(&self, invoice_id: &str) -> bool {
+        self.waivers.iter().any(|w| w.invoice_id == invoice_id && w.status == WaiverStatus::Pending)
+    }
+
+    /// A member's standing at `now`: the worst standing of their unpaid invoices.
+    ///
+    /// An invoice with a pending waiver request can make a member late, but can't
+    /// suspend their vote while the request is being decided.
+    pub fn standing(&self, member: &str, policy: &DuesPolicy, now: i64) -> DuesStanding {
+        self.invoices.iter()
+            .filter(|i| i.member == member && i.outstanding() > 0 && now >= i.due_at)
+            .map(|invoice| {
+                let late_from = policy.late_from(invoice.due_at);
+                if now < late_from {
+                    return DuesStanding::Grace;
+                }
+                let suspended = policy.suspend_voting_after_days
+                    .map(|days| now >= late_from.saturating_add((days as i64).saturating_mul(SECONDS_PER_DAY)))
+                    .unwrap_or(false);
+                if suspended && !self.has_pending_waiver(&invoice.id) {
+                    DuesStanding::Suspended
+                } else {
+                    DuesStanding::Late
+                }
+            })
+            .max()
+            .unwrap_or(DuesStanding::Good)
+    }
+}
+
+/// Store a dues ledger
+pub async fn save_dues_ledger(
+    ledger: &DuesLedger,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(ledger)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize dues ledger: {}", e)))?;
+
+    let key = format!("{}{}", DUES_KEY_PREFIX, ledger.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's dues ledger; scopes without invoices get an empty ledger
+pub async fn load_dues_ledger(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<DuesLedger> {
+    let key = format!("{}{}", DUES_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize dues ledger: {}", e))),
+        None => Ok(DuesLedger::new(scope_id)),
+    }
+}
+
+/// Bill a member for a period. Returns the invoice ID.
+pub async fn issue_invoice(
+    scope_id: &str,
+    member: &str,
+    period: &str,
+    amount: u64,
+    due_at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut ledger = load_dues_ledger(scope_id, storage).await?;
+    if ledger.invoices.iter().any(|i| i.member == member && i.period == period) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("{} was already billed for {}", member, period)
+        ));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    ledger.invoices.push(DuesInvoice {
+        id: id.clone(),
+        member: member.to_string(),
+        period: period.to_string(),
+        amount,
+        due_at,
+        paid: 0,
+        late_fee: 0,
+        fee_assessed_at: None,
+        waived: false,
+    });
+    save_dues_ledger(&ledger, storage).await?;
+    Ok(id)
+}
+
+/// Record a payment against an invoice. Returns what is still owed.
+pub async fn record_payment(
+    scope_id: &str,
+    invoice_id: &str,
+    amount: u64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<u64> {
+    let mut ledger = load_dues_ledger(scope_id, storage).await?;
+    let invoice = ledger.invoice_mut(invoice_id)?;
+    if amount > invoice.outstanding() {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Payment of {} is more than the {} owed on invoice {}", amount, invoice.outstanding(), invoice_id)
+        ));
+    }
+
+    invoice.paid += amount;
+    let outstanding = invoice.outstanding();
+    save_dues_ledger(&ledger, storage).await?;
+    Ok(outstanding)
+}
+
+/// Assess late fees on every invoice that is unpaid past its grace period. Each invoice
+/// is assessed at most once, and invoices with a pending waiver request are skipped
+/// until the request is decided. Returns the assessed invoice IDs and fees.
+pub async fn assess_late_fees(
+    scope_id: &str,
+    policy: &DuesPolicy,
+    now: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<Vec<(String, u64)>> {
+    let mut ledger = load_dues_ledger(scope_id, storage).await?;
+    let pending: Vec<String> = ledger.waivers.iter()
+        .filter(|w| w.status == WaiverStatus::Pending)
+        .map(|w| w.invoice_id.clone())
+        .collect();
+
+    let mut assessed = Vec::new();
+    for invoice in &mut ledger.invoices {
+        if invoice.fee_assessed_at.is_some()
+            || invoice.outstanding() == 0
+            || now < policy.late_from(invoice.due_at)
+            || pending.contains(&invoice.id)
+        {
+            continue;
+        }
+
+        let fee = policy.fee_for(invoice.outstanding());
+        invoice.late_fee = fee;
+        invoice.fee_assessed_at = Some(now);
+        assessed.push((invoice.id.clone(), fee));
+    }
+
+    if !assessed.is_empty() {
+        save_dues_ledger(&ledger, storage).await?;
+    }
+    Ok(assessed)
+}
+
+/// Ask for a hardship waiver of one of the member's invoices. Returns the request ID.
+pub async fn request_waiver(
+    scope_id: &str,
+    invoice_id: &str,
+    member: &str,
+    reason: &str,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut ledger = load_dues_ledger(scope_id, storage).await?;
+    let invoice = ledger.invoice_mut(invoice_id)?;
+    if invoice.member != member {
+        return Err(EconomicsError::Unauthorized(
+            format!("Invoice {} was not billed to {}", invoice_id, member)
+        ));
+    }
+    if invoice.outstanding() == 0 {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Invoice {} has nothing left to waive", invoice_id)
+        ));
+    }
+    if ledger.has_pending_waiver(invoice_id) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Invoice {} already has a pending waiver request", invoice_id)
+        ));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    ledger.waivers.push(WaiverRequest {
+        id: id.clone(),
+        invoice_id: invoice_id.to_string(),
+        member: member.to_string(),
+        reason: reason.to_string(),
+        requested_at: at,
+        approvals: Vec::new(),
+        status: WaiverStatus::Pending,
+        rejected_by: None,
+        decided_at: None,
+    });
+    save_dues_ledger(&ledger, storage).await?;
+    Ok(id)
+}
+
+/// Approve or reject a pending waiver request. A single rejection rejects it; once it
+/// has the policy's number of approvals the invoice is forgiven. Members can't review
+/// their own request.
+pub async fn review_waiver(
+    scope_id: &str,
+    waiver_id: &str,
+    reviewer: &str,
+    approve: bool,
+    policy: &DuesPolicy,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<WaiverStatus> {
+    let mut ledger = load_dues_ledger(scope_id, storage).await?;
+    let waiver = ledger.waivers.iter_mut()
+        .find(|w| w.id == waiver_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No waiver request {} in {}", waiver_id, scope_id)))?;
+
+    if waiver.status != WaiverStatus::Pending {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Waiver request {} was already {:?}", waiver_id, waiver.status)
+        ));
+    }
+    if waiver.member == reviewer {
+        return Err(EconomicsError::Unauthorized(
+            format!("{} can't review their own waiver request", reviewer)
+        ));
+    }
+    if waiver.approvals.iter().any(|a| a == reviewer) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("{} already approved waiver request {}", reviewer, waiver_id)
+        ));
+    }
+
+    if approve {
+        waiver.approvals.push(reviewer.to_string());
+        if waiver.approvals.len() >= policy.waiver_approvals {
+            waiver.status = WaiverStatus::Approved;
+            waiver.decided_at = Some(at);
+        }
+    } else {
+        waiver.status = WaiverStatus::Rejected;
+        waiver.rejected_by = Some(reviewer.to_string());
+        waiver.decided_at = Some(at);
+    }
+
+    let status = waiver.status;
+    if status == WaiverStatus::Approved {
+        let invoice_id = waiver.invoice_id.clone();
+        ledger.invoice_mut(&invoice_id)?.waived = true;
+    }
+    save_dues_ledger(&ledger, storage).await?;
+    Ok(status)
+}
+
+/// A member's dues standing in a scope
+pub async fn member_standing(
+    scope_id: &str,
+    member: &str,
+    policy: &DuesPolicy,
+    now: i64,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<DuesStanding> {
+    let ledger = load_dues_ledger(scope_id, storage).await?;
+    Ok(ledger.standing(member, policy, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    fn policy() -> DuesPolicy {
+        DuesPolicy::from_membership_dues(Some(10), Some(5), Some(1000), Some(20), Some(2)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_late_fees_and_voting_suspension() {
+        let mut storage = MockBudgetStorage::new();
+        let policy = policy();
+        let scope = "did:icn:coop";
+
+        let invoice = issue_invoice(scope, "alice", "2025-03", 100, 0, &mut storage).await.unwrap();
+        assert!(issue_invoice(scope, "alice", "2025-03", 100, 0, &mut storage).await.is_err());
+
+        // Within the grace window nothing is assessed
+        assert!(assess_late_fees(scope, &policy, 5 * DAY, &mut storage).await.unwrap().is_empty());
+        assert_eq!(member_standing(scope, "alice", &policy, 5 * DAY, &storage).await.unwrap(), DuesStanding::Grace);
+
+        // After it, a flat fee plus 10% of the dues, assessed once
+        let assessed = assess_late_fees(scope, &policy, 11 * DAY, &mut storage).await.unwrap();
+        assert_eq!(assessed, vec![(invoice.clone(), 15)]);
+        assert!(assess_late_fees(scope, &policy, 12 * DAY, &mut storage).await.unwrap().is_empty());
+
+        let standing = member_standing(scope, "alice", &policy, 31 * DAY, &storage).await.unwrap();
+        assert_eq!(standing, DuesStanding::Suspended);
+        assert!(!standing.can_vote());
+
+        // Paying in full restores standing
+        assert_eq!(record_payment(scope, &invoice, 115, &mut storage).await.unwrap(), 0);
+        assert_eq!(member_standing(scope, "alice", &policy, 31 * DAY, &storage).await.unwrap(), DuesStanding::Good);
+
+        assert!(DuesPolicy::from_membership_dues(None, None, Some(20_000), None, None).is_err());
+    }
+
+    #[test]
+    fn test_late_fees_follow_the_rounding_policy() {
+        // 2.5% of 1_500 is 37.5
+        let policy = DuesPolicy::from_membership_dues(None, None, Some(250), None, None).unwrap();
+        assert_eq!(policy.fee_for(1_500), 37);
+
+        let bankers = policy.clone().with_rounding(RoundingPolicy::from_economic_model("half_even").unwrap());
+        assert_eq!(bankers.fee_for(1_500), 38);
+        assert_eq!(bankers.fee_for(1_100), 28);
+        assert_eq!(bankers.fee_for(1_380), 34);
+
+        let tens = policy.with_rounding(RoundingPolicy::from_economic_model("half_even(unit=10)").unwrap());
+        assert_eq!(tens.fee_for(1_500), 40);
+    }
+
+    #[tokio::test]
+    async fn test_hardship_waiver_approval_flow() {
+        let mut storage = MockBudgetStorage::new();
+        let policy = policy();
+        let scope = "did:icn:coop";
+
+        let invoice = issue_invoice(scope, "bob", "2025-03", 100, 0, &mut storage).await.unwrap();
+        assert!(request_waiver(scope, &invoice, "carol", "Not my invoice", DAY, &mut storage).await.is_err());
+        let waiver = request_waiver(scope, &invoice, "bob", "Medical leave", DAY, &mut storage).await.unwrap();
+
+        // A pending request holds off fees and suspension
+        assert!(assess_late_fees(scope, &policy, 40 * DAY, &mut storage).await.unwrap().is_empty());
+        assert_eq!(member_standing(scope, "bob", &policy, 40 * DAY, &storage).await.unwrap(), DuesStanding::Late);
+
+        assert!(review_waiver(scope, &waiver, "bob", true, &policy, 2 * DAY, &mut storage).await.is_err());
+        assert_eq!(review_waiver(scope, &waiver, "carol", true, &policy, 2 * DAY, &mut storage).await.unwrap(), WaiverStatus::Pending);
+        assert!(review_waiver(scope, &waiver, "carol", true, &policy, 2 * DAY, &mut storage).await.is_err());
+        assert_eq!(review_waiver(scope, &waiver, "dave", true, &policy, 3 * DAY, &mut storage).await.unwrap(), WaiverStatus::Approved);
+
+        assert_eq!(member_standing(scope, "bob", &policy, 40 * DAY, &storage).await.unwrap(), DuesStanding::Good);
+        let ledger = load_dues_ledger(scope, &storage).await.unwrap();
+        assert_eq!(ledger.invoices[0].outstanding(), 0);
+    }
+}
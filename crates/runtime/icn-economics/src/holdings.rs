@@ -0,0 +1,404 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+
+/// Storage key prefix for treasury holdings registries
+const HOLDINGS_KEY_PREFIX: &str = "holdings::registry::";
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u128 = 10_000;
+
+/// Investment guardrails from the `investment_policy` of an economic model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InvestmentPolicy {
+    /// Maximum share of total holdings per asset class, in basis points.
+    /// Classes without a limit are unconstrained.
+    pub max_allocation_bps: HashMap<String, u64>,
+
+    /// Instruments the treasury may not hold
+    pub prohibited_instruments: Vec<String>,
+}
+
+impl InvestmentPolicy {
+    /// Build a policy from the `max_allocation_bps` and `prohibited_instruments` fields
+    /// of an economic model's investment policy
+    pub fn from_economic_model(
+        max_allocation_bps: Option<HashMap<String, u64>>,
+        prohibited_instruments: Option<Vec<String>>,
+    ) -> EconomicsResult<Self> {
+        let max_allocation_bps = max_allocation_bps.unwrap_or_default();
+        if let Some((class, limit)) = max_allocation_bps.iter().find(|(_, limit)| **limit as u128 > BASIS_POINTS) {
+            return Err(EconomicsError::InvalidBudget(
+                format!("Allocation limit for {} is {} bps, above 100%", class, limit)
+            ));
+        }
+
+        Ok(Self {
+            max_allocation_bps,
+            prohibited_instruments: prohibited_instruments.unwrap_or_default(),
+        })
+    }
+}
+
+/// A position held in an external instrument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    pub id: String,
+
+    /// Instrument identifier (e.g., a bond ISIN or a credit union account name)
+    pub instrument: String,
+
+    /// Asset class the guardrails apply to (e.g., "cash", "bonds", "equities")
+    pub asset_class: String,
+
+    /// Most recent valuation, in the treasury's unit of account
+    pub value: u64,
+
+    /// When the holding was last valued (Unix timestamp)
+    pub valued_at: i64,
+}
+
+/// Value of every holding at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValuationSnapshot {
+    /// When the snapshot was taken (Unix timestamp)
+    pub taken_at: i64,
+    pub values: BTreeMap<String, u64>,
+    pub total: u64,
+}
+
+/// A way holdings break the investment policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComplianceViolation {
+    /// A holding is in a prohibited instrument
+    ProhibitedInstrument { holding_id: String, instrument: String },
+
+    /// An asset class is above its maximum allocation
+    AllocationExceeded { asset_class: String, allocation_bps: u64, limit_bps: u64 },
+}
+
+/// Result of checking holdings against the investment policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    /// When the check ran (Unix timestamp)
+    pub checked_at: i64,
+
+    /// Share of total holdings per asset class, in basis points
+    pub allocation_bps: BTreeMap<String, u64>,
+    pub violations: Vec<ComplianceViolation>,
+}
+
+impl ComplianceReport {
+    pub fn is_compliant(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Holdings of a scope's treasury with their valuation and compliance history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoldingsRegistry {
+    pub scope_id: String,
+    pub holdings: Vec<Holding>,
+    pub snapshots: Vec<ValuationSnapshot>,
+    pub compliance_checks: Vec<ComplianceReport>,
+}
+
+impl HoldingsRegistry {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            holdings: Vec::new(),
+            snapshots: Vec::new(),
+            compliance_checks: Vec::new(),
+        }
+    }
+
+    /// Total value of all holdings
+    pub fn total_value(&self) -> u64 {
+        self.holdings.iter().map(|h| h.value).sum()
+    }
+}
+
+/// Holdings activity over a reporting period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoldingsPeriodReport {
+    pub scope_id: String,
+    pub period_start: i64,
+    pub period_end: i64,
+
+    /// Total value at the last snapshot before the period, if any
+    pub opening_value: Option<u64>,
+
+    /// Total value at the last snapshot within the period, if any
+    pub closing_value: Option<u64>,
+    pub snapshots: Vec<ValuationSnapshot>,
+
+    /// Number of compliance checks run during the period
+    pub compliance_checks: usize,
+
+    /// Every distinct violation found during the period
+    pub violations: Vec<ComplianceViolation>,
+
+    /// Whether the last check in the period found the holdings compliant
+    pub compliant_at_close: bool,
+}
+
+/// Check holdings against an investment policy
+pub fn check_compliance(holdings: &[Holding], policy: &InvestmentPolicy, at: i64) -> ComplianceReport {
+    let total: u128 = holdings.iter().map(|h| h.value as u128).sum();
+
+    let mut class_values: BTreeMap<String, u128> = BTreeMap::new();
+    for holding in holdings {
+        *class_values.entry(holding.asset_class.clone()).or_default() += holding.value as u128;
+    }
+    let allocation_bps: BTreeMap<String, u64> = class_values.into_iter()
+        .map(|(class, value)| {
+            let bps = if total == 0 { 0 } else { (value * BASIS_POINTS / total) as u64 };
+            (class, bps)
+        })
+        .collect();
+
+    let mut violations: Vec<ComplianceViolation> = holdings.iter()
+        .filter(|h| policy.prohibited_instruments.contains(&h.instrument))
+        .map(|h| ComplianceViolation::ProhibitedInstrument {
+            holding_id: h.id.clone(),
+            instrument: h.instrument.clone(),
+        })
+        .collect();
+
+    for (class, allocation) in &allocation_bps {
+        if let Some(limit) = policy.max_allocation_bps.get(class) {
+            if allocation > limit {
+                violations.push(ComplianceViolation::AllocationExceeded {
+                    asset_class: class.clone(),
+                    allocation_bps: *allocation,
+                    limit_bps: *limit,
+                });
+            }
+        }
+    }
+
+    ComplianceReport { checked_at: at, allocation_bps, violations }
+}
+
+/// Store a holdings registry
+pub async fn save_holdings_registry(
+    registry: &HoldingsRegistry,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(registry)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize holdings registry: {}", e)))?;
+
+    let key = format!("{}{}", HOLDINGS_KEY_PREFIX, registry.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's holdings registry; scopes without holdings get an empty registry
+pub async fn load_holdings_registry(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<HoldingsRegistry> {
+    let key = format!("{}{}", HOLDINGS_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize holdings registry: {}", e))),
+        None => Ok(HoldingsRegistry::new(scope_id)),
+    }
+}
+
+/// Check the registry, record the result and store it
+async fn check_and_save(
+    mut registry: HoldingsRegistry,
+    policy: &InvestmentPolicy,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ComplianceReport> {
+    let report = check_compliance(&registry.holdings, policy, at);
+    registry.compliance_checks.push(report.clone());
+    save_holdings_registry(&registry, storage).await?;
+    Ok(report)
+}
+
+/// Add or replace a holding and check the registry against the policy.
+///
+/// Holdings that break the policy are still recorded, since the registry has to reflect
+/// what the treasury actually holds; the violations are returned and kept in the
+/// compliance history for the period report.
+pub async fn record_holding(
+    scope_id: &str,
+    holding: Holding,
+    policy: &InvestmentPolicy,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ComplianceReport> {
+    let mut registry = load_holdings_registry(scope_id, storage).await?;
+    registry.holdings.retain(|h| h.id != holding.id);
+    registry.holdings.push(holding);
+    check_and_save(registry, policy, at, storage).await
+}
+
+/// Remove a disposed holding and check the remaining holdings against the policy
+pub async fn remove_holding(
+    scope_id: &str,
+    holding_id: &str,
+    policy: &InvestmentPolicy,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ComplianceReport> {
+    let mut registry = load_holdings_registry(scope_id, storage).await?;
+    let before = registry.holdings.len();
+    registry.holdings.retain(|h| h.id != holding_id);
+    if registry.holdings.len() == before {
+        return Err(EconomicsError::InvalidBudget(
+            format!("No holding {} in the treasury of {}", holding_id, scope_id)
+        ));
+    }
+    check_and_save(registry, policy, at, storage).await
+}
+
+/// Revalue holdings, take a valuation snapshot and check the policy, since a change in
+/// value alone can push an asset class over its limit. Holdings missing from
+/// `values` keep their previous valuation.
+pub async fn record_valuations(
+    scope_id: &str,
+    values: &HashMap<String, u64>,
+    policy: &InvestmentPolicy,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ComplianceReport> {
+    let mut registry = load_holdings_registry(scope_id, storage).await?;
+    if let Some(unknown) = values.keys().find(|id| !registry.holdings.iter().any(|h| &h.id == *id)) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("No holding {} in the treasury of {}", unknown, scope_id)
+        ));
+    }
+
+    for holding in &mut registry.holdings {
+        if let Some(value) = values.get(&holding.id) {
+            holding.value = *value;
+            holding.valued_at = at;
+        }
+    }
+
+    registry.snapshots.push(ValuationSnapshot {
+        taken_at: at,
+        values: registry.holdings.iter().map(|h| (h.id.clone(), h.value)).collect(),
+        total: registry.total_value(),
+    });
+    check_and_save(registry, policy, at, storage).await
+}
+
+/// Summarize holdings valuations and compliance over `[period_start, period_end)`
+pub async fn holdings_period_report(
+    scope_id: &str,
+    period_start: i64,
+    period_end: i64,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<HoldingsPeriodReport> {
+    let registry = load_holdings_registry(scope_id, storage).await?;
+    let in_period = |at: i64| at >= period_start && at < period_end;
+
+    let opening_value = registry.snapshots.iter()
+        .filter(|s| s.taken_at < period_start)
+        .last()
+        .map(|s| s.total);
+    let snapshots: Vec<ValuationSnapshot> = registry.snapshots.iter()
+        .filter(|s| in_period(s.taken_at))
+        .cloned()
+        .collect();
+    let closing_value = snapshots.last().map(|s| s.total);
+
+    let checks: Vec<&ComplianceReport> = registry.compliance_checks.iter()
+        .filter(|c| in_period(c.checked_at))
+        .collect();
+    let mut violations: Vec<ComplianceViolation> = Vec::new();
+    for violation in checks.iter().flat_map(|c| c.violations.iter()) {
+        if !violations.contains(violation) {
+            violations.push(violation.clone());
+        }
+    }
+
+    Ok(HoldingsPeriodReport {
+        scope_id: scope_id.to_string(),
+        period_start,
+        period_end,
+        opening_value,
+        closing_value,
+        snapshots,
+        compliance_checks: checks.len(),
+        violations,
+        compliant_at_close: checks.last().map(|c| c.is_compliant()).unwrap_or(true),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    fn holding(id: &str, instrument: &str, asset_class: &str, value: u64) -> Holding {
+        Holding {
+            id: id.to_string(),
+            instrument: instrument.to_string(),
+            asset_class: asset_class.to_string(),
+            value,
+            valued_at: 0,
+        }
+    }
+
+    fn policy() -> InvestmentPolicy {
+        InvestmentPolicy::from_economic_model(
+            Some([("equities".to_string(), 3000)].into_iter().collect()),
+            Some(vec!["fossil-fuel-fund".to_string()]),
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_compliance_flags_prohibited_and_overweight_holdings() {
+        let holdings = vec![
+            holding("h1", "credit-union-savings", "cash", 6000),
+            holding("h2", "solar-coop-bond", "bonds", 2000),
+            holding("h3", "fossil-fuel-fund", "equities", 2000),
+        ];
+        let report = check_compliance(&holdings, &policy(), 10);
+        assert_eq!(report.allocation_bps["cash"], 6000);
+        assert_eq!(report.violations, vec![ComplianceViolation::ProhibitedInstrument {
+            holding_id: "h3".to_string(),
+            instrument: "fossil-fuel-fund".to_string(),
+        }]);
+
+        assert!(InvestmentPolicy::from_economic_model(
+            Some([("cash".to_string(), 12_000)].into_iter().collect()), None,
+        ).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_valuation_changes_are_checked_and_reported() {
+        let mut storage = MockBudgetStorage::new();
+        let policy = policy();
+
+        record_holding("did:icn:fed", holding("h1", "credit-union-savings", "cash", 8000), &policy, 10, &mut storage).await.unwrap();
+        let report = record_holding("did:icn:fed", holding("h2", "coop-index", "equities", 2000), &policy, 20, &mut storage).await.unwrap();
+        assert!(report.is_compliant());
+        record_valuations("did:icn:fed", &HashMap::new(), &policy, 30, &mut storage).await.unwrap();
+
+        // Equities rally past the 30% limit
+        let values: HashMap<String, u64> = [("h2".to_string(), 6000)].into_iter().collect();
+        let report = record_valuations("did:icn:fed", &values, &policy, 110, &mut storage).await.unwrap();
+        assert_eq!(report.violations, vec![ComplianceViolation::AllocationExceeded {
+            asset_class: "equities".to_string(),
+            allocation_bps: 4285,
+            limit_bps: 3000,
+        }]);
+
+        let period = holdings_period_report("did:icn:fed", 100, 200, &storage).await.unwrap();
+        assert_eq!(period.opening_value, Some(10_000));
+        assert_eq!(period.closing_value, Some(14_000));
+        assert_eq!(period.compliance_checks, 1);
+        assert!(!period.compliant_at_close);
+
+        assert!(remove_holding("did:icn:fed", "h9", &policy, 120, &mut storage).await.is_err());
+        assert!(remove_holding("did:icn:fed", "h2", &policy, 120, &mut storage).await.unwrap().is_compliant());
+    }
+}
@@ -0,0 +1,182 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use sha2::{Sha256, Digest};
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+
+/// Storage key prefix for the results of idempotent calls
+const IDEMPOTENCY_KEY_PREFIX: &str = "idempotency::";
+
+/// How long a call's result is kept for retries to replay (7 days)
+pub const DEFAULT_DEDUP_WINDOW_SECS: i64 = 7 * 86_400;
+
+/// The result of a transfer or disbursement call, kept under the caller's idempotency key
+/// so a retried call gets the same result instead of applying again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    pub scope_id: String,
+    pub key: String,
+
+    /// Call the key was used for (e.g., "execute_transfer_plan")
+    pub operation: String,
+
+    /// SHA-256 of the call's arguments; a key reused with other arguments is refused
+    pub request_hash: String,
+
+    pub response: serde_json::Value,
+    pub recorded_at: i64,
+
+    /// When the key may be used again
+    pub expires_at: i64,
+}
+
+impl IdempotencyRecord {
+    /// Whether the record still deduplicates calls at `at`
+    pub fn is_live(&self, at: i64) -> bool {
+        at < self.expires_at
+    }
+}
+
+/// Where idempotency records are kept. Budget storage keeps them alongside budgets;
+/// token stores that aren't budget storage implement it directly.
+#[async_trait]
+pub trait IdempotencyStorage: Send + Sync {
+    /// Load the serialized record stored under a key
+    async fn load_idempotency_data(&self, key: &str) -> EconomicsResult<Option<Vec<u8>>>;
+
+    /// Store a serialized record under a key
+    async fn store_idempotency_data(&mut self, key: &str, data: Vec<u8>) -> EconomicsResult<()>;
+}
+
+#[async_trait]
+impl<T: BudgetStorage> IdempotencyStorage for T {
+    async fn load_idempotency_data(&self, key: &str) -> EconomicsResult<Option<Vec<u8>>> {
+        self.get_budget(key).await
+    }
+
+    async fn store_idempotency_data(&mut self, key: &str, data: Vec<u8>) -> EconomicsResult<()> {
+        self.store_budget(key, data).await
+    }
+}
+
+fn record_key(scope_id: &str, key: &str) -> String {
+    format!("{}{}::{}", IDEMPOTENCY_KEY_PREFIX, scope_id, key)
+}
+
+/// Hex SHA-256 of a call's arguments
+pub fn request_fingerprint(request: &impl Serialize) -> EconomicsResult<String> {
+    let data = serde_json::to_vec(request)
+        .map_err(|e| EconomicsError::Serialization(format!("Failed to serialize request: {}", e)))?;
+    Ok(format!("{:x}", Sha256::digest(&data)))
+}
+
+/// Load the record kept under an idempotency key, live or expired
+pub async fn load_idempotency_record(
+    scope_id: &str,
+    key: &str,
+    storage: &impl IdempotencyStorage,
+) -> EconomicsResult<Option<IdempotencyRecord>> {
+    match storage.load_idempotency_data(&record_key(scope_id, key)).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| EconomicsError::Serialization(format!("Failed to deserialize idempotency record: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// The original result of a call already made with `key`, if it is within the dedup
+/// window. Fails if the key was used for a different call or different arguments.
+pub async fn replay_result<T: DeserializeOwned>(
+    scope_id: &str,
+    key: &str,
+    operation: &str,
+    request_hash: &str,
+    at: i64,
+    storage: &impl IdempotencyStorage,
+) -> EconomicsResult<Option<T>> {
+    if key.trim().is_empty() {
+        return Err(EconomicsError::IdempotencyConflict("Idempotency key must not be empty".to_string()));
+    }
+
+    let record = match load_idempotency_record(scope_id, key, storage).await? {
+        Some(record) if record.is_live(at) => record,
+        _ => return Ok(None),
+    };
+    if record.operation != operation || record.request_hash != request_hash {
+        return Err(EconomicsError::IdempotencyConflict(format!(
+            "Idempotency key {} was already used for a different {} call", key, record.operation
+        )));
+    }
+
+    tracing::info!("Replaying {} result for idempotency key {}", operation, key);
+    serde_json::from_value(record.response)
+        .map(Some)
+        .map_err(|e| EconomicsError::Serialization(format!("Failed to deserialize idempotent result: {}", e)))
+}
+
+/// Keep the result of a call under `key` for `window_secs`
+#[allow(clippy::too_many_arguments)]
+pub async fn remember_result<T: Serialize>(
+    scope_id: &str,
+    key: &str,
+    operation: &str,
+    request_hash: &str,
+    response: &T,
+    at: i64,
+    window_secs: i64,
+    storage: &mut impl IdempotencyStorage,
+) -> EconomicsResult<()> {
+    let record = IdempotencyRecord {
+        scope_id: scope_id.to_string(),
+        key: key.to_string(),
+        operation: operation.to_string(),
+        request_hash: request_hash.to_string(),
+        response: serde_json::to_value(response)
+            .map_err(|e| EconomicsError::Serialization(format!("Failed to serialize idempotent result: {}", e)))?,
+        recorded_at: at,
+        expires_at: at.saturating_add(window_secs),
+    };
+
+    let data = serde_json::to_vec(&record)
+        .map_err(|e| EconomicsError::Serialization(format!("Failed to serialize idempotency record: {}", e)))?;
+    storage.store_idempotency_data(&record_key(scope_id, key), data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    const SCOPE: &str = "did:icn:coop";
+
+    #[tokio::test]
+    async fn test_results_replay_within_the_window() {
+        let mut storage = MockBudgetStorage::new();
+        let hash = request_fingerprint(&("plan-1", 100u64)).unwrap();
+
+        let first: Option<u64> = replay_result(SCOPE, "key-1", "pay", &hash, 0, &storage).await.unwrap();
+        assert_eq!(first, None);
+        remember_result(SCOPE, "key-1", "pay", &hash, &100u64, 0, 3_600, &mut storage).await.unwrap();
+
+        let retried: Option<u64> = replay_result(SCOPE, "key-1", "pay", &hash, 60, &storage).await.unwrap();
+        assert_eq!(retried, Some(100));
+
+        // A stored result that doesn't fit the call's result type is a serialization error
+        assert!(matches!(
+            replay_result::<String>(SCOPE, "key-1", "pay", &hash, 60, &storage).await,
+            Err(EconomicsError::Serialization(_))
+        ));
+
+        // The same key with other arguments is refused
+        let other = request_fingerprint(&("plan-1", 200u64)).unwrap();
+        assert!(matches!(
+            replay_result::<u64>(SCOPE, "key-1", "pay", &other, 60, &storage).await,
+            Err(EconomicsError::IdempotencyConflict(_))
+        ));
+
+        // Once the window has passed the key is free again
+        let expired: Option<u64> = replay_result(SCOPE, "key-1", "pay", &other, 3_600, &storage).await.unwrap();
+        assert_eq!(expired, None);
+    }
+}
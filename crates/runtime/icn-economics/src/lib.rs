@@ -34,6 +34,60 @@ pub mod resources;
 // Policy module
 pub mod policy;
 
+// Governance-approved transfer plans
+pub mod transfer_plan;
+
+// Patronage dividend calculation
+pub mod patronage;
+
+// End-of-period surplus distribution under the economic model's policy
+pub mod surplus;
+
+// Expense reimbursement claims
+pub mod reimbursement;
+
+// Member capital accounts
+pub mod capital;
+
+// Treasury holdings and investment guardrails
+pub mod holdings;
+
+// Membership dues with late fees and hardship waivers
+pub mod dues;
+
+// Donations from external contributors
+pub mod donations;
+
+// Signed monthly treasury statements
+pub mod statements;
+
+// Shared federation costs split by allocation keys
+pub mod cost_allocation;
+
+// Real-time balance change subscriptions
+pub mod balance_feed;
+
+// Chargebacks for mistaken or fraudulent internal transfers
+pub mod chargebacks;
+
+// Budget allocations that activate once revenue is recognized
+pub mod revenue_triggers;
+
+// Solidarity funds paying members in need
+pub mod solidarity;
+
+// Tax category tagging and period tax reports
+pub mod tax;
+
+// Idempotency keys deduplicating retried transfers and disbursements
+pub mod idempotency;
+
+// Purchase requests with sealed vendor bids, awarded through the budget engine
+pub mod procurement;
+
+// Precision and rounding of monetary amounts split in proportion
+pub mod rounding;
+
 // Export key types from resources module
 pub use resources::{RegenerativeResourceType, ManaPool, ResourceManager, OperationCosts};
 
@@ -66,6 +120,18 @@ pub enum EconomicsError {
     
     #[error("Token not found with ID: {0}")]
     TokenNotFound(Uuid),
+    
+    #[error("Invalid transfer plan: {0}")]
+    InvalidTransferPlan(String),
+    
+    #[error("Invalid rounding policy: {0}")]
+    InvalidRoundingPolicy(String),
+    
+    #[error("Idempotency conflict: {0}")]
+    IdempotencyConflict(String),
+    
+    #[error("Serialization error: {0}")]
+    Serialization(String),
 }
 
 /// Result type for economic operations
@@ -325,6 +391,7 @@ pub async fn consume_authorization(
 pub mod token_ops {
     use super::*;
     use crate::token_storage::TokenStorage;
+    use crate::idempotency::{self, IdempotencyStorage, DEFAULT_DEDUP_WINDOW_SECS};
     use std::sync::Arc;
     use futures::lock::Mutex;
     
@@ -354,13 +421,24 @@ pub mod token_ops {
         Ok(token)
     }
     
-    /// Transfer a token from one owner to another
+    /// Transfer a token from one owner to another. A retried call with the same
+    /// `idempotency_key` returns without transferring again.
     pub async fn transfer_token(
         token_id: Uuid,
         from_did: &str,
         to_did: &str,
-        storage: &mut impl TokenStorage,
+        idempotency_key: &str,
+        storage: &mut (impl TokenStorage + IdempotencyStorage),
     ) -> EconomicsResult<()> {
+        // A retry of a transfer that already went through gets the original result
+        let now = chrono::Utc::now().timestamp();
+        let request_hash = idempotency::request_fingerprint(&(token_id, from_did, to_did))?;
+        if idempotency::replay_result::<()>(
+            from_did, idempotency_key, "transfer_token", &request_hash, now, storage,
+        ).await?.is_some() {
+            return Ok(());
+        }
+        
         // Retrieve the token
         let token_opt = storage.get_token(&token_id).await?;
         
@@ -382,7 +460,10 @@ pub mod token_ops {
         // Store the updated token
         storage.store_token(&token).await?;
         
-        Ok(())
+        // Remember the transfer so retries don't apply it again
+        idempotency::remember_result(
+            from_did, idempotency_key, "transfer_token", &request_hash, &(), now, DEFAULT_DEDUP_WINDOW_SECS, storage,
+        ).await
     }
     
     /// Burn a token (remove it from circulation)
@@ -509,6 +590,10 @@ pub struct CategoryRule {
     
     /// Description of this category
     pub description: Option<String>,
+    
+    /// Tax category spending in this category is reported under
+    #[serde(default)]
+    pub tax_category: Option<String>,
 }
 
 /// Status of a budget proposal
@@ -738,6 +823,7 @@ mod tests {
             token.token_id,
             "did:icn:alice",
             "did:icn:bob",
+            "transfer-1",
             &mut storage
         ).await.unwrap();
         
@@ -745,6 +831,25 @@ mod tests {
         let transferred_token = token_ops::get_token_by_id(&token.token_id, &storage).await.unwrap().unwrap();
         assert_eq!(transferred_token.owner_did, "did:icn:bob");
         
+        // A retried transfer returns the original result instead of failing on ownership
+        token_ops::transfer_token(
+            token.token_id,
+            "did:icn:alice",
+            "did:icn:bob",
+            "transfer-1",
+            &mut storage
+        ).await.unwrap();
+        
+        // Reusing the key for a different transfer is refused
+        let result = token_ops::transfer_token(
+            token.token_id,
+            "did:icn:alice",
+            "did:icn:charlie",
+            "transfer-1",
+            &mut storage
+        ).await;
+        assert!(matches!(result, Err(EconomicsError::IdempotencyConflict(_))));
+        
         // Test burning the token
         token_ops::burn_token(
             token.token_id,
@@ -777,6 +882,7 @@ mod tests {
             token.token_id,
             "did:icn:bob", // Not the owner
             "did:icn:charlie",
+            "transfer-2",
             &mut storage
         ).await;
         
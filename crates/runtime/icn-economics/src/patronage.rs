@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::BudgetStorage;
+use crate::rounding::RoundingPolicy;
+use crate::transfer_plan::{PlannedTransfer, TransferPlan, save_transfer_plan};
+
+/// Weighting formula for patronage dividends.
+///
+/// Each member's share is the weighted sum of their fraction of total hours, total sales
+/// and total tenure. Weights are normalized, so only their ratios matter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatronageFormula {
+    /// Weight of hours worked
+    pub hours_weight: f64,
+
+    /// Weight of sales/purchases through the cooperative
+    pub sales_weight: f64,
+
+    /// Weight of tenure
+    pub tenure_weight: f64,
+
+    /// Tenure beyond this many days counts as this many days
+    pub tenure_cap_days: Option<u64>,
+
+    /// Hourly rates per work type from the compensation policy.
+    /// When set, hours are valued at their rate instead of counted equally.
+    pub hourly_rates: Option<HashMap<String, u64>>,
+}
+
+impl Default for PatronageFormula {
+    fn default() -> Self {
+        Self {
+            hours_weight: 1.0,
+            sales_weight: 0.0,
+            tenure_weight: 0.0,
+            tenure_cap_days: None,
+            hourly_rates: None,
+        }
+    }
+}
+
+impl PatronageFormula {
+    /// Build a formula from the `surplus_distribution` and `hourly_rates` fields of an
+    /// economic model.
+    ///
+    /// Accepts `"patronage"` (hours only) or a weighted form such as
+    /// `"patronage(hours=0.6, sales=0.3, tenure=0.1, tenure_cap_days=1825)"`.
+    pub fn from_economic_model(
+        surplus_distribution: &str,
+        hourly_rates: Option<HashMap<String, u64>>,
+    ) -> EconomicsResult<Self> {
+        let spec = surplus_distribution.trim();
+        let (method, params) = match spec.find('(') {
+            Some(open) if spec.ends_with(')') => (&spec[..open], &spec[open + 1..spec.len() - 1]),
+            Some(_) => return Err(EconomicsError::InvalidBudget(
+                format!("Malformed surplus distribution: {}", spec)
+            )),
+            None => (spec, ""),
+        };
+
+        if method.trim() != "patronage" {
+            return Err(EconomicsError::InvalidBudget(
+                format!("Surplus distribution '{}' is not patronage based", method.trim())
+            ));
+        }
+
+        let mut formula = PatronageFormula { hourly_rates, ..Default::default() };
+        if params.trim().is_empty() {
+            return Ok(formula);
+        }
+
+        formula.hours_weight = 0.0;
+        for param in params.split(',') {
+            let (key, value) = param.split_once('=').ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("Malformed patronage parameter: {}", param.trim())
+            ))?;
+            let value = value.trim();
+            let parse_weight = || value.parse::<f64>().map_err(|_| EconomicsError::InvalidBudget(
+                format!("Invalid patronage weight for {}: {}", key.trim(), value)
+            ));
+
+            match key.trim() {
+                "hours" => formula.hours_weight = parse_weight()?,
+                "sales" => formula.sales_weight = parse_weight()?,
+                "tenure" => formula.tenure_weight = parse_weight()?,
+                "tenure_cap_days" => formula.tenure_cap_days = Some(value.parse::<u64>().map_err(|_| {
+                    EconomicsError::InvalidBudget(format!("Invalid tenure cap: {}", value))
+                })?),
+                other => return Err(EconomicsError::InvalidBudget(
+                    format!("Unknown patronage parameter: {}", other)
+                )),
+            }
+        }
+
+        formula.validate()?;
+        Ok(formula)
+    }
+
+    /// Check that the weights are usable
+    pub fn validate(&self) -> EconomicsResult<()> {
+        let weights = [self.hours_weight, self.sales_weight, self.tenure_weight];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(EconomicsError::InvalidBudget("Patronage weights must be non-negative".to_string()));
+        }
+        if weights.iter().sum::<f64>() <= 0.0 {
+            return Err(EconomicsError::InvalidBudget("At least one patronage weight must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Value a member's hours according to the configured hourly rates
+    fn hours_value(&self, contribution: &MemberContribution) -> f64 {
+        contribution.hours.iter()
+            .map(|(work_type, hours)| match &self.hourly_rates {
+                // Work types without a configured rate are not compensated by patronage
+                Some(rates) => hours * rates.get(work_type).copied().unwrap_or(0) as f64,
+                None => *hours,
+            })
+            .sum()
+    }
+
+    fn tenure_value(&self, contribution: &MemberContribution) -> f64 {
+        match self.tenure_cap_days {
+            Some(cap) => contribution.tenure_days.min(cap) as f64,
+            None => contribution.tenure_days as f64,
+        }
+    }
+}
+
+/// A member's contributions over the distribution period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberContribution {
+    /// The member's DID
+    pub member_did: String,
+
+    /// Hours worked, per work type
+    pub hours: HashMap<String, f64>,
+
+    /// Sales or purchases through the cooperative
+    pub sales: u64,
+
+    /// Days of membership
+    pub tenure_days: u64,
+}
+
+/// A member's computed patronage share
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatronageShare {
+    /// The member's DID
+    pub member_did: String,
+
+    /// Fraction of the surplus (0.0 - 1.0)
+    pub fraction: f64,
+
+    /// Amount allocated to the member
+    pub amount: u64,
+}
+
+/// Compute each member's share of `surplus` under `formula`.
+///
+/// Amounts are rounded under `rounding` and never add up to more than `surplus`. If the
+/// policy keeps the rounding residue back, it is what the amounts leave of `surplus`;
+/// otherwise they add up to exactly `surplus`.
+pub fn compute_patronage_shares(
+    surplus: u64,
+    contributions: &[MemberContribution],
+    formula: &PatronageFormula,
+    rounding: &RoundingPolicy,
+) -> EconomicsResult<Vec<PatronageShare>> {
+    formula.validate()?;
+
+    if contributions.is_empty() {
+        return Err(EconomicsError::InvalidBudget("No member contributions to distribute over".to_string()));
+    }
+
+    let hours: Vec<f64> = contributions.iter().map(|c| formula.hours_value(c)).collect();
+    let sales: Vec<f64> = contributions.iter().map(|c| c.sales as f64).collect();
+    let tenure: Vec<f64> = contributions.iter().map(|c| formula.tenure_value(c)).collect();
+
+    // Components with no activity drop out so their weight is redistributed
+    let components = [
+        (formula.hours_weight, &hours),
+        (formula.sales_weight, &sales),
+        (formula.tenure_weight, &tenure),
+    ];
+    let active: Vec<(f64, &Vec<f64>, f64)> = components.iter()
+        .map(|(weight, values)| (*weight, *values, values.iter().sum::<f64>()))
+        .filter(|(weight, _, total)| *weight > 0.0 && *total > 0.0)
+        .collect();
+
+    let weight_total: f64 = active.iter().map(|(w, _, _)| w).sum();
+    if weight_total <= 0.0 {
+        return Err(EconomicsError::InvalidBudget("No contributions recorded for any weighted component".to_string()));
+    }
+
+    let fractions: Vec<f64> = (0..contributions.len())
+        .map(|i| active.iter().map(|(w, values, total)| (w / weight_total) * (values[i] / total)).sum())
+        .collect();
+
+    let amounts = rounding.split_fractions(surplus, &fractions)?.shares;
+
+    Ok(contributions.iter().enumerate()
+        .map(|(i, c)| PatronageShare {
+            member_did: c.member_did.clone(),
+            fraction: fractions[i],
+            amount: amounts[i],
+        })
+        .collect())
+}
+
+/// Compute patronage dividends and store them as a draft transfer plan from the treasury.
+/// Any rounding residue stays in the treasury.
+///
+/// The plan must be submitted for governance approval before it can be executed.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_patronage_plan(
+    scope_id: &str,
+    treasury_did: &str,
+    resource_type: ResourceType,
+    surplus: u64,
+    period: &str,
+    contributions: &[MemberContribution],
+    formula: &PatronageFormula,
+    rounding: &RoundingPolicy,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<TransferPlan> {
+    let shares = compute_patronage_shares(surplus, contributions, formula, rounding)?;
+    let residue = surplus - shares.iter().map(|share| share.amount).sum::<u64>();
+
+    let transfers = shares.iter()
+        .filter(|share| share.amount > 0)
+        .map(|share| PlannedTransfer {
+            recipient_did: share.member_did.clone(),
+            amount: share.amount,
+            memo: Some(format!("Patronage dividend {}", period)),
+        })
+        .collect();
+
+    let metadata = serde_json::json!({
+        "period": period,
+        "surplus": surplus,
+        "formula": formula,
+        "rounding": rounding,
+        "residue": residue,
+        "shares": shares,
+    });
+
+    let plan = TransferPlan::new(scope_id, treasury_did, resource_type, "patronage", transfers, Some(metadata));
+    save_transfer_plan(&plan, storage).await?;
+
+    tracing::info!("Created patronage plan {} for {} ({} members)", plan.id, period, plan.transfers.len());
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+    use crate::transfer_plan::{TransferPlanStatus, load_transfer_plan};
+
+    fn contribution(did: &str, hours: f64, sales: u64, tenure_days: u64) -> MemberContribution {
+        let mut hours_map = HashMap::new();
+        hours_map.insert("general".to_string(), hours);
+        MemberContribution {
+            member_did: did.to_string(),
+            hours: hours_map,
+            sales,
+            tenure_days,
+        }
+    }
+
+    #[test]
+    fn test_parse_formula_from_economic_model() {
+        let formula = PatronageFormula::from_economic_model(
+            "patronage(hours=0.6, sales=0.3, tenure=0.1, tenure_cap_days=1825)", None
+        ).unwrap();
+        assert_eq!(formula.hours_weight, 0.6);
+        assert_eq!(formula.sales_weight, 0.3);
+        assert_eq!(formula.tenure_weight, 0.1);
+        assert_eq!(formula.tenure_cap_days, Some(1825));
+
+        let default = PatronageFormula::from_economic_model("patronage", None).unwrap();
+        assert_eq!(default.hours_weight, 1.0);
+
+        assert!(PatronageFormula::from_economic_model("equal", None).is_err());
+        assert!(PatronageFormula::from_economic_model("patronage(hours=-1)", None).is_err());
+    }
+
+    #[test]
+    fn test_hours_only_shares_sum_to_surplus() {
+        let contributions = vec![
+            contribution("did:icn:alice", 100.0, 0, 0),
+            contribution("did:icn:bob", 50.0, 0, 0),
+            contribution("did:icn:carol", 50.0, 0, 0),
+        ];
+
+        let shares = compute_patronage_shares(1001, &contributions, &PatronageFormula::default(), &RoundingPolicy::default()).unwrap();
+
+        assert_eq!(shares.iter().map(|s| s.amount).sum::<u64>(), 1001);
+        assert!(shares[0].amount >= 500);
+        assert!((shares[0].fraction - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weighted_shares_and_hourly_rates() {
+        let mut rates = HashMap::new();
+        rates.insert("general".to_string(), 2);
+        let formula = PatronageFormula {
+            hours_weight: 0.5,
+            sales_weight: 0.5,
+            tenure_weight: 0.0,
+            tenure_cap_days: None,
+            hourly_rates: Some(rates),
+        };
+        let contributions = vec![
+            contribution("did:icn:alice", 10.0, 0, 400),
+            contribution("did:icn:bob", 10.0, 100, 10),
+        ];
+
+        let shares = compute_patronage_shares(1000, &contributions, &formula, &RoundingPolicy::default()).unwrap();
+
+        // Equal hours split the hours half, bob takes the whole sales half
+        assert_eq!(shares[0].amount, 250);
+        assert_eq!(shares[1].amount, 750);
+    }
+
+    #[test]
+    fn test_shares_never_exceed_surplus_under_any_rounding() {
+        let contributions = vec![
+            contribution("did:icn:alice", 7.0, 13, 400),
+            contribution("did:icn:bob", 3.0, 0, 10),
+            contribution("did:icn:carol", 11.0, 29, 0),
+        ];
+        let formula = PatronageFormula { hours_weight: 0.5, sales_weight: 0.3, tenure_weight: 0.2, ..Default::default() };
+
+        for spec in ["down", "half_even", "down(unit=10, residue=reserve)", "half_even(unit=25)", "half_even(residue=reserve)"] {
+            let rounding = RoundingPolicy::from_economic_model(spec).unwrap();
+            for surplus in [0, 1, 2, 99, 1_000, 1_001, 12_345] {
+                let shares = compute_patronage_shares(surplus, &contributions, &formula, &rounding).unwrap();
+                let paid: u64 = shares.iter().map(|s| s.amount).sum();
+                assert!(paid <= surplus, "{} paid {} of {}", spec, paid, surplus);
+                assert!(shares.iter().all(|s| s.amount % rounding.unit == 0));
+                if rounding.residue == crate::rounding::ResidueHandling::Distribute {
+                    assert!(surplus - paid < rounding.unit, "{} left {} of {}", spec, surplus - paid, surplus);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_patronage_plan_awaits_governance_approval() {
+        let mut storage = MockBudgetStorage::new();
+        let contributions = vec![
+            contribution("did:icn:alice", 30.0, 0, 0),
+            contribution("did:icn:bob", 10.0, 0, 0),
+        ];
+
+        let plan = create_patronage_plan(
+            "did:icn:coop",
+            "did:icn:coop:treasury",
+            ResourceType::Compute,
+            400,
+            "2024",
+            &contributions,
+            &PatronageFormula::default(),
+            &RoundingPolicy::default(),
+            &mut storage,
+        ).await.unwrap();
+
+        let stored = load_transfer_plan(&plan.id, &storage).await.unwrap();
+        assert_eq!(stored.status, TransferPlanStatus::Draft);
+        assert_eq!(stored.total_amount(), 400);
+        assert_eq!(stored.transfers[0].amount, 300);
+    }
+}
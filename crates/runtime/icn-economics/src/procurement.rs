@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use crate::{EconomicsError, EconomicsResult, ProposalStatus, ResourceType};
+use crate::budget_ops::{BudgetStorage, load_budget, propose_budget_spend, query_budget_balance};
+use crate::transfer_plan::TransferExecutor;
+use crate::idempotency::{self, DEFAULT_DEDUP_WINDOW_SECS};
+
+/// Storage key prefix for procurement ledgers
+const PROCUREMENT_KEY_PREFIX: &str = "procurement::ledger::";
+
+/// Who a bid comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Vendor {
+    /// A vendor with an identity on the network, paid at its DID
+    Registered { did: String },
+
+    /// A vendor outside the network, paid through the account the scope holds for it
+    External { name: String, contact: String, payout_account: String },
+}
+
+impl Vendor {
+    /// Account an award to this vendor is paid to
+    pub fn payee(&self) -> &str {
+        match self {
+            Vendor::Registered { did } => did,
+            Vendor::External { payout_account, .. } => payout_account,
+        }
+    }
+}
+
+/// Commitment a vendor submits in place of its bid: the hex SHA-256 of the request,
+/// the vendor, the amount, the terms and a salt the vendor keeps until it reveals
+pub fn bid_commitment(
+    request_id: &str,
+    vendor: &Vendor,
+    amount: u64,
+    terms: &str,
+    salt: &str,
+) -> EconomicsResult<String> {
+    let data = serde_json::to_vec(&(request_id, vendor, amount, terms, salt))
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize bid: {}", e)))?;
+    Ok(format!("{:x}", Sha256::digest(&data)))
+}
+
+/// Where a purchase request is in its procurement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcurementStatus {
+    /// Sealed bids are being taken, then revealed
+    Bidding,
+
+    /// A selection proposal is before the budget's voters
+    Selection { proposal_id: Uuid, bid_id: String },
+
+    /// The selection was approved; the award waits to be paid
+    Awarded { proposal_id: Uuid, bid_id: String },
+
+    Paid { bid_id: String, paid_at: i64 },
+
+    /// No valid bid was revealed, or the selection was rejected
+    Cancelled { reason: String },
+}
+
+/// A purchase large enough to need bids, charged to a budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurchaseRequest {
+    pub id: String,
+    pub budget_id: String,
+    pub requester: String,
+    pub description: String,
+    pub resource_type: ResourceType,
+
+    /// Most the purchase may cost; bids above it are refused on reveal
+    pub max_amount: u64,
+
+    /// Account awards are paid from
+    pub treasury_did: String,
+
+    /// Sealed bids are accepted from `bids_open_at` until `bids_close_at`
+    pub bids_open_at: i64,
+    pub bids_close_at: i64,
+
+    /// Bids not revealed by this time are left out of the selection
+    pub reveal_close_at: i64,
+
+    pub status: ProcurementStatus,
+
+    /// Idempotency key of the call that paid the award
+    #[serde(default)]
+    pub payment_key: Option<String>,
+}
+
+/// Details of a purchase being requested
+#[derive(Debug, Clone)]
+pub struct NewPurchaseRequest {
+    pub budget_id: String,
+    pub requester: String,
+    pub description: String,
+    pub resource_type: ResourceType,
+    pub max_amount: u64,
+    pub treasury_did: String,
+    pub bids_open_at: i64,
+    pub bids_close_at: i64,
+    pub reveal_close_at: i64,
+}
+
+/// A bid whose amount and terms open only once it is revealed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevealedBid {
+    pub amount: u64,
+    pub terms: String,
+    pub revealed_at: i64,
+}
+
+/// A vendor's sealed bid on a purchase request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedBid {
+    pub id: String,
+    pub request_id: String,
+    pub vendor: Vendor,
+
+    /// See [`bid_commitment`]
+    pub commitment: String,
+    pub submitted_at: i64,
+    pub revealed: Option<RevealedBid>,
+}
+
+/// One bid as laid out for the voters deciding the selection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BidComparisonLine {
+    pub bid_id: String,
+    pub vendor: Vendor,
+    pub amount: u64,
+    pub terms: String,
+}
+
+/// The revealed bids on a request, cheapest first, attached to its selection proposal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BidComparison {
+    pub request_id: String,
+    pub bids: Vec<BidComparisonLine>,
+
+    /// Sealed bids that were never revealed
+    pub unrevealed: usize,
+}
+
+/// Purchase requests of a scope and the bids on them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcurementLedger {
+    pub scope_id: String,
+    pub requests: Vec<PurchaseRequest>,
+    pub bids: Vec<SealedBid>,
+}
+
+impl ProcurementLedger {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            requests: Vec::new(),
+            bids: Vec::new(),
+        }
+    }
+
+    fn request(&self, request_id: &str) -> EconomicsResult<&PurchaseRequest> {
+        self.requests.iter()
+            .find(|r| r.id == request_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("No purchase request {} in {}", request_id, self.scope_id)
+            ))
+    }
+
+    fn request_mut(&mut self, request_id: &str) -> EconomicsResult<&mut PurchaseRequest> {
+        let scope_id = &self.scope_id;
+        self.requests.iter_mut()
+            .find(|r| r.id == request_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("No purchase request {} in {}", request_id, scope_id)
+            ))
+    }
+
+    /// Bids on a request
+    pub fn bids_for(&self, request_id: &str) -> impl Iterator<Item = &SealedBid> + '_ {
+        let request_id = request_id.to_string();
+        self.bids.iter().filter(move |b| b.request_id == request_id)
+    }
+
+    /// Comparison of the bids revealed on a request
+    pub fn bid_comparison(&self, request_id: &str) -> BidComparison {
+        let mut bids: Vec<BidComparisonLine> = self.bids_for(request_id)
+            .filter_map(|b| b.revealed.as_ref().map(|r| BidComparisonLine {
+                bid_id: b.id.clone(),
+                vendor: b.vendor.clone(),
+                amount: r.amount,
+                terms: r.terms.clone(),
+            }))
+            .collect();
+        // Ties go to the bid submitted first, which the ledger already orders by
+        bids.sort_by_key(|b| b.amount);
+
+        let unrevealed = self.bids_for(request_id).filter(|b| b.revealed.is_none()).count();
+        BidComparison { request_id: request_id.to_string(), bids, unrevealed }
+    }
+}
+
+/// Store a procurement ledger
+pub async fn save_procurement_ledger(
+    ledger: &ProcurementLedger,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(ledger)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize procurement ledger: {}", e)))?;
+
+    let key = format!("{}{}", PROCUREMENT_KEY_PREFIX, ledger.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's procurement ledger; scopes without purchase requests get an empty ledger
+pub async fn load_procurement_ledger(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<ProcurementLedger> {
+    let key = format!("{}{}", PROCUREMENT_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize procurement ledger: {}", e))),
+        None => Ok(ProcurementLedger::new(scope_id)),
+    }
+}
+
+/// Open a purchase request and its bid window. The budget must belong to the scope
+/// and have enough left to cover the most the purchase may cost.
+pub async fn open_purchase_request(
+    scope_id: &str,
+    new_request: NewPurchaseRequest,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<PurchaseRequest> {
+    if new_request.max_amount == 0 {
+        return Err(EconomicsError::InvalidBudget("A purchase request needs a positive maximum amount".to_string()));
+    }
+    if new_request.bids_close_at <= new_request.bids_open_at || new_request.reveal_close_at <= new_request.bids_close_at {
+        return Err(EconomicsError::InvalidBudget(
+            "Bidding must open, then close, then close for reveals, in that order".to_string()
+        ));
+    }
+
+    let budget = load_budget(&new_request.budget_id, storage).await?;
+    if budget.scope_id != scope_id {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Budget {} does not belong to scope {}", new_request.budget_id, scope_id
+        )));
+    }
+    let available = query_budget_balance(&new_request.budget_id, &new_request.resource_type, storage).await?;
+    if new_request.max_amount > available {
+        return Err(EconomicsError::InsufficientBalance(format!(
+            "Purchase of up to {} exceeds available budget balance {}", new_request.max_amount, available
+        )));
+    }
+
+    let request = PurchaseRequest {
+        id: Uuid::new_v4().to_string(),
+        budget_id: new_request.budget_id,
+        requester: new_request.requester,
+        description: new_request.description,
+        resource_type: new_request.resource_type,
+        max_amount: new_request.max_amount,
+        treasury_did: new_request.treasury_did,
+        bids_open_at: new_request.bids_open_at,
+        bids_close_at: new_request.bids_close_at,
+        reveal_close_at: new_request.reveal_close_at,
+        status: ProcurementStatus::Bidding,
+        payment_key: None,
+    };
+
+    let mut ledger = load_procurement_ledger(scope_id, storage).await?;
+    ledger.requests.push(request.clone());
+    save_procurement_ledger(&ledger, storage).await?;
+
+    tracing::info!("Purchase request {} opened by {} for up to {}", request.id, request.requester, request.max_amount);
+
+    Ok(request)
+}
+
+/// Submit a sealed bid while the request's bid window is open. Each vendor bids once.
+/// Returns the bid ID.
+pub async fn submit_sealed_bid(
+    scope_id: &str,
+    request_id: &str,
+    vendor: Vendor,
+    commitment: &str,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut ledger = load_procurement_ledger(scope_id, storage).await?;
+    let request = ledger.request(request_id)?;
+    if request.status != ProcurementStatus::Bidding || at < request.bids_open_at || at >= request.bids_close_at {
+        return Err(EconomicsError::InvalidBudget(format!("Purchase request {} is not taking bids", request_id)));
+    }
+    if commitment.len() != 64 || !commitment.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(EconomicsError::InvalidBudget("A bid commitment must be a hex SHA-256 digest".to_string()));
+    }
+    if ledger.bids_for(request_id).any(|b| b.vendor == vendor) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("{} already bid on purchase request {}", vendor.payee(), request_id)
+        ));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    ledger.bids.push(SealedBid {
+        id: id.clone(),
+        request_id: request_id.to_string(),
+        vendor,
+        commitment: commitment.to_ascii_lowercase(),
+        submitted_at: at,
+        revealed: None,
+    });
+    save_procurement_ledger(&ledger, storage).await?;
+    Ok(id)
+}
+
+/// Reveal a sealed bid once bidding has closed. The amount, terms and salt must hash
+/// to the bid's commitment, and the amount can't exceed the request's maximum.
+pub async fn reveal_bid(
+    scope_id: &str,
+    request_id: &str,
+    bid_id: &str,
+    amount: u64,
+    terms: &str,
+    salt: &str,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let mut ledger = load_procurement_ledger(scope_id, storage).await?;
+    let request = ledger.request(request_id)?;
+    if request.status != ProcurementStatus::Bidding || at < request.bids_close_at || at >= request.reveal_close_at {
+        return Err(EconomicsError::InvalidBudget(format!("Bids on purchase request {} can't be revealed now", request_id)));
+    }
+    if amount == 0 || amount > request.max_amount {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Bids on purchase request {} must be between 1 and {}", request_id, request.max_amount
+        )));
+    }
+
+    let bid = ledger.bids.iter_mut()
+        .find(|b| b.id == bid_id && b.request_id == request_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No bid {} on purchase request {}", bid_id, request_id)))?;
+    if bid.revealed.is_some() {
+        return Err(EconomicsError::InvalidBudget(format!("Bid {} was already revealed", bid_id)));
+    }
+    if bid_commitment(request_id, &bid.vendor, amount, terms, salt)? != bid.commitment {
+        return Err(EconomicsError::Unauthorized(format!("Revealed bid doesn't match the commitment of bid {}", bid_id)));
+    }
+
+    bid.revealed = Some(RevealedBid { amount, terms: terms.to_string(), revealed_at: at });
+    save_procurement_ledger(&ledger, storage).await
+}
+
+/// Once reveals have closed, put the cheapest revealed bid to the budget's voters as a
+/// spend proposal, with the comparison of every revealed bid attached to it. A request
+/// without any revealed bid is cancelled instead. Returns the new status.
+pub async fn propose_selection(
+    scope_id: &str,
+    request_id: &str,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ProcurementStatus> {
+    let mut ledger = load_procurement_ledger(scope_id, storage).await?;
+    let request = ledger.request(request_id)?.clone();
+    if request.status != ProcurementStatus::Bidding || at < request.reveal_close_at {
+        return Err(EconomicsError::InvalidBudget(format!("Purchase request {} is not ready for selection", request_id)));
+    }
+
+    let comparison = ledger.bid_comparison(request_id);
+    let status = match comparison.bids.first() {
+        Some(selected) => {
+            let metadata = serde_json::json!({
+                "procurement_request_id": request_id,
+                "selected_bid_id": selected.bid_id,
+                "bid_comparison": comparison,
+            });
+            let proposal_id = propose_budget_spend(
+                &request.budget_id,
+                &format!("Award: {}", request.description),
+                &format!(
+                    "Award purchase request {} to {} for {}, the lowest of {} revealed bids",
+                    request_id, selected.vendor.payee(), selected.amount, comparison.bids.len()
+                ),
+                HashMap::from([(request.resource_type.clone(), selected.amount)]),
+                &request.requester,
+                None,
+                Some(metadata),
+                storage,
+            ).await?;
+            ProcurementStatus::Selection { proposal_id, bid_id: selected.bid_id.clone() }
+        }
+        None => ProcurementStatus::Cancelled { reason: "No bids were revealed".to_string() },
+    };
+
+    ledger.request_mut(request_id)?.status = status.clone();
+    save_procurement_ledger(&ledger, storage).await?;
+    Ok(status)
+}
+
+/// Settle a request's selection once its proposal has been decided: an executed proposal
+/// awards the bid, a rejected one cancels the request. Returns the new status.
+pub async fn award_bid(
+    scope_id: &str,
+    request_id: &str,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ProcurementStatus> {
+    let mut ledger = load_procurement_ledger(scope_id, storage).await?;
+    let request = ledger.request(request_id)?;
+    let (proposal_id, bid_id) = match &request.status {
+        ProcurementStatus::Selection { proposal_id, bid_id } => (*proposal_id, bid_id.clone()),
+        status => return Err(EconomicsError::InvalidBudget(format!(
+            "Purchase request {} has no selection to award: {:?}", request_id, status
+        ))),
+    };
+
+    let budget = load_budget(&request.budget_id, storage).await?;
+    let proposal = budget.proposals.get(&proposal_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("Proposal not found with id: {}", proposal_id)))?;
+    let status = match proposal.status {
+        ProposalStatus::Executed => ProcurementStatus::Awarded { proposal_id, bid_id },
+        ProposalStatus::Rejected | ProposalStatus::Failed | ProposalStatus::Cancelled => ProcurementStatus::Cancelled {
+            reason: format!("Selection proposal {} was {:?}", proposal_id, proposal.status),
+        },
+        ref status => return Err(EconomicsError::InvalidBudget(format!(
+            "Selection proposal {} has not been decided: {:?}", proposal_id, status
+        ))),
+    };
+
+    ledger.request_mut(request_id)?.status = status.clone();
+    save_procurement_ledger(&ledger, storage).await?;
+    Ok(status)
+}
+
+/// Pay an awarded bid from the request's treasury. The spend was already charged to the
+/// budget when its proposal executed. A retried call with the same `idempotency_key`
+/// returns the amount paid instead of paying again.
+pub async fn pay_award(
+    scope_id: &str,
+    request_id: &str,
+    idempotency_key: &str,
+    executor: &mut impl TransferExecutor,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<u64> {
+    let request_hash = idempotency::request_fingerprint(&request_id)?;
+    if let Some(paid) = idempotency::replay_result(
+        scope_id, idempotency_key, "pay_award", &request_hash, at, storage,
+    ).await? {
+        return Ok(paid);
+    }
+
+    let mut ledger = load_procurement_ledger(scope_id, storage).await?;
+    let request = ledger.request(request_id)?.clone();
+    let bid_id = match &request.status {
+        ProcurementStatus::Awarded { bid_id, .. } => bid_id.clone(),
+        status => return Err(EconomicsError::Unauthorized(format!(
+            "Purchase request {} has not been awarded: {:?}", request_id, status
+        ))),
+    };
+    let bid = ledger.bids.iter()
+        .find(|b| b.id == bid_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No bid {} on purchase request {}", bid_id, request_id)))?;
+    let amount = bid.revealed.as_ref()
+        .map(|r| r.amount)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("Bid {} was never revealed", bid_id)))?;
+
+    executor.execute_transfer(&request.treasury_did, bid.vendor.payee(), &request.resource_type, amount).await?;
+
+    let stored = ledger.request_mut(request_id)?;
+    stored.status = ProcurementStatus::Paid { bid_id, paid_at: at };
+    stored.payment_key = Some(idempotency_key.to_string());
+    save_procurement_ledger(&ledger, storage).await?;
+    idempotency::remember_result(
+        scope_id, idempotency_key, "pay_award", &request_hash, &amount, at, DEFAULT_DEDUP_WINDOW_SECS, storage,
+    ).await?;
+
+    tracing::info!("Purchase request {} paid {} from {}", request_id, amount, request.treasury_did);
+
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::IdentityScope;
+    use crate::VoteChoice;
+    use crate::budget_ops::{MockBudgetStorage, create_budget, allocate_to_budget, record_budget_vote, finalize_budget_proposal};
+    use crate::transfer_plan::MockTransferExecutor;
+
+    const SCOPE: &str = "did:icn:coop";
+
+    #[tokio::test]
+    async fn test_sealed_bids_selection_award_and_payment() {
+        let mut storage = MockBudgetStorage::new();
+        let budget_id = create_budget("Equipment", SCOPE, IdentityScope::Cooperative, 0, i64::MAX, None, &mut storage)
+            .await.unwrap();
+        allocate_to_budget(&budget_id, ResourceType::Compute, 1000, &mut storage).await.unwrap();
+
+        let request = open_purchase_request(SCOPE, NewPurchaseRequest {
+            budget_id: budget_id.clone(),
+            requester: "did:icn:alice".to_string(),
+            description: "Server rack".to_string(),
+            resource_type: ResourceType::Compute,
+            max_amount: 800,
+            treasury_did: "did:icn:coop:treasury".to_string(),
+            bids_open_at: 0,
+            bids_close_at: 100,
+            reveal_close_at: 200,
+        }, &mut storage).await.unwrap();
+
+        let acme = Vendor::Registered { did: "did:icn:acme".to_string() };
+        let local = Vendor::External {
+            name: "Local Hardware".to_string(),
+            contact: "sales@local.example".to_string(),
+            payout_account: "did:icn:coop:payables:local".to_string(),
+        };
+        let acme_bid = submit_sealed_bid(SCOPE, &request.id, acme.clone(),
+            &bid_commitment(&request.id, &acme, 700, "Net 30", "acme-salt").unwrap(), 10, &mut storage).await.unwrap();
+        let local_bid = submit_sealed_bid(SCOPE, &request.id, local.clone(),
+            &bid_commitment(&request.id, &local, 650, "On delivery", "local-salt").unwrap(), 20, &mut storage).await.unwrap();
+        assert!(submit_sealed_bid(SCOPE, &request.id, acme.clone(), &"0".repeat(64), 30, &mut storage).await.is_err());
+
+        // Nothing opens before bidding closes, and a bid must open to its commitment
+        assert!(reveal_bid(SCOPE, &request.id, &acme_bid, 700, "Net 30", "acme-salt", 50, &mut storage).await.is_err());
+        assert!(reveal_bid(SCOPE, &request.id, &acme_bid, 600, "Net 30", "acme-salt", 150, &mut storage).await.is_err());
+        reveal_bid(SCOPE, &request.id, &acme_bid, 700, "Net 30", "acme-salt", 150, &mut storage).await.unwrap();
+        reveal_bid(SCOPE, &request.id, &local_bid, 650, "On delivery", "local-salt", 160, &mut storage).await.unwrap();
+
+        assert!(propose_selection(SCOPE, &request.id, 180, &mut storage).await.is_err());
+        let ProcurementStatus::Selection { proposal_id, bid_id } = propose_selection(SCOPE, &request.id, 200, &mut storage)
+            .await.unwrap() else { panic!("expected a selection proposal") };
+        assert_eq!(bid_id, local_bid);
+
+        let budget = load_budget(&budget_id, &storage).await.unwrap();
+        let metadata = budget.proposals[&proposal_id].metadata.clone().unwrap();
+        let comparison: BidComparison = serde_json::from_value(metadata["bid_comparison"].clone()).unwrap();
+        assert_eq!(comparison.bids.iter().map(|b| b.amount).collect::<Vec<_>>(), vec![650, 700]);
+
+        let mut executor = MockTransferExecutor::default();
+        executor.balances.insert("did:icn:coop:treasury".to_string(), 1000);
+        assert!(award_bid(SCOPE, &request.id, &mut storage).await.is_err());
+        assert!(pay_award(SCOPE, &request.id, "rack-1", &mut executor, 300, &mut storage).await.is_err());
+
+        record_budget_vote(&budget_id, proposal_id, "did:icn:bob".to_string(), VoteChoice::Approve, &mut storage).await.unwrap();
+        finalize_budget_proposal(&budget_id, proposal_id, &mut storage).await.unwrap();
+        assert!(matches!(award_bid(SCOPE, &request.id, &mut storage).await.unwrap(), ProcurementStatus::Awarded { .. }));
+        assert_eq!(query_budget_balance(&budget_id, &ResourceType::Compute, &storage).await.unwrap(), 350);
+
+        assert_eq!(pay_award(SCOPE, &request.id, "rack-1", &mut executor, 300, &mut storage).await.unwrap(), 650);
+        assert_eq!(pay_award(SCOPE, &request.id, "rack-1", &mut executor, 300, &mut storage).await.unwrap(), 650);
+        assert_eq!(executor.balances["did:icn:coop:payables:local"], 650);
+        assert_eq!(executor.balances["did:icn:coop:treasury"], 350);
+    }
+}
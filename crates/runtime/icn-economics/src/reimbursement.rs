@@ -0,0 +1,632 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use cid::Cid;
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::{BudgetStorage, load_budget, save_budget, query_budget_balance};
+use crate::transfer_plan::{PlannedTransfer, TransferPlan, TransferPlanStatus, save_transfer_plan, load_transfer_plan};
+
+/// Storage key prefix for expense claims
+const CLAIM_KEY_PREFIX: &str = "reimbursement::claim::";
+
+/// Storage key prefix for the per-scope claim index
+const CLAIM_INDEX_KEY_PREFIX: &str = "reimbursement::index::";
+
+/// Storage key prefix for period-close statements
+const STATEMENT_KEY_PREFIX: &str = "reimbursement::statement::";
+
+/// An approval tier: claims up to `max_amount` need `required_approvals` approvals from
+/// holders of `approver_role`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalTier {
+    /// Largest claim amount covered by this tier (None = no upper bound)
+    pub max_amount: Option<u64>,
+
+    /// Role an approver must hold (e.g., "treasurer", "board")
+    pub approver_role: String,
+
+    /// Number of distinct approvals required
+    pub required_approvals: usize,
+}
+
+/// Policy governing expense claims in a scope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReimbursementPolicy {
+    /// Approval tiers, checked in ascending order of `max_amount`
+    pub tiers: Vec<ApprovalTier>,
+
+    /// Claims above this amount must attach at least one receipt
+    pub receipt_required_above: u64,
+}
+
+impl Default for ReimbursementPolicy {
+    fn default() -> Self {
+        Self {
+            tiers: vec![
+                ApprovalTier { max_amount: Some(500), approver_role: "treasurer".to_string(), required_approvals: 1 },
+                ApprovalTier { max_amount: Some(5000), approver_role: "treasurer".to_string(), required_approvals: 2 },
+                ApprovalTier { max_amount: None, approver_role: "board".to_string(), required_approvals: 3 },
+            ],
+            receipt_required_above: 0,
+        }
+    }
+}
+
+impl ReimbursementPolicy {
+    /// The tier that applies to a claim amount
+    pub fn tier_for(&self, amount: u64) -> EconomicsResult<&ApprovalTier> {
+        let mut tiers: Vec<&ApprovalTier> = self.tiers.iter().collect();
+        tiers.sort_by_key(|t| t.max_amount.unwrap_or(u64::MAX));
+        tiers.into_iter()
+            .find(|t| t.max_amount.map_or(true, |max| amount <= max))
+            .ok_or_else(|| EconomicsError::Unauthorized(
+                format!("No approval tier covers a claim of {}", amount)
+            ))
+    }
+}
+
+/// Status of an expense claim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClaimStatus {
+    /// Awaiting approvals
+    Submitted,
+
+    /// Approved and waiting to be scheduled for payment
+    Approved,
+
+    /// Rejected by an approver
+    Rejected { reason: String },
+
+    /// Included in a reimbursement transfer plan
+    Scheduled { plan_id: Uuid },
+
+    /// Paid out
+    Paid { paid_at: i64 },
+}
+
+/// An approval recorded against a claim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaimApproval {
+    /// The approving DID
+    pub approver_did: String,
+
+    /// The role the approval was given under
+    pub role: String,
+
+    /// Unix timestamp of the approval
+    pub approved_at: i64,
+}
+
+/// A member's request to be reimbursed for an expense
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpenseClaim {
+    /// Unique identifier for this claim
+    pub id: Uuid,
+
+    /// The DID of the governing Coop/Community
+    pub scope_id: String,
+
+    /// The budget the expense is charged to
+    pub budget_id: String,
+
+    /// Budget category the expense falls under
+    pub category: String,
+
+    /// The DID of the member to reimburse
+    pub claimant_did: String,
+
+    /// What the expense was for
+    pub description: String,
+
+    /// The resource the claim is paid in
+    pub resource_type: ResourceType,
+
+    /// Amount to reimburse
+    pub amount: u64,
+
+    /// CIDs of anchored receipt blobs
+    pub receipt_cids: Vec<String>,
+
+    /// Unix timestamp when the expense was incurred
+    pub incurred_at: i64,
+
+    /// Unix timestamp when the claim was submitted
+    pub submitted_at: i64,
+
+    /// Approvals collected so far
+    pub approvals: Vec<ClaimApproval>,
+
+    /// Current status
+    pub status: ClaimStatus,
+}
+
+/// Details of an expense being claimed
+#[derive(Debug, Clone)]
+pub struct NewExpenseClaim {
+    pub scope_id: String,
+    pub budget_id: String,
+    pub category: String,
+    pub claimant_did: String,
+    pub description: String,
+    pub resource_type: ResourceType,
+    pub amount: u64,
+    pub receipt_cids: Vec<String>,
+    pub incurred_at: i64,
+}
+
+/// A paid or outstanding claim as listed in a statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub claim_id: Uuid,
+    pub claimant_did: String,
+    pub budget_id: String,
+    pub category: String,
+    pub amount: u64,
+    pub receipt_cids: Vec<String>,
+}
+
+/// Reimbursement section of a period-close statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReimbursementStatement {
+    /// The DID of the governing Coop/Community
+    pub scope_id: String,
+
+    /// Start of the period (Unix timestamp, inclusive)
+    pub period_start: i64,
+
+    /// End of the period (Unix timestamp, exclusive)
+    pub period_end: i64,
+
+    /// Claims paid during the period
+    pub paid: Vec<StatementLine>,
+
+    /// Claims submitted during the period that are not yet paid or rejected
+    pub outstanding: Vec<StatementLine>,
+
+    /// Number of claims submitted during the period that were rejected
+    pub rejected_count: usize,
+
+    /// Paid totals by budget category
+    pub paid_by_category: HashMap<String, u64>,
+
+    /// Paid totals by budget
+    pub paid_by_budget: HashMap<String, u64>,
+
+    /// Total paid during the period
+    pub total_paid: u64,
+}
+
+/// Store an expense claim
+pub async fn save_expense_claim(
+    claim: &ExpenseClaim,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(claim)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize expense claim: {}", e)))?;
+
+    let key = format!("{}{}", CLAIM_KEY_PREFIX, claim.id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load an expense claim by ID
+pub async fn load_expense_claim(
+    claim_id: &Uuid,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<ExpenseClaim> {
+    let key = format!("{}{}", CLAIM_KEY_PREFIX, claim_id);
+    let data = storage.get_budget(&key).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("Expense claim not found: {}", claim_id)))?;
+
+    serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize expense claim: {}", e)))
+}
+
+/// List every claim submitted in a scope
+pub async fn list_expense_claims(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Vec<ExpenseClaim>> {
+    let mut claims = Vec::new();
+    for claim_id in load_claim_index(scope_id, storage).await? {
+        claims.push(load_expense_claim(&claim_id, storage).await?);
+    }
+    Ok(claims)
+}
+
+async fn load_claim_index(scope_id: &str, storage: &impl BudgetStorage) -> EconomicsResult<Vec<Uuid>> {
+    let key = format!("{}{}", CLAIM_INDEX_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize claim index: {}", e))),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Submit an expense claim against a budget.
+///
+/// Receipts must be valid CIDs of anchored blobs, the category must exist in the budget's
+/// rules (if it defines any), and the budget must have enough left to cover the claim.
+pub async fn submit_expense_claim(
+    new_claim: NewExpenseClaim,
+    policy: &ReimbursementPolicy,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ExpenseClaim> {
+    if new_claim.amount == 0 {
+        return Err(EconomicsError::InvalidBudget("Expense claim amount must be positive".to_string()));
+    }
+
+    if new_claim.amount > policy.receipt_required_above && new_claim.receipt_cids.is_empty() {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Claims above {} must attach a receipt", policy.receipt_required_above
+        )));
+    }
+    for receipt in &new_claim.receipt_cids {
+        Cid::try_from(receipt.as_str())
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Invalid receipt CID {}: {}", receipt, e)))?;
+    }
+
+    // Make sure some tier can approve this claim before accepting it
+    policy.tier_for(new_claim.amount)?;
+
+    let budget = load_budget(&new_claim.budget_id, storage).await?;
+    if budget.scope_id != new_claim.scope_id {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Budget {} does not belong to scope {}", new_claim.budget_id, new_claim.scope_id
+        )));
+    }
+    if let Some(categories) = budget.rules.as_ref().and_then(|r| r.categories.as_ref()) {
+        if !categories.contains_key(&new_claim.category) {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "Budget {} has no category {}", new_claim.budget_id, new_claim.category
+            )));
+        }
+    }
+
+    let available = query_budget_balance(&new_claim.budget_id, &new_claim.resource_type, storage).await?;
+    if new_claim.amount > available {
+        return Err(EconomicsError::InsufficientBalance(format!(
+            "Claim of {} exceeds available budget balance {}", new_claim.amount, available
+        )));
+    }
+
+    let claim = ExpenseClaim {
+        id: Uuid::new_v4(),
+        scope_id: new_claim.scope_id,
+        budget_id: new_claim.budget_id,
+        category: new_claim.category,
+        claimant_did: new_claim.claimant_did,
+        description: new_claim.description,
+        resource_type: new_claim.resource_type,
+        amount: new_claim.amount,
+        receipt_cids: new_claim.receipt_cids,
+        incurred_at: new_claim.incurred_at,
+        submitted_at: chrono::Utc::now().timestamp(),
+        approvals: Vec::new(),
+        status: ClaimStatus::Submitted,
+    };
+
+    save_expense_claim(&claim, storage).await?;
+
+    let mut index = load_claim_index(&claim.scope_id, storage).await?;
+    index.push(claim.id);
+    let index_data = serde_json::to_vec(&index)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize claim index: {}", e)))?;
+    storage.store_budget(&format!("{}{}", CLAIM_INDEX_KEY_PREFIX, claim.scope_id), index_data).await?;
+
+    tracing::info!("Expense claim {} submitted by {} for {}", claim.id, claim.claimant_did, claim.amount);
+
+    Ok(claim)
+}
+
+/// Record an approval of a claim.
+///
+/// The approver must hold the role required by the claim's tier and may not approve
+/// their own claim. The claim becomes `Approved` once the tier's approval count is met.
+pub async fn approve_expense_claim(
+    claim_id: &Uuid,
+    approver_did: &str,
+    approver_roles: &[String],
+    policy: &ReimbursementPolicy,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ClaimStatus> {
+    let mut claim = load_expense_claim(claim_id, storage).await?;
+
+    if claim.status != ClaimStatus::Submitted {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Expense claim {} is not awaiting approval: {:?}", claim_id, claim.status
+        )));
+    }
+    if claim.claimant_did == approver_did {
+        return Err(EconomicsError::Unauthorized("Claimants cannot approve their own expense claims".to_string()));
+    }
+
+    let tier = policy.tier_for(claim.amount)?;
+    if !approver_roles.contains(&tier.approver_role) {
+        return Err(EconomicsError::Unauthorized(format!(
+            "Claims of {} require approval by role {}", claim.amount, tier.approver_role
+        )));
+    }
+    if claim.approvals.iter().any(|a| a.approver_did == approver_did) {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "{} has already approved expense claim {}", approver_did, claim_id
+        )));
+    }
+
+    claim.approvals.push(ClaimApproval {
+        approver_did: approver_did.to_string(),
+        role: tier.approver_role.clone(),
+        approved_at: chrono::Utc::now().timestamp(),
+    });
+
+    if claim.approvals.len() >= tier.required_approvals {
+        claim.status = ClaimStatus::Approved;
+    }
+
+    save_expense_claim(&claim, storage).await?;
+
+    Ok(claim.status)
+}
+
+/// Reject a claim that is awaiting approval
+pub async fn reject_expense_claim(
+    claim_id: &Uuid,
+    reason: &str,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let mut claim = load_expense_claim(claim_id, storage).await?;
+
+    if !matches!(claim.status, ClaimStatus::Submitted | ClaimStatus::Approved) {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Expense claim {} can no longer be rejected: {:?}", claim_id, claim.status
+        )));
+    }
+
+    claim.status = ClaimStatus::Rejected { reason: reason.to_string() };
+    save_expense_claim(&claim, storage).await
+}
+
+/// Gather approved claims paid in `resource_type` into a draft reimbursement transfer plan
+/// from the treasury.
+///
+/// The plan goes through the same governance approval and execution as any other
+/// transfer plan; call [`settle_reimbursement_plan`] once it has been executed.
+pub async fn create_reimbursement_plan(
+    scope_id: &str,
+    treasury_did: &str,
+    resource_type: ResourceType,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<TransferPlan> {
+    let claims: Vec<ExpenseClaim> = list_expense_claims(scope_id, storage).await?
+        .into_iter()
+        .filter(|c| c.status == ClaimStatus::Approved && c.resource_type == resource_type)
+        .collect();
+
+    if claims.is_empty() {
+        return Err(EconomicsError::InvalidTransferPlan(format!(
+            "No approved expense claims to reimburse in scope {}", scope_id
+        )));
+    }
+
+    let transfers = claims.iter()
+        .map(|claim| PlannedTransfer {
+            recipient_did: claim.claimant_did.clone(),
+            amount: claim.amount,
+            memo: Some(format!("Reimbursement {}: {}", claim.id, claim.description)),
+        })
+        .collect();
+
+    let claim_ids: Vec<Uuid> = claims.iter().map(|c| c.id).collect();
+    let metadata = serde_json::json!({ "claim_ids": claim_ids });
+
+    let plan = TransferPlan::new(scope_id, treasury_did, resource_type, "reimbursement", transfers, Some(metadata));
+    save_transfer_plan(&plan, storage).await?;
+
+    for mut claim in claims {
+        claim.status = ClaimStatus::Scheduled { plan_id: plan.id };
+        save_expense_claim(&claim, storage).await?;
+    }
+
+    tracing::info!("Created reimbursement plan {} covering {} claims", plan.id, claim_ids.len());
+
+    Ok(plan)
+}
+
+/// Mark the claims of an executed reimbursement plan as paid and charge them to their budgets.
+///
+/// If the plan was rejected, its claims return to `Approved` so they can be rescheduled.
+pub async fn settle_reimbursement_plan(
+    plan_id: &Uuid,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<Vec<Uuid>> {
+    let plan = load_transfer_plan(plan_id, storage).await?;
+
+    let claim_ids: Vec<Uuid> = plan.metadata.as_ref()
+        .and_then(|m| m.get("claim_ids"))
+        .and_then(|ids| serde_json::from_value(ids.clone()).ok())
+        .ok_or_else(|| EconomicsError::InvalidTransferPlan(format!(
+            "Transfer plan {} is not a reimbursement plan", plan_id
+        )))?;
+
+    let paid_at = match plan.status {
+        TransferPlanStatus::Executed { executed_at } => Some(executed_at),
+        TransferPlanStatus::Rejected { .. } => None,
+        status => return Err(EconomicsError::InvalidTransferPlan(format!(
+            "Reimbursement plan {} has not been executed: {:?}", plan_id, status
+        ))),
+    };
+
+    for claim_id in &claim_ids {
+        let mut claim = load_expense_claim(claim_id, storage).await?;
+        if claim.status != (ClaimStatus::Scheduled { plan_id: *plan_id }) {
+            continue;
+        }
+
+        match paid_at {
+            Some(paid_at) => {
+                // Record the spend against the budget so its balance reflects the payout
+                let mut budget = load_budget(&claim.budget_id, storage).await?;
+                budget.spent_by_proposal.entry(claim.id)
+                    .or_default()
+                    .insert(claim.resource_type.clone(), claim.amount);
+                save_budget(&budget, storage).await?;
+
+                claim.status = ClaimStatus::Paid { paid_at };
+            }
+            None => claim.status = ClaimStatus::Approved,
+        }
+        save_expense_claim(&claim, storage).await?;
+    }
+
+    Ok(claim_ids)
+}
+
+/// Build and store the reimbursement section of a period-close statement
+pub async fn close_reimbursement_period(
+    scope_id: &str,
+    period_start: i64,
+    period_end: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ReimbursementStatement> {
+    if period_end <= period_start {
+        return Err(EconomicsError::InvalidBudget("Period end must be after its start".to_string()));
+    }
+
+    let in_period = |ts: i64| ts >= period_start && ts < period_end;
+    let line = |claim: &ExpenseClaim| StatementLine {
+        claim_id: claim.id,
+        claimant_did: claim.claimant_did.clone(),
+        budget_id: claim.budget_id.clone(),
+        category: claim.category.clone(),
+        amount: claim.amount,
+        receipt_cids: claim.receipt_cids.clone(),
+    };
+
+    let mut statement = ReimbursementStatement {
+        scope_id: scope_id.to_string(),
+        period_start,
+        period_end,
+        paid: Vec::new(),
+        outstanding: Vec::new(),
+        rejected_count: 0,
+        paid_by_category: HashMap::new(),
+        paid_by_budget: HashMap::new(),
+        total_paid: 0,
+    };
+
+    for claim in list_expense_claims(scope_id, storage).await? {
+        match &claim.status {
+            ClaimStatus::Paid { paid_at } if in_period(*paid_at) => {
+                *statement.paid_by_category.entry(claim.category.clone()).or_insert(0) += claim.amount;
+                *statement.paid_by_budget.entry(claim.budget_id.clone()).or_insert(0) += claim.amount;
+                statement.total_paid += claim.amount;
+                statement.paid.push(line(&claim));
+            }
+            ClaimStatus::Rejected { .. } if in_period(claim.submitted_at) => statement.rejected_count += 1,
+            ClaimStatus::Submitted | ClaimStatus::Approved | ClaimStatus::Scheduled { .. }
+                if claim.submitted_at < period_end => statement.outstanding.push(line(&claim)),
+            _ => {}
+        }
+    }
+
+    let data = serde_json::to_vec(&statement)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize statement: {}", e)))?;
+    storage.store_budget(&format!("{}{}::{}", STATEMENT_KEY_PREFIX, scope_id, period_end), data).await?;
+
+    Ok(statement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::IdentityScope;
+    use crate::budget_ops::{MockBudgetStorage, create_budget, allocate_to_budget};
+    use crate::transfer_plan::{MockTransferExecutor, submit_transfer_plan, record_transfer_plan_decision, execute_transfer_plan};
+
+    const RECEIPT_CID: &str = "bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy";
+
+    async fn setup_budget(storage: &mut MockBudgetStorage) -> String {
+        let budget_id = create_budget("Operations", "did:icn:coop", IdentityScope::Cooperative, 0, i64::MAX, None, storage)
+            .await.unwrap();
+        allocate_to_budget(&budget_id, ResourceType::Compute, 1000, storage).await.unwrap();
+        budget_id
+    }
+
+    fn new_claim(budget_id: &str, amount: u64) -> NewExpenseClaim {
+        NewExpenseClaim {
+            scope_id: "did:icn:coop".to_string(),
+            budget_id: budget_id.to_string(),
+            category: "supplies".to_string(),
+            claimant_did: "did:icn:alice".to_string(),
+            description: "Printer paper".to_string(),
+            resource_type: ResourceType::Compute,
+            amount,
+            receipt_cids: vec![RECEIPT_CID.to_string()],
+            incurred_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_claim_validation_and_tiered_approval() {
+        let mut storage = MockBudgetStorage::new();
+        let budget_id = setup_budget(&mut storage).await;
+        let policy = ReimbursementPolicy::default();
+
+        // Receipts are mandatory and must be CIDs
+        let mut missing_receipt = new_claim(&budget_id, 100);
+        missing_receipt.receipt_cids.clear();
+        assert!(submit_expense_claim(missing_receipt, &policy, &mut storage).await.is_err());
+        let mut bad_receipt = new_claim(&budget_id, 100);
+        bad_receipt.receipt_cids = vec!["not-a-cid".to_string()];
+        assert!(submit_expense_claim(bad_receipt, &policy, &mut storage).await.is_err());
+
+        // Claims can't exceed what is left in the budget
+        assert!(submit_expense_claim(new_claim(&budget_id, 2000), &policy, &mut storage).await.is_err());
+
+        // A claim in the second tier needs two treasurer approvals
+        let claim = submit_expense_claim(new_claim(&budget_id, 800), &policy, &mut storage).await.unwrap();
+        let treasurer = vec!["treasurer".to_string()];
+
+        assert!(approve_expense_claim(&claim.id, "did:icn:alice", &treasurer, &policy, &mut storage).await.is_err());
+        assert!(approve_expense_claim(&claim.id, "did:icn:bob", &[], &policy, &mut storage).await.is_err());
+
+        let status = approve_expense_claim(&claim.id, "did:icn:bob", &treasurer, &policy, &mut storage).await.unwrap();
+        assert_eq!(status, ClaimStatus::Submitted);
+        assert!(approve_expense_claim(&claim.id, "did:icn:bob", &treasurer, &policy, &mut storage).await.is_err());
+        let status = approve_expense_claim(&claim.id, "did:icn:carol", &treasurer, &policy, &mut storage).await.unwrap();
+        assert_eq!(status, ClaimStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn test_reimbursement_paid_and_reported() {
+        let mut storage = MockBudgetStorage::new();
+        let budget_id = setup_budget(&mut storage).await;
+        let policy = ReimbursementPolicy::default();
+        let treasurer = vec!["treasurer".to_string()];
+
+        let claim = submit_expense_claim(new_claim(&budget_id, 300), &policy, &mut storage).await.unwrap();
+        approve_expense_claim(&claim.id, "did:icn:bob", &treasurer, &policy, &mut storage).await.unwrap();
+        let pending = submit_expense_claim(new_claim(&budget_id, 50), &policy, &mut storage).await.unwrap();
+
+        let plan = create_reimbursement_plan("did:icn:coop", "did:icn:coop:treasury", ResourceType::Compute, &mut storage)
+            .await.unwrap();
+        assert_eq!(plan.total_amount(), 300);
+
+        submit_transfer_plan(&plan.id, &mut storage).await.unwrap();
+        record_transfer_plan_decision(&plan.id, "proposal:reimburse-q1", true, &mut storage).await.unwrap();
+
+        let mut executor = MockTransferExecutor::default();
+        executor.balances.insert("did:icn:coop:treasury".to_string(), 1000);
+        execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.unwrap();
+
+        settle_reimbursement_plan(&plan.id, &mut storage).await.unwrap();
+        assert!(matches!(load_expense_claim(&claim.id, &storage).await.unwrap().status, ClaimStatus::Paid { .. }));
+        assert_eq!(query_budget_balance(&budget_id, &ResourceType::Compute, &storage).await.unwrap(), 700);
+
+        let statement = close_reimbursement_period("did:icn:coop", 0, i64::MAX, &mut storage).await.unwrap();
+        assert_eq!(statement.total_paid, 300);
+        assert_eq!(statement.paid_by_category.get("supplies"), Some(&300));
+        assert_eq!(statement.outstanding.len(), 1);
+        assert_eq!(statement.outstanding[0].claim_id, pending.id);
+    }
+}
@@ -0,0 +1,342 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::{self, BudgetStorage};
+use crate::statements::{self, TreasuryEntry, TreasuryFlow};
+
+/// Storage key prefix for a scope's conditional allocations
+const CONDITIONAL_KEY_PREFIX: &str = "treasury::conditional_allocations::";
+
+/// Basis points in a whole
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Revenue an allocation waits for: inflows to the scope's treasury journal matching
+/// every filter that is set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevenueTrigger {
+    /// Journal category the revenue is recognized under (e.g., "grants")
+    pub category: Option<String>,
+
+    /// External reference of the revenue (e.g., the grant's award ID)
+    pub reference: Option<String>,
+
+    /// Matching inflows must add up to at least this much; any matching inflow
+    /// triggers when zero
+    pub threshold: u64,
+}
+
+impl RevenueTrigger {
+    /// Whether a journal entry counts towards the trigger
+    pub fn matches(&self, entry: &TreasuryEntry) -> bool {
+        entry.flow == TreasuryFlow::Inflow
+            && self.category.as_ref().map_or(true, |c| &entry.category == c)
+            && self.reference.as_ref().map_or(true, |r| entry.reference.as_ref() == Some(r))
+    }
+}
+
+/// How much an allocation releases once triggered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AllocationShare {
+    /// A fixed amount
+    Fixed(u64),
+    /// A share of the matching revenue, in basis points (5000 = 50%)
+    OfRevenue { bps: u32 },
+}
+
+impl AllocationShare {
+    /// Amount released for the given matching revenue
+    pub fn amount(&self, revenue: u64) -> u64 {
+        match self {
+            AllocationShare::Fixed(amount) => *amount,
+            AllocationShare::OfRevenue { bps } => {
+                (revenue as u128 * *bps as u128 / BPS_DENOMINATOR as u128) as u64
+            }
+        }
+    }
+}
+
+/// Where a conditional allocation stands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionalAllocationStatus {
+    /// Waiting for its revenue
+    Pending,
+    /// The revenue arrived and the amount was allocated to the budget
+    Activated {
+        amount: u64,
+        activated_at: i64,
+        /// Journal entries that met the trigger
+        entry_ids: Vec<String>,
+    },
+    Cancelled,
+}
+
+/// A budget allocation that only takes effect once revenue is recognized
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConditionalAllocation {
+    pub id: String,
+    pub scope_id: String,
+    pub budget_id: String,
+    pub resource: ResourceType,
+    pub trigger: RevenueTrigger,
+    pub share: AllocationShare,
+
+    /// Most the allocation may release, whatever the revenue
+    pub cap: Option<u64>,
+
+    pub created_by: String,
+    pub created_at: i64,
+    pub status: ConditionalAllocationStatus,
+}
+
+impl ConditionalAllocation {
+    /// The amount to allocate for the journal's matching inflows, with the IDs of the
+    /// entries that met the trigger, or None while the trigger isn't met
+    pub fn evaluate(&self, entries: &[TreasuryEntry]) -> Option<(u64, Vec<String>)> {
+        let matching: Vec<&TreasuryEntry> = entries.iter().filter(|e| self.trigger.matches(e)).collect();
+        if matching.is_empty() {
+            return None;
+        }
+        let revenue: u64 = matching.iter().map(|e| e.amount).sum();
+        if revenue < self.trigger.threshold {
+            return None;
+        }
+
+        let amount = self.share.amount(revenue);
+        let amount = self.cap.map_or(amount, |cap| amount.min(cap));
+        Some((amount, matching.iter().map(|e| e.id.clone()).collect()))
+    }
+}
+
+/// Every conditional allocation of a scope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConditionalAllocations {
+    pub scope_id: String,
+    pub allocations: Vec<ConditionalAllocation>,
+}
+
+/// Notified when a conditional allocation activates, e.g. to tell the budget's members
+#[async_trait]
+pub trait AllocationActivationHook: Send + Sync {
+    async fn allocation_activated(&self, allocation: &ConditionalAllocation) -> EconomicsResult<()>;
+}
+
+/// Save a scope's conditional allocations
+pub async fn save_conditional_allocations(
+    allocations: &ConditionalAllocations,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(allocations)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize conditional allocations: {}", e)))?;
+
+    let key = format!("{}{}", CONDITIONAL_KEY_PREFIX, allocations.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's conditional allocations
+pub async fn load_conditional_allocations(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<ConditionalAllocations> {
+    let key = format!("{}{}", CONDITIONAL_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize conditional allocations: {}", e))),
+        None => Ok(ConditionalAllocations { scope_id: scope_id.to_string(), allocations: Vec::new() }),
+    }
+}
+
+/// Bind an allocation to one of the scope's budgets that activates once its revenue
+/// is recognized. Returns the allocation ID.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_conditional_allocation(
+    scope_id: &str,
+    budget_id: &str,
+    resource: ResourceType,
+    trigger: RevenueTrigger,
+    share: AllocationShare,
+    cap: Option<u64>,
+    created_by: &str,
+    created_at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let budget = budget_ops::load_budget(budget_id, storage).await?;
+    if budget.scope_id != scope_id {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Budget {} does not belong to {}", budget_id, scope_id
+        )));
+    }
+    match share {
+        AllocationShare::Fixed(0) | AllocationShare::OfRevenue { bps: 0 } => {
+            return Err(EconomicsError::InvalidBudget("Conditional allocation must release a positive amount".to_string()));
+        }
+        AllocationShare::OfRevenue { bps } if bps as u64 > BPS_DENOMINATOR => {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "Conditional allocation can't release more than all of its revenue ({} bps)", bps
+            )));
+        }
+        _ => {}
+    }
+
+    let mut allocations = load_conditional_allocations(scope_id, storage).await?;
+    let id = Uuid::new_v4().to_string();
+    allocations.allocations.push(ConditionalAllocation {
+        id: id.clone(),
+        scope_id: scope_id.to_string(),
+        budget_id: budget_id.to_string(),
+        resource,
+        trigger,
+        share,
+        cap,
+        created_by: created_by.to_string(),
+        created_at,
+        status: ConditionalAllocationStatus::Pending,
+    });
+    save_conditional_allocations(&allocations, storage).await?;
+    Ok(id)
+}
+
+/// Cancel a pending conditional allocation
+pub async fn cancel_conditional_allocation(
+    scope_id: &str,
+    allocation_id: &str,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let mut allocations = load_conditional_allocations(scope_id, storage).await?;
+    let allocation = allocations.allocations.iter_mut()
+        .find(|a| a.id == allocation_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No conditional allocation {} in {}", allocation_id, scope_id)))?;
+    if allocation.status != ConditionalAllocationStatus::Pending {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Conditional allocation {} is no longer pending", allocation_id
+        )));
+    }
+    allocation.status = ConditionalAllocationStatus::Cancelled;
+    save_conditional_allocations(&allocations, storage).await
+}
+
+/// Activate every pending allocation of a scope whose revenue has been recognized in
+/// its treasury journal: the amount is allocated to the budget and the hook, if any,
+/// is notified. Returns the allocations activated.
+pub async fn process_revenue_triggers(
+    scope_id: &str,
+    now: i64,
+    hook: Option<&dyn AllocationActivationHook>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<Vec<ConditionalAllocation>> {
+    let journal = match statements::load_treasury_journal(scope_id, storage).await? {
+        Some(journal) => journal,
+        None => return Ok(Vec::new()),
+    };
+    let mut allocations = load_conditional_allocations(scope_id, storage).await?;
+
+    let mut activated = Vec::new();
+    for allocation in allocations.allocations.iter_mut() {
+        if allocation.status != ConditionalAllocationStatus::Pending {
+            continue;
+        }
+        let (amount, entry_ids) = match allocation.evaluate(&journal.entries) {
+            Some(outcome) => outcome,
+            None => continue,
+        };
+
+        if amount > 0 {
+            budget_ops::allocate_to_budget(&allocation.budget_id, allocation.resource.clone(), amount, storage).await?;
+        }
+        allocation.status = ConditionalAllocationStatus::Activated { amount, activated_at: now, entry_ids };
+        activated.push(allocation.clone());
+    }
+
+    if activated.is_empty() {
+        return Ok(activated);
+    }
+    save_conditional_allocations(&allocations, storage).await?;
+
+    if let Some(hook) = hook {
+        for allocation in &activated {
+            hook.allocation_activated(allocation).await?;
+        }
+    }
+    Ok(activated)
+}
+
+/// [`statements::record_treasury_entry`], then activate any allocation the entry
+/// triggers. Returns the entry ID and the allocations activated.
+#[allow(clippy::too_many_arguments)]
+pub async fn record_treasury_entry_and_trigger(
+    scope_id: &str,
+    flow: TreasuryFlow,
+    category: &str,
+    amount: u64,
+    occurred_at: i64,
+    reference: Option<String>,
+    hook: Option<&dyn AllocationActivationHook>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<(String, Vec<ConditionalAllocation>)> {
+    let entry_id = statements::record_treasury_entry(scope_id, flow, category, amount, occurred_at, reference, storage).await?;
+    let activated = match flow {
+        TreasuryFlow::Inflow => process_revenue_triggers(scope_id, occurred_at, hook, storage).await?,
+        TreasuryFlow::Outflow => Vec::new(),
+    };
+    Ok((entry_id, activated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use icn_identity::IdentityScope;
+    use crate::budget_ops::{create_budget, query_budget_balance, MockBudgetStorage};
+    use crate::statements::open_treasury_journal;
+
+    #[derive(Default)]
+    struct RecordingHook {
+        activated: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl AllocationActivationHook for RecordingHook {
+        async fn allocation_activated(&self, allocation: &ConditionalAllocation) -> EconomicsResult<()> {
+            self.activated.lock().unwrap().push(allocation.id.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allocation_activates_when_grant_arrives() {
+        let mut storage = MockBudgetStorage::new();
+        let hook = RecordingHook::default();
+        open_treasury_journal("coop-1", "USD", 0, &mut storage).await.unwrap();
+        let budget_id = create_budget("Outreach", "coop-1", IdentityScope::Cooperative, 0, i64::MAX, None, &mut storage).await.unwrap();
+
+        // Spend up to half of grant G-7 once it's received, never more than 4,000
+        let trigger = RevenueTrigger { category: Some("grants".into()), reference: Some("G-7".into()), threshold: 0 };
+        let id = create_conditional_allocation(
+            "coop-1", &budget_id, ResourceType::Compute, trigger, AllocationShare::OfRevenue { bps: 5_000 },
+            Some(4_000), "did:icn:treasurer", 10, &mut storage,
+        ).await.unwrap();
+
+        // Other revenue leaves the allocation pending
+        let (_, activated) = record_treasury_entry_and_trigger(
+            "coop-1", TreasuryFlow::Inflow, "grants", 9_000, 100, Some("G-8".into()), Some(&hook), &mut storage,
+        ).await.unwrap();
+        assert!(activated.is_empty());
+        assert_eq!(query_budget_balance(&budget_id, &ResourceType::Compute, &storage).await.unwrap(), 0);
+
+        let (entry_id, activated) = record_treasury_entry_and_trigger(
+            "coop-1", TreasuryFlow::Inflow, "grants", 10_000, 200, Some("G-7".into()), Some(&hook), &mut storage,
+        ).await.unwrap();
+        assert_eq!(activated.len(), 1);
+        assert_eq!(activated[0].status, ConditionalAllocationStatus::Activated {
+            amount: 4_000,
+            activated_at: 200,
+            entry_ids: vec![entry_id],
+        });
+        assert_eq!(query_budget_balance(&budget_id, &ResourceType::Compute, &storage).await.unwrap(), 4_000);
+        assert_eq!(*hook.activated.lock().unwrap(), vec![id.clone()]);
+
+        // An activated allocation doesn't fire again, and can't be cancelled
+        assert!(process_revenue_triggers("coop-1", 300, Some(&hook), &mut storage).await.unwrap().is_empty());
+        assert!(cancel_conditional_allocation("coop-1", &id, &mut storage).await.is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult};
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u128 = 10_000;
+
+/// Patronage fractions are turned into integer weights at this precision before the
+/// surplus is split, so the split itself is exact
+const FRACTION_WEIGHT_SCALE: f64 = 1e12;
+
+/// How an amount that falls between two units is rounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Toward zero
+    Down,
+
+    /// To the nearest unit, ties to the even unit (banker's rounding), so ties don't
+    /// all round the same way
+    HalfEven,
+}
+
+/// Where the residue of a proportional split goes: what is left of the amount once
+/// every share has been rounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResidueHandling {
+    /// One unit at a time to the shares rounded furthest below their exact value,
+    /// earlier participants first
+    Distribute,
+
+    /// Kept whole and reported, for the caller to move to the scope's reserve
+    ToReserve,
+}
+
+/// Precision and rounding of monetary amounts, from the `rounding` field of an economic
+/// model. Applied wherever an amount is scaled or split in proportion: dues late fees,
+/// patronage and surplus shares, shared federation costs and divided escrow awards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+    pub residue: ResidueHandling,
+
+    /// Smallest amount a rounded share may be a multiple of, in ledger units
+    pub unit: u64,
+}
+
+impl Default for RoundingPolicy {
+    /// Round down to the ledger unit and hand the residue out by largest remainder
+    fn default() -> Self {
+        Self {
+            mode: RoundingMode::Down,
+            residue: ResidueHandling::Distribute,
+            unit: 1,
+        }
+    }
+}
+
+/// An amount split in proportion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProportionalSplit {
+    /// Each participant's share, in the order of the weights
+    pub shares: Vec<u64>,
+
+    /// What the shares leave of the amount. Always zero under
+    /// [`ResidueHandling::Distribute`] with a unit of 1.
+    pub residue: u64,
+}
+
+impl RoundingPolicy {
+    /// Build a policy from the `rounding` field of an economic model.
+    ///
+    /// Accepts `"down"` or `"half_even"`, optionally with a unit and residue handling:
+    /// `"half_even(unit=100, residue=reserve)"`. The residue is `distribute` unless set.
+    pub fn from_economic_model(spec: &str) -> EconomicsResult<Self> {
+        let spec = spec.trim();
+        let (method, params) = match spec.find('(') {
+            Some(open) if spec.ends_with(')') => (spec[..open].trim(), &spec[open + 1..spec.len() - 1]),
+            Some(_) => return Err(EconomicsError::InvalidRoundingPolicy(
+                format!("Unbalanced parentheses in {}", spec)
+            )),
+            None => (spec, ""),
+        };
+
+        let mut policy = RoundingPolicy {
+            mode: match method {
+                "down" => RoundingMode::Down,
+                "half_even" => RoundingMode::HalfEven,
+                other => return Err(EconomicsError::InvalidRoundingPolicy(
+                    format!("Unknown mode: {}", other)
+                )),
+            },
+            ..Default::default()
+        };
+
+        for param in params.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = param.split_once('=').ok_or_else(|| EconomicsError::InvalidRoundingPolicy(
+                format!("Malformed parameter: {}", param.trim())
+            ))?;
+            match (key.trim(), value.trim()) {
+                ("unit", unit) => {
+                    policy.unit = match unit.parse::<u64>() {
+                        Ok(unit) if unit > 0 => unit,
+                        _ => return Err(EconomicsError::InvalidRoundingPolicy(
+                            format!("Unit must be a positive whole amount: {}", unit)
+                        )),
+                    };
+                }
+                ("residue", "distribute") => policy.residue = ResidueHandling::Distribute,
+                ("residue", "reserve") => policy.residue = ResidueHandling::ToReserve,
+                (key, value) => return Err(EconomicsError::InvalidRoundingPolicy(
+                    format!("Unknown parameter {}={}", key, value)
+                )),
+            }
+        }
+        Ok(policy)
+    }
+
+    /// `numerator / denominator` rounded to a multiple of the unit
+    pub fn round(&self, numerator: u128, denominator: u128) -> u64 {
+        let (units, remainder) = self.units(numerator, denominator);
+        let units = if self.rounds_up(units, remainder, denominator * self.unit as u128) { units + 1 } else { units };
+        (units * self.unit as u128) as u64
+    }
+
+    /// `bps` basis points of `amount`, rounded
+    pub fn apply_bps(&self, amount: u64, bps: u64) -> u64 {
+        self.round(amount as u128 * bps as u128, BASIS_POINTS)
+    }
+
+    /// Split `amount` in proportion to `weights`. The shares and the residue always add
+    /// up to exactly the amount.
+    pub fn split(&self, amount: u64, weights: &[u64]) -> EconomicsResult<ProportionalSplit> {
+        let total: u128 = weights.iter().map(|w| *w as u128).sum();
+        if total == 0 {
+            return Err(EconomicsError::InvalidBudget("No participant carries any weight under the allocation key".to_string()));
+        }
+        let unit = self.unit as u128;
+        let per_unit = total * unit;
+
+        // Each share rounded on its own, remembering how far below or above its exact
+        // value it landed, in 1/total of a ledger unit
+        let mut shares: Vec<u128> = Vec::with_capacity(weights.len());
+        let mut below: Vec<u128> = Vec::with_capacity(weights.len());
+        let mut rounded_up: Vec<bool> = Vec::with_capacity(weights.len());
+        for weight in weights {
+            let exact = amount as u128 * *weight as u128;
+            let (units, remainder) = self.units(exact, total);
+            let up = self.rounds_up(units, remainder, per_unit);
+            shares.push((if up { units + 1 } else { units }) * unit);
+            below.push(remainder);
+            rounded_up.push(up);
+        }
+
+        // Rounding half up can hand out more than there is; take a unit back from the
+        // shares that were closest to rounding down, later participants first
+        let mut allocated: u128 = shares.iter().sum();
+        if allocated > amount as u128 {
+            let mut order: Vec<usize> = (0..shares.len()).filter(|i| rounded_up[*i]).collect();
+            order.sort_by(|a, b| below[*a].cmp(&below[*b]).then(b.cmp(a)));
+            for i in order {
+                if allocated <= amount as u128 {
+                    break;
+                }
+                shares[i] -= unit;
+                rounded_up[i] = false;
+                allocated -= unit;
+            }
+        }
+
+        let mut residue = amount as u128 - allocated;
+        if self.residue == ResidueHandling::Distribute {
+            let mut order: Vec<usize> = (0..shares.len()).filter(|i| !rounded_up[*i] && below[*i] > 0).collect();
+            order.sort_by(|a, b| below[*b].cmp(&below[*a]).then(a.cmp(b)));
+            for i in order {
+                if residue < unit {
+                    break;
+                }
+                shares[i] += unit;
+                residue -= unit;
+            }
+        }
+
+        Ok(ProportionalSplit {
+            shares: shares.into_iter().map(|s| s as u64).collect(),
+            residue: residue as u64,
+        })
+    }
+
+    /// Split `amount` by fractions that add up to one, such as patronage fractions
+    pub fn split_fractions(&self, amount: u64, fractions: &[f64]) -> EconomicsResult<ProportionalSplit> {
+        let weights: Vec<u64> = fractions.iter()
+            .map(|f| (f.max(0.0) * FRACTION_WEIGHT_SCALE).round() as u64)
+            .collect();
+        self.split(amount, &weights)
+    }
+
+    /// Whole units in `numerator / denominator`, and what is left over in 1/denominator
+    /// of a ledger unit
+    fn units(&self, numerator: u128, denominator: u128) -> (u128, u128) {
+        let per_unit = denominator * self.unit as u128;
+        (numerator / per_unit, numerator % per_unit)
+    }
+
+    fn rounds_up(&self, units: u128, remainder: u128, per_unit: u128) -> bool {
+        match self.mode {
+            RoundingMode::Down => false,
+            RoundingMode::HalfEven => match (remainder * 2).cmp(&per_unit) {
+                std::cmp::Ordering::Greater => true,
+                std::cmp::Ordering::Equal => units % 2 == 1,
+                std::cmp::Ordering::Less => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policies() -> Vec<RoundingPolicy> {
+        let mut policies = Vec::new();
+        for mode in [RoundingMode::Down, RoundingMode::HalfEven] {
+            for residue in [ResidueHandling::Distribute, ResidueHandling::ToReserve] {
+                for unit in [1, 5, 100] {
+                    policies.push(RoundingPolicy { mode, residue, unit });
+                }
+            }
+        }
+        policies
+    }
+
+    #[test]
+    fn test_policy_from_economic_model() {
+        assert_eq!(RoundingPolicy::from_economic_model("down").unwrap(), RoundingPolicy::default());
+
+        let policy = RoundingPolicy::from_economic_model("half_even(unit=100, residue=reserve)").unwrap();
+        assert_eq!(policy, RoundingPolicy { mode: RoundingMode::HalfEven, residue: ResidueHandling::ToReserve, unit: 100 });
+
+        assert!(matches!(RoundingPolicy::from_economic_model("half_up"), Err(EconomicsError::InvalidRoundingPolicy(_))));
+        assert!(matches!(RoundingPolicy::from_economic_model("down(unit=0)"), Err(EconomicsError::InvalidRoundingPolicy(_))));
+        assert!(matches!(RoundingPolicy::from_economic_model("down(residue=burn)"), Err(EconomicsError::InvalidRoundingPolicy(_))));
+    }
+
+    #[test]
+    fn test_bankers_rounding_sends_ties_to_even_units() {
+        let policy = RoundingPolicy { mode: RoundingMode::HalfEven, ..Default::default() };
+        assert_eq!(policy.round(5, 2), 2);
+        assert_eq!(policy.round(7, 2), 4);
+        assert_eq!(policy.round(11, 4), 3);
+        assert_eq!(policy.apply_bps(250, 50), 1);
+
+        let cents = RoundingPolicy { mode: RoundingMode::HalfEven, unit: 100, ..Default::default() };
+        assert_eq!(cents.round(250, 1), 200);
+        assert_eq!(cents.round(350, 1), 400);
+        assert_eq!(cents.round(351, 1), 400);
+        assert_eq!(RoundingPolicy { unit: 100, ..Default::default() }.round(399, 1), 300);
+    }
+
+    #[test]
+    fn test_residue_goes_to_reserve_or_largest_remainders() {
+        let distribute = RoundingPolicy::default().split(100, &[1, 1, 1]).unwrap();
+        assert_eq!(distribute, ProportionalSplit { shares: vec![34, 33, 33], residue: 0 });
+
+        let reserve = RoundingPolicy { residue: ResidueHandling::ToReserve, ..Default::default() };
+        assert_eq!(reserve.split(100, &[1, 1, 1]).unwrap(), ProportionalSplit { shares: vec![33, 33, 33], residue: 1 });
+
+        // Rounding each half up would hand out 102
+        let half_even = RoundingPolicy { mode: RoundingMode::HalfEven, ..Default::default() };
+        assert_eq!(half_even.split(101, &[1, 1]).unwrap().shares.iter().sum::<u64>(), 101);
+        assert_eq!(half_even.split(5, &[1, 1, 1, 1, 1, 1]).unwrap().shares.iter().sum::<u64>(), 5);
+
+        assert!(RoundingPolicy::default().split(100, &[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_splits_always_balance_to_the_unit() {
+        let weight_sets: [&[u64]; 6] = [&[1], &[1, 1, 1], &[3, 7], &[1, 2, 3, 4, 5, 6, 7], &[0, 5, 0, 9], &[9_999, 1]];
+        for policy in policies() {
+            for amount in (0..1_000).chain([9_999, 10_001, 123_457, u32::MAX as u64]) {
+                for weights in weight_sets {
+                    let split = policy.split(amount, weights).unwrap();
+                    let total: u128 = weights.iter().map(|w| *w as u128).sum();
+                    assert_eq!(
+                        split.shares.iter().sum::<u64>() + split.residue, amount,
+                        "{:?} splitting {} by {:?}", policy, amount, weights
+                    );
+
+                    for (share, weight) in split.shares.iter().zip(weights.iter()) {
+                        // Within a unit of the exact share, and only the weightless get nothing
+                        let exact = amount as f64 * *weight as f64 / total as f64;
+                        assert!((*share as f64 - exact).abs() < policy.unit as f64, "{:?}: {} for exact {}", policy, share, exact);
+                        assert!(*weight > 0 || *share == 0);
+                        assert_eq!(share % policy.unit, 0);
+                    }
+                    if policy.residue == ResidueHandling::Distribute {
+                        assert!(split.residue < policy.unit);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fraction_splits_balance() {
+        let fractions = [1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0];
+        for policy in policies() {
+            for amount in [0, 1, 2, 1_000, 1_001, 99_999] {
+                let split = policy.split_fractions(amount, &fractions).unwrap();
+                assert_eq!(split.shares.iter().sum::<u64>() + split.residue, amount);
+            }
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use cid::Cid;
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::BudgetStorage;
+use crate::transfer_plan::TransferExecutor;
+use crate::idempotency::{self, DEFAULT_DEDUP_WINDOW_SECS};
+
+/// Storage key prefix for solidarity fund ledgers
+const SOLIDARITY_KEY_PREFIX: &str = "solidarity::ledger::";
+
+/// Storage key prefix for the need details of applications, kept out of the ledger
+const NEED_KEY_PREFIX: &str = "solidarity::need::";
+
+/// A mutual-aid fund that pays members in need, as decided by its committee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolidarityFund {
+    pub fund_id: String,
+    pub name: String,
+
+    /// Account disbursements are paid from
+    pub account_did: String,
+    pub resource_type: ResourceType,
+
+    /// DIDs of the members who review applications
+    pub committee: Vec<String>,
+
+    /// Committee approvals an application needs
+    pub required_approvals: usize,
+
+    /// Largest amount a single application can ask for
+    pub max_disbursement: u64,
+
+    /// Length of a disbursement period in seconds, counted from when the fund opened
+    pub period_secs: i64,
+
+    /// Most the fund pays out in one period
+    pub period_cap: u64,
+
+    /// Periods with fewer recipients than this report counts only, not the total paid
+    pub report_min_recipients: usize,
+
+    pub opened_at: i64,
+}
+
+impl SolidarityFund {
+    fn validate(&self) -> EconomicsResult<()> {
+        if self.committee.is_empty() {
+            return Err(EconomicsError::InvalidBudget(format!("Solidarity fund {} needs a committee", self.fund_id)));
+        }
+        if self.required_approvals == 0 || self.required_approvals > self.committee.len() {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "Solidarity fund {} needs between 1 and {} approvals, not {}",
+                self.fund_id, self.committee.len(), self.required_approvals
+            )));
+        }
+        if self.period_secs <= 0 {
+            return Err(EconomicsError::InvalidBudget("Disbursement periods must have a positive length".to_string()));
+        }
+        if self.max_disbursement == 0 || self.max_disbursement > self.period_cap {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "Disbursements of up to {} don't fit a period cap of {}", self.max_disbursement, self.period_cap
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether a DID sits on the fund's committee
+    pub fn is_committee_member(&self, did: &str) -> bool {
+        self.committee.iter().any(|m| m == did)
+    }
+
+    /// Start and end of the disbursement period containing `at`
+    pub fn period_containing(&self, at: i64) -> (i64, i64) {
+        let start = self.opened_at + (at - self.opened_at).div_euclid(self.period_secs) * self.period_secs;
+        (start, start + self.period_secs)
+    }
+}
+
+/// Where an application is in its review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApplicationStatus {
+    Pending,
+    Approved,
+    Declined,
+    Disbursed { disbursed_at: i64 },
+}
+
+/// A member's application for support. What the need is stays in its [`NeedDetails`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NeedApplication {
+    pub id: String,
+    pub fund_id: String,
+    pub applicant: String,
+    pub amount: u64,
+    pub submitted_at: i64,
+    pub approvals: Vec<String>,
+    pub status: ApplicationStatus,
+
+    /// Committee member who declined the application, if it was declined
+    pub declined_by: Option<String>,
+    pub decided_at: Option<i64>,
+
+    /// Idempotency key of the call that paid the application
+    #[serde(default)]
+    pub disbursement_key: Option<String>,
+}
+
+/// What an applicant needs support for, visible only to the fund's committee
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NeedDetails {
+    pub description: String,
+
+    /// CIDs of anchored supporting documents
+    pub document_cids: Vec<String>,
+}
+
+/// Solidarity funds of a scope and the applications to them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolidarityLedger {
+    pub scope_id: String,
+    pub funds: Vec<SolidarityFund>,
+    pub applications: Vec<NeedApplication>,
+}
+
+impl SolidarityLedger {
+    pub fn new(scope_id: &str) -> Self {
+        Self {
+            scope_id: scope_id.to_string(),
+            funds: Vec::new(),
+            applications: Vec::new(),
+        }
+    }
+
+    fn fund(&self, fund_id: &str) -> EconomicsResult<&SolidarityFund> {
+        self.funds.iter()
+            .find(|f| f.fund_id == fund_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("No solidarity fund {} in {}", fund_id, self.scope_id)
+            ))
+    }
+
+    fn application(&self, application_id: &str) -> EconomicsResult<&NeedApplication> {
+        self.applications.iter()
+            .find(|a| a.id == application_id)
+            .ok_or_else(|| EconomicsError::InvalidBudget(
+                format!("No solidarity application {} in {}", application_id, self.scope_id)
+            ))
+    }
+
+    /// Total a fund paid out between `start` (inclusive) and `end` (exclusive)
+    pub fn disbursed_between(&self, fund_id: &str, start: i64, end: i64) -> u64 {
+        self.applications.iter()
+            .filter(|a| a.fund_id == fund_id)
+            .filter_map(|a| match a.status {
+                ApplicationStatus::Disbursed { disbursed_at } if disbursed_at >= start && disbursed_at < end => Some(a.amount),
+                _ => None,
+            })
+            .sum()
+    }
+}
+
+/// Anonymized totals of a fund for a reporting period. Names no applicants and,
+/// for periods with few recipients, withholds the total paid.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolidarityReport {
+    pub scope_id: String,
+    pub fund_id: String,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub applications_received: usize,
+    pub applications_declined: usize,
+    pub disbursement_count: usize,
+
+    /// Distinct members paid during the period
+    pub recipient_count: usize,
+
+    /// Total paid during the period, if enough members were paid to report it
+    pub total_disbursed: Option<u64>,
+}
+
+/// Store a solidarity ledger
+pub async fn save_solidarity_ledger(
+    ledger: &SolidarityLedger,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(ledger)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize solidarity ledger: {}", e)))?;
+
+    let key = format!("{}{}", SOLIDARITY_KEY_PREFIX, ledger.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's solidarity ledger; scopes without funds get an empty ledger
+pub async fn load_solidarity_ledger(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<SolidarityLedger> {
+    let key = format!("{}{}", SOLIDARITY_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize solidarity ledger: {}", e))),
+        None => Ok(SolidarityLedger::new(scope_id)),
+    }
+}
+
+/// Open a solidarity fund
+pub async fn open_solidarity_fund(
+    scope_id: &str,
+    fund: SolidarityFund,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    fund.validate()?;
+    let mut ledger = load_solidarity_ledger(scope_id, storage).await?;
+    if ledger.funds.iter().any(|f| f.fund_id == fund.fund_id) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Solidarity fund {} already exists in {}", fund.fund_id, scope_id)
+        ));
+    }
+
+    ledger.funds.push(fund);
+    save_solidarity_ledger(&ledger, storage).await
+}
+
+/// Apply to a fund for support. The need details are stored apart from the ledger,
+/// where only the committee can read them. Returns the application ID.
+pub async fn apply_for_support(
+    scope_id: &str,
+    fund_id: &str,
+    applicant: &str,
+    amount: u64,
+    details: NeedDetails,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut ledger = load_solidarity_ledger(scope_id, storage).await?;
+    let fund = ledger.fund(fund_id)?;
+    if amount == 0 || amount > fund.max_disbursement {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "Solidarity fund {} pays between 1 and {} per application", fund_id, fund.max_disbursement
+        )));
+    }
+    for document in &details.document_cids {
+        Cid::try_from(document.as_str())
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Invalid document CID {}: {}", document, e)))?;
+    }
+    if ledger.applications.iter().any(|a| a.fund_id == fund_id && a.applicant == applicant && a.status == ApplicationStatus::Pending) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("{} already has a pending application to {}", applicant, fund_id)
+        ));
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let data = serde_json::to_vec(&details)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize need details: {}", e)))?;
+    storage.store_budget(&format!("{}{}", NEED_KEY_PREFIX, id), data).await?;
+
+    ledger.applications.push(NeedApplication {
+        id: id.clone(),
+        fund_id: fund_id.to_string(),
+        applicant: applicant.to_string(),
+        amount,
+        submitted_at: at,
+        approvals: Vec::new(),
+        status: ApplicationStatus::Pending,
+        declined_by: None,
+        decided_at: None,
+        disbursement_key: None,
+    });
+    save_solidarity_ledger(&ledger, storage).await?;
+    Ok(id)
+}
+
+/// Read an application's need details. Only members of the fund's committee may.
+pub async fn view_need_details(
+    scope_id: &str,
+    application_id: &str,
+    viewer: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<NeedDetails> {
+    let ledger = load_solidarity_ledger(scope_id, storage).await?;
+    let application = ledger.application(application_id)?;
+    if !ledger.fund(&application.fund_id)?.is_committee_member(viewer) {
+        return Err(EconomicsError::Unauthorized(
+            format!("Only the committee of {} can read the needs applied for", application.fund_id)
+        ));
+    }
+
+    let data = storage.get_budget(&format!("{}{}", NEED_KEY_PREFIX, application_id)).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No need details for application {}", application_id)))?;
+    serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize need details: {}", e)))
+}
+
+/// Approve or decline a pending application. A single decline declines it; once it has
+/// the fund's number of approvals it can be disbursed. Reviewers must sit on the
+/// committee and can't review their own application.
+pub async fn review_application(
+    scope_id: &str,
+    application_id: &str,
+    reviewer: &str,
+    approve: bool,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<ApplicationStatus> {
+    let mut ledger = load_solidarity_ledger(scope_id, storage).await?;
+    let fund = ledger.fund(&ledger.application(application_id)?.fund_id)?.clone();
+    let application = ledger.applications.iter_mut()
+        .find(|a| a.id == application_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No solidarity application {} in {}", application_id, scope_id)))?;
+
+    if application.status != ApplicationStatus::Pending {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Application {} was already {:?}", application_id, application.status)
+        ));
+    }
+    if !fund.is_committee_member(reviewer) {
+        return Err(EconomicsError::Unauthorized(
+            format!("{} isn't on the committee of {}", reviewer, fund.fund_id)
+        ));
+    }
+    if application.applicant == reviewer {
+        return Err(EconomicsError::Unauthorized(
+            format!("{} can't review their own application", reviewer)
+        ));
+    }
+    if application.approvals.iter().any(|a| a == reviewer) {
+        return Err(EconomicsError::InvalidBudget(
+            format!("{} already approved application {}", reviewer, application_id)
+        ));
+    }
+
+    if approve {
+        application.approvals.push(reviewer.to_string());
+        if application.approvals.len() >= fund.required_approvals {
+            application.status = ApplicationStatus::Approved;
+            application.decided_at = Some(at);
+        }
+    } else {
+        application.status = ApplicationStatus::Declined;
+        application.declined_by = Some(reviewer.to_string());
+        application.decided_at = Some(at);
+    }
+
+    let status = application.status;
+    save_solidarity_ledger(&ledger, storage).await?;
+    Ok(status)
+}
+
+/// Pay an approved application from the fund's account. Fails without paying if the
+/// payment would take the fund over its cap for the current period; the application
+/// stays approved and can be paid once a new period starts. A retried call with the
+/// same `idempotency_key` returns the amount paid instead of paying again.
+pub async fn disburse_application(
+    scope_id: &str,
+    application_id: &str,
+    idempotency_key: &str,
+    executor: &mut impl TransferExecutor,
+    at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<u64> {
+    let request_hash = idempotency::request_fingerprint(&application_id)?;
+    if let Some(paid) = idempotency::replay_result(
+        scope_id, idempotency_key, "disburse_application", &request_hash, at, storage,
+    ).await? {
+        return Ok(paid);
+    }
+
+    let mut ledger = load_solidarity_ledger(scope_id, storage).await?;
+    let application = ledger.application(application_id)?.clone();
+    if application.status != ApplicationStatus::Approved {
+        return Err(EconomicsError::Unauthorized(
+            format!("Application {} has not been approved: {:?}", application_id, application.status)
+        ));
+    }
+
+    let fund = ledger.fund(&application.fund_id)?;
+    let (period_start, period_end) = fund.period_containing(at);
+    let disbursed = ledger.disbursed_between(&fund.fund_id, period_start, period_end);
+    if disbursed.saturating_add(application.amount) > fund.period_cap {
+        return Err(EconomicsError::InsufficientBalance(format!(
+            "Solidarity fund {} has {} of its {} left for this period, {} is needed",
+            fund.fund_id, fund.period_cap.saturating_sub(disbursed), fund.period_cap, application.amount
+        )));
+    }
+
+    executor.execute_transfer(&fund.account_did, &application.applicant, &fund.resource_type, application.amount).await?;
+
+    if let Some(stored) = ledger.applications.iter_mut().find(|a| a.id == application_id) {
+        stored.status = ApplicationStatus::Disbursed { disbursed_at: at };
+        stored.disbursement_key = Some(idempotency_key.to_string());
+    }
+    save_solidarity_ledger(&ledger, storage).await?;
+    idempotency::remember_result(
+        scope_id, idempotency_key, "disburse_application", &request_hash, &application.amount, at, DEFAULT_DEDUP_WINDOW_SECS, storage,
+    ).await?;
+
+    tracing::info!("Solidarity application {} disbursed from {}", application_id, application.fund_id);
+
+    Ok(application.amount)
+}
+
+/// Public report of a fund's activity between `period_start` (inclusive) and
+/// `period_end` (exclusive)
+pub async fn solidarity_report(
+    scope_id: &str,
+    fund_id: &str,
+    period_start: i64,
+    period_end: i64,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<SolidarityReport> {
+    if period_end <= period_start {
+        return Err(EconomicsError::InvalidBudget("Period end must be after its start".to_string()));
+    }
+
+    let ledger = load_solidarity_ledger(scope_id, storage).await?;
+    let fund = ledger.fund(fund_id)?;
+    let in_period = |ts: i64| ts >= period_start && ts < period_end;
+    let applications: Vec<&NeedApplication> = ledger.applications.iter().filter(|a| a.fund_id == fund_id).collect();
+
+    let disbursed: Vec<&&NeedApplication> = applications.iter()
+        .filter(|a| matches!(a.status, ApplicationStatus::Disbursed { disbursed_at } if in_period(disbursed_at)))
+        .collect();
+    let recipient_count = disbursed.iter().map(|a| a.applicant.as_str()).collect::<HashSet<_>>().len();
+
+    Ok(SolidarityReport {
+        scope_id: scope_id.to_string(),
+        fund_id: fund_id.to_string(),
+        period_start,
+        period_end,
+        applications_received: applications.iter().filter(|a| in_period(a.submitted_at)).count(),
+        applications_declined: applications.iter()
+            .filter(|a| a.status == ApplicationStatus::Declined && a.decided_at.map_or(false, in_period))
+            .count(),
+        disbursement_count: disbursed.len(),
+        recipient_count,
+        total_disbursed: (recipient_count >= fund.report_min_recipients)
+            .then(|| disbursed.iter().map(|a| a.amount).sum()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+    use crate::transfer_plan::MockTransferExecutor;
+
+    const DAY: i64 = 86_400;
+    const SCOPE: &str = "did:icn:coop";
+
+    fn fund() -> SolidarityFund {
+        SolidarityFund {
+            fund_id: "mutual-aid".to_string(),
+            name: "Mutual aid".to_string(),
+            account_did: "did:icn:coop:mutual-aid".to_string(),
+            resource_type: ResourceType::Compute,
+            committee: vec!["did:icn:bob".to_string(), "did:icn:carol".to_string(), "did:icn:dana".to_string()],
+            required_approvals: 2,
+            max_disbursement: 300,
+            period_secs: 30 * DAY,
+            period_cap: 500,
+            report_min_recipients: 2,
+            opened_at: 0,
+        }
+    }
+
+    fn details(description: &str) -> NeedDetails {
+        NeedDetails { description: description.to_string(), document_cids: Vec::new() }
+    }
+
+    #[tokio::test]
+    async fn test_committee_review_period_cap_and_anonymized_report() {
+        let mut storage = MockBudgetStorage::new();
+        open_solidarity_fund(SCOPE, fund(), &mut storage).await.unwrap();
+        let mut executor = MockTransferExecutor::default();
+        executor.balances.insert("did:icn:coop:mutual-aid".to_string(), 1000);
+
+        assert!(apply_for_support(SCOPE, "mutual-aid", "did:icn:alice", 400, details("Rent"), DAY, &mut storage).await.is_err());
+        let rent = apply_for_support(SCOPE, "mutual-aid", "did:icn:alice", 300, details("Rent"), DAY, &mut storage)
+            .await.unwrap();
+
+        // Only the committee reads the need, and the ledger doesn't hold it
+        assert!(view_need_details(SCOPE, &rent, "did:icn:bob", &storage).await.is_ok());
+        assert!(view_need_details(SCOPE, &rent, "did:icn:eve", &storage).await.is_err());
+        let ledger = serde_json::to_string(&load_solidarity_ledger(SCOPE, &storage).await.unwrap()).unwrap();
+        assert!(!ledger.contains("Rent"));
+
+        assert!(review_application(SCOPE, &rent, "did:icn:eve", true, DAY, &mut storage).await.is_err());
+        assert!(disburse_application(SCOPE, &rent, "rent-1", &mut executor, DAY, &mut storage).await.is_err());
+        assert_eq!(review_application(SCOPE, &rent, "did:icn:bob", true, DAY, &mut storage).await.unwrap(), ApplicationStatus::Pending);
+        assert_eq!(review_application(SCOPE, &rent, "did:icn:carol", true, DAY, &mut storage).await.unwrap(), ApplicationStatus::Approved);
+        assert_eq!(disburse_application(SCOPE, &rent, "rent-1", &mut executor, 2 * DAY, &mut storage).await.unwrap(), 300);
+        assert_eq!(executor.balances["did:icn:alice"], 300);
+
+        // A retried disbursement doesn't pay twice
+        assert_eq!(disburse_application(SCOPE, &rent, "rent-1", &mut executor, 2 * DAY, &mut storage).await.unwrap(), 300);
+        assert_eq!(executor.balances["did:icn:alice"], 300);
+
+        // A second payment this period would pass the cap, so it waits for the next
+        let medical = apply_for_support(SCOPE, "mutual-aid", "did:icn:frank", 250, details("Medical bills"), 3 * DAY, &mut storage)
+            .await.unwrap();
+        review_application(SCOPE, &medical, "did:icn:bob", true, 3 * DAY, &mut storage).await.unwrap();
+        review_application(SCOPE, &medical, "did:icn:dana", true, 3 * DAY, &mut storage).await.unwrap();
+        assert!(disburse_application(SCOPE, &medical, "medical-1", &mut executor, 4 * DAY, &mut storage).await.is_err());
+        assert_eq!(disburse_application(SCOPE, &medical, "medical-1", &mut executor, 31 * DAY, &mut storage).await.unwrap(), 250);
+
+        let declined = apply_for_support(SCOPE, "mutual-aid", "did:icn:gus", 50, details("Travel"), 5 * DAY, &mut storage)
+            .await.unwrap();
+        review_application(SCOPE, &declined, "did:icn:dana", false, 5 * DAY, &mut storage).await.unwrap();
+
+        // With a single recipient in the first period, its total is withheld
+        let first = solidarity_report(SCOPE, "mutual-aid", 0, 30 * DAY, &storage).await.unwrap();
+        assert_eq!((first.applications_received, first.applications_declined, first.recipient_count), (3, 1, 1));
+        assert_eq!(first.total_disbursed, None);
+        let both = solidarity_report(SCOPE, "mutual-aid", 0, 60 * DAY, &storage).await.unwrap();
+        assert_eq!(both.total_disbursed, Some(550));
+        assert!(!serde_json::to_string(&both).unwrap().contains("did:icn:alice"));
+    }
+}
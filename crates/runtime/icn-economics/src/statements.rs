@@ -0,0 +1,475 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use chrono::{NaiveDate, TimeZone, Utc};
+use cid::Cid;
+use uuid::Uuid;
+use sha2::{Sha256, Digest};
+use serde::{Serialize, Deserialize};
+use icn_identity::KeyPair;
+use crate::{EconomicsError, EconomicsResult};
+use crate::budget_ops::BudgetStorage;
+
+/// Storage key prefix for treasury journals
+const JOURNAL_KEY_PREFIX: &str = "treasury::journal::";
+
+/// Storage key prefix for the CIDs of closed statements
+const STATEMENT_KEY_PREFIX: &str = "treasury::statement::";
+
+/// Raw multicodec, for statements anchored by the hash of their bytes
+const RAW_CODEC: u64 = 0x55;
+
+/// Role that may close and sign a scope's monthly statement
+pub const TREASURER_ROLE: &str = "treasurer";
+
+/// A calendar month a statement covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StatementPeriod {
+    pub year: i32,
+    pub month: u32,
+}
+
+impl StatementPeriod {
+    pub fn new(year: i32, month: u32) -> EconomicsResult<Self> {
+        if !(1..=12).contains(&month) {
+            return Err(EconomicsError::InvalidBudget(format!("Invalid statement month: {}", month)));
+        }
+        Ok(Self { year, month })
+    }
+
+    /// Parse a period written as `YYYY-MM`
+    pub fn parse(period: &str) -> EconomicsResult<Self> {
+        let invalid = || EconomicsError::InvalidBudget(format!("Invalid statement period: {}", period));
+        let (year, month) = period.split_once('-').ok_or_else(invalid)?;
+        Self::new(year.parse().map_err(|_| invalid())?, month.parse().map_err(|_| invalid())?)
+    }
+
+    /// The period containing a Unix timestamp
+    pub fn containing(timestamp: i64) -> EconomicsResult<Self> {
+        let date = Utc.timestamp_opt(timestamp, 0).single()
+            .ok_or_else(|| EconomicsError::InvalidBudget(format!("Invalid timestamp: {}", timestamp)))?;
+        Self::new(chrono::Datelike::year(&date), chrono::Datelike::month(&date))
+    }
+
+    /// The following month
+    pub fn next(&self) -> Self {
+        if self.month == 12 {
+            Self { year: self.year + 1, month: 1 }
+        } else {
+            Self { year: self.year, month: self.month + 1 }
+        }
+    }
+
+    fn start_timestamp(&self) -> EconomicsResult<i64> {
+        NaiveDate::from_ymd_opt(self.year, self.month, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|start| Utc.from_utc_datetime(&start).timestamp())
+            .ok_or_else(|| EconomicsError::InvalidBudget(format!("Invalid statement period: {}", self)))
+    }
+
+    /// Start (inclusive) and end (exclusive) of the period as Unix timestamps
+    pub fn bounds(&self) -> EconomicsResult<(i64, i64)> {
+        Ok((self.start_timestamp()?, self.next().start_timestamp()?))
+    }
+}
+
+impl fmt::Display for StatementPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+/// Direction of money through the treasury
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TreasuryFlow {
+    Inflow,
+    Outflow,
+}
+
+/// A movement of money in or out of a scope's treasury
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryEntry {
+    pub id: String,
+    pub flow: TreasuryFlow,
+
+    /// Reporting category (e.g., "dues", "donations", "payroll")
+    pub category: String,
+
+    pub amount: u64,
+    pub occurred_at: i64,
+
+    /// External reference (e.g., a payment or receipt ID); entries are unique by it
+    pub reference: Option<String>,
+
+    /// Tax category code under the scope's tax regime (see `tax`)
+    #[serde(default)]
+    pub tax_category: Option<String>,
+
+    /// Idempotency key of the call that posted the entry, if it was made with one
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+}
+
+/// Every treasury movement of a scope, and the months already closed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryJournal {
+    pub scope_id: String,
+
+    /// Unit amounts are denominated in (e.g., "USD")
+    pub unit: String,
+
+    /// Balance before the first entry
+    pub opening_balance: i64,
+
+    pub entries: Vec<TreasuryEntry>,
+    pub closed_periods: Vec<StatementPeriod>,
+}
+
+impl TreasuryJournal {
+    /// Balance after every entry
+    pub fn balance(&self) -> i64 {
+        self.balance_before(i64::MAX)
+    }
+
+    /// Balance after every entry before `timestamp`
+    fn balance_before(&self, timestamp: i64) -> i64 {
+        self.entries.iter()
+            .filter(|e| e.occurred_at < timestamp)
+            .fold(self.opening_balance, |balance, e| match e.flow {
+                TreasuryFlow::Inflow => balance + e.amount as i64,
+                TreasuryFlow::Outflow => balance - e.amount as i64,
+            })
+    }
+}
+
+/// A closed month of a scope's treasury, signed by its treasurer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryStatement {
+    pub scope_id: String,
+    pub period: StatementPeriod,
+    pub unit: String,
+    pub opening_balance: i64,
+    pub inflows_by_category: BTreeMap<String, u64>,
+    pub outflows_by_category: BTreeMap<String, u64>,
+    pub total_inflows: u64,
+    pub total_outflows: u64,
+    pub ending_balance: i64,
+    pub entry_count: usize,
+
+    /// Treasurer who closed the month
+    pub signer_did: String,
+    pub closed_at: i64,
+
+    /// Hex signature of the treasurer over the statement without its signature
+    pub signature: String,
+}
+
+impl TreasuryStatement {
+    /// Bytes the treasurer signs
+    pub fn signing_bytes(&self) -> EconomicsResult<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signature = String::new();
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize treasury statement: {}", e)))
+    }
+}
+
+/// A statement together with the CID it was anchored under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnchoredStatement {
+    pub cid: String,
+    pub statement: TreasuryStatement,
+}
+
+/// Store a treasury journal
+pub async fn save_treasury_journal(
+    journal: &TreasuryJournal,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(journal)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize treasury journal: {}", e)))?;
+
+    let key = format!("{}{}", JOURNAL_KEY_PREFIX, journal.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's treasury journal, if its treasury has been opened
+pub async fn load_treasury_journal(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<TreasuryJournal>> {
+    let key = format!("{}{}", JOURNAL_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize treasury journal: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// Start keeping a treasury journal for a scope
+pub async fn open_treasury_journal(
+    scope_id: &str,
+    unit: &str,
+    opening_balance: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    if load_treasury_journal(scope_id, storage).await?.is_some() {
+        return Err(EconomicsError::InvalidBudget(format!("Treasury journal of {} is already open", scope_id)));
+    }
+
+    save_treasury_journal(&TreasuryJournal {
+        scope_id: scope_id.to_string(),
+        unit: unit.to_string(),
+        opening_balance,
+        entries: Vec::new(),
+        closed_periods: Vec::new(),
+    }, storage).await
+}
+
+/// Record a movement in a scope's treasury. Months that have been closed can't
+/// receive new entries. Returns the entry ID.
+pub async fn record_treasury_entry(
+    scope_id: &str,
+    flow: TreasuryFlow,
+    category: &str,
+    amount: u64,
+    occurred_at: i64,
+    reference: Option<String>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    record_keyed_treasury_entry(scope_id, flow, category, amount, occurred_at, reference, None, storage).await
+}
+
+/// Record a movement posted by an idempotent call, keeping the call's key on the entry
+#[allow(clippy::too_many_arguments)]
+pub async fn record_keyed_treasury_entry(
+    scope_id: &str,
+    flow: TreasuryFlow,
+    category: &str,
+    amount: u64,
+    occurred_at: i64,
+    reference: Option<String>,
+    idempotency_key: Option<&str>,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut journal = load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+
+    if amount == 0 {
+        return Err(EconomicsError::InvalidBudget("Treasury entry amount must be positive".to_string()));
+    }
+    let period = StatementPeriod::containing(occurred_at)?;
+    if journal.closed_periods.contains(&period) {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "The {} statement of {} is closed", period, scope_id
+        )));
+    }
+    if let Some(reference) = &reference {
+        if let Some(existing) = journal.entries.iter().find(|e| e.reference.as_ref() == Some(reference)) {
+            return Ok(existing.id.clone());
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    journal.entries.push(TreasuryEntry {
+        id: id.clone(),
+        flow,
+        category: category.to_string(),
+        amount,
+        occurred_at,
+        reference,
+        tax_category: None,
+        idempotency_key: idempotency_key.map(str::to_string),
+    });
+    save_treasury_journal(&journal, storage).await?;
+    Ok(id)
+}
+
+/// CID of a statement's JSON bytes
+pub fn statement_cid(statement: &TreasuryStatement) -> EconomicsResult<Cid> {
+    let data = serde_json::to_vec(statement)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize treasury statement: {}", e)))?;
+    let digest = Sha256::digest(&data);
+    let hash = cid::multihash::Multihash::wrap(0x12, digest.as_slice())
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to hash treasury statement: {}", e)))?;
+    Ok(Cid::new_v1(RAW_CODEC, hash))
+}
+
+/// Close a month of a scope's treasury: build the statement, sign it as the treasurer,
+/// anchor it under its CID and stop accepting entries for the month.
+///
+/// The first month closed can be any month; after that months close in order, so every
+/// statement's opening balance is the previous statement's ending balance.
+pub async fn close_statement_period(
+    scope_id: &str,
+    period: StatementPeriod,
+    signer_did: &str,
+    signer_roles: &[String],
+    signer_keys: &KeyPair,
+    closed_at: i64,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<AnchoredStatement> {
+    if !signer_roles.iter().any(|role| role == TREASURER_ROLE) {
+        return Err(EconomicsError::Unauthorized(format!(
+            "Treasury statements must be signed by role {}", TREASURER_ROLE
+        )));
+    }
+
+    let mut journal = load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+    if journal.closed_periods.contains(&period) {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "The {} statement of {} is already closed", period, scope_id
+        )));
+    }
+    if let Some(last) = journal.closed_periods.iter().max() {
+        if last.next() != period {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "The next statement of {} to close is {}, not {}", scope_id, last.next(), period
+            )));
+        }
+    }
+
+    let (start, end) = period.bounds()?;
+    if closed_at < end {
+        return Err(EconomicsError::InvalidBudget(format!("The {} period has not ended", period)));
+    }
+
+    let mut inflows_by_category = BTreeMap::new();
+    let mut outflows_by_category = BTreeMap::new();
+    let mut entry_count = 0;
+    for entry in journal.entries.iter().filter(|e| e.occurred_at >= start && e.occurred_at < end) {
+        let totals = match entry.flow {
+            TreasuryFlow::Inflow => &mut inflows_by_category,
+            TreasuryFlow::Outflow => &mut outflows_by_category,
+        };
+        *totals.entry(entry.category.clone()).or_insert(0) += entry.amount;
+        entry_count += 1;
+    }
+
+    let opening_balance = journal.balance_before(start);
+    let total_inflows: u64 = inflows_by_category.values().sum();
+    let total_outflows: u64 = outflows_by_category.values().sum();
+
+    let mut statement = TreasuryStatement {
+        scope_id: scope_id.to_string(),
+        period,
+        unit: journal.unit.clone(),
+        opening_balance,
+        inflows_by_category,
+        outflows_by_category,
+        total_inflows,
+        total_outflows,
+        ending_balance: opening_balance + total_inflows as i64 - total_outflows as i64,
+        entry_count,
+        signer_did: signer_did.to_string(),
+        closed_at,
+        signature: String::new(),
+    };
+    let signature = signer_keys.sign(Sha256::digest(&statement.signing_bytes()?).as_slice())
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to sign treasury statement: {}", e)))?;
+    statement.signature = signature.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let cid = statement_cid(&statement)?;
+    let data = serde_json::to_vec(&statement)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize treasury statement: {}", e)))?;
+    storage.put_with_key(cid, data).await?;
+    storage.store_budget(&format!("{}{}::{}", STATEMENT_KEY_PREFIX, scope_id, period), cid.to_string().into_bytes()).await?;
+
+    journal.closed_periods.push(period);
+    save_treasury_journal(&journal, storage).await?;
+
+    Ok(AnchoredStatement { cid: cid.to_string(), statement })
+}
+
+/// The closed statement of a scope for a month, checked against its CID
+pub async fn get_statement(
+    scope_id: &str,
+    period: StatementPeriod,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<AnchoredStatement>> {
+    let pointer = match storage.get_budget(&format!("{}{}::{}", STATEMENT_KEY_PREFIX, scope_id, period)).await? {
+        Some(pointer) => pointer,
+        None => return Ok(None),
+    };
+    let cid = String::from_utf8(pointer).ok()
+        .and_then(|cid| Cid::try_from(cid.as_str()).ok())
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("Invalid statement CID for {} {}", scope_id, period)))?;
+
+    let data = storage.get_by_cid(&cid).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury statement {}", cid)))?;
+    let statement: TreasuryStatement = serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize treasury statement: {}", e)))?;
+
+    if statement_cid(&statement)? != cid {
+        return Err(EconomicsError::InvalidBudget(
+            format!("Treasury statement {} does not match its hash", cid)
+        ));
+    }
+    Ok(Some(AnchoredStatement { cid: cid.to_string(), statement }))
+}
+
+/// Every closed statement of a scope, oldest first
+pub async fn list_statements(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Vec<AnchoredStatement>> {
+    let mut periods = match load_treasury_journal(scope_id, storage).await? {
+        Some(journal) => journal.closed_periods,
+        None => return Ok(Vec::new()),
+    };
+    periods.sort();
+
+    let mut statements = Vec::new();
+    for period in periods {
+        if let Some(statement) = get_statement(scope_id, period, storage).await? {
+            statements.push(statement);
+        }
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    fn ts(year: i32, month: u32, day: u32) -> i64 {
+        Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(12, 0, 0).unwrap()).timestamp()
+    }
+
+    #[tokio::test]
+    async fn test_monthly_close_carries_balance_forward() {
+        let mut storage = MockBudgetStorage::new();
+        let keys = KeyPair::generate_random();
+        let treasurer = vec![TREASURER_ROLE.to_string()];
+        open_treasury_journal("coop-1", "USD", 1_000, &mut storage).await.unwrap();
+
+        record_treasury_entry("coop-1", TreasuryFlow::Inflow, "dues", 300, ts(2026, 9, 3), Some("pay-1".into()), &mut storage).await.unwrap();
+        record_treasury_entry("coop-1", TreasuryFlow::Inflow, "dues", 300, ts(2026, 9, 3), Some("pay-1".into()), &mut storage).await.unwrap();
+        record_treasury_entry("coop-1", TreasuryFlow::Outflow, "rent", 500, ts(2026, 9, 15), None, &mut storage).await.unwrap();
+        record_treasury_entry("coop-1", TreasuryFlow::Inflow, "donations", 50, ts(2026, 10, 2), None, &mut storage).await.unwrap();
+
+        let september = StatementPeriod::parse("2026-09").unwrap();
+        assert!(close_statement_period("coop-1", september, "did:icn:bob", &[], &keys, ts(2026, 10, 5), &mut storage).await.is_err());
+        assert!(close_statement_period("coop-1", september, "did:icn:bob", &treasurer, &keys, ts(2026, 9, 20), &mut storage).await.is_err());
+
+        let closed = close_statement_period("coop-1", september, "did:icn:bob", &treasurer, &keys, ts(2026, 10, 5), &mut storage).await.unwrap();
+        assert_eq!(closed.statement.opening_balance, 1_000);
+        assert_eq!(closed.statement.inflows_by_category.get("dues"), Some(&300));
+        assert_eq!(closed.statement.outflows_by_category.get("rent"), Some(&500));
+        assert_eq!(closed.statement.ending_balance, 800);
+        assert!(!closed.statement.signature.is_empty());
+        assert_eq!(get_statement("coop-1", september, &storage).await.unwrap(), Some(closed.clone()));
+
+        // A closed month takes no more entries, and months close in order
+        assert!(record_treasury_entry("coop-1", TreasuryFlow::Outflow, "rent", 10, ts(2026, 9, 30), None, &mut storage).await.is_err());
+        let november = StatementPeriod::new(2026, 11).unwrap();
+        assert!(close_statement_period("coop-1", november, "did:icn:bob", &treasurer, &keys, ts(2026, 12, 5), &mut storage).await.is_err());
+
+        let october = close_statement_period("coop-1", september.next(), "did:icn:bob", &treasurer, &keys, ts(2026, 11, 1), &mut storage).await.unwrap();
+        assert_eq!(october.statement.opening_balance, closed.statement.ending_balance);
+        assert_eq!(october.statement.ending_balance, 850);
+        assert_eq!(list_statements("coop-1", &storage).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::BudgetStorage;
+use crate::patronage::{MemberContribution, PatronageFormula, PatronageShare, compute_patronage_shares};
+use crate::rounding::RoundingPolicy;
+use crate::transfer_plan::{PlannedTransfer, TransferPlan, TransferPlanStatus, load_transfer_plan, save_transfer_plan};
+
+/// Storage key prefix for the plan distributing each period's surplus
+const SURPLUS_PERIOD_KEY_PREFIX: &str = "surplus::period::";
+
+/// Basis points in one whole (100%)
+const BASIS_POINTS: u64 = 10_000;
+
+/// How an end-of-period surplus is divided, from the `surplus_distribution` field of an
+/// economic model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SurplusPolicy {
+    /// The same amount to every member
+    Equal,
+
+    /// In proportion to each member's patronage
+    Patronage(PatronageFormula),
+
+    /// A share set aside for the reserve first, the rest divided by `then`
+    ReserveFirst { reserve_bps: u64, then: Box<SurplusPolicy> },
+}
+
+/// Split parameters at the commas outside any parentheses
+fn split_params(params: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&params[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&params[start..]);
+    parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+impl SurplusPolicy {
+    /// Build a policy from the `surplus_distribution` and `hourly_rates` fields of an
+    /// economic model.
+    ///
+    /// Accepts `"equal"`, any patronage formula (see
+    /// [`PatronageFormula::from_economic_model`]) or
+    /// `"reserve_first(reserve_bps=2000, then=patronage(hours=0.7, sales=0.3))"`. A
+    /// reserve-first policy without `then` divides the rest equally.
+    pub fn from_economic_model(
+        surplus_distribution: &str,
+        hourly_rates: Option<HashMap<String, u64>>,
+    ) -> EconomicsResult<Self> {
+        let spec = surplus_distribution.trim();
+        let (method, params) = match spec.find('(') {
+            Some(open) if spec.ends_with(')') => (spec[..open].trim(), &spec[open + 1..spec.len() - 1]),
+            Some(_) => return Err(EconomicsError::InvalidBudget(
+                format!("Malformed surplus distribution: {}", spec)
+            )),
+            None => (spec, ""),
+        };
+
+        match method {
+            "equal" if params.trim().is_empty() => Ok(SurplusPolicy::Equal),
+            "equal" => Err(EconomicsError::InvalidBudget(
+                "Equal surplus distribution takes no parameters".to_string()
+            )),
+            "patronage" => Ok(SurplusPolicy::Patronage(PatronageFormula::from_economic_model(spec, hourly_rates)?)),
+            "reserve_first" => {
+                let mut reserve_bps = None;
+                let mut then = SurplusPolicy::Equal;
+                for param in split_params(params) {
+                    let (key, value) = param.split_once('=').ok_or_else(|| EconomicsError::InvalidBudget(
+                        format!("Malformed reserve-first parameter: {}", param.trim())
+                    ))?;
+                    match key.trim() {
+                        "reserve_bps" => reserve_bps = Some(value.trim().parse::<u64>().map_err(|_| {
+                            EconomicsError::InvalidBudget(format!("Invalid reserve share: {}", value.trim()))
+                        })?),
+                        "then" => then = SurplusPolicy::from_economic_model(value, hourly_rates.clone())?,
+                        other => return Err(EconomicsError::InvalidBudget(
+                            format!("Unknown reserve-first parameter: {}", other)
+                        )),
+                    }
+                }
+
+                let reserve_bps = reserve_bps.ok_or_else(|| EconomicsError::InvalidBudget(
+                    "Reserve-first surplus distribution needs reserve_bps".to_string()
+                ))?;
+                if reserve_bps > BASIS_POINTS {
+                    return Err(EconomicsError::InvalidBudget(
+                        format!("Reserve share of {} bps is more than the whole surplus", reserve_bps)
+                    ));
+                }
+                if matches!(then, SurplusPolicy::ReserveFirst { .. }) {
+                    return Err(EconomicsError::InvalidBudget(
+                        "The rest of a reserve-first distribution can't set aside another reserve".to_string()
+                    ));
+                }
+                Ok(SurplusPolicy::ReserveFirst { reserve_bps, then: Box::new(then) })
+            }
+            other => Err(EconomicsError::InvalidBudget(
+                format!("Unknown surplus distribution: {}", other)
+            )),
+        }
+    }
+}
+
+/// A period's surplus divided under a policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurplusDistribution {
+    /// The whole surplus
+    pub surplus: u64,
+
+    /// Amount set aside for the reserve before members are paid, including any
+    /// rounding residue
+    pub reserve: u64,
+
+    /// Rounding residue of the members' shares, included in `reserve`
+    #[serde(default)]
+    pub residue: u64,
+
+    /// Each member's share of what's left; fractions are of that remainder
+    pub shares: Vec<PatronageShare>,
+}
+
+/// Divide `surplus` between the members under `policy`, rounding the shares under
+/// `rounding`.
+///
+/// The reserve and the shares always add up to exactly `surplus`.
+pub fn compute_surplus_distribution(
+    surplus: u64,
+    contributions: &[MemberContribution],
+    policy: &SurplusPolicy,
+    rounding: &RoundingPolicy,
+) -> EconomicsResult<SurplusDistribution> {
+    if contributions.is_empty() {
+        return Err(EconomicsError::InvalidBudget("No members to distribute the surplus to".to_string()));
+    }
+
+    let (reserve, shares) = match policy {
+        SurplusPolicy::Equal => {
+            let amounts = rounding.split(surplus, &vec![1; contributions.len()])?.shares;
+            let fraction = 1.0 / contributions.len() as f64;
+            let shares = contributions.iter().zip(amounts)
+                .map(|(c, amount)| PatronageShare { member_did: c.member_did.clone(), fraction, amount })
+                .collect();
+            (0, shares)
+        }
+        SurplusPolicy::Patronage(formula) => (0, compute_patronage_shares(surplus, contributions, formula, rounding)?),
+        SurplusPolicy::ReserveFirst { reserve_bps, then } => {
+            // Rounded to the nearest unit, the reserve could exceed a surplus that isn't
+            // a whole number of units
+            let reserve = rounding.apply_bps(surplus, *reserve_bps).min(surplus);
+            let rest = compute_surplus_distribution(surplus - reserve, contributions, then, rounding)?;
+            // The rest's residue is counted once, below
+            (reserve + rest.reserve - rest.residue, rest.shares)
+        }
+    };
+
+    let residue = surplus - reserve - shares.iter().map(|share| share.amount).sum::<u64>();
+    Ok(SurplusDistribution { surplus, reserve: reserve + residue, residue, shares })
+}
+
+/// The surplus of one accounting period and the members it's divided between
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SurplusPeriod {
+    /// The DID of the governing Coop/Community
+    pub scope_id: String,
+
+    /// Label of the period (e.g., "2025")
+    pub period: String,
+
+    /// The DID the surplus is paid from
+    pub treasury_did: String,
+
+    /// The DID the reserve is moved to; needed when the policy sets one aside
+    pub reserve_did: Option<String>,
+
+    pub resource_type: ResourceType,
+
+    pub surplus: u64,
+
+    /// Every member taking part in the distribution, with their contributions
+    pub contributions: Vec<MemberContribution>,
+}
+
+fn period_key(scope_id: &str, period: &str) -> String {
+    format!("{}{}::{}", SURPLUS_PERIOD_KEY_PREFIX, scope_id, period)
+}
+
+/// The plan distributing a period's surplus, if one was created
+pub async fn surplus_plan_for_period(
+    scope_id: &str,
+    period: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<TransferPlan>> {
+    let Some(data) = storage.get_budget(&period_key(scope_id, period)).await? else {
+        return Ok(None);
+    };
+    let plan_id: Uuid = serde_json::from_slice(&data)
+        .map_err(|e| EconomicsError::InvalidTransferPlan(format!("Failed to deserialize surplus plan index: {}", e)))?;
+    load_transfer_plan(&plan_id, storage).await.map(Some)
+}
+
+/// Divide a period's surplus under `policy` and store it as a draft transfer plan from
+/// the treasury, with the reserve and any rounding residue as its first transfer.
+///
+/// A period is distributed once: a new plan only replaces one that is still a draft or
+/// was rejected. The plan must be submitted for governance approval before it can be
+/// executed.
+pub async fn create_surplus_plan(
+    period: &SurplusPeriod,
+    policy: &SurplusPolicy,
+    rounding: &RoundingPolicy,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<TransferPlan> {
+    if let Some(existing) = surplus_plan_for_period(&period.scope_id, &period.period, storage).await? {
+        if !matches!(existing.status, TransferPlanStatus::Draft | TransferPlanStatus::Rejected { .. }) {
+            return Err(EconomicsError::InvalidTransferPlan(format!(
+                "The surplus of {} in {} is already distributed by plan {} ({:?})",
+                period.period, period.scope_id, existing.id, existing.status
+            )));
+        }
+    }
+
+    let distribution = compute_surplus_distribution(period.surplus, &period.contributions, policy, rounding)?;
+
+    let mut transfers = Vec::new();
+    if distribution.reserve > 0 {
+        let reserve_did = period.reserve_did.as_ref().ok_or_else(|| EconomicsError::InvalidTransferPlan(
+            "The surplus policy sets aside a reserve or rounding residue but no reserve account was given".to_string()
+        ))?;
+        transfers.push(PlannedTransfer {
+            recipient_did: reserve_did.clone(),
+            amount: distribution.reserve,
+            memo: Some(format!("Surplus reserve {}", period.period)),
+        });
+    }
+    transfers.extend(distribution.shares.iter()
+        .filter(|share| share.amount > 0)
+        .map(|share| PlannedTransfer {
+            recipient_did: share.member_did.clone(),
+            amount: share.amount,
+            memo: Some(format!("Surplus distribution {}", period.period)),
+        }));
+
+    let metadata = serde_json::json!({
+        "period": period.period,
+        "surplus": period.surplus,
+        "policy": policy,
+        "reserve": distribution.reserve,
+        "residue": distribution.residue,
+        "shares": distribution.shares,
+    });
+
+    let plan = TransferPlan::new(
+        &period.scope_id, &period.treasury_did, period.resource_type.clone(), "surplus", transfers, Some(metadata),
+    );
+    save_transfer_plan(&plan, storage).await?;
+
+    let index = serde_json::to_vec(&plan.id)
+        .map_err(|e| EconomicsError::InvalidTransferPlan(format!("Failed to serialize surplus plan index: {}", e)))?;
+    storage.store_budget(&period_key(&period.scope_id, &period.period), index).await?;
+
+    tracing::info!(
+        "Created surplus plan {} for {} in {} ({} reserved, {} transfers)",
+        plan.id, period.period, period.scope_id, distribution.reserve, plan.transfers.len()
+    );
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+    use crate::transfer_plan::{submit_transfer_plan, record_transfer_plan_decision};
+
+    fn contribution(did: &str, hours: f64) -> MemberContribution {
+        MemberContribution {
+            member_did: did.to_string(),
+            hours: HashMap::from([("general".to_string(), hours)]),
+            sales: 0,
+            tenure_days: 0,
+        }
+    }
+
+    fn period(surplus: u64) -> SurplusPeriod {
+        SurplusPeriod {
+            scope_id: "did:icn:coop".to_string(),
+            period: "2025".to_string(),
+            treasury_did: "did:icn:coop:treasury".to_string(),
+            reserve_did: Some("did:icn:coop:reserve".to_string()),
+            resource_type: ResourceType::Compute,
+            surplus,
+            contributions: vec![
+                contribution("did:icn:alice", 30.0),
+                contribution("did:icn:bob", 10.0),
+                contribution("did:icn:carol", 0.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_policies_from_economic_model() {
+        assert_eq!(SurplusPolicy::from_economic_model("equal", None).unwrap(), SurplusPolicy::Equal);
+        assert!(matches!(SurplusPolicy::from_economic_model("patronage(hours=1)", None).unwrap(), SurplusPolicy::Patronage(_)));
+
+        let policy = SurplusPolicy::from_economic_model(
+            "reserve_first(reserve_bps=2500, then=patronage(hours=0.5, sales=0.5))", None
+        ).unwrap();
+        let SurplusPolicy::ReserveFirst { reserve_bps, then } = policy else { panic!("expected reserve first") };
+        assert_eq!(reserve_bps, 2500);
+        assert!(matches!(*then, SurplusPolicy::Patronage(ref f) if f.sales_weight == 0.5));
+
+        assert!(SurplusPolicy::from_economic_model("reserve_first(reserve_bps=12000)", None).is_err());
+        assert!(SurplusPolicy::from_economic_model("reserve_first(then=equal)", None).is_err());
+        assert!(SurplusPolicy::from_economic_model("lottery", None).is_err());
+    }
+
+    #[test]
+    fn test_distributions_add_up_to_the_surplus() {
+        let contributions = period(0).contributions;
+
+        let equal = compute_surplus_distribution(1000, &contributions, &SurplusPolicy::Equal, &RoundingPolicy::default()).unwrap();
+        assert_eq!(equal.shares.iter().map(|s| s.amount).collect::<Vec<_>>(), vec![334, 333, 333]);
+
+        let policy = SurplusPolicy::from_economic_model("reserve_first(reserve_bps=2000, then=patronage)", None).unwrap();
+        let reserved = compute_surplus_distribution(1000, &contributions, &policy, &RoundingPolicy::default()).unwrap();
+        assert_eq!(reserved.reserve, 200);
+        assert_eq!(reserved.shares.iter().map(|s| s.amount).collect::<Vec<_>>(), vec![600, 200, 0]);
+
+        // Kept back, the residue joins the reserve
+        let rounding = RoundingPolicy::from_economic_model("down(residue=reserve)").unwrap();
+        let equal = compute_surplus_distribution(1000, &contributions, &SurplusPolicy::Equal, &rounding).unwrap();
+        assert_eq!(equal.shares.iter().map(|s| s.amount).collect::<Vec<_>>(), vec![333, 333, 333]);
+        assert_eq!((equal.reserve, equal.residue), (1, 1));
+
+        let rounding = RoundingPolicy::from_economic_model("half_even(unit=10, residue=reserve)").unwrap();
+        let policy = SurplusPolicy::from_economic_model("reserve_first(reserve_bps=1250, then=equal)", None).unwrap();
+        for surplus in [0, 1, 999, 1000, 1001, 54_321] {
+            let distribution = compute_surplus_distribution(surplus, &contributions, &policy, &rounding).unwrap();
+            let paid: u64 = distribution.shares.iter().map(|s| s.amount).sum();
+            assert_eq!(distribution.reserve + paid, surplus);
+            assert!(distribution.residue <= distribution.reserve);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_surplus_plan_is_created_once_per_period() {
+        let mut storage = MockBudgetStorage::new();
+        let policy = SurplusPolicy::from_economic_model("reserve_first(reserve_bps=2000, then=patronage)", None).unwrap();
+
+        let plan = create_surplus_plan(&period(1000), &policy, &RoundingPolicy::default(), &mut storage).await.unwrap();
+        assert_eq!(plan.status, TransferPlanStatus::Draft);
+        assert_eq!(plan.transfers[0].recipient_did, "did:icn:coop:reserve");
+        assert_eq!(plan.transfers.len(), 3);
+        assert_eq!(plan.total_amount(), 1000);
+
+        // A draft can be replaced, a plan awaiting approval can't
+        let plan = create_surplus_plan(&period(1000), &policy, &RoundingPolicy::default(), &mut storage).await.unwrap();
+        submit_transfer_plan(&plan.id, &mut storage).await.unwrap();
+        assert!(create_surplus_plan(&period(1000), &policy, &RoundingPolicy::default(), &mut storage).await.is_err());
+
+        // Once rejected, the period can be planned again
+        record_transfer_plan_decision(&plan.id, "proposal:surplus-2025", false, &mut storage).await.unwrap();
+        let replanned = create_surplus_plan(&period(800), &policy, &RoundingPolicy::default(), &mut storage).await.unwrap();
+        let stored = surplus_plan_for_period("did:icn:coop", "2025", &storage).await.unwrap().unwrap();
+        assert_eq!(stored.id, replanned.id);
+
+        let mut no_reserve = period(1000);
+        no_reserve.period = "2026".to_string();
+        no_reserve.reserve_did = None;
+        assert!(create_surplus_plan(&no_reserve, &policy, &RoundingPolicy::default(), &mut storage).await.is_err());
+    }
+}
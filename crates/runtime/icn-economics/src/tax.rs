@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, HashSet};
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult, ParticipatoryBudget};
+use crate::budget_ops::BudgetStorage;
+use crate::statements::{
+    self, StatementPeriod, TreasuryEntry, TreasuryFlow, TREASURER_ROLE,
+};
+
+/// Storage key prefix for per-scope tax regimes
+const TAX_REGIME_KEY_PREFIX: &str = "tax::regime::";
+
+/// A category of taxable activity, e.g., standard-rated or zero-rated VAT
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxCategory {
+    /// Code entries are tagged with (e.g., "S", "Z", "E")
+    pub code: String,
+    pub name: String,
+
+    /// Tax rate in basis points; amounts tagged with it are tax-inclusive
+    pub rate_bps: u32,
+}
+
+/// The tax categories a scope reports under, as set by its jurisdiction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxRegime {
+    pub scope_id: String,
+
+    /// Jurisdiction the categories come from (e.g., "GB", "DE", "CA-QC")
+    pub jurisdiction: String,
+
+    pub categories: Vec<TaxCategory>,
+}
+
+impl TaxRegime {
+    pub fn category(&self, code: &str) -> Option<&TaxCategory> {
+        self.categories.iter().find(|c| c.code == code)
+    }
+
+    fn validate(&self) -> EconomicsResult<()> {
+        let mut codes = HashSet::new();
+        for category in &self.categories {
+            if category.code.is_empty() {
+                return Err(EconomicsError::InvalidBudget("Tax category codes can't be empty".to_string()));
+            }
+            if !codes.insert(category.code.as_str()) {
+                return Err(EconomicsError::InvalidBudget(format!(
+                    "Tax category {} is defined twice for {}", category.code, self.jurisdiction
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tax included in a tax-inclusive amount
+pub fn tax_included(gross: u64, rate_bps: u32) -> u64 {
+    ((gross as u128 * rate_bps as u128) / (10_000 + rate_bps as u128)) as u64
+}
+
+/// Totals for one tax category and direction over a report's periods
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxSummaryLine {
+    pub code: String,
+    pub name: String,
+    pub rate_bps: u32,
+    pub flow: TreasuryFlow,
+    pub gross: u64,
+    pub tax: u64,
+    pub entry_count: usize,
+}
+
+/// Tax totals of a scope over a run of closed statement periods
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaxSummary {
+    pub scope_id: String,
+    pub jurisdiction: String,
+    pub unit: String,
+    pub from: StatementPeriod,
+    pub to: StatementPeriod,
+    pub lines: Vec<TaxSummaryLine>,
+
+    /// Tax collected on inflows
+    pub output_tax: u64,
+
+    /// Tax paid on outflows
+    pub input_tax: u64,
+
+    /// Output tax less input tax; negative when a refund is due
+    pub net_tax: i64,
+
+    /// Entries in the periods with no tax category
+    pub untagged_entries: usize,
+
+    /// CIDs of the signed statements the report covers, oldest first
+    pub statement_cids: Vec<String>,
+}
+
+/// Store a scope's tax regime
+pub async fn save_tax_regime(
+    regime: &TaxRegime,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let data = serde_json::to_vec(regime)
+        .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to serialize tax regime: {}", e)))?;
+
+    let key = format!("{}{}", TAX_REGIME_KEY_PREFIX, regime.scope_id);
+    storage.store_budget(&key, data).await
+}
+
+/// Load a scope's tax regime, if one has been set
+pub async fn load_tax_regime(
+    scope_id: &str,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<Option<TaxRegime>> {
+    let key = format!("{}{}", TAX_REGIME_KEY_PREFIX, scope_id);
+    match storage.get_budget(&key).await? {
+        Some(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| EconomicsError::InvalidBudget(format!("Failed to deserialize tax regime: {}", e))),
+        None => Ok(None),
+    }
+}
+
+fn require_treasurer(signer_roles: &[String], action: &str) -> EconomicsResult<()> {
+    if !signer_roles.iter().any(|role| role == TREASURER_ROLE) {
+        return Err(EconomicsError::Unauthorized(format!("Only role {} may {}", TREASURER_ROLE, action)));
+    }
+    Ok(())
+}
+
+/// Set the tax categories a scope reports under. Categories already used by entries in
+/// open periods can't be removed.
+pub async fn set_tax_regime(
+    regime: TaxRegime,
+    signer_roles: &[String],
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    require_treasurer(signer_roles, "set the tax regime")?;
+    regime.validate()?;
+
+    if let Some(journal) = statements::load_treasury_journal(&regime.scope_id, storage).await? {
+        for entry in &journal.entries {
+            if let Some(code) = &entry.tax_category {
+                let period = StatementPeriod::containing(entry.occurred_at)?;
+                if !journal.closed_periods.contains(&period) && regime.category(code).is_none() {
+                    return Err(EconomicsError::InvalidBudget(format!(
+                        "Tax category {} is still used by entry {}", code, entry.id
+                    )));
+                }
+            }
+        }
+    }
+
+    save_tax_regime(&regime, storage).await
+}
+
+/// Tag a treasury entry with a tax category of the scope's regime, or clear its tag.
+/// Entries in closed months are part of a signed statement and can't be retagged.
+pub async fn tag_treasury_entry(
+    scope_id: &str,
+    entry_id: &str,
+    tax_category: Option<&str>,
+    signer_roles: &[String],
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    require_treasurer(signer_roles, "tag treasury entries")?;
+
+    if let Some(code) = tax_category {
+        let regime = load_tax_regime(scope_id, storage).await?
+            .ok_or_else(|| EconomicsError::InvalidBudget(format!("No tax regime for {}", scope_id)))?;
+        if regime.category(code).is_none() {
+            return Err(EconomicsError::InvalidBudget(format!(
+                "Unknown tax category {} in {}", code, regime.jurisdiction
+            )));
+        }
+    }
+
+    let mut journal = statements::load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+    let closed_periods = journal.closed_periods.clone();
+    let entry = journal.entries.iter_mut()
+        .find(|e| e.id == entry_id)
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury entry {} in {}", entry_id, scope_id)))?;
+
+    let period = StatementPeriod::containing(entry.occurred_at)?;
+    if closed_periods.contains(&period) {
+        return Err(EconomicsError::InvalidBudget(format!(
+            "The {} statement of {} is closed", period, scope_id
+        )));
+    }
+
+    entry.tax_category = tax_category.map(str::to_string);
+    statements::save_treasury_journal(&journal, storage).await
+}
+
+/// Check that every tax category a budget's categories are tagged with exists in the
+/// regime. Returns the budget categories left untagged.
+pub fn check_budget_tax_categories(
+    budget: &ParticipatoryBudget,
+    regime: &TaxRegime,
+) -> EconomicsResult<Vec<String>> {
+    let mut untagged = Vec::new();
+    let categories = budget.rules.as_ref().and_then(|rules| rules.categories.as_ref());
+    for (name, rule) in categories.into_iter().flatten() {
+        match &rule.tax_category {
+            Some(code) if regime.category(code).is_none() => {
+                return Err(EconomicsError::InvalidBudget(format!(
+                    "Budget category {} of {} is tagged with unknown tax category {}", name, budget.id, code
+                )));
+            },
+            Some(_) => {},
+            None => untagged.push(name.clone()),
+        }
+    }
+    untagged.sort();
+    Ok(untagged)
+}
+
+/// The entries of `from..=to` together with the signed statements covering them.
+/// Every period in the range must be closed, so reports only show figures members have
+/// already seen in a statement.
+async fn closed_entries(
+    scope_id: &str,
+    from: StatementPeriod,
+    to: StatementPeriod,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<(String, Vec<TreasuryEntry>, Vec<String>)> {
+    if to < from {
+        return Err(EconomicsError::InvalidBudget(format!("Tax report period {} ends before {}", to, from)));
+    }
+    let journal = statements::load_treasury_journal(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No treasury journal for {}", scope_id)))?;
+
+    let mut statement_cids = Vec::new();
+    let mut period = from;
+    loop {
+        let statement = statements::get_statement(scope_id, period, storage).await?
+            .ok_or_else(|| EconomicsError::InvalidBudget(format!(
+                "The {} statement of {} is not closed", period, scope_id
+            )))?;
+        statement_cids.push(statement.cid);
+        if period == to {
+            break;
+        }
+        period = period.next();
+    }
+
+    let (start, _) = from.bounds()?;
+    let (_, end) = to.bounds()?;
+    let mut entries: Vec<TreasuryEntry> = journal.entries.into_iter()
+        .filter(|e| e.occurred_at >= start && e.occurred_at < end)
+        .collect();
+    entries.sort_by(|a, b| a.occurred_at.cmp(&b.occurred_at).then_with(|| a.id.cmp(&b.id)));
+    Ok((journal.unit, entries, statement_cids))
+}
+
+/// Summarize tax by category over the closed periods `from..=to`
+pub async fn tax_summary(
+    scope_id: &str,
+    from: StatementPeriod,
+    to: StatementPeriod,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<TaxSummary> {
+    let regime = load_tax_regime(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No tax regime for {}", scope_id)))?;
+    let (unit, entries, statement_cids) = closed_entries(scope_id, from, to, storage).await?;
+
+    let mut lines: BTreeMap<(String, bool), TaxSummaryLine> = BTreeMap::new();
+    let mut untagged_entries = 0;
+    for entry in &entries {
+        let category = match entry.tax_category.as_deref().and_then(|code| regime.category(code)) {
+            Some(category) => category,
+            None => {
+                untagged_entries += 1;
+                continue;
+            }
+        };
+        let line = lines.entry((category.code.clone(), entry.flow == TreasuryFlow::Outflow))
+            .or_insert_with(|| TaxSummaryLine {
+                code: category.code.clone(),
+                name: category.name.clone(),
+                rate_bps: category.rate_bps,
+                flow: entry.flow,
+                gross: 0,
+                tax: 0,
+                entry_count: 0,
+            });
+        line.gross += entry.amount;
+        line.tax += tax_included(entry.amount, category.rate_bps);
+        line.entry_count += 1;
+    }
+
+    let lines: Vec<TaxSummaryLine> = lines.into_values().collect();
+    let output_tax = lines.iter().filter(|l| l.flow == TreasuryFlow::Inflow).map(|l| l.tax).sum::<u64>();
+    let input_tax = lines.iter().filter(|l| l.flow == TreasuryFlow::Outflow).map(|l| l.tax).sum::<u64>();
+
+    Ok(TaxSummary {
+        scope_id: scope_id.to_string(),
+        jurisdiction: regime.jurisdiction,
+        unit,
+        from,
+        to,
+        lines,
+        output_tax,
+        input_tax,
+        net_tax: output_tax as i64 - input_tax as i64,
+        untagged_entries,
+        statement_cids,
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Export the entries of the closed periods `from..=to` as CSV, one row per entry with
+/// its tax split and the CID of the statement it was closed in
+pub async fn export_tax_csv(
+    scope_id: &str,
+    from: StatementPeriod,
+    to: StatementPeriod,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let regime = load_tax_regime(scope_id, storage).await?
+        .ok_or_else(|| EconomicsError::InvalidBudget(format!("No tax regime for {}", scope_id)))?;
+    let (unit, entries, statement_cids) = closed_entries(scope_id, from, to, storage).await?;
+
+    let mut csv = String::from(
+        "entry_id,period,occurred_at,flow,category,tax_category,rate_bps,gross,tax,net,unit,reference,statement_cid\n"
+    );
+    for entry in &entries {
+        let period = StatementPeriod::containing(entry.occurred_at)?;
+        let statement_cid = &statement_cids[period_index(from, period)];
+        let category = entry.tax_category.as_deref().and_then(|code| regime.category(code));
+        let rate_bps = category.map(|c| c.rate_bps).unwrap_or(0);
+        let tax = tax_included(entry.amount, rate_bps);
+        let flow = match entry.flow {
+            TreasuryFlow::Inflow => "inflow",
+            TreasuryFlow::Outflow => "outflow",
+        };
+
+        let row = [
+            csv_field(&entry.id),
+            period.to_string(),
+            entry.occurred_at.to_string(),
+            flow.to_string(),
+            csv_field(&entry.category),
+            csv_field(entry.tax_category.as_deref().unwrap_or("")),
+            rate_bps.to_string(),
+            entry.amount.to_string(),
+            tax.to_string(),
+            (entry.amount - tax).to_string(),
+            csv_field(&unit),
+            csv_field(entry.reference.as_deref().unwrap_or("")),
+            statement_cid.clone(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+/// Months from `from` to `period`
+fn period_index(from: StatementPeriod, period: StatementPeriod) -> usize {
+    ((period.year - from.year) * 12 + period.month as i32 - from.month as i32) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use icn_identity::KeyPair;
+    use crate::budget_ops::MockBudgetStorage;
+    use crate::statements::{close_statement_period, open_treasury_journal, record_treasury_entry};
+
+    fn ts(year: i32, month: u32, day: u32) -> i64 {
+        Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(12, 0, 0).unwrap()).timestamp()
+    }
+
+    #[tokio::test]
+    async fn test_tagged_entries_summarize_and_export_from_closed_periods() {
+        let mut storage = MockBudgetStorage::new();
+        let treasurer = vec![TREASURER_ROLE.to_string()];
+        let keys = KeyPair::generate_random();
+        open_treasury_journal("coop-1", "EUR", 0, &mut storage).await.unwrap();
+
+        let regime = TaxRegime {
+            scope_id: "coop-1".to_string(),
+            jurisdiction: "DE".to_string(),
+            categories: vec![
+                TaxCategory { code: "S".to_string(), name: "Standard rate".to_string(), rate_bps: 1_900 },
+                TaxCategory { code: "E".to_string(), name: "Exempt".to_string(), rate_bps: 0 },
+            ],
+        };
+        assert!(set_tax_regime(regime.clone(), &[], &mut storage).await.is_err());
+        set_tax_regime(regime, &treasurer, &mut storage).await.unwrap();
+
+        let sale = record_treasury_entry("coop-1", TreasuryFlow::Inflow, "sales", 1_190, ts(2026, 9, 3), None, &mut storage).await.unwrap();
+        let supplies = record_treasury_entry("coop-1", TreasuryFlow::Outflow, "supplies, office", 119, ts(2026, 9, 10), None, &mut storage).await.unwrap();
+        record_treasury_entry("coop-1", TreasuryFlow::Inflow, "dues", 50, ts(2026, 9, 12), None, &mut storage).await.unwrap();
+        tag_treasury_entry("coop-1", &sale, Some("S"), &treasurer, &mut storage).await.unwrap();
+        tag_treasury_entry("coop-1", &supplies, Some("S"), &treasurer, &mut storage).await.unwrap();
+        assert!(tag_treasury_entry("coop-1", &sale, Some("R"), &treasurer, &mut storage).await.is_err());
+
+        // Reports only cover periods with a signed statement
+        let september = StatementPeriod::new(2026, 9).unwrap();
+        assert!(tax_summary("coop-1", september, september, &storage).await.is_err());
+        let statement = close_statement_period("coop-1", september, "did:icn:bob", &treasurer, &keys, ts(2026, 10, 2), &mut storage).await.unwrap();
+        assert!(tag_treasury_entry("coop-1", &sale, Some("E"), &treasurer, &mut storage).await.is_err());
+
+        let summary = tax_summary("coop-1", september, september, &storage).await.unwrap();
+        assert_eq!(summary.output_tax, 190);
+        assert_eq!(summary.input_tax, 19);
+        assert_eq!(summary.net_tax, 171);
+        assert_eq!(summary.untagged_entries, 1);
+        assert_eq!(summary.statement_cids, vec![statement.cid.clone()]);
+
+        let csv = export_tax_csv("coop-1", september, september, &storage).await.unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with(&format!("{},2026-09,", sale)));
+        assert!(rows[1].ends_with(&format!(",1900,1190,190,1000,EUR,,{}", statement.cid)));
+        assert!(rows[2].contains(",outflow,\"supplies, office\",S,"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{EconomicsError, EconomicsResult, ScopedResourceToken, ResourceAuthorization};
+use crate::idempotency::IdempotencyStorage;
 use async_trait::async_trait;
 use cid::Cid;
 use sha2::{Sha256, Digest};
@@ -197,6 +198,8 @@ pub struct MockTokenStorage {
     pub tokens: HashMap<Uuid, ScopedResourceToken>,
     /// Mock cids for list_all
     pub cids: Vec<Cid>,
+    /// Idempotency records of transfers, by key
+    pub records: HashMap<String, Vec<u8>>,
 }
 
 impl MockTokenStorage {
@@ -205,10 +208,23 @@ impl MockTokenStorage {
         Self {
             tokens: HashMap::new(),
             cids: Vec::new(),
+            records: HashMap::new(),
         }
     }
 }
 
+#[async_trait]
+impl IdempotencyStorage for MockTokenStorage {
+    async fn load_idempotency_data(&self, key: &str) -> EconomicsResult<Option<Vec<u8>>> {
+        Ok(self.records.get(key).cloned())
+    }
+
+    async fn store_idempotency_data(&mut self, key: &str, data: Vec<u8>) -> EconomicsResult<()> {
+        self.records.insert(key.to_string(), data);
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl TokenStorage for MockTokenStorage {
     async fn store_token(&mut self, token: &ScopedResourceToken) -> EconomicsResult<()> {
@@ -0,0 +1,382 @@
+use uuid::Uuid;
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use crate::{EconomicsError, EconomicsResult, ResourceType};
+use crate::budget_ops::BudgetStorage;
+use crate::idempotency::{self, DEFAULT_DEDUP_WINDOW_SECS};
+
+/// Storage key prefix for transfer plans
+const TRANSFER_PLAN_KEY_PREFIX: &str = "transfer_plan::";
+
+/// A single transfer within a plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedTransfer {
+    /// The DID receiving the transfer
+    pub recipient_did: String,
+
+    /// Amount to transfer
+    pub amount: u64,
+
+    /// Optional human-readable note (e.g., "patronage dividend 2024")
+    pub memo: Option<String>,
+}
+
+/// Status of a transfer plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TransferPlanStatus {
+    /// Plan has been drafted but not yet submitted to governance
+    Draft,
+
+    /// Plan is awaiting a governance decision
+    PendingApproval,
+
+    /// Plan was approved by the referenced governance proposal
+    Approved { proposal_id: String },
+
+    /// Plan was rejected by the referenced governance proposal
+    Rejected { proposal_id: String },
+
+    /// All transfers in the plan have been executed
+    Executed { executed_at: i64 },
+
+    /// Execution stopped partway; `completed` transfers were applied
+    Failed { completed: usize, reason: String },
+}
+
+/// A set of transfers from a single source that requires governance approval before execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferPlan {
+    /// Unique identifier for this plan
+    pub id: Uuid,
+
+    /// The DID of the governing Coop/Community
+    pub scope_id: String,
+
+    /// The DID funds are transferred from (typically the treasury)
+    pub source_did: String,
+
+    /// The resource being transferred
+    pub resource_type: ResourceType,
+
+    /// Why the plan exists (e.g., "patronage", "reimbursement")
+    pub purpose: String,
+
+    /// The transfers to execute
+    pub transfers: Vec<PlannedTransfer>,
+
+    /// Current status of the plan
+    pub status: TransferPlanStatus,
+
+    /// Unix timestamp when this plan was created
+    pub created_at: i64,
+
+    /// Additional metadata (e.g., the formula or period the plan was derived from)
+    pub metadata: Option<serde_json::Value>,
+
+    /// Idempotency key of the call that executed the plan
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+}
+
+impl TransferPlan {
+    /// Create a new draft plan
+    pub fn new(
+        scope_id: &str,
+        source_did: &str,
+        resource_type: ResourceType,
+        purpose: &str,
+        transfers: Vec<PlannedTransfer>,
+        metadata: Option<serde_json::Value>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            scope_id: scope_id.to_string(),
+            source_did: source_did.to_string(),
+            resource_type,
+            purpose: purpose.to_string(),
+            transfers,
+            status: TransferPlanStatus::Draft,
+            created_at: chrono::Utc::now().timestamp(),
+            metadata,
+            idempotency_key: None,
+        }
+    }
+
+    /// Total amount moved by this plan
+    pub fn total_amount(&self) -> u64 {
+        self.transfers.iter().map(|t| t.amount).sum()
+    }
+
+    /// Summary suitable for the body of a governance proposal approving this plan
+    pub fn approval_summary(&self) -> String {
+        let mut summary = format!(
+            "Transfer plan {} ({}): {} transfers totalling {} {:?} from {}",
+            self.id, self.purpose, self.transfers.len(), self.total_amount(), self.resource_type, self.source_did
+        );
+        for transfer in &self.transfers {
+            summary.push_str(&format!("\n- {}: {}", transfer.recipient_did, transfer.amount));
+        }
+        summary
+    }
+}
+
+/// Executes individual transfers of a plan against a ledger
+#[async_trait]
+pub trait TransferExecutor: Send + Sync {
+    /// Move `amount` of `resource_type` from `from_did` to `to_did`
+    async fn execute_transfer(
+        &mut self,
+        from_did: &str,
+        to_did: &str,
+        resource_type: &ResourceType,
+        amount: u64,
+    ) -> EconomicsResult<()>;
+}
+
+/// Store a transfer plan
+pub async fn save_transfer_plan(
+    plan: &TransferPlan,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<()> {
+    let plan_data = serde_json::to_vec(plan)
+        .map_err(|e| EconomicsError::InvalidTransferPlan(format!("Failed to serialize transfer plan: {}", e)))?;
+
+    let storage_key = format!("{}{}", TRANSFER_PLAN_KEY_PREFIX, plan.id);
+    storage.store_budget(&storage_key, plan_data)
+        .await
+        .map_err(|e| EconomicsError::InvalidTransferPlan(format!("Failed to store transfer plan: {}", e)))
+}
+
+/// Load a transfer plan from storage
+pub async fn load_transfer_plan(
+    plan_id: &Uuid,
+    storage: &impl BudgetStorage,
+) -> EconomicsResult<TransferPlan> {
+    let storage_key = format!("{}{}", TRANSFER_PLAN_KEY_PREFIX, plan_id);
+
+    let plan_data = storage.get_budget(&storage_key)
+        .await
+        .map_err(|e| EconomicsError::InvalidTransferPlan(format!("Failed to load transfer plan: {}", e)))?
+        .ok_or_else(|| EconomicsError::InvalidTransferPlan(format!("Transfer plan not found with id: {}", plan_id)))?;
+
+    serde_json::from_slice(&plan_data)
+        .map_err(|e| EconomicsError::InvalidTransferPlan(format!("Failed to deserialize transfer plan: {}", e)))
+}
+
+/// Submit a draft plan for governance approval.
+///
+/// Returns the summary to use as the governance proposal body.
+pub async fn submit_transfer_plan(
+    plan_id: &Uuid,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<String> {
+    let mut plan = load_transfer_plan(plan_id, storage).await?;
+
+    if plan.status != TransferPlanStatus::Draft {
+        return Err(EconomicsError::InvalidTransferPlan(
+            format!("Only draft plans can be submitted, plan {} is {:?}", plan_id, plan.status)
+        ));
+    }
+
+    if plan.transfers.is_empty() {
+        return Err(EconomicsError::InvalidTransferPlan(
+            format!("Transfer plan {} has no transfers", plan_id)
+        ));
+    }
+
+    plan.status = TransferPlanStatus::PendingApproval;
+    save_transfer_plan(&plan, storage).await?;
+
+    Ok(plan.approval_summary())
+}
+
+/// Record the outcome of the governance proposal that decided on a plan
+pub async fn record_transfer_plan_decision(
+    plan_id: &Uuid,
+    proposal_id: &str,
+    approved: bool,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<TransferPlanStatus> {
+    let mut plan = load_transfer_plan(plan_id, storage).await?;
+
+    if plan.status != TransferPlanStatus::PendingApproval {
+        return Err(EconomicsError::InvalidTransferPlan(
+            format!("Transfer plan {} is not pending approval: {:?}", plan_id, plan.status)
+        ));
+    }
+
+    plan.status = if approved {
+        TransferPlanStatus::Approved { proposal_id: proposal_id.to_string() }
+    } else {
+        TransferPlanStatus::Rejected { proposal_id: proposal_id.to_string() }
+    };
+
+    save_transfer_plan(&plan, storage).await?;
+
+    Ok(plan.status)
+}
+
+/// Execute an approved plan.
+///
+/// Transfers are applied in order; on failure the plan is marked `Failed` with the
+/// number of transfers that were already applied. A retried call with the same
+/// `idempotency_key` returns the executed plan instead of applying it again.
+pub async fn execute_transfer_plan(
+    plan_id: &Uuid,
+    idempotency_key: &str,
+    executor: &mut impl TransferExecutor,
+    storage: &mut impl BudgetStorage,
+) -> EconomicsResult<TransferPlan> {
+    let mut plan = load_transfer_plan(plan_id, storage).await?;
+
+    let now = chrono::Utc::now().timestamp();
+    let request_hash = idempotency::request_fingerprint(plan_id)?;
+    if let Some(executed) = idempotency::replay_result(
+        &plan.scope_id, idempotency_key, "execute_transfer_plan", &request_hash, now, storage,
+    ).await? {
+        return Ok(executed);
+    }
+
+    if !matches!(plan.status, TransferPlanStatus::Approved { .. }) {
+        return Err(EconomicsError::Unauthorized(
+            format!("Transfer plan {} has not been approved by governance: {:?}", plan_id, plan.status)
+        ));
+    }
+
+    plan.idempotency_key = Some(idempotency_key.to_string());
+    let transfers = plan.transfers.clone();
+    for (index, transfer) in transfers.iter().enumerate() {
+        if let Err(e) = executor.execute_transfer(
+            &plan.source_did,
+            &transfer.recipient_did,
+            &plan.resource_type,
+            transfer.amount,
+        ).await {
+            tracing::warn!("Transfer plan {} failed at transfer {}: {}", plan_id, index, e);
+            plan.status = TransferPlanStatus::Failed { completed: index, reason: e.to_string() };
+            save_transfer_plan(&plan, storage).await?;
+            return Err(e);
+        }
+    }
+
+    plan.status = TransferPlanStatus::Executed { executed_at: chrono::Utc::now().timestamp() };
+    save_transfer_plan(&plan, storage).await?;
+    idempotency::remember_result(
+        &plan.scope_id, idempotency_key, "execute_transfer_plan", &request_hash, &plan, now, DEFAULT_DEDUP_WINDOW_SECS, storage,
+    ).await?;
+
+    tracing::info!("Executed transfer plan {} ({} transfers)", plan_id, plan.transfers.len());
+
+    Ok(plan)
+}
+
+/// In-memory executor that tracks balances, for testing
+#[derive(Default, Debug, Clone)]
+pub struct MockTransferExecutor {
+    /// Balances keyed by DID
+    pub balances: std::collections::HashMap<String, u64>,
+}
+
+#[async_trait]
+impl TransferExecutor for MockTransferExecutor {
+    async fn execute_transfer(
+        &mut self,
+        from_did: &str,
+        to_did: &str,
+        _resource_type: &ResourceType,
+        amount: u64,
+    ) -> EconomicsResult<()> {
+        let available = self.balances.get(from_did).copied().unwrap_or(0);
+        if available < amount {
+            return Err(EconomicsError::InsufficientBalance(
+                format!("{} has {} but {} was requested", from_did, available, amount)
+            ));
+        }
+        self.balances.insert(from_did.to_string(), available - amount);
+        *self.balances.entry(to_did.to_string()).or_insert(0) += amount;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget_ops::MockBudgetStorage;
+
+    fn test_plan() -> TransferPlan {
+        TransferPlan::new(
+            "did:icn:coop",
+            "did:icn:treasury",
+            ResourceType::Compute,
+            "test",
+            vec![
+                PlannedTransfer { recipient_did: "did:icn:alice".to_string(), amount: 60, memo: None },
+                PlannedTransfer { recipient_did: "did:icn:bob".to_string(), amount: 40, memo: None },
+            ],
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_plan_requires_approval_before_execution() {
+        let mut storage = MockBudgetStorage::new();
+        let mut executor = MockTransferExecutor::default();
+        executor.balances.insert("did:icn:treasury".to_string(), 100);
+
+        let plan = test_plan();
+        save_transfer_plan(&plan, &mut storage).await.unwrap();
+
+        // Draft plans cannot be executed
+        assert!(execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.is_err());
+
+        submit_transfer_plan(&plan.id, &mut storage).await.unwrap();
+        assert!(execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.is_err());
+
+        record_transfer_plan_decision(&plan.id, "proposal:pay", true, &mut storage).await.unwrap();
+        let executed = execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.unwrap();
+
+        assert!(matches!(executed.status, TransferPlanStatus::Executed { .. }));
+        assert_eq!(executor.balances.get("did:icn:alice"), Some(&60));
+        assert_eq!(executor.balances.get("did:icn:bob"), Some(&40));
+        assert_eq!(executor.balances.get("did:icn:treasury"), Some(&0));
+        assert_eq!(executed.idempotency_key.as_deref(), Some("pay-1"));
+
+        // A retried call returns the executed plan without paying again
+        let retried = execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.unwrap();
+        assert_eq!(retried.status, executed.status);
+        assert_eq!(executor.balances.get("did:icn:alice"), Some(&60));
+
+        // A new key is a new call, and the plan has already run
+        assert!(execute_transfer_plan(&plan.id, "pay-2", &mut executor, &mut storage).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_plan_cannot_execute() {
+        let mut storage = MockBudgetStorage::new();
+        let mut executor = MockTransferExecutor::default();
+
+        let plan = test_plan();
+        save_transfer_plan(&plan, &mut storage).await.unwrap();
+        submit_transfer_plan(&plan.id, &mut storage).await.unwrap();
+        record_transfer_plan_decision(&plan.id, "proposal:pay", false, &mut storage).await.unwrap();
+
+        assert!(execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_partial_failure_is_recorded() {
+        let mut storage = MockBudgetStorage::new();
+        let mut executor = MockTransferExecutor::default();
+        executor.balances.insert("did:icn:treasury".to_string(), 70);
+
+        let plan = test_plan();
+        save_transfer_plan(&plan, &mut storage).await.unwrap();
+        submit_transfer_plan(&plan.id, &mut storage).await.unwrap();
+        record_transfer_plan_decision(&plan.id, "proposal:pay", true, &mut storage).await.unwrap();
+
+        assert!(execute_transfer_plan(&plan.id, "pay-1", &mut executor, &mut storage).await.is_err());
+
+        let stored = load_transfer_plan(&plan.id, &storage).await.unwrap();
+        assert!(matches!(stored.status, TransferPlanStatus::Failed { completed: 1, .. }));
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use icn_execution_tools::cli_helpers::{vm_diff_command, vm_run_command};
+use std::path::PathBuf;
+
+/// Differential testing between ICN VM builds
+#[derive(Parser)]
+#[clap(name = "icn-vm-diff", version = "0.1.0", about = "Compare execution receipts between VM builds before upgrading")]
+struct Cli {
+    /// Commands
+    #[clap(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Execute one module on this build and print its receipt as JSON
+    Run {
+        /// Path to the module (.wasm or .wat)
+        module: PathBuf,
+
+        /// Path to the execution context (defaults to the module's .json sibling)
+        context: Option<PathBuf>,
+    },
+
+    /// Run a corpus on a baseline build and on this build and compare receipts
+    Diff {
+        /// Directory of corpus modules
+        #[clap(short, long)]
+        corpus: PathBuf,
+
+        /// Baseline build's icn-vm-diff binary
+        #[clap(short, long)]
+        baseline: PathBuf,
+
+        /// Arguments passed to the baseline before the module and context paths
+        #[clap(long = "baseline-arg", default_value = "run")]
+        baseline_args: Vec<String>,
+
+        /// Print the full report as JSON
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Run { module, context } => {
+            println!("{}", vm_run_command(&module, context.as_deref()).await?);
+        }
+        Commands::Diff { corpus, baseline, baseline_args, json } => {
+            let report = vm_diff_command(&corpus, &baseline, baseline_args).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{} vs {}: {} case(s)", report.left_build, report.right_build, report.cases.len());
+                for case in report.divergent_cases() {
+                    println!("DIVERGED {}", case.case);
+                    for difference in &case.differences {
+                        println!("  {}: {} -> {}", difference.field, difference.left, difference.right);
+                    }
+                }
+            }
+
+            if !report.is_clean() {
+                std::process::exit(1);
+            }
+            if !json {
+                println!("All cases match");
+            }
+        }
+    }
+
+    Ok(())
+}
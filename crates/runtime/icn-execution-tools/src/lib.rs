@@ -147,6 +147,9 @@ impl CredentialHelper {
 /// CLI command helpers
 pub mod cli_helpers {
     use super::*;
+    use icn_core_vm::differential::{
+        DifferentialReport, ExternalBuild, InProcessBuild, VmBuild, load_case, load_corpus, run_differential,
+    };
     
     /// Helper for propose command
     pub fn propose_command(
@@ -195,6 +198,34 @@ pub mod cli_helpers {
         // Placeholder implementation
         Err(anyhow::anyhow!("Not implemented"))
     }
+    
+    /// Helper for vm run command: execute one module on this build and return its
+    /// differential receipt as JSON. This is the interface other builds are driven
+    /// through by `vm_diff_command`.
+    pub async fn vm_run_command(module_path: &Path, context_path: Option<&Path>) -> Result<String> {
+        let mut case = load_case(module_path)?;
+        if let Some(context_path) = context_path {
+            case.context = serde_json::from_slice(&fs::read(context_path)?)?;
+        }
+        
+        let receipt = InProcessBuild::default().execute(&case).await?;
+        Ok(serde_json::to_string(&receipt)?)
+    }
+    
+    /// Helper for vm diff command: run a corpus on a baseline build and on this build
+    pub async fn vm_diff_command(
+        corpus_dir: &Path,
+        baseline_program: &Path,
+        baseline_args: Vec<String>,
+    ) -> Result<DifferentialReport> {
+        let corpus = load_corpus(corpus_dir)?;
+        if corpus.is_empty() {
+            return Err(anyhow::anyhow!("Corpus {} contains no modules", corpus_dir.display()));
+        }
+        
+        let baseline = ExternalBuild::new(baseline_program.display().to_string(), baseline_program, baseline_args);
+        Ok(run_differential(&corpus, &baseline, &InProcessBuild::default()).await?)
+    }
 }
 
 /// Derive resource authorizations from a proposal template
@@ -0,0 +1,683 @@
+/*!
+# Federation Health Analytics
+
+Aggregates periodic federation signals (participation rate, proposal throughput,
+treasury runway, node liveness and dispute volume) into a single health score, and
+raises early-warning alerts when a signal crosses its threshold. Where nodes export VM
+execution metrics, liveness is discounted by the share of executions that failed.
+
+Each alert carries a suggested intervention. Alerts are handed to a [`HealthEventSink`],
+which the governance layer implements to surface them as governance events, so members
+can act before a crisis forces a merge or dissolution.
+*/
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use async_trait::async_trait;
+use crate::error::{FederationError, FederationResult};
+
+/// Raw signals collected for a federation over one reporting period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthSignals {
+    /// Federation DID
+    pub federation_did: String,
+    /// Start of the reporting period
+    pub period_start: DateTime<Utc>,
+    /// End of the reporting period
+    pub period_end: DateTime<Utc>,
+    /// Number of members in the federation
+    pub member_count: u64,
+    /// Fraction of eligible members who voted during the period (0.0 - 1.0)
+    pub participation_rate: f64,
+    /// Proposals submitted during the period
+    pub proposals_submitted: u64,
+    /// Proposals that reached a final outcome during the period
+    pub proposals_decided: u64,
+    /// Days the shared treasury can sustain current spending, if known
+    pub treasury_runway_days: Option<f64>,
+    /// Fraction of federation nodes that were reachable during the period (0.0 - 1.0)
+    pub node_liveness: f64,
+    /// Disputes opened during the period
+    pub disputes_opened: u64,
+    /// Fraction of the federation's VM executions that succeeded during the period,
+    /// if its nodes export execution metrics
+    #[serde(default)]
+    pub execution_success_rate: Option<f64>,
+}
+
+impl HealthSignals {
+    /// Fraction of submitted proposals that reached an outcome.
+    /// A period with no submissions counts as fully processed.
+    pub fn proposal_throughput(&self) -> f64 {
+        if self.proposals_submitted == 0 {
+            return 1.0;
+        }
+        (self.proposals_decided as f64 / self.proposals_submitted as f64).min(1.0)
+    }
+
+    /// Disputes opened per member during the period
+    pub fn dispute_rate(&self) -> f64 {
+        if self.member_count == 0 {
+            return 0.0;
+        }
+        self.disputes_opened as f64 / self.member_count as f64
+    }
+}
+
+/// Relative weight of each signal in the overall score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthWeights {
+    pub participation: f64,
+    pub throughput: f64,
+    pub treasury: f64,
+    pub liveness: f64,
+    pub disputes: f64,
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        Self {
+            participation: 0.25,
+            throughput: 0.15,
+            treasury: 0.25,
+            liveness: 0.20,
+            disputes: 0.15,
+        }
+    }
+}
+
+impl HealthWeights {
+    fn total(&self) -> f64 {
+        self.participation + self.throughput + self.treasury + self.liveness + self.disputes
+    }
+}
+
+/// Thresholds below (or, for disputes, above) which an alert is raised
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthThresholds {
+    /// Overall score below which the federation is in a warning state
+    pub warning_score: f64,
+    /// Overall score below which the federation is in a critical state
+    pub critical_score: f64,
+    /// Minimum acceptable participation rate
+    pub min_participation: f64,
+    /// Minimum acceptable proposal throughput
+    pub min_throughput: f64,
+    /// Minimum acceptable treasury runway in days
+    pub min_runway_days: f64,
+    /// Minimum acceptable node liveness
+    pub min_liveness: f64,
+    /// Maximum acceptable disputes per member per period
+    pub max_dispute_rate: f64,
+    /// Consecutive declining periods that trigger a trend alert
+    pub declining_periods: usize,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            warning_score: 0.6,
+            critical_score: 0.4,
+            min_participation: 0.3,
+            min_throughput: 0.5,
+            min_runway_days: 90.0,
+            min_liveness: 0.75,
+            max_dispute_rate: 0.1,
+            declining_periods: 3,
+        }
+    }
+}
+
+/// Configuration for federation health scoring
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Signal weights
+    pub weights: HealthWeights,
+    /// Alert thresholds
+    pub thresholds: HealthThresholds,
+    /// Runway at which the treasury component scores 1.0
+    pub target_runway_days: f64,
+    /// Dispute rate at which the disputes component scores 0.0
+    pub dispute_rate_ceiling: f64,
+    /// Length of a reporting period in seconds
+    pub period_seconds: i64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            weights: HealthWeights::default(),
+            thresholds: HealthThresholds::default(),
+            target_runway_days: 365.0,
+            dispute_rate_ceiling: 0.25,
+            period_seconds: 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Validate the configuration
+    pub fn validate(&self) -> FederationResult<()> {
+        let w = &self.weights;
+        if [w.participation, w.throughput, w.treasury, w.liveness, w.disputes].iter().any(|v| *v < 0.0) {
+            return Err(FederationError::ValidationError("Health weights must not be negative".to_string()));
+        }
+        if w.total() <= 0.0 {
+            return Err(FederationError::ValidationError("Health weights must not all be zero".to_string()));
+        }
+        if self.thresholds.critical_score > self.thresholds.warning_score {
+            return Err(FederationError::ValidationError(
+                "Critical score threshold must not exceed warning threshold".to_string()
+            ));
+        }
+        if self.target_runway_days <= 0.0 || self.dispute_rate_ceiling <= 0.0 {
+            return Err(FederationError::ValidationError(
+                "Runway target and dispute ceiling must be positive".to_string()
+            ));
+        }
+        if self.period_seconds <= 0 {
+            return Err(FederationError::ValidationError("Reporting period must be positive".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Normalized per-signal scores (each 0.0 - 1.0)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthComponents {
+    pub participation: f64,
+    pub throughput: f64,
+    pub treasury: f64,
+    pub liveness: f64,
+    pub disputes: f64,
+}
+
+/// Overall health classification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthStatus {
+    Healthy,
+    Warning,
+    Critical,
+}
+
+/// Health score computed for one reporting period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FederationHealthScore {
+    /// Federation DID
+    pub federation_did: String,
+    /// Start of the reporting period
+    pub period_start: DateTime<Utc>,
+    /// End of the reporting period
+    pub period_end: DateTime<Utc>,
+    /// When the score was computed
+    pub computed_at: DateTime<Utc>,
+    /// Per-signal scores
+    pub components: HealthComponents,
+    /// Weighted overall score (0.0 - 1.0)
+    pub overall: f64,
+    /// Classification of the overall score
+    pub status: HealthStatus,
+}
+
+/// The signal an alert was raised for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthSignal {
+    Participation,
+    Throughput,
+    TreasuryRunway,
+    NodeLiveness,
+    DisputeVolume,
+    OverallScore,
+    DecliningTrend,
+    /// A shared service from the service registry
+    ServiceAvailability,
+}
+
+/// Intervention suggested to the federation's members
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SuggestedIntervention {
+    /// Run an engagement drive or revisit quorum rules
+    ParticipationDrive,
+    /// Triage the open proposal backlog
+    ReviewProposalBacklog,
+    /// Review budgets and contributions before the treasury runs dry
+    TreasuryReview,
+    /// Contact operators of unreachable nodes
+    NodeOperatorOutreach,
+    /// Open mediation for recurring disputes
+    DisputeMediation,
+    /// Convene a federation-wide review of its structure
+    FederationReview,
+    /// Ask a shared service's maintainers to restore it
+    ContactServiceMaintainers,
+}
+
+/// Alert severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AlertSeverity {
+    Warning,
+    Critical,
+}
+
+/// Early-warning alert raised when a signal crosses its threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthAlert {
+    /// Federation DID
+    pub federation_did: String,
+    /// The signal that triggered the alert
+    pub signal: HealthSignal,
+    /// Alert severity
+    pub severity: AlertSeverity,
+    /// Observed value
+    pub observed: f64,
+    /// Threshold that was crossed
+    pub threshold: f64,
+    /// Suggested intervention
+    pub intervention: SuggestedIntervention,
+    /// Human-readable description
+    pub message: String,
+    /// When the alert was raised
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Receives health scores and alerts.
+///
+/// The governance layer implements this to record scores and emit alerts as governance
+/// events in the federation's scope.
+#[async_trait]
+pub trait HealthEventSink {
+    /// Record a computed health score
+    async fn record_score(&self, score: &FederationHealthScore) -> FederationResult<()>;
+
+    /// Emit an early-warning alert
+    async fn emit_alert(&self, alert: &HealthAlert) -> FederationResult<()>;
+}
+
+/// Result of evaluating one reporting period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub score: FederationHealthScore,
+    pub alerts: Vec<HealthAlert>,
+}
+
+/// Compute normalized component scores for a set of signals
+pub fn compute_components(signals: &HealthSignals, config: &HealthConfig) -> HealthComponents {
+    let treasury = match signals.treasury_runway_days {
+        Some(days) => (days / config.target_runway_days).clamp(0.0, 1.0),
+        // No treasury reporting is treated as neutral rather than healthy
+        None => 0.5,
+    };
+
+    HealthComponents {
+        participation: signals.participation_rate.clamp(0.0, 1.0),
+        throughput: signals.proposal_throughput(),
+        treasury,
+        // Reachable nodes whose executions keep failing aren't really live
+        liveness: signals.node_liveness.clamp(0.0, 1.0) * signals.execution_success_rate.unwrap_or(1.0).clamp(0.0, 1.0),
+        disputes: 1.0 - (signals.dispute_rate() / config.dispute_rate_ceiling).clamp(0.0, 1.0),
+    }
+}
+
+/// Compute the weighted health score for a reporting period
+pub fn compute_health_score(signals: &HealthSignals, config: &HealthConfig) -> FederationResult<FederationHealthScore> {
+    config.validate()?;
+    if signals.period_end <= signals.period_start {
+        return Err(FederationError::ValidationError("Reporting period end must be after its start".to_string()));
+    }
+
+    let components = compute_components(signals, config);
+    let w = &config.weights;
+    let overall = (components.participation * w.participation
+        + components.throughput * w.throughput
+        + components.treasury * w.treasury
+        + components.liveness * w.liveness
+        + components.disputes * w.disputes)
+        / w.total();
+
+    let status = if overall < config.thresholds.critical_score {
+        HealthStatus::Critical
+    } else if overall < config.thresholds.warning_score {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Healthy
+    };
+
+    Ok(FederationHealthScore {
+        federation_did: signals.federation_did.clone(),
+        period_start: signals.period_start,
+        period_end: signals.period_end,
+        computed_at: Utc::now(),
+        components,
+        overall,
+        status,
+    })
+}
+
+/// Check a period's signals and score against the thresholds.
+/// `history` holds earlier scores, oldest first, and is used for trend detection.
+pub fn detect_alerts(
+    signals: &HealthSignals,
+    score: &FederationHealthScore,
+    history: &[FederationHealthScore],
+    thresholds: &HealthThresholds,
+) -> Vec<HealthAlert> {
+    let now = Utc::now();
+    let mut alerts = Vec::new();
+    let mut raise = |signal, severity, observed: f64, threshold: f64, intervention, message: String| {
+        alerts.push(HealthAlert {
+            federation_did: signals.federation_did.clone(),
+            signal,
+            severity,
+            observed,
+            threshold,
+            intervention,
+            message,
+            raised_at: now,
+        });
+    };
+
+    // Falling below half of a minimum escalates the alert to critical
+    let severity_below = |observed: f64, min: f64| {
+        if observed < min / 2.0 { AlertSeverity::Critical } else { AlertSeverity::Warning }
+    };
+
+    if signals.participation_rate < thresholds.min_participation {
+        raise(
+            HealthSignal::Participation,
+            severity_below(signals.participation_rate, thresholds.min_participation),
+            signals.participation_rate,
+            thresholds.min_participation,
+            SuggestedIntervention::ParticipationDrive,
+            format!("Participation fell to {:.0}%", signals.participation_rate * 100.0),
+        );
+    }
+
+    let throughput = signals.proposal_throughput();
+    if throughput < thresholds.min_throughput {
+        raise(
+            HealthSignal::Throughput,
+            severity_below(throughput, thresholds.min_throughput),
+            throughput,
+            thresholds.min_throughput,
+            SuggestedIntervention::ReviewProposalBacklog,
+            format!(
+                "Only {} of {} proposals reached an outcome",
+                signals.proposals_decided, signals.proposals_submitted
+            ),
+        );
+    }
+
+    if let Some(runway) = signals.treasury_runway_days {
+        if runway < thresholds.min_runway_days {
+            raise(
+                HealthSignal::TreasuryRunway,
+                severity_below(runway, thresholds.min_runway_days),
+                runway,
+                thresholds.min_runway_days,
+                SuggestedIntervention::TreasuryReview,
+                format!("Treasury runway is {:.0} days", runway),
+            );
+        }
+    }
+
+    if signals.node_liveness < thresholds.min_liveness {
+        raise(
+            HealthSignal::NodeLiveness,
+            severity_below(signals.node_liveness, thresholds.min_liveness),
+            signals.node_liveness,
+            thresholds.min_liveness,
+            SuggestedIntervention::NodeOperatorOutreach,
+            format!("Only {:.0}% of nodes were reachable", signals.node_liveness * 100.0),
+        );
+    }
+
+    let dispute_rate = signals.dispute_rate();
+    if dispute_rate > thresholds.max_dispute_rate {
+        let severity = if dispute_rate > thresholds.max_dispute_rate * 2.0 {
+            AlertSeverity::Critical
+        } else {
+            AlertSeverity::Warning
+        };
+        raise(
+            HealthSignal::DisputeVolume,
+            severity,
+            dispute_rate,
+            thresholds.max_dispute_rate,
+            SuggestedIntervention::DisputeMediation,
+            format!("{} disputes opened across {} members", signals.disputes_opened, signals.member_count),
+        );
+    }
+
+    match score.status {
+        HealthStatus::Critical => raise(
+            HealthSignal::OverallScore,
+            AlertSeverity::Critical,
+            score.overall,
+            thresholds.critical_score,
+            SuggestedIntervention::FederationReview,
+            format!("Federation health score is critical ({:.2})", score.overall),
+        ),
+        HealthStatus::Warning => raise(
+            HealthSignal::OverallScore,
+            AlertSeverity::Warning,
+            score.overall,
+            thresholds.warning_score,
+            SuggestedIntervention::FederationReview,
+            format!("Federation health score is low ({:.2})", score.overall),
+        ),
+        HealthStatus::Healthy => {}
+    }
+
+    // A steady decline is flagged even while the score is still above the thresholds
+    if thresholds.declining_periods > 0 && history.len() >= thresholds.declining_periods {
+        let recent: Vec<f64> = history[history.len() - thresholds.declining_periods..]
+            .iter()
+            .map(|s| s.overall)
+            .chain(std::iter::once(score.overall))
+            .collect();
+        if recent.windows(2).all(|pair| pair[1] < pair[0]) {
+            raise(
+                HealthSignal::DecliningTrend,
+                AlertSeverity::Warning,
+                score.overall,
+                recent[0],
+                SuggestedIntervention::FederationReview,
+                format!(
+                    "Health score declined for {} consecutive periods ({:.2} -> {:.2})",
+                    thresholds.declining_periods, recent[0], score.overall
+                ),
+            );
+        }
+    }
+
+    alerts
+}
+
+/// Computes a federation's health score once per reporting period and forwards
+/// scores and alerts to a sink
+#[derive(Debug, Clone)]
+pub struct FederationHealthMonitor {
+    federation_did: String,
+    config: HealthConfig,
+    history: Vec<FederationHealthScore>,
+}
+
+impl FederationHealthMonitor {
+    /// Create a monitor for a federation
+    pub fn new(federation_did: impl Into<String>, config: HealthConfig) -> FederationResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            federation_did: federation_did.into(),
+            config,
+            history: Vec::new(),
+        })
+    }
+
+    /// Health configuration
+    pub fn config(&self) -> &HealthConfig {
+        &self.config
+    }
+
+    /// Scores of all evaluated periods, oldest first
+    pub fn history(&self) -> &[FederationHealthScore] {
+        &self.history
+    }
+
+    /// Most recent score, if any period has been evaluated
+    pub fn latest(&self) -> Option<&FederationHealthScore> {
+        self.history.last()
+    }
+
+    /// Whether a new reporting period has elapsed since the last evaluation
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        match self.history.last() {
+            Some(last) => now >= last.period_end + Duration::seconds(self.config.period_seconds),
+            None => true,
+        }
+    }
+
+    /// Score a reporting period, record it and emit any alerts
+    pub async fn evaluate<K: HealthEventSink + Sync>(
+        &mut self,
+        signals: &HealthSignals,
+        sink: &K,
+    ) -> FederationResult<HealthReport> {
+        if signals.federation_did != self.federation_did {
+            return Err(FederationError::ValidationError(format!(
+                "Signals for {} cannot be evaluated by the monitor for {}",
+                signals.federation_did, self.federation_did
+            )));
+        }
+        if let Some(last) = self.history.last() {
+            if signals.period_start < last.period_end {
+                return Err(FederationError::ValidationError(
+                    "Reporting period overlaps the previously evaluated period".to_string()
+                ));
+            }
+        }
+
+        let score = compute_health_score(signals, &self.config)?;
+        let alerts = detect_alerts(signals, &score, &self.history, &self.config.thresholds);
+
+        sink.record_score(&score).await?;
+        for alert in &alerts {
+            sink.emit_alert(alert).await?;
+        }
+
+        self.history.push(score.clone());
+        Ok(HealthReport { score, alerts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        scores: Mutex<Vec<FederationHealthScore>>,
+        alerts: Mutex<Vec<HealthAlert>>,
+    }
+
+    #[async_trait]
+    impl HealthEventSink for RecordingSink {
+        async fn record_score(&self, score: &FederationHealthScore) -> FederationResult<()> {
+            self.scores.lock().unwrap().push(score.clone());
+            Ok(())
+        }
+
+        async fn emit_alert(&self, alert: &HealthAlert) -> FederationResult<()> {
+            self.alerts.lock().unwrap().push(alert.clone());
+            Ok(())
+        }
+    }
+
+    fn healthy_signals(period: i64) -> HealthSignals {
+        let start = Utc.timestamp_opt(1_700_000_000 + period * 2_592_000, 0).unwrap();
+        HealthSignals {
+            federation_did: "did:icn:federation:test".to_string(),
+            period_start: start,
+            period_end: start + Duration::seconds(2_592_000),
+            member_count: 40,
+            participation_rate: 0.8,
+            proposals_submitted: 10,
+            proposals_decided: 9,
+            treasury_runway_days: Some(400.0),
+            node_liveness: 0.95,
+            disputes_opened: 1,
+            execution_success_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_healthy_federation_raises_no_alerts() {
+        let config = HealthConfig::default();
+        let signals = healthy_signals(0);
+        let score = compute_health_score(&signals, &config).unwrap();
+
+        assert_eq!(score.status, HealthStatus::Healthy);
+        assert!(score.overall > 0.8);
+        assert!(detect_alerts(&signals, &score, &[], &config.thresholds).is_empty());
+    }
+
+    #[test]
+    fn test_failing_executions_lower_liveness() {
+        let config = HealthConfig::default();
+        let mut signals = healthy_signals(0);
+        let baseline = compute_components(&signals, &config).liveness;
+
+        signals.execution_success_rate = Some(0.5);
+        let components = compute_components(&signals, &config);
+        assert!((components.liveness - baseline * 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_thresholds_suggest_interventions() {
+        let config = HealthConfig::default();
+        let mut signals = healthy_signals(0);
+        signals.participation_rate = 0.1;
+        signals.treasury_runway_days = Some(60.0);
+        signals.disputes_opened = 12;
+
+        let score = compute_health_score(&signals, &config).unwrap();
+        let alerts = detect_alerts(&signals, &score, &[], &config.thresholds);
+        let find = |signal| alerts.iter().find(|a| a.signal == signal).unwrap();
+
+        let participation = find(HealthSignal::Participation);
+        assert_eq!(participation.severity, AlertSeverity::Critical);
+        assert_eq!(participation.intervention, SuggestedIntervention::ParticipationDrive);
+
+        let treasury = find(HealthSignal::TreasuryRunway);
+        assert_eq!(treasury.severity, AlertSeverity::Warning);
+        assert_eq!(treasury.intervention, SuggestedIntervention::TreasuryReview);
+
+        assert_eq!(find(HealthSignal::DisputeVolume).severity, AlertSeverity::Critical);
+        assert!(alerts.iter().all(|a| a.signal != HealthSignal::NodeLiveness));
+    }
+
+    #[tokio::test]
+    async fn test_monitor_detects_declining_trend() {
+        let mut monitor = FederationHealthMonitor::new("did:icn:federation:test", HealthConfig::default()).unwrap();
+        let sink = RecordingSink::default();
+
+        for period in 0..4 {
+            let mut signals = healthy_signals(period);
+            signals.participation_rate = 0.9 - 0.1 * period as f64;
+            let report = monitor.evaluate(&signals, &sink).await.unwrap();
+            if period < 3 {
+                assert!(report.alerts.is_empty());
+            } else {
+                assert_eq!(report.alerts.len(), 1);
+                assert_eq!(report.alerts[0].signal, HealthSignal::DecliningTrend);
+            }
+        }
+
+        assert_eq!(sink.scores.lock().unwrap().len(), 4);
+        assert_eq!(sink.alerts.lock().unwrap().len(), 1);
+
+        // Re-evaluating an earlier period is rejected
+        assert!(monitor.evaluate(&healthy_signals(1), &sink).await.is_err());
+    }
+}
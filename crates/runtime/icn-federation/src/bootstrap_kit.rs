@@ -0,0 +1,345 @@
+/*!
+# Federation Bootstrap Kit
+
+Starting a federation used to mean hand-assembling the genesis metadata, signer keys,
+bylaws, roles and treasury. [`bootstrap_federation`] takes all of that as one
+declarative [`BootstrapSpec`] and produces the whole genesis state:
+
+- a signer key and signer credential for every founder, making the founders the
+  federation's initial signer quorum;
+- genesis records for the bylaws CCL, the role definitions, each founder's initial
+  role authorizations and the initial treasury, issued as credentials and included in
+  the genesis metadata's initial policies so the founders' quorum signs them;
+- the establishment credential, the genesis trust bundle and its genesis anchor.
+
+Nothing is written until every record has been generated and the trust bundle
+verifies; the genesis anchor is then stored as the single DAG event, so a failed
+bootstrap leaves no partial federation behind. The returned [`BootstrapReport`] lists
+the CID of every record.
+*/
+
+use std::collections::{BTreeMap, HashSet};
+use serde::{Deserialize, Serialize};
+use chrono::Utc;
+use cid::Cid;
+use cid::multihash::Multihash;
+use sha2::{Digest, Sha256};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use icn_identity::{generate_did_key, IdentityId, KeyPair, VerifiableCredential};
+
+use crate::dag_anchor::anchor;
+use crate::dag_client::{DagClient, FederationDagEvent};
+use crate::error::{FederationError, FederationResult};
+use crate::genesis::{trustbundle, FederationEstablishmentCredential, FederationMetadata, GenesisTrustBundle};
+use crate::quorum::{decisions, QuorumType, SignerQuorumConfig};
+use crate::services::ServiceEndpoint;
+use crate::signer::{initialization, Signer};
+
+/// A founding member of the federation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FounderSpec {
+    pub name: String,
+    /// The founder's DID; one is generated if unset
+    #[serde(default)]
+    pub did: Option<String>,
+    /// Roles the founder holds from genesis
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// A role and the permissions it grants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleSpec {
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+/// An initial treasury fund
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreasuryFund {
+    /// Fund name (e.g., "general", "solidarity")
+    pub fund: String,
+    pub token: String,
+    pub amount: u64,
+}
+
+/// Everything needed to start a federation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BootstrapSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub founders: Vec<FounderSpec>,
+    /// Quorum the founders' signatures must meet
+    pub quorum_type: QuorumType,
+    /// Initial bylaws, in CCL
+    pub bylaws_ccl: String,
+    #[serde(default)]
+    pub roles: Vec<RoleSpec>,
+    #[serde(default)]
+    pub treasury: Vec<TreasuryFund>,
+    #[serde(default)]
+    pub endpoints: Vec<ServiceEndpoint>,
+}
+
+impl BootstrapSpec {
+    /// Check the spec is complete and consistent
+    pub fn validate(&self) -> FederationResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(FederationError::ValidationError("Federation name must not be empty".to_string()));
+        }
+        if self.founders.is_empty() {
+            return Err(FederationError::ValidationError("A federation needs at least one founder".to_string()));
+        }
+        if self.bylaws_ccl.trim().is_empty() {
+            return Err(FederationError::ValidationError("Initial bylaws must not be empty".to_string()));
+        }
+
+        let mut role_names = HashSet::new();
+        for role in &self.roles {
+            if !role_names.insert(role.name.as_str()) {
+                return Err(FederationError::ValidationError(format!("Role {} is defined twice", role.name)));
+            }
+        }
+
+        let mut founder_dids = HashSet::new();
+        for founder in &self.founders {
+            if let Some(did) = &founder.did {
+                if !founder_dids.insert(did.as_str()) {
+                    return Err(FederationError::ValidationError(format!("Founder DID {} is listed twice", did)));
+                }
+            }
+            if let Some(role) = founder.roles.iter().find(|r| !role_names.contains(r.as_str())) {
+                return Err(FederationError::ValidationError(format!(
+                    "Founder {} holds undefined role {}", founder.name, role
+                )));
+            }
+        }
+
+        let mut funds = HashSet::new();
+        for fund in &self.treasury {
+            if fund.amount == 0 {
+                return Err(FederationError::ValidationError(format!("Treasury fund {} is empty", fund.fund)));
+            }
+            if !funds.insert(fund.fund.as_str()) {
+                return Err(FederationError::ValidationError(format!("Treasury fund {} is listed twice", fund.fund)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// CIDs of everything a bootstrap produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapReport {
+    pub federation_did: String,
+    /// CID of the genesis metadata
+    pub metadata_cid: String,
+    /// DAG root CID of the genesis trust bundle
+    pub trust_bundle_cid: String,
+    /// CID of the genesis anchor event in the DAG
+    pub genesis_event_cid: String,
+    /// CID of the bylaws CCL source
+    pub bylaws_cid: String,
+    /// CID of the bylaws record
+    pub bylaws_record_cid: String,
+    pub roles_record_cid: String,
+    /// CID of each founder's authorization record, keyed by founder DID
+    pub authorization_cids: BTreeMap<String, String>,
+    pub treasury_record_cid: String,
+    /// Founder DIDs, in the order of the spec
+    pub signers: Vec<String>,
+}
+
+/// Output of a bootstrap: the report and the founders' signers, which hold the keys
+/// generated for them
+#[derive(Debug, Clone)]
+pub struct BootstrapKit {
+    pub report: BootstrapReport,
+    pub signers: Vec<Signer>,
+    pub trust_bundle: GenesisTrustBundle,
+}
+
+/// CID (dag-json, SHA-256) of a record's bytes
+fn content_cid(bytes: &[u8]) -> FederationResult<String> {
+    let mh = Multihash::wrap(0x12, &Sha256::digest(bytes))
+        .map_err(|_| FederationError::CidError("Failed to create multihash".to_string()))?;
+    Ok(Cid::new_v1(0x0129, mh).to_string())
+}
+
+fn record_cid(record: &VerifiableCredential) -> FederationResult<String> {
+    let bytes = serde_json::to_vec(record)
+        .map_err(|e| FederationError::SerializationError(format!("Failed to serialize genesis record: {}", e)))?;
+    content_cid(&bytes)
+}
+
+fn credential(kind: &str, federation: &IdentityId, subject: &IdentityId, claims: serde_json::Value) -> VerifiableCredential {
+    VerifiableCredential::new(
+        vec!["VerifiableCredential".to_string(), kind.to_string()],
+        federation,
+        subject,
+        claims,
+    )
+}
+
+/// Generate and anchor the genesis state of a new federation
+pub async fn bootstrap_federation(spec: &BootstrapSpec, dag: &impl DagClient) -> FederationResult<BootstrapKit> {
+    spec.validate()?;
+
+    let (federation_did, _federation_jwk) = generate_did_key().await
+        .map_err(|e| FederationError::BootstrapError(format!("Failed to generate federation DID: {}", e)))?;
+    let federation_id = IdentityId(federation_did.clone());
+
+    // One signer per founder; the founders form the initial quorum
+    let mut signers = Vec::with_capacity(spec.founders.len());
+    for (index, founder) in spec.founders.iter().enumerate() {
+        let did = founder.did.clone()
+            .unwrap_or_else(|| format!("did:icn:founder:{}:{}", Utc::now().timestamp_millis(), index));
+        let keypair = KeyPair::generate_random();
+        let mut signer = Signer { did: IdentityId(did), keypair, credential: None };
+        signer.credential = Some(initialization::create_signer_credential(&signer, &federation_did, &signer.keypair).await?);
+        signers.push(signer);
+    }
+    let signer_dids: Vec<String> = signers.iter().map(|s| s.did.0.clone()).collect();
+    let quorum_config = SignerQuorumConfig::new(spec.quorum_type.clone(), signer_dids.clone());
+
+    // Genesis records
+    let bylaws_cid = content_cid(spec.bylaws_ccl.as_bytes())?;
+    let bylaws = credential("FederationBylawsCredential", &federation_id, &federation_id, serde_json::json!({
+        "bylawsCid": bylaws_cid,
+        "ccl": spec.bylaws_ccl,
+        "version": 1,
+    }));
+    let roles = credential("FederationRolesCredential", &federation_id, &federation_id, serde_json::json!({
+        "roles": spec.roles,
+    }));
+    let mut authorizations = Vec::with_capacity(signers.len());
+    for (founder, signer) in spec.founders.iter().zip(&signers) {
+        let permissions: Vec<&String> = spec.roles.iter()
+            .filter(|r| founder.roles.contains(&r.name))
+            .flat_map(|r| r.permissions.iter())
+            .collect();
+        authorizations.push(credential("RoleAuthorizationCredential", &federation_id, &signer.did, serde_json::json!({
+            "name": founder.name,
+            "roles": founder.roles,
+            "permissions": permissions,
+        })));
+    }
+    let treasury = credential("FederationTreasuryCredential", &federation_id, &federation_id, serde_json::json!({
+        "funds": spec.treasury,
+    }));
+
+    let bylaws_record_cid = record_cid(&bylaws)?;
+    let roles_record_cid = record_cid(&roles)?;
+    let treasury_record_cid = record_cid(&treasury)?;
+    let mut authorization_cids = BTreeMap::new();
+    for (signer, authorization) in signers.iter().zip(&authorizations) {
+        authorization_cids.insert(signer.did.0.clone(), record_cid(authorization)?);
+    }
+
+    let mut initial_policies = vec![bylaws, roles, treasury];
+    initial_policies.extend(authorizations);
+
+    let metadata = FederationMetadata {
+        federation_did: federation_did.clone(),
+        name: spec.name.clone(),
+        description: spec.description.clone(),
+        created_at: Utc::now(),
+        initial_policies,
+        initial_members: signer_dids.clone(),
+        initial_signers: signer_dids.clone(),
+        quorum_config: quorum_config.clone(),
+        endpoints: spec.endpoints.clone(),
+        genesis_cid: Cid::default(),
+        additional_metadata: Some(serde_json::json!({
+            "bylaws_cid": bylaws_cid,
+            "bootstrap": "kit",
+        })),
+    };
+
+    // The founders' quorum signs the metadata, and with it every genesis record
+    let metadata_bytes = serde_json::to_vec(&metadata)
+        .map_err(|e| FederationError::SerializationError(format!("Failed to serialize federation metadata: {}", e)))?;
+    let quorum_proof = decisions::create_quorum_proof(&metadata_bytes, &signers, &quorum_config).await?;
+    let establishment_credential = FederationEstablishmentCredential {
+        metadata: metadata.clone(),
+        epoch: 0,
+        signer_signatures: quorum_proof.votes.iter()
+            .map(|(id, sig)| (id.clone(), URL_SAFE_NO_PAD.encode(&sig.0)))
+            .collect(),
+    };
+
+    let signer_credentials = signers.iter().filter_map(|s| s.credential.clone()).collect();
+    let trust_bundle = trustbundle::create_trust_bundle(&metadata, establishment_credential, signer_credentials, &signers).await?;
+    trustbundle::verify_trust_bundle(&trust_bundle, &signer_dids).await?;
+
+    let genesis_anchor = anchor::create_genesis_anchor(&trust_bundle, &signers[0].keypair, &federation_did).await?;
+    let trust_bundle_cid = genesis_anchor.dag_root_cid.clone();
+
+    // Everything is generated and verified; anchoring the genesis is the only write
+    let genesis_event_cid = dag.store_event(FederationDagEvent::Genesis(genesis_anchor)).await?;
+
+    Ok(BootstrapKit {
+        report: BootstrapReport {
+            federation_did,
+            metadata_cid: trust_bundle.federation_metadata_cid.clone(),
+            trust_bundle_cid,
+            genesis_event_cid,
+            bylaws_cid,
+            bylaws_record_cid,
+            roles_record_cid,
+            authorization_cids,
+            treasury_record_cid,
+            signers: signer_dids,
+        },
+        signers,
+        trust_bundle,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag_client::InMemoryDagClient;
+
+    fn spec() -> BootstrapSpec {
+        BootstrapSpec {
+            name: "Riverbend Federation".to_string(),
+            description: Some("Food cooperatives of the river valley".to_string()),
+            founders: vec![
+                FounderSpec { name: "Riverbend Grocers".to_string(), did: None, roles: vec!["steward".to_string()] },
+                FounderSpec { name: "Valley Bakers".to_string(), did: None, roles: vec![] },
+            ],
+            quorum_type: QuorumType::Majority,
+            bylaws_ccl: "bylaws { quorum 0.5 }".to_string(),
+            roles: vec![RoleSpec { name: "steward".to_string(), permissions: vec!["manage_treasury".to_string()] }],
+            treasury: vec![TreasuryFund { fund: "general".to_string(), token: "credit".to_string(), amount: 10_000 }],
+            endpoints: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_generates_and_anchors_genesis() {
+        let dag = InMemoryDagClient::default();
+        let kit = bootstrap_federation(&spec(), &dag).await.unwrap();
+
+        let report = &kit.report;
+        assert_eq!(report.signers.len(), 2);
+        assert_eq!(report.authorization_cids.len(), 2);
+        assert_eq!(kit.trust_bundle.federation_establishment_credential.metadata.initial_policies.len(), 5);
+        assert_eq!(report.bylaws_cid, content_cid(b"bylaws { quorum 0.5 }").unwrap());
+
+        assert!(!report.genesis_event_cid.is_empty());
+        assert!(trustbundle::verify_trust_bundle(&kit.trust_bundle, &report.signers).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_undefined_role_is_rejected() {
+        let dag = InMemoryDagClient::default();
+        let mut bad = spec();
+        bad.founders[1].roles.push("treasurer".to_string());
+
+        assert!(matches!(bootstrap_federation(&bad, &dag).await, Err(FederationError::ValidationError(_))));
+    }
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use crate::error::{FederationError, FederationResult};
-use crate::recovery::{RecoveryEvent, RecoveryEventType, FederationKeyRotationEvent, SuccessionEvent, MetadataUpdateEvent, DisasterRecoveryAnchor};
+use crate::recovery::{RecoveryEvent, RecoveryEventType, FederationKeyRotationEvent, SuccessionEvent, MetadataUpdateEvent, DisasterRecoveryAnchor, ServiceCredentialEvent};
 use crate::dag_anchor::GenesisAnchor;
 use crate::genesis::FederationMetadata;
 use crate::quorum::{SignerQuorumConfig, QuorumType};
@@ -23,6 +23,8 @@ pub enum FederationDagEvent {
     MetadataUpdate(MetadataUpdateEvent),
     /// Disaster recovery event
     DisasterRecovery(DisasterRecoveryAnchor),
+    /// Service credential event
+    ServiceCredential(ServiceCredentialEvent),
 }
 
 impl FederationDagEvent {
@@ -34,6 +36,7 @@ impl FederationDagEvent {
             FederationDagEvent::Succession(e) => &e.base.federation_did,
             FederationDagEvent::MetadataUpdate(e) => &e.base.federation_did,
             FederationDagEvent::DisasterRecovery(e) => &e.base.federation_did,
+            FederationDagEvent::ServiceCredential(e) => &e.base.federation_did,
         }
     }
     
@@ -45,6 +48,7 @@ impl FederationDagEvent {
             FederationDagEvent::Succession(e) => e.base.timestamp,
             FederationDagEvent::MetadataUpdate(e) => e.base.timestamp,
             FederationDagEvent::DisasterRecovery(e) => e.base.timestamp,
+            FederationDagEvent::ServiceCredential(e) => e.base.timestamp,
         }
     }
     
@@ -56,6 +60,7 @@ impl FederationDagEvent {
             FederationDagEvent::Succession(e) => e.base.previous_event_cid.as_ref(),
             FederationDagEvent::MetadataUpdate(e) => e.base.previous_event_cid.as_ref(),
             FederationDagEvent::DisasterRecovery(e) => e.base.previous_event_cid.as_ref(),
+            FederationDagEvent::ServiceCredential(e) => e.base.previous_event_cid.as_ref(),
         }
     }
     
@@ -67,6 +72,7 @@ impl FederationDagEvent {
             FederationDagEvent::Succession(e) => e.base.sequence_number,
             FederationDagEvent::MetadataUpdate(e) => e.base.sequence_number,
             FederationDagEvent::DisasterRecovery(e) => e.base.sequence_number,
+            FederationDagEvent::ServiceCredential(e) => e.base.sequence_number,
         }
     }
     
@@ -78,6 +84,7 @@ impl FederationDagEvent {
             FederationDagEvent::Succession(_) => "succession",
             FederationDagEvent::MetadataUpdate(_) => "metadata_update",
             FederationDagEvent::DisasterRecovery(_) => "disaster_recovery",
+            FederationDagEvent::ServiceCredential(_) => "service_credential",
         }
     }
 }
@@ -215,6 +222,7 @@ impl DagClient for InMemoryDagClient {
             FederationDagEvent::Succession(_) => "bafy_succession",
             FederationDagEvent::MetadataUpdate(_) => "bafy_metadata",
             FederationDagEvent::DisasterRecovery(_) => "bafy_recovery",
+            FederationDagEvent::ServiceCredential(_) => "bafy_service_credential",
         };
         
         let federation = event.federation_did().split(':').last().unwrap_or("unknown");
@@ -419,9 +427,11 @@ mod tests {
                     min_wait_time_seconds: None,
                     additional_requirements: None,
                 },
+                endpoints: Vec::new(),
                 genesis_cid: cid::Cid::default(),
                 additional_metadata: Some(serde_json::json!({"test": "metadata"})),
             },
+            previous_metadata_hash: None,
         });
         
         let metadata_cid = client.store_event(metadata_update.clone()).await.unwrap();
@@ -0,0 +1,379 @@
+/*!
+# Emergency Federation Key Rotation
+
+If a federation's signing key is compromised, anyone holding it can issue trust bundles
+and anchors in the federation's name, so the key can't be trusted to approve its own
+replacement. An emergency rotation is instead approved by the member co-ops.
+
+Any member proposes the new key together with the point from which the old key is to
+be treated as compromised, and proves possession of the new key by signing the proposal
+with it. Once a supermajority of members ([`EMERGENCY_ROTATION_THRESHOLD`] percent by
+default) have signed, the rotation becomes a [`KeyRotationAttestation`], anchored to the
+DAG as a key rotation event.
+
+Verifiers keep the attestations in a [`KeyRotationLog`]. From the rotation point on the
+log accepts only the new key and rejects the old one; trust bundles are checked against
+it with `genesis::trustbundle::verify_trust_bundle_with_rotations`.
+*/
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use icn_identity::{IdentityId, KeyPair, QuorumProof, Signature, sign_message, verify_signature};
+
+use crate::dag_client::{DagClient, FederationDagEvent};
+use crate::error::{FederationError, FederationResult};
+use crate::quorum::{decisions, SignerQuorumConfig};
+use crate::recovery::{FederationKeyRotationEvent, RecoveryEvent, RecoveryEventType};
+use crate::signer::Signer;
+
+/// Share of member co-ops, in percent, that must sign an emergency rotation
+pub const EMERGENCY_ROTATION_THRESHOLD: u8 = 67;
+
+/// A proposed emergency rotation collecting member signatures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencyRotationProposal {
+    pub id: String,
+    pub federation_did: String,
+    /// DID of the compromised key
+    pub retired_key_did: String,
+    /// DID of the replacement key
+    pub new_key_did: String,
+    /// From when the retired key is treated as compromised
+    pub compromised_since: DateTime<Utc>,
+    pub reason: String,
+    pub proposed_by: IdentityId,
+    pub proposed_at: DateTime<Utc>,
+    /// Signature over the signing payload with the new key
+    pub key_proof: Signature,
+    pub signatures: Vec<(IdentityId, Signature)>,
+}
+
+impl EmergencyRotationProposal {
+    /// Bytes the new key and the members sign
+    pub fn signing_payload(&self) -> Vec<u8> {
+        format!(
+            "emergency-key-rotation:{}:{}:{}:{}:{}",
+            self.id, self.federation_did, self.retired_key_did, self.new_key_did, self.compromised_since.to_rfc3339()
+        ).into_bytes()
+    }
+}
+
+/// A member-approved key rotation, as anchored to the DAG
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotationAttestation {
+    pub rotation_id: String,
+    pub federation_did: String,
+    pub retired_key_did: String,
+    pub new_key_did: String,
+    /// The rotation point: the retired key is rejected from here on
+    pub effective_at: DateTime<Utc>,
+    pub reason: String,
+    /// Signatures of the approving members
+    pub member_proof: QuorumProof,
+    pub key_proof: Signature,
+    /// CID of the key rotation anchor
+    pub anchor_cid: String,
+    pub attested_at: DateTime<Utc>,
+}
+
+/// The keys a federation has signed with, as a verifier reconstructs them from its
+/// rotation attestations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotationLog {
+    /// The key the federation was established with
+    pub genesis_key_did: String,
+    /// Applied rotations, oldest first
+    rotations: Vec<KeyRotationAttestation>,
+}
+
+impl KeyRotationLog {
+    pub fn new(genesis_key_did: impl Into<String>) -> Self {
+        Self { genesis_key_did: genesis_key_did.into(), rotations: Vec::new() }
+    }
+
+    /// Applied rotations, oldest first
+    pub fn rotations(&self) -> &[KeyRotationAttestation] {
+        &self.rotations
+    }
+
+    /// The key currently in effect
+    pub fn current_key(&self) -> &str {
+        self.rotations.last().map_or(self.genesis_key_did.as_str(), |r| r.new_key_did.as_str())
+    }
+
+    /// The key in effect at `at`
+    pub fn key_at(&self, at: DateTime<Utc>) -> &str {
+        self.rotations.iter()
+            .take_while(|r| r.effective_at <= at)
+            .last()
+            .map_or(self.genesis_key_did.as_str(), |r| r.new_key_did.as_str())
+    }
+
+    /// Add a rotation. It must retire the current key, and its rotation point can't
+    /// precede the previous rotation's.
+    pub fn apply(&mut self, attestation: KeyRotationAttestation) -> FederationResult<()> {
+        if attestation.retired_key_did != self.current_key() {
+            return Err(FederationError::VerificationError(format!(
+                "Rotation {} retires {}, but the current key is {}",
+                attestation.rotation_id, attestation.retired_key_did, self.current_key()
+            )));
+        }
+        if let Some(previous) = self.rotations.last() {
+            if attestation.effective_at < previous.effective_at {
+                return Err(FederationError::VerificationError(format!(
+                    "Rotation {} takes effect before rotation {}", attestation.rotation_id, previous.rotation_id
+                )));
+            }
+        }
+        self.rotations.push(attestation);
+        Ok(())
+    }
+
+    /// Check that `key_did` was the federation's key at `at`. A key that has been
+    /// rotated out is rejected for anything from its rotation point on.
+    pub fn check_key(&self, key_did: &str, at: DateTime<Utc>) -> FederationResult<()> {
+        if key_did == self.key_at(at) {
+            return Ok(());
+        }
+        match self.rotations.iter().find(|r| r.retired_key_did == key_did) {
+            Some(rotation) if at >= rotation.effective_at => Err(FederationError::VerificationError(format!(
+                "Key {} was rotated out at {} by rotation {}", key_did, rotation.effective_at.to_rfc3339(), rotation.rotation_id
+            ))),
+            _ => Err(FederationError::VerificationError(format!(
+                "Key {} was not the federation's key at {}", key_did, at.to_rfc3339()
+            ))),
+        }
+    }
+}
+
+/// A federation's emergency rotations: pending proposals and the log of applied ones
+pub struct EmergencyKeyRotation {
+    federation_did: String,
+    /// Member co-ops and the share of them that must sign
+    member_quorum: SignerQuorumConfig,
+    pending: HashMap<String, EmergencyRotationProposal>,
+    log: KeyRotationLog,
+    last_anchor_cid: Option<String>,
+    anchored_events: u64,
+}
+
+impl EmergencyKeyRotation {
+    /// Rotations approved by [`EMERGENCY_ROTATION_THRESHOLD`] percent of `members`
+    pub fn new(federation_did: impl Into<String>, current_key_did: impl Into<String>, members: Vec<String>) -> Self {
+        Self::with_threshold(federation_did, current_key_did, members, EMERGENCY_ROTATION_THRESHOLD)
+    }
+
+    /// Rotations approved by `threshold_percentage` percent of `members`
+    pub fn with_threshold(
+        federation_did: impl Into<String>,
+        current_key_did: impl Into<String>,
+        members: Vec<String>,
+        threshold_percentage: u8,
+    ) -> Self {
+        Self {
+            federation_did: federation_did.into(),
+            member_quorum: SignerQuorumConfig::new_threshold(members, threshold_percentage),
+            pending: HashMap::new(),
+            log: KeyRotationLog::new(current_key_did),
+            last_anchor_cid: None,
+            anchored_events: 0,
+        }
+    }
+
+    /// The rotations applied so far
+    pub fn log(&self) -> &KeyRotationLog {
+        &self.log
+    }
+
+    /// A pending rotation
+    pub fn pending_rotation(&self, id: &str) -> Option<&EmergencyRotationProposal> {
+        self.pending.get(id)
+    }
+
+    fn require_member(&self, did: &IdentityId) -> FederationResult<()> {
+        if self.member_quorum.signers.contains(&did.0) {
+            Ok(())
+        } else {
+            Err(FederationError::Unauthorized(format!(
+                "{} is not a member of federation {}", did.0, self.federation_did
+            )))
+        }
+    }
+
+    /// Propose replacing the current key with `new_key_did`, whose keypair signs the
+    /// proposal as proof of possession. Returns the proposal ID.
+    pub fn propose(
+        &mut self,
+        proposer: &IdentityId,
+        new_key_did: &str,
+        new_keypair: &KeyPair,
+        compromised_since: DateTime<Utc>,
+        reason: impl Into<String>,
+    ) -> FederationResult<String> {
+        self.require_member(proposer)?;
+        if new_key_did == self.log.current_key() || self.log.rotations().iter().any(|r| r.retired_key_did == new_key_did) {
+            return Err(FederationError::ValidationError(format!(
+                "{} is not a fresh key for federation {}", new_key_did, self.federation_did
+            )));
+        }
+        let now = Utc::now();
+        if compromised_since > now {
+            return Err(FederationError::ValidationError("The compromise can't start in the future".to_string()));
+        }
+        if let Some(previous) = self.log.rotations().last() {
+            if compromised_since < previous.effective_at {
+                return Err(FederationError::ValidationError(format!(
+                    "The current key has only been in use since {}", previous.effective_at.to_rfc3339()
+                )));
+            }
+        }
+
+        let mut proposal = EmergencyRotationProposal {
+            id: Uuid::new_v4().to_string(),
+            federation_did: self.federation_did.clone(),
+            retired_key_did: self.log.current_key().to_string(),
+            new_key_did: new_key_did.to_string(),
+            compromised_since,
+            reason: reason.into(),
+            proposed_by: proposer.clone(),
+            proposed_at: now,
+            key_proof: Signature(Vec::new()),
+            signatures: Vec::new(),
+        };
+        proposal.key_proof = sign_message(&proposal.signing_payload(), new_keypair)
+            .map_err(|e| FederationError::CryptoError(format!("Failed to sign key proof: {}", e)))?;
+
+        let id = proposal.id.clone();
+        self.pending.insert(id.clone(), proposal);
+        Ok(id)
+    }
+
+    /// Add a member's signature to a proposal. Returns the number of signatures collected.
+    pub fn sign(&mut self, id: &str, member: &Signer) -> FederationResult<usize> {
+        self.require_member(&member.did)?;
+        let proposal = self.pending.get_mut(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Emergency rotation {} not found", id)))?;
+        if proposal.signatures.iter().any(|(did, _)| did == &member.did) {
+            return Err(FederationError::ValidationError(format!(
+                "{} already signed emergency rotation {}", member.did.0, id
+            )));
+        }
+
+        let signature = sign_message(&proposal.signing_payload(), &member.keypair)
+            .map_err(|e| FederationError::CryptoError(format!("Signature failed: {}", e)))?;
+        proposal.signatures.push((member.did.clone(), signature));
+        Ok(proposal.signatures.len())
+    }
+
+    /// Apply a rotation once a supermajority of members has signed it and anchor the
+    /// attestation to the DAG
+    pub async fn finalize(&mut self, id: &str, dag: &impl DagClient) -> FederationResult<&KeyRotationAttestation> {
+        let proposal = self.pending.get(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Emergency rotation {} not found", id)))?
+            .clone();
+
+        let required = self.member_quorum.required_signatures();
+        if proposal.signatures.len() < required {
+            return Err(FederationError::VerificationError(format!(
+                "Not enough member signatures: got {}, need {} for an emergency rotation",
+                proposal.signatures.len(), required
+            )));
+        }
+
+        let payload = proposal.signing_payload();
+        let member_proof = QuorumProof {
+            votes: proposal.signatures.clone(),
+            config: self.member_quorum.to_quorum_config(),
+        };
+        if !decisions::verify_quorum_proof(&member_proof, &payload, &self.member_quorum.signers).await? {
+            return Err(FederationError::VerificationError(format!(
+                "Member signatures on emergency rotation {} do not verify", id
+            )));
+        }
+        let key_proof_valid = verify_signature(&payload, &proposal.key_proof, &IdentityId(proposal.new_key_did.clone()))
+            .map_err(|e| FederationError::VerificationError(format!("Key proof verification error: {}", e)))?;
+        if !key_proof_valid {
+            return Err(FederationError::VerificationError(format!(
+                "Key proof of emergency rotation {} does not verify", id
+            )));
+        }
+
+        let event = FederationKeyRotationEvent {
+            base: RecoveryEvent {
+                event_type: RecoveryEventType::FederationKeyRotation,
+                federation_did: self.federation_did.clone(),
+                sequence_number: self.anchored_events + 1,
+                previous_event_cid: self.last_anchor_cid.clone(),
+                timestamp: Utc::now(),
+                signatures: proposal.signatures.iter().map(|(_, sig)| sig.clone()).collect(),
+            },
+            new_federation_did: proposal.new_key_did.clone(),
+            key_proof: proposal.key_proof.clone(),
+        };
+        let anchor_cid = dag.store_event(FederationDagEvent::KeyRotation(event)).await?;
+
+        self.log.apply(KeyRotationAttestation {
+            rotation_id: proposal.id.clone(),
+            federation_did: self.federation_did.clone(),
+            retired_key_did: proposal.retired_key_did,
+            new_key_did: proposal.new_key_did,
+            effective_at: proposal.compromised_since,
+            reason: proposal.reason,
+            member_proof,
+            key_proof: proposal.key_proof,
+            anchor_cid: anchor_cid.clone(),
+            attested_at: Utc::now(),
+        })?;
+        self.anchored_events += 1;
+        self.last_anchor_cid = Some(anchor_cid);
+
+        // Other proposals were made against the key just retired
+        self.pending.clear();
+
+        Ok(self.log.rotations().last().expect("rotation was just applied"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::dag_client::InMemoryDagClient;
+    use crate::quorum::QuorumType;
+    use crate::signer::initialization;
+
+    #[tokio::test]
+    async fn test_supermajority_rotation_retires_old_key() {
+        let (members, member_quorum) = initialization::initialize_signer_set(4, QuorumType::Majority).await.unwrap();
+        let mut rotation = EmergencyKeyRotation::new("did:key:z6MkFederation", "did:key:z6MkOldKey", member_quorum.signers);
+        let dag = InMemoryDagClient::default();
+
+        let compromised_since = Utc::now() - Duration::hours(2);
+        let new_keypair = KeyPair::generate_random();
+        let id = rotation.propose(&members[0].did, "did:key:z6MkNewKey", &new_keypair, compromised_since, "Key leaked").unwrap();
+
+        // 67% of 4 members is 3 signatures
+        rotation.sign(&id, &members[0]).unwrap();
+        rotation.sign(&id, &members[1]).unwrap();
+        assert!(rotation.finalize(&id, &dag).await.is_err());
+        rotation.sign(&id, &members[3]).unwrap();
+        let attestation = rotation.finalize(&id, &dag).await.unwrap().clone();
+        assert_eq!(attestation.member_proof.votes.len(), 3);
+
+        let anchored = dag.get_event(&attestation.anchor_cid).await.unwrap();
+        assert_eq!(anchored.event.event_type(), "key_rotation");
+
+        let log = rotation.log();
+        assert_eq!(log.current_key(), "did:key:z6MkNewKey");
+        let before = compromised_since - Duration::minutes(1);
+        assert!(log.check_key("did:key:z6MkOldKey", before).is_ok());
+        assert!(log.check_key("did:key:z6MkOldKey", compromised_since).is_err());
+        assert!(log.check_key("did:key:z6MkNewKey", Utc::now()).is_ok());
+        assert!(log.check_key("did:key:z6MkNewKey", before).is_err());
+
+        // The retired key can't come back
+        assert!(rotation.propose(&members[1].did, "did:key:z6MkOldKey", &KeyPair::generate_random(), Utc::now(), "Undo").is_err());
+    }
+}
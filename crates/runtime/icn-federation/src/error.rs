@@ -56,6 +56,10 @@ pub enum FederationError {
     #[error("Validation error: {0}")]
     ValidationError(String),
     
+    /// Error when a caller has exceeded its rate limit
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+    
     /// Any other error
     #[error("Other error: {0}")]
     Other(String),
@@ -10,6 +10,8 @@ use crate::error::{FederationResult, FederationError};
 use crate::quorum::{SignerQuorumConfig, QuorumType};
 use crate::quorum::decisions;
 use crate::signer::Signer;
+use crate::services::ServiceEndpoint;
+use crate::emergency_rotation::KeyRotationLog;
 
 /// Metadata about a federation entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +44,10 @@ pub struct FederationMetadata {
     /// Quorum configuration for this federation
     pub quorum_config: SignerQuorumConfig,
     
+    /// Network endpoints where the federation can be reached
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<ServiceEndpoint>,
+    
     /// Genesis DAG CID where this federation was anchored
     pub genesis_cid: Cid,
     
@@ -152,6 +158,7 @@ pub mod bootstrap {
             initial_members: member_dids,
             initial_signers: Vec::new(),
             quorum_config: quorum_config.clone(),
+            endpoints: Vec::new(),
             genesis_cid: Cid::default(), // Will be set later in Phase 4
             additional_metadata: None,
         };
@@ -458,6 +465,19 @@ pub mod trustbundle {
         // All verification checks passed
         Ok(true)
     }
+    
+    /// Verify a genesis trust bundle, also requiring that the federation key it names
+    /// was still in effect when it was issued
+    pub async fn verify_trust_bundle_with_rotations(
+        bundle: &GenesisTrustBundle,
+        authorized_signer_dids: &[String],
+        rotations: &KeyRotationLog,
+    ) -> FederationResult<bool> {
+        let metadata = &bundle.federation_establishment_credential.metadata;
+        rotations.check_key(&metadata.federation_did, bundle.issued_at)?;
+        
+        verify_trust_bundle(bundle, authorized_signer_dids).await
+    }
 }
 
 #[cfg(test)]
@@ -572,6 +592,7 @@ mod tests {
                 "did:key:z6MkGuardian2".to_string(),
                 "did:key:z6MkGuardian3".to_string(),
             ]),
+            endpoints: Vec::new(),
             genesis_cid: Cid::default(),
             additional_metadata: None,
         };
@@ -17,6 +17,14 @@ pub mod receipt;
 pub mod recovery;
 pub mod dag_client;
 pub mod signer;
+pub mod analytics;
+pub mod services;
+pub mod metadata;
+pub mod service_identity;
+pub mod bootstrap_kit;
+pub mod emergency_rotation;
+pub mod observer;
+pub mod residency;
 
 // Re-export core structs
 pub use genesis::{FederationMetadata, FederationEstablishmentCredential, GenesisTrustBundle};
@@ -29,11 +37,40 @@ pub use receipt::verification::{generate_federation_receipt, verify_federation_r
 
 // Re-export recovery types and functions
 pub use recovery::{RecoveryEvent, RecoveryEventType, FederationKeyRotationEvent, 
-                 SuccessionEvent, DisasterRecoveryAnchor, MetadataUpdateEvent};
+                 SuccessionEvent, DisasterRecoveryAnchor, MetadataUpdateEvent, ServiceCredentialEvent};
 
 // Re-export DAG client types and functions
 pub use dag_client::{FederationDagEvent, FederationDagNode, DagClient, InMemoryDagClient, FederationReplayEngine};
 pub use dag_client::validation::{validate_event_chain, validate_event};
 
+// Re-export health analytics types
+pub use analytics::{HealthSignals, HealthConfig, HealthThresholds, FederationHealthScore,
+                    HealthAlert, HealthEventSink, FederationHealthMonitor, SuggestedIntervention};
+
+// Re-export shared service registry types
+pub use services::{ServiceRegistry, SharedService, ServiceKind, ServiceStatus, ServiceEndpoint, ServiceProbe};
+
+// Re-export quorum-approved metadata update types
+pub use metadata::{MetadataRegistry, MetadataChange, MetadataUpdateProposal, ApprovedMetadata};
+
+// Re-export service identity types
+pub use service_identity::{ServiceIdentityRegistry, ServiceCredential, ServiceGrant, ServiceRateLimit,
+                           ServiceCredentialChange, ServiceCredentialProposal, ServiceAuditRecord, ServiceAccessOutcome};
+
+// Re-export bootstrap kit types
+pub use bootstrap_kit::{bootstrap_federation, BootstrapSpec, FounderSpec, RoleSpec, TreasuryFund,
+                       BootstrapReport, BootstrapKit};
+
+// Re-export emergency key rotation types
+pub use emergency_rotation::{EmergencyKeyRotation, EmergencyRotationProposal, KeyRotationAttestation, KeyRotationLog};
+
+// Re-export observer federation types
+pub use observer::{ObserverRegistry, ObserverRelationship, ObserverCredential, ObserverScope, ObserverStatus,
+                   ObserverConversion};
+
+// Re-export data residency types
+pub use residency::{ResidencyRegistry, ResidencyClass, ResidencyTag, RegionPin, StoragePolicy, RecordRef,
+                    PlacementPlan, ReplicaPlacement, RefusedReplica, ScopeResidency, ComplianceExport};
+
 // Public re-exports
 pub use error::{FederationError, FederationResult};
\ No newline at end of file
@@ -0,0 +1,307 @@
+/*!
+# Quorum-Approved Metadata Updates
+
+A federation's name, description and endpoints can't be edited unilaterally. A signer
+proposes a [`MetadataChange`], the federation's signers sign it, and once the signatures
+meet the federation's [`SignerQuorumConfig`] the new metadata becomes current and is
+anchored to the DAG as a [`MetadataUpdateEvent`] carrying the hash of the metadata it
+replaces.
+
+Each proposal is bound to the metadata it was made against. If another update is
+approved first, the proposal is stale and has to be made again, so two concurrent
+updates can't silently overwrite each other.
+*/
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use icn_identity::{IdentityId, QuorumProof, Signature, sign_message};
+
+use crate::dag_client::{DagClient, FederationDagEvent};
+use crate::error::{FederationError, FederationResult};
+use crate::genesis::FederationMetadata;
+use crate::quorum::decisions;
+use crate::recovery::{MetadataUpdateEvent, RecoveryEvent, RecoveryEventType};
+use crate::services::ServiceEndpoint;
+use crate::signer::Signer;
+
+/// Fields of the federation metadata to change; unset fields are kept
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataChange {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub endpoints: Option<Vec<ServiceEndpoint>>,
+}
+
+impl MetadataChange {
+    /// The metadata with this change applied
+    pub fn apply(&self, metadata: &FederationMetadata) -> FederationMetadata {
+        let mut updated = metadata.clone();
+        if let Some(name) = &self.name {
+            updated.name = name.clone();
+        }
+        if let Some(description) = &self.description {
+            updated.description = Some(description.clone());
+        }
+        if let Some(endpoints) = &self.endpoints {
+            updated.endpoints = endpoints.clone();
+        }
+        updated
+    }
+}
+
+/// Hex-encoded SHA-256 of the serialized metadata
+pub fn metadata_hash(metadata: &FederationMetadata) -> FederationResult<String> {
+    let bytes = serde_json::to_vec(metadata)
+        .map_err(|e| FederationError::SerializationError(format!("Failed to serialize federation metadata: {}", e)))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// A proposed metadata update collecting signer signatures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataUpdateProposal {
+    pub id: String,
+    /// Hash of the metadata the update was proposed against
+    pub previous_hash: String,
+    pub proposed: FederationMetadata,
+    pub proposed_by: IdentityId,
+    pub proposed_at: DateTime<Utc>,
+    pub signatures: Vec<(IdentityId, Signature)>,
+}
+
+impl MetadataUpdateProposal {
+    /// Bytes signers sign: the previous and proposed metadata hashes
+    pub fn signing_payload(&self) -> FederationResult<Vec<u8>> {
+        Ok(format!("{}:{}", self.previous_hash, metadata_hash(&self.proposed)?).into_bytes())
+    }
+}
+
+/// A quorum-approved version of the federation metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovedMetadata {
+    /// Version number, 0 for the genesis metadata
+    pub version: u64,
+    pub metadata: FederationMetadata,
+    pub metadata_hash: String,
+    /// Hash of the version this one replaced
+    pub previous_hash: Option<String>,
+    pub quorum_proof: QuorumProof,
+    /// CID of the DAG anchor, for versions after genesis
+    pub anchor_cid: Option<String>,
+    pub approved_at: DateTime<Utc>,
+}
+
+/// History of a federation's approved metadata and its pending updates
+pub struct MetadataRegistry {
+    versions: Vec<ApprovedMetadata>,
+    pending: HashMap<String, MetadataUpdateProposal>,
+}
+
+impl MetadataRegistry {
+    /// Start from the genesis metadata and the quorum proof it was established with
+    pub fn new(genesis: FederationMetadata, genesis_proof: QuorumProof) -> FederationResult<Self> {
+        let genesis = ApprovedMetadata {
+            version: 0,
+            metadata_hash: metadata_hash(&genesis)?,
+            metadata: genesis,
+            previous_hash: None,
+            quorum_proof: genesis_proof,
+            anchor_cid: None,
+            approved_at: Utc::now(),
+        };
+        Ok(Self { versions: vec![genesis], pending: HashMap::new() })
+    }
+
+    fn current(&self) -> &ApprovedMetadata {
+        self.versions.last().expect("registry always holds the genesis version")
+    }
+
+    /// The latest quorum-approved metadata
+    pub fn metadata(&self) -> &FederationMetadata {
+        &self.current().metadata
+    }
+
+    /// Every approved version, oldest first
+    pub fn history(&self) -> &[ApprovedMetadata] {
+        &self.versions
+    }
+
+    /// A pending update proposal
+    pub fn pending_update(&self, id: &str) -> Option<&MetadataUpdateProposal> {
+        self.pending.get(id)
+    }
+
+    fn require_signer(&self, did: &IdentityId) -> FederationResult<()> {
+        if self.metadata().quorum_config.signers.contains(&did.0) {
+            Ok(())
+        } else {
+            Err(FederationError::Unauthorized(format!(
+                "{} is not a signer of federation {}", did.0, self.metadata().federation_did
+            )))
+        }
+    }
+
+    /// Propose a change to the current metadata. Returns the proposal ID.
+    pub fn propose_update(&mut self, proposer: &IdentityId, change: MetadataChange) -> FederationResult<String> {
+        self.require_signer(proposer)?;
+        if change.name.as_ref().is_some_and(|n| n.trim().is_empty()) {
+            return Err(FederationError::ValidationError("Federation name must not be empty".to_string()));
+        }
+
+        let proposal = MetadataUpdateProposal {
+            id: Uuid::new_v4().to_string(),
+            previous_hash: self.current().metadata_hash.clone(),
+            proposed: change.apply(self.metadata()),
+            proposed_by: proposer.clone(),
+            proposed_at: Utc::now(),
+            signatures: Vec::new(),
+        };
+        let id = proposal.id.clone();
+        self.pending.insert(id.clone(), proposal);
+        Ok(id)
+    }
+
+    /// Add a signer's signature to a proposal. Returns the number of signatures collected.
+    pub fn sign_update(&mut self, id: &str, signer: &Signer) -> FederationResult<usize> {
+        self.require_signer(&signer.did)?;
+        let proposal = self.pending.get_mut(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Metadata update {} not found", id)))?;
+        if proposal.signatures.iter().any(|(did, _)| did == &signer.did) {
+            return Err(FederationError::ValidationError(format!(
+                "{} already signed metadata update {}", signer.did.0, id
+            )));
+        }
+
+        let signature = sign_message(&proposal.signing_payload()?, &signer.keypair)
+            .map_err(|e| FederationError::CryptoError(format!("Signature failed: {}", e)))?;
+        proposal.signatures.push((signer.did.clone(), signature));
+        Ok(proposal.signatures.len())
+    }
+
+    /// Approve a proposal once its signatures meet the quorum, anchor it to the DAG and
+    /// make it the current metadata
+    pub async fn finalize_update(&mut self, id: &str, dag: &impl DagClient) -> FederationResult<&ApprovedMetadata> {
+        let proposal = self.pending.get(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Metadata update {} not found", id)))?
+            .clone();
+
+        let current = self.current();
+        if proposal.previous_hash != current.metadata_hash {
+            self.pending.remove(id);
+            return Err(FederationError::ValidationError(format!(
+                "Metadata update {} was proposed against a superseded version", id
+            )));
+        }
+
+        let quorum_config = &current.metadata.quorum_config;
+        let required = quorum_config.required_signatures();
+        if proposal.signatures.len() < required {
+            return Err(FederationError::VerificationError(format!(
+                "Not enough signatures: got {}, need {} for quorum",
+                proposal.signatures.len(), required
+            )));
+        }
+
+        let quorum_proof = QuorumProof {
+            votes: proposal.signatures.clone(),
+            config: quorum_config.to_quorum_config(),
+        };
+        let payload = proposal.signing_payload()?;
+        if !decisions::verify_quorum_proof(&quorum_proof, &payload, &quorum_config.signers).await? {
+            return Err(FederationError::VerificationError(format!(
+                "Quorum proof for metadata update {} does not verify", id
+            )));
+        }
+
+        let version = current.version + 1;
+        let event = MetadataUpdateEvent {
+            base: RecoveryEvent {
+                event_type: RecoveryEventType::MetadataUpdate,
+                federation_did: current.metadata.federation_did.clone(),
+                sequence_number: version,
+                previous_event_cid: current.anchor_cid.clone(),
+                timestamp: Utc::now(),
+                signatures: proposal.signatures.iter().map(|(_, sig)| sig.clone()).collect(),
+            },
+            updated_metadata: proposal.proposed.clone(),
+            previous_metadata_hash: Some(proposal.previous_hash.clone()),
+        };
+        let anchor_cid = dag.store_event(FederationDagEvent::MetadataUpdate(event)).await?;
+
+        self.pending.remove(id);
+        self.versions.push(ApprovedMetadata {
+            version,
+            metadata_hash: metadata_hash(&proposal.proposed)?,
+            metadata: proposal.proposed,
+            previous_hash: Some(proposal.previous_hash),
+            quorum_proof,
+            anchor_cid: Some(anchor_cid),
+            approved_at: Utc::now(),
+        });
+        Ok(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cid::Cid;
+    use crate::dag_client::InMemoryDagClient;
+    use crate::quorum::QuorumType;
+    use crate::signer::initialization;
+
+    #[tokio::test]
+    async fn test_metadata_changes_need_quorum_and_chain_hashes() {
+        let (signers, quorum_config) = initialization::initialize_signer_set(3, QuorumType::Majority).await.unwrap();
+        let genesis = FederationMetadata {
+            federation_did: "did:key:z6MkFederation".to_string(),
+            name: "Lakeshore Federation".to_string(),
+            description: None,
+            created_at: Utc::now(),
+            initial_policies: vec![],
+            initial_members: vec![],
+            initial_signers: vec![],
+            quorum_config: quorum_config.clone(),
+            endpoints: Vec::new(),
+            genesis_cid: Cid::default(),
+            additional_metadata: None,
+        };
+        let genesis_proof = QuorumProof { votes: vec![], config: quorum_config.to_quorum_config() };
+        let mut registry = MetadataRegistry::new(genesis, genesis_proof).unwrap();
+        let dag = InMemoryDagClient::default();
+
+        let outsider = IdentityId("did:key:z6MkOutsider".to_string());
+        assert!(registry.propose_update(&outsider, MetadataChange::default()).is_err());
+
+        let change = MetadataChange {
+            endpoints: Some(vec![ServiceEndpoint { protocol: "https".to_string(), address: "fed.example".to_string() }]),
+            ..Default::default()
+        };
+        let first = registry.propose_update(&signers[0].did, change).unwrap();
+        let rename = registry.propose_update(&signers[1].did, MetadataChange {
+            name: Some("Lakeshore Cooperative Federation".to_string()),
+            ..Default::default()
+        }).unwrap();
+
+        // One signature of three is not a majority; reads still see the old version
+        registry.sign_update(&first, &signers[0]).unwrap();
+        assert!(registry.sign_update(&first, &signers[0]).is_err());
+        assert!(registry.finalize_update(&first, &dag).await.is_err());
+        assert!(registry.metadata().endpoints.is_empty());
+
+        registry.sign_update(&first, &signers[1]).unwrap();
+        let genesis_hash = registry.history()[0].metadata_hash.clone();
+        let approved = registry.finalize_update(&first, &dag).await.unwrap();
+        assert_eq!(approved.version, 1);
+        assert_eq!(approved.previous_hash, Some(genesis_hash));
+        assert_eq!(registry.metadata().endpoints.len(), 1);
+
+        // The rename was proposed against genesis, which is no longer current
+        registry.sign_update(&rename, &signers[1]).unwrap();
+        registry.sign_update(&rename, &signers[2]).unwrap();
+        assert!(registry.finalize_update(&rename, &dag).await.is_err());
+        assert_eq!(registry.metadata().name, "Lakeshore Federation");
+    }
+}
@@ -0,0 +1,291 @@
+/*!
+# Observer Federations
+
+Before merging, a federation may want to watch how another one works. An observer
+relationship gives the prospective federation read access to the host's public
+proposals and calendars without making it a member: observers have no vote, and no
+observer scope grants one.
+
+A signer of the host federation grants the relationship, which yields an
+[`ObserverCredential`] signed by that signer, naming the scopes it may read and when
+it expires. Every read goes through [`ObserverRegistry::authorize`], which checks the
+signature, the scope and the relationship's status. The relationship ends when it
+expires, when a signer revokes it, or when it's converted into a full merge proposal or
+a mutual-aid agreement, which replaces observation with the new arrangement.
+*/
+
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use icn_identity::{IdentityId, Signature, sign_message, verify_signature};
+
+use crate::error::{FederationError, FederationResult};
+use crate::quorum::SignerQuorumConfig;
+use crate::signer::Signer;
+
+/// What an observer federation may read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ObserverScope {
+    /// Proposals the host federation publishes, and their outcomes
+    PublicProposals,
+    /// The host federation's meeting and voting calendars
+    Calendars,
+}
+
+/// A scoped read credential held by an observer federation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObserverCredential {
+    pub relationship_id: String,
+    pub host_federation: String,
+    pub observer_federation: String,
+    pub scopes: BTreeSet<ObserverScope>,
+    pub expires_at: DateTime<Utc>,
+    /// Signer of the host federation who granted the relationship
+    pub issued_by: IdentityId,
+    pub signature: Signature,
+}
+
+impl ObserverCredential {
+    /// Bytes the issuing signer signs: every field but the signature
+    pub fn signing_payload(&self) -> FederationResult<Vec<u8>> {
+        serde_json::to_vec(&(
+            &self.relationship_id,
+            &self.host_federation,
+            &self.observer_federation,
+            &self.scopes,
+            self.expires_at,
+            &self.issued_by,
+        ))
+        .map_err(|e| FederationError::SerializationError(format!("Failed to serialize observer credential: {}", e)))
+    }
+}
+
+/// What an observer relationship was converted into
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObserverConversion {
+    /// The federations went on to propose a full merge
+    Merge { proposal_id: String },
+    /// The federations entered a mutual-aid agreement instead of merging
+    MutualAid { agreement_id: String },
+}
+
+/// Where an observer relationship stands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObserverStatus {
+    /// The observer may read its scopes until the relationship expires
+    Active,
+    Revoked { reason: String },
+    Converted(ObserverConversion),
+}
+
+/// A prospective federation observing this one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObserverRelationship {
+    pub id: String,
+    pub observer_federation: String,
+    pub scopes: BTreeSet<ObserverScope>,
+    pub granted_by: IdentityId,
+    pub granted_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub status: ObserverStatus,
+    /// When the relationship was revoked or converted
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl ObserverRelationship {
+    /// Whether the observer may read at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.status == ObserverStatus::Active && now < self.expires_at
+    }
+}
+
+/// A federation's observer relationships
+pub struct ObserverRegistry {
+    federation_did: String,
+    quorum_config: SignerQuorumConfig,
+    relationships: HashMap<String, ObserverRelationship>,
+}
+
+impl ObserverRegistry {
+    pub fn new(federation_did: impl Into<String>, quorum_config: SignerQuorumConfig) -> Self {
+        Self {
+            federation_did: federation_did.into(),
+            quorum_config,
+            relationships: HashMap::new(),
+        }
+    }
+
+    /// An observer relationship, active or not
+    pub fn relationship(&self, id: &str) -> Option<&ObserverRelationship> {
+        self.relationships.get(id)
+    }
+
+    /// Relationships in which `observer_federation` may read at `now`
+    pub fn active_for(&self, observer_federation: &str, now: DateTime<Utc>) -> Vec<&ObserverRelationship> {
+        self.relationships.values()
+            .filter(|r| r.observer_federation == observer_federation && r.is_active(now))
+            .collect()
+    }
+
+    fn require_signer(&self, did: &IdentityId) -> FederationResult<()> {
+        if self.quorum_config.signers.contains(&did.0) {
+            Ok(())
+        } else {
+            Err(FederationError::Unauthorized(format!(
+                "{} is not a signer of federation {}", did.0, self.federation_did
+            )))
+        }
+    }
+
+    /// Grant a federation observer status, returning the read credential to hand to it
+    pub fn grant(
+        &mut self,
+        signer: &Signer,
+        observer_federation: &str,
+        scopes: BTreeSet<ObserverScope>,
+        expires_at: DateTime<Utc>,
+    ) -> FederationResult<ObserverCredential> {
+        self.require_signer(&signer.did)?;
+        if observer_federation == self.federation_did {
+            return Err(FederationError::ValidationError("A federation can't observe itself".to_string()));
+        }
+        if scopes.is_empty() {
+            return Err(FederationError::ValidationError("An observer must be granted at least one scope".to_string()));
+        }
+        let now = Utc::now();
+        if expires_at <= now {
+            return Err(FederationError::ValidationError("Observer status must expire in the future".to_string()));
+        }
+        if !self.active_for(observer_federation, now).is_empty() {
+            return Err(FederationError::ValidationError(format!(
+                "{} already observes federation {}", observer_federation, self.federation_did
+            )));
+        }
+
+        let mut credential = ObserverCredential {
+            relationship_id: Uuid::new_v4().to_string(),
+            host_federation: self.federation_did.clone(),
+            observer_federation: observer_federation.to_string(),
+            scopes: scopes.clone(),
+            expires_at,
+            issued_by: signer.did.clone(),
+            signature: Signature(Vec::new()),
+        };
+        credential.signature = sign_message(&credential.signing_payload()?, &signer.keypair)
+            .map_err(|e| FederationError::CryptoError(format!("Signature failed: {}", e)))?;
+
+        self.relationships.insert(credential.relationship_id.clone(), ObserverRelationship {
+            id: credential.relationship_id.clone(),
+            observer_federation: observer_federation.to_string(),
+            scopes,
+            granted_by: signer.did.clone(),
+            granted_at: now,
+            expires_at,
+            status: ObserverStatus::Active,
+            ended_at: None,
+        });
+        Ok(credential)
+    }
+
+    /// Check a read of `scope` made with an observer credential at `now`
+    pub fn authorize(&self, credential: &ObserverCredential, scope: ObserverScope, now: DateTime<Utc>) -> FederationResult<()> {
+        if credential.host_federation != self.federation_did {
+            return Err(FederationError::Unauthorized(format!(
+                "Observer credential was issued by {}, not {}", credential.host_federation, self.federation_did
+            )));
+        }
+        self.require_signer(&credential.issued_by)?;
+        let valid = verify_signature(&credential.signing_payload()?, &credential.signature, &credential.issued_by)
+            .map_err(|e| FederationError::CryptoError(format!("Signature verification failed: {}", e)))?;
+        if !valid {
+            return Err(FederationError::VerificationError("Observer credential signature does not verify".to_string()));
+        }
+
+        let relationship = self.relationships.get(&credential.relationship_id)
+            .filter(|r| r.observer_federation == credential.observer_federation)
+            .ok_or_else(|| FederationError::Unauthorized(format!(
+                "Observer relationship {} not found", credential.relationship_id
+            )))?;
+        match &relationship.status {
+            ObserverStatus::Active => {}
+            ObserverStatus::Revoked { .. } => {
+                return Err(FederationError::Unauthorized(format!("Observer relationship {} has been revoked", relationship.id)));
+            }
+            ObserverStatus::Converted(_) => {
+                return Err(FederationError::Unauthorized(format!("Observer relationship {} has been converted", relationship.id)));
+            }
+        }
+        if now >= relationship.expires_at.min(credential.expires_at) {
+            return Err(FederationError::Unauthorized(format!("Observer relationship {} has expired", relationship.id)));
+        }
+        if !relationship.scopes.contains(&scope) || !credential.scopes.contains(&scope) {
+            return Err(FederationError::Unauthorized(format!(
+                "Observer relationship {} does not grant {:?}", relationship.id, scope
+            )));
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, signer: &Signer, id: &str, status: ObserverStatus) -> FederationResult<&ObserverRelationship> {
+        self.require_signer(&signer.did)?;
+        let relationship = self.relationships.get_mut(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Observer relationship {} not found", id)))?;
+        if relationship.status != ObserverStatus::Active {
+            return Err(FederationError::ValidationError(format!(
+                "Observer relationship {} has already ended: {:?}", id, relationship.status
+            )));
+        }
+
+        relationship.status = status;
+        relationship.ended_at = Some(Utc::now());
+        Ok(relationship)
+    }
+
+    /// End an observer relationship before it expires
+    pub fn revoke(&mut self, signer: &Signer, id: &str, reason: impl Into<String>) -> FederationResult<&ObserverRelationship> {
+        self.end(signer, id, ObserverStatus::Revoked { reason: reason.into() })
+    }
+
+    /// Record that an observer relationship became a merge proposal or a mutual-aid
+    /// agreement. Its read credential stops working; the new arrangement governs access.
+    /// An expired relationship can still be converted.
+    pub fn convert(&mut self, signer: &Signer, id: &str, conversion: ObserverConversion) -> FederationResult<&ObserverRelationship> {
+        self.end(signer, id, ObserverStatus::Converted(conversion))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::quorum::QuorumType;
+    use crate::signer::initialization;
+
+    #[tokio::test]
+    async fn test_observers_read_their_scopes_until_converted() {
+        let (signers, quorum_config) = initialization::initialize_signer_set(2, QuorumType::Majority).await.unwrap();
+        let mut registry = ObserverRegistry::new("did:key:z6MkHostFederation", quorum_config);
+
+        let observer = "did:key:z6MkProspectiveFederation";
+        let expires_at = Utc::now() + Duration::days(30);
+        let credential = registry.grant(&signers[0], observer, BTreeSet::from([ObserverScope::PublicProposals]), expires_at).unwrap();
+        assert!(registry.grant(&signers[1], observer, BTreeSet::from([ObserverScope::Calendars]), expires_at).is_err());
+
+        let now = Utc::now();
+        assert!(registry.authorize(&credential, ObserverScope::PublicProposals, now).is_ok());
+        assert!(registry.authorize(&credential, ObserverScope::Calendars, now).is_err());
+        assert!(registry.authorize(&credential, ObserverScope::PublicProposals, expires_at).is_err());
+
+        // Widening the credential's scopes breaks its signature
+        let mut widened = credential.clone();
+        widened.scopes.insert(ObserverScope::Calendars);
+        assert!(registry.authorize(&widened, ObserverScope::Calendars, now).is_err());
+
+        let conversion = ObserverConversion::Merge { proposal_id: "merge-proposal-1".to_string() };
+        let relationship = registry.convert(&signers[1], &credential.relationship_id, conversion.clone()).unwrap();
+        assert_eq!(relationship.status, ObserverStatus::Converted(conversion));
+        assert!(registry.authorize(&credential, ObserverScope::PublicProposals, now).is_err());
+        assert!(registry.revoke(&signers[0], &credential.relationship_id, "Merge proposed").is_err());
+    }
+}
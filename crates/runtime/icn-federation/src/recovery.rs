@@ -23,6 +23,8 @@ pub enum RecoveryEventType {
     DisasterRecovery,
     /// Federation metadata update
     MetadataUpdate,
+    /// Service credential issued or revoked
+    ServiceCredential,
 }
 
 /// Base structure for all recovery events
@@ -103,6 +105,24 @@ pub struct MetadataUpdateEvent {
     pub base: RecoveryEvent,
     /// Updated federation metadata
     pub updated_metadata: FederationMetadata,
+    /// Hash of the metadata this update replaces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_metadata_hash: Option<String>,
+}
+
+/// Service credential issuance or revocation event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceCredentialEvent {
+    /// Base recovery event data
+    pub base: RecoveryEvent,
+    /// ID of the credential issued or revoked
+    pub credential_id: String,
+    /// DID of the service the credential belongs to
+    pub service_did: String,
+    /// Permissions the credential grants
+    pub permissions: Vec<String>,
+    /// Whether the credential was revoked rather than issued
+    pub revoked: bool,
 }
 
 /// Recovery module functions
@@ -264,6 +284,7 @@ pub mod recovery {
         let metadata_event = MetadataUpdateEvent {
             base,
             updated_metadata,
+            previous_metadata_hash: None,
         };
         
         // Here we would collect signatures from current signers
@@ -354,6 +375,7 @@ mod tests {
                 initial_members: vec![],
                 initial_signers: vec![],
                 quorum_config: quorum_config.clone(),
+                endpoints: Vec::new(),
                 genesis_cid: Cid::default(),
                 additional_metadata: None,
             },
@@ -538,6 +560,7 @@ mod tests {
             initial_members: vec![],
             initial_signers: signers.iter().map(|s| s.did.0.clone()).collect(),
             quorum_config: quorum_config.clone(),
+            endpoints: Vec::new(),
             genesis_cid: Cid::default(),
             additional_metadata: None,
         };
@@ -0,0 +1,460 @@
+/*!
+# Data Residency
+
+Federations spanning jurisdictions may have to keep some records inside designated
+regions. Every node declares the region it runs in, scopes carry a [`ResidencyTag`]
+naming their home regions, and the federation's [`StoragePolicy`] pins record classes
+such as member PII or financial records either to the home regions of the scope that
+owns them or to an explicit set of regions. Classes the policy doesn't pin may be
+replicated anywhere.
+
+The replication layer enforces the policy twice: [`ResidencyRegistry::plan_replication`]
+only picks targets in allowed regions, and [`ResidencyRegistry::admit_replica`] refuses
+a replica on a node outside them, so a misbehaving sender can't place one either.
+Pinning fails closed: a class pinned to its scope's home regions can't be replicated at
+all for a scope without a tag, and a node that hasn't declared a region never holds a
+pinned record.
+
+Refused replicas, and replicas stranded outside their allowed regions after the policy
+tightened or a node moved, are reported in the [`ComplianceExport`].
+*/
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use icn_identity::IdentityId;
+
+use crate::error::{FederationError, FederationResult};
+use crate::quorum::SignerQuorumConfig;
+use crate::signer::Signer;
+
+/// Classes of records a storage policy can pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ResidencyClass {
+    /// Member identities, contact details and other personal data
+    MemberPii,
+    /// Ledgers, treasury holdings and payment records
+    FinancialRecords,
+    /// Proposals, votes and minutes
+    GovernanceRecords,
+    /// Anything else
+    General,
+}
+
+/// Where a storage policy pins a record class
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegionPin {
+    /// The home regions of the scope that owns the record
+    ScopeHome,
+    /// These regions, whatever the scope
+    Regions(BTreeSet<String>),
+}
+
+/// The record classes a federation pins to regions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoragePolicy {
+    pub pins: BTreeMap<ResidencyClass, RegionPin>,
+}
+
+impl StoragePolicy {
+    /// Pin a class, replacing any earlier pin for it
+    pub fn pin(mut self, class: ResidencyClass, pin: RegionPin) -> Self {
+        self.pins.insert(class, pin);
+        self
+    }
+
+    /// Regions a record of `class` owned by a scope tagged `tag` may be stored in, or
+    /// None if the class isn't pinned
+    pub fn allowed_regions(&self, class: ResidencyClass, tag: Option<&ResidencyTag>) -> Option<BTreeSet<String>> {
+        match self.pins.get(&class)? {
+            RegionPin::ScopeHome => Some(tag.map(|t| t.home_regions.clone()).unwrap_or_default()),
+            RegionPin::Regions(regions) => Some(regions.clone()),
+        }
+    }
+}
+
+/// The regions a scope's pinned records live in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResidencyTag {
+    pub home_regions: BTreeSet<String>,
+    pub tagged_by: IdentityId,
+    pub tagged_at: DateTime<Utc>,
+}
+
+/// A record the replication layer moves between nodes
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RecordRef {
+    /// CID or key of the record
+    pub record_id: String,
+    /// Scope that owns the record
+    pub scope_id: String,
+    pub class: ResidencyClass,
+}
+
+/// Where a record may be replicated to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacementPlan {
+    pub record: RecordRef,
+    /// None if the record's class isn't pinned
+    pub allowed_regions: Option<BTreeSet<String>>,
+    /// Nodes to replicate to, in candidate order
+    pub targets: Vec<String>,
+    /// Candidates left out because of the record's residency
+    pub excluded: Vec<String>,
+    /// Replicas short of the requested factor for lack of allowed nodes
+    pub shortfall: usize,
+}
+
+/// A replica placed on a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaPlacement {
+    pub record: RecordRef,
+    pub node_id: String,
+    /// The node's region, or None if it hasn't declared one
+    pub region: Option<String>,
+}
+
+/// A replica refused because the node is outside the record's allowed regions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefusedReplica {
+    pub placement: ReplicaPlacement,
+    pub refused_at: DateTime<Utc>,
+}
+
+/// Residency of one scope, as reported in a compliance export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopeResidency {
+    pub scope_id: String,
+    /// None if the scope has no residency tag
+    pub home_regions: Option<BTreeSet<String>>,
+    /// Replicas of the scope's records in pinned classes
+    pub pinned_replicas: usize,
+    /// Replicas outside their allowed regions
+    pub stranded: Vec<ReplicaPlacement>,
+    /// Replicas refused since the registry was created
+    pub refused: Vec<RefusedReplica>,
+}
+
+/// A federation's residency controls and how well replicas follow them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplianceExport {
+    pub federation_did: String,
+    pub generated_at: DateTime<Utc>,
+    pub policy: StoragePolicy,
+    /// Region of every node that declared one
+    pub node_regions: BTreeMap<String, String>,
+    /// Every scope with a tag or replicas, sorted by scope
+    pub scopes: Vec<ScopeResidency>,
+    /// Hex SHA-256 of the export with this field empty
+    pub digest: String,
+}
+
+impl ComplianceExport {
+    /// Whether every replica sits in an allowed region
+    pub fn is_compliant(&self) -> bool {
+        self.scopes.iter().all(|s| s.stranded.is_empty())
+    }
+
+    fn compute_digest(&self) -> FederationResult<String> {
+        let mut unsigned = self.clone();
+        unsigned.digest = String::new();
+        let bytes = serde_json::to_vec(&unsigned)
+            .map_err(|e| FederationError::SerializationError(format!("Failed to serialize compliance export: {}", e)))?;
+        Ok(format!("{:x}", Sha256::digest(&bytes)))
+    }
+
+    /// Whether the export is unchanged since it was generated
+    pub fn verify_digest(&self) -> FederationResult<bool> {
+        Ok(self.compute_digest()? == self.digest)
+    }
+}
+
+/// A federation's node regions, scope tags and storage policy, with the replicas
+/// placed under them
+pub struct ResidencyRegistry {
+    federation_did: String,
+    quorum_config: SignerQuorumConfig,
+    node_regions: HashMap<String, String>,
+    scope_tags: HashMap<String, ResidencyTag>,
+    policy: StoragePolicy,
+    /// Replicas by record, then node
+    placements: HashMap<RecordRef, BTreeSet<String>>,
+    refused: Vec<RefusedReplica>,
+}
+
+impl ResidencyRegistry {
+    pub fn new(federation_did: impl Into<String>, quorum_config: SignerQuorumConfig) -> Self {
+        Self {
+            federation_did: federation_did.into(),
+            quorum_config,
+            node_regions: HashMap::new(),
+            scope_tags: HashMap::new(),
+            policy: StoragePolicy::default(),
+            placements: HashMap::new(),
+            refused: Vec::new(),
+        }
+    }
+
+    fn require_signer(&self, did: &IdentityId) -> FederationResult<()> {
+        if self.quorum_config.signers.contains(&did.0) {
+            Ok(())
+        } else {
+            Err(FederationError::Unauthorized(format!(
+                "{} is not a signer of federation {}", did.0, self.federation_did
+            )))
+        }
+    }
+
+    /// Record the region a node runs in. A node that moves keeps its replicas, which
+    /// the compliance export reports if they're now outside their allowed regions.
+    pub fn register_node(&mut self, signer: &Signer, node_id: &str, region: &str) -> FederationResult<()> {
+        self.require_signer(&signer.did)?;
+        if region.is_empty() {
+            return Err(FederationError::ValidationError(format!("Node {} must declare a region", node_id)));
+        }
+        self.node_regions.insert(node_id.to_string(), region.to_string());
+        Ok(())
+    }
+
+    /// The region a node declared
+    pub fn node_region(&self, node_id: &str) -> Option<&str> {
+        self.node_regions.get(node_id).map(String::as_str)
+    }
+
+    /// Tag a scope with the regions its pinned records live in
+    pub fn set_scope_residency(
+        &mut self,
+        signer: &Signer,
+        scope_id: &str,
+        home_regions: BTreeSet<String>,
+    ) -> FederationResult<&ResidencyTag> {
+        self.require_signer(&signer.did)?;
+        if home_regions.is_empty() {
+            return Err(FederationError::ValidationError(format!(
+                "Scope {} must have at least one home region", scope_id
+            )));
+        }
+        self.scope_tags.insert(scope_id.to_string(), ResidencyTag {
+            home_regions,
+            tagged_by: signer.did.clone(),
+            tagged_at: Utc::now(),
+        });
+        Ok(&self.scope_tags[scope_id])
+    }
+
+    /// A scope's residency tag
+    pub fn scope_residency(&self, scope_id: &str) -> Option<&ResidencyTag> {
+        self.scope_tags.get(scope_id)
+    }
+
+    /// Replace the federation's storage policy. Replicas already placed are kept.
+    pub fn set_storage_policy(&mut self, signer: &Signer, policy: StoragePolicy) -> FederationResult<()> {
+        self.require_signer(&signer.did)?;
+        if policy.pins.values().any(|pin| matches!(pin, RegionPin::Regions(regions) if regions.is_empty())) {
+            return Err(FederationError::ValidationError(
+                "A class pinned to explicit regions needs at least one region".to_string()
+            ));
+        }
+        self.policy = policy;
+        Ok(())
+    }
+
+    pub fn storage_policy(&self) -> &StoragePolicy {
+        &self.policy
+    }
+
+    /// Regions a record may be stored in, or None if its class isn't pinned
+    pub fn allowed_regions(&self, record: &RecordRef) -> Option<BTreeSet<String>> {
+        self.policy.allowed_regions(record.class, self.scope_tags.get(&record.scope_id))
+    }
+
+    /// Whether a node may hold a replica of a record
+    pub fn may_hold(&self, record: &RecordRef, node_id: &str) -> bool {
+        match self.allowed_regions(record) {
+            None => true,
+            Some(allowed) => self.node_region(node_id).is_some_and(|region| allowed.contains(region)),
+        }
+    }
+
+    /// Choose up to `factor` of the candidate nodes to replicate a record to, skipping
+    /// nodes outside its allowed regions and nodes that already hold it
+    pub fn plan_replication(&self, record: &RecordRef, candidates: &[String], factor: usize) -> PlacementPlan {
+        let held = self.placements.get(record);
+        let mut targets = Vec::new();
+        let mut excluded = Vec::new();
+        for node_id in candidates {
+            if held.is_some_and(|nodes| nodes.contains(node_id)) || targets.contains(node_id) {
+                continue;
+            }
+            if !self.may_hold(record, node_id) {
+                excluded.push(node_id.clone());
+            } else if targets.len() < factor {
+                targets.push(node_id.clone());
+            }
+        }
+
+        PlacementPlan {
+            record: record.clone(),
+            allowed_regions: self.allowed_regions(record),
+            shortfall: factor.saturating_sub(targets.len()),
+            targets,
+            excluded,
+        }
+    }
+
+    /// Accept a replica of a record on a node, or refuse it if the node is outside the
+    /// record's allowed regions. Refusals are kept for the compliance export.
+    pub fn admit_replica(&mut self, record: &RecordRef, node_id: &str, now: DateTime<Utc>) -> FederationResult<()> {
+        if !self.may_hold(record, node_id) {
+            let region = self.node_region(node_id).map(str::to_string);
+            self.refused.push(RefusedReplica {
+                placement: ReplicaPlacement { record: record.clone(), node_id: node_id.to_string(), region: region.clone() },
+                refused_at: now,
+            });
+            return Err(FederationError::Unauthorized(format!(
+                "{:?} record {} of scope {} may not be stored in region {}",
+                record.class, record.record_id, record.scope_id, region.as_deref().unwrap_or("(undeclared)")
+            )));
+        }
+        self.placements.entry(record.clone()).or_default().insert(node_id.to_string());
+        Ok(())
+    }
+
+    /// Forget a replica once it's deleted from a node
+    pub fn drop_replica(&mut self, record: &RecordRef, node_id: &str) {
+        if let Some(nodes) = self.placements.get_mut(record) {
+            nodes.remove(node_id);
+            if nodes.is_empty() {
+                self.placements.remove(record);
+            }
+        }
+    }
+
+    /// Nodes holding a replica of a record
+    pub fn replicas(&self, record: &RecordRef) -> Vec<String> {
+        self.placements.get(record).map(|nodes| nodes.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Replicas outside their allowed regions, to be moved
+    pub fn stranded_replicas(&self) -> Vec<ReplicaPlacement> {
+        let mut stranded: Vec<ReplicaPlacement> = self.placements.iter()
+            .flat_map(|(record, nodes)| nodes.iter()
+                .filter(|node_id| !self.may_hold(record, node_id))
+                .map(|node_id| ReplicaPlacement {
+                    record: record.clone(),
+                    node_id: node_id.clone(),
+                    region: self.node_region(node_id).map(str::to_string),
+                }))
+            .collect();
+        stranded.sort_by(|a, b| (&a.record.scope_id, &a.record.record_id, &a.node_id)
+            .cmp(&(&b.record.scope_id, &b.record.record_id, &b.node_id)));
+        stranded
+    }
+
+    /// The federation's residency controls, per-scope replica counts, stranded replicas
+    /// and refusals, with a digest auditors can check the export against
+    pub fn compliance_export(&self, now: DateTime<Utc>) -> FederationResult<ComplianceExport> {
+        let mut scope_ids: BTreeSet<&String> = self.scope_tags.keys().collect();
+        scope_ids.extend(self.placements.keys().map(|record| &record.scope_id));
+        scope_ids.extend(self.refused.iter().map(|r| &r.placement.record.scope_id));
+
+        let stranded = self.stranded_replicas();
+        let scopes = scope_ids.into_iter()
+            .map(|scope_id| ScopeResidency {
+                scope_id: scope_id.clone(),
+                home_regions: self.scope_tags.get(scope_id).map(|tag| tag.home_regions.clone()),
+                pinned_replicas: self.placements.iter()
+                    .filter(|(record, _)| &record.scope_id == scope_id && self.policy.pins.contains_key(&record.class))
+                    .map(|(_, nodes)| nodes.len())
+                    .sum(),
+                stranded: stranded.iter().filter(|p| &p.record.scope_id == scope_id).cloned().collect(),
+                refused: self.refused.iter().filter(|r| &r.placement.record.scope_id == scope_id).cloned().collect(),
+            })
+            .collect();
+
+        let mut export = ComplianceExport {
+            federation_did: self.federation_did.clone(),
+            generated_at: now,
+            policy: self.policy.clone(),
+            node_regions: self.node_regions.iter().map(|(node, region)| (node.clone(), region.clone())).collect(),
+            scopes,
+            digest: String::new(),
+        };
+        export.digest = export.compute_digest()?;
+        Ok(export)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quorum::QuorumType;
+    use crate::signer::initialization;
+
+    fn record(id: &str, scope_id: &str, class: ResidencyClass) -> RecordRef {
+        RecordRef { record_id: id.to_string(), scope_id: scope_id.to_string(), class }
+    }
+
+    fn regions(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_pinned_records_stay_in_their_regions() {
+        let (signers, quorum_config) = initialization::initialize_signer_set(1, QuorumType::Majority).await.unwrap();
+        let signer = &signers[0];
+        let mut registry = ResidencyRegistry::new("did:key:z6MkFederation", quorum_config);
+        registry.register_node(signer, "node-berlin", "eu-central").unwrap();
+        registry.register_node(signer, "node-paris", "eu-west").unwrap();
+        registry.register_node(signer, "node-toronto", "ca-central").unwrap();
+        registry.set_scope_residency(signer, "coop-eu", regions(&["eu-central", "eu-west"])).unwrap();
+        registry.set_storage_policy(signer, StoragePolicy::default()
+            .pin(ResidencyClass::MemberPii, RegionPin::ScopeHome)
+            .pin(ResidencyClass::FinancialRecords, RegionPin::Regions(regions(&["eu-central"])))).unwrap();
+
+        let candidates: Vec<String> = ["node-toronto", "node-berlin", "node-paris", "node-unknown"]
+            .iter().map(|n| n.to_string()).collect();
+
+        // PII follows the scope's home regions; unpinned records go anywhere
+        let pii = record("bafy-member-1", "coop-eu", ResidencyClass::MemberPii);
+        let plan = registry.plan_replication(&pii, &candidates, 3);
+        assert_eq!(plan.targets, vec!["node-berlin", "node-paris"]);
+        assert_eq!(plan.excluded, vec!["node-toronto", "node-unknown"]);
+        assert_eq!(plan.shortfall, 1);
+        let minutes = record("bafy-minutes-1", "coop-eu", ResidencyClass::General);
+        assert_eq!(registry.plan_replication(&minutes, &candidates, 2).targets, vec!["node-toronto", "node-berlin"]);
+
+        // A scope without a tag can't replicate PII anywhere
+        let untagged = record("bafy-member-2", "coop-ca", ResidencyClass::MemberPii);
+        assert!(registry.plan_replication(&untagged, &candidates, 1).targets.is_empty());
+
+        // The receiving side refuses replicas outside the allowed regions
+        let now = Utc::now();
+        let ledger = record("bafy-ledger-1", "coop-eu", ResidencyClass::FinancialRecords);
+        registry.admit_replica(&ledger, "node-berlin", now).unwrap();
+        assert!(registry.admit_replica(&ledger, "node-paris", now).is_err());
+        registry.admit_replica(&pii, "node-paris", now).unwrap();
+        assert_eq!(registry.replicas(&ledger), vec!["node-berlin"]);
+
+        let export = registry.compliance_export(now).unwrap();
+        assert!(export.is_compliant());
+        assert!(export.verify_digest().unwrap());
+        let eu = export.scopes.iter().find(|s| s.scope_id == "coop-eu").unwrap();
+        assert_eq!(eu.pinned_replicas, 2);
+        assert_eq!(eu.refused.len(), 1);
+        assert_eq!(eu.refused[0].placement.region.as_deref(), Some("eu-west"));
+
+        // Moving a node strands the replicas it holds
+        registry.register_node(signer, "node-paris", "us-east").unwrap();
+        let export = registry.compliance_export(now).unwrap();
+        assert!(!export.is_compliant());
+        let eu = export.scopes.iter().find(|s| s.scope_id == "coop-eu").unwrap();
+        assert_eq!(eu.stranded.len(), 1);
+        assert_eq!(eu.stranded[0].record, pii);
+
+        let mut tampered = export.clone();
+        tampered.scopes.clear();
+        assert!(!tampered.verify_digest().unwrap());
+    }
+}
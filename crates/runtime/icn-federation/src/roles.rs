@@ -630,6 +630,9 @@ mod tests {
                 quorum: None,
                 majority: None,
                 term_length: None,
+                invariants: None,
+                member_classes: None,
+                calendar: None,
                 roles: Some(vec![
                     Role {
                         name: "Guardian".to_string(),
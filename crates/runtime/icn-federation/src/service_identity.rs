@@ -0,0 +1,468 @@
+/*!
+# Service Identities and API Keys
+
+Integrations such as a payroll exporter, a bank bridge or a status page act against the
+federation without being members. Each integration gets a [`ServiceCredential`]: a
+federation-issued credential bound to the service's DID, granting a narrow set of
+permissions until it expires.
+
+Credentials follow the same path as metadata updates. A signer proposes issuing or
+revoking one, the federation's signers sign the proposal, and once the signatures meet
+the [`SignerQuorumConfig`] the change takes effect and is anchored to the DAG as a
+[`ServiceCredentialEvent`]. Issuing a credential yields an API key; the registry keeps
+only its hash, so the key is shown once, to the proposer.
+
+Every request made with a key goes through [`ServiceIdentityRegistry::authorize`], which
+enforces expiry, revocation and scope, counts the request against the credential's own
+[`ServiceRateLimit`] (separate from any member's limits), and writes a
+[`ServiceAuditRecord`] whether the request was allowed or not.
+*/
+
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use icn_identity::{IdentityId, QuorumProof, Signature, sign_message};
+
+use crate::dag_client::{DagClient, FederationDagEvent};
+use crate::error::{FederationError, FederationResult};
+use crate::quorum::{decisions, SignerQuorumConfig};
+use crate::recovery::{RecoveryEvent, RecoveryEventType, ServiceCredentialEvent};
+use crate::signer::Signer;
+
+/// Prefix of every service API key
+pub const API_KEY_PREFIX: &str = "icn_svc_";
+
+/// Requests a service may make per rate-limit window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceRateLimit {
+    pub max_requests: u32,
+    pub window_secs: i64,
+}
+
+impl Default for ServiceRateLimit {
+    fn default() -> Self {
+        Self { max_requests: 60, window_secs: 60 }
+    }
+}
+
+/// Hex-encoded SHA-256 of an API key
+pub fn api_key_hash(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
+
+fn generate_api_key() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    let hex: String = secret.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", API_KEY_PREFIX, hex)
+}
+
+/// Terms of a credential to issue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceGrant {
+    /// DID of the service
+    pub service_did: String,
+    /// Human-readable name of the integration
+    pub name: String,
+    /// Permissions granted, e.g. "treasury:read"
+    pub permissions: BTreeSet<String>,
+    pub expires_at: DateTime<Utc>,
+    pub rate_limit: ServiceRateLimit,
+}
+
+/// A change to the federation's service credentials
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceCredentialChange {
+    /// Issue a credential; carries the hash of the API key generated with the proposal
+    Issue { grant: ServiceGrant, key_hash: String },
+    /// Revoke an issued credential
+    Revoke { credential_id: String, reason: String },
+}
+
+/// A proposed credential change collecting signer signatures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceCredentialProposal {
+    pub id: String,
+    pub change: ServiceCredentialChange,
+    pub proposed_by: IdentityId,
+    pub proposed_at: DateTime<Utc>,
+    pub signatures: Vec<(IdentityId, Signature)>,
+}
+
+impl ServiceCredentialProposal {
+    /// Bytes signers sign: the proposal ID and the hash of the change
+    pub fn signing_payload(&self) -> FederationResult<Vec<u8>> {
+        let bytes = serde_json::to_vec(&self.change)
+            .map_err(|e| FederationError::SerializationError(format!("Failed to serialize credential change: {}", e)))?;
+        Ok(format!("service-credential:{}:{:x}", self.id, Sha256::digest(&bytes)).into_bytes())
+    }
+}
+
+/// A quorum-approved service credential
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceCredential {
+    /// Credential ID; the ID of the proposal that issued it
+    pub id: String,
+    pub grant: ServiceGrant,
+    /// Hash of the credential's API key
+    pub key_hash: String,
+    pub issued_at: DateTime<Utc>,
+    pub quorum_proof: QuorumProof,
+    /// CID of the issuance anchor
+    pub anchor_cid: String,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<String>,
+}
+
+impl ServiceCredential {
+    /// Whether the credential can be used at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && now < self.grant.expires_at
+    }
+}
+
+/// Outcome of a request made with a service API key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceAccessOutcome {
+    Allowed,
+    Denied(String),
+}
+
+/// A request made with a service API key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceAuditRecord {
+    /// Credential the key belongs to; `None` for unknown keys
+    pub credential_id: Option<String>,
+    pub service_did: Option<String>,
+    pub permission: String,
+    pub outcome: ServiceAccessOutcome,
+    pub at: DateTime<Utc>,
+}
+
+/// Requests counted in a credential's current rate-limit window
+#[derive(Debug, Clone, Copy)]
+struct UsageWindow {
+    started_at: DateTime<Utc>,
+    requests: u32,
+}
+
+/// A federation's service credentials, their pending changes and their usage
+pub struct ServiceIdentityRegistry {
+    federation_did: String,
+    quorum_config: SignerQuorumConfig,
+    credentials: HashMap<String, ServiceCredential>,
+    /// Credential ID by key hash
+    keys: HashMap<String, String>,
+    pending: HashMap<String, ServiceCredentialProposal>,
+    usage: HashMap<String, UsageWindow>,
+    audit_log: Vec<ServiceAuditRecord>,
+    last_anchor_cid: Option<String>,
+    anchored_events: u64,
+}
+
+impl ServiceIdentityRegistry {
+    pub fn new(federation_did: impl Into<String>, quorum_config: SignerQuorumConfig) -> Self {
+        Self {
+            federation_did: federation_did.into(),
+            quorum_config,
+            credentials: HashMap::new(),
+            keys: HashMap::new(),
+            pending: HashMap::new(),
+            usage: HashMap::new(),
+            audit_log: Vec::new(),
+            last_anchor_cid: None,
+            anchored_events: 0,
+        }
+    }
+
+    /// An issued credential, active or not
+    pub fn credential(&self, id: &str) -> Option<&ServiceCredential> {
+        self.credentials.get(id)
+    }
+
+    /// Credentials issued to a service
+    pub fn credentials_for(&self, service_did: &str) -> Vec<&ServiceCredential> {
+        self.credentials.values().filter(|c| c.grant.service_did == service_did).collect()
+    }
+
+    /// A pending credential change
+    pub fn pending_change(&self, id: &str) -> Option<&ServiceCredentialProposal> {
+        self.pending.get(id)
+    }
+
+    /// Every request made with a service API key, oldest first
+    pub fn audit_log(&self) -> &[ServiceAuditRecord] {
+        &self.audit_log
+    }
+
+    fn require_signer(&self, did: &IdentityId) -> FederationResult<()> {
+        if self.quorum_config.signers.contains(&did.0) {
+            Ok(())
+        } else {
+            Err(FederationError::Unauthorized(format!(
+                "{} is not a signer of federation {}", did.0, self.federation_did
+            )))
+        }
+    }
+
+    fn add_proposal(&mut self, proposer: &IdentityId, change: ServiceCredentialChange) -> String {
+        let proposal = ServiceCredentialProposal {
+            id: Uuid::new_v4().to_string(),
+            change,
+            proposed_by: proposer.clone(),
+            proposed_at: Utc::now(),
+            signatures: Vec::new(),
+        };
+        let id = proposal.id.clone();
+        self.pending.insert(id.clone(), proposal);
+        id
+    }
+
+    /// Propose issuing a credential. Returns the proposal ID and the credential's API key,
+    /// which can't be recovered later and only works once the proposal is approved.
+    pub fn propose_issue(&mut self, proposer: &IdentityId, grant: ServiceGrant) -> FederationResult<(String, String)> {
+        self.require_signer(proposer)?;
+        if grant.permissions.is_empty() {
+            return Err(FederationError::ValidationError("A service credential must grant at least one permission".to_string()));
+        }
+        if grant.expires_at <= Utc::now() {
+            return Err(FederationError::ValidationError("A service credential must expire in the future".to_string()));
+        }
+        if grant.rate_limit.max_requests == 0 || grant.rate_limit.window_secs <= 0 {
+            return Err(FederationError::ValidationError("A service credential needs a non-empty rate limit".to_string()));
+        }
+
+        let api_key = generate_api_key();
+        let id = self.add_proposal(proposer, ServiceCredentialChange::Issue { grant, key_hash: api_key_hash(&api_key) });
+        Ok((id, api_key))
+    }
+
+    /// Propose revoking an issued credential. Returns the proposal ID.
+    pub fn propose_revoke(&mut self, proposer: &IdentityId, credential_id: &str, reason: impl Into<String>) -> FederationResult<String> {
+        self.require_signer(proposer)?;
+        let credential = self.credentials.get(credential_id)
+            .ok_or_else(|| FederationError::NotFound(format!("Service credential {} not found", credential_id)))?;
+        if credential.revoked_at.is_some() {
+            return Err(FederationError::ValidationError(format!("Service credential {} is already revoked", credential_id)));
+        }
+
+        Ok(self.add_proposal(proposer, ServiceCredentialChange::Revoke {
+            credential_id: credential_id.to_string(),
+            reason: reason.into(),
+        }))
+    }
+
+    /// Add a signer's signature to a proposal. Returns the number of signatures collected.
+    pub fn sign_change(&mut self, id: &str, signer: &Signer) -> FederationResult<usize> {
+        self.require_signer(&signer.did)?;
+        let proposal = self.pending.get_mut(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Service credential change {} not found", id)))?;
+        if proposal.signatures.iter().any(|(did, _)| did == &signer.did) {
+            return Err(FederationError::ValidationError(format!(
+                "{} already signed service credential change {}", signer.did.0, id
+            )));
+        }
+
+        let signature = sign_message(&proposal.signing_payload()?, &signer.keypair)
+            .map_err(|e| FederationError::CryptoError(format!("Signature failed: {}", e)))?;
+        proposal.signatures.push((signer.did.clone(), signature));
+        Ok(proposal.signatures.len())
+    }
+
+    /// Apply a proposal once its signatures meet the quorum and anchor it to the DAG.
+    /// Returns the credential issued or revoked.
+    pub async fn finalize_change(&mut self, id: &str, dag: &impl DagClient) -> FederationResult<&ServiceCredential> {
+        let proposal = self.pending.get(id)
+            .ok_or_else(|| FederationError::NotFound(format!("Service credential change {} not found", id)))?
+            .clone();
+
+        let required = self.quorum_config.required_signatures();
+        if proposal.signatures.len() < required {
+            return Err(FederationError::VerificationError(format!(
+                "Not enough signatures: got {}, need {} for quorum",
+                proposal.signatures.len(), required
+            )));
+        }
+
+        let quorum_proof = QuorumProof {
+            votes: proposal.signatures.clone(),
+            config: self.quorum_config.to_quorum_config(),
+        };
+        let payload = proposal.signing_payload()?;
+        if !decisions::verify_quorum_proof(&quorum_proof, &payload, &self.quorum_config.signers).await? {
+            return Err(FederationError::VerificationError(format!(
+                "Quorum proof for service credential change {} does not verify", id
+            )));
+        }
+
+        let (credential_id, grant, revoked) = match &proposal.change {
+            ServiceCredentialChange::Issue { grant, .. } => (proposal.id.clone(), grant.clone(), false),
+            ServiceCredentialChange::Revoke { credential_id, .. } => {
+                let credential = self.credentials.get(credential_id)
+                    .ok_or_else(|| FederationError::NotFound(format!("Service credential {} not found", credential_id)))?;
+                if credential.revoked_at.is_some() {
+                    self.pending.remove(id);
+                    return Err(FederationError::ValidationError(format!(
+                        "Service credential {} is already revoked", credential_id
+                    )));
+                }
+                (credential_id.clone(), credential.grant.clone(), true)
+            }
+        };
+
+        let event = ServiceCredentialEvent {
+            base: RecoveryEvent {
+                event_type: RecoveryEventType::ServiceCredential,
+                federation_did: self.federation_did.clone(),
+                sequence_number: self.anchored_events + 1,
+                previous_event_cid: self.last_anchor_cid.clone(),
+                timestamp: Utc::now(),
+                signatures: proposal.signatures.iter().map(|(_, sig)| sig.clone()).collect(),
+            },
+            credential_id: credential_id.clone(),
+            service_did: grant.service_did.clone(),
+            permissions: grant.permissions.iter().cloned().collect(),
+            revoked,
+        };
+        let anchor_cid = dag.store_event(FederationDagEvent::ServiceCredential(event)).await?;
+        self.anchored_events += 1;
+        self.last_anchor_cid = Some(anchor_cid.clone());
+        self.pending.remove(id);
+
+        match proposal.change {
+            ServiceCredentialChange::Issue { grant, key_hash } => {
+                self.keys.insert(key_hash.clone(), credential_id.clone());
+                self.credentials.insert(credential_id.clone(), ServiceCredential {
+                    id: credential_id.clone(),
+                    grant,
+                    key_hash,
+                    issued_at: Utc::now(),
+                    quorum_proof,
+                    anchor_cid,
+                    revoked_at: None,
+                    revocation_reason: None,
+                });
+            }
+            ServiceCredentialChange::Revoke { reason, .. } => {
+                let credential = self.credentials.get_mut(&credential_id)
+                    .expect("revoked credential was checked above");
+                credential.revoked_at = Some(Utc::now());
+                credential.revocation_reason = Some(reason);
+                self.usage.remove(&credential_id);
+            }
+        }
+        Ok(&self.credentials[&credential_id])
+    }
+
+    /// Check a request made with `api_key` for `permission` at `now`, count it against
+    /// the credential's rate limit and record it in the audit log
+    pub fn authorize(&mut self, api_key: &str, permission: &str, now: DateTime<Utc>) -> FederationResult<&ServiceCredential> {
+        let credential_id = self.keys.get(&api_key_hash(api_key)).cloned();
+        let result = match &credential_id {
+            None => Err(FederationError::Unauthorized("Unknown service API key".to_string())),
+            Some(id) => self.check_request(id, permission, now),
+        };
+
+        self.audit_log.push(ServiceAuditRecord {
+            service_did: credential_id.as_ref()
+                .and_then(|id| self.credentials.get(id))
+                .map(|c| c.grant.service_did.clone()),
+            credential_id: credential_id.clone(),
+            permission: permission.to_string(),
+            outcome: match &result {
+                Ok(()) => ServiceAccessOutcome::Allowed,
+                Err(e) => ServiceAccessOutcome::Denied(e.to_string()),
+            },
+            at: now,
+        });
+
+        result.map(move |()| &self.credentials[&credential_id.expect("allowed requests have a credential")])
+    }
+
+    fn check_request(&mut self, credential_id: &str, permission: &str, now: DateTime<Utc>) -> FederationResult<()> {
+        let credential = &self.credentials[credential_id];
+        if credential.revoked_at.is_some() {
+            return Err(FederationError::Unauthorized(format!("Service credential {} has been revoked", credential_id)));
+        }
+        if now >= credential.grant.expires_at {
+            return Err(FederationError::Unauthorized(format!("Service credential {} has expired", credential_id)));
+        }
+        if !credential.grant.permissions.contains(permission) {
+            return Err(FederationError::Unauthorized(format!(
+                "Service credential {} does not grant {}", credential_id, permission
+            )));
+        }
+
+        let limit = credential.grant.rate_limit;
+        let window = self.usage.entry(credential_id.to_string())
+            .or_insert(UsageWindow { started_at: now, requests: 0 });
+        if now >= window.started_at + Duration::seconds(limit.window_secs) {
+            *window = UsageWindow { started_at: now, requests: 0 };
+        }
+        if window.requests >= limit.max_requests {
+            return Err(FederationError::RateLimited(format!(
+                "Service credential {} exceeded {} requests per {}s", credential_id, limit.max_requests, limit.window_secs
+            )));
+        }
+        window.requests += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag_client::InMemoryDagClient;
+    use crate::quorum::QuorumType;
+    use crate::signer::initialization;
+
+    #[tokio::test]
+    async fn test_service_keys_are_quorum_issued_scoped_and_rate_limited() {
+        let (signers, quorum_config) = initialization::initialize_signer_set(3, QuorumType::Majority).await.unwrap();
+        let mut registry = ServiceIdentityRegistry::new("did:key:z6MkFederation", quorum_config);
+        let dag = InMemoryDagClient::default();
+
+        let grant = ServiceGrant {
+            service_did: "did:key:z6MkPayrollExporter".to_string(),
+            name: "Payroll exporter".to_string(),
+            permissions: BTreeSet::from(["treasury:read".to_string()]),
+            expires_at: Utc::now() + Duration::days(90),
+            rate_limit: ServiceRateLimit { max_requests: 2, window_secs: 60 },
+        };
+        let (issue, api_key) = registry.propose_issue(&signers[0].did, grant).unwrap();
+        assert!(api_key.starts_with(API_KEY_PREFIX));
+
+        // The key does nothing until a quorum approves the credential
+        let now = Utc::now();
+        assert!(registry.authorize(&api_key, "treasury:read", now).is_err());
+        registry.sign_change(&issue, &signers[0]).unwrap();
+        assert!(registry.finalize_change(&issue, &dag).await.is_err());
+        registry.sign_change(&issue, &signers[1]).unwrap();
+        let credential = registry.finalize_change(&issue, &dag).await.unwrap();
+        assert_ne!(credential.key_hash, api_key);
+
+        assert!(registry.authorize(&api_key, "treasury:read", now).is_ok());
+        assert!(registry.authorize(&api_key, "treasury:write", now).is_err());
+        assert!(registry.authorize(&api_key, "treasury:read", now).is_ok());
+        assert!(matches!(
+            registry.authorize(&api_key, "treasury:read", now),
+            Err(FederationError::RateLimited(_))
+        ));
+        assert!(registry.authorize(&api_key, "treasury:read", now + Duration::seconds(61)).is_ok());
+
+        let revoke = registry.propose_revoke(&signers[2].did, &issue, "Integration retired").unwrap();
+        registry.sign_change(&revoke, &signers[1]).unwrap();
+        registry.sign_change(&revoke, &signers[2]).unwrap();
+        assert!(registry.finalize_change(&revoke, &dag).await.unwrap().revoked_at.is_some());
+        assert!(registry.authorize(&api_key, "treasury:read", now + Duration::seconds(61)).is_err());
+
+        let log = registry.audit_log();
+        assert_eq!(log.len(), 7);
+        assert_eq!(log[0].credential_id, None);
+        assert_eq!(log.iter().filter(|r| r.outcome == ServiceAccessOutcome::Allowed).count(), 3);
+        assert_eq!(log[6].service_did.as_deref(), Some("did:key:z6MkPayrollExporter"));
+    }
+}
@@ -0,0 +1,367 @@
+/*!
+# Shared Service Registry
+
+Federations run shared infrastructure: mailing lists, storage nodes, CI runners and the
+like. The service registry is the federation's catalog of that infrastructure. Each
+service has its own DID, its endpoints, the members who maintain it and the cost center
+its spending is charged to.
+
+Budget proposals reference services by DID, and [`ServiceRegistry::resolve_budget_reference`]
+checks that the service is registered and still in operation before the spending is
+accepted. Availability probes recorded against each service feed the health subsystem,
+which raises alerts for services that drop below the federation's liveness threshold.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::analytics::{AlertSeverity, HealthAlert, HealthEventSink, HealthSignal, HealthThresholds, SuggestedIntervention};
+use crate::error::{FederationError, FederationResult};
+
+/// Default number of probes kept per service for availability
+pub const DEFAULT_PROBE_WINDOW: usize = 50;
+
+/// Kind of shared service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceKind {
+    MailingList,
+    StorageNode,
+    ContinuousIntegration,
+    Website,
+    Other(String),
+}
+
+/// Operational status of a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceStatus {
+    /// In operation
+    Active,
+    /// Still running, but scheduled for retirement; no new budget commitments
+    Deprecated,
+    /// No longer running
+    Retired,
+}
+
+/// A network endpoint of a service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceEndpoint {
+    /// Protocol (e.g., "https", "smtp", "ipfs")
+    pub protocol: String,
+    /// Endpoint address
+    pub address: String,
+}
+
+/// A shared service registered with the federation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedService {
+    /// DID of the service
+    pub service_did: String,
+    /// Human-readable name
+    pub name: String,
+    /// Kind of service
+    pub kind: ServiceKind,
+    /// Endpoints the service is reachable at
+    pub endpoints: Vec<ServiceEndpoint>,
+    /// DIDs of the members who maintain the service
+    pub maintainers: Vec<String>,
+    /// Cost center the service's spending is charged to
+    pub cost_center: String,
+    /// Operational status
+    pub status: ServiceStatus,
+    /// When the service was registered
+    pub registered_at: DateTime<Utc>,
+    /// When the service entry was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SharedService {
+    /// Whether the given DID maintains this service
+    pub fn is_maintainer(&self, did: &str) -> bool {
+        self.maintainers.iter().any(|m| m == did)
+    }
+}
+
+/// Result of one availability check against a service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceProbe {
+    /// When the probe ran
+    pub checked_at: DateTime<Utc>,
+    /// Whether the service answered
+    pub reachable: bool,
+    /// Response latency, when reachable
+    pub latency_ms: Option<u64>,
+}
+
+/// Catalog of a federation's shared services
+#[derive(Debug, Clone)]
+pub struct ServiceRegistry {
+    federation_did: String,
+    services: HashMap<String, SharedService>,
+    probes: HashMap<String, VecDeque<ServiceProbe>>,
+    probe_window: usize,
+}
+
+impl ServiceRegistry {
+    /// Create an empty registry for a federation
+    pub fn new(federation_did: impl Into<String>) -> Self {
+        Self {
+            federation_did: federation_did.into(),
+            services: HashMap::new(),
+            probes: HashMap::new(),
+            probe_window: DEFAULT_PROBE_WINDOW,
+        }
+    }
+
+    /// Keep the given number of probes per service when computing availability
+    pub fn with_probe_window(mut self, probe_window: usize) -> Self {
+        self.probe_window = probe_window.max(1);
+        self
+    }
+
+    /// Federation DID
+    pub fn federation_did(&self) -> &str {
+        &self.federation_did
+    }
+
+    /// Register a new service
+    pub fn register_service(&mut self, service: SharedService) -> FederationResult<()> {
+        validate_service(&service)?;
+        if self.services.contains_key(&service.service_did) {
+            return Err(FederationError::ValidationError(format!(
+                "Service {} is already registered", service.service_did
+            )));
+        }
+
+        self.services.insert(service.service_did.clone(), service);
+        Ok(())
+    }
+
+    /// Replace a service's endpoints. Only a maintainer may do this.
+    pub fn update_endpoints(&mut self, service_did: &str, caller_did: &str, endpoints: Vec<ServiceEndpoint>) -> FederationResult<()> {
+        let service = self.maintained_service_mut(service_did, caller_did)?;
+        if endpoints.is_empty() {
+            return Err(FederationError::ValidationError("A service needs at least one endpoint".to_string()));
+        }
+
+        service.endpoints = endpoints;
+        service.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Change a service's status. Only a maintainer may do this, and retired services
+    /// stay retired.
+    pub fn set_status(&mut self, service_did: &str, caller_did: &str, status: ServiceStatus) -> FederationResult<()> {
+        let service = self.maintained_service_mut(service_did, caller_did)?;
+        if service.status == ServiceStatus::Retired && status != ServiceStatus::Retired {
+            return Err(FederationError::ValidationError(format!(
+                "Service {} is retired and cannot be reactivated", service_did
+            )));
+        }
+
+        service.status = status;
+        service.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Get a service by DID
+    pub fn get(&self, service_did: &str) -> Option<&SharedService> {
+        self.services.get(service_did)
+    }
+
+    /// All registered services, ordered by DID
+    pub fn services(&self) -> Vec<&SharedService> {
+        let mut services: Vec<_> = self.services.values().collect();
+        services.sort_by(|a, b| a.service_did.cmp(&b.service_did));
+        services
+    }
+
+    /// Services charged to a cost center, ordered by DID
+    pub fn services_for_cost_center(&self, cost_center: &str) -> Vec<&SharedService> {
+        self.services().into_iter()
+            .filter(|s| s.cost_center == cost_center)
+            .collect()
+    }
+
+    /// Resolve a service referenced by a budget proposal.
+    ///
+    /// Only active services can take new budget commitments.
+    pub fn resolve_budget_reference(&self, service_did: &str) -> FederationResult<&SharedService> {
+        let service = self.services.get(service_did)
+            .ok_or_else(|| FederationError::NotFound(format!("Service {}", service_did)))?;
+
+        if service.status != ServiceStatus::Active {
+            return Err(FederationError::ValidationError(format!(
+                "Service {} is {:?} and cannot be budgeted for", service_did, service.status
+            )));
+        }
+        Ok(service)
+    }
+
+    /// Record an availability probe for a service
+    pub fn record_probe(&mut self, service_did: &str, probe: ServiceProbe) -> FederationResult<()> {
+        if !self.services.contains_key(service_did) {
+            return Err(FederationError::NotFound(format!("Service {}", service_did)));
+        }
+
+        let probes = self.probes.entry(service_did.to_string()).or_default();
+        probes.push_back(probe);
+        while probes.len() > self.probe_window {
+            probes.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Fraction of recent probes that reached the service, if it has been probed
+    pub fn availability(&self, service_did: &str) -> Option<f64> {
+        let probes = self.probes.get(service_did).filter(|p| !p.is_empty())?;
+        let reachable = probes.iter().filter(|p| p.reachable).count();
+        Some(reachable as f64 / probes.len() as f64)
+    }
+
+    /// Alerts for active services whose availability is below the liveness threshold
+    pub fn service_alerts(&self, thresholds: &HealthThresholds, now: DateTime<Utc>) -> Vec<HealthAlert> {
+        self.services().into_iter()
+            .filter(|s| s.status == ServiceStatus::Active)
+            .filter_map(|service| {
+                let availability = self.availability(&service.service_did)?;
+                if availability >= thresholds.min_liveness {
+                    return None;
+                }
+
+                let severity = if availability < thresholds.min_liveness / 2.0 {
+                    AlertSeverity::Critical
+                } else {
+                    AlertSeverity::Warning
+                };
+
+                Some(HealthAlert {
+                    federation_did: self.federation_did.clone(),
+                    signal: HealthSignal::ServiceAvailability,
+                    severity,
+                    observed: availability,
+                    threshold: thresholds.min_liveness,
+                    intervention: SuggestedIntervention::ContactServiceMaintainers,
+                    message: format!(
+                        "{} ({}) answered {:.0}% of recent probes; maintainers: {}",
+                        service.name, service.service_did, availability * 100.0, service.maintainers.join(", ")
+                    ),
+                    raised_at: now,
+                })
+            })
+            .collect()
+    }
+
+    /// Raise service availability alerts through the health subsystem's sink
+    pub async fn report_service_alerts<K: HealthEventSink + Sync>(
+        &self,
+        thresholds: &HealthThresholds,
+        now: DateTime<Utc>,
+        sink: &K,
+    ) -> FederationResult<Vec<HealthAlert>> {
+        let alerts = self.service_alerts(thresholds, now);
+        for alert in &alerts {
+            sink.emit_alert(alert).await?;
+        }
+        Ok(alerts)
+    }
+
+    fn maintained_service_mut(&mut self, service_did: &str, caller_did: &str) -> FederationResult<&mut SharedService> {
+        let service = self.services.get_mut(service_did)
+            .ok_or_else(|| FederationError::NotFound(format!("Service {}", service_did)))?;
+
+        if !service.is_maintainer(caller_did) {
+            return Err(FederationError::Unauthorized(format!(
+                "{} does not maintain service {}", caller_did, service_did
+            )));
+        }
+        Ok(service)
+    }
+}
+
+fn validate_service(service: &SharedService) -> FederationResult<()> {
+    if !service.service_did.starts_with("did:") {
+        return Err(FederationError::ValidationError(format!(
+            "Service identifier {} is not a DID", service.service_did
+        )));
+    }
+    if service.name.trim().is_empty() {
+        return Err(FederationError::ValidationError("Service name must not be empty".to_string()));
+    }
+    if service.endpoints.is_empty() {
+        return Err(FederationError::ValidationError("A service needs at least one endpoint".to_string()));
+    }
+    if service.maintainers.is_empty() {
+        return Err(FederationError::ValidationError("A service needs at least one maintainer".to_string()));
+    }
+    if service.cost_center.trim().is_empty() {
+        return Err(FederationError::ValidationError("A service must be charged to a cost center".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn mailing_list() -> SharedService {
+        let now = Utc::now();
+        SharedService {
+            service_did: "did:icn:service:lists".to_string(),
+            name: "Federation mailing lists".to_string(),
+            kind: ServiceKind::MailingList,
+            endpoints: vec![ServiceEndpoint {
+                protocol: "smtp".to_string(),
+                address: "lists.federation.example".to_string(),
+            }],
+            maintainers: vec!["did:icn:member:alice".to_string()],
+            cost_center: "communications".to_string(),
+            status: ServiceStatus::Active,
+            registered_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_register_and_budget_reference() {
+        let mut registry = ServiceRegistry::new("did:icn:federation:test");
+        registry.register_service(mailing_list()).unwrap();
+        assert!(registry.register_service(mailing_list()).is_err());
+
+        let mut no_cost_center = mailing_list();
+        no_cost_center.service_did = "did:icn:service:ci".to_string();
+        no_cost_center.cost_center = String::new();
+        assert!(registry.register_service(no_cost_center).is_err());
+
+        assert_eq!(registry.resolve_budget_reference("did:icn:service:lists").unwrap().cost_center, "communications");
+        assert_eq!(registry.services_for_cost_center("communications").len(), 1);
+
+        // Only maintainers can change a service
+        assert!(registry.set_status("did:icn:service:lists", "did:icn:member:bob", ServiceStatus::Deprecated).is_err());
+        registry.set_status("did:icn:service:lists", "did:icn:member:alice", ServiceStatus::Deprecated).unwrap();
+        assert!(registry.resolve_budget_reference("did:icn:service:lists").is_err());
+    }
+
+    #[test]
+    fn test_unavailable_service_raises_alert() {
+        let mut registry = ServiceRegistry::new("did:icn:federation:test").with_probe_window(4);
+        registry.register_service(mailing_list()).unwrap();
+
+        let start = Utc::now();
+        for (i, reachable) in [true, true, false, false, false].iter().enumerate() {
+            registry.record_probe("did:icn:service:lists", ServiceProbe {
+                checked_at: start + Duration::minutes(i as i64),
+                reachable: *reachable,
+                latency_ms: None,
+            }).unwrap();
+        }
+
+        // Only the last four probes count
+        assert_eq!(registry.availability("did:icn:service:lists"), Some(0.25));
+
+        let alerts = registry.service_alerts(&HealthThresholds::default(), start);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].signal, HealthSignal::ServiceAvailability);
+        assert_eq!(alerts[0].intervention, SuggestedIntervention::ContactServiceMaintainers);
+    }
+}
@@ -338,6 +338,9 @@ fn test_create_signed_mandate() {
                 quorum: None,
                 majority: None,
                 term_length: None,
+                invariants: None,
+                member_classes: None,
+                calendar: None,
                 roles: Some(vec![
                     Role {
                         name: "Guardian".to_string(),
@@ -408,6 +411,9 @@ fn test_guardian_mandate_verify() {
                 quorum: None,
                 majority: None,
                 term_length: None,
+                invariants: None,
+                member_classes: None,
+                calendar: None,
                 roles: Some(vec![
                     Role {
                         name: "Guardian".to_string(),
@@ -488,6 +494,9 @@ fn test_governance_kernel_config() {
                 quorum: None,
                 majority: None,
                 term_length: None,
+                invariants: None,
+                member_classes: None,
+                calendar: None,
                 roles: Some(vec![
                     Role {
                         name: "Guardian".to_string(),
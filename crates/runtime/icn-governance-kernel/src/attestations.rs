@@ -0,0 +1,208 @@
+/*!
+# External Attestations
+
+Members often want an outside opinion before voting: a lawyer's review of a bylaw
+change, an auditor's opinion on a budget. Third parties attach such opinions to a
+proposal as signed attestations. An attestation names its issuer, the kind of review,
+the CID of the document blob holding the full opinion and the claims the issuer
+stands behind (e.g., `"complies_with": "state cooperative statute"`), and is signed
+with the issuer's key.
+
+Attestations can be attached while a proposal is a draft or open for voting. They
+are returned with the proposal's deliberation context, and the CID of each is listed
+in the proposal's outcome evidence, so the opinions members saw stay bound to the
+decision.
+*/
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use icn_identity::{IdentityId, KeyPair, Signature};
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, ProposalStatus, SignatureProof};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::outcomes::content_cid;
+
+/// The kind of review an attestation records
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AttestationKind {
+    LegalReview,
+    FinancialAudit,
+    ExpertReview,
+    Other(String),
+}
+
+/// A third party's signed opinion on a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProposalAttestation {
+    pub proposal_id: String,
+    pub issuer: IdentityId,
+    pub kind: AttestationKind,
+    /// CID of the blob holding the full opinion
+    pub document_cid: String,
+    /// What the issuer attests to, by claim name
+    pub claims: BTreeMap<String, String>,
+    /// When the opinion stops standing, if it lapses (Unix timestamp)
+    pub valid_until: Option<i64>,
+    /// When the attestation was issued (Unix timestamp)
+    pub issued_at: i64,
+    pub proof: SignatureProof,
+}
+
+impl ProposalAttestation {
+    /// An unsigned attestation issued now
+    pub fn new(
+        proposal_id: &str,
+        issuer: IdentityId,
+        kind: AttestationKind,
+        document_cid: &str,
+        claims: BTreeMap<String, String>,
+        valid_until: Option<i64>,
+    ) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            proposal_id: proposal_id.to_string(),
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: String::new(), // Will be filled after signing
+                created: now,
+                verification_method: format!("{}#keys-1", issuer.0),
+                purpose: "assertionMethod".to_string(),
+            },
+            issuer,
+            kind,
+            document_cid: document_cid.to_string(),
+            claims,
+            valid_until,
+            issued_at: now,
+        }
+    }
+
+    /// Bytes covered by the signature: the attestation with an empty signature value
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, GovernanceError> {
+        let mut unsigned = self.clone();
+        unsigned.proof.signature_value = String::new();
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize attestation for signing: {}", e)))
+    }
+
+    /// Sign the attestation with the issuer's key
+    pub fn sign(mut self, keypair: &KeyPair) -> Result<Self, GovernanceError> {
+        let signature = keypair.sign(Sha256::digest(self.signing_bytes()?).as_slice())
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to sign attestation: {}", e)))?;
+        self.proof.signature_value = BASE64.encode(signature);
+        Ok(self)
+    }
+
+    /// Check the signature against the issuer's DID
+    pub fn verify(&self) -> Result<bool, GovernanceError> {
+        let signature = BASE64.decode(&self.proof.signature_value)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Invalid attestation signature encoding: {}", e)))?;
+        Ok(icn_identity::verify_signature(&self.signing_bytes()?, &Signature(signature), &self.issuer).unwrap_or(false))
+    }
+
+    /// CID of the signed attestation
+    pub fn cid(&self) -> Result<String, GovernanceError> {
+        let bytes = serde_json::to_vec(self)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize attestation: {}", e)))?;
+        Ok(content_cid(&bytes))
+    }
+}
+
+fn attestations_key(proposal_id: &str) -> String {
+    format!("proposal::attestations::{}", proposal_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Attach a signed attestation to a proposal before its vote closes. Returns the
+    /// attestation's CID.
+    pub async fn attach_attestation(&self, attestation: ProposalAttestation) -> Result<String, GovernanceError> {
+        let proposal = self.get_proposal(attestation.proposal_id.clone()).await?;
+        if proposal.status != ProposalStatus::Active && proposal.status != ProposalStatus::Draft {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot attach attestations to a proposal with status {:?}", proposal.status
+            )));
+        }
+        if cid::Cid::try_from(attestation.document_cid.as_str()).is_err() {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Invalid attestation document CID: {}", attestation.document_cid
+            )));
+        }
+        if attestation.valid_until.map_or(false, |until| until <= chrono::Utc::now().timestamp()) {
+            return Err(GovernanceError::InvalidProposal("Attestation has already lapsed".to_string()));
+        }
+        if !attestation.verify()? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Attestation is not signed by its issuer {}", attestation.issuer.0
+            )));
+        }
+
+        let mut attestations = self.get_proposal_attestations(&attestation.proposal_id).await?;
+        if attestations.iter().any(|a| a.issuer == attestation.issuer && a.document_cid == attestation.document_cid) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "{} has already attached document {} to proposal {}",
+                attestation.issuer.0, attestation.document_cid, attestation.proposal_id
+            )));
+        }
+        let attestation_cid = attestation.cid()?;
+        attestations.push(attestation.clone());
+        let bytes = serde_json::to_vec(&attestations)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize attestations: {}", e)))?;
+        self.store_record(&attestations_key(&attestation.proposal_id), bytes).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::AttestationAttached,
+            attestation.issuer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(attestation.proposal_id.clone()),
+            serde_json::json!({
+                "attestation_cid": attestation_cid,
+                "kind": attestation.kind,
+                "document_cid": attestation.document_cid,
+                "claims": attestation.claims
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(attestation_cid)
+    }
+
+    /// Attestations attached to a proposal, in the order they were attached
+    pub async fn get_proposal_attestations(&self, proposal_id: &str) -> Result<Vec<ProposalAttestation>, GovernanceError> {
+        Ok(self.load_record(&attestations_key(proposal_id), "attestations").await?
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attestation_signature_covers_contents() {
+        let keypair = KeyPair::generate_random();
+        let claims = BTreeMap::from([("complies_with".to_string(), "bylaws article 4".to_string())]);
+        let attestation = ProposalAttestation::new(
+            "proposal:bylaws",
+            IdentityId("did:icn:counsel".to_string()),
+            AttestationKind::LegalReview,
+            &content_cid(b"opinion"),
+            claims,
+            None,
+        ).sign(&keypair).unwrap();
+        assert!(attestation.verify().unwrap());
+
+        // Re-signing yields the same signature, so it covers only the contents
+        let resigned = attestation.clone().sign(&keypair).unwrap();
+        assert_eq!(resigned.proof.signature_value, attestation.proof.signature_value);
+
+        let mut tampered = attestation.clone();
+        tampered.claims.insert("complies_with".to_string(), "nothing".to_string());
+        assert_ne!(tampered.clone().sign(&keypair).unwrap().proof.signature_value, attestation.proof.signature_value);
+        assert_ne!(tampered.cid().unwrap(), attestation.cid().unwrap());
+    }
+}
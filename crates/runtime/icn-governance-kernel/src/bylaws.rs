@@ -0,0 +1,419 @@
+/*!
+# Bylaws Sections
+
+A scope's bylaws are tracked section by section rather than as one config. Each
+top-level section of the CCL config (identity, governance, membership and so on) is
+anchored under the CID of its own content and has its own revision chain, every
+revision linking to the CID it replaced.
+
+An amendment targets one section and names the revision it was drafted against. It's
+attached to a proposal so voters can see the section-level diff they're deciding on.
+Once the proposal has passed and been finalized, applying it writes the section into
+the scope's config and appends a revision recording who amended it, under which
+proposal and when. If the section was amended by something else in the meantime the
+amendment no longer applies and has to be redrafted against the new revision.
+
+A section's history answers who changed it and when, back to the revision it was
+first anchored at. The scope of a federation keeps the federation's own history.
+*/
+
+use std::collections::BTreeSet;
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, ProposalStatus};
+use crate::config::GovernanceConfig;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::outcomes::content_cid;
+
+/// A top-level section of a scope's bylaws
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BylawsSection {
+    Identity,
+    Governance,
+    Membership,
+    Proposals,
+    WorkingGroups,
+    DisputeResolution,
+    EconomicModel,
+}
+
+impl BylawsSection {
+    /// Every section, in config order
+    pub const ALL: [BylawsSection; 7] = [
+        BylawsSection::Identity,
+        BylawsSection::Governance,
+        BylawsSection::Membership,
+        BylawsSection::Proposals,
+        BylawsSection::WorkingGroups,
+        BylawsSection::DisputeResolution,
+        BylawsSection::EconomicModel,
+    ];
+
+    /// The section's field in the CCL config
+    pub fn field(&self) -> &'static str {
+        match self {
+            BylawsSection::Identity => "identity",
+            BylawsSection::Governance => "governance",
+            BylawsSection::Membership => "membership",
+            BylawsSection::Proposals => "proposals",
+            BylawsSection::WorkingGroups => "working_groups",
+            BylawsSection::DisputeResolution => "dispute_resolution",
+            BylawsSection::EconomicModel => "economic_model",
+        }
+    }
+
+    /// The section's content in a config, or None if the config doesn't have it
+    pub fn content(&self, config: &GovernanceConfig) -> Result<Option<JsonValue>, GovernanceError> {
+        let value = serde_json::to_value(config)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize governance config: {}", e)))?;
+        Ok(value.get(self.field()).filter(|v| !v.is_null()).cloned())
+    }
+
+    /// A copy of `config` with the section replaced by `content`
+    pub fn replace(&self, config: &GovernanceConfig, content: JsonValue) -> Result<GovernanceConfig, GovernanceError> {
+        let mut value = serde_json::to_value(config)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize governance config: {}", e)))?;
+        value[self.field()] = content;
+        serde_json::from_value(value)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Amended {} section is not valid: {}", self.field(), e)))
+    }
+}
+
+/// CID a section's content is anchored under
+pub fn section_cid(content: &JsonValue) -> Result<String, GovernanceError> {
+    let bytes = serde_json::to_vec(content)
+        .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize bylaws section: {}", e)))?;
+    Ok(content_cid(&bytes))
+}
+
+/// One revision in a section's chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SectionRevision {
+    pub scope_id: String,
+    pub section: BylawsSection,
+    /// Position in the chain, starting at 1
+    pub revision: u64,
+    /// CID of the section's content after this revision
+    pub cid: String,
+    /// CID of the content it replaced; None for the revision the section was first anchored at
+    pub previous_cid: Option<String>,
+    pub amended_by: IdentityId,
+    /// The proposal that adopted the revision, if any
+    pub proposal_id: Option<String>,
+    /// When the revision was made (Unix timestamp)
+    pub timestamp: i64,
+}
+
+/// A change to one field of a section
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    /// Dotted path of the field within the section
+    pub path: String,
+    /// Value before the amendment; None if the field is added
+    pub before: Option<JsonValue>,
+    /// Value after the amendment; None if the field is removed
+    pub after: Option<JsonValue>,
+}
+
+/// What an amendment changes in a section, field by field
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SectionDiff {
+    pub section: BylawsSection,
+    pub from_cid: Option<String>,
+    pub to_cid: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Every field that differs between two versions of a section. Objects are compared
+/// field by field; any other value, including arrays, is compared whole.
+pub fn diff_section(before: Option<&JsonValue>, after: &JsonValue) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_values("", before, Some(after), &mut changes);
+    changes
+}
+
+fn diff_values(path: &str, before: Option<&JsonValue>, after: Option<&JsonValue>, changes: &mut Vec<FieldChange>) {
+    let before = before.filter(|v| !v.is_null());
+    let after = after.filter(|v| !v.is_null());
+    match (before, after) {
+        (Some(JsonValue::Object(old)), Some(JsonValue::Object(new))) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                diff_values(&child, old.get(key), new.get(key), changes);
+            }
+        }
+        (before, after) if before != after => changes.push(FieldChange {
+            path: path.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// A proposed new version of one section
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SectionAmendment {
+    pub scope_id: String,
+    pub section: BylawsSection,
+    /// CID of the revision the amendment was drafted against; None if the section
+    /// hasn't been anchored yet
+    pub base_cid: Option<String>,
+    pub content: JsonValue,
+}
+
+fn history_key(scope_id: &str, section: BylawsSection) -> String {
+    format!("bylaws::section_history::{}::{}", scope_id, section.field())
+}
+
+fn content_key(cid: &str) -> String {
+    format!("bylaws::section_content::{}", cid)
+}
+
+fn amendment_key(proposal_id: &str) -> String {
+    format!("bylaws::amendment::{}", proposal_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Every revision of a section, oldest first
+    pub async fn get_section_history(&self, scope_id: &str, section: BylawsSection) -> Result<Vec<SectionRevision>, GovernanceError> {
+        Ok(self.load_record(&history_key(scope_id, section), "bylaws section history").await?.unwrap_or_default())
+    }
+
+    /// The section's current revision, if it has been anchored
+    pub async fn get_section_head(&self, scope_id: &str, section: BylawsSection) -> Result<Option<SectionRevision>, GovernanceError> {
+        Ok(self.get_section_history(scope_id, section).await?.pop())
+    }
+
+    /// The content of a section revision by CID
+    pub async fn get_section_content(&self, cid: &str) -> Result<Option<JsonValue>, GovernanceError> {
+        self.load_record(&content_key(cid), "bylaws section content").await
+    }
+
+    /// Anchor `content` and append a revision for it to the section's chain
+    async fn append_section_revision(
+        &self,
+        scope_id: &str,
+        section: BylawsSection,
+        content: &JsonValue,
+        amended_by: &IdentityId,
+        proposal_id: Option<String>,
+    ) -> Result<SectionRevision, GovernanceError> {
+        let cid = section_cid(content)?;
+        let bytes = serde_json::to_vec(content)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize bylaws section: {}", e)))?;
+        self.store_record(&content_key(&cid), bytes).await?;
+
+        let mut history = self.get_section_history(scope_id, section).await?;
+        let revision = SectionRevision {
+            scope_id: scope_id.to_string(),
+            section,
+            revision: history.len() as u64 + 1,
+            cid,
+            previous_cid: history.last().map(|r| r.cid.clone()),
+            amended_by: amended_by.clone(),
+            proposal_id,
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        history.push(revision.clone());
+        let history_bytes = serde_json::to_vec(&history)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize bylaws section history: {}", e)))?;
+        self.store_record(&history_key(scope_id, section), history_bytes).await?;
+        Ok(revision)
+    }
+
+    /// Anchor every section of the scope's current config whose content differs from its
+    /// head revision, so later amendments have something to build on. Needs the
+    /// `manage_bylaws` permission. Returns the revisions appended.
+    pub async fn anchor_bylaws_sections(&self, caller: &IdentityId, scope_id: &str) -> Result<Vec<SectionRevision>, GovernanceError> {
+        if !self.check_permission(caller, scope_id, "manage_bylaws").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} lacks permission to anchor bylaws in scope {}", caller.0, scope_id
+            )));
+        }
+        let config = self.load_governance_config(scope_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Scope {} has no governance config", scope_id)))?;
+
+        let mut anchored = Vec::new();
+        for section in BylawsSection::ALL {
+            let Some(content) = section.content(&config)? else { continue };
+            let head = self.get_section_head(scope_id, section).await?;
+            if head.map(|r| r.cid) == Some(section_cid(&content)?) {
+                continue;
+            }
+            anchored.push(self.append_section_revision(scope_id, section, &content, caller, None).await?);
+        }
+        Ok(anchored)
+    }
+
+    /// The diff an amendment makes against the section's current revision
+    pub async fn diff_section_amendment(&self, amendment: &SectionAmendment) -> Result<SectionDiff, GovernanceError> {
+        let head = self.get_section_head(&amendment.scope_id, amendment.section).await?;
+        let current = match &head {
+            Some(head) => Some(self.get_section_content(&head.cid).await?
+                .ok_or_else(|| GovernanceError::StorageError(format!("Bylaws section content {} is missing", head.cid)))?),
+            None => None,
+        };
+
+        Ok(SectionDiff {
+            section: amendment.section,
+            from_cid: head.map(|r| r.cid),
+            to_cid: section_cid(&amendment.content)?,
+            changes: diff_section(current.as_ref(), &amendment.content),
+        })
+    }
+
+    /// Check an amendment was drafted against the section's current revision
+    async fn check_amendment_base(&self, amendment: &SectionAmendment) -> Result<(), GovernanceError> {
+        let head = self.get_section_head(&amendment.scope_id, amendment.section).await?.map(|r| r.cid);
+        if head != amendment.base_cid {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Amendment to the {} section was drafted against {:?} but the section is now at {:?}",
+                amendment.section.field(), amendment.base_cid, head
+            )));
+        }
+        Ok(())
+    }
+
+    /// Attach a section amendment to a proposal in the same scope that is still a
+    /// draft or open for voting. Returns the diff voters will see.
+    pub async fn attach_section_amendment(&self, proposal_id: &str, amendment: SectionAmendment) -> Result<SectionDiff, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if !matches!(proposal.status, ProposalStatus::Draft | ProposalStatus::Active) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot attach an amendment to proposal with status {:?}", proposal.status
+            )));
+        }
+        if proposal.scope_id.as_ref().map(|sid| sid.0.as_str()) != Some(amendment.scope_id.as_str()) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} is not in scope {}", proposal_id, amendment.scope_id
+            )));
+        }
+        self.check_amendment_base(&amendment).await?;
+        let config = self.load_governance_config(&amendment.scope_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Scope {} has no governance config", amendment.scope_id)))?;
+        amendment.section.replace(&config, amendment.content.clone())?;
+
+        let diff = self.diff_section_amendment(&amendment).await?;
+        if diff.changes.is_empty() {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Amendment doesn't change the {} section", amendment.section.field()
+            )));
+        }
+        let bytes = serde_json::to_vec(&amendment)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize section amendment: {}", e)))?;
+        self.store_record(&amendment_key(proposal_id), bytes).await?;
+        Ok(diff)
+    }
+
+    /// The section amendment attached to a proposal, if any
+    pub async fn get_section_amendment(&self, proposal_id: &str) -> Result<Option<SectionAmendment>, GovernanceError> {
+        self.load_record(&amendment_key(proposal_id), "section amendment").await
+    }
+
+    /// Write a passed proposal's section amendment into the scope's config and append
+    /// the revision to the section's chain
+    pub async fn apply_section_amendment(&self, proposal_id: &str) -> Result<SectionRevision, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if !self.proposal_has_passed(proposal_id, &proposal).await? {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} has not passed, so its amendment can't be applied", proposal_id
+            )));
+        }
+        let amendment = self.get_section_amendment(proposal_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Proposal {} has no section amendment", proposal_id)))?;
+        if self.get_section_history(&amendment.scope_id, amendment.section).await?
+            .iter()
+            .any(|r| r.proposal_id.as_deref() == Some(proposal_id))
+        {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "The amendment of proposal {} has already been applied", proposal_id
+            )));
+        }
+        self.check_amendment_base(&amendment).await?;
+
+        let config = self.load_governance_config(&amendment.scope_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Scope {} has no governance config", amendment.scope_id)))?;
+        let amended = amendment.section.replace(&config, amendment.content.clone())?;
+        self.store_governance_config(&amendment.scope_id, amended).await?;
+
+        let revision = self.append_section_revision(
+            &amendment.scope_id, amendment.section, &amendment.content, &proposal.proposer, Some(proposal_id.to_string()),
+        ).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::BylawsSectionAmended,
+            proposal.proposer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "section": amendment.section.field(),
+                "revision": revision.revision,
+                "cid": revision.cid,
+                "previous_cid": revision.previous_cid
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::IdentityScope;
+
+    fn config() -> GovernanceConfig {
+        serde_json::from_value(serde_json::json!({
+            "template_type": "coop_bylaws",
+            "template_version": "v1",
+            "governing_scope": IdentityScope::Cooperative,
+            "identity": { "name": "Bakers Co-op", "description": null, "founding_date": null, "mission_statement": null },
+            "governance": null,
+            "membership": null,
+            "proposals": null,
+            "working_groups": null,
+            "dispute_resolution": null,
+            "economic_model": null
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_sections_are_read_replaced_and_diffed_independently() {
+        let config = config();
+        let identity = BylawsSection::Identity.content(&config).unwrap().unwrap();
+        assert_eq!(BylawsSection::Membership.content(&config).unwrap(), None);
+
+        let mut renamed = identity.clone();
+        renamed["name"] = JsonValue::from("Bakers Cooperative");
+        renamed["mission_statement"] = JsonValue::from("Bread for all");
+        let amended = BylawsSection::Identity.replace(&config, renamed.clone()).unwrap();
+        assert_eq!(amended.identity.unwrap().name.as_deref(), Some("Bakers Cooperative"));
+        assert_ne!(section_cid(&identity).unwrap(), section_cid(&renamed).unwrap());
+
+        // Only the fields that changed show up, with their old and new values
+        let changes = diff_section(Some(&identity), &renamed);
+        assert_eq!(changes, vec![
+            FieldChange {
+                path: "mission_statement".to_string(),
+                before: None,
+                after: Some(JsonValue::from("Bread for all")),
+            },
+            FieldChange {
+                path: "name".to_string(),
+                before: Some(JsonValue::from("Bakers Co-op")),
+                after: Some(JsonValue::from("Bakers Cooperative")),
+            },
+        ]);
+
+        // A section amended into a shape the config can't hold is refused
+        assert!(BylawsSection::Identity.replace(&config, JsonValue::from(42)).is_err());
+    }
+}
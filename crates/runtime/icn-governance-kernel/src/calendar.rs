@@ -0,0 +1,433 @@
+/*!
+# Governance Calendar
+
+Bylaws fix when recurring governance happens: the AGM every June, budget season each
+autumn, elections every other year. A scope declares these events under
+`governance.calendar`, along with the deadlines that hang off them:
+
+```text
+calendar: [
+  {
+    name: "AGM",
+    recurrence: "yearly",
+    starts_on: "2025-06-15",
+    deadlines: [
+      { name: "Notice of meeting", days_before: 21 },
+      { name: "Member proposals due", days_before: 14 }
+    ],
+    standing_proposal: { title: "Approve annual accounts", proposal_type: "financial", days_before: 14 }
+  }
+]
+```
+
+Recurrence is `yearly`, `quarterly`, `monthly` or `every N days`, counted from
+`starts_on`. Each deadline falls `days_before` days ahead of every occurrence.
+
+The kernel derives the calendar from the scope's configuration. A scheduler calls
+`announce_calendar_deadlines` periodically to emit an event for each deadline coming
+up within its horizon, and `open_standing_proposals` to submit the standing proposal
+of any occurrence whose opening day has come; voting on it closes when the event
+starts. Both remember what they've done, so calling them again is harmless. The
+calendar can be exported in iCalendar format for members' own calendar tools.
+*/
+
+use chrono::{Duration, Months, NaiveDate, NaiveDateTime};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::config::{CalendarDeadlineConfig, CalendarEventConfig, GovernanceConfig, StandingProposalConfig};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Metadata field naming the calendar event a standing proposal was opened for
+pub const CALENDAR_EVENT_METADATA_KEY: &str = "calendar_event";
+
+/// Metadata field holding the occurrence date a standing proposal was opened for
+pub const CALENDAR_OCCURRENCE_METADATA_KEY: &str = "calendar_occurrence";
+
+/// Upper bound on the occurrences of one event considered in a single query
+const MAX_OCCURRENCES: u32 = 10_000;
+
+/// How often an event recurs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    Months(u32),
+    Days(u32),
+}
+
+impl Recurrence {
+    /// Parse `yearly`, `quarterly`, `monthly` or `every N days`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "yearly" | "annually" => Ok(Recurrence::Months(12)),
+            "quarterly" => Ok(Recurrence::Months(3)),
+            "monthly" => Ok(Recurrence::Months(1)),
+            other => {
+                let days = other.strip_prefix("every ")
+                    .and_then(|rest| rest.strip_suffix(" days").or_else(|| rest.strip_suffix(" day")))
+                    .and_then(|n| n.trim().parse::<u32>().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("Unknown recurrence '{}'", other))?;
+                Ok(Recurrence::Days(days))
+            }
+        }
+    }
+}
+
+/// A recurring event on a scope's calendar
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub name: String,
+    pub recurrence: Recurrence,
+    pub starts_on: NaiveDate,
+    pub deadlines: Vec<CalendarDeadlineConfig>,
+    pub standing_proposal: Option<StandingProposalConfig>,
+}
+
+impl CalendarEvent {
+    pub fn from_config(config: &CalendarEventConfig) -> Result<Self, String> {
+        let recurrence = Recurrence::parse(&config.recurrence)?;
+        let starts_on = NaiveDate::parse_from_str(&config.starts_on, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid start date '{}': {}", config.starts_on, e))?;
+        Ok(CalendarEvent {
+            name: config.name.clone(),
+            recurrence,
+            starts_on,
+            deadlines: config.deadlines.clone().unwrap_or_default(),
+            standing_proposal: config.standing_proposal.clone(),
+        })
+    }
+
+    /// The `n`th occurrence, counting the first as 0
+    pub fn occurrence(&self, n: u32) -> Option<NaiveDate> {
+        match self.recurrence {
+            // Counted from the start date so a 31st doesn't drift after a short month
+            Recurrence::Months(months) => self.starts_on.checked_add_months(Months::new(months.checked_mul(n)?)),
+            Recurrence::Days(days) => self.starts_on.checked_add_signed(Duration::days(days as i64 * n as i64)),
+        }
+    }
+
+    /// Occurrences on or after `from` and before `to`
+    pub fn occurrences_between(&self, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        (0..MAX_OCCURRENCES)
+            .map_while(|n| self.occurrence(n))
+            .skip_while(|date| *date < from)
+            .take_while(|date| *date < to)
+            .collect()
+    }
+}
+
+/// A deadline ahead of one occurrence of an event
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarDeadline {
+    pub event: String,
+    pub name: String,
+    pub occurrence: NaiveDate,
+    pub due: NaiveDate,
+}
+
+/// The recurring events of a scope
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GovernanceCalendar {
+    pub events: Vec<CalendarEvent>,
+}
+
+impl GovernanceCalendar {
+    pub fn from_config(config: &GovernanceConfig) -> Result<Self, String> {
+        let events = config.governance.as_ref()
+            .and_then(|governance| governance.calendar.as_ref())
+            .map(|events| events.iter()
+                .map(|event| CalendarEvent::from_config(event)
+                    .map_err(|e| format!("Calendar event {}: {}", event.name, e)))
+                .collect::<Result<Vec<_>, _>>())
+            .transpose()?
+            .unwrap_or_default();
+        Ok(GovernanceCalendar { events })
+    }
+
+    /// Deadlines due on or after `from` and before `to`, earliest first
+    pub fn deadlines_between(&self, from: NaiveDate, to: NaiveDate) -> Vec<CalendarDeadline> {
+        let mut deadlines = Vec::new();
+        for event in &self.events {
+            for deadline in &event.deadlines {
+                let lead = Duration::days(deadline.days_before as i64);
+                for occurrence in event.occurrences_between(from + lead, to + lead) {
+                    deadlines.push(CalendarDeadline {
+                        event: event.name.clone(),
+                        name: deadline.name.clone(),
+                        occurrence,
+                        due: occurrence - lead,
+                    });
+                }
+            }
+        }
+        deadlines.sort_by(|a, b| a.due.cmp(&b.due).then_with(|| a.event.cmp(&b.event)));
+        deadlines
+    }
+
+    /// Occurrences whose standing proposal should be open on `today`: the opening day
+    /// has come and the event hasn't started yet
+    pub fn standing_proposals_due(&self, today: NaiveDate) -> Vec<(&CalendarEvent, &StandingProposalConfig, NaiveDate)> {
+        self.events.iter()
+            .filter_map(|event| event.standing_proposal.as_ref().map(|standing| (event, standing)))
+            .flat_map(|(event, standing)| {
+                let lead = Duration::days(standing.days_before as i64);
+                event.occurrences_between(today + Duration::days(1), today + lead + Duration::days(1))
+                    .into_iter()
+                    .map(move |occurrence| (event, standing, occurrence))
+            })
+            .collect()
+    }
+
+    /// Export occurrences, deadlines and standing proposal openings in `[from, to)` as
+    /// an iCalendar document
+    pub fn to_ical(&self, scope_id: &str, from: NaiveDate, to: NaiveDate, generated_at: NaiveDateTime) -> String {
+        let stamp = generated_at.format("%Y%m%dT%H%M%SZ").to_string();
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//ICN//Governance Calendar//EN".to_string(),
+            format!("X-WR-CALNAME:{}", ical_escape(scope_id)),
+        ];
+        let mut push_event = |uid: String, date: NaiveDate, summary: String| {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}@{}", ical_escape(&uid), ical_escape(scope_id)));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")));
+            lines.push(format!("SUMMARY:{}", ical_escape(&summary)));
+            lines.push("END:VEVENT".to_string());
+        };
+
+        for event in &self.events {
+            for occurrence in event.occurrences_between(from, to) {
+                push_event(format!("{}-{}", event.name, occurrence), occurrence, event.name.clone());
+            }
+            if let Some(standing) = &event.standing_proposal {
+                let lead = Duration::days(standing.days_before as i64);
+                for occurrence in event.occurrences_between(from + lead, to + lead) {
+                    push_event(
+                        format!("{}-{}-standing-proposal", event.name, occurrence),
+                        occurrence - lead,
+                        format!("{}: voting opens on {}", event.name, standing.title),
+                    );
+                }
+            }
+        }
+        for deadline in self.deadlines_between(from, to) {
+            push_event(
+                format!("{}-{}-{}", deadline.event, deadline.occurrence, deadline.name),
+                deadline.due,
+                format!("{}: {}", deadline.event, deadline.name),
+            );
+        }
+
+        lines.push("END:VCALENDAR".to_string());
+        lines.join("\r\n") + "\r\n"
+    }
+}
+
+/// Escape text for an iCalendar property value
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn announced_key(scope_id: &str, deadline: &CalendarDeadline) -> String {
+    format!("calendar::announced::{}::{}::{}::{}", scope_id, deadline.event, deadline.occurrence, deadline.name)
+}
+
+fn standing_proposal_key(scope_id: &str, event: &str, occurrence: NaiveDate) -> String {
+    format!("calendar::standing::{}::{}::{}", scope_id, event, occurrence)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The recurring events declared in a scope's configuration
+    pub async fn get_governance_calendar(&self, scope_id: &str) -> Result<GovernanceCalendar, GovernanceError> {
+        match self.load_governance_config(scope_id).await? {
+            Some(config) => GovernanceCalendar::from_config(&config)
+                .map_err(|e| GovernanceError::InvalidProposal(format!(
+                    "Invalid governance calendar for scope {}: {}", scope_id, e
+                ))),
+            None => Ok(GovernanceCalendar::default()),
+        }
+    }
+
+    /// Emit an event for each deadline due within `horizon_days` of `today` that hasn't
+    /// been announced yet, returning the deadlines announced
+    pub async fn announce_calendar_deadlines(
+        &self,
+        scope_id: &str,
+        issuer: &IdentityId,
+        today: NaiveDate,
+        horizon_days: u32,
+    ) -> Result<Vec<CalendarDeadline>, GovernanceError> {
+        let calendar = self.get_governance_calendar(scope_id).await?;
+        let scope = self.load_governance_config(scope_id).await?
+            .map(|config| config.governing_scope)
+            .unwrap_or(icn_identity::IdentityScope::Federation);
+
+        let mut announced = Vec::new();
+        for deadline in calendar.deadlines_between(today, today + Duration::days(horizon_days as i64)) {
+            let key = announced_key(scope_id, &deadline);
+            if self.load_record::<bool>(&key, "calendar announcement").await?.is_some() {
+                continue;
+            }
+
+            let event = GovernanceEvent::new(
+                GovernanceEventType::CalendarDeadlineUpcoming,
+                issuer.clone(),
+                scope,
+                Some(IdentityId(scope_id.to_string())),
+                None,
+                serde_json::json!({
+                    "event": deadline.event,
+                    "deadline": deadline.name,
+                    "occurrence": deadline.occurrence.to_string(),
+                    "due": deadline.due.to_string(),
+                    "days_remaining": (deadline.due - today).num_days()
+                })
+            );
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+            let marker = serde_json::to_vec(&true)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize calendar announcement: {}", e)))?;
+            self.store_record(&key, marker).await?;
+            announced.push(deadline);
+        }
+        Ok(announced)
+    }
+
+    /// Submit the standing proposal of every occurrence whose opening day has come,
+    /// unless it was already submitted. `proposer` needs `create_proposals` in the
+    /// scope. Returns the IDs of the proposals submitted.
+    pub async fn open_standing_proposals(
+        &self,
+        scope_id: &str,
+        proposer: &IdentityId,
+        today: NaiveDate,
+    ) -> Result<Vec<String>, GovernanceError> {
+        let calendar = self.get_governance_calendar(scope_id).await?;
+        let scope = self.load_governance_config(scope_id).await?
+            .map(|config| config.governing_scope)
+            .unwrap_or(icn_identity::IdentityScope::Federation);
+
+        let mut opened = Vec::new();
+        for (event, standing, occurrence) in calendar.standing_proposals_due(today) {
+            let key = standing_proposal_key(scope_id, &event.name, occurrence);
+            if self.load_record::<String>(&key, "standing proposal").await?.is_some() {
+                continue;
+            }
+
+            let proposal = standing_proposal(scope, scope_id, proposer, event, standing, occurrence);
+            let proposal_id = self.process_proposal(proposal).await?;
+
+            let record = serde_json::to_vec(&proposal_id)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize standing proposal: {}", e)))?;
+            self.store_record(&key, record).await?;
+            opened.push(proposal_id);
+        }
+        Ok(opened)
+    }
+
+    /// The scope's calendar between `from` and `to` as an iCalendar document
+    pub async fn export_calendar_ical(&self, scope_id: &str, from: NaiveDate, to: NaiveDate) -> Result<String, GovernanceError> {
+        let calendar = self.get_governance_calendar(scope_id).await?;
+        Ok(calendar.to_ical(scope_id, from, to, chrono::Utc::now().naive_utc()))
+    }
+}
+
+/// The proposal put to members ahead of one occurrence of an event
+pub fn standing_proposal(
+    scope: icn_identity::IdentityScope,
+    scope_id: &str,
+    proposer: &IdentityId,
+    event: &CalendarEvent,
+    standing: &StandingProposalConfig,
+    occurrence: NaiveDate,
+) -> Proposal {
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert(CALENDAR_EVENT_METADATA_KEY.to_string(), event.name.clone());
+    metadata.insert(CALENDAR_OCCURRENCE_METADATA_KEY.to_string(), occurrence.to_string());
+
+    Proposal {
+        title: format!("{} ({})", standing.title, occurrence),
+        description: standing.description.clone()
+            .unwrap_or_else(|| format!("Standing proposal for the {} on {}", event.name, occurrence)),
+        proposer: proposer.clone(),
+        scope,
+        scope_id: Some(IdentityId(scope_id.to_string())),
+        status: ProposalStatus::Draft,
+        voting_end_time: occurrence.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp(),
+        votes_for: 0,
+        votes_against: 0,
+        votes_abstain: 0,
+        ccl_code: None,
+        wasm_bytes: None,
+        wasm_cid: None,
+        thread_id: None,
+        metadata,
+        proposal_type: standing.proposal_type.clone(),
+        created_at: 0,
+        effects: Vec::new(),
+        effective_at: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn agm() -> CalendarEvent {
+        CalendarEvent::from_config(&CalendarEventConfig {
+            name: "AGM".to_string(),
+            recurrence: "yearly".to_string(),
+            starts_on: "2025-06-15".to_string(),
+            deadlines: Some(vec![
+                CalendarDeadlineConfig { name: "Notice of meeting".to_string(), days_before: 21 },
+                CalendarDeadlineConfig { name: "Member proposals due".to_string(), days_before: 14 },
+            ]),
+            standing_proposal: Some(StandingProposalConfig {
+                title: "Approve annual accounts".to_string(),
+                description: None,
+                proposal_type: Some("financial".to_string()),
+                days_before: 14,
+            }),
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_calendar_derives_deadlines_and_standing_proposals() {
+        assert_eq!(Recurrence::parse("every 90 days"), Ok(Recurrence::Days(90)));
+        assert!(Recurrence::parse("fortnightly").is_err());
+
+        let calendar = GovernanceCalendar { events: vec![agm()] };
+        assert_eq!(calendar.events[0].occurrences_between(date("2026-01-01"), date("2028-01-01")),
+            vec![date("2026-06-15"), date("2027-06-15")]);
+
+        // Deadlines ahead of the 2026 AGM, earliest first
+        let deadlines = calendar.deadlines_between(date("2026-05-01"), date("2026-06-30"));
+        assert_eq!(deadlines.len(), 2);
+        assert_eq!((deadlines[0].name.as_str(), deadlines[0].due), ("Notice of meeting", date("2026-05-25")));
+        assert_eq!((deadlines[1].name.as_str(), deadlines[1].due), ("Member proposals due", date("2026-06-01")));
+        assert!(deadlines.iter().all(|d| d.occurrence == date("2026-06-15")));
+
+        // The standing proposal is open from its opening day until the AGM starts
+        assert!(calendar.standing_proposals_due(date("2026-05-31")).is_empty());
+        assert_eq!(calendar.standing_proposals_due(date("2026-06-01"))[0].2, date("2026-06-15"));
+        assert_eq!(calendar.standing_proposals_due(date("2026-06-14")).len(), 1);
+        assert!(calendar.standing_proposals_due(date("2026-06-15")).is_empty());
+
+        let ical = calendar.to_ical("coop-1", date("2026-01-01"), date("2027-01-01"), date("2026-01-01").and_hms_opt(0, 0, 0).unwrap());
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert_eq!(ical.matches("BEGIN:VEVENT").count(), 4);
+        assert!(ical.contains("DTSTART;VALUE=DATE:20260615\r\nSUMMARY:AGM\r\n"));
+        assert!(ical.contains("SUMMARY:AGM: Notice of meeting\r\n"));
+    }
+}
@@ -0,0 +1,605 @@
+/*!
+# Conflicts of Interest
+
+Members register their interests (relationships, financial stakes, employment) in a
+scope. When a proposal's title, description or metadata mentions a registered interest,
+the kernel flags the member as conflicted on that proposal and asks them to file a
+declaration. Depending on the scope's settings, a flagged member's vote is either left
+out of the tally automatically, or the member must file an explicit declaration
+(recusing themselves or disclosing the interest) before they can vote at all.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+use uuid::Uuid;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, Vote, VoteChoice};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Kind of interest a member can register
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InterestKind {
+    /// Family or personal relationship
+    Relationship,
+    /// Ownership, investment or other financial stake
+    FinancialStake,
+    /// Employment or paid engagement
+    Employment,
+    /// Any other interest
+    Other,
+}
+
+/// An interest registered by a member
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RegisteredInterest {
+    /// Interest ID, assigned on registration
+    #[serde(default)]
+    pub id: String,
+    /// The member holding the interest
+    pub member: IdentityId,
+    /// The scope the interest is registered in
+    pub scope_id: String,
+    /// Kind of interest
+    pub kind: InterestKind,
+    /// The party the interest is in (a person, vendor or organization)
+    pub subject: String,
+    /// Further terms that identify the subject in proposals (DIDs, trading names)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Free-text description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When the interest was registered (Unix timestamp)
+    #[serde(default)]
+    pub registered_at: i64,
+}
+
+impl RegisteredInterest {
+    /// Terms matched against proposals
+    pub fn terms(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.subject).chain(self.keywords.iter())
+            .filter(|term| !term.trim().is_empty())
+    }
+}
+
+/// How flagged conflicts are enforced
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecusalMode {
+    /// Votes by flagged members are recorded but left out of the tally
+    ExcludeVote,
+    /// Flagged members must file a declaration before voting; recused members can't vote
+    RequireRecusal,
+}
+
+impl Default for RecusalMode {
+    fn default() -> Self {
+        RecusalMode::ExcludeVote
+    }
+}
+
+/// Per-scope conflict-of-interest settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ConflictOfInterestSettings {
+    /// Whether conflicts are enforced in this scope
+    pub enabled: bool,
+    /// How flagged conflicts are enforced
+    #[serde(default)]
+    pub mode: RecusalMode,
+}
+
+/// Where a proposal mentioned a registered interest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InterestMatch {
+    /// The matching interest
+    pub interest_id: String,
+    /// The term that matched
+    pub term: String,
+    /// The proposal field that matched (`title`, `description` or `metadata.<key>`)
+    pub field: String,
+}
+
+/// A member's declaration on a proposal they are conflicted on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConflictDeclaration {
+    /// The proposal
+    pub proposal_id: String,
+    /// The declaring member
+    pub member: IdentityId,
+    /// Whether the member recuses themselves from the vote
+    pub recuse: bool,
+    /// Statement describing the interest
+    pub statement: String,
+    /// When the declaration was filed (Unix timestamp)
+    pub declared_at: i64,
+}
+
+/// A member flagged as conflicted on a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConflictFlag {
+    /// The proposal
+    pub proposal_id: String,
+    /// The conflicted member
+    pub member: IdentityId,
+    /// Interests the proposal matched; empty for self-declared conflicts
+    pub matches: Vec<InterestMatch>,
+    /// The member's declaration, once filed
+    pub declaration: Option<ConflictDeclaration>,
+}
+
+impl ConflictFlag {
+    /// Whether the member has recused themselves
+    pub fn is_recused(&self) -> bool {
+        self.declaration.as_ref().map(|d| d.recuse).unwrap_or(false)
+    }
+}
+
+/// Vote tally with conflicted votes left out
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ConflictAwareTally {
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub votes_abstain: u64,
+    /// Voters whose votes were left out because of a conflict
+    pub excluded: Vec<IdentityId>,
+}
+
+/// Whether a voter's vote is left out of the tally under the given settings and flags
+pub fn is_vote_excluded(settings: &ConflictOfInterestSettings, flags: &[ConflictFlag], voter: &IdentityId) -> bool {
+    let flag = flags.iter().find(|f| &f.member == voter);
+    settings.enabled && match settings.mode {
+        RecusalMode::ExcludeVote => flag.is_some(),
+        RecusalMode::RequireRecusal => flag.map(|f| f.is_recused() || f.declaration.is_none()).unwrap_or(false),
+    }
+}
+
+/// Find every place a proposal mentions one of the given interests
+pub fn match_interests(proposal: &Proposal, interests: &[RegisteredInterest]) -> Vec<(IdentityId, InterestMatch)> {
+    let mut fields = vec![
+        ("title".to_string(), proposal.title.to_lowercase()),
+        ("description".to_string(), proposal.description.to_lowercase()),
+    ];
+    let mut metadata: Vec<_> = proposal.metadata.iter().collect();
+    metadata.sort();
+    for (key, value) in metadata {
+        fields.push((format!("metadata.{}", key), value.to_lowercase()));
+    }
+
+    let mut matches = Vec::new();
+    for interest in interests {
+        for term in interest.terms() {
+            let needle = term.trim().to_lowercase();
+            if let Some((field, _)) = fields.iter().find(|(_, text)| text.contains(&needle)) {
+                matches.push((interest.member.clone(), InterestMatch {
+                    interest_id: interest.id.clone(),
+                    term: term.clone(),
+                    field: field.clone(),
+                }));
+                break;
+            }
+        }
+    }
+    matches
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Store the conflict-of-interest settings for a scope
+    pub async fn store_coi_settings(&self, scope_id: &str, settings: ConflictOfInterestSettings) -> Result<(), GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("coi::config::{}", scope_id))?;
+
+        let settings_bytes = serde_json::to_vec(&settings)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize conflict-of-interest settings: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, settings_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+
+        let event_data = serde_json::json!({
+            "scope_id": scope_id,
+            "config_type": "conflict_of_interest",
+            "enabled": settings.enabled,
+            "mode": settings.mode,
+            "timestamp": chrono::Utc::now().timestamp()
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ConfigUpdated,
+            IdentityId("did:icn:system:governance".to_string()),
+            self.scope_type_for(scope_id).await?,
+            Some(IdentityId(scope_id.to_string())),
+            None,
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// Get the conflict-of-interest settings for a scope (disabled by default)
+    pub async fn get_coi_settings(&self, scope_id: &str) -> Result<ConflictOfInterestSettings, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("coi::config::{}", scope_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize conflict-of-interest settings: {}", e))),
+            Ok(None) => Ok(ConflictOfInterestSettings::default()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load conflict-of-interest settings: {}", e))),
+        }
+    }
+
+    /// Register an interest for the caller. Returns the interest ID.
+    pub async fn register_interest(&self, caller: &IdentityId, mut interest: RegisteredInterest) -> Result<String, GovernanceError> {
+        if caller != &interest.member {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} cannot register interests for {}", caller.0, interest.member.0
+            )));
+        }
+        if interest.subject.trim().is_empty() {
+            return Err(GovernanceError::InvalidProposal("Interest subject must not be empty".to_string()));
+        }
+
+        interest.id = Uuid::new_v4().to_string();
+        interest.registered_at = chrono::Utc::now().timestamp();
+        self.store_interest(&interest).await?;
+        self.append_to_index(&format!("coi::interests::{}", interest.scope_id), &interest.id).await?;
+
+        Ok(interest.id)
+    }
+
+    /// Withdraw one of the caller's registered interests
+    pub async fn withdraw_interest(&self, caller: &IdentityId, scope_id: &str, interest_id: &str) -> Result<(), GovernanceError> {
+        let interest = self.get_interest(interest_id).await?
+            .filter(|i| i.scope_id == scope_id)
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Interest {} not found in scope {}", interest_id, scope_id)))?;
+
+        if caller != &interest.member {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} cannot withdraw interests of {}", caller.0, interest.member.0
+            )));
+        }
+
+        let index_key = format!("coi::interests::{}", scope_id);
+        let mut ids = self.load_index(&index_key).await?;
+        ids.retain(|id| id != interest_id);
+        self.store_index(&index_key, &ids).await
+    }
+
+    /// List the interests registered in a scope
+    pub async fn list_interests(&self, scope_id: &str) -> Result<Vec<RegisteredInterest>, GovernanceError> {
+        let mut interests = Vec::new();
+        for id in self.load_index(&format!("coi::interests::{}", scope_id)).await? {
+            if let Some(interest) = self.get_interest(&id).await? {
+                interests.push(interest);
+            }
+        }
+        Ok(interests)
+    }
+
+    /// Match a proposal against the interests registered in its scope and flag the
+    /// conflicted members. Each newly flagged member is prompted to file a declaration.
+    pub async fn flag_conflicts(&self, proposal_id: &str) -> Result<Vec<ConflictFlag>, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        let interests = self.list_interests(&scope_id).await?;
+        let mut flags = self.get_conflict_flags(proposal_id).await?;
+        let mut newly_flagged = Vec::new();
+
+        for (member, interest_match) in match_interests(&proposal, &interests) {
+            match flags.iter_mut().find(|f| f.member == member) {
+                Some(flag) => {
+                    if !flag.matches.contains(&interest_match) {
+                        flag.matches.push(interest_match);
+                    }
+                }
+                None => {
+                    newly_flagged.push(member.clone());
+                    flags.push(ConflictFlag {
+                        proposal_id: proposal_id.to_string(),
+                        member,
+                        matches: vec![interest_match],
+                        declaration: None,
+                    });
+                }
+            }
+        }
+
+        self.store_conflict_flags(proposal_id, &flags).await?;
+
+        for member in newly_flagged {
+            let flag = flags.iter().find(|f| f.member == member).expect("flag was just added");
+            let event_data = serde_json::json!({
+                "member": member.0,
+                "matches": flag.matches,
+                "declaration_required": true
+            });
+
+            let event = GovernanceEvent::new(
+                GovernanceEventType::ConflictOfInterestFlagged,
+                IdentityId("did:icn:system:governance".to_string()),
+                proposal.scope,
+                proposal.scope_id.clone(),
+                Some(proposal_id.to_string()),
+                event_data
+            );
+
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        }
+
+        Ok(flags)
+    }
+
+    /// File a conflict declaration. Members may also declare conflicts the kernel
+    /// didn't flag.
+    pub async fn declare_conflict(&self, caller: &IdentityId, proposal_id: &str, recuse: bool, statement: String) -> Result<(), GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+
+        if statement.trim().is_empty() {
+            return Err(GovernanceError::InvalidProposal("Declaration statement must not be empty".to_string()));
+        }
+
+        let declaration = ConflictDeclaration {
+            proposal_id: proposal_id.to_string(),
+            member: caller.clone(),
+            recuse,
+            statement,
+            declared_at: chrono::Utc::now().timestamp(),
+        };
+
+        let mut flags = self.get_conflict_flags(proposal_id).await?;
+        match flags.iter_mut().find(|f| &f.member == caller) {
+            Some(flag) => flag.declaration = Some(declaration),
+            None => flags.push(ConflictFlag {
+                proposal_id: proposal_id.to_string(),
+                member: caller.clone(),
+                matches: Vec::new(),
+                declaration: Some(declaration),
+            }),
+        }
+        self.store_conflict_flags(proposal_id, &flags).await?;
+
+        let event_data = serde_json::json!({
+            "member": caller.0,
+            "recuse": recuse
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ConflictDeclared,
+            caller.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// Conflict flags recorded on a proposal
+    pub async fn get_conflict_flags(&self, proposal_id: &str) -> Result<Vec<ConflictFlag>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("coi::flags::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize conflict flags: {}", e))),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load conflict flags: {}", e))),
+        }
+    }
+
+    /// Reject a vote the scope's conflict settings don't allow
+    pub(crate) async fn check_vote_conflicts(&self, vote: &Vote, scope_id: &str) -> Result<(), GovernanceError> {
+        let settings = self.get_coi_settings(scope_id).await?;
+        if !settings.enabled || settings.mode != RecusalMode::RequireRecusal {
+            return Ok(());
+        }
+
+        let flags = self.get_conflict_flags(&vote.proposal_id).await?;
+        match flags.iter().find(|f| f.member == vote.voter) {
+            Some(flag) if flag.is_recused() => Err(GovernanceError::Unauthorized(format!(
+                "Identity {} has recused themselves from proposal {}", vote.voter.0, vote.proposal_id
+            ))),
+            Some(flag) if flag.declaration.is_none() => Err(GovernanceError::Unauthorized(format!(
+                "Identity {} must file a conflict-of-interest declaration before voting on proposal {}",
+                vote.voter.0, vote.proposal_id
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tally the recorded votes on a proposal, leaving out conflicted votes
+    pub async fn conflict_aware_tally(&self, proposal_id: &str) -> Result<ConflictAwareTally, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let settings = match &proposal.scope_id {
+            Some(sid) => self.get_coi_settings(&sid.0).await?,
+            None => ConflictOfInterestSettings::default(),
+        };
+        let flags = self.get_conflict_flags(proposal_id).await?;
+
+        let mut tally = ConflictAwareTally::default();
+        for voter in self.load_index(&format!("proposal::voters::{}", proposal_id)).await? {
+            let key_cid = self.create_key_cid(&format!("vote::{}::{}", proposal_id, voter))?;
+            let storage = self.storage.lock().await;
+            let bytes = match storage.get_kv(&key_cid).await {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => continue,
+                Err(e) => return Err(GovernanceError::StorageError(format!("Failed to load vote: {}", e))),
+            };
+            drop(storage);
+
+            let vote: Vote = serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize vote: {}", e)))?;
+
+            if is_vote_excluded(&settings, &flags, &vote.voter) {
+                tally.excluded.push(vote.voter);
+                continue;
+            }
+
+            match vote.choice {
+                VoteChoice::For => tally.votes_for += vote.weight,
+                VoteChoice::Against => tally.votes_against += vote.weight,
+                VoteChoice::Abstain => tally.votes_abstain += vote.weight,
+            }
+        }
+
+        Ok(tally)
+    }
+
+    async fn store_interest(&self, interest: &RegisteredInterest) -> Result<(), GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("coi::interest::{}", interest.id))?;
+        let interest_bytes = serde_json::to_vec(interest)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize interest: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, interest_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to store interest: {}", e)))
+    }
+
+    async fn get_interest(&self, interest_id: &str) -> Result<Option<RegisteredInterest>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("coi::interest::{}", interest_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize interest: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load interest: {}", e))),
+        }
+    }
+
+    async fn store_conflict_flags(&self, proposal_id: &str, flags: &[ConflictFlag]) -> Result<(), GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("coi::flags::{}", proposal_id))?;
+        let flag_bytes = serde_json::to_vec(flags)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize conflict flags: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, flag_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to store conflict flags: {}", e)))
+    }
+
+    pub(crate) async fn load_index(&self, index_key: &str) -> Result<Vec<String>, GovernanceError> {
+        let index_cid = self.create_key_cid(index_key)?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&index_cid).await {
+            Ok(Some(bytes)) => Ok(serde_json::from_slice::<Vec<String>>(&bytes).unwrap_or_default()),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load index: {}", e))),
+        }
+    }
+
+    async fn store_index(&self, index_key: &str, values: &[String]) -> Result<(), GovernanceError> {
+        let index_cid = self.create_key_cid(index_key)?;
+        let index_bytes = serde_json::to_vec(values)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize index: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(index_cid, index_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to store index: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use icn_identity::IdentityScope;
+    use crate::ProposalStatus;
+
+    fn interest(member: &str, subject: &str, keywords: &[&str]) -> RegisteredInterest {
+        RegisteredInterest {
+            id: format!("interest-{}", member),
+            member: IdentityId(member.to_string()),
+            scope_id: "coop-1".to_string(),
+            kind: InterestKind::FinancialStake,
+            subject: subject.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            description: None,
+            registered_at: 0,
+        }
+    }
+
+    fn proposal() -> Proposal {
+        let mut metadata = HashMap::new();
+        metadata.insert("vendor".to_string(), "did:icn:org:greenprint".to_string());
+
+        Proposal {
+            title: "Renew printing contract".to_string(),
+            description: "Extend the contract with Riverside Supplies for another year".to_string(),
+            proposer: IdentityId("did:icn:member:alice".to_string()),
+            scope: IdentityScope::Cooperative,
+            scope_id: Some(IdentityId("coop-1".to_string())),
+            status: ProposalStatus::Draft,
+            voting_end_time: 0,
+            votes_for: 0,
+            votes_against: 0,
+            votes_abstain: 0,
+            ccl_code: None,
+            wasm_bytes: None,
+            wasm_cid: None,
+            thread_id: None,
+            metadata,
+            proposal_type: None,
+            created_at: 0,
+            effects: Vec::new(),
+            effective_at: None,
+        }
+    }
+
+    #[test]
+    fn test_interests_match_text_and_metadata() {
+        let interests = vec![
+            interest("did:icn:member:bob", "riverside supplies", &[]),
+            interest("did:icn:member:carol", "GreenPrint Ltd", &["did:icn:org:greenprint"]),
+            interest("did:icn:member:dave", "Hilltop Bakery", &[""]),
+        ];
+
+        let matches = match_interests(&proposal(), &interests);
+        assert_eq!(matches.len(), 2);
+
+        assert_eq!(matches[0].0, IdentityId("did:icn:member:bob".to_string()));
+        assert_eq!(matches[0].1.field, "description");
+
+        assert_eq!(matches[1].0, IdentityId("did:icn:member:carol".to_string()));
+        assert_eq!(matches[1].1.field, "metadata.vendor");
+        assert_eq!(matches[1].1.term, "did:icn:org:greenprint");
+    }
+
+    #[test]
+    fn test_recusal_flag() {
+        let mut flag = ConflictFlag {
+            proposal_id: "proposal:renew-printing-contract".to_string(),
+            member: IdentityId("did:icn:member:bob".to_string()),
+            matches: Vec::new(),
+            declaration: None,
+        };
+        assert!(!flag.is_recused());
+
+        flag.declaration = Some(ConflictDeclaration {
+            proposal_id: flag.proposal_id.clone(),
+            member: flag.member.clone(),
+            recuse: true,
+            statement: "I own shares in Riverside Supplies".to_string(),
+            declared_at: 0,
+        });
+        assert!(flag.is_recused());
+    }
+}
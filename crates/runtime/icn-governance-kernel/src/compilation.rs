@@ -0,0 +1,137 @@
+/*!
+# Proposal Compilation
+
+When a proposal is activated, its `ccl_code` is compiled against the scope's governance
+configuration. The resulting WASM and its CID are stored on the proposal before voting
+opens, so the module voters approve is exactly the one that executes. Activation is
+rejected if compilation fails. A proposal submitted as Active is opened the same way, and
+any WASM a submitter supplies is discarded.
+
+The kernel doesn't depend on the compiler crate. The compiler plugs in through the
+[`ProposalCompiler`] trait instead.
+*/
+
+use std::sync::Arc;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::config::GovernanceConfig;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Compiles a proposal's CCL code into a WASM module
+pub trait ProposalCompiler: Send + Sync {
+    /// Compile `ccl_code` against the governance configuration of the proposal's scope
+    fn compile(&self, ccl_code: &str, scope_config: &GovernanceConfig) -> Result<Vec<u8>, String>;
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Use the given compiler for `ccl_code` when proposals are activated
+    pub fn with_compiler(mut self, compiler: Arc<dyn ProposalCompiler>) -> Self {
+        self.compiler = Some(compiler);
+        self
+    }
+
+    /// Move a draft proposal to Active, compiling its CCL code first.
+    ///
+    /// Only the proposer or an identity holding the `activate_proposals` permission may
+    /// activate a proposal, and only once its type's discussion period has elapsed and
+    /// it has the co-sponsors its type requires.
+    pub async fn activate_proposal(&self, proposal_id: String, caller: &IdentityId) -> Result<(), GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.clone()).await?;
+
+        if proposal.status != ProposalStatus::Draft {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot activate proposal with status {:?}", proposal.status
+            )));
+        }
+
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        if caller != &proposal.proposer && !self.check_permission(caller, &scope_id, "activate_proposals").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to activate proposals in scope {}", caller.0, scope_id
+            )));
+        }
+
+        self.check_deliberation_period(&proposal_id, &proposal).await?;
+
+        self.open_voting(proposal_id, proposal, caller).await
+    }
+
+    /// Compile a draft proposal's CCL code, store the result and open it for voting
+    pub(crate) async fn open_voting(&self, proposal_id: String, mut proposal: Proposal, caller: &IdentityId) -> Result<(), GovernanceError> {
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        // No pathway opens a proposal that is still short of co-sponsors
+        self.check_co_sponsorship(&proposal_id, &proposal).await?;
+
+        match &proposal.ccl_code {
+            Some(ccl_code) => {
+                let compiler = self.compiler.as_ref()
+                    .ok_or_else(|| GovernanceError::CompilationFailed("No CCL compiler is configured".to_string()))?;
+                let scope_config = self.load_governance_config(&scope_id).await?
+                    .ok_or_else(|| GovernanceError::CompilationFailed(format!(
+                        "No governance configuration found for scope {}", scope_id
+                    )))?;
+
+                let wasm_bytes = compiler.compile(ccl_code, &scope_config)
+                    .map_err(GovernanceError::CompilationFailed)?;
+
+                let storage = self.storage.lock().await;
+                let wasm_cid = storage.put_blob(&wasm_bytes)
+                    .await
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to store compiled WASM: {}", e)))?;
+                drop(storage);
+
+                proposal.wasm_bytes = Some(wasm_bytes);
+                proposal.wasm_cid = Some(wasm_cid.to_string());
+            }
+            None => {
+                // WASM without its source can't be checked against anything
+                if proposal.wasm_bytes.is_some() {
+                    return Err(GovernanceError::InvalidProposal(
+                        "Proposal carries WASM but no CCL code to compile it from".to_string()
+                    ));
+                }
+                proposal.wasm_cid = None;
+            }
+        }
+
+        proposal.status = ProposalStatus::Active;
+
+        let proposal_bytes = serde_json::to_vec(&proposal)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to serialize proposal: {}", e)))?;
+        let key_cid = self.create_key_cid(&format!("proposal::{}", &proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, proposal_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+
+        let event_data = serde_json::json!({
+            "title": proposal.title,
+            "activated_by": caller.0,
+            "wasm_cid": proposal.wasm_cid
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ProposalActivated,
+            caller.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+}
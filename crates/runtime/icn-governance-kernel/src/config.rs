@@ -73,6 +73,106 @@ pub struct GovernanceStructure {
     
     /// Defined roles in the organization
     pub roles: Option<Vec<Role>>,
+    
+    /// Rules that must always hold, checked against every proposal
+    #[serde(default)]
+    pub invariants: Option<Vec<InvariantConfig>>,
+    
+    /// Classes of membership (e.g. full, probationary, affiliate) and their rights
+    #[serde(default)]
+    pub member_classes: Option<Vec<MemberClassConfig>>,
+    
+    /// Recurring events set by the bylaws (e.g. the AGM, budget season, elections)
+    #[serde(default)]
+    pub calendar: Option<Vec<CalendarEventConfig>>,
+}
+
+/// A recurring governance event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEventConfig {
+    /// Name of the event
+    pub name: String,
+    
+    /// How often the event recurs: "yearly", "quarterly", "monthly" or "every N days"
+    pub recurrence: String,
+    
+    /// Date of the first occurrence (YYYY-MM-DD)
+    pub starts_on: String,
+    
+    /// Deadlines falling a number of days before each occurrence
+    #[serde(default)]
+    pub deadlines: Option<Vec<CalendarDeadlineConfig>>,
+    
+    /// Proposal opened automatically ahead of each occurrence
+    #[serde(default)]
+    pub standing_proposal: Option<StandingProposalConfig>,
+}
+
+/// A deadline ahead of a recurring event (e.g. notice of meeting, nominations close)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarDeadlineConfig {
+    /// Name of the deadline
+    pub name: String,
+    
+    /// How many days before the event the deadline falls
+    pub days_before: u64,
+}
+
+/// A proposal put to members ahead of every occurrence of an event (e.g. the annual budget)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandingProposalConfig {
+    /// Title of the proposal
+    pub title: String,
+    
+    /// Description of the proposal
+    #[serde(default)]
+    pub description: Option<String>,
+    
+    /// Proposal type the proposal is submitted as
+    #[serde(default)]
+    pub proposal_type: Option<String>,
+    
+    /// How many days before the event the proposal opens; voting closes when the event starts
+    pub days_before: u64,
+}
+
+/// A class of membership
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberClassConfig {
+    /// Name of the class
+    pub name: String,
+    
+    /// Permissions members of this class may exercise through their roles
+    /// (all of them if unset)
+    #[serde(default)]
+    pub permissions: Option<Vec<String>>,
+    
+    /// Whether members of this class count towards the quorum (default true)
+    #[serde(default)]
+    pub counts_toward_quorum: Option<bool>,
+    
+    /// Class members move to when a transition vote passes (e.g. probationary -> full)
+    #[serde(default)]
+    pub promotes_to: Option<String>,
+    
+    /// Whether members with no recorded class belong to this one
+    #[serde(default)]
+    pub default: Option<bool>,
+}
+
+/// A bylaw invariant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvariantConfig {
+    /// Name of the invariant
+    pub name: String,
+    
+    /// The rule, e.g. `reserve_floor(budget=operations, minimum=10000)` or
+    /// `exclusive_roles(Treasurer, Auditor)`
+    pub rule: String,
+    
+    /// "block" (the default) or "flag"
+    #[serde(default)]
+    pub enforcement: Option<String>,
 }
 
 /// A role in the organization
@@ -96,6 +196,10 @@ pub struct MembershipRules {
     
     /// Offboarding process
     pub offboarding: Option<Offboarding>,
+    
+    /// How member participation is scored
+    #[serde(default)]
+    pub participation: Option<ParticipationScoring>,
 }
 
 /// Onboarding process
@@ -122,6 +226,29 @@ pub struct Dues {
     
     /// Variable options for dues
     pub variable_options: Option<Vec<DuesOption>>,
+    
+    /// Grace periods, late fees and hardship waivers
+    #[serde(default)]
+    pub late_policy: Option<DuesLatePolicy>,
+}
+
+/// What happens when dues are paid late
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuesLatePolicy {
+    /// Days after the due date before a payment is late
+    pub grace_period_days: Option<u64>,
+    
+    /// Flat fee assessed on late dues
+    pub late_fee: Option<u64>,
+    
+    /// Fee assessed on late dues as a share of the amount owed, in basis points
+    pub late_fee_bps: Option<u64>,
+    
+    /// Days after the grace period before a late member loses their vote
+    pub suspend_voting_after_days: Option<u64>,
+    
+    /// Approvals a hardship waiver needs
+    pub waiver_approvals: Option<u64>,
 }
 
 /// A dues option
@@ -144,11 +271,57 @@ pub struct Offboarding {
     pub max_inactive_days: Option<u64>,
 }
 
+/// Participation scoring rules
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParticipationScoring {
+    /// Points per proposal voted on (default 1)
+    pub vote_points: Option<u64>,
+    
+    /// Points per proposal authored (default 3)
+    pub proposal_points: Option<u64>,
+    
+    /// Points per meeting attended (default 2)
+    pub meeting_points: Option<u64>,
+    
+    /// Points per recorded work-group contribution (default 2)
+    pub contribution_points: Option<u64>,
+    
+    /// Only activity from the last this many days counts (all activity if unset)
+    pub window_days: Option<u64>,
+    
+    /// Whether participation may add to a member's vote weight (default false)
+    pub weights_votes: Option<bool>,
+    
+    /// Points per unit of vote weight above the base weight of 1 (default 10)
+    pub points_per_vote_weight: Option<u64>,
+    
+    /// Highest vote weight participation can bring a member to
+    pub max_vote_weight: Option<u64>,
+}
+
 /// Proposal process
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProposalProcess {
     /// Types of proposals
     pub types: Option<Vec<ProposalType>>,
+    
+    /// Whether members may change or retract votes on active proposals
+    #[serde(default)]
+    pub vote_changes: Option<VoteChangePolicy>,
+}
+
+/// Vote change and retraction rules
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VoteChangePolicy {
+    /// Whether a member may replace their vote with a different one
+    pub allow_change: Option<bool>,
+    
+    /// Whether a member may withdraw their vote entirely
+    pub allow_retraction: Option<bool>,
+    
+    /// Hours after the first vote during which it may be changed or retracted
+    /// (no limit if unset)
+    pub window_hours: Option<u64>,
 }
 
 /// A proposal type
@@ -165,6 +338,46 @@ pub struct ProposalType {
     
     /// Discussion period in days
     pub discussion_period_days: Option<u64>,
+    
+    /// Whether emergency proposals of this type may skip the discussion period
+    #[serde(default)]
+    pub allow_fast_track: Option<bool>,
+    
+    /// How a tied vote on proposals of this type is resolved
+    #[serde(default)]
+    pub tie_break: Option<TieBreakPolicy>,
+    
+    /// Co-sponsors a proposal of this type needs before it can open for voting
+    #[serde(default)]
+    pub co_sponsorship: Option<CoSponsorshipRule>,
+}
+
+/// Co-sponsorship required of high-impact proposals (e.g. asset sales, mergers)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoSponsorshipRule {
+    /// Distinct co-sponsors required, not counting the proposer
+    pub required: u64,
+    
+    /// Roles a co-sponsor must hold one of (any member may co-sponsor if unset)
+    #[serde(default)]
+    pub roles: Option<Vec<String>>,
+}
+
+/// How a tied vote is resolved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreakPolicy {
+    /// A tie rejects the proposal
+    Fail,
+    
+    /// A member with the `break_ties` permission decides the outcome
+    ChairDecides,
+    
+    /// The outcome is drawn from a beacon anchored alongside the tally
+    RandomBeacon,
+    
+    /// Voting reopens for the given number of hours; a second tie rejects the proposal
+    Revote { extension_hours: u64 },
 }
 
 /// Working groups structure
@@ -208,6 +421,30 @@ pub struct EconomicModel {
     
     /// Compensation policy
     pub compensation_policy: Option<CompensationPolicy>,
+    
+    /// Guardrails on reserves held in external instruments
+    #[serde(default)]
+    pub investment_policy: Option<InvestmentPolicyConfig>,
+    
+    /// Allocation keys for splitting shared expenses, by name
+    /// (e.g. `hosting: "usage(meter=storage_gb)"`)
+    #[serde(default)]
+    pub cost_allocation: Option<HashMap<String, String>>,
+    
+    /// How monetary amounts split in proportion are rounded
+    /// (e.g. `"half_even(unit=100, residue=reserve)"`)
+    #[serde(default)]
+    pub rounding: Option<String>,
+}
+
+/// Treasury investment guardrails
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvestmentPolicyConfig {
+    /// Maximum share of holdings per asset class, in basis points
+    pub max_allocation_bps: Option<HashMap<String, u64>>,
+    
+    /// Instruments the treasury may not hold
+    pub prohibited_instruments: Option<Vec<String>>,
 }
 
 /// Compensation policy
@@ -430,6 +667,7 @@ impl BudgetConfig {
                                         min_allocation: None,
                                         max_allocation: None,
                                         description: None,
+                                        tax_category: None,
                                     };
                                     
                                     // Parse description
@@ -437,6 +675,11 @@ impl BudgetConfig {
                                         rule.description = Some(desc.clone());
                                     }
                                     
+                                    // Parse tax category
+                                    if let Some(Value::String(tax)) = category_obj.get("tax_category") {
+                                        rule.tax_category = Some(tax.clone());
+                                    }
+                                    
                                     // Parse min_allocation
                                     if let Some(Value::String(min)) = category_obj.get("min_allocation") {
                                         // Parse percentage (e.g., "30%")
@@ -0,0 +1,222 @@
+/*!
+# Co-Sponsorship
+
+High-impact proposal types (asset sales, merger initiation) can require co-sponsors
+before they go to a vote, set per type with `ProposalType::co_sponsorship`. A proposal
+of such a type is always submitted as a Draft, and it can't open for voting, whether by
+activation or fast-tracking, until enough distinct members other than the proposer have
+signed on.
+
+If the rule names roles, a co-sponsor must hold one of them in the proposal's scope.
+Otherwise any member who may create proposals may co-sponsor one. Each signature is
+recorded with its time and published as an event.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::config::CoSponsorshipRule;
+use crate::deliberation::find_proposal_type;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// A member's signature co-sponsoring a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoSponsorship {
+    pub sponsor: IdentityId,
+    /// When the member signed on (Unix timestamp)
+    pub signed_at: i64,
+}
+
+/// Where a proposal stands against its type's co-sponsorship rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoSponsorshipStatus {
+    pub proposal_id: String,
+    /// Distinct co-sponsors required
+    pub required: u64,
+    pub sponsors: Vec<CoSponsorship>,
+}
+
+impl CoSponsorshipStatus {
+    /// Whether the proposal has all the co-sponsors it needs
+    pub fn is_satisfied(&self) -> bool {
+        self.sponsors.len() as u64 >= self.required
+    }
+
+    /// Co-sponsors still needed
+    pub fn outstanding(&self) -> u64 {
+        self.required.saturating_sub(self.sponsors.len() as u64)
+    }
+}
+
+/// Whether a member holding `roles` may co-sponsor under `rule`. A rule without roles
+/// leaves the decision to the `create_proposals` permission.
+pub fn roles_qualify(rule: &CoSponsorshipRule, roles: &[String]) -> bool {
+    match &rule.roles {
+        Some(required) => required.iter().any(|role| roles.contains(role)),
+        None => true,
+    }
+}
+
+fn sponsors_key(proposal_id: &str) -> String {
+    format!("proposal::cosponsors::{}", proposal_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Sign on as a co-sponsor of a draft proposal whose type requires co-sponsors
+    pub async fn co_sponsor_proposal(&self, proposal_id: &str, sponsor: &IdentityId) -> Result<CoSponsorshipStatus, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if proposal.status != ProposalStatus::Draft {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot co-sponsor proposal with status {:?}", proposal.status
+            )));
+        }
+
+        let rule = self.co_sponsorship_rule(&proposal).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Proposal {} does not require co-sponsors", proposal_id
+            )))?;
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        if sponsor == &proposal.proposer {
+            return Err(GovernanceError::InvalidProposal("A proposer can't co-sponsor their own proposal".to_string()));
+        }
+
+        let qualified = match &rule.roles {
+            Some(_) => roles_qualify(&rule, &self.get_verified_roles(sponsor, &scope_id).await?),
+            None => self.check_permission(sponsor, &scope_id, "create_proposals").await?,
+        };
+        if !qualified {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not qualified to co-sponsor proposals in scope {}", sponsor.0, scope_id
+            )));
+        }
+
+        let mut sponsors = self.load_co_sponsors(proposal_id).await?;
+        if sponsors.iter().any(|s| &s.sponsor == sponsor) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Identity {} already co-sponsors proposal {}", sponsor.0, proposal_id
+            )));
+        }
+        sponsors.push(CoSponsorship {
+            sponsor: sponsor.clone(),
+            signed_at: chrono::Utc::now().timestamp(),
+        });
+
+        let sponsors_bytes = serde_json::to_vec(&sponsors)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize co-sponsors: {}", e)))?;
+        self.store_record(&sponsors_key(proposal_id), sponsors_bytes).await?;
+
+        let status = CoSponsorshipStatus {
+            proposal_id: proposal_id.to_string(),
+            required: rule.required,
+            sponsors,
+        };
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ProposalCoSponsored,
+            sponsor.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "co_sponsor": sponsor.0,
+                "co_sponsors": status.sponsors.len(),
+                "required": status.required
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(status)
+    }
+
+    /// Where a proposal stands against its co-sponsorship rule, if its type has one
+    pub async fn get_co_sponsorship_status(&self, proposal_id: &str) -> Result<Option<CoSponsorshipStatus>, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let rule = match self.co_sponsorship_rule(&proposal).await? {
+            Some(rule) => rule,
+            None => return Ok(None),
+        };
+
+        Ok(Some(CoSponsorshipStatus {
+            proposal_id: proposal_id.to_string(),
+            required: rule.required,
+            sponsors: self.load_co_sponsors(proposal_id).await?,
+        }))
+    }
+
+    /// Reject opening voting on a proposal that is still short of co-sponsors
+    pub(crate) async fn check_co_sponsorship(&self, proposal_id: &str, proposal: &Proposal) -> Result<(), GovernanceError> {
+        let rule = match self.co_sponsorship_rule(proposal).await? {
+            Some(rule) => rule,
+            None => return Ok(()),
+        };
+
+        let sponsors = self.load_co_sponsors(proposal_id).await?;
+        if (sponsors.len() as u64) < rule.required {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} has {} of the {} co-sponsors it needs before voting can open",
+                proposal_id, sponsors.len(), rule.required
+            )));
+        }
+        Ok(())
+    }
+
+    /// The co-sponsorship rule of the proposal's type, if it requires any co-sponsors
+    pub(crate) async fn co_sponsorship_rule(&self, proposal: &Proposal) -> Result<Option<CoSponsorshipRule>, GovernanceError> {
+        let scope_id = match &proposal.scope_id {
+            Some(sid) => &sid.0,
+            None => return Ok(None),
+        };
+        let config = match self.load_governance_config(scope_id).await? {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+
+        Ok(find_proposal_type(&config, proposal)?
+            .and_then(|t| t.co_sponsorship.clone())
+            .filter(|rule| rule.required > 0))
+    }
+
+    async fn load_co_sponsors(&self, proposal_id: &str) -> Result<Vec<CoSponsorship>, GovernanceError> {
+        Ok(self.load_record(&sponsors_key(proposal_id), "co-sponsors").await?.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_co_sponsorship_rules() {
+        let rule = CoSponsorshipRule {
+            required: 2,
+            roles: Some(vec!["board".to_string(), "treasurer".to_string()]),
+        };
+        assert!(roles_qualify(&rule, &["member".to_string(), "treasurer".to_string()]));
+        assert!(!roles_qualify(&rule, &["member".to_string()]));
+        assert!(roles_qualify(&CoSponsorshipRule { required: 2, roles: None }, &[]));
+
+        let mut status = CoSponsorshipStatus {
+            proposal_id: "proposal:sell-the-warehouse".to_string(),
+            required: 2,
+            sponsors: vec![CoSponsorship {
+                sponsor: IdentityId("did:icn:member:bob".to_string()),
+                signed_at: 1_700_000_000,
+            }],
+        };
+        assert!(!status.is_satisfied());
+        assert_eq!(status.outstanding(), 1);
+
+        status.sponsors.push(CoSponsorship {
+            sponsor: IdentityId("did:icn:member:carol".to_string()),
+            signed_at: 1_700_000_100,
+        });
+        assert!(status.is_satisfied());
+        assert_eq!(status.outstanding(), 0);
+    }
+}
@@ -0,0 +1,175 @@
+/*!
+# Shared Cost Allocation
+
+Federations carry costs no single co-op owns: hosting, insurance, legal retainers. The
+federation's bylaws name allocation keys in the `cost_allocation` section of their
+economic model (per capita, usage from metering, or negotiated percentages), and each
+shared expense is split by one of them. The invoices and disputes live in
+`icn_economics::cost_allocation`; the kernel checks who may allocate and decide,
+records each step in the event log, and passes contested shares to the dispute hook
+set with [`GovernanceKernel::with_cost_dispute_hook`].
+*/
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use icn_economics::cost_allocation::{
+    self, AllocationInputs, AllocationKey, CostDisputeHook, CostDisputeOutcome, CostInvoice, SharedExpense,
+};
+use icn_economics::statements::StatementPeriod;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Notify the given hook when a co-op contests its share of shared costs
+    pub fn with_cost_dispute_hook(mut self, hook: Arc<dyn CostDisputeHook>) -> Self {
+        self.cost_dispute_hook = Some(hook);
+        self
+    }
+
+    /// The allocation keys a scope's bylaws define, by name
+    pub async fn cost_allocation_keys(&self, scope_id: &str) -> Result<HashMap<String, AllocationKey>, GovernanceError> {
+        let specs = self.load_governance_config(scope_id).await?
+            .and_then(|config| config.economic_model)
+            .and_then(|model| model.cost_allocation)
+            .unwrap_or_default();
+
+        specs.into_iter()
+            .map(|(name, spec)| AllocationKey::from_economic_model(&spec)
+                .map(|key| (name.clone(), key))
+                .map_err(|e| GovernanceError::InvalidProposal(format!(
+                    "Invalid allocation key {} for scope {}: {}", name, scope_id, e
+                ))))
+            .collect()
+    }
+
+    async fn emit_cost_event(
+        &self,
+        event_type: GovernanceEventType,
+        issuer: &IdentityId,
+        scope_id: &str,
+        data: serde_json::Value,
+    ) -> Result<(), GovernanceError> {
+        let scope = self.load_governance_config(scope_id).await?
+            .map(|config| config.governing_scope)
+            .unwrap_or(icn_identity::IdentityScope::Federation);
+
+        let event = GovernanceEvent::new(
+            event_type,
+            issuer.clone(),
+            scope,
+            Some(IdentityId(scope_id.to_string())),
+            None,
+            data
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        Ok(())
+    }
+
+    /// Split a period's shared expenses between the participating co-ops and bill each
+    /// of them. The caller needs the `allocate_shared_costs` permission.
+    pub async fn allocate_shared_costs(
+        &self,
+        caller: &IdentityId,
+        scope_id: &str,
+        period: StatementPeriod,
+        expenses: &[SharedExpense],
+        participants: &[String],
+        inputs: &AllocationInputs,
+    ) -> Result<Vec<CostInvoice>, GovernanceError> {
+        if !self.check_permission(caller, scope_id, "allocate_shared_costs").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to allocate shared costs in scope {}", caller.0, scope_id
+            )));
+        }
+
+        let keys = self.cost_allocation_keys(scope_id).await?;
+        let rounding = self.rounding_policy(scope_id).await?;
+        let invoices = {
+            let mut storage = self.storage.lock().await;
+            cost_allocation::allocate_shared_costs(scope_id, period, expenses, &keys, participants, inputs, &rounding, &mut *storage)
+                .await
+                .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to allocate shared costs: {}", e)))?
+        };
+
+        let totals: HashMap<&str, u64> = invoices.iter().map(|i| (i.member.as_str(), i.total)).collect();
+        self.emit_cost_event(GovernanceEventType::SharedCostsAllocated, caller, scope_id, serde_json::json!({
+            "period": period.to_string(),
+            "expenses": expenses.iter().map(|e| &e.id).collect::<Vec<_>>(),
+            "totals": totals
+        })).await?;
+
+        Ok(invoices)
+    }
+
+    /// Contest a co-op's share. The caller must be the co-op the invoice was billed to.
+    /// Returns the dispute ID.
+    pub async fn contest_cost_share(
+        &self,
+        caller: &IdentityId,
+        scope_id: &str,
+        invoice_id: &str,
+        reason: &str,
+    ) -> Result<String, GovernanceError> {
+        let dispute_id = {
+            let mut storage = self.storage.lock().await;
+            cost_allocation::dispute_cost_share(
+                scope_id,
+                invoice_id,
+                &caller.0,
+                reason,
+                chrono::Utc::now().timestamp(),
+                self.cost_dispute_hook.as_deref(),
+                &mut *storage,
+            )
+            .await
+            .map_err(|e| match e {
+                icn_economics::EconomicsError::Unauthorized(msg) => GovernanceError::Unauthorized(msg),
+                other => GovernanceError::InvalidProposal(format!("Failed to contest cost share: {}", other)),
+            })?
+        };
+
+        self.emit_cost_event(GovernanceEventType::CostShareDisputed, caller, scope_id, serde_json::json!({
+            "invoice_id": invoice_id,
+            "dispute_id": dispute_id,
+            "reason": reason
+        })).await?;
+
+        Ok(dispute_id)
+    }
+
+    /// Decide a contested share. The caller needs the `resolve_cost_disputes` permission.
+    pub async fn resolve_cost_share_dispute(
+        &self,
+        caller: &IdentityId,
+        scope_id: &str,
+        dispute_id: &str,
+        outcome: CostDisputeOutcome,
+    ) -> Result<CostInvoice, GovernanceError> {
+        if !self.check_permission(caller, scope_id, "resolve_cost_disputes").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to resolve cost disputes in scope {}", caller.0, scope_id
+            )));
+        }
+
+        let invoice = {
+            let mut storage = self.storage.lock().await;
+            cost_allocation::resolve_cost_dispute(scope_id, dispute_id, outcome.clone(), chrono::Utc::now().timestamp(), &mut *storage)
+                .await
+                .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to resolve cost dispute: {}", e)))?
+        };
+
+        self.emit_cost_event(GovernanceEventType::CostShareDisputeResolved, caller, scope_id, serde_json::json!({
+            "dispute_id": dispute_id,
+            "invoice_id": invoice.id,
+            "member": invoice.member,
+            "outcome": outcome,
+            "total": invoice.total
+        })).await?;
+
+        Ok(invoice)
+    }
+}
@@ -0,0 +1,245 @@
+/*!
+# Deliberation Periods
+
+A scope's proposal process can give each proposal type a minimum discussion period
+(`ProposalType::discussion_period_days`). A proposal of such a type is submitted as a
+Draft and only takes votes once activated, which it can't be until that period has
+elapsed since it was submitted. Early activation fails with
+[`GovernanceError::DeliberationPeriodActive`], which carries the earliest time voting
+can open.
+
+Emergencies go through the fast-track pathway instead. The proposal's type must allow
+fast-tracking, the caller must hold the `fast_track_proposals` permission, and the
+justification is recorded and published as an event.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::config::{GovernanceConfig, ProposalType};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Record of a proposal that skipped its discussion period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FastTrackRecord {
+    pub proposal_id: String,
+    /// Who fast-tracked the proposal
+    pub approved_by: IdentityId,
+    /// Why the discussion period was skipped
+    pub justification: String,
+    /// When voting would otherwise have opened (Unix timestamp)
+    pub waived_until: i64,
+    /// When the proposal was fast-tracked (Unix timestamp)
+    pub fast_tracked_at: i64,
+}
+
+/// Look up a proposal's type in the scope's proposal process
+pub fn find_proposal_type<'a>(config: &'a GovernanceConfig, proposal: &Proposal) -> Result<Option<&'a ProposalType>, GovernanceError> {
+    let type_name = match &proposal.proposal_type {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    config.proposals.as_ref()
+        .and_then(|p| p.types.as_ref())
+        .and_then(|types| types.iter().find(|t| &t.name == type_name))
+        .map(Some)
+        .ok_or_else(|| GovernanceError::InvalidProposal(format!("Unknown proposal type {}", type_name)))
+}
+
+/// Earliest time a proposal of the given type may open for voting, if the type has a
+/// discussion period. Proposals without a submission time aren't constrained.
+pub fn earliest_voting_time(proposal: &Proposal, proposal_type: Option<&ProposalType>) -> Option<i64> {
+    let days = proposal_type.and_then(|t| t.discussion_period_days)?;
+    if proposal.created_at == 0 || days == 0 {
+        return None;
+    }
+    Some(proposal.created_at.saturating_add((days as i64).saturating_mul(SECONDS_PER_DAY)))
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Earliest time the proposal may open for voting, if it has a discussion period
+    pub async fn earliest_activation_time(&self, proposal_id: &str) -> Result<Option<i64>, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        self.earliest_voting_time_for(&proposal).await
+    }
+
+    /// Reject opening voting on a proposal whose discussion period is still running
+    pub(crate) async fn check_deliberation_period(&self, proposal_id: &str, proposal: &Proposal) -> Result<(), GovernanceError> {
+        if let Some(earliest_allowed) = self.earliest_voting_time_for(proposal).await? {
+            if chrono::Utc::now().timestamp() < earliest_allowed {
+                return Err(GovernanceError::DeliberationPeriodActive {
+                    proposal_id: proposal_id.to_string(),
+                    earliest_allowed,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Open an emergency proposal for voting before its discussion period has elapsed
+    pub async fn fast_track_proposal(&self, proposal_id: String, caller: &IdentityId, justification: String) -> Result<(), GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.clone()).await?;
+
+        if proposal.status != ProposalStatus::Draft {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot fast-track proposal with status {:?}", proposal.status
+            )));
+        }
+        if justification.trim().is_empty() {
+            return Err(GovernanceError::InvalidProposal("Fast-tracking requires a justification".to_string()));
+        }
+
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        if !self.check_permission(caller, &scope_id, "fast_track_proposals").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to fast-track proposals in scope {}", caller.0, scope_id
+            )));
+        }
+
+        let config = self.load_governance_config(&scope_id).await?
+            .ok_or_else(|| GovernanceError::Unauthorized(format!("No governance configuration found for scope {}", scope_id)))?;
+        let proposal_type = find_proposal_type(&config, &proposal)?;
+        if !proposal_type.and_then(|t| t.allow_fast_track).unwrap_or(false) {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Proposal type {} does not allow fast-tracking",
+                proposal.proposal_type.as_deref().unwrap_or("(none)")
+            )));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let waived_until = earliest_voting_time(&proposal, proposal_type).unwrap_or(now);
+        let (scope, scope_id) = (proposal.scope, proposal.scope_id.clone());
+
+        // Open voting first so a failed compilation leaves no fast-track record behind
+        self.open_voting(proposal_id.clone(), proposal, caller).await?;
+
+        let record = FastTrackRecord {
+            proposal_id: proposal_id.clone(),
+            approved_by: caller.clone(),
+            justification,
+            waived_until,
+            fast_tracked_at: now,
+        };
+
+        let record_bytes = serde_json::to_vec(&record)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize fast-track record: {}", e)))?;
+        let key_cid = self.create_key_cid(&format!("proposal::fast_track::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, record_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+
+        let event_data = serde_json::json!({
+            "fast_tracked_by": caller.0,
+            "justification": record.justification,
+            "waived_until": record.waived_until
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ProposalFastTracked,
+            caller.clone(),
+            scope,
+            scope_id,
+            Some(proposal_id),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// The fast-track record of a proposal, if it was fast-tracked
+    pub async fn get_fast_track_record(&self, proposal_id: &str) -> Result<Option<FastTrackRecord>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("proposal::fast_track::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize fast-track record: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load fast-track record: {}", e))),
+        }
+    }
+
+    pub(crate) async fn earliest_voting_time_for(&self, proposal: &Proposal) -> Result<Option<i64>, GovernanceError> {
+        let scope_id = match &proposal.scope_id {
+            Some(sid) => &sid.0,
+            None => return Ok(None),
+        };
+        let config = match self.load_governance_config(scope_id).await? {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+
+        let proposal_type = find_proposal_type(&config, proposal)?;
+        Ok(earliest_voting_time(proposal, proposal_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use icn_identity::IdentityScope;
+
+    fn proposal_type(days: Option<u64>) -> ProposalType {
+        ProposalType {
+            name: "bylaw_amendment".to_string(),
+            quorum_modifier: None,
+            majority_modifier: None,
+            discussion_period_days: days,
+            allow_fast_track: None,
+            tie_break: None,
+            co_sponsorship: None,
+        }
+    }
+
+    #[test]
+    fn test_earliest_voting_time() {
+        let mut proposal = Proposal {
+            title: "Amend bylaws".to_string(),
+            description: "Change the quorum rules".to_string(),
+            proposer: IdentityId("did:icn:member:alice".to_string()),
+            scope: IdentityScope::Cooperative,
+            scope_id: Some(IdentityId("coop-1".to_string())),
+            status: ProposalStatus::Draft,
+            voting_end_time: 0,
+            votes_for: 0,
+            votes_against: 0,
+            votes_abstain: 0,
+            ccl_code: None,
+            wasm_bytes: None,
+            wasm_cid: None,
+            thread_id: None,
+            metadata: HashMap::new(),
+            proposal_type: Some("bylaw_amendment".to_string()),
+            created_at: 1_700_000_000,
+            effects: Vec::new(),
+            effective_at: None,
+        };
+
+        assert_eq!(
+            earliest_voting_time(&proposal, Some(&proposal_type(Some(14)))),
+            Some(1_700_000_000 + 14 * SECONDS_PER_DAY)
+        );
+        assert_eq!(earliest_voting_time(&proposal, Some(&proposal_type(None))), None);
+        assert_eq!(earliest_voting_time(&proposal, None), None);
+
+        // Proposals stored before submission times were recorded aren't held back
+        proposal.created_at = 0;
+        assert_eq!(earliest_voting_time(&proposal, Some(&proposal_type(Some(14)))), None);
+    }
+}
@@ -18,6 +18,10 @@ use async_trait;
 pub enum GovernanceEventType {
     /// A new governance proposal was created
     ProposalCreated,
+    /// A proposal was compiled and opened for voting
+    ProposalActivated,
+    /// A proposal skipped its discussion period through the fast-track pathway
+    ProposalFastTracked,
     /// A vote was cast on a proposal
     VoteCast,
     /// A proposal was finalized
@@ -32,6 +36,80 @@ pub enum GovernanceEventType {
     TrustBundleUpdated,
     /// A governance configuration was updated
     ConfigUpdated,
+    /// Meeting minutes were anchored and linked to a proposal
+    MinutesAnchored,
+    /// A member was flagged as conflicted on a proposal
+    ConflictOfInterestFlagged,
+    /// A member filed a conflict-of-interest declaration
+    ConflictDeclared,
+    /// The receipt hashes of a proposal's counted ballots were published
+    BallotReceiptsPublished,
+    /// A member withdrew their vote
+    VoteRetracted,
+    /// Signed outcome evidence of a proposal was issued to its members
+    OutcomeEvidenceIssued,
+    /// A tied vote was sent back to members for a re-vote
+    TieRevoteScheduled,
+    /// Retention policies redacted or archived a scope's records
+    RetentionApplied,
+    /// A referendum was put to a scope's roster
+    ReferendumOpened,
+    /// A member cast a referendum ballot
+    ReferendumBallotCast,
+    /// A referendum was closed and tallied
+    ReferendumClosed,
+    /// A period's shared costs were split into invoices for the member co-ops
+    SharedCostsAllocated,
+    /// A co-op contested its share of shared costs
+    CostShareDisputed,
+    /// A contested cost share was decided
+    CostShareDisputeResolved,
+    /// A proposal went ahead despite breaking a flag-only bylaw invariant
+    InvariantViolationFlagged,
+    /// A scope's state broke a bylaw invariant after a proposal executed
+    InvariantBreached,
+    /// A motion was mirrored into a scope as part of a joint proposal
+    JointProposalCreated,
+    /// The combined outcome of a joint proposal was anchored and linked from a scope
+    JointOutcomeAnchored,
+    /// A member was admitted to, or moved between, membership classes
+    MemberClassChanged,
+    /// A member filed a resignation from a scope
+    ResignationFiled,
+    /// A member withdrew their resignation before it took effect
+    ResignationWithdrawn,
+    /// A resignation took effect and the member's exit record was anchored
+    MemberExited,
+    /// A member's contribution to a working group was recorded
+    ContributionRecorded,
+    /// A member's participation score was attested
+    ParticipationAttested,
+    /// A third party attached a signed attestation to a proposal
+    AttestationAttached,
+    /// A proposal was withdrawn before it was finalized
+    ProposalWithdrawn,
+    /// A proposal was closed in favour of a newer one
+    ProposalSuperseded,
+    /// A deadline on the governance calendar is coming up
+    CalendarDeadlineUpcoming,
+    /// A member co-sponsored a proposal
+    ProposalCoSponsored,
+    /// A finalized proposal's tally was recounted
+    TallyRecounted,
+    /// A section of a scope's bylaws was amended by a passed proposal
+    BylawsSectionAmended,
+    /// A period's surplus plan was attached to a proposal
+    SurplusDistributionProposed,
+    /// A passed proposal's surplus plan was paid out
+    SurplusDistributed,
+    /// A passed proposal was held until its effective time
+    ProposalExecutionScheduled,
+    /// A held proposal was cancelled by a counter-proposal before it took effect
+    ScheduledExecutionCancelled,
+    /// A held proposal reached its effective time and was executed
+    ScheduledProposalActivated,
+    /// A held proposal reached its effective time but failed to execute
+    ScheduledActivationFailed,
     /// A custom event
     Custom(String),
 }
@@ -115,6 +193,45 @@ impl GovernanceEvent {
             GovernanceEventType::TrustBundleCreated => credential_types.push("TrustBundleCreationCredential".to_string()),
             GovernanceEventType::TrustBundleUpdated => credential_types.push("TrustBundleUpdateCredential".to_string()),
             GovernanceEventType::ConfigUpdated => credential_types.push("ConfigUpdateCredential".to_string()),
+            GovernanceEventType::ProposalActivated => credential_types.push("ProposalActivationCredential".to_string()),
+            GovernanceEventType::ProposalFastTracked => credential_types.push("ProposalFastTrackCredential".to_string()),
+            GovernanceEventType::MinutesAnchored => credential_types.push("MinutesAnchoringCredential".to_string()),
+            GovernanceEventType::ConflictOfInterestFlagged => credential_types.push("ConflictOfInterestFlagCredential".to_string()),
+            GovernanceEventType::ConflictDeclared => credential_types.push("ConflictDeclarationCredential".to_string()),
+            GovernanceEventType::BallotReceiptsPublished => credential_types.push("BallotReceiptsPublicationCredential".to_string()),
+            GovernanceEventType::VoteRetracted => credential_types.push("VoteRetractionCredential".to_string()),
+            GovernanceEventType::OutcomeEvidenceIssued => credential_types.push("OutcomeEvidenceCredential".to_string()),
+            GovernanceEventType::TieRevoteScheduled => credential_types.push("TieRevoteCredential".to_string()),
+            GovernanceEventType::RetentionApplied => credential_types.push("RetentionCredential".to_string()),
+            GovernanceEventType::ReferendumOpened => credential_types.push("ReferendumOpenedCredential".to_string()),
+            GovernanceEventType::ReferendumBallotCast => credential_types.push("ReferendumBallotCredential".to_string()),
+            GovernanceEventType::ReferendumClosed => credential_types.push("ReferendumResultCredential".to_string()),
+            GovernanceEventType::SharedCostsAllocated => credential_types.push("CostAllocationCredential".to_string()),
+            GovernanceEventType::CostShareDisputed => credential_types.push("CostDisputeCredential".to_string()),
+            GovernanceEventType::CostShareDisputeResolved => credential_types.push("CostDisputeResolutionCredential".to_string()),
+            GovernanceEventType::InvariantViolationFlagged => credential_types.push("InvariantViolationCredential".to_string()),
+            GovernanceEventType::InvariantBreached => credential_types.push("InvariantBreachCredential".to_string()),
+            GovernanceEventType::JointProposalCreated => credential_types.push("JointProposalCredential".to_string()),
+            GovernanceEventType::JointOutcomeAnchored => credential_types.push("JointOutcomeCredential".to_string()),
+            GovernanceEventType::MemberClassChanged => credential_types.push("MemberClassCredential".to_string()),
+            GovernanceEventType::ResignationFiled => credential_types.push("ResignationCredential".to_string()),
+            GovernanceEventType::ResignationWithdrawn => credential_types.push("ResignationWithdrawalCredential".to_string()),
+            GovernanceEventType::MemberExited => credential_types.push("MemberExitCredential".to_string()),
+            GovernanceEventType::ContributionRecorded => credential_types.push("ContributionCredential".to_string()),
+            GovernanceEventType::ParticipationAttested => credential_types.push("ParticipationCredential".to_string()),
+            GovernanceEventType::AttestationAttached => credential_types.push("ProposalAttestationCredential".to_string()),
+            GovernanceEventType::ProposalWithdrawn => credential_types.push("ProposalWithdrawalCredential".to_string()),
+            GovernanceEventType::ProposalSuperseded => credential_types.push("ProposalSupersessionCredential".to_string()),
+            GovernanceEventType::CalendarDeadlineUpcoming => credential_types.push("CalendarDeadlineCredential".to_string()),
+            GovernanceEventType::ProposalCoSponsored => credential_types.push("ProposalCoSponsorshipCredential".to_string()),
+            GovernanceEventType::TallyRecounted => credential_types.push("TallyRecountCredential".to_string()),
+            GovernanceEventType::BylawsSectionAmended => credential_types.push("BylawsAmendmentCredential".to_string()),
+            GovernanceEventType::SurplusDistributionProposed => credential_types.push("SurplusProposalCredential".to_string()),
+            GovernanceEventType::SurplusDistributed => credential_types.push("SurplusDistributionCredential".to_string()),
+            GovernanceEventType::ProposalExecutionScheduled => credential_types.push("ProposalScheduleCredential".to_string()),
+            GovernanceEventType::ScheduledExecutionCancelled => credential_types.push("ScheduleCancellationCredential".to_string()),
+            GovernanceEventType::ScheduledProposalActivated => credential_types.push("ProposalActivationCredential".to_string()),
+            GovernanceEventType::ScheduledActivationFailed => credential_types.push("ProposalActivationCredential".to_string()),
             GovernanceEventType::Custom(ref name) => credential_types.push(format!("{}Credential", name)),
         }
         
@@ -0,0 +1,99 @@
+/*!
+# Execution Allowlists
+
+Roles say what a member may do in a scope, and the kernel checks them when a proposal
+is created. They also say which host actions a module may perform when it executes on
+that member's behalf: a role's `host:<function>` permissions (e.g.,
+`host:anchor_to_dag`, or `host:*` for everything) form its allowlist.
+
+[`GovernanceKernel::execution_policy`] turns the verified roles of the acting identity
+into an [`ExecutionPolicy`] that the VM host enforces on every host call. A scope
+whose roles grant no `host:` permissions at all hasn't adopted allowlists, and its
+executions are not restricted.
+*/
+
+use std::collections::BTreeSet;
+use icn_core_vm::ExecutionPolicy;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError};
+use crate::config::Role;
+
+/// Prefix of the permissions that allow host actions
+pub const HOST_ACTION_PREFIX: &str = "host:";
+
+/// The host actions granted by the given roles, or None if no role in the scope grants any
+pub fn host_action_allowlist(defined_roles: &[Role], assigned_roles: &[String]) -> Option<BTreeSet<String>> {
+    let host_actions = |role: &Role| -> Vec<String> {
+        role.permissions.iter()
+            .filter_map(|p| p.strip_prefix(HOST_ACTION_PREFIX))
+            .map(str::to_string)
+            .collect()
+    };
+
+    if defined_roles.iter().all(|role| host_actions(role).is_empty()) {
+        return None;
+    }
+
+    Some(defined_roles.iter()
+        .filter(|role| assigned_roles.contains(&role.name))
+        .flat_map(host_actions)
+        .collect())
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The host actions a module may perform when executing on behalf of `actor` in a scope
+    pub async fn execution_policy(&self, actor: &IdentityId, scope_id: &str) -> Result<ExecutionPolicy, GovernanceError> {
+        let config = self.load_governance_config(scope_id).await?
+            .ok_or_else(|| GovernanceError::Unauthorized(format!("No governance configuration found for scope {}", scope_id)))?;
+
+        let defined_roles = config.governance.as_ref()
+            .and_then(|g| g.roles.clone())
+            .unwrap_or_default();
+        let assigned_roles = self.get_verified_roles(actor, scope_id).await?;
+
+        Ok(match host_action_allowlist(&defined_roles, &assigned_roles) {
+            Some(allowed) => ExecutionPolicy::new(scope_id, &actor.0, assigned_roles, allowed),
+            None => ExecutionPolicy::unrestricted(scope_id, &actor.0),
+        })
+    }
+
+    /// The execution policy for a proposal's module: what its proposer's roles allow in
+    /// the proposal's scope
+    pub async fn proposal_execution_policy(&self, proposal_id: &str) -> Result<ExecutionPolicy, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let scope_id = proposal.scope_id.as_ref()
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+        self.execution_policy(&proposal.proposer, &scope_id.0).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(name: &str, permissions: &[&str]) -> Role {
+        Role { name: name.to_string(), permissions: permissions.iter().map(|p| p.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_allowlist_comes_from_assigned_roles() {
+        let roles = vec![
+            role("member", &["vote", "host:get_value"]),
+            role("steward", &["manage_treasury", "host:set_value", "host:anchor_to_dag"]),
+        ];
+
+        let member = host_action_allowlist(&roles, &["member".to_string()]).unwrap();
+        assert_eq!(member, BTreeSet::from(["get_value".to_string()]));
+
+        let both = host_action_allowlist(&roles, &["member".to_string(), "steward".to_string()]).unwrap();
+        assert_eq!(both.len(), 3);
+
+        // Unassigned callers get nothing in a gated scope
+        assert!(host_action_allowlist(&roles, &[]).unwrap().is_empty());
+
+        // Scopes without host permissions are not gated
+        assert!(host_action_allowlist(&[role("member", &["vote"])], &["member".to_string()]).is_none());
+    }
+}
@@ -0,0 +1,477 @@
+/*!
+# Bylaw Invariants
+
+Bylaws can declare rules that must always hold, under `governance.invariants`:
+
+```text
+invariants: [
+  { name: "operating_reserve", rule: "reserve_floor(budget=operations, resource=Compute, minimum=10000)" },
+  { name: "separation_of_duties", rule: "exclusive_roles(Treasurer, Auditor)", enforcement: "flag" }
+]
+```
+
+A proposal declares what executing it will do as a list of [`ProposalEffect`]s. When
+the proposal is submitted, and again just before it executes, the kernel applies those
+effects to the scope's current state and evaluates every invariant against the result.
+A violation the proposal would introduce stops it if the invariant's enforcement is
+`block` (the default) and is recorded in the event log if it is `flag`. Violations that
+already hold before the proposal are reported but never attributed to it.
+
+Declared effects can be wrong, so after execution the invariants are evaluated again
+against the scope's actual state. Anything broken at that point can't be blocked any
+more; it is stored as the proposal's invariant audit and flagged in the event log.
+*/
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Serialize, Deserialize};
+use icn_economics::ResourceType;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal};
+use crate::config::InvariantConfig;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Something executing a proposal will change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProposalEffect {
+    /// Spend an amount of a resource from a budget
+    Spend { budget: String, resource: String, amount: u64 },
+
+    /// Give a member roles in the proposal's scope
+    AssignRoles { member: String, roles: Vec<String> },
+
+    /// Take roles away from a member in the proposal's scope
+    RevokeRoles { member: String, roles: Vec<String> },
+}
+
+/// What an invariant requires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvariantRule {
+    /// A budget's available balance of a resource never drops below `minimum`
+    ReserveFloor { budget: String, resource: String, minimum: u64 },
+
+    /// No member holds more than one of these roles at a time
+    ExclusiveRoles { roles: Vec<String> },
+}
+
+/// What happens when a proposal would break an invariant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Enforcement {
+    /// The proposal can't be submitted or executed
+    Block,
+    /// The proposal goes ahead and the violation is recorded
+    Flag,
+}
+
+/// An invariant declared in a scope's bylaws
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invariant {
+    pub name: String,
+    pub rule: InvariantRule,
+    pub enforcement: Enforcement,
+}
+
+/// The resource type a rule's `resource` parameter names, spelled as in budget configs
+pub fn parse_resource_type(name: &str) -> ResourceType {
+    match name {
+        "Compute" => ResourceType::Compute,
+        "Storage" => ResourceType::Storage,
+        "Network" => ResourceType::NetworkBandwidth,
+        "Labor" => ResourceType::LaborHours { skill: "general".to_string() },
+        other => ResourceType::Custom { identifier: other.to_string() },
+    }
+}
+
+impl InvariantRule {
+    /// Parse a rule from its bylaw form.
+    ///
+    /// Accepts `"reserve_floor(budget=operations, resource=Compute, minimum=10000)"` or
+    /// `"exclusive_roles(Treasurer, Auditor)"`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (method, params) = match spec.find('(') {
+            Some(open) if spec.ends_with(')') => (spec[..open].trim(), &spec[open + 1..spec.len() - 1]),
+            _ => return Err(format!("Malformed invariant rule: {}", spec)),
+        };
+        let params: Vec<&str> = params.split(',').map(str::trim).filter(|p| !p.is_empty()).collect();
+
+        match method {
+            "reserve_floor" => {
+                let mut named = BTreeMap::new();
+                for param in params {
+                    let (key, value) = param.split_once('=')
+                        .ok_or_else(|| format!("Malformed reserve_floor parameter: {}", param))?;
+                    named.insert(key.trim(), value.trim());
+                }
+                let budget = named.get("budget").filter(|b| !b.is_empty())
+                    .ok_or_else(|| format!("reserve_floor needs a budget: {}", spec))?;
+                let minimum = named.get("minimum")
+                    .ok_or_else(|| format!("reserve_floor needs a minimum: {}", spec))?;
+                let minimum = minimum.parse::<u64>()
+                    .map_err(|_| format!("Invalid reserve_floor minimum: {}", minimum))?;
+                Ok(InvariantRule::ReserveFloor {
+                    budget: budget.to_string(),
+                    resource: named.get("resource").unwrap_or(&"Compute").to_string(),
+                    minimum,
+                })
+            }
+            "exclusive_roles" => {
+                if params.len() < 2 {
+                    return Err(format!("exclusive_roles needs at least two roles: {}", spec));
+                }
+                Ok(InvariantRule::ExclusiveRoles { roles: params.into_iter().map(str::to_string).collect() })
+            }
+            other => Err(format!("Unknown invariant rule: {}", other)),
+        }
+    }
+}
+
+impl Invariant {
+    /// Build an invariant from its bylaw entry
+    pub fn from_config(config: &InvariantConfig) -> Result<Self, String> {
+        let enforcement = match config.enforcement.as_deref() {
+            None | Some("block") => Enforcement::Block,
+            Some("flag") => Enforcement::Flag,
+            Some(other) => return Err(format!("Unknown enforcement {} for invariant {}", other, config.name)),
+        };
+        Ok(Invariant { name: config.name.clone(), rule: InvariantRule::parse(&config.rule)?, enforcement })
+    }
+}
+
+/// The parts of a scope's state invariants are evaluated against
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvariantState {
+    /// Available balance, by budget and resource
+    pub balances: HashMap<(String, String), u64>,
+    /// Roles held, by member
+    pub roles: HashMap<String, Vec<String>>,
+}
+
+impl InvariantState {
+    /// The state after the given effects
+    pub fn apply(&self, effects: &[ProposalEffect]) -> InvariantState {
+        let mut state = self.clone();
+        for effect in effects {
+            match effect {
+                ProposalEffect::Spend { budget, resource, amount } => {
+                    if let Some(balance) = state.balances.get_mut(&(budget.clone(), resource.clone())) {
+                        *balance = balance.saturating_sub(*amount);
+                    }
+                }
+                ProposalEffect::AssignRoles { member, roles } => {
+                    let held = state.roles.entry(member.clone()).or_default();
+                    for role in roles {
+                        if !held.contains(role) {
+                            held.push(role.clone());
+                        }
+                    }
+                }
+                ProposalEffect::RevokeRoles { member, roles } => {
+                    if let Some(held) = state.roles.get_mut(member) {
+                        held.retain(|role| !roles.contains(role));
+                    }
+                }
+            }
+        }
+        state
+    }
+}
+
+/// An invariant that doesn't hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvariantViolation {
+    /// Name of the invariant
+    pub invariant: String,
+    pub enforcement: Enforcement,
+    /// What is wrong
+    pub detail: String,
+    /// Whether the violation held before the proposal, so isn't the proposal's doing
+    pub pre_existing: bool,
+}
+
+/// Evaluate invariants against a state. Reserve floors on budgets the state has no
+/// balance for are skipped.
+pub fn evaluate_invariants(invariants: &[Invariant], state: &InvariantState) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+    for invariant in invariants {
+        match &invariant.rule {
+            InvariantRule::ReserveFloor { budget, resource, minimum } => {
+                if let Some(balance) = state.balances.get(&(budget.clone(), resource.clone())) {
+                    if balance < minimum {
+                        violations.push(InvariantViolation {
+                            invariant: invariant.name.clone(),
+                            enforcement: invariant.enforcement,
+                            detail: format!("{} {} balance {} is below the reserve floor of {}", budget, resource, balance, minimum),
+                            pre_existing: false,
+                        });
+                    }
+                }
+            }
+            InvariantRule::ExclusiveRoles { roles } => {
+                let mut members: Vec<&String> = state.roles.keys().collect();
+                members.sort();
+                for member in members {
+                    let held: Vec<&String> = roles.iter().filter(|role| state.roles[member].contains(role)).collect();
+                    if held.len() > 1 {
+                        violations.push(InvariantViolation {
+                            invariant: invariant.name.clone(),
+                            enforcement: invariant.enforcement,
+                            detail: format!("{} holds {}", member, held.iter().map(|r| r.as_str()).collect::<Vec<_>>().join(" and ")),
+                            pre_existing: false,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// Violations in the state a proposal's effects lead to, marking those that already
+/// hold in the current state
+pub fn check_effects(invariants: &[Invariant], current: &InvariantState, effects: &[ProposalEffect]) -> Vec<InvariantViolation> {
+    let existing = evaluate_invariants(invariants, current);
+    let mut violations = evaluate_invariants(invariants, &current.apply(effects));
+    for violation in violations.iter_mut() {
+        let reserve_floor = invariants.iter()
+            .any(|i| i.name == violation.invariant && matches!(i.rule, InvariantRule::ReserveFloor { .. }));
+        // A reserve's detail names its balance, so any earlier breach of the same floor counts
+        violation.pre_existing = existing.iter()
+            .any(|e| e.invariant == violation.invariant && (reserve_floor || e.detail == violation.detail));
+    }
+    violations
+}
+
+/// Invariants evaluated against a scope's state after a proposal executed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvariantAudit {
+    pub proposal_id: String,
+    pub scope_id: String,
+    pub violations: Vec<InvariantViolation>,
+    /// When the audit ran (Unix timestamp)
+    pub audited_at: i64,
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The invariants a scope's bylaws declare
+    pub async fn bylaw_invariants(&self, scope_id: &str) -> Result<Vec<Invariant>, GovernanceError> {
+        let configs = self.load_governance_config(scope_id).await?
+            .and_then(|config| config.governance)
+            .and_then(|governance| governance.invariants)
+            .unwrap_or_default();
+
+        configs.iter()
+            .map(|config| Invariant::from_config(config)
+                .map_err(|e| GovernanceError::InvalidProposal(format!("Invalid invariant in scope {}: {}", scope_id, e))))
+            .collect()
+    }
+
+    /// The current state of everything a scope's invariants refer to
+    pub async fn invariant_state(&self, scope_id: &str, invariants: &[Invariant]) -> Result<InvariantState, GovernanceError> {
+        let mut state = InvariantState::default();
+
+        for invariant in invariants {
+            if let InvariantRule::ReserveFloor { budget, resource, .. } = &invariant.rule {
+                let storage = self.storage.lock().await;
+                // Budgets that don't exist yet have nothing to hold a reserve in
+                if let Ok(balance) = icn_economics::budget_ops::query_budget_balance(budget, &parse_resource_type(resource), &*storage).await {
+                    state.balances.insert((budget.clone(), resource.clone()), balance);
+                }
+            }
+        }
+
+        if invariants.iter().any(|i| matches!(i.rule, InvariantRule::ExclusiveRoles { .. })) {
+            for member in self.get_scope_members(scope_id).await? {
+                let roles = self.get_verified_roles(&member, scope_id).await?;
+                state.roles.insert(member.0, roles);
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// The violations a proposal's declared effects would cause in its scope
+    pub async fn check_proposal_invariants(&self, proposal: &Proposal) -> Result<Vec<InvariantViolation>, GovernanceError> {
+        let scope_id = match &proposal.scope_id {
+            Some(scope_id) => scope_id.0.clone(),
+            None => return Ok(Vec::new()),
+        };
+        let invariants = self.bylaw_invariants(&scope_id).await?;
+        if invariants.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let current = self.invariant_state(&scope_id, &invariants).await?;
+        Ok(check_effects(&invariants, &current, &proposal.effects))
+    }
+
+    /// Refuse a proposal whose effects would break a blocking invariant, and flag the
+    /// violations of the others. `stage` says whether it is being submitted or executed.
+    pub(crate) async fn enforce_proposal_invariants(&self, proposal_id: &str, proposal: &Proposal, stage: &str) -> Result<(), GovernanceError> {
+        let violations: Vec<InvariantViolation> = self.check_proposal_invariants(proposal).await?
+            .into_iter()
+            .filter(|v| !v.pre_existing)
+            .collect();
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(blocking) = violations.iter().find(|v| v.enforcement == Enforcement::Block) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} violates invariant {}: {}", proposal_id, blocking.invariant, blocking.detail
+            )));
+        }
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::InvariantViolationFlagged,
+            proposal.proposer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "stage": stage,
+                "violations": violations
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        Ok(())
+    }
+
+    /// Evaluate a scope's invariants against its actual state after a proposal executed,
+    /// store the result and flag any violations
+    pub(crate) async fn audit_invariants_after_execution(&self, proposal_id: &str, proposal: &Proposal) -> Result<Option<InvariantAudit>, GovernanceError> {
+        let scope_id = match &proposal.scope_id {
+            Some(scope_id) => scope_id.0.clone(),
+            None => return Ok(None),
+        };
+        let invariants = self.bylaw_invariants(&scope_id).await?;
+        if invariants.is_empty() {
+            return Ok(None);
+        }
+
+        let state = self.invariant_state(&scope_id, &invariants).await?;
+        let audit = InvariantAudit {
+            proposal_id: proposal_id.to_string(),
+            scope_id,
+            violations: evaluate_invariants(&invariants, &state),
+            audited_at: chrono::Utc::now().timestamp(),
+        };
+
+        let bytes = serde_json::to_vec(&audit)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize invariant audit: {}", e)))?;
+        self.store_record(&format!("invariants::audit::{}", proposal_id), bytes).await?;
+
+        if !audit.violations.is_empty() {
+            let event = GovernanceEvent::new(
+                GovernanceEventType::InvariantBreached,
+                IdentityId(self.identity.did().to_string()),
+                proposal.scope,
+                proposal.scope_id.clone(),
+                Some(proposal_id.to_string()),
+                serde_json::json!({ "violations": audit.violations })
+            );
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        }
+
+        Ok(Some(audit))
+    }
+
+    /// The invariant audit taken after a proposal executed, if its scope declares invariants
+    pub async fn get_invariant_audit(&self, proposal_id: &str) -> Result<Option<InvariantAudit>, GovernanceError> {
+        self.load_record(&format!("invariants::audit::{}", proposal_id), "invariant audit").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invariants() -> Vec<Invariant> {
+        vec![
+            Invariant {
+                name: "operating_reserve".to_string(),
+                rule: InvariantRule::parse("reserve_floor(budget=operations, resource=Compute, minimum=1000)").unwrap(),
+                enforcement: Enforcement::Block,
+            },
+            Invariant {
+                name: "separation_of_duties".to_string(),
+                rule: InvariantRule::parse("exclusive_roles(Treasurer, Auditor)").unwrap(),
+                enforcement: Enforcement::Flag,
+            },
+        ]
+    }
+
+    fn state() -> InvariantState {
+        InvariantState {
+            balances: HashMap::from([(("operations".to_string(), "Compute".to_string()), 1500)]),
+            roles: HashMap::from([
+                ("did:icn:alice".to_string(), vec!["Treasurer".to_string()]),
+                ("did:icn:bob".to_string(), vec!["Auditor".to_string(), "Member".to_string()]),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_rule_parsing() {
+        assert_eq!(InvariantRule::parse("reserve_floor(budget=ops, minimum=5)").unwrap(), InvariantRule::ReserveFloor {
+            budget: "ops".to_string(), resource: "Compute".to_string(), minimum: 5,
+        });
+        assert!(InvariantRule::parse("reserve_floor(budget=ops)").is_err());
+        assert!(InvariantRule::parse("exclusive_roles(Treasurer)").is_err());
+        assert!(InvariantRule::parse("quorum_floor(0.5)").is_err());
+
+        let flagged = Invariant::from_config(&InvariantConfig {
+            name: "x".to_string(),
+            rule: "exclusive_roles(A, B)".to_string(),
+            enforcement: Some("flag".to_string()),
+        }).unwrap();
+        assert_eq!(flagged.enforcement, Enforcement::Flag);
+    }
+
+    #[test]
+    fn test_effects_checked_against_invariants() {
+        let invariants = invariants();
+        assert!(evaluate_invariants(&invariants, &state()).is_empty());
+
+        // Spending within the reserve and moving roles around cleanly is fine
+        let fine = vec![
+            ProposalEffect::Spend { budget: "operations".to_string(), resource: "Compute".to_string(), amount: 500 },
+            ProposalEffect::AssignRoles { member: "did:icn:carol".to_string(), roles: vec!["Auditor".to_string()] },
+        ];
+        assert!(check_effects(&invariants, &state(), &fine).is_empty());
+
+        let breaking = vec![
+            ProposalEffect::Spend { budget: "operations".to_string(), resource: "Compute".to_string(), amount: 501 },
+            ProposalEffect::AssignRoles { member: "did:icn:alice".to_string(), roles: vec!["Auditor".to_string()] },
+        ];
+        let violations = check_effects(&invariants, &state(), &breaking);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].invariant, "operating_reserve");
+        assert_eq!(violations[0].enforcement, Enforcement::Block);
+        assert_eq!(violations[1].detail, "did:icn:alice holds Treasurer and Auditor");
+        assert!(violations.iter().all(|v| !v.pre_existing));
+
+        // Revoking the conflicting role first keeps separation of duties intact
+        let swap = vec![
+            ProposalEffect::RevokeRoles { member: "did:icn:alice".to_string(), roles: vec!["Treasurer".to_string()] },
+            ProposalEffect::AssignRoles { member: "did:icn:alice".to_string(), roles: vec!["Auditor".to_string()] },
+        ];
+        assert!(check_effects(&invariants, &state(), &swap).is_empty());
+    }
+
+    #[test]
+    fn test_pre_existing_violations_not_attributed() {
+        let mut current = state();
+        current.balances.insert(("operations".to_string(), "Compute".to_string()), 800);
+
+        let violations = check_effects(&invariants(), &current, &[
+            ProposalEffect::Spend { budget: "operations".to_string(), resource: "Compute".to_string(), amount: 100 },
+        ]);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].pre_existing);
+    }
+}
@@ -0,0 +1,339 @@
+/*!
+# Joint Proposals
+
+Two federations sometimes have to decide the same motion together: a shared contract,
+a merger, a common standard. A joint proposal mirrors one motion into every
+participating scope. Each mirror is an ordinary proposal in its scope, submitted by a
+proposer authorized there and voted on by that scope's members. All mirrors carry the
+same correlation CID, the content address of the motion and its participants, in the
+`joint_correlation_cid` metadata field.
+
+Once voting has closed everywhere, each side is judged by its own bylaws: the
+`quorum` and `majority` of its governance section, over its conflict-aware tally. The
+joint motion carries only if every side passed. The combined outcome is anchored once
+under its own CID and linked from every participating scope, and each mirror's status
+is set to the joint result, so a side that passed alone doesn't go on to execute.
+*/
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::coi::ConflictAwareTally;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::outcomes::content_cid;
+
+/// Metadata field linking a mirrored proposal to its joint proposal
+pub const CORRELATION_METADATA_KEY: &str = "joint_correlation_cid";
+
+/// A motion to be put to several scopes at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JointProposalDraft {
+    pub title: String,
+    pub description: String,
+    /// The participating scopes, each with the member submitting the motion there
+    pub sides: Vec<(String, IdentityId)>,
+    /// When voting closes in every scope (Unix timestamp)
+    pub voting_end_time: i64,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// How one scope decided a joint motion
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SideResult {
+    pub tally: ConflictAwareTally,
+    /// Members of the scope counting towards its quorum when the result was taken
+    pub eligible: usize,
+    pub quorum: f64,
+    pub majority: f64,
+    pub passed: bool,
+}
+
+/// One scope's mirror of a joint motion
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JointSide {
+    pub scope_id: String,
+    pub proposal_id: String,
+    pub proposer: IdentityId,
+}
+
+/// The combined result of a joint motion
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JointOutcome {
+    pub correlation_cid: String,
+    /// Each scope's result, in the order of the sides
+    pub results: Vec<(String, SideResult)>,
+    pub passed: bool,
+    pub decided_at: i64,
+}
+
+/// A motion mirrored into several scopes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JointProposal {
+    pub correlation_cid: String,
+    pub title: String,
+    pub sides: Vec<JointSide>,
+    pub voting_end_time: i64,
+    pub created_at: i64,
+    /// CID the combined outcome was anchored under, once decided
+    pub outcome_cid: Option<String>,
+}
+
+/// Whether a tally passes a scope's rules: enough of the `eligible` members voted
+/// (abstentions count towards the quorum) and the votes for reach `majority` of the
+/// votes cast for or against. A majority of one half needs strictly more than half.
+pub fn side_passes(tally: &ConflictAwareTally, eligible: usize, quorum: f64, majority: f64) -> bool {
+    let turnout = tally.votes_for + tally.votes_against + tally.votes_abstain;
+    let quorum_met = if eligible == 0 {
+        quorum <= 0.0
+    } else {
+        turnout as f64 >= quorum * eligible as f64
+    };
+
+    let decisive = tally.votes_for + tally.votes_against;
+    if decisive == 0 {
+        return false;
+    }
+    let share = tally.votes_for as f64 / decisive as f64;
+    let majority_met = if majority <= 0.5 { share > 0.5 } else { share >= majority };
+
+    quorum_met && majority_met
+}
+
+fn joint_key(correlation_cid: &str) -> String {
+    format!("joint::{}", correlation_cid)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    async fn store_joint_proposal(&self, joint: &JointProposal) -> Result<(), GovernanceError> {
+        let bytes = serde_json::to_vec(joint)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize joint proposal: {}", e)))?;
+        self.store_record(&joint_key(&joint.correlation_cid), bytes).await
+    }
+
+    async fn emit_joint_event(
+        &self,
+        event_type: GovernanceEventType,
+        issuer: &IdentityId,
+        side: &JointSide,
+        data: serde_json::Value,
+    ) -> Result<(), GovernanceError> {
+        let event = GovernanceEvent::new(
+            event_type,
+            issuer.clone(),
+            self.scope_type_for(&side.scope_id).await?,
+            Some(IdentityId(side.scope_id.clone())),
+            Some(side.proposal_id.clone()),
+            data
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        Ok(())
+    }
+
+    /// Put a motion to every scope in the draft. Each side's proposer must be allowed
+    /// to create proposals in their scope. Returns the joint proposal, whose
+    /// correlation CID identifies it.
+    pub async fn create_joint_proposal(&self, draft: JointProposalDraft) -> Result<JointProposal, GovernanceError> {
+        let mut scopes: Vec<&str> = draft.sides.iter().map(|(scope_id, _)| scope_id.as_str()).collect();
+        scopes.sort();
+        scopes.dedup();
+        if scopes.len() < 2 || scopes.len() != draft.sides.len() {
+            return Err(GovernanceError::InvalidProposal(
+                "A joint proposal needs at least two distinct scopes".to_string()
+            ));
+        }
+
+        let created_at = chrono::Utc::now().timestamp();
+        let correlation_cid = content_cid(&serde_json::to_vec(&serde_json::json!({
+            "title": draft.title,
+            "description": draft.description,
+            "scopes": scopes,
+            "voting_end_time": draft.voting_end_time,
+            "created_at": created_at
+        })).map_err(|e| GovernanceError::StorageError(format!("Failed to serialize joint motion: {}", e)))?);
+
+        if self.get_joint_proposal(&correlation_cid).await?.is_some() {
+            return Err(GovernanceError::InvalidProposal(format!("Joint proposal {} already exists", correlation_cid)));
+        }
+
+        let mut sides = Vec::new();
+        for (scope_id, proposer) in &draft.sides {
+            let mut metadata = draft.metadata.clone();
+            metadata.insert(CORRELATION_METADATA_KEY.to_string(), correlation_cid.clone());
+
+            // Mirrors are named after their scope so their IDs don't collide, and open for
+            // voting as soon as they're submitted
+            let proposal = Proposal {
+                title: format!("{} ({})", draft.title, scope_id),
+                description: draft.description.clone(),
+                proposer: proposer.clone(),
+                scope: self.scope_type_for(scope_id).await?,
+                scope_id: Some(IdentityId(scope_id.clone())),
+                status: ProposalStatus::Active,
+                voting_end_time: draft.voting_end_time,
+                votes_for: 0,
+                votes_against: 0,
+                votes_abstain: 0,
+                ccl_code: None,
+                wasm_bytes: None,
+                wasm_cid: None,
+                thread_id: None,
+                metadata,
+                proposal_type: None,
+                created_at: 0,
+                effects: Vec::new(),
+                effective_at: None,
+            };
+            let proposal_id = self.process_proposal(proposal).await?;
+            sides.push(JointSide { scope_id: scope_id.clone(), proposal_id, proposer: proposer.clone() });
+        }
+
+        let joint = JointProposal {
+            correlation_cid: correlation_cid.clone(),
+            title: draft.title,
+            sides,
+            voting_end_time: draft.voting_end_time,
+            created_at,
+            outcome_cid: None,
+        };
+        self.store_joint_proposal(&joint).await?;
+
+        let scope_ids: Vec<&str> = joint.sides.iter().map(|s| s.scope_id.as_str()).collect();
+        for side in &joint.sides {
+            self.append_to_index(&format!("joint_index::{}", side.scope_id), &correlation_cid).await?;
+            self.emit_joint_event(GovernanceEventType::JointProposalCreated, &side.proposer, side, serde_json::json!({
+                "correlation_cid": correlation_cid,
+                "title": joint.title,
+                "scopes": scope_ids
+            })).await?;
+        }
+
+        Ok(joint)
+    }
+
+    /// Get a joint proposal by its correlation CID
+    pub async fn get_joint_proposal(&self, correlation_cid: &str) -> Result<Option<JointProposal>, GovernanceError> {
+        self.load_record(&joint_key(correlation_cid), "joint proposal").await
+    }
+
+    /// Correlation CIDs of every joint proposal a scope takes part in
+    pub async fn get_scope_joint_proposals(&self, scope_id: &str) -> Result<Vec<String>, GovernanceError> {
+        self.load_index(&format!("joint_index::{}", scope_id)).await
+    }
+
+    /// Judge one scope's mirror by that scope's quorum and majority
+    pub async fn joint_side_result(&self, side: &JointSide) -> Result<SideResult, GovernanceError> {
+        let governance = self.load_governance_config(&side.scope_id).await?
+            .and_then(|config| config.governance);
+        let quorum = governance.as_ref().and_then(|g| g.quorum).unwrap_or(0.0);
+        let majority = governance.as_ref().and_then(|g| g.majority).unwrap_or(0.5);
+
+        let tally = self.conflict_aware_tally(&side.proposal_id).await?;
+        let eligible = self.quorum_denominator(&side.scope_id).await?;
+        let passed = side_passes(&tally, eligible, quorum, majority);
+        Ok(SideResult { tally, eligible, quorum, majority, passed })
+    }
+
+    /// Decide a joint motion once voting has closed: it carries only if every side
+    /// passed. The combined outcome is anchored, linked from every scope, and each
+    /// mirror's status set to the joint result.
+    pub async fn decide_joint_proposal(&self, correlation_cid: &str) -> Result<JointOutcome, GovernanceError> {
+        let mut joint = self.get_joint_proposal(correlation_cid).await?
+            .ok_or_else(|| GovernanceError::ProposalNotFound(format!("joint proposal {}", correlation_cid)))?;
+        if joint.outcome_cid.is_some() {
+            return Err(GovernanceError::InvalidProposal(format!("Joint proposal {} is already decided", correlation_cid)));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if now < joint.voting_end_time {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Voting on joint proposal {} is open until {}", correlation_cid, joint.voting_end_time
+            )));
+        }
+
+        let mut results = Vec::new();
+        for side in &joint.sides {
+            results.push((side.scope_id.clone(), self.joint_side_result(side).await?));
+        }
+        let outcome = JointOutcome {
+            correlation_cid: correlation_cid.to_string(),
+            passed: results.iter().all(|(_, result)| result.passed),
+            results,
+            decided_at: now,
+        };
+
+        let outcome_bytes = serde_json::to_vec(&outcome)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize joint outcome: {}", e)))?;
+        let storage = self.storage.lock().await;
+        let outcome_cid = storage.put_blob(&outcome_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to anchor joint outcome: {}", e)))?
+            .to_string();
+        drop(storage);
+
+        joint.outcome_cid = Some(outcome_cid.clone());
+        self.store_joint_proposal(&joint).await?;
+
+        let status = if outcome.passed { ProposalStatus::Passed } else { ProposalStatus::Rejected };
+        let issuer = IdentityId(self.identity.did().to_string());
+        for (side, (_, result)) in joint.sides.iter().zip(&outcome.results) {
+            let mut proposal = self.get_proposal(side.proposal_id.clone()).await?;
+            proposal.status = status.clone();
+            let proposal_bytes = serde_json::to_vec(&proposal)
+                .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to serialize proposal: {}", e)))?;
+            self.store_record(&format!("proposal::{}", side.proposal_id), proposal_bytes).await?;
+
+            self.append_to_index(&format!("joint_outcomes::{}", side.scope_id), &outcome_cid).await?;
+            self.emit_joint_event(GovernanceEventType::JointOutcomeAnchored, &issuer, side, serde_json::json!({
+                "correlation_cid": correlation_cid,
+                "outcome_cid": outcome_cid,
+                "passed": outcome.passed,
+                "side_passed": result.passed
+            })).await?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Get an anchored joint outcome by CID
+    pub async fn get_joint_outcome(&self, outcome_cid: &str) -> Result<JointOutcome, GovernanceError> {
+        let cid = cid::Cid::try_from(outcome_cid)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Invalid joint outcome CID: {}", e)))?;
+        let storage = self.storage.lock().await;
+        let bytes = storage.get_blob(&cid)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to load joint outcome: {}", e)))?
+            .ok_or_else(|| GovernanceError::ProposalNotFound(format!("joint outcome {}", outcome_cid)))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize joint outcome: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(votes_for: u64, votes_against: u64, votes_abstain: u64) -> ConflictAwareTally {
+        ConflictAwareTally { votes_for, votes_against, votes_abstain, excluded: Vec::new() }
+    }
+
+    #[test]
+    fn test_each_side_judged_by_its_own_rules() {
+        // Simple majority needs more than half of the decisive votes
+        assert!(side_passes(&tally(3, 2, 0), 10, 0.0, 0.5));
+        assert!(!side_passes(&tally(2, 2, 0), 10, 0.0, 0.5));
+
+        // The same votes fail a two-thirds majority
+        assert!(!side_passes(&tally(3, 2, 0), 10, 0.0, 0.67));
+        assert!(side_passes(&tally(4, 2, 0), 10, 0.0, 0.66));
+
+        // Abstentions count towards the quorum but not the majority
+        assert!(!side_passes(&tally(3, 0, 0), 10, 0.5, 0.5));
+        assert!(side_passes(&tally(3, 0, 2), 10, 0.5, 0.5));
+        assert!(!side_passes(&tally(0, 0, 5), 10, 0.5, 0.5));
+    }
+}
@@ -23,9 +23,40 @@ pub mod ast;
 pub mod parser;
 pub mod config;
 pub mod events;
+pub mod transparency;
+pub mod minutes;
+pub mod compilation;
+pub mod coi;
+pub mod deliberation;
+pub mod receipts;
+pub mod revisions;
+pub mod outcomes;
+pub mod standing;
+pub mod ties;
+pub mod execution_policy;
+pub mod vm_host;
+pub mod retention;
+pub mod referenda;
+pub mod cost_sharing;
+pub mod invariants;
+pub mod onboarding;
+pub mod joint;
+pub mod member_classes;
+pub mod offboarding;
+pub mod participation;
+pub mod attestations;
+pub mod withdrawal;
+pub mod calendar;
+pub mod cosponsorship;
+pub mod recount;
+pub mod bylaws;
+pub mod surplus;
+pub mod scheduling;
 
 // Re-export for public use
 pub use events::GovernanceEventType;
+pub use compilation::ProposalCompiler;
+pub use outcomes::NotificationDispatcher;
 use events::{GovernanceEvent, EventEmitter};
 
 /// Helper function to create a SHA-256 multihash (copied from storage crate)
@@ -56,6 +87,16 @@ pub enum GovernanceError {
     
     #[error("Event emission error: {0}")]
     EventEmissionError(String),
+    
+    #[error("Compilation failed: {0}")]
+    CompilationFailed(String),
+    
+    #[error("Deliberation period for proposal {proposal_id} has not elapsed; voting can open at {earliest_allowed}")]
+    DeliberationPeriodActive {
+        proposal_id: String,
+        /// Earliest time voting may open (Unix timestamp)
+        earliest_allowed: i64,
+    },
 }
 
 /// Vote choice in a governance proposal
@@ -76,6 +117,10 @@ pub enum ProposalStatus {
     Executed,
     Expired,
     Finalized,
+    /// Withdrawn before it was finalized
+    Withdrawn,
+    /// Closed in favour of a newer proposal
+    Superseded,
 }
 
 /// A governance proposal
@@ -117,8 +162,33 @@ pub struct Proposal {
     /// Compiled WASM for this proposal (if applicable)
     pub wasm_bytes: Option<Vec<u8>>,
     
+    /// CID of the compiled WASM, set when the proposal is activated
+    #[serde(default)]
+    pub wasm_cid: Option<String>,
+    
     /// Link to AgoraNet thread ID for deliberation
     pub thread_id: Option<String>,
+    
+    /// Structured metadata (e.g., counterparties, vendors, beneficiaries)
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    
+    /// Name of the proposal type from the scope's proposal process
+    #[serde(default)]
+    pub proposal_type: Option<String>,
+    
+    /// When the proposal was submitted (Unix timestamp), set by the kernel
+    #[serde(default)]
+    pub created_at: i64,
+    
+    /// What executing the proposal will change, checked against the scope's invariants
+    #[serde(default)]
+    pub effects: Vec<invariants::ProposalEffect>,
+    
+    /// When the proposal takes effect if it passes (Unix timestamp); until then it is
+    /// held rather than executed
+    #[serde(default)]
+    pub effective_at: Option<i64>,
 }
 
 impl Proposal {
@@ -200,6 +270,12 @@ pub struct GovernanceKernel<S> {
     events: Arc<Mutex<HashMap<String, GovernanceEvent>>>,
     // Add VC storage for issued credentials
     credentials: Arc<Mutex<HashMap<String, VerifiableCredential>>>,
+    // Compiler for proposal CCL code, used on activation
+    compiler: Option<Arc<dyn ProposalCompiler>>,
+    // Dispatcher for outcome evidence, used on finalization and execution
+    notifier: Option<Arc<dyn NotificationDispatcher>>,
+    // Notified when a co-op contests its share of shared costs
+    cost_dispute_hook: Option<Arc<dyn icn_economics::cost_allocation::CostDisputeHook>>,
 }
 
 impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
@@ -210,6 +286,9 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
             identity,
             events: Arc::new(Mutex::new(HashMap::new())),
             credentials: Arc::new(Mutex::new(HashMap::new())),
+            compiler: None,
+            notifier: None,
+            cost_dispute_hook: None,
         }
     }
 
@@ -272,7 +351,9 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
                     // Check if any assigned roles have the required permission
                     for role in defined_roles {
                         if assigned_role_names.contains(&role.name) && role.permissions.contains(&permission.to_string()) {
-                            return Ok(true);
+                            // The caller's member class may still withhold it
+                            let classes = governance.member_classes.as_deref().unwrap_or_default();
+                            return self.check_class_permission(caller_id, scope_id, classes, permission).await;
                         }
                     }
                 }
@@ -287,7 +368,7 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
     }
 
     /// Process a proposal by submitting it to the governance system
-    pub async fn process_proposal(&self, proposal: Proposal) -> Result<String, GovernanceError> {
+    pub async fn process_proposal(&self, mut proposal: Proposal) -> Result<String, GovernanceError> {
         // Get the scope_id string for authorization check
         let scope_id_str = if let Some(sid) = &proposal.scope_id {
             sid.0.as_str()
@@ -308,6 +389,31 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         // Create an ID for the proposal
         let proposal_id = proposal.calculate_id();
         
+        // Refuse proposals whose declared effects would break a blocking bylaw invariant
+        self.enforce_proposal_invariants(&proposal_id, &proposal, "submission").await?;
+        
+        // A counter-proposal must name a held proposal that can still be cancelled
+        self.check_counter_proposal(&proposal).await?;
+        
+        // The deliberation period runs from submission
+        proposal.created_at = chrono::Utc::now().timestamp();
+        
+        // A submission is stored as a Draft with nothing counted yet. The WASM voters
+        // approve is only ever compiled from `ccl_code`, so any supplied module is dropped.
+        let open_now = proposal.status == ProposalStatus::Active;
+        proposal.status = ProposalStatus::Draft;
+        proposal.wasm_bytes = None;
+        proposal.wasm_cid = None;
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.votes_abstain = 0;
+        
+        // Types with a discussion period, or with co-sponsors to gather, wait in Draft
+        // until they're activated
+        let open_now = open_now
+            && self.co_sponsorship_rule(&proposal).await?.is_none()
+            && self.earliest_voting_time_for(&proposal).await?.is_none();
+        
         // Serialize the proposal
         let proposal_bytes = serde_json::to_vec(&proposal)
             .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to serialize proposal: {}", e)))?;
@@ -321,6 +427,10 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         storage.put_kv(key_cid, proposal_bytes)
             .await
             .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+        
+        // Track the proposal in the scope's proposal index
+        self.index_scope_proposal(scope_id_str, &proposal_id).await?;
         
         // Create an event for the proposal creation
         let event_data = serde_json::json!({
@@ -342,11 +452,22 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         self.emit_event(event).await
             .map_err(|e| GovernanceError::EventEmissionError(e))?;
         
+        // Flag members whose registered interests the proposal touches
+        self.flag_conflicts(&proposal_id).await?;
+        
+        // A proposal submitted as Active opens the way an activation would, compiling
+        // its CCL code first
+        if open_now {
+            let proposer = proposal.proposer.clone();
+            self.open_voting(proposal_id.clone(), proposal, &proposer).await?;
+        }
+        
         Ok(proposal_id)
     }
 
-    /// Record a vote on a proposal
-    pub async fn record_vote(&self, vote: Vote) -> Result<(), GovernanceError> {
+    /// Record a vote on a proposal. Returns the voter's ballot receipt, which is the only
+    /// copy of the nonce that opens its commitment.
+    pub async fn record_vote(&self, vote: Vote) -> Result<receipts::BallotReceipt, GovernanceError> {
         // Get the scope_id string for authorization check
         let scope_id_str = if let Some(sid) = &vote.scope_id {
             sid.0.as_str()
@@ -364,14 +485,24 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
             )));
         }
         
-        // Also check if the proposal exists and is in a votable state
+        // Also check if the proposal exists and is open for voting. Drafts open through
+        // activation, which enforces deliberation, co-sponsorship and compilation.
         let proposal = self.get_proposal(vote.proposal_id.clone()).await?;
-        if proposal.status != ProposalStatus::Active && proposal.status != ProposalStatus::Draft {
+        if proposal.status != ProposalStatus::Active {
             return Err(GovernanceError::InvalidProposal(format!(
                 "Cannot vote on proposal with status {:?}", proposal.status
             )));
         }
         
+        // Members suspended for unpaid dues can't vote
+        self.check_dues_standing(&vote.voter, scope_id_str).await?;
+        
+        // Conflicted members may need to declare or recuse before voting
+        self.check_vote_conflicts(&vote, scope_id_str).await?;
+        
+        // A member who already voted may only change it if the scope allows
+        let revision = self.check_vote_revision(&vote, scope_id_str).await?;
+        
         // Serialize the vote
         let vote_bytes = serde_json::to_vec(&vote)
             .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize vote: {}", e)))?;
@@ -385,13 +516,24 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         storage.put_kv(key_cid, vote_bytes)
             .await
             .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+        
+        // Track the voter so the tally can be recomputed
+        self.append_to_index(&format!("proposal::voters::{}", vote.proposal_id), &vote.voter.0).await?;
+        
+        // Give the voter a receipt they can check against the published tally
+        let receipt = self.issue_ballot_receipt(&vote).await?;
+        
+        // Keep the full history of the member's votes for audit
+        self.record_vote_revision(&vote, revision).await?;
             
         // After vote is successfully recorded, emit an event
         let event_data = serde_json::json!({
             "voter": vote.voter.0,
             "choice": format!("{:?}", vote.choice),
             "weight": vote.weight,
-            "reason": vote.reason
+            "reason": vote.reason,
+            "revision": format!("{:?}", revision)
         });
         
         let event = GovernanceEvent::new(
@@ -407,7 +549,7 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         self.emit_event(event).await
             .map_err(|e| GovernanceError::EventEmissionError(e))?;
         
-        Ok(())
+        Ok(receipt)
     }
 
     /// Finalize a proposal based on voting results
@@ -415,9 +557,26 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         // Get the proposal 
         let proposal = self.get_proposal(proposal_id.clone()).await?;
         
-        // Update the proposal status (in a real implementation)
+        // Withdrawn and superseded proposals are closed for good
+        if matches!(proposal.status, ProposalStatus::Withdrawn | ProposalStatus::Superseded) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot finalize proposal with status {:?}", proposal.status
+            )));
+        }
+        
+        // Break a tie according to the proposal type's policy; a re-vote reopens voting
+        let tie_break = self.apply_tie_break(&proposal_id, &proposal).await?;
+        if let Some(ties::TieBreakRecord { outcome: ties::TieOutcome::Revote { .. }, .. }) = tie_break {
+            return Ok(());
+        }
+        
+        // A broken tie decides the outcome; otherwise it follows from the tally
         let mut updated_proposal = proposal.clone();
-        updated_proposal.status = ProposalStatus::Finalized;
+        updated_proposal.status = match &tie_break {
+            Some(ties::TieBreakRecord { outcome: ties::TieOutcome::Passed, .. }) => ProposalStatus::Passed,
+            Some(ties::TieBreakRecord { outcome: ties::TieOutcome::Rejected, .. }) => ProposalStatus::Rejected,
+            _ => ProposalStatus::Finalized,
+        };
         
         // Serialize the updated proposal
         let proposal_bytes = serde_json::to_vec(&updated_proposal)
@@ -432,13 +591,25 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         storage.put_kv(key_cid, proposal_bytes)
             .await
             .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
         
+        // Publish the receipt hashes of every counted ballot
+        self.publish_receipt_hashes(&proposal_id, &IdentityId(self.identity.did().to_string())).await?;
+        
+        // Keep what the tally depended on so it can be recounted later
+        self.record_tally_inputs(&proposal_id).await?;
+        
+        // Send every member verifiable evidence of the result
+        self.issue_outcome_evidence(&proposal_id, None).await?;
+        
+        let tally = self.conflict_aware_tally(&proposal_id).await?;
         let event_data = serde_json::json!({
             "title": proposal.title,
             "status": format!("{:?}", updated_proposal.status),
-            "votes_for": proposal.votes_for,
-            "votes_against": proposal.votes_against,
-            "votes_abstain": proposal.votes_abstain
+            "votes_for": tally.votes_for,
+            "votes_against": tally.votes_against,
+            "votes_abstain": tally.votes_abstain,
+            "tie_break": tie_break
         });
         
         let event = GovernanceEvent::new(
@@ -446,7 +617,7 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
             proposal.proposer.clone(),
             proposal.scope,
             proposal.scope_id.clone(),
-            Some(proposal_id),
+            Some(proposal_id.clone()),
             event_data
         );
         
@@ -454,14 +625,52 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         self.emit_event(event).await
             .map_err(|e| GovernanceError::EventEmissionError(e))?;
         
+        // Hold a passed proposal with a later effective time, and cancel the held
+        // proposal a passed counter-proposal names
+        self.schedule_if_deferred(&proposal_id, &updated_proposal).await?;
+        self.apply_counter_proposal(&proposal_id, &updated_proposal).await?;
+        
         Ok(())
     }
     
     /// Execute a proposal after it has been finalized and approved
     pub async fn execute_proposal(&self, proposal_id: String) -> Result<(), GovernanceError> {
+        self.execute_proposal_with_receipt(proposal_id, None).await
+    }
+    
+    /// Execute a proposal, recording the CID of the receipt of the run that carried it
+    /// out in the outcome evidence sent to members
+    pub async fn execute_proposal_with_receipt(&self, proposal_id: String, execution_receipt_cid: Option<String>) -> Result<(), GovernanceError> {
+        // Proposals held for a later effective time are executed by the scheduler
+        let proposal = self.get_proposal(proposal_id.clone()).await?;
+        self.check_effective(&proposal_id, &proposal).await?;
+        
+        self.execute_passed_proposal(proposal_id, execution_receipt_cid).await
+    }
+    
+    /// Execute a proposal without checking its effective time
+    pub(crate) async fn execute_passed_proposal(&self, proposal_id: String, execution_receipt_cid: Option<String>) -> Result<(), GovernanceError> {
         // Get the proposal
         let proposal = self.get_proposal(proposal_id.clone()).await?;
         
+        if matches!(proposal.status, ProposalStatus::Withdrawn | ProposalStatus::Superseded | ProposalStatus::Rejected) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot execute proposal with status {:?}", proposal.status
+            )));
+        }
+        
+        // A decided proposal only runs if it passed
+        if matches!(proposal.status, ProposalStatus::Finalized | ProposalStatus::Passed)
+            && !self.proposal_has_passed(&proposal_id, &proposal).await?
+        {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} did not pass, so it can't be executed", proposal_id
+            )));
+        }
+        
+        // The scope's state may have moved on since submission, so check the effects again
+        self.enforce_proposal_invariants(&proposal_id, &proposal, "execution").await?;
+        
         // Update the proposal status (in a real implementation)
         let mut updated_proposal = proposal.clone();
         updated_proposal.status = ProposalStatus::Executed;
@@ -479,10 +688,18 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         storage.put_kv(key_cid, proposal_bytes)
             .await
             .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+        
+        // Send every member verifiable evidence of the execution
+        self.issue_outcome_evidence(&proposal_id, execution_receipt_cid.clone()).await?;
+        
+        // Check the invariants against what execution actually did
+        self.audit_invariants_after_execution(&proposal_id, &proposal).await?;
         
         let event_data = serde_json::json!({
             "title": proposal.title,
             "execution_status": "completed",
+            "execution_receipt_cid": execution_receipt_cid,
             "execution_timestamp": chrono::Utc::now().timestamp()
         });
         
@@ -582,7 +799,13 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
                 votes_abstain: 0,
                 ccl_code: None,
                 wasm_bytes: None,
+                wasm_cid: None,
                 thread_id: None,
+                metadata: HashMap::new(),
+                proposal_type: None,
+                created_at: 0,
+                effects: Vec::new(),
+                effective_at: None,
             };
             
             Ok(proposal)
@@ -708,6 +931,10 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
             
         storage.put_kv(index_cid, index_bytes).await
             .map_err(|e| GovernanceError::StorageError(format!("Failed to store index: {}", e)))?;
+        drop(storage);
+        
+        // Add the subject to the scope's roster
+        self.append_to_index(&referenda::scope_members_index(scope_id), &subject_id.0).await?;
         
         // Optional: Store in DAG for immutability
         if options.store_in_dag {
@@ -875,6 +1102,50 @@ impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
         Ok(())
     }
 
+    /// Add a proposal ID to the proposal index of a scope
+    async fn index_scope_proposal(&self, scope_id: &str, proposal_id: &str) -> Result<(), GovernanceError> {
+        let index_key = format!("proposal_index::{}", scope_id);
+        let index_cid = self.create_key_cid(&index_key)?;
+        
+        let storage = self.storage.lock().await;
+        let mut proposal_ids = match storage.get_kv(&index_cid).await {
+            Ok(Some(bytes)) => {
+                serde_json::from_slice::<Vec<String>>(&bytes)
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize proposal index: {}", e)))?
+            },
+            Ok(None) => Vec::new(),
+            Err(e) => return Err(GovernanceError::StorageError(format!("Failed to load proposal index: {}", e))),
+        };
+        
+        if !proposal_ids.iter().any(|id| id == proposal_id) {
+            proposal_ids.push(proposal_id.to_string());
+        }
+        
+        let index_bytes = serde_json::to_vec(&proposal_ids)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize proposal index: {}", e)))?;
+            
+        storage.put_kv(index_cid, index_bytes).await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to store proposal index: {}", e)))?;
+        
+        Ok(())
+    }
+    
+    /// Get the IDs of all proposals submitted within a scope
+    pub async fn get_scope_proposal_ids(&self, scope_id: &str) -> Result<Vec<String>, GovernanceError> {
+        let index_key = format!("proposal_index::{}", scope_id);
+        let index_cid = self.create_key_cid(&index_key)?;
+        
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&index_cid).await {
+            Ok(Some(bytes)) => {
+                serde_json::from_slice::<Vec<String>>(&bytes)
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize proposal index: {}", e)))
+            },
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load proposal index: {}", e))),
+        }
+    }
+
     /// Get the assigned roles for an identity within a scope (for backward compatibility)
     pub async fn get_assigned_roles(&self, identity_id: &IdentityId, scope_id: &str) -> Result<Vec<String>, GovernanceError> {
         self.get_verified_roles(identity_id, scope_id).await
@@ -1202,6 +1473,9 @@ impl CclInterpreter {
                     let mut majority = None;
                     let mut term_length = None;
                     let mut roles = None;
+                    let mut invariants = None;
+                    let mut member_classes = None;
+                    let mut calendar = None;
                     
                     for gov_pair in gov_pairs {
                         match gov_pair.key.as_str() {
@@ -1264,6 +1538,157 @@ impl CclInterpreter {
                                     }
                                 }
                             },
+                            "invariants" => {
+                                if let ast::CclValue::Array(invariant_values) = &gov_pair.value {
+                                    let mut invariant_vec = Vec::new();
+                                    
+                                    for invariant_val in invariant_values {
+                                        if let ast::CclValue::Object(invariant_pairs) = invariant_val {
+                                            let mut name = String::new();
+                                            let mut rule = String::new();
+                                            let mut enforcement = None;
+                                            
+                                            for ip in invariant_pairs {
+                                                if let ast::CclValue::String(s) = &ip.value {
+                                                    match ip.key.as_str() {
+                                                        "name" => name = s.clone(),
+                                                        "rule" => rule = s.clone(),
+                                                        "enforcement" => enforcement = Some(s.clone()),
+                                                        _ => {}
+                                                    }
+                                                }
+                                            }
+                                            
+                                            if !name.is_empty() && !rule.is_empty() {
+                                                invariant_vec.push(config::InvariantConfig { name, rule, enforcement });
+                                            }
+                                        }
+                                    }
+                                    
+                                    if !invariant_vec.is_empty() {
+                                        invariants = Some(invariant_vec);
+                                    }
+                                }
+                            },
+                            "member_classes" => {
+                                if let ast::CclValue::Array(class_values) = &gov_pair.value {
+                                    let mut class_vec = Vec::new();
+
+                                    for class_val in class_values {
+                                        if let ast::CclValue::Object(class_pairs) = class_val {
+                                            let mut class = config::MemberClassConfig {
+                                                name: String::new(),
+                                                permissions: None,
+                                                counts_toward_quorum: None,
+                                                promotes_to: None,
+                                                default: None,
+                                            };
+
+                                            for cp in class_pairs {
+                                                match (cp.key.as_str(), &cp.value) {
+                                                    ("name", ast::CclValue::String(s)) => class.name = s.clone(),
+                                                    ("permissions", ast::CclValue::Array(perm_vals)) => {
+                                                        class.permissions = Some(perm_vals.iter()
+                                                            .filter_map(|pv| match pv {
+                                                                ast::CclValue::String(s) => Some(s.clone()),
+                                                                _ => None,
+                                                            })
+                                                            .collect());
+                                                    },
+                                                    ("counts_toward_quorum", ast::CclValue::Boolean(b)) => class.counts_toward_quorum = Some(*b),
+                                                    ("promotes_to", ast::CclValue::String(s)) => class.promotes_to = Some(s.clone()),
+                                                    ("default", ast::CclValue::Boolean(b)) => class.default = Some(*b),
+                                                    _ => {}
+                                                }
+                                            }
+
+                                            if !class.name.is_empty() {
+                                                class_vec.push(class);
+                                            }
+                                        }
+                                    }
+
+                                    if !class_vec.is_empty() {
+                                        member_classes = Some(class_vec);
+                                    }
+                                }
+                            },
+                            "calendar" => {
+                                if let ast::CclValue::Array(event_values) = &gov_pair.value {
+                                    let mut event_vec = Vec::new();
+
+                                    for event_val in event_values {
+                                        if let ast::CclValue::Object(event_pairs) = event_val {
+                                            let mut event = config::CalendarEventConfig {
+                                                name: String::new(),
+                                                recurrence: String::new(),
+                                                starts_on: String::new(),
+                                                deadlines: None,
+                                                standing_proposal: None,
+                                            };
+
+                                            for ep in event_pairs {
+                                                match (ep.key.as_str(), &ep.value) {
+                                                    ("name", ast::CclValue::String(s)) => event.name = s.clone(),
+                                                    ("recurrence", ast::CclValue::String(s)) => event.recurrence = s.clone(),
+                                                    ("starts_on", ast::CclValue::String(s)) => event.starts_on = s.clone(),
+                                                    ("deadlines", ast::CclValue::Array(deadline_vals)) => {
+                                                        event.deadlines = Some(deadline_vals.iter()
+                                                            .filter_map(|dv| match dv {
+                                                                ast::CclValue::Object(deadline_pairs) => {
+                                                                    let mut name = None;
+                                                                    let mut days_before = None;
+                                                                    for dp in deadline_pairs {
+                                                                        match (dp.key.as_str(), &dp.value) {
+                                                                            ("name", ast::CclValue::String(s)) => name = Some(s.clone()),
+                                                                            ("days_before", ast::CclValue::Number(n)) => days_before = Some(*n as u64),
+                                                                            _ => {}
+                                                                        }
+                                                                    }
+                                                                    Some(config::CalendarDeadlineConfig { name: name?, days_before: days_before? })
+                                                                },
+                                                                _ => None,
+                                                            })
+                                                            .collect());
+                                                    },
+                                                    ("standing_proposal", ast::CclValue::Object(sp_pairs)) => {
+                                                        let mut title = None;
+                                                        let mut description = None;
+                                                        let mut proposal_type = None;
+                                                        let mut days_before = None;
+                                                        for sp in sp_pairs {
+                                                            match (sp.key.as_str(), &sp.value) {
+                                                                ("title", ast::CclValue::String(s)) => title = Some(s.clone()),
+                                                                ("description", ast::CclValue::String(s)) => description = Some(s.clone()),
+                                                                ("proposal_type", ast::CclValue::String(s)) => proposal_type = Some(s.clone()),
+                                                                ("days_before", ast::CclValue::Number(n)) => days_before = Some(*n as u64),
+                                                                _ => {}
+                                                            }
+                                                        }
+                                                        if let (Some(title), Some(days_before)) = (title, days_before) {
+                                                            event.standing_proposal = Some(config::StandingProposalConfig {
+                                                                title,
+                                                                description,
+                                                                proposal_type,
+                                                                days_before,
+                                                            });
+                                                        }
+                                                    },
+                                                    _ => {}
+                                                }
+                                            }
+
+                                            if !event.name.is_empty() {
+                                                event_vec.push(event);
+                                            }
+                                        }
+                                    }
+
+                                    if !event_vec.is_empty() {
+                                        calendar = Some(event_vec);
+                                    }
+                                }
+                            },
                             _ => {}
                         }
                     }
@@ -1274,6 +1699,9 @@ impl CclInterpreter {
                         majority,
                         term_length,
                         roles,
+                        invariants,
+                        member_classes,
+                        calendar,
                     });
                 }
             }
@@ -1290,6 +1718,7 @@ impl CclInterpreter {
                     let mut onboarding = None;
                     let mut dues = None;
                     let mut offboarding = None;
+                    let mut participation = None;
                     
                     for mem_pair in mem_pairs {
                         match mem_pair.key.as_str() {
@@ -1342,6 +1771,7 @@ impl CclInterpreter {
                                     let mut amount = None;
                                     let mut frequency = None;
                                     let mut variable_options = None;
+                                    let mut late_policy = None;
                                     
                                     for dues_pair in dues_pairs {
                                         match dues_pair.key.as_str() {
@@ -1390,6 +1820,36 @@ impl CclInterpreter {
                                                     }
                                                 }
                                             },
+                                            "late_policy" => {
+                                                if let ast::CclValue::Object(late_pairs) = &dues_pair.value {
+                                                    let mut grace_period_days = None;
+                                                    let mut late_fee = None;
+                                                    let mut late_fee_bps = None;
+                                                    let mut suspend_voting_after_days = None;
+                                                    let mut waiver_approvals = None;
+                                                    
+                                                    for lp in late_pairs {
+                                                        if let ast::CclValue::Number(n) = &lp.value {
+                                                            match lp.key.as_str() {
+                                                                "grace_period_days" => grace_period_days = Some(*n as u64),
+                                                                "late_fee" => late_fee = Some(*n as u64),
+                                                                "late_fee_bps" => late_fee_bps = Some(*n as u64),
+                                                                "suspend_voting_after_days" => suspend_voting_after_days = Some(*n as u64),
+                                                                "waiver_approvals" => waiver_approvals = Some(*n as u64),
+                                                                _ => {}
+                                                            }
+                                                        }
+                                                    }
+                                                    
+                                                    late_policy = Some(config::DuesLatePolicy {
+                                                        grace_period_days,
+                                                        late_fee,
+                                                        late_fee_bps,
+                                                        suspend_voting_after_days,
+                                                        waiver_approvals,
+                                                    });
+                                                }
+                                            },
                                             _ => {}
                                         }
                                     }
@@ -1398,6 +1858,7 @@ impl CclInterpreter {
                                         amount,
                                         frequency,
                                         variable_options,
+                                        late_policy,
                                     });
                                 }
                             },
@@ -1428,6 +1889,27 @@ impl CclInterpreter {
                                     });
                                 }
                             },
+                            "participation" => {
+                                if let ast::CclValue::Object(part_pairs) = &mem_pair.value {
+                                    let mut scoring = config::ParticipationScoring::default();
+                                    
+                                    for part_pair in part_pairs {
+                                        match (part_pair.key.as_str(), &part_pair.value) {
+                                            ("vote_points", ast::CclValue::Number(n)) => scoring.vote_points = Some(*n as u64),
+                                            ("proposal_points", ast::CclValue::Number(n)) => scoring.proposal_points = Some(*n as u64),
+                                            ("meeting_points", ast::CclValue::Number(n)) => scoring.meeting_points = Some(*n as u64),
+                                            ("contribution_points", ast::CclValue::Number(n)) => scoring.contribution_points = Some(*n as u64),
+                                            ("window_days", ast::CclValue::Number(n)) => scoring.window_days = Some(*n as u64),
+                                            ("weights_votes", ast::CclValue::Boolean(b)) => scoring.weights_votes = Some(*b),
+                                            ("points_per_vote_weight", ast::CclValue::Number(n)) => scoring.points_per_vote_weight = Some(*n as u64),
+                                            ("max_vote_weight", ast::CclValue::Number(n)) => scoring.max_vote_weight = Some(*n as u64),
+                                            _ => {}
+                                        }
+                                    }
+                                    
+                                    participation = Some(scoring);
+                                }
+                            },
                             _ => {}
                         }
                     }
@@ -1436,6 +1918,7 @@ impl CclInterpreter {
                         onboarding,
                         dues,
                         offboarding,
+                        participation,
                     });
                 }
             }
@@ -1450,8 +1933,25 @@ impl CclInterpreter {
             if pair.key == "proposals" {
                 if let ast::CclValue::Object(prop_pairs) = &pair.value {
                     let mut types = None;
+                    let mut vote_changes = None;
                     
                     for prop_pair in prop_pairs {
+                        if prop_pair.key == "vote_changes" {
+                            if let ast::CclValue::Object(vc_pairs) = &prop_pair.value {
+                                let mut policy = config::VoteChangePolicy::default();
+                                
+                                for vc_pair in vc_pairs {
+                                    match (vc_pair.key.as_str(), &vc_pair.value) {
+                                        ("allow_change", ast::CclValue::Boolean(b)) => policy.allow_change = Some(*b),
+                                        ("allow_retraction", ast::CclValue::Boolean(b)) => policy.allow_retraction = Some(*b),
+                                        ("window_hours", ast::CclValue::Number(n)) => policy.window_hours = Some(*n as u64),
+                                        _ => {}
+                                    }
+                                }
+                                
+                                vote_changes = Some(policy);
+                            }
+                        }
                         if prop_pair.key == "types" {
                             if let ast::CclValue::Array(type_vals) = &prop_pair.value {
                                 let mut type_vec = Vec::new();
@@ -1462,6 +1962,11 @@ impl CclInterpreter {
                                         let mut quorum_modifier = None;
                                         let mut majority_modifier = None;
                                         let mut discussion_period_days = None;
+                                        let mut allow_fast_track = None;
+                                        let mut tie_break = None;
+                                        let mut revote_extension_hours = None;
+                                        let mut co_sponsors_required = None;
+                                        let mut co_sponsor_roles = None;
                                         
                                         for tp in type_pairs {
                                             match tp.key.as_str() {
@@ -1485,16 +1990,62 @@ impl CclInterpreter {
                                                         discussion_period_days = Some(*n as u64);
                                                     }
                                                 },
+                                                "allow_fast_track" => {
+                                                    if let ast::CclValue::Boolean(b) = &tp.value {
+                                                        allow_fast_track = Some(*b);
+                                                    }
+                                                },
+                                                "tie_break" => {
+                                                    if let ast::CclValue::String(s) = &tp.value {
+                                                        tie_break = Some(s.clone());
+                                                    }
+                                                },
+                                                "revote_extension_hours" => {
+                                                    if let ast::CclValue::Number(n) = &tp.value {
+                                                        revote_extension_hours = Some(*n as u64);
+                                                    }
+                                                },
+                                                "co_sponsors_required" => {
+                                                    if let ast::CclValue::Number(n) = &tp.value {
+                                                        co_sponsors_required = Some(*n as u64);
+                                                    }
+                                                },
+                                                "co_sponsor_roles" => {
+                                                    if let ast::CclValue::Array(role_vals) = &tp.value {
+                                                        co_sponsor_roles = Some(role_vals.iter()
+                                                            .filter_map(|v| match v {
+                                                                ast::CclValue::String(s) => Some(s.clone()),
+                                                                _ => None,
+                                                            })
+                                                            .collect());
+                                                    }
+                                                },
                                                 _ => {}
                                             }
                                         }
                                         
+                                        let tie_break = match tie_break.as_deref() {
+                                            Some("fail") => Some(config::TieBreakPolicy::Fail),
+                                            Some("chair_decides") => Some(config::TieBreakPolicy::ChairDecides),
+                                            Some("random_beacon") => Some(config::TieBreakPolicy::RandomBeacon),
+                                            Some("revote") => Some(config::TieBreakPolicy::Revote {
+                                                extension_hours: revote_extension_hours.unwrap_or(48),
+                                            }),
+                                            _ => None,
+                                        };
+                                        
                                         if !name.is_empty() {
                                             type_vec.push(config::ProposalType {
                                                 name,
                                                 quorum_modifier,
                                                 majority_modifier,
                                                 discussion_period_days,
+                                                allow_fast_track,
+                                                tie_break,
+                                                co_sponsorship: co_sponsors_required.map(|required| config::CoSponsorshipRule {
+                                                    required,
+                                                    roles: co_sponsor_roles,
+                                                }),
                                             });
                                         }
                                     }
@@ -1509,6 +2060,7 @@ impl CclInterpreter {
                     
                     return Some(config::ProposalProcess {
                         types,
+                        vote_changes,
                     });
                 }
             }
@@ -1633,6 +2185,9 @@ impl CclInterpreter {
                 if let ast::CclValue::Object(econ_pairs) = &pair.value {
                     let mut surplus_distribution = None;
                     let mut compensation_policy = None;
+                    let mut investment_policy = None;
+                    let mut cost_allocation = None;
+                    let mut rounding = None;
                     
                     for econ_pair in econ_pairs {
                         match econ_pair.key.as_str() {
@@ -1685,6 +2240,72 @@ impl CclInterpreter {
                                     });
                                 }
                             },
+                            "investment_policy" => {
+                                if let ast::CclValue::Object(inv_pairs) = &econ_pair.value {
+                                    let mut max_allocation_bps = None;
+                                    let mut prohibited_instruments = None;
+                                    
+                                    for inv_pair in inv_pairs {
+                                        match inv_pair.key.as_str() {
+                                            "max_allocation_bps" => {
+                                                if let ast::CclValue::Object(limit_pairs) = &inv_pair.value {
+                                                    let mut limits = std::collections::HashMap::new();
+                                                    
+                                                    for lp in limit_pairs {
+                                                        if let ast::CclValue::Number(n) = &lp.value {
+                                                            limits.insert(lp.key.clone(), *n as u64);
+                                                        }
+                                                    }
+                                                    
+                                                    if !limits.is_empty() {
+                                                        max_allocation_bps = Some(limits);
+                                                    }
+                                                }
+                                            },
+                                            "prohibited_instruments" => {
+                                                if let ast::CclValue::Array(vals) = &inv_pair.value {
+                                                    let instruments: Vec<String> = vals.iter()
+                                                        .filter_map(|v| match v {
+                                                            ast::CclValue::String(s) => Some(s.clone()),
+                                                            _ => None,
+                                                        })
+                                                        .collect();
+                                                    
+                                                    if !instruments.is_empty() {
+                                                        prohibited_instruments = Some(instruments);
+                                                    }
+                                                }
+                                            },
+                                            _ => {}
+                                        }
+                                    }
+                                    
+                                    investment_policy = Some(config::InvestmentPolicyConfig {
+                                        max_allocation_bps,
+                                        prohibited_instruments,
+                                    });
+                                }
+                            },
+                            "cost_allocation" => {
+                                if let ast::CclValue::Object(key_pairs) = &econ_pair.value {
+                                    let mut keys = std::collections::HashMap::new();
+                                    
+                                    for kp in key_pairs {
+                                        if let ast::CclValue::String(spec) = &kp.value {
+                                            keys.insert(kp.key.clone(), spec.clone());
+                                        }
+                                    }
+                                    
+                                    if !keys.is_empty() {
+                                        cost_allocation = Some(keys);
+                                    }
+                                }
+                            },
+                            "rounding" => {
+                                if let ast::CclValue::String(s) = &econ_pair.value {
+                                    rounding = Some(s.clone());
+                                }
+                            },
                             _ => {}
                         }
                     }
@@ -1692,6 +2313,9 @@ impl CclInterpreter {
                     return Some(config::EconomicModel {
                         surplus_distribution,
                         compensation_policy,
+                        investment_policy,
+                        cost_allocation,
+                        rounding,
                     });
                 }
             }
@@ -1755,7 +2379,13 @@ mod tests {
             votes_abstain: 0,
             ccl_code: None,
             wasm_bytes: None,
+            wasm_cid: None,
             thread_id: None,
+            metadata: HashMap::new(),
+            proposal_type: None,
+            created_at: 0,
+            effects: Vec::new(),
+            effective_at: None,
         };
         
         assert_eq!(proposal.calculate_id(), "proposal:test-proposal");
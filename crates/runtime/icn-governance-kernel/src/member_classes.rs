@@ -0,0 +1,352 @@
+/*!
+# Member Classes
+
+Cooperatives grant members different rights by class: full members vote and hold any
+role, probationary members take part while they serve a trial period, affiliates follow
+along without a vote. Classes are declared under `governance.member_classes`:
+
+```text
+member_classes: [
+  { name: "full", default: true },
+  { name: "probationary", permissions: ["create_proposals"], counts_toward_quorum: false, promotes_to: "full" },
+  { name: "affiliate", permissions: [], counts_toward_quorum: false }
+]
+```
+
+A class narrows what roles grant: a member may only exercise a role's permission if
+their class lists it, or lists no permissions at all. Members with no recorded class
+belong to the default class. Scopes that declare no classes don't restrict anyone.
+
+A member with the `manage_member_classes` permission places members in a class
+directly. Moving along a class's `promotes_to` path is decided by a vote instead: a
+transition proposal is submitted in the scope, and once it's finalized and has passed
+the scope's `quorum` and `majority` the member's class changes. Members of classes that
+don't count towards the quorum are left out of its denominator, for transition votes
+and for joint proposals alike.
+*/
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::config::MemberClassConfig;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::joint::side_passes;
+
+/// Metadata field naming the member a transition proposal moves
+pub const TRANSITION_MEMBER_METADATA_KEY: &str = "class_transition_member";
+
+/// Metadata field naming the class the member moves from
+pub const TRANSITION_FROM_METADATA_KEY: &str = "class_transition_from";
+
+/// Metadata field naming the class the member moves to
+pub const TRANSITION_TO_METADATA_KEY: &str = "class_transition_to";
+
+/// How a member came to be in a class
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ClassChangeSource {
+    /// Set directly by a member with `manage_member_classes`
+    Assigned(IdentityId),
+    /// Carried by a transition vote
+    Vote { proposal_id: String },
+}
+
+/// A member's recorded class in a scope
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MemberClassRecord {
+    pub scope_id: String,
+    pub member: IdentityId,
+    pub class: String,
+    /// The class the member held before, if one was recorded
+    pub previous_class: Option<String>,
+    /// When the member entered the class (Unix timestamp)
+    pub since: i64,
+    pub source: ClassChangeSource,
+}
+
+/// The class a member belongs to: their recorded class if the scope still declares it,
+/// otherwise the scope's default class
+pub fn effective_class<'a>(classes: &'a [MemberClassConfig], recorded: Option<&str>) -> Option<&'a MemberClassConfig> {
+    recorded
+        .and_then(|name| classes.iter().find(|c| c.name == name))
+        .or_else(|| classes.iter().find(|c| c.default.unwrap_or(false)))
+}
+
+/// Whether a member of `class` may exercise `permission`. Members outside any class
+/// aren't restricted.
+pub fn class_permits(class: Option<&MemberClassConfig>, permission: &str) -> bool {
+    match class.and_then(|c| c.permissions.as_ref()) {
+        Some(permissions) => permissions.iter().any(|p| p == permission),
+        None => true,
+    }
+}
+
+/// Whether members of `class` count towards the quorum denominator
+pub fn counts_toward_quorum(class: Option<&MemberClassConfig>) -> bool {
+    class.and_then(|c| c.counts_toward_quorum).unwrap_or(true)
+}
+
+fn class_key(scope_id: &str, member: &IdentityId) -> String {
+    format!("member_class::{}::{}", scope_id, member.0)
+}
+
+fn class_history_key(scope_id: &str, member: &IdentityId) -> String {
+    format!("member_class::history::{}::{}", scope_id, member.0)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The member classes a scope declares
+    pub async fn member_classes(&self, scope_id: &str) -> Result<Vec<MemberClassConfig>, GovernanceError> {
+        Ok(self.load_governance_config(scope_id).await?
+            .and_then(|config| config.governance)
+            .and_then(|governance| governance.member_classes)
+            .unwrap_or_default())
+    }
+
+    /// A member's recorded class in a scope, if one was set
+    pub async fn get_member_class_record(&self, scope_id: &str, member: &IdentityId) -> Result<Option<MemberClassRecord>, GovernanceError> {
+        self.load_record(&class_key(scope_id, member), "member class").await
+    }
+
+    /// Every class change of a member in a scope, oldest first
+    pub async fn get_member_class_history(&self, scope_id: &str, member: &IdentityId) -> Result<Vec<MemberClassRecord>, GovernanceError> {
+        Ok(self.load_record(&class_history_key(scope_id, member), "member class history").await?
+            .unwrap_or_default())
+    }
+
+    /// The name of the class a member belongs to, if the scope declares classes
+    pub async fn get_member_class(&self, scope_id: &str, member: &IdentityId) -> Result<Option<String>, GovernanceError> {
+        let classes = self.member_classes(scope_id).await?;
+        let recorded = self.get_member_class_record(scope_id, member).await?;
+        Ok(effective_class(&classes, recorded.as_ref().map(|r| r.class.as_str())).map(|c| c.name.clone()))
+    }
+
+    /// Whether a member's class lets them exercise a permission their roles grant
+    pub(crate) async fn check_class_permission(&self, member: &IdentityId, scope_id: &str, classes: &[MemberClassConfig], permission: &str) -> Result<bool, GovernanceError> {
+        if classes.is_empty() {
+            return Ok(true);
+        }
+        let recorded = self.get_member_class_record(scope_id, member).await?;
+        Ok(class_permits(effective_class(classes, recorded.as_ref().map(|r| r.class.as_str())), permission))
+    }
+
+    /// Members of a scope's roster who belong to a class, sorted
+    pub async fn get_scope_members_by_class(&self, scope_id: &str, class: &str) -> Result<Vec<IdentityId>, GovernanceError> {
+        let classes = self.member_classes(scope_id).await?;
+        let mut members = Vec::new();
+        for member in self.get_scope_members(scope_id).await? {
+            let recorded = self.get_member_class_record(scope_id, &member).await?;
+            if effective_class(&classes, recorded.as_ref().map(|r| r.class.as_str())).map_or(false, |c| c.name == class) {
+                members.push(member);
+            }
+        }
+        Ok(members)
+    }
+
+    /// Members of a scope's roster whose class counts towards the quorum
+    pub async fn quorum_denominator(&self, scope_id: &str) -> Result<usize, GovernanceError> {
+        let classes = self.member_classes(scope_id).await?;
+        let mut count = 0;
+        for member in self.get_scope_members(scope_id).await? {
+            let recorded = self.get_member_class_record(scope_id, &member).await?;
+            if counts_toward_quorum(effective_class(&classes, recorded.as_ref().map(|r| r.class.as_str()))) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Store a member's new class, append it to their history and log the change
+    async fn record_member_class(&self, scope_id: &str, member: &IdentityId, class: &str, source: ClassChangeSource, actor: &IdentityId) -> Result<MemberClassRecord, GovernanceError> {
+        let previous_class = self.get_member_class(scope_id, member).await?;
+        let record = MemberClassRecord {
+            scope_id: scope_id.to_string(),
+            member: member.clone(),
+            class: class.to_string(),
+            previous_class,
+            since: chrono::Utc::now().timestamp(),
+            source,
+        };
+
+        let bytes = serde_json::to_vec(&record)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize member class: {}", e)))?;
+        self.store_record(&class_key(scope_id, member), bytes).await?;
+
+        let mut history = self.get_member_class_history(scope_id, member).await?;
+        history.push(record.clone());
+        let history_bytes = serde_json::to_vec(&history)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize member class history: {}", e)))?;
+        self.store_record(&class_history_key(scope_id, member), history_bytes).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::MemberClassChanged,
+            actor.clone(),
+            self.scope_type_for(scope_id).await?,
+            Some(IdentityId(scope_id.to_string())),
+            match &record.source {
+                ClassChangeSource::Vote { proposal_id } => Some(proposal_id.clone()),
+                ClassChangeSource::Assigned(_) => None,
+            },
+            serde_json::json!({
+                "member": member.0,
+                "class": record.class,
+                "previous_class": record.previous_class,
+                "source": record.source
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(record)
+    }
+
+    /// Place a member in a class directly, e.g. on admission or demotion
+    pub async fn set_member_class(&self, caller: &IdentityId, scope_id: &str, member: &IdentityId, class: &str) -> Result<MemberClassRecord, GovernanceError> {
+        if !self.check_permission(caller, scope_id, "manage_member_classes").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to manage member classes in scope {}", caller.0, scope_id
+            )));
+        }
+        if !self.member_classes(scope_id).await?.iter().any(|c| c.name == class) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Member class '{}' does not exist in scope {}", class, scope_id
+            )));
+        }
+
+        self.record_member_class(scope_id, member, class, ClassChangeSource::Assigned(caller.clone()), caller).await
+    }
+
+    /// Submit a proposal moving a member along their class's `promotes_to` path.
+    /// Returns the proposal ID.
+    pub async fn propose_class_transition(&self, proposer: &IdentityId, scope_id: &str, member: &IdentityId, voting_end_time: i64) -> Result<String, GovernanceError> {
+        let classes = self.member_classes(scope_id).await?;
+        let recorded = self.get_member_class_record(scope_id, member).await?;
+        let current = effective_class(&classes, recorded.as_ref().map(|r| r.class.as_str()))
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Identity {} has no member class in scope {}", member.0, scope_id
+            )))?;
+        let target = current.promotes_to.clone()
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Member class '{}' has no transition path", current.name
+            )))?;
+        if !classes.iter().any(|c| c.name == target) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Member class '{}' promotes to undeclared class '{}'", current.name, target
+            )));
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert(TRANSITION_MEMBER_METADATA_KEY.to_string(), member.0.clone());
+        metadata.insert(TRANSITION_FROM_METADATA_KEY.to_string(), current.name.clone());
+        metadata.insert(TRANSITION_TO_METADATA_KEY.to_string(), target.clone());
+
+        let proposal = Proposal {
+            title: format!("Move {} from {} to {}", member.0, current.name, target),
+            description: format!("Member class transition of {} in scope {}", member.0, scope_id),
+            proposer: proposer.clone(),
+            scope: self.scope_type_for(scope_id).await?,
+            scope_id: Some(IdentityId(scope_id.to_string())),
+            status: ProposalStatus::Draft,
+            voting_end_time,
+            votes_for: 0,
+            votes_against: 0,
+            votes_abstain: 0,
+            ccl_code: None,
+            wasm_bytes: None,
+            wasm_cid: None,
+            thread_id: None,
+            metadata,
+            proposal_type: None,
+            created_at: 0,
+            effects: Vec::new(),
+            effective_at: None,
+        };
+        self.process_proposal(proposal).await
+    }
+
+    /// Apply a finalized transition proposal. The member moves to the new class if the
+    /// vote met the scope's quorum and majority; returns the new record, or `None` if
+    /// the vote failed.
+    pub async fn complete_class_transition(&self, proposal_id: &str) -> Result<Option<MemberClassRecord>, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if !matches!(proposal.status, ProposalStatus::Finalized | ProposalStatus::Passed | ProposalStatus::Rejected | ProposalStatus::Executed) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Transition proposal {} is not finalized", proposal_id
+            )));
+        }
+
+        let field = |key: &str| proposal.metadata.get(key).cloned()
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Proposal {} is not a member class transition", proposal_id
+            )));
+        let member = IdentityId(field(TRANSITION_MEMBER_METADATA_KEY)?);
+        let from = field(TRANSITION_FROM_METADATA_KEY)?;
+        let to = field(TRANSITION_TO_METADATA_KEY)?;
+        let scope_id = proposal.scope_id.as_ref().map(|s| s.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        // A member whose class changed since the vote opened isn't moved again
+        if self.get_member_class(&scope_id, &member).await?.as_deref() != Some(from.as_str()) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Identity {} is no longer in member class '{}'", member.0, from
+            )));
+        }
+
+        let governance = self.load_governance_config(&scope_id).await?
+            .and_then(|config| config.governance);
+        let quorum = governance.as_ref().and_then(|g| g.quorum).unwrap_or(0.0);
+        let majority = governance.as_ref().and_then(|g| g.majority).unwrap_or(0.5);
+        let tally = self.conflict_aware_tally(proposal_id).await?;
+        let eligible = self.quorum_denominator(&scope_id).await?;
+        if proposal.status == ProposalStatus::Rejected || !side_passes(&tally, eligible, quorum, majority) {
+            return Ok(None);
+        }
+
+        let source = ClassChangeSource::Vote { proposal_id: proposal_id.to_string() };
+        let actor = IdentityId(self.identity.did().to_string());
+        self.record_member_class(&scope_id, &member, &to, source, &actor).await.map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(name: &str, permissions: Option<&[&str]>, counts: Option<bool>, default: bool) -> MemberClassConfig {
+        MemberClassConfig {
+            name: name.to_string(),
+            permissions: permissions.map(|p| p.iter().map(|s| s.to_string()).collect()),
+            counts_toward_quorum: counts,
+            promotes_to: None,
+            default: Some(default),
+        }
+    }
+
+    #[test]
+    fn test_class_restricts_permissions_and_quorum() {
+        let classes = vec![
+            class("full", None, None, true),
+            class("probationary", Some(&["create_proposals"]), Some(false), false),
+            class("affiliate", Some(&[]), Some(false), false),
+        ];
+
+        // Unrecorded members and members of a class the scope dropped fall back to the default
+        assert_eq!(effective_class(&classes, None).unwrap().name, "full");
+        assert_eq!(effective_class(&classes, Some("alumni")).unwrap().name, "full");
+
+        let probationary = effective_class(&classes, Some("probationary"));
+        assert!(class_permits(probationary, "create_proposals"));
+        assert!(!class_permits(probationary, "vote_on_proposals"));
+        assert!(!class_permits(effective_class(&classes, Some("affiliate")), "create_proposals"));
+        assert!(class_permits(effective_class(&classes, None), "vote_on_proposals"));
+
+        assert!(counts_toward_quorum(effective_class(&classes, None)));
+        assert!(!counts_toward_quorum(probationary));
+
+        // Without classes nobody is restricted
+        assert!(class_permits(effective_class(&[], Some("probationary")), "vote_on_proposals"));
+        assert!(counts_toward_quorum(None));
+    }
+}
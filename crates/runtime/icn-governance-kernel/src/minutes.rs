@@ -0,0 +1,274 @@
+/*!
+# Meeting Minutes
+
+Structured minutes documents (agenda items, attendees, decisions) are anchored as
+content-addressed blobs and linked to the proposals their agenda items discuss. The
+links are indexed per proposal, so the deliberation context stays permanently attached
+to the decision and can be retrieved from the proposal record.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal};
+use crate::attestations::ProposalAttestation;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// A meeting attendee
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Attendee {
+    /// The attendee's identity
+    pub did: IdentityId,
+    /// The attendee's role in the meeting (e.g., "chair", "secretary")
+    pub role: Option<String>,
+}
+
+/// A decision recorded against an agenda item
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MeetingDecision {
+    /// Outcome as recorded by the secretary (e.g., "approved", "deferred")
+    pub outcome: String,
+    /// Votes in favour, if a vote was taken
+    pub votes_for: Option<u64>,
+    /// Votes against, if a vote was taken
+    pub votes_against: Option<u64>,
+    /// Abstentions, if a vote was taken
+    pub votes_abstain: Option<u64>,
+}
+
+/// An item on the meeting agenda
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgendaItem {
+    /// Item title
+    pub title: String,
+    /// Discussion notes
+    pub notes: String,
+    /// The proposal this item discusses, if any
+    pub proposal_id: Option<String>,
+    /// The decision taken on this item, if any
+    pub decision: Option<MeetingDecision>,
+}
+
+/// Structured minutes of a meeting
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MeetingMinutes {
+    /// The scope the meeting was held in
+    pub scope_id: String,
+    /// Meeting title
+    pub title: String,
+    /// When the meeting was held (Unix timestamp)
+    pub held_at: i64,
+    /// Who recorded the minutes
+    pub recorder: IdentityId,
+    /// Meeting attendees
+    pub attendees: Vec<Attendee>,
+    /// Agenda items in order
+    pub agenda: Vec<AgendaItem>,
+}
+
+impl MeetingMinutes {
+    /// IDs of all proposals discussed in these minutes
+    pub fn linked_proposals(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.agenda.iter()
+            .filter_map(|item| item.proposal_id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Minutes together with the CID they were anchored under
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnchoredMinutes {
+    /// CID of the anchored minutes document
+    pub cid: String,
+    /// The minutes document
+    pub minutes: MeetingMinutes,
+}
+
+/// A proposal together with the minutes of every meeting that discussed it and the
+/// external attestations attached to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposalDeliberation {
+    pub proposal: Proposal,
+    pub minutes: Vec<AnchoredMinutes>,
+    #[serde(default)]
+    pub attestations: Vec<ProposalAttestation>,
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Anchor meeting minutes and link them to the proposals on their agenda
+    pub async fn anchor_minutes(&self, minutes: MeetingMinutes) -> Result<String, GovernanceError> {
+        if minutes.agenda.is_empty() {
+            return Err(GovernanceError::InvalidProposal("Minutes must contain at least one agenda item".to_string()));
+        }
+
+        // Every linked proposal must belong to the scope the meeting was held in
+        let linked = minutes.linked_proposals();
+        let scope_proposals = self.get_scope_proposal_ids(&minutes.scope_id).await?;
+        for proposal_id in &linked {
+            if !scope_proposals.contains(proposal_id) {
+                return Err(GovernanceError::ProposalNotFound(format!(
+                    "{} in scope {}", proposal_id, minutes.scope_id
+                )));
+            }
+        }
+
+        let minutes_bytes = serde_json::to_vec(&minutes)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize minutes: {}", e)))?;
+
+        // Anchor the document content-addressed so it can't change after the fact
+        let storage = self.storage.lock().await;
+        let minutes_cid = storage.put_blob(&minutes_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to anchor minutes: {}", e)))?
+            .to_string();
+        drop(storage);
+
+        self.append_to_index(&format!("minutes_index::{}", minutes.scope_id), &minutes_cid).await?;
+        for proposal_id in &linked {
+            self.append_to_index(&format!("proposal_minutes::{}", proposal_id), &minutes_cid).await?;
+        }
+
+        for proposal_id in &linked {
+            let event_data = serde_json::json!({
+                "minutes_cid": minutes_cid,
+                "meeting_title": minutes.title,
+                "held_at": minutes.held_at,
+                "attendee_count": minutes.attendees.len()
+            });
+
+            let event = GovernanceEvent::new(
+                GovernanceEventType::MinutesAnchored,
+                minutes.recorder.clone(),
+                self.load_governance_config(&minutes.scope_id).await?
+                    .map(|c| c.governing_scope)
+                    .unwrap_or(icn_identity::IdentityScope::Cooperative),
+                Some(IdentityId(minutes.scope_id.clone())),
+                Some(proposal_id.clone()),
+                event_data
+            );
+
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        }
+
+        Ok(minutes_cid)
+    }
+
+    /// Get anchored minutes by CID
+    pub async fn get_minutes(&self, minutes_cid: &str) -> Result<MeetingMinutes, GovernanceError> {
+        let cid = cid::Cid::try_from(minutes_cid)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Invalid minutes CID: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        let bytes = storage.get_blob(&cid)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Minutes not found: {}", minutes_cid)))?;
+
+        serde_json::from_slice(&bytes)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize minutes: {}", e)))
+    }
+
+    /// Get all minutes that discussed a proposal, in the order they were anchored
+    pub async fn get_proposal_minutes(&self, proposal_id: &str) -> Result<Vec<AnchoredMinutes>, GovernanceError> {
+        self.load_minutes_index(&format!("proposal_minutes::{}", proposal_id)).await
+    }
+
+    /// Get all minutes anchored in a scope, in the order they were anchored
+    pub async fn get_scope_minutes(&self, scope_id: &str) -> Result<Vec<AnchoredMinutes>, GovernanceError> {
+        self.load_minutes_index(&format!("minutes_index::{}", scope_id)).await
+    }
+
+    /// Get a proposal together with the minutes of every meeting that discussed it and
+    /// the attestations attached to it
+    pub async fn get_proposal_deliberation(&self, proposal_id: &str) -> Result<ProposalDeliberation, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let minutes = self.get_proposal_minutes(proposal_id).await?;
+        let attestations = self.get_proposal_attestations(proposal_id).await?;
+        Ok(ProposalDeliberation { proposal, minutes, attestations })
+    }
+
+    /// Load every minutes document referenced by an index
+    async fn load_minutes_index(&self, index_key: &str) -> Result<Vec<AnchoredMinutes>, GovernanceError> {
+        let index_cid = self.create_key_cid(index_key)?;
+
+        let storage = self.storage.lock().await;
+        let cids = match storage.get_kv(&index_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice::<Vec<String>>(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize minutes index: {}", e)))?,
+            _ => Vec::new(),
+        };
+        drop(storage);
+
+        let mut anchored = Vec::new();
+        for cid in cids {
+            let minutes = self.get_minutes(&cid).await?;
+            anchored.push(AnchoredMinutes { cid, minutes });
+        }
+
+        Ok(anchored)
+    }
+
+    /// Append a value to a string-list index, skipping duplicates
+    pub(crate) async fn append_to_index(&self, index_key: &str, value: &str) -> Result<(), GovernanceError> {
+        let index_cid = self.create_key_cid(index_key)?;
+
+        let storage = self.storage.lock().await;
+        let mut values = match storage.get_kv(&index_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice::<Vec<String>>(&bytes).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        if !values.iter().any(|v| v == value) {
+            values.push(value.to_string());
+        }
+
+        let index_bytes = serde_json::to_vec(&values)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize index: {}", e)))?;
+
+        storage.put_kv(index_cid, index_bytes).await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to store index: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, proposal_id: Option<&str>) -> AgendaItem {
+        AgendaItem {
+            title: title.to_string(),
+            notes: String::new(),
+            proposal_id: proposal_id.map(|p| p.to_string()),
+            decision: None,
+        }
+    }
+
+    #[test]
+    fn test_linked_proposals_are_deduplicated() {
+        let minutes = MeetingMinutes {
+            scope_id: "coop-1".to_string(),
+            title: "General assembly".to_string(),
+            held_at: 1_700_000_000,
+            recorder: IdentityId("did:icn:secretary".to_string()),
+            attendees: vec![],
+            agenda: vec![
+                item("Budget review", Some("proposal:budget")),
+                item("Any other business", None),
+                item("Budget vote", Some("proposal:budget")),
+                item("New member", Some("proposal:admit-carol")),
+            ],
+        };
+
+        assert_eq!(minutes.linked_proposals(), vec![
+            "proposal:admit-carol".to_string(),
+            "proposal:budget".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,352 @@
+/*!
+# Resignation and Exit
+
+A member leaves a scope by filing a resignation. The scope's bylaws set the notice
+period (`membership.offboarding.notice_period_days`); the resignation takes effect once
+it has run, and the member can withdraw it until then.
+
+When a resignation falls due, [`GovernanceKernel::process_due_resignations`] carries out
+the exit: the member's role credentials are revoked, an open capital account is put on
+a redemption schedule (`icn_economics::capital`) starting at the effective date, and an
+exit record listing what was done is anchored content-addressed and indexed under the
+scope.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_economics::capital::{self, CapitalAccountStatus, RedemptionSchedule};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Status of a resignation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ResignationStatus {
+    /// Filed; the notice period is running
+    Pending,
+    /// Withdrawn by the member before it took effect
+    Withdrawn,
+    /// Took effect; the member has exited
+    Completed,
+}
+
+/// A member's notice that they are leaving a scope
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Resignation {
+    pub scope_id: String,
+    pub member: IdentityId,
+    pub reason: Option<String>,
+    /// When the resignation was filed (Unix timestamp)
+    pub filed_at: i64,
+    /// When the notice period ends and the exit is carried out (Unix timestamp)
+    pub effective_at: i64,
+    pub status: ResignationStatus,
+    /// CID of the anchored exit record once the exit is carried out
+    pub exit_record_cid: Option<String>,
+}
+
+impl Resignation {
+    /// Whether the exit should be carried out at `now`
+    pub fn is_due(&self, now: i64) -> bool {
+        self.status == ResignationStatus::Pending && self.effective_at <= now
+    }
+}
+
+/// How an exiting member's capital account is paid out
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RedemptionTerms {
+    pub installments: u32,
+    /// Days between installments
+    pub interval_days: u64,
+}
+
+impl Default for RedemptionTerms {
+    /// The whole balance in one payment at the effective date
+    fn default() -> Self {
+        Self { installments: 1, interval_days: 0 }
+    }
+}
+
+/// The anchored account of a member's exit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExitRecord {
+    pub scope_id: String,
+    pub member: IdentityId,
+    pub reason: Option<String>,
+    pub filed_at: i64,
+    pub effective_at: i64,
+    /// When the exit was carried out (Unix timestamp)
+    pub exited_at: i64,
+    /// Roles the member held when they exited
+    pub roles: Vec<String>,
+    /// IDs of the role credentials revoked
+    pub revoked_credentials: Vec<String>,
+    /// Redemption schedule of the member's capital account, if they had an open one
+    pub capital_redemption: Option<RedemptionSchedule>,
+}
+
+/// When a resignation filed at `filed_at` takes effect
+pub fn effective_date(filed_at: i64, notice_period_days: u64) -> i64 {
+    filed_at.saturating_add((notice_period_days as i64).saturating_mul(SECONDS_PER_DAY))
+}
+
+fn resignation_key(scope_id: &str, member: &IdentityId) -> String {
+    format!("resignation::{}::{}", scope_id, member.0)
+}
+
+fn resignation_index(scope_id: &str) -> String {
+    format!("resignation_index::{}", scope_id)
+}
+
+fn exit_index(scope_id: &str) -> String {
+    format!("exit_index::{}", scope_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The notice period a scope's bylaws require, in days
+    pub async fn notice_period_days(&self, scope_id: &str) -> Result<u64, GovernanceError> {
+        Ok(self.load_governance_config(scope_id).await?
+            .and_then(|config| config.membership)
+            .and_then(|membership| membership.offboarding)
+            .and_then(|offboarding| offboarding.notice_period_days)
+            .unwrap_or(0))
+    }
+
+    /// A member's latest resignation from a scope
+    pub async fn get_resignation(&self, scope_id: &str, member: &IdentityId) -> Result<Option<Resignation>, GovernanceError> {
+        self.load_record(&resignation_key(scope_id, member), "resignation").await
+    }
+
+    async fn store_resignation(&self, resignation: &Resignation) -> Result<(), GovernanceError> {
+        let bytes = serde_json::to_vec(resignation)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize resignation: {}", e)))?;
+        self.store_record(&resignation_key(&resignation.scope_id, &resignation.member), bytes).await
+    }
+
+    async fn emit_offboarding_event(
+        &self,
+        event_type: GovernanceEventType,
+        issuer: &IdentityId,
+        scope_id: &str,
+        data: serde_json::Value,
+    ) -> Result<(), GovernanceError> {
+        let event = GovernanceEvent::new(
+            event_type,
+            issuer.clone(),
+            self.scope_type_for(scope_id).await?,
+            Some(IdentityId(scope_id.to_string())),
+            None,
+            data
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        Ok(())
+    }
+
+    /// File a member's resignation. The exit takes effect after the scope's notice
+    /// period; members can only resign for themselves.
+    pub async fn file_resignation(&self, caller: &IdentityId, scope_id: &str, reason: Option<String>) -> Result<Resignation, GovernanceError> {
+        if !self.get_scope_members(scope_id).await?.contains(caller) {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not a member of scope {}", caller.0, scope_id
+            )));
+        }
+        if let Some(existing) = self.get_resignation(scope_id, caller).await? {
+            if existing.status == ResignationStatus::Pending {
+                return Err(GovernanceError::InvalidProposal(format!(
+                    "Identity {} already has a pending resignation from scope {}", caller.0, scope_id
+                )));
+            }
+        }
+
+        let filed_at = chrono::Utc::now().timestamp();
+        let resignation = Resignation {
+            scope_id: scope_id.to_string(),
+            member: caller.clone(),
+            reason,
+            filed_at,
+            effective_at: effective_date(filed_at, self.notice_period_days(scope_id).await?),
+            status: ResignationStatus::Pending,
+            exit_record_cid: None,
+        };
+        self.store_resignation(&resignation).await?;
+        self.append_to_index(&resignation_index(scope_id), &caller.0).await?;
+
+        self.emit_offboarding_event(GovernanceEventType::ResignationFiled, caller, scope_id, serde_json::json!({
+            "member": caller.0,
+            "reason": resignation.reason,
+            "effective_at": resignation.effective_at
+        })).await?;
+
+        Ok(resignation)
+    }
+
+    /// Withdraw a pending resignation before it takes effect
+    pub async fn withdraw_resignation(&self, caller: &IdentityId, scope_id: &str) -> Result<Resignation, GovernanceError> {
+        let mut resignation = self.get_resignation(scope_id, caller).await?
+            .filter(|r| r.status == ResignationStatus::Pending)
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Identity {} has no pending resignation from scope {}", caller.0, scope_id
+            )))?;
+        if resignation.is_due(chrono::Utc::now().timestamp()) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "The resignation of {} from scope {} has already taken effect", caller.0, scope_id
+            )));
+        }
+
+        resignation.status = ResignationStatus::Withdrawn;
+        self.store_resignation(&resignation).await?;
+
+        self.emit_offboarding_event(GovernanceEventType::ResignationWithdrawn, caller, scope_id, serde_json::json!({
+            "member": caller.0
+        })).await?;
+
+        Ok(resignation)
+    }
+
+    /// Carry out every resignation in a scope whose notice period has run. Returns the
+    /// exit records anchored.
+    pub async fn process_due_resignations(&self, scope_id: &str, terms: RedemptionTerms) -> Result<Vec<ExitRecord>, GovernanceError> {
+        let now = chrono::Utc::now().timestamp();
+        let mut exits = Vec::new();
+        for did in self.load_index(&resignation_index(scope_id)).await? {
+            if let Some(resignation) = self.get_resignation(scope_id, &IdentityId(did)).await? {
+                if resignation.is_due(now) {
+                    exits.push(self.complete_exit(resignation, terms, now).await?);
+                }
+            }
+        }
+        Ok(exits)
+    }
+
+    /// Revoke the member's credentials, schedule their capital redemption and anchor
+    /// the exit record
+    async fn complete_exit(&self, mut resignation: Resignation, terms: RedemptionTerms, now: i64) -> Result<ExitRecord, GovernanceError> {
+        let scope_id = resignation.scope_id.clone();
+        let member = resignation.member.clone();
+        let roles = self.get_verified_roles(&member, &scope_id).await?;
+        let revoked_credentials = self.revoke_role_credentials(&scope_id, &member).await?;
+
+        let capital_redemption = {
+            let mut storage = self.storage.lock().await;
+            match capital::load_capital_account(&scope_id, &member.0, &*storage).await {
+                Ok(account) if account.status == CapitalAccountStatus::Open => Some(
+                    capital::schedule_redemption(
+                        &scope_id,
+                        &member.0,
+                        terms.installments,
+                        resignation.effective_at,
+                        (terms.interval_days as i64).saturating_mul(SECONDS_PER_DAY),
+                        resignation.effective_at,
+                        &mut *storage,
+                    )
+                    .await
+                    .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to schedule capital redemption: {}", e)))?
+                ),
+                _ => None,
+            }
+        };
+
+        let record = ExitRecord {
+            scope_id: scope_id.clone(),
+            member: member.clone(),
+            reason: resignation.reason.clone(),
+            filed_at: resignation.filed_at,
+            effective_at: resignation.effective_at,
+            exited_at: now,
+            roles,
+            revoked_credentials,
+            capital_redemption,
+        };
+
+        let record_bytes = serde_json::to_vec(&record)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize exit record: {}", e)))?;
+        let storage = self.storage.lock().await;
+        let record_cid = storage.put_blob(&record_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to anchor exit record: {}", e)))?
+            .to_string();
+        drop(storage);
+        self.append_to_index(&exit_index(&scope_id), &record_cid).await?;
+
+        resignation.status = ResignationStatus::Completed;
+        resignation.exit_record_cid = Some(record_cid.clone());
+        self.store_resignation(&resignation).await?;
+
+        let actor = IdentityId(self.identity.did().to_string());
+        self.emit_offboarding_event(GovernanceEventType::MemberExited, &actor, &scope_id, serde_json::json!({
+            "member": member.0,
+            "exit_record_cid": record_cid,
+            "revoked_credentials": record.revoked_credentials.len(),
+            "capital_outstanding": record.capital_redemption.as_ref().map(|r| r.outstanding())
+        })).await?;
+
+        Ok(record)
+    }
+
+    /// Drop every role credential of a member from the scope's role index, so none of
+    /// them verify any longer. The credentials themselves are kept for the record.
+    async fn revoke_role_credentials(&self, scope_id: &str, member: &IdentityId) -> Result<Vec<String>, GovernanceError> {
+        let index_key = format!("role_index::{}::{}", scope_id, member.0);
+        let revoked = self.load_index(&index_key).await?;
+        let bytes = serde_json::to_vec(&Vec::<String>::new())
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize role index: {}", e)))?;
+        self.store_record(&index_key, bytes).await?;
+        Ok(revoked)
+    }
+
+    /// An anchored exit record
+    pub async fn get_exit_record(&self, record_cid: &str) -> Result<ExitRecord, GovernanceError> {
+        let cid = cid::Cid::try_from(record_cid)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Invalid exit record CID: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        let bytes = storage.get_blob(&cid)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Exit record not found: {}", record_cid)))?;
+
+        serde_json::from_slice(&bytes)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize exit record: {}", e)))
+    }
+
+    /// Every exit record anchored in a scope, oldest first
+    pub async fn get_scope_exits(&self, scope_id: &str) -> Result<Vec<ExitRecord>, GovernanceError> {
+        let mut records = Vec::new();
+        for cid in self.load_index(&exit_index(scope_id)).await? {
+            records.push(self.get_exit_record(&cid).await?);
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resignation_falls_due_after_notice() {
+        let filed_at = 1_700_000_000;
+        let resignation = Resignation {
+            scope_id: "coop-1".to_string(),
+            member: IdentityId("did:icn:alice".to_string()),
+            reason: None,
+            filed_at,
+            effective_at: effective_date(filed_at, 14),
+            status: ResignationStatus::Pending,
+            exit_record_cid: None,
+        };
+
+        assert_eq!(resignation.effective_at, filed_at + 14 * 86_400);
+        assert!(!resignation.is_due(filed_at + 13 * 86_400));
+        assert!(resignation.is_due(filed_at + 14 * 86_400));
+
+        // Without a notice period the exit is due at once; withdrawn resignations never are
+        assert_eq!(effective_date(filed_at, 0), filed_at);
+        let withdrawn = Resignation { status: ResignationStatus::Withdrawn, ..resignation };
+        assert!(!withdrawn.is_due(i64::MAX));
+    }
+}
@@ -0,0 +1,41 @@
+/*!
+# Onboarding Attestations
+
+A scope's bylaws can make a verified email address or phone number a condition of
+joining by listing `verified_email` or `verified_phone` among the onboarding
+requirements in the `membership` section. The gateway's verifiers issue the matching
+[`ContactAttestation`]s (see `icn_identity::contact`). Before admitting an applicant,
+the kernel checks the attestations they present against the scope's requirements.
+*/
+
+use icn_identity::contact::{self, ContactAttestation};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError};
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The onboarding requirements a scope's bylaws declare
+    pub async fn onboarding_requirements(&self, scope_id: &str) -> Result<Vec<String>, GovernanceError> {
+        Ok(self.load_governance_config(scope_id).await?
+            .and_then(|config| config.membership)
+            .and_then(|membership| membership.onboarding)
+            .and_then(|onboarding| onboarding.requirements)
+            .unwrap_or_default())
+    }
+
+    /// The contact attestations a scope requires that `applicant` has not presented.
+    /// Only attestations signed by one of `trusted_issuers` and still in effect count.
+    /// An empty result means the applicant meets the scope's contact requirements.
+    pub async fn missing_onboarding_attestations(
+        &self,
+        scope_id: &str,
+        applicant: &IdentityId,
+        attestations: &[ContactAttestation],
+        trusted_issuers: &[IdentityId],
+    ) -> Result<Vec<String>, GovernanceError> {
+        let requirements = self.onboarding_requirements(scope_id).await?;
+        contact::missing_contact_requirements(&requirements, attestations, applicant, trusted_issuers, chrono::Utc::now())
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to check onboarding attestations: {}", e)))
+    }
+}
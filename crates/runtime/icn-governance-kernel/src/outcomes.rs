@@ -0,0 +1,360 @@
+/*!
+# Proposal Outcome Evidence
+
+When a proposal is finalized or executed, the kernel issues a signed evidence bundle
+instead of just a status change. The bundle carries the conflict-aware tally, the CID
+of a snapshot of whose votes were counted and whose were excluded, the CID of the
+published ballot receipt hashes, the CIDs of the external attestations attached to the
+proposal and, once executed, the CID of the execution receipt.
+
+The bundle is stored with the proposal, recorded in the event log and delivered to the
+proposer, every voter in the eligibility snapshot and every member of the proposal's
+scope through the kernel's [`NotificationDispatcher`], if one is set.
+A member holding the bundle can check it against their ballot receipt and the
+published receipt set without trusting the status field of the proposal.
+*/
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use async_trait::async_trait;
+use cid::Cid;
+use tokio::sync::Mutex;
+use std::sync::Arc;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, ProposalStatus, SignatureProof, create_sha256_multihash};
+use crate::coi::ConflictAwareTally;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::referenda::Referendum;
+
+/// Delivers outcome evidence and referenda to members
+#[async_trait]
+pub trait NotificationDispatcher: Send + Sync {
+    /// Deliver a bundle to one member
+    async fn deliver(&self, recipient: &IdentityId, evidence: &OutcomeEvidence) -> Result<(), String>;
+
+    /// Tell one member that a referendum opened or, once it carries a result, closed
+    async fn deliver_referendum(&self, _recipient: &IdentityId, _referendum: &Referendum) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// A dispatcher that keeps every delivery in memory, for testing
+#[derive(Default)]
+pub struct InMemoryNotificationDispatcher {
+    delivered: Mutex<Vec<(IdentityId, OutcomeEvidence)>>,
+    referenda: Mutex<Vec<(IdentityId, Referendum)>>,
+}
+
+impl InMemoryNotificationDispatcher {
+    /// Every bundle delivered so far, with its recipient
+    pub async fn delivered(&self) -> Vec<(IdentityId, OutcomeEvidence)> {
+        self.delivered.lock().await.clone()
+    }
+
+    /// Every referendum notice delivered so far, with its recipient
+    pub async fn delivered_referenda(&self) -> Vec<(IdentityId, Referendum)> {
+        self.referenda.lock().await.clone()
+    }
+}
+
+#[async_trait]
+impl NotificationDispatcher for InMemoryNotificationDispatcher {
+    async fn deliver(&self, recipient: &IdentityId, evidence: &OutcomeEvidence) -> Result<(), String> {
+        self.delivered.lock().await.push((recipient.clone(), evidence.clone()));
+        Ok(())
+    }
+
+    async fn deliver_referendum(&self, recipient: &IdentityId, referendum: &Referendum) -> Result<(), String> {
+        self.referenda.lock().await.push((recipient.clone(), referendum.clone()));
+        Ok(())
+    }
+}
+
+/// Whose votes were counted in a proposal's tally, or who may vote in a referendum
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EligibilitySnapshot {
+    /// The proposal or referendum the snapshot belongs to
+    pub proposal_id: String,
+    /// Sorted voters whose votes were counted, or the referendum's eligible roster
+    pub counted: Vec<IdentityId>,
+    /// Sorted members left out: conflicted voters, or members suspended for unpaid dues
+    pub excluded: Vec<IdentityId>,
+    /// When the snapshot was taken (Unix timestamp)
+    pub taken_at: i64,
+}
+
+/// Signed evidence of a proposal's outcome
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutcomeEvidence {
+    /// The kernel identity that issued the bundle
+    pub issuer: IdentityId,
+    pub proposal_id: String,
+    pub status: ProposalStatus,
+    pub tally: ConflictAwareTally,
+    /// CID of the [`EligibilitySnapshot`]
+    pub eligibility_snapshot_cid: String,
+    /// CID of the published ballot receipt hashes, if any were published
+    pub receipt_hashes_cid: Option<String>,
+    /// CID of the execution receipt, once the proposal was executed
+    pub execution_receipt_cid: Option<String>,
+    /// CIDs of the external attestations attached to the proposal
+    #[serde(default)]
+    pub attestation_cids: Vec<String>,
+    /// When the bundle was issued (Unix timestamp)
+    pub issued_at: i64,
+    pub proof: SignatureProof,
+}
+
+impl OutcomeEvidence {
+    /// Bytes covered by the signature: the bundle with an empty signature value
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, GovernanceError> {
+        let mut unsigned = self.clone();
+        unsigned.proof.signature_value = String::new();
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize outcome evidence for signing: {}", e)))
+    }
+}
+
+/// CID of a serialized record
+pub fn content_cid(bytes: &[u8]) -> String {
+    Cid::new_v1(0x71, create_sha256_multihash(bytes)).to_string()
+}
+
+fn sign_evidence(keypair: &icn_identity::KeyPair, evidence: &OutcomeEvidence) -> Result<String, GovernanceError> {
+    let signature = keypair.sign(Sha256::digest(evidence.signing_bytes()?).as_slice())
+        .map_err(|e| GovernanceError::StorageError(format!("Failed to sign outcome evidence: {}", e)))?;
+    Ok(BASE64.encode(signature))
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Deliver outcome evidence to members through the given dispatcher
+    pub fn with_notification_dispatcher(mut self, dispatcher: Arc<dyn NotificationDispatcher>) -> Self {
+        self.notifier = Some(dispatcher);
+        self
+    }
+
+    pub(crate) async fn store_record(&self, key: &str, bytes: Vec<u8>) -> Result<(), GovernanceError> {
+        let key_cid = self.create_key_cid(key)?;
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))
+    }
+
+    pub(crate) async fn load_record<T: serde::de::DeserializeOwned>(&self, key: &str, what: &str) -> Result<Option<T>, GovernanceError> {
+        let key_cid = self.create_key_cid(key)?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize {}: {}", what, e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load {}: {}", what, e))),
+        }
+    }
+
+    /// Snapshot whose votes count in the tally and store it under its CID
+    async fn snapshot_eligibility(&self, proposal_id: &str, tally: &ConflictAwareTally) -> Result<String, GovernanceError> {
+        let mut excluded = tally.excluded.clone();
+        excluded.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut counted = Vec::new();
+        for voter in self.load_index(&format!("proposal::voters::{}", proposal_id)).await? {
+            let voter = IdentityId(voter);
+            if excluded.contains(&voter) {
+                continue;
+            }
+            if self.get_ballot_record(proposal_id, &voter).await?.is_some() {
+                counted.push(voter);
+            }
+        }
+        counted.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let snapshot = EligibilitySnapshot {
+            proposal_id: proposal_id.to_string(),
+            counted,
+            excluded,
+            taken_at: chrono::Utc::now().timestamp(),
+        };
+        self.store_eligibility_snapshot(&snapshot).await
+    }
+
+    /// Store a snapshot under its CID
+    pub(crate) async fn store_eligibility_snapshot(&self, snapshot: &EligibilitySnapshot) -> Result<String, GovernanceError> {
+        let snapshot_bytes = serde_json::to_vec(snapshot)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize eligibility snapshot: {}", e)))?;
+        let cid = content_cid(&snapshot_bytes);
+        self.store_record(&format!("proposal::eligibility::{}", cid), snapshot_bytes).await?;
+        Ok(cid)
+    }
+
+    /// Issue, store, log and deliver the outcome evidence of a finalized or executed
+    /// proposal. Delivery failures don't fail the outcome; they're listed in the event.
+    pub(crate) async fn issue_outcome_evidence(&self, proposal_id: &str, execution_receipt_cid: Option<String>) -> Result<OutcomeEvidence, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let tally = self.conflict_aware_tally(proposal_id).await?;
+        let eligibility_snapshot_cid = self.snapshot_eligibility(proposal_id, &tally).await?;
+
+        let receipt_hashes_cid = match self.get_published_receipts(proposal_id).await? {
+            Some(published) => Some(content_cid(&serde_json::to_vec(&published)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize receipt hashes: {}", e)))?)),
+            None => None,
+        };
+
+        let attestation_cids = self.get_proposal_attestations(proposal_id).await?.iter()
+            .map(|attestation| attestation.cid())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut evidence = OutcomeEvidence {
+            issuer: IdentityId(self.identity.did().to_string()),
+            proposal_id: proposal_id.to_string(),
+            status: proposal.status.clone(),
+            tally,
+            eligibility_snapshot_cid,
+            receipt_hashes_cid,
+            execution_receipt_cid,
+            attestation_cids,
+            issued_at: chrono::Utc::now().timestamp(),
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: String::new(), // Will be filled after signing
+                created: chrono::Utc::now().timestamp(),
+                verification_method: format!("{}#keys-1", self.identity.did()),
+                purpose: "assertionMethod".to_string(),
+            },
+        };
+        evidence.proof.signature_value = sign_evidence(self.identity.keypair(), &evidence)?;
+
+        let evidence_bytes = serde_json::to_vec(&evidence)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize outcome evidence: {}", e)))?;
+        let evidence_cid = content_cid(&evidence_bytes);
+        self.store_record(&format!("proposal::outcome_evidence::{}", proposal_id), evidence_bytes).await?;
+
+        let snapshot = self.get_eligibility_snapshot(&evidence.eligibility_snapshot_cid).await?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Eligibility snapshot {} not found", evidence.eligibility_snapshot_cid)))?;
+        let scope_members = match &proposal.scope_id {
+            Some(scope_id) => self.get_scope_members(&scope_id.0).await?,
+            None => Vec::new(),
+        };
+        let mut recipients = vec![proposal.proposer.clone()];
+        for member in snapshot.counted.into_iter().chain(snapshot.excluded).chain(scope_members) {
+            if !recipients.contains(&member) {
+                recipients.push(member);
+            }
+        }
+
+        let mut undelivered = Vec::new();
+        if let Some(notifier) = &self.notifier {
+            for recipient in &recipients {
+                if let Err(e) = notifier.deliver(recipient, &evidence).await {
+                    undelivered.push(serde_json::json!({ "recipient": recipient.0, "error": e }));
+                }
+            }
+        }
+
+        let event_data = serde_json::json!({
+            "evidence_cid": evidence_cid,
+            "status": format!("{:?}", evidence.status),
+            "eligibility_snapshot_cid": evidence.eligibility_snapshot_cid,
+            "receipt_hashes_cid": evidence.receipt_hashes_cid,
+            "execution_receipt_cid": evidence.execution_receipt_cid,
+            "attestation_cids": evidence.attestation_cids,
+            "recipients": recipients.len(),
+            "undelivered": undelivered
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::OutcomeEvidenceIssued,
+            evidence.issuer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(evidence)
+    }
+
+    /// The latest outcome evidence issued for a proposal
+    pub async fn get_outcome_evidence(&self, proposal_id: &str) -> Result<Option<OutcomeEvidence>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("proposal::outcome_evidence::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize outcome evidence: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load outcome evidence: {}", e))),
+        }
+    }
+
+    /// The eligibility snapshot an evidence bundle refers to
+    pub async fn get_eligibility_snapshot(&self, cid: &str) -> Result<Option<EligibilitySnapshot>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("proposal::eligibility::{}", cid))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize eligibility snapshot: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load eligibility snapshot: {}", e))),
+        }
+    }
+
+    /// Check that a bundle was signed by this kernel and hasn't been altered
+    pub fn verify_outcome_evidence(&self, evidence: &OutcomeEvidence) -> Result<bool, GovernanceError> {
+        if evidence.issuer.0 != self.identity.did() {
+            return Ok(false);
+        }
+        Ok(sign_evidence(self.identity.keypair(), evidence)? == evidence.proof.signature_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::KeyPair;
+
+    #[test]
+    fn test_evidence_signature_covers_contents() {
+        let keypair = KeyPair::generate_random();
+        let mut evidence = OutcomeEvidence {
+            issuer: IdentityId("did:icn:kernel".to_string()),
+            proposal_id: "proposal:budget".to_string(),
+            status: ProposalStatus::Finalized,
+            tally: ConflictAwareTally { votes_for: 5, votes_against: 2, votes_abstain: 1, excluded: vec![] },
+            eligibility_snapshot_cid: content_cid(b"snapshot"),
+            receipt_hashes_cid: Some(content_cid(b"receipts")),
+            execution_receipt_cid: None,
+            attestation_cids: vec![],
+            issued_at: 1_700_000_000,
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: String::new(),
+                created: 1_700_000_000,
+                verification_method: "did:icn:kernel#keys-1".to_string(),
+                purpose: "assertionMethod".to_string(),
+            },
+        };
+        evidence.proof.signature_value = sign_evidence(&keypair, &evidence).unwrap();
+
+        // The signature doesn't depend on its own value, only on the contents
+        assert_eq!(sign_evidence(&keypair, &evidence).unwrap(), evidence.proof.signature_value);
+
+        let mut tampered = evidence.clone();
+        tampered.tally.votes_against = 6;
+        assert_ne!(sign_evidence(&keypair, &tampered).unwrap(), evidence.proof.signature_value);
+
+        assert_eq!(content_cid(b"snapshot"), evidence.eligibility_snapshot_cid);
+        assert_ne!(content_cid(b"snapshot"), content_cid(b"receipts"));
+    }
+}
@@ -0,0 +1,339 @@
+/*!
+# Participation Scoring
+
+Cooperatives reward members who take part. A member's participation score adds up
+the proposals they voted on, the proposals they authored, the meetings whose minutes
+list them as attending and the work-group contributions recorded for them, each
+worth the points the bylaws give it under `membership.participation`:
+
+```text
+participation: {
+  vote_points: 1, proposal_points: 3, meeting_points: 2, contribution_points: 2,
+  window_days: 365,
+  weights_votes: true, points_per_vote_weight: 10, max_vote_weight: 3
+}
+```
+
+Contributions are recorded by a member holding `record_contributions`, typically a
+working group's coordinator. The kernel issues a signed attestation of a member's
+score, which is stored and can be queried and verified later. Where the bylaws set
+`weights_votes`, the score also yields a vote weight for vote-weight providers to use:
+one, plus one per `points_per_vote_weight` points, up to `max_vote_weight`.
+*/
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, SignatureProof, Vote};
+use crate::config::ParticipationScoring;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// A member's counted activity in a scope
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParticipationActivity {
+    /// Proposals the member voted on
+    pub votes_cast: u64,
+    pub proposals_authored: u64,
+    pub meetings_attended: u64,
+    pub contributions: u64,
+}
+
+impl ParticipationScoring {
+    /// The score a member's activity earns
+    pub fn score(&self, activity: &ParticipationActivity) -> u64 {
+        activity.votes_cast * self.vote_points.unwrap_or(1)
+            + activity.proposals_authored * self.proposal_points.unwrap_or(3)
+            + activity.meetings_attended * self.meeting_points.unwrap_or(2)
+            + activity.contributions * self.contribution_points.unwrap_or(2)
+    }
+
+    /// The vote weight a score earns, if the bylaws let participation weight votes
+    pub fn vote_weight(&self, score: u64) -> Option<u64> {
+        if !self.weights_votes.unwrap_or(false) {
+            return None;
+        }
+        let weight = 1 + score / self.points_per_vote_weight.unwrap_or(10).max(1);
+        Some(self.max_vote_weight.map_or(weight, |max| weight.min(max.max(1))))
+    }
+
+    /// Earliest time counted activity may have taken place, if the score has a window
+    pub fn window_start(&self, now: i64) -> Option<i64> {
+        self.window_days.map(|days| now - days as i64 * SECONDS_PER_DAY)
+    }
+}
+
+/// A member's contribution to a working group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkGroupContribution {
+    pub scope_id: String,
+    pub member: IdentityId,
+    pub work_group: String,
+    pub description: String,
+    /// Who recorded the contribution
+    pub recorded_by: IdentityId,
+    /// When the contribution was recorded (Unix timestamp)
+    pub recorded_at: i64,
+}
+
+/// Signed attestation of a member's participation score
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParticipationAttestation {
+    /// The kernel identity that issued the attestation
+    pub issuer: IdentityId,
+    pub scope_id: String,
+    pub member: IdentityId,
+    pub activity: ParticipationActivity,
+    pub score: u64,
+    /// The vote weight the score earns, if the bylaws let participation weight votes
+    pub vote_weight: Option<u64>,
+    /// Earliest activity counted, if the score has a window (Unix timestamp)
+    pub window_start: Option<i64>,
+    /// When the attestation was issued (Unix timestamp)
+    pub issued_at: i64,
+    pub proof: SignatureProof,
+}
+
+impl ParticipationAttestation {
+    /// Bytes covered by the signature: the attestation with an empty signature value
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, GovernanceError> {
+        let mut unsigned = self.clone();
+        unsigned.proof.signature_value = String::new();
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize participation attestation for signing: {}", e)))
+    }
+}
+
+fn sign_attestation(keypair: &icn_identity::KeyPair, attestation: &ParticipationAttestation) -> Result<String, GovernanceError> {
+    let signature = keypair.sign(Sha256::digest(attestation.signing_bytes()?).as_slice())
+        .map_err(|e| GovernanceError::StorageError(format!("Failed to sign participation attestation: {}", e)))?;
+    Ok(BASE64.encode(signature))
+}
+
+fn contributions_key(scope_id: &str) -> String {
+    format!("contributions::{}", scope_id)
+}
+
+fn attestation_key(scope_id: &str, member: &IdentityId) -> String {
+    format!("participation::{}::{}", scope_id, member.0)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The participation scoring rules of a scope, or the defaults if it sets none
+    pub async fn participation_scoring(&self, scope_id: &str) -> Result<ParticipationScoring, GovernanceError> {
+        Ok(self.load_governance_config(scope_id).await?
+            .and_then(|config| config.membership)
+            .and_then(|membership| membership.participation)
+            .unwrap_or_default())
+    }
+
+    /// Record a member's contribution to a working group
+    pub async fn record_contribution(
+        &self,
+        recorder: &IdentityId,
+        scope_id: &str,
+        member: &IdentityId,
+        work_group: &str,
+        description: &str,
+    ) -> Result<WorkGroupContribution, GovernanceError> {
+        if !self.check_permission(recorder, scope_id, "record_contributions").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to record contributions in scope {}", recorder.0, scope_id
+            )));
+        }
+        if !self.get_scope_members(scope_id).await?.contains(member) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Identity {} is not a member of scope {}", member.0, scope_id
+            )));
+        }
+
+        let contribution = WorkGroupContribution {
+            scope_id: scope_id.to_string(),
+            member: member.clone(),
+            work_group: work_group.to_string(),
+            description: description.to_string(),
+            recorded_by: recorder.clone(),
+            recorded_at: chrono::Utc::now().timestamp(),
+        };
+
+        let mut contributions: Vec<WorkGroupContribution> = self.load_record(&contributions_key(scope_id), "contributions").await?
+            .unwrap_or_default();
+        contributions.push(contribution.clone());
+        let bytes = serde_json::to_vec(&contributions)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize contributions: {}", e)))?;
+        self.store_record(&contributions_key(scope_id), bytes).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ContributionRecorded,
+            recorder.clone(),
+            self.scope_type_for(scope_id).await?,
+            Some(IdentityId(scope_id.to_string())),
+            None,
+            serde_json::json!({
+                "member": member.0,
+                "work_group": work_group,
+                "description": description
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(contribution)
+    }
+
+    /// Work-group contributions recorded for a member, oldest first
+    pub async fn get_member_contributions(&self, scope_id: &str, member: &IdentityId) -> Result<Vec<WorkGroupContribution>, GovernanceError> {
+        let contributions: Vec<WorkGroupContribution> = self.load_record(&contributions_key(scope_id), "contributions").await?
+            .unwrap_or_default();
+        Ok(contributions.into_iter().filter(|c| &c.member == member).collect())
+    }
+
+    /// A member's activity in a scope since `since` (all activity if `None`)
+    pub async fn participation_activity(&self, scope_id: &str, member: &IdentityId, since: Option<i64>) -> Result<ParticipationActivity, GovernanceError> {
+        let counts = |timestamp: i64| since.map_or(true, |since| timestamp >= since);
+        let mut activity = ParticipationActivity::default();
+
+        for proposal_id in self.get_scope_proposal_ids(scope_id).await? {
+            let vote: Option<Vote> = self.load_record(&format!("vote::{}::{}", proposal_id, member.0), "vote").await?;
+            if vote.map_or(false, |v| counts(v.timestamp)) {
+                activity.votes_cast += 1;
+            }
+
+            let proposal = self.get_proposal(proposal_id).await?;
+            if &proposal.proposer == member && counts(proposal.created_at) {
+                activity.proposals_authored += 1;
+            }
+        }
+
+        activity.meetings_attended = self.get_scope_minutes(scope_id).await?.iter()
+            .filter(|anchored| counts(anchored.minutes.held_at))
+            .filter(|anchored| anchored.minutes.attendees.iter().any(|a| &a.did == member))
+            .count() as u64;
+
+        activity.contributions = self.get_member_contributions(scope_id, member).await?.iter()
+            .filter(|c| counts(c.recorded_at))
+            .count() as u64;
+
+        Ok(activity)
+    }
+
+    /// A member's current participation score in a scope
+    pub async fn participation_score(&self, scope_id: &str, member: &IdentityId) -> Result<u64, GovernanceError> {
+        let scoring = self.participation_scoring(scope_id).await?;
+        let activity = self.participation_activity(scope_id, member, scoring.window_start(chrono::Utc::now().timestamp())).await?;
+        Ok(scoring.score(&activity))
+    }
+
+    /// The vote weight a member's participation earns, or `None` where the bylaws
+    /// don't let participation weight votes
+    pub async fn participation_vote_weight(&self, scope_id: &str, member: &IdentityId) -> Result<Option<u64>, GovernanceError> {
+        let scoring = self.participation_scoring(scope_id).await?;
+        if !scoring.weights_votes.unwrap_or(false) {
+            return Ok(None);
+        }
+        Ok(scoring.vote_weight(self.participation_score(scope_id, member).await?))
+    }
+
+    /// Score a member's participation and issue a signed attestation of it. The
+    /// attestation replaces the member's previous one.
+    pub async fn attest_participation(&self, scope_id: &str, member: &IdentityId) -> Result<ParticipationAttestation, GovernanceError> {
+        let scoring = self.participation_scoring(scope_id).await?;
+        let now = chrono::Utc::now().timestamp();
+        let window_start = scoring.window_start(now);
+        let activity = self.participation_activity(scope_id, member, window_start).await?;
+        let score = scoring.score(&activity);
+
+        let mut attestation = ParticipationAttestation {
+            issuer: IdentityId(self.identity.did().to_string()),
+            scope_id: scope_id.to_string(),
+            member: member.clone(),
+            activity,
+            score,
+            vote_weight: scoring.vote_weight(score),
+            window_start,
+            issued_at: now,
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: String::new(), // Will be filled after signing
+                created: now,
+                verification_method: format!("{}#keys-1", self.identity.did()),
+                purpose: "assertionMethod".to_string(),
+            },
+        };
+        attestation.proof.signature_value = sign_attestation(self.identity.keypair(), &attestation)?;
+
+        let bytes = serde_json::to_vec(&attestation)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize participation attestation: {}", e)))?;
+        self.store_record(&attestation_key(scope_id, member), bytes).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ParticipationAttested,
+            attestation.issuer.clone(),
+            self.scope_type_for(scope_id).await?,
+            Some(IdentityId(scope_id.to_string())),
+            None,
+            serde_json::json!({
+                "member": member.0,
+                "score": score,
+                "activity": activity,
+                "vote_weight": attestation.vote_weight
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(attestation)
+    }
+
+    /// A member's latest participation attestation in a scope
+    pub async fn get_participation_attestation(&self, scope_id: &str, member: &IdentityId) -> Result<Option<ParticipationAttestation>, GovernanceError> {
+        self.load_record(&attestation_key(scope_id, member), "participation attestation").await
+    }
+
+    /// Check that an attestation was signed by this kernel and hasn't been altered
+    pub fn verify_participation_attestation(&self, attestation: &ParticipationAttestation) -> Result<bool, GovernanceError> {
+        if attestation.issuer.0 != self.identity.did() {
+            return Ok(false);
+        }
+        Ok(sign_attestation(self.identity.keypair(), attestation)? == attestation.proof.signature_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_and_vote_weight() {
+        let activity = ParticipationActivity {
+            votes_cast: 10,
+            proposals_authored: 2,
+            meetings_attended: 4,
+            contributions: 3,
+        };
+
+        let defaults = ParticipationScoring::default();
+        assert_eq!(defaults.score(&activity), 10 + 6 + 8 + 6);
+        // Participation doesn't weight votes unless the bylaws say so
+        assert_eq!(defaults.vote_weight(30), None);
+
+        let weighted = ParticipationScoring {
+            meeting_points: Some(5),
+            weights_votes: Some(true),
+            max_vote_weight: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(weighted.score(&activity), 10 + 6 + 20 + 6);
+        assert_eq!(weighted.vote_weight(0), Some(1));
+        assert_eq!(weighted.vote_weight(19), Some(2));
+        assert_eq!(weighted.vote_weight(42), Some(3));
+
+        let windowed = ParticipationScoring { window_days: Some(30), ..Default::default() };
+        assert_eq!(windowed.window_start(1_700_000_000), Some(1_700_000_000 - 30 * 86_400));
+        assert_eq!(defaults.window_start(1_700_000_000), None);
+    }
+}
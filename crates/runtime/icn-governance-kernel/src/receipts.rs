@@ -0,0 +1,289 @@
+/*!
+# Ballot Receipts
+
+Every recorded vote earns the voter a signed ballot receipt. The receipt names the
+proposal and the time of the vote and carries a commitment to the choice: a SHA-256
+hash over the proposal, voter, choice and a random nonce. The receipt, nonce included,
+is returned to the voter by [`GovernanceKernel::record_vote`] and nowhere else; the voter
+keeps it as proof of participation and can open the commitment with
+[`BallotReceipt::opens_to`]. The kernel only keeps a [`BallotRecord`] of the commitment
+and the receipt's hash, so nobody with access to its storage can open a commitment.
+
+When a proposal is finalized the kernel publishes the hashes of the receipts of every
+counted ballot; ballots left out for a conflict of interest aren't included. A voter confirms their vote was counted by finding the hash of their receipt in the
+published set, without the set revealing anyone's choice.
+*/
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use uuid::Uuid;
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, SignatureProof, Vote, VoteChoice};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// A signed receipt for a cast ballot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BallotReceipt {
+    /// The kernel identity that issued the receipt
+    pub issuer: IdentityId,
+    pub voter: IdentityId,
+    pub proposal_id: String,
+    /// Hex-encoded commitment to the vote choice
+    pub choice_commitment: String,
+    /// Random nonce that opens the commitment; held by the voter, never stored by the kernel
+    pub nonce: String,
+    /// When the vote was cast (Unix timestamp)
+    pub timestamp: i64,
+    pub proof: SignatureProof,
+}
+
+impl BallotReceipt {
+    /// Hash identifying this receipt in the published set
+    pub fn receipt_hash(&self) -> Result<String, GovernanceError> {
+        let canonical = serde_json::to_vec(self)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize ballot receipt: {}", e)))?;
+        Ok(format!("{:x}", Sha256::digest(&canonical)))
+    }
+
+    /// Whether the receipt's commitment was made to the given choice
+    pub fn opens_to(&self, choice: &VoteChoice) -> bool {
+        choice_commitment(&self.proposal_id, &self.voter, choice, &self.nonce) == self.choice_commitment
+    }
+}
+
+/// What the kernel keeps of an issued receipt
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BallotRecord {
+    pub voter: IdentityId,
+    pub proposal_id: String,
+    /// Hex-encoded commitment to the vote choice
+    pub choice_commitment: String,
+    /// Hash of the voter's receipt, published when the proposal is finalized
+    pub receipt_hash: String,
+    /// When the vote was cast (Unix timestamp)
+    pub timestamp: i64,
+}
+
+impl BallotRecord {
+    /// The record of a receipt, without the nonce that opens its commitment
+    pub fn of(receipt: &BallotReceipt) -> Result<Self, GovernanceError> {
+        Ok(Self {
+            voter: receipt.voter.clone(),
+            proposal_id: receipt.proposal_id.clone(),
+            choice_commitment: receipt.choice_commitment.clone(),
+            receipt_hash: receipt.receipt_hash()?,
+            timestamp: receipt.timestamp,
+        })
+    }
+}
+
+/// Receipt hashes published when a proposal's votes were tallied
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublishedReceipts {
+    pub proposal_id: String,
+    /// Sorted receipt hashes, one per counted ballot
+    pub receipt_hashes: Vec<String>,
+    /// When the set was published (Unix timestamp)
+    pub published_at: i64,
+}
+
+impl PublishedReceipts {
+    /// Whether the receipt's ballot was counted
+    pub fn includes(&self, receipt: &BallotReceipt) -> Result<bool, GovernanceError> {
+        Ok(self.receipt_hashes.binary_search(&receipt.receipt_hash()?).is_ok())
+    }
+}
+
+/// Commitment to a vote choice
+pub fn choice_commitment(proposal_id: &str, voter: &IdentityId, choice: &VoteChoice, nonce: &str) -> String {
+    let preimage = format!("{}|{}|{:?}|{}", proposal_id, voter.0, choice, nonce);
+    format!("{:x}", Sha256::digest(preimage.as_bytes()))
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Issue a signed receipt for a recorded vote and store its record. A revote
+    /// replaces the earlier record.
+    pub(crate) async fn issue_ballot_receipt(&self, vote: &Vote) -> Result<BallotReceipt, GovernanceError> {
+        let nonce = Uuid::new_v4().to_string();
+        let mut receipt = BallotReceipt {
+            issuer: IdentityId(self.identity.did().to_string()),
+            voter: vote.voter.clone(),
+            proposal_id: vote.proposal_id.clone(),
+            choice_commitment: choice_commitment(&vote.proposal_id, &vote.voter, &vote.choice, &nonce),
+            nonce,
+            timestamp: vote.timestamp,
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: String::new(), // Will be filled after signing
+                created: chrono::Utc::now().timestamp(),
+                verification_method: format!("{}#keys-1", self.identity.did()),
+                purpose: "assertionMethod".to_string(),
+            },
+        };
+
+        let canonical = serde_json::to_string(&receipt)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize receipt for signing: {}", e)))?;
+        let signature = self.identity.keypair().sign(Sha256::digest(canonical.as_bytes()).as_slice())
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to sign ballot receipt: {}", e)))?;
+        receipt.proof.signature_value = BASE64.encode(signature);
+
+        let record_bytes = serde_json::to_vec(&BallotRecord::of(&receipt)?)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize ballot record: {}", e)))?;
+        let key_cid = self.create_key_cid(&format!("ballot_receipt::{}::{}", vote.proposal_id, vote.voter.0))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, record_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+
+        Ok(receipt)
+    }
+
+    /// The record of the voter's ballot receipt for a proposal, if they voted
+    pub async fn get_ballot_record(&self, proposal_id: &str, voter: &IdentityId) -> Result<Option<BallotRecord>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("ballot_receipt::{}::{}", proposal_id, voter.0))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize ballot record: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load ballot record: {}", e))),
+        }
+    }
+
+    /// Publish the hashes of the receipts of every counted ballot on a proposal. Ballots
+    /// left out of the tally for a conflict of interest aren't published.
+    pub(crate) async fn publish_receipt_hashes(&self, proposal_id: &str, issuer: &IdentityId) -> Result<PublishedReceipts, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let excluded = self.conflict_aware_tally(proposal_id).await?.excluded;
+
+        let mut receipt_hashes = Vec::new();
+        for voter in self.load_index(&format!("proposal::voters::{}", proposal_id)).await? {
+            let voter = IdentityId(voter);
+            if excluded.contains(&voter) {
+                continue;
+            }
+            if let Some(record) = self.get_ballot_record(proposal_id, &voter).await? {
+                receipt_hashes.push(record.receipt_hash);
+            }
+        }
+        receipt_hashes.sort();
+
+        let published = PublishedReceipts {
+            proposal_id: proposal_id.to_string(),
+            receipt_hashes,
+            published_at: chrono::Utc::now().timestamp(),
+        };
+
+        let published_bytes = serde_json::to_vec(&published)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize receipt hashes: {}", e)))?;
+        let key_cid = self.create_key_cid(&format!("proposal::receipt_hashes::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, published_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+
+        let event_data = serde_json::json!({
+            "receipt_hashes": published.receipt_hashes
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::BallotReceiptsPublished,
+            issuer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(published)
+    }
+
+    /// The receipt hashes published when the proposal was finalized
+    pub async fn get_published_receipts(&self, proposal_id: &str) -> Result<Option<PublishedReceipts>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("proposal::receipt_hashes::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize receipt hashes: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load receipt hashes: {}", e))),
+        }
+    }
+
+    /// Check that a voter's ballot was counted in the published tally
+    pub async fn verify_ballot_counted(&self, receipt: &BallotReceipt) -> Result<bool, GovernanceError> {
+        match self.get_published_receipts(&receipt.proposal_id).await? {
+            Some(published) => published.includes(receipt),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(choice: &VoteChoice, nonce: &str) -> BallotReceipt {
+        let voter = IdentityId("did:icn:member:alice".to_string());
+        BallotReceipt {
+            issuer: IdentityId("did:icn:kernel".to_string()),
+            choice_commitment: choice_commitment("proposal:budget", &voter, choice, nonce),
+            voter,
+            proposal_id: "proposal:budget".to_string(),
+            nonce: nonce.to_string(),
+            timestamp: 1_700_000_000,
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: "c2lnbmF0dXJl".to_string(),
+                created: 1_700_000_000,
+                verification_method: "did:icn:kernel#keys-1".to_string(),
+                purpose: "assertionMethod".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_receipt_commitment_and_inclusion() {
+        let alice = receipt(&VoteChoice::For, "nonce-1");
+        assert!(alice.opens_to(&VoteChoice::For));
+        assert!(!alice.opens_to(&VoteChoice::Against));
+
+        // The same choice under a different nonce gives an unlinkable commitment
+        let other = receipt(&VoteChoice::For, "nonce-2");
+        assert_ne!(alice.choice_commitment, other.choice_commitment);
+
+        let mut receipt_hashes = vec![alice.receipt_hash().unwrap(), receipt(&VoteChoice::Against, "nonce-3").receipt_hash().unwrap()];
+        receipt_hashes.sort();
+        let published = PublishedReceipts {
+            proposal_id: "proposal:budget".to_string(),
+            receipt_hashes,
+            published_at: 1_700_086_400,
+        };
+        assert!(published.includes(&alice).unwrap());
+        assert!(!published.includes(&other).unwrap());
+    }
+
+    #[test]
+    fn test_record_keeps_no_nonce() {
+        let alice = receipt(&VoteChoice::For, "nonce-1");
+        let record = BallotRecord::of(&alice).unwrap();
+        assert_eq!(record.receipt_hash, alice.receipt_hash().unwrap());
+        assert_eq!(record.choice_commitment, alice.choice_commitment);
+
+        // What the kernel stores can't open the commitment
+        assert!(!serde_json::to_string(&record).unwrap().contains("nonce-1"));
+    }
+}
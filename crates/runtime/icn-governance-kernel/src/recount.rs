@@ -0,0 +1,357 @@
+/*!
+# Tally Recounts
+
+A contested result can be recounted from what the kernel stored, without trusting the
+recorded tally. When a proposal is finalized the kernel records the inputs its tally
+depends on besides the votes themselves: the conflict-of-interest settings and flags in
+effect, and the CID of the governance config version the scope had then, a copy of
+which is kept under that CID.
+
+[`GovernanceKernel::recount`] re-runs the tally from the stored votes and those inputs,
+checks the counted and excluded voters against the eligibility snapshot of the outcome
+evidence, checks that the evidence is still validly signed, and issues a signed
+[`RecountCertificate`] listing any discrepancy. Every certificate is kept, so repeated
+recounts of the same proposal can be compared.
+*/
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, ProposalStatus, SignatureProof, Vote, VoteChoice};
+use crate::coi::{ConflictAwareTally, ConflictFlag, ConflictOfInterestSettings, is_vote_excluded};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::outcomes::{EligibilitySnapshot, content_cid};
+
+/// Inputs of a proposal's tally besides the votes, recorded when it was finalized
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TallyInputs {
+    pub proposal_id: String,
+    /// CID of the governance config version in effect, if the scope had a config
+    pub governance_config_cid: Option<String>,
+    pub coi_settings: ConflictOfInterestSettings,
+    pub conflict_flags: Vec<ConflictFlag>,
+    /// When the inputs were recorded (Unix timestamp)
+    pub recorded_at: i64,
+}
+
+/// Signed result of recounting a proposal's tally
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecountCertificate {
+    /// The kernel identity that performed the recount
+    pub issuer: IdentityId,
+    pub proposal_id: String,
+    /// Tally recorded in the outcome evidence
+    pub recorded_tally: ConflictAwareTally,
+    /// Tally recomputed from the stored votes
+    pub recounted_tally: ConflictAwareTally,
+    /// CID of the eligibility snapshot the recount was checked against
+    pub eligibility_snapshot_cid: String,
+    /// CID of the recorded [`TallyInputs`]
+    pub inputs_cid: String,
+    pub governance_config_cid: Option<String>,
+    /// Whether the recount reproduced the recorded result
+    pub matches: bool,
+    /// Every difference found; empty when the result matches
+    pub discrepancies: Vec<String>,
+    /// When the recount was performed (Unix timestamp)
+    pub recounted_at: i64,
+    pub proof: SignatureProof,
+}
+
+impl RecountCertificate {
+    /// Bytes covered by the signature: the certificate with an empty signature value
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, GovernanceError> {
+        let mut unsigned = self.clone();
+        unsigned.proof.signature_value = String::new();
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize recount certificate for signing: {}", e)))
+    }
+}
+
+fn sign_certificate(keypair: &icn_identity::KeyPair, certificate: &RecountCertificate) -> Result<String, GovernanceError> {
+    let signature = keypair.sign(Sha256::digest(certificate.signing_bytes()?).as_slice())
+        .map_err(|e| GovernanceError::StorageError(format!("Failed to sign recount certificate: {}", e)))?;
+    Ok(BASE64.encode(signature))
+}
+
+/// Tally the votes under the recorded inputs, returning the tally and the sorted counted voters
+pub fn tally_votes(votes: &[Vote], inputs: &TallyInputs) -> (ConflictAwareTally, Vec<IdentityId>) {
+    let mut tally = ConflictAwareTally::default();
+    let mut counted = Vec::new();
+    for vote in votes {
+        if is_vote_excluded(&inputs.coi_settings, &inputs.conflict_flags, &vote.voter) {
+            tally.excluded.push(vote.voter.clone());
+            continue;
+        }
+
+        match vote.choice {
+            VoteChoice::For => tally.votes_for += vote.weight,
+            VoteChoice::Against => tally.votes_against += vote.weight,
+            VoteChoice::Abstain => tally.votes_abstain += vote.weight,
+        }
+        counted.push(vote.voter.clone());
+    }
+
+    tally.excluded.sort_by(|a, b| a.0.cmp(&b.0));
+    counted.sort_by(|a, b| a.0.cmp(&b.0));
+    (tally, counted)
+}
+
+/// Differences between a recount and the recorded tally and eligibility snapshot
+pub fn compare_recount(
+    recorded: &ConflictAwareTally,
+    recounted: &ConflictAwareTally,
+    counted: &[IdentityId],
+    snapshot: &EligibilitySnapshot,
+) -> Vec<String> {
+    let mut discrepancies = Vec::new();
+    let totals = [
+        ("for", recorded.votes_for, recounted.votes_for),
+        ("against", recorded.votes_against, recounted.votes_against),
+        ("abstain", recorded.votes_abstain, recounted.votes_abstain),
+    ];
+    for (choice, recorded, recounted) in totals {
+        if recorded != recounted {
+            discrepancies.push(format!("Votes {}: recorded {}, recounted {}", choice, recorded, recounted));
+        }
+    }
+
+    for voter in counted.iter().filter(|v| !snapshot.counted.contains(v)) {
+        discrepancies.push(format!("Vote by {} was counted in the recount but not in the snapshot", voter.0));
+    }
+    for voter in snapshot.counted.iter().filter(|v| !counted.contains(v)) {
+        discrepancies.push(format!("Vote by {} was counted in the snapshot but not in the recount", voter.0));
+    }
+    for voter in recounted.excluded.iter().filter(|v| !snapshot.excluded.contains(v)) {
+        discrepancies.push(format!("Vote by {} was excluded in the recount but not in the snapshot", voter.0));
+    }
+    for voter in snapshot.excluded.iter().filter(|v| !recounted.excluded.contains(v)) {
+        discrepancies.push(format!("Vote by {} was excluded in the snapshot but not in the recount", voter.0));
+    }
+    discrepancies
+}
+
+fn inputs_key(proposal_id: &str) -> String {
+    format!("proposal::tally_inputs::{}", proposal_id)
+}
+
+fn recounts_key(proposal_id: &str) -> String {
+    format!("proposal::recounts::{}", proposal_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Record the inputs of a proposal's tally, keeping a copy of the scope's current
+    /// governance config under its CID
+    pub(crate) async fn record_tally_inputs(&self, proposal_id: &str) -> Result<TallyInputs, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let scope_id = proposal.scope_id.as_ref().map(|sid| sid.0.clone());
+
+        let (governance_config_cid, coi_settings) = match &scope_id {
+            Some(scope_id) => {
+                let config_cid = match self.load_governance_config(scope_id).await? {
+                    Some(config) => {
+                        let config_bytes = serde_json::to_vec(&config)
+                            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize governance config: {}", e)))?;
+                        let cid = content_cid(&config_bytes);
+                        self.store_record(&format!("governance::config_version::{}", cid), config_bytes).await?;
+                        Some(cid)
+                    }
+                    None => None,
+                };
+                (config_cid, self.get_coi_settings(scope_id).await?)
+            }
+            None => (None, ConflictOfInterestSettings::default()),
+        };
+
+        let inputs = TallyInputs {
+            proposal_id: proposal_id.to_string(),
+            governance_config_cid,
+            coi_settings,
+            conflict_flags: self.get_conflict_flags(proposal_id).await?,
+            recorded_at: chrono::Utc::now().timestamp(),
+        };
+        let inputs_bytes = serde_json::to_vec(&inputs)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize tally inputs: {}", e)))?;
+        self.store_record(&inputs_key(proposal_id), inputs_bytes).await?;
+        Ok(inputs)
+    }
+
+    /// The tally inputs recorded when a proposal was finalized
+    pub async fn get_tally_inputs(&self, proposal_id: &str) -> Result<Option<TallyInputs>, GovernanceError> {
+        self.load_record(&inputs_key(proposal_id), "tally inputs").await
+    }
+
+    /// A governance config version kept for recounts
+    pub async fn get_governance_config_version(&self, cid: &str) -> Result<Option<crate::config::GovernanceConfig>, GovernanceError> {
+        self.load_record(&format!("governance::config_version::{}", cid), "governance config version").await
+    }
+
+    /// Re-run a finalized proposal's tally from its stored votes and recorded inputs,
+    /// and issue a signed certificate saying whether it reproduces the recorded result
+    pub async fn recount(&self, proposal_id: &str) -> Result<RecountCertificate, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if !matches!(proposal.status, ProposalStatus::Finalized | ProposalStatus::Passed | ProposalStatus::Rejected | ProposalStatus::Executed) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot recount proposal with status {:?}", proposal.status
+            )));
+        }
+
+        let evidence = self.get_outcome_evidence(proposal_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("No outcome evidence was issued for proposal {}", proposal_id)))?;
+        let inputs = self.get_tally_inputs(proposal_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("No tally inputs were recorded when proposal {} was finalized", proposal_id)))?;
+        let snapshot = self.get_eligibility_snapshot(&evidence.eligibility_snapshot_cid).await?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Eligibility snapshot {} is missing", evidence.eligibility_snapshot_cid)))?;
+
+        let mut votes = Vec::new();
+        for voter in self.load_index(&format!("proposal::voters::{}", proposal_id)).await? {
+            let vote: Option<Vote> = self.load_record(&format!("vote::{}::{}", proposal_id, voter), "vote").await?;
+            votes.extend(vote);
+        }
+
+        let (recounted_tally, counted) = tally_votes(&votes, &inputs);
+        let mut discrepancies = compare_recount(&evidence.tally, &recounted_tally, &counted, &snapshot);
+
+        if !self.verify_outcome_evidence(&evidence)? {
+            discrepancies.push("The outcome evidence signature does not verify".to_string());
+        }
+        if let Some(cid) = &inputs.governance_config_cid {
+            let config = self.get_governance_config_version(cid).await?;
+            let intact = match &config {
+                Some(config) => serde_json::to_vec(config).map(|bytes| &content_cid(&bytes) == cid).unwrap_or(false),
+                None => false,
+            };
+            if !intact {
+                discrepancies.push(format!("Governance config version {} is missing or altered", cid));
+            }
+        }
+
+        let inputs_bytes = serde_json::to_vec(&inputs)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize tally inputs: {}", e)))?;
+
+        let mut certificate = RecountCertificate {
+            issuer: IdentityId(self.identity.did().to_string()),
+            proposal_id: proposal_id.to_string(),
+            recorded_tally: evidence.tally.clone(),
+            recounted_tally,
+            eligibility_snapshot_cid: evidence.eligibility_snapshot_cid.clone(),
+            inputs_cid: content_cid(&inputs_bytes),
+            governance_config_cid: inputs.governance_config_cid.clone(),
+            matches: discrepancies.is_empty(),
+            discrepancies,
+            recounted_at: chrono::Utc::now().timestamp(),
+            proof: SignatureProof {
+                signature_type: "Ed25519Signature2020".to_string(),
+                signature_value: String::new(), // Will be filled after signing
+                created: chrono::Utc::now().timestamp(),
+                verification_method: format!("{}#keys-1", self.identity.did()),
+                purpose: "assertionMethod".to_string(),
+            },
+        };
+        certificate.proof.signature_value = sign_certificate(self.identity.keypair(), &certificate)?;
+
+        let mut recounts = self.get_recounts(proposal_id).await?;
+        recounts.push(certificate.clone());
+        let recounts_bytes = serde_json::to_vec(&recounts)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize recount certificates: {}", e)))?;
+        self.store_record(&recounts_key(proposal_id), recounts_bytes).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::TallyRecounted,
+            certificate.issuer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "matches": certificate.matches,
+                "discrepancies": certificate.discrepancies,
+                "eligibility_snapshot_cid": certificate.eligibility_snapshot_cid,
+                "inputs_cid": certificate.inputs_cid,
+                "governance_config_cid": certificate.governance_config_cid
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(certificate)
+    }
+
+    /// Every recount certificate issued for a proposal, oldest first
+    pub async fn get_recounts(&self, proposal_id: &str) -> Result<Vec<RecountCertificate>, GovernanceError> {
+        Ok(self.load_record(&recounts_key(proposal_id), "recount certificates").await?.unwrap_or_default())
+    }
+
+    /// Check that a recount certificate was signed by this kernel and hasn't been altered
+    pub fn verify_recount_certificate(&self, certificate: &RecountCertificate) -> Result<bool, GovernanceError> {
+        if certificate.issuer.0 != self.identity.did() {
+            return Ok(false);
+        }
+        Ok(sign_certificate(self.identity.keypair(), certificate)? == certificate.proof.signature_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::IdentityScope;
+    use crate::coi::RecusalMode;
+
+    fn vote(voter: &str, choice: VoteChoice) -> Vote {
+        Vote {
+            voter: IdentityId(voter.to_string()),
+            proposal_id: "proposal:new-roof".to_string(),
+            choice,
+            weight: 1,
+            scope: IdentityScope::Cooperative,
+            scope_id: None,
+            reason: None,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_recount_reproduces_the_recorded_tally() {
+        let votes = vec![
+            vote("did:icn:alice", VoteChoice::For),
+            vote("did:icn:bob", VoteChoice::Against),
+            vote("did:icn:carol", VoteChoice::For),
+        ];
+        let inputs = TallyInputs {
+            proposal_id: "proposal:new-roof".to_string(),
+            governance_config_cid: None,
+            coi_settings: ConflictOfInterestSettings { enabled: true, mode: RecusalMode::ExcludeVote },
+            conflict_flags: vec![ConflictFlag {
+                proposal_id: "proposal:new-roof".to_string(),
+                member: IdentityId("did:icn:carol".to_string()),
+                matches: vec![],
+                declaration: None,
+            }],
+            recorded_at: 1_700_000_000,
+        };
+
+        let (recounted, counted) = tally_votes(&votes, &inputs);
+        assert_eq!((recounted.votes_for, recounted.votes_against), (1, 1));
+        assert_eq!(recounted.excluded, vec![IdentityId("did:icn:carol".to_string())]);
+
+        let snapshot = EligibilitySnapshot {
+            proposal_id: "proposal:new-roof".to_string(),
+            counted: counted.clone(),
+            excluded: recounted.excluded.clone(),
+            taken_at: 1_700_000_000,
+        };
+        assert!(compare_recount(&recounted, &recounted, &counted, &snapshot).is_empty());
+
+        // A recorded result that the stored votes don't support is reported
+        let recorded = ConflictAwareTally { votes_for: 2, ..recounted.clone() };
+        let discrepancies = compare_recount(&recorded, &recounted, &counted, &snapshot);
+        assert_eq!(discrepancies, vec!["Votes for: recorded 2, recounted 1".to_string()]);
+
+        // So is a vote that wasn't in the snapshot
+        let mut stale = snapshot.clone();
+        stale.counted.retain(|v| v.0 != "did:icn:bob");
+        assert_eq!(compare_recount(&recounted, &recounted, &counted, &stale).len(), 1);
+    }
+}
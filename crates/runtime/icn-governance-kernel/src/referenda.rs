@@ -0,0 +1,497 @@
+/*!
+# Referenda
+
+Proposals are decided by the members whose roles carry `vote_on_proposals`. Some
+questions belong to the whole membership instead: dissolving the cooperative, amending
+its bylaws, merging with another scope. A referendum puts one question to the entire
+roster of a scope, regardless of role.
+
+A member with the `call_referendum` permission opens a referendum with a ballot format
+(yes/no, or a choice between options), a minimum turnout and the majority the winning
+answer needs. Opening takes an [`EligibilitySnapshot`] of the roster: every member
+holding a role in the scope, less those whose vote is suspended for unpaid dues. Only
+members in the snapshot may vote, so joining or leaving afterwards doesn't change who
+decides. The snapshot's CID is kept with the referendum, and eligible members are
+notified through the kernel's [`NotificationDispatcher`] when it opens and again with
+the result.
+
+A referendum carries only if enough of the snapshot voted (abstentions count towards
+turnout) and the winning answer reached the required majority of the non-abstaining
+ballots. Ties between options carry nothing.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::{IdentityId, IdentityScope};
+use icn_storage::StorageBackend;
+use uuid::Uuid;
+
+use crate::{GovernanceKernel, GovernanceError};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+use crate::outcomes::{EligibilitySnapshot, NotificationDispatcher};
+
+/// Basis points in one whole
+const BPS: u64 = 10_000;
+
+/// Index of every member holding a role in a scope
+pub(crate) fn scope_members_index(scope_id: &str) -> String {
+    format!("scope::members::{}", scope_id)
+}
+
+/// What members choose between
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum BallotFormat {
+    YesNo,
+    /// One of several options
+    MultiOption { options: Vec<String> },
+}
+
+impl BallotFormat {
+    /// Labels of the answers, in tally order
+    pub fn answers(&self) -> Vec<String> {
+        match self {
+            BallotFormat::YesNo => vec!["yes".to_string(), "no".to_string()],
+            BallotFormat::MultiOption { options } => options.clone(),
+        }
+    }
+}
+
+/// A member's ballot in a referendum
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReferendumChoice {
+    Yes,
+    No,
+    /// Index into the options of a multi-option ballot
+    Option(usize),
+    Abstain,
+}
+
+/// Share of the non-abstaining ballots the winning answer needs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Majority {
+    /// More than half
+    Simple,
+    /// At least two thirds
+    TwoThirds,
+    /// At least three quarters
+    ThreeQuarters,
+}
+
+impl Majority {
+    /// Whether `support` out of `total` ballots meets this majority
+    pub fn met(&self, support: u64, total: u64) -> bool {
+        if total == 0 {
+            return false;
+        }
+        match self {
+            Majority::Simple => support * 2 > total,
+            Majority::TwoThirds => support * 3 >= total * 2,
+            Majority::ThreeQuarters => support * 4 >= total * 3,
+        }
+    }
+}
+
+/// Thresholds a referendum must meet to carry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReferendumRules {
+    /// Share of the eligible roster that must cast a ballot, in basis points
+    pub min_turnout_bps: u32,
+    pub majority: Majority,
+}
+
+/// A question to put to a scope's membership
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferendumDraft {
+    pub scope: IdentityScope,
+    pub scope_id: String,
+    pub question: String,
+    pub description: String,
+    pub format: BallotFormat,
+    pub rules: ReferendumRules,
+    /// When voting closes (Unix timestamp)
+    pub closes_at: i64,
+}
+
+/// Whether a referendum is still taking ballots
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReferendumStatus {
+    Open,
+    Closed,
+}
+
+/// How a closed referendum was decided
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReferendumOutcome {
+    /// The answer with the given label carried
+    Carried(String),
+    /// Too few eligible members voted
+    TurnoutNotMet,
+    /// No answer reached the required majority
+    NoMajority,
+}
+
+/// The tally of a closed referendum
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReferendumResult {
+    /// Ballots per answer, in the order of [`BallotFormat::answers`]
+    pub tally: Vec<u64>,
+    pub abstentions: u64,
+    /// Members in the eligibility snapshot
+    pub eligible: u64,
+    /// Share of the eligible members who voted, in basis points
+    pub turnout_bps: u64,
+    pub outcome: ReferendumOutcome,
+}
+
+/// A question put to a scope's entire roster
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Referendum {
+    pub id: String,
+    pub scope: IdentityScope,
+    pub scope_id: String,
+    pub question: String,
+    pub description: String,
+    pub format: BallotFormat,
+    pub rules: ReferendumRules,
+    pub opened_by: IdentityId,
+    /// When the referendum opened (Unix timestamp)
+    pub opened_at: i64,
+    /// When voting closes (Unix timestamp)
+    pub closes_at: i64,
+    /// CID of the roster snapshot of who may vote
+    pub eligibility_snapshot_cid: String,
+    pub status: ReferendumStatus,
+    pub result: Option<ReferendumResult>,
+}
+
+/// A member's recorded referendum ballot
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReferendumBallot {
+    pub referendum_id: String,
+    pub voter: IdentityId,
+    pub choice: ReferendumChoice,
+    /// When the ballot was cast (Unix timestamp)
+    pub cast_at: i64,
+}
+
+/// Tally ballots against the eligible roster and decide the outcome
+pub fn tally_referendum(
+    format: &BallotFormat,
+    rules: &ReferendumRules,
+    eligible: u64,
+    ballots: &[ReferendumChoice],
+) -> ReferendumResult {
+    let answers = format.answers();
+    let mut tally = vec![0u64; answers.len()];
+    let mut abstentions = 0;
+    for choice in ballots {
+        match (format, choice) {
+            (_, ReferendumChoice::Abstain) => abstentions += 1,
+            (BallotFormat::YesNo, ReferendumChoice::Yes) => tally[0] += 1,
+            (BallotFormat::YesNo, ReferendumChoice::No) => tally[1] += 1,
+            (BallotFormat::MultiOption { .. }, ReferendumChoice::Option(i)) if *i < tally.len() => tally[*i] += 1,
+            // Ballots are validated when cast, so mismatched choices never reach the tally
+            _ => {}
+        }
+    }
+
+    let turnout_bps = if eligible == 0 { 0 } else { ballots.len() as u64 * BPS / eligible };
+    let decisive: u64 = tally.iter().sum();
+    let top = tally.iter().copied().max().unwrap_or(0);
+    let leaders: Vec<usize> = (0..tally.len()).filter(|&i| tally[i] == top).collect();
+
+    let outcome = if turnout_bps < rules.min_turnout_bps as u64 {
+        ReferendumOutcome::TurnoutNotMet
+    } else if leaders.len() == 1 && rules.majority.met(top, decisive) {
+        ReferendumOutcome::Carried(answers[leaders[0]].clone())
+    } else {
+        ReferendumOutcome::NoMajority
+    };
+
+    ReferendumResult { tally, abstentions, eligible, turnout_bps, outcome }
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    async fn store_referendum(&self, referendum: &Referendum) -> Result<(), GovernanceError> {
+        let bytes = serde_json::to_vec(referendum)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize referendum: {}", e)))?;
+        self.store_record(&format!("referendum::{}", referendum.id), bytes).await
+    }
+
+    /// Every member holding a currently valid role in a scope, sorted
+    pub async fn get_scope_members(&self, scope_id: &str) -> Result<Vec<IdentityId>, GovernanceError> {
+        let mut members = Vec::new();
+        for did in self.load_index(&scope_members_index(scope_id)).await? {
+            let member = IdentityId(did);
+            if !self.get_verified_roles(&member, scope_id).await?.is_empty() {
+                members.push(member);
+            }
+        }
+        members.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(members)
+    }
+
+    /// Snapshot who may vote in a referendum: the roster, less members whose vote is
+    /// suspended for unpaid dues
+    async fn snapshot_roster(&self, referendum_id: &str, scope_id: &str) -> Result<(String, EligibilitySnapshot), GovernanceError> {
+        let mut counted = Vec::new();
+        let mut excluded = Vec::new();
+        for member in self.get_scope_members(scope_id).await? {
+            if self.dues_standing(scope_id, &member).await?.can_vote() {
+                counted.push(member);
+            } else {
+                excluded.push(member);
+            }
+        }
+
+        let snapshot = EligibilitySnapshot {
+            proposal_id: referendum_id.to_string(),
+            counted,
+            excluded,
+            taken_at: chrono::Utc::now().timestamp(),
+        };
+        let cid = self.store_eligibility_snapshot(&snapshot).await?;
+        Ok((cid, snapshot))
+    }
+
+    async fn notify_roster(&self, referendum: &Referendum, recipients: &[IdentityId]) -> Vec<serde_json::Value> {
+        let mut undelivered = Vec::new();
+        if let Some(notifier) = &self.notifier {
+            for recipient in recipients {
+                if let Err(e) = notifier.deliver_referendum(recipient, referendum).await {
+                    undelivered.push(serde_json::json!({ "recipient": recipient.0, "error": e }));
+                }
+            }
+        }
+        undelivered
+    }
+
+    /// Put a question to a scope's entire roster. Returns the referendum ID.
+    pub async fn open_referendum(&self, opened_by: &IdentityId, draft: ReferendumDraft) -> Result<String, GovernanceError> {
+        if !self.check_permission(opened_by, &draft.scope_id, "call_referendum").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to call referenda in scope {}", opened_by.0, draft.scope_id
+            )));
+        }
+
+        if let BallotFormat::MultiOption { options } = &draft.format {
+            let mut distinct = options.clone();
+            distinct.sort();
+            distinct.dedup();
+            if distinct.len() < 2 || distinct.len() != options.len() {
+                return Err(GovernanceError::InvalidProposal(
+                    "A multi-option referendum needs at least two distinct options".to_string()
+                ));
+            }
+        }
+        if draft.rules.min_turnout_bps as u64 > BPS {
+            return Err(GovernanceError::InvalidProposal("Minimum turnout can't exceed 100%".to_string()));
+        }
+        let now = chrono::Utc::now().timestamp();
+        if draft.closes_at <= now {
+            return Err(GovernanceError::InvalidProposal("A referendum must close in the future".to_string()));
+        }
+
+        let id = format!("referendum:{}", Uuid::new_v4());
+        let (eligibility_snapshot_cid, snapshot) = self.snapshot_roster(&id, &draft.scope_id).await?;
+
+        let referendum = Referendum {
+            id: id.clone(),
+            scope: draft.scope,
+            scope_id: draft.scope_id,
+            question: draft.question,
+            description: draft.description,
+            format: draft.format,
+            rules: draft.rules,
+            opened_by: opened_by.clone(),
+            opened_at: now,
+            closes_at: draft.closes_at,
+            eligibility_snapshot_cid,
+            status: ReferendumStatus::Open,
+            result: None,
+        };
+        self.store_referendum(&referendum).await?;
+        self.append_to_index(&format!("scope::referenda::{}", referendum.scope_id), &id).await?;
+
+        let undelivered = self.notify_roster(&referendum, &snapshot.counted).await;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ReferendumOpened,
+            opened_by.clone(),
+            referendum.scope,
+            Some(IdentityId(referendum.scope_id.clone())),
+            Some(id.clone()),
+            serde_json::json!({
+                "question": referendum.question,
+                "format": referendum.format,
+                "rules": referendum.rules,
+                "closes_at": referendum.closes_at,
+                "eligibility_snapshot_cid": referendum.eligibility_snapshot_cid,
+                "eligible": snapshot.counted.len(),
+                "suspended": snapshot.excluded.len(),
+                "undelivered": undelivered
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(id)
+    }
+
+    /// A referendum by ID
+    pub async fn get_referendum(&self, referendum_id: &str) -> Result<Referendum, GovernanceError> {
+        self.load_record(&format!("referendum::{}", referendum_id), "referendum").await?
+            .ok_or_else(|| GovernanceError::ProposalNotFound(format!("Referendum {} not found", referendum_id)))
+    }
+
+    /// IDs of every referendum held in a scope
+    pub async fn get_scope_referendum_ids(&self, scope_id: &str) -> Result<Vec<String>, GovernanceError> {
+        self.load_index(&format!("scope::referenda::{}", scope_id)).await
+    }
+
+    /// A member's ballot in a referendum, if they cast one
+    pub async fn get_referendum_ballot(&self, referendum_id: &str, voter: &IdentityId) -> Result<Option<ReferendumBallot>, GovernanceError> {
+        self.load_record(&format!("referendum::ballot::{}::{}", referendum_id, voter.0), "referendum ballot").await
+    }
+
+    /// Cast a member's ballot. Only members in the referendum's eligibility snapshot may
+    /// vote, once each.
+    pub async fn cast_referendum_ballot(&self, voter: &IdentityId, referendum_id: &str, choice: ReferendumChoice) -> Result<(), GovernanceError> {
+        let referendum = self.get_referendum(referendum_id).await?;
+        let now = chrono::Utc::now().timestamp();
+        if referendum.status != ReferendumStatus::Open || now >= referendum.closes_at {
+            return Err(GovernanceError::InvalidProposal(format!("Referendum {} is closed", referendum_id)));
+        }
+
+        let valid = match (&referendum.format, &choice) {
+            (_, ReferendumChoice::Abstain) => true,
+            (BallotFormat::YesNo, ReferendumChoice::Yes | ReferendumChoice::No) => true,
+            (BallotFormat::MultiOption { options }, ReferendumChoice::Option(i)) => *i < options.len(),
+            _ => false,
+        };
+        if !valid {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Choice {:?} doesn't match the ballot of referendum {}", choice, referendum_id
+            )));
+        }
+
+        let snapshot = self.get_eligibility_snapshot(&referendum.eligibility_snapshot_cid).await?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Eligibility snapshot of referendum {} is missing", referendum_id)))?;
+        if !snapshot.counted.contains(voter) {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not on the roster of referendum {}", voter.0, referendum_id
+            )));
+        }
+        if self.get_referendum_ballot(referendum_id, voter).await?.is_some() {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Identity {} already voted in referendum {}", voter.0, referendum_id
+            )));
+        }
+
+        let ballot = ReferendumBallot {
+            referendum_id: referendum_id.to_string(),
+            voter: voter.clone(),
+            choice,
+            cast_at: now,
+        };
+        let bytes = serde_json::to_vec(&ballot)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize referendum ballot: {}", e)))?;
+        self.store_record(&format!("referendum::ballot::{}::{}", referendum_id, voter.0), bytes).await?;
+        self.append_to_index(&format!("referendum::voters::{}", referendum_id), &voter.0).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ReferendumBallotCast,
+            voter.clone(),
+            referendum.scope,
+            Some(IdentityId(referendum.scope_id.clone())),
+            Some(referendum_id.to_string()),
+            serde_json::json!({
+                "voter": voter.0,
+                "choice": format!("{:?}", ballot.choice)
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// Close a referendum once its voting period is over, tally it and notify the roster
+    pub async fn close_referendum(&self, referendum_id: &str) -> Result<ReferendumResult, GovernanceError> {
+        let mut referendum = self.get_referendum(referendum_id).await?;
+        if referendum.status == ReferendumStatus::Closed {
+            return Err(GovernanceError::InvalidProposal(format!("Referendum {} is already closed", referendum_id)));
+        }
+        if chrono::Utc::now().timestamp() < referendum.closes_at {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Referendum {} is open until {}", referendum_id, referendum.closes_at
+            )));
+        }
+
+        let snapshot = self.get_eligibility_snapshot(&referendum.eligibility_snapshot_cid).await?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Eligibility snapshot of referendum {} is missing", referendum_id)))?;
+
+        let mut ballots = Vec::new();
+        for voter in self.load_index(&format!("referendum::voters::{}", referendum_id)).await? {
+            if let Some(ballot) = self.get_referendum_ballot(referendum_id, &IdentityId(voter)).await? {
+                ballots.push(ballot.choice);
+            }
+        }
+
+        let result = tally_referendum(&referendum.format, &referendum.rules, snapshot.counted.len() as u64, &ballots);
+        referendum.status = ReferendumStatus::Closed;
+        referendum.result = Some(result.clone());
+        self.store_referendum(&referendum).await?;
+
+        let undelivered = self.notify_roster(&referendum, &snapshot.counted).await;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ReferendumClosed,
+            IdentityId(self.identity.did().to_string()),
+            referendum.scope,
+            Some(IdentityId(referendum.scope_id.clone())),
+            Some(referendum_id.to_string()),
+            serde_json::json!({
+                "result": result,
+                "undelivered": undelivered
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referendum_needs_turnout_and_majority() {
+        use ReferendumChoice::*;
+        let rules = ReferendumRules { min_turnout_bps: 5_000, majority: Majority::TwoThirds };
+
+        // 6 of 10 voted; 4 yes of 6 decisive is exactly two thirds
+        let result = tally_referendum(&BallotFormat::YesNo, &rules, 10, &[Yes, Yes, Yes, Yes, No, No]);
+        assert_eq!(result.tally, vec![4, 2]);
+        assert_eq!(result.turnout_bps, 6_000);
+        assert_eq!(result.outcome, ReferendumOutcome::Carried("yes".to_string()));
+
+        // Abstentions count towards turnout but not towards the majority
+        let result = tally_referendum(&BallotFormat::YesNo, &rules, 10, &[Yes, Yes, Yes, No, No, Abstain]);
+        assert_eq!(result.abstentions, 1);
+        assert_eq!(result.outcome, ReferendumOutcome::NoMajority);
+
+        let result = tally_referendum(&BallotFormat::YesNo, &rules, 10, &[Yes, Yes, Yes, Yes]);
+        assert_eq!(result.outcome, ReferendumOutcome::TurnoutNotMet);
+
+        let format = BallotFormat::MultiOption { options: vec!["harbor".into(), "mill".into(), "depot".into()] };
+        let simple = ReferendumRules { min_turnout_bps: 0, majority: Majority::Simple };
+        let result = tally_referendum(&format, &simple, 5, &[Option(1), Option(1), Option(1), Option(0), Option(2)]);
+        assert_eq!(result.outcome, ReferendumOutcome::Carried("mill".to_string()));
+
+        // A tie between options carries nothing
+        let result = tally_referendum(&format, &simple, 4, &[Option(0), Option(0), Option(2), Option(2)]);
+        assert_eq!(result.outcome, ReferendumOutcome::NoMajority);
+    }
+}
@@ -0,0 +1,422 @@
+/*!
+# Archival and Retention
+
+Each scope sets a retention policy per record class: keep forever, redact personal
+data after M years, and/or summarize and archive after N years. The archival job
+([`GovernanceKernel::run_retention`]) applies the policy to closed proposals.
+
+Archiving anchors a compact [`ArchiveSummary`] (outcome, tallies, voter count and a
+digest of the verbose records) as a content-addressed blob first, and only prunes the
+verbose records once the summary is anchored. The tallies are counted from the stored
+votes, and kept on the proposal when the votes are pruned. Redaction keeps the records but replaces
+member identities with a pseudonym and drops free-text vote reasons; the voter index
+is kept so the records can still be found until they're archived. Every action applied
+to a proposal is recorded, so running the job again is a no-op until the next policy
+threshold is reached.
+*/
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus, Vote};
+use crate::revisions::VoteRevision;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+const SECONDS_PER_YEAR: i64 = 365 * 24 * 3_600;
+
+/// Metadata key holding the CID of a proposal's archive summary
+pub const ARCHIVE_SUMMARY_KEY: &str = "archive_summary_cid";
+
+/// Kinds of governance records a retention policy applies to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum RecordClass {
+    /// Proposal records (description, CCL, compiled module)
+    Proposals,
+    /// Votes, vote histories and ballot receipts
+    Votes,
+}
+
+/// What the retention job does to a record
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RetentionAction {
+    /// Replace member identities with pseudonyms and drop free-text reasons
+    RedactPii,
+    /// Anchor a summary, then prune the verbose records
+    Archive,
+}
+
+/// Retention policy for one record class. Both thresholds unset keeps records forever.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Redact personal data this many years after the proposal closed
+    pub redact_pii_after_years: Option<u32>,
+    /// Summarize and archive this many years after the proposal closed
+    pub archive_after_years: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// A policy that never redacts or archives
+    pub fn keep_forever() -> Self {
+        Self::default()
+    }
+
+    /// The strongest action due for a record of this age (in seconds). Archiving
+    /// supersedes redaction, since the archived summary holds no personal data.
+    pub fn action_due(&self, age_secs: i64) -> Option<RetentionAction> {
+        let reached = |years: Option<u32>| years
+            .map(|y| age_secs >= (y as i64).saturating_mul(SECONDS_PER_YEAR))
+            .unwrap_or(false);
+
+        if reached(self.archive_after_years) {
+            Some(RetentionAction::Archive)
+        } else if reached(self.redact_pii_after_years) {
+            Some(RetentionAction::RedactPii)
+        } else {
+            None
+        }
+    }
+}
+
+/// Retention policies of a scope. Classes without a policy are kept forever.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RetentionConfig {
+    pub policies: HashMap<RecordClass, RetentionPolicy>,
+}
+
+impl RetentionConfig {
+    pub fn policy(&self, class: RecordClass) -> RetentionPolicy {
+        self.policies.get(&class).cloned().unwrap_or_default()
+    }
+}
+
+/// Compact record of a proposal's archived data
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchiveSummary {
+    pub scope_id: String,
+    pub proposal_id: String,
+    pub class: RecordClass,
+    pub title: String,
+    pub status: ProposalStatus,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub votes_abstain: u64,
+    pub voter_count: usize,
+    /// Hex SHA-256 of the verbose records the summary replaces
+    pub records_digest: String,
+    /// When the proposal closed (Unix timestamp)
+    pub closed_at: i64,
+    /// When the summary was anchored (Unix timestamp)
+    pub archived_at: i64,
+}
+
+/// A retention action applied to one class of a proposal's records
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AppliedRetention {
+    pub proposal_id: String,
+    pub class: RecordClass,
+    pub action: RetentionAction,
+    /// CID of the anchored summary, for archive actions
+    pub summary_cid: Option<String>,
+    pub applied_at: i64,
+}
+
+/// What one run of the retention job did
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RetentionReport {
+    pub scope_id: String,
+    pub ran_at: i64,
+    pub applied: Vec<AppliedRetention>,
+}
+
+/// Stable pseudonym replacing a member identity in redacted records
+pub fn pseudonymize(did: &IdentityId) -> IdentityId {
+    IdentityId(format!("redacted:{:x}", Sha256::digest(did.0.as_bytes())))
+}
+
+/// When a proposal closed, or None while it can still change
+fn closed_at(proposal: &Proposal) -> Option<i64> {
+    match proposal.status {
+        ProposalStatus::Draft | ProposalStatus::Active => None,
+        _ => Some(if proposal.voting_end_time > 0 { proposal.voting_end_time } else { proposal.created_at }),
+    }
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Set a scope's retention policies
+    pub async fn set_retention_config(&self, caller: &IdentityId, scope_id: &str, config: RetentionConfig) -> Result<(), GovernanceError> {
+        if !self.check_permission(caller, scope_id, "manage_retention").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to manage retention in scope {}", caller.0, scope_id
+            )));
+        }
+
+        let config_bytes = serde_json::to_vec(&config)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize retention config: {}", e)))?;
+        self.store_record(&format!("retention::config::{}", scope_id), config_bytes).await
+    }
+
+    /// A scope's retention policies
+    pub async fn get_retention_config(&self, scope_id: &str) -> Result<RetentionConfig, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("retention::config::{}", scope_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize retention config: {}", e))),
+            Ok(None) => Ok(RetentionConfig::default()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load retention config: {}", e))),
+        }
+    }
+
+    /// Retention actions applied to a proposal's records so far
+    pub async fn get_applied_retention(&self, proposal_id: &str) -> Result<Vec<AppliedRetention>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("retention::applied::{}", proposal_id))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize retention records: {}", e))),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load retention records: {}", e))),
+        }
+    }
+
+    /// An anchored archive summary
+    pub async fn get_archive_summary(&self, summary_cid: &str) -> Result<ArchiveSummary, GovernanceError> {
+        let cid = cid::Cid::try_from(summary_cid)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Invalid summary CID: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        let bytes = storage.get_blob(&cid)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?
+            .ok_or_else(|| GovernanceError::StorageError(format!("Archive summary not found: {}", summary_cid)))?;
+
+        serde_json::from_slice(&bytes)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize archive summary: {}", e)))
+    }
+
+    /// Apply a scope's retention policies to its closed proposals as of `now`
+    pub async fn run_retention(&self, scope_id: &str, now: i64) -> Result<RetentionReport, GovernanceError> {
+        let config = self.get_retention_config(scope_id).await?;
+        let mut report = RetentionReport { scope_id: scope_id.to_string(), ran_at: now, applied: Vec::new() };
+
+        for proposal_id in self.get_scope_proposal_ids(scope_id).await? {
+            let proposal = self.get_proposal(proposal_id.clone()).await?;
+            let closed_at = match closed_at(&proposal) {
+                Some(t) => t,
+                None => continue,
+            };
+            let mut applied = self.get_applied_retention(&proposal_id).await?;
+
+            for class in [RecordClass::Votes, RecordClass::Proposals] {
+                let action = match config.policy(class).action_due(now - closed_at) {
+                    Some(action) => action,
+                    None => continue,
+                };
+                let done = applied.iter()
+                    .any(|a| a.class == class && (a.action == action || a.action == RetentionAction::Archive));
+                if done {
+                    continue;
+                }
+
+                let summary_cid = match action {
+                    RetentionAction::Archive => Some(self.archive_records(scope_id, &proposal_id, class, closed_at, now).await?),
+                    RetentionAction::RedactPii => {
+                        self.redact_records(&proposal_id, class).await?;
+                        None
+                    }
+                };
+                let record = AppliedRetention { proposal_id: proposal_id.clone(), class, action, summary_cid, applied_at: now };
+                applied.push(record.clone());
+                report.applied.push(record);
+            }
+
+            let applied_bytes = serde_json::to_vec(&applied)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize retention records: {}", e)))?;
+            self.store_record(&format!("retention::applied::{}", proposal_id), applied_bytes).await?;
+        }
+
+        if !report.applied.is_empty() {
+            let event_data = serde_json::json!({
+                "ran_at": now,
+                "applied": report.applied
+            });
+
+            let event = GovernanceEvent::new(
+                GovernanceEventType::RetentionApplied,
+                IdentityId(self.identity.did().to_string()),
+                self.load_governance_config(scope_id).await?
+                    .map(|c| c.governing_scope)
+                    .unwrap_or(icn_identity::IdentityScope::Cooperative),
+                Some(IdentityId(scope_id.to_string())),
+                None,
+                event_data
+            );
+
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        }
+
+        Ok(report)
+    }
+
+    /// The stored votes and vote histories of a proposal's voters
+    async fn load_vote_records(&self, proposal_id: &str) -> Result<Vec<(IdentityId, Option<Vote>, Vec<VoteRevision>)>, GovernanceError> {
+        let mut records = Vec::new();
+        for voter in self.load_index(&format!("proposal::voters::{}", proposal_id)).await? {
+            let voter = IdentityId(voter);
+            let key_cid = self.create_key_cid(&format!("vote::{}::{}", proposal_id, voter.0))?;
+            let vote = {
+                let storage = self.storage.lock().await;
+                match storage.get_kv(&key_cid).await {
+                    Ok(Some(bytes)) => serde_json::from_slice(&bytes).ok(),
+                    _ => None,
+                }
+            };
+            let history = self.get_vote_history(proposal_id, &voter).await?;
+            records.push((voter, vote, history));
+        }
+        Ok(records)
+    }
+
+    /// Anchor a summary of a class of a proposal's records, then prune them
+    async fn archive_records(&self, scope_id: &str, proposal_id: &str, class: RecordClass, closed_at: i64, now: i64) -> Result<String, GovernanceError> {
+        let mut proposal = self.get_proposal(proposal_id.to_string()).await?;
+        let votes = self.load_vote_records(proposal_id).await?;
+
+        let verbose = match class {
+            RecordClass::Proposals => serde_json::to_vec(&proposal),
+            RecordClass::Votes => serde_json::to_vec(&votes.iter().map(|(_, v, h)| (v, h)).collect::<Vec<_>>()),
+        }.map_err(|e| GovernanceError::StorageError(format!("Failed to serialize records: {}", e)))?;
+
+        // Count the stored votes; once they've been pruned, the counts kept on the
+        // proposal when they were are all that's left
+        if !votes.is_empty() {
+            let tally = self.conflict_aware_tally(proposal_id).await?;
+            proposal.votes_for = tally.votes_for;
+            proposal.votes_against = tally.votes_against;
+            proposal.votes_abstain = tally.votes_abstain;
+        }
+
+        let summary = ArchiveSummary {
+            scope_id: scope_id.to_string(),
+            proposal_id: proposal_id.to_string(),
+            class,
+            title: proposal.title.clone(),
+            status: proposal.status.clone(),
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            votes_abstain: proposal.votes_abstain,
+            voter_count: votes.len(),
+            records_digest: format!("{:x}", Sha256::digest(&verbose)),
+            closed_at,
+            archived_at: now,
+        };
+        let summary_bytes = serde_json::to_vec(&summary)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize archive summary: {}", e)))?;
+
+        // Nothing is pruned unless the summary is anchored
+        let storage = self.storage.lock().await;
+        let summary_cid = storage.put_blob(&summary_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to anchor archive summary: {}", e)))?
+            .to_string();
+        drop(storage);
+
+        match class {
+            RecordClass::Proposals => {
+                proposal.description = String::new();
+                proposal.ccl_code = None;
+                proposal.wasm_bytes = None;
+                proposal.metadata.insert(ARCHIVE_SUMMARY_KEY.to_string(), summary_cid.clone());
+                let proposal_bytes = serde_json::to_vec(&proposal)
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize proposal: {}", e)))?;
+                self.store_record(&format!("proposal::{}", proposal_id), proposal_bytes).await?;
+            }
+            RecordClass::Votes => {
+                // Keep the final counts on the proposal before the votes go
+                let proposal_bytes = serde_json::to_vec(&proposal)
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize proposal: {}", e)))?;
+                self.store_record(&format!("proposal::{}", proposal_id), proposal_bytes).await?;
+
+                let storage = self.storage.lock().await;
+                for (voter, _, _) in &votes {
+                    for key in [
+                        format!("vote::{}::{}", proposal_id, voter.0),
+                        format!("vote::history::{}::{}", proposal_id, voter.0),
+                        format!("ballot_receipt::{}::{}", proposal_id, voter.0),
+                    ] {
+                        storage.delete_kv(&self.create_key_cid(&key)?)
+                            .await
+                            .map_err(|e| GovernanceError::StorageError(format!("Failed to prune vote record: {}", e)))?;
+                    }
+                }
+                storage.delete_kv(&self.create_key_cid(&format!("proposal::voters::{}", proposal_id))?)
+                    .await
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to prune voter index: {}", e)))?;
+            }
+        }
+
+        Ok(summary_cid)
+    }
+
+    /// Replace member identities in a class of a proposal's records with pseudonyms
+    async fn redact_records(&self, proposal_id: &str, class: RecordClass) -> Result<(), GovernanceError> {
+        match class {
+            RecordClass::Proposals => {
+                let mut proposal = self.get_proposal(proposal_id.to_string()).await?;
+                proposal.proposer = pseudonymize(&proposal.proposer);
+                let proposal_bytes = serde_json::to_vec(&proposal)
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize proposal: {}", e)))?;
+                self.store_record(&format!("proposal::{}", proposal_id), proposal_bytes).await
+            }
+            RecordClass::Votes => {
+                for (voter, vote, history) in self.load_vote_records(proposal_id).await? {
+                    if let Some(mut vote) = vote {
+                        vote.voter = pseudonymize(&vote.voter);
+                        vote.reason = None;
+                        let vote_bytes = serde_json::to_vec(&vote)
+                            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize vote: {}", e)))?;
+                        self.store_record(&format!("vote::{}::{}", proposal_id, voter.0), vote_bytes).await?;
+                    }
+
+                    let history: Vec<VoteRevision> = history.into_iter()
+                        .map(|mut revision| {
+                            revision.voter = pseudonymize(&revision.voter);
+                            revision.reason = None;
+                            revision
+                        })
+                        .collect();
+                    let history_bytes = serde_json::to_vec(&history)
+                        .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize vote history: {}", e)))?;
+                    self.store_record(&format!("vote::history::{}::{}", proposal_id, voter.0), history_bytes).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_supersedes_redaction() {
+        let policy = RetentionPolicy { redact_pii_after_years: Some(2), archive_after_years: Some(7) };
+
+        assert_eq!(policy.action_due(SECONDS_PER_YEAR), None);
+        assert_eq!(policy.action_due(2 * SECONDS_PER_YEAR), Some(RetentionAction::RedactPii));
+        assert_eq!(policy.action_due(7 * SECONDS_PER_YEAR), Some(RetentionAction::Archive));
+        assert_eq!(RetentionPolicy::keep_forever().action_due(100 * SECONDS_PER_YEAR), None);
+        assert_eq!(RetentionConfig::default().policy(RecordClass::Votes), RetentionPolicy::keep_forever());
+
+        let did = IdentityId("did:icn:alice".to_string());
+        assert_eq!(pseudonymize(&did), pseudonymize(&did));
+        assert!(!pseudonymize(&did).0.contains("alice"));
+    }
+}
@@ -0,0 +1,309 @@
+/*!
+# Vote Revisions
+
+A scope's proposal process can let members change or retract their vote while a
+proposal is Active (`ProposalProcess::vote_changes`), optionally only within a window
+after their first vote. Without that policy a member's first vote is final. Voting
+again after a retraction counts as a change, so the same policy and window apply.
+
+Only the latest vote is stored under the member's vote key, so the tally counts one
+vote per member and a retracted vote isn't counted at all. Every cast, change and
+retraction is appended to the member's revision history for audit.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, ProposalStatus, Vote, VoteChoice};
+use crate::config::VoteChangePolicy;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+const SECONDS_PER_HOUR: i64 = 3_600;
+
+/// What a revision did to the member's vote
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VoteRevisionKind {
+    Cast,
+    Changed,
+    Retracted,
+}
+
+/// One entry in a member's vote history on a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VoteRevision {
+    pub voter: IdentityId,
+    pub proposal_id: String,
+    pub kind: VoteRevisionKind,
+    /// The choice after this revision (None once retracted)
+    pub choice: Option<VoteChoice>,
+    pub weight: u64,
+    pub reason: Option<String>,
+    /// When the revision was made (Unix timestamp)
+    pub timestamp: i64,
+}
+
+/// Whether a new vote is a first cast or a change. Only a member who has never voted
+/// on the proposal casts; a vote after a retraction is a change.
+pub fn revision_kind(history: &[VoteRevision], has_vote: bool) -> VoteRevisionKind {
+    if history.is_empty() && !has_vote {
+        VoteRevisionKind::Cast
+    } else {
+        VoteRevisionKind::Changed
+    }
+}
+
+/// Check a change or retraction against the scope's policy. `first_vote_at` is when
+/// the member first voted on the proposal.
+pub fn check_revision_allowed(
+    policy: Option<&VoteChangePolicy>,
+    kind: VoteRevisionKind,
+    first_vote_at: i64,
+    now: i64,
+) -> Result<(), GovernanceError> {
+    let allowed = match kind {
+        VoteRevisionKind::Cast => return Ok(()),
+        VoteRevisionKind::Changed => policy.and_then(|p| p.allow_change),
+        VoteRevisionKind::Retracted => policy.and_then(|p| p.allow_retraction),
+    };
+    if !allowed.unwrap_or(false) {
+        return Err(GovernanceError::Unauthorized(format!(
+            "This scope does not allow votes to be {}",
+            if kind == VoteRevisionKind::Changed { "changed" } else { "retracted" }
+        )));
+    }
+
+    if let Some(hours) = policy.and_then(|p| p.window_hours) {
+        let closes_at = first_vote_at.saturating_add((hours as i64).saturating_mul(SECONDS_PER_HOUR));
+        if now >= closes_at {
+            return Err(GovernanceError::Unauthorized(format!(
+                "The {} hour window for revising this vote has closed", hours
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Every cast, change and retraction of a member's vote on a proposal, oldest first
+    pub async fn get_vote_history(&self, proposal_id: &str, voter: &IdentityId) -> Result<Vec<VoteRevision>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("vote::history::{}::{}", proposal_id, voter.0))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize vote history: {}", e))),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load vote history: {}", e))),
+        }
+    }
+
+    async fn store_vote_history(&self, proposal_id: &str, voter: &IdentityId, history: &[VoteRevision]) -> Result<(), GovernanceError> {
+        let history_bytes = serde_json::to_vec(history)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize vote history: {}", e)))?;
+        let key_cid = self.create_key_cid(&format!("vote::history::{}::{}", proposal_id, voter.0))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, history_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))
+    }
+
+    async fn get_vote(&self, proposal_id: &str, voter: &IdentityId) -> Result<Option<Vote>, GovernanceError> {
+        let key_cid = self.create_key_cid(&format!("vote::{}::{}", proposal_id, voter.0))?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize vote: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load vote: {}", e))),
+        }
+    }
+
+    async fn vote_change_policy(&self, scope_id: &str) -> Result<Option<VoteChangePolicy>, GovernanceError> {
+        Ok(self.load_governance_config(scope_id).await?
+            .and_then(|config| config.proposals)
+            .and_then(|process| process.vote_changes))
+    }
+
+    /// Reject a change of vote the scope doesn't allow
+    pub(crate) async fn check_vote_revision(&self, vote: &Vote, scope_id: &str) -> Result<VoteRevisionKind, GovernanceError> {
+        let history = self.get_vote_history(&vote.proposal_id, &vote.voter).await?;
+        let previous = self.get_vote(&vote.proposal_id, &vote.voter).await?;
+
+        if revision_kind(&history, previous.is_some()) == VoteRevisionKind::Cast {
+            return Ok(VoteRevisionKind::Cast);
+        }
+        let first_vote_at = history.first()
+            .map(|r| r.timestamp)
+            .or(previous.map(|v| v.timestamp))
+            .unwrap_or_else(|| chrono::Utc::now().timestamp());
+
+        let policy = self.vote_change_policy(scope_id).await?;
+        check_revision_allowed(policy.as_ref(), VoteRevisionKind::Changed, first_vote_at, chrono::Utc::now().timestamp())?;
+        Ok(VoteRevisionKind::Changed)
+    }
+
+    /// Append a recorded vote to the member's revision history
+    pub(crate) async fn record_vote_revision(&self, vote: &Vote, kind: VoteRevisionKind) -> Result<(), GovernanceError> {
+        let mut history = self.get_vote_history(&vote.proposal_id, &vote.voter).await?;
+        history.push(VoteRevision {
+            voter: vote.voter.clone(),
+            proposal_id: vote.proposal_id.clone(),
+            kind,
+            choice: Some(vote.choice.clone()),
+            weight: vote.weight,
+            reason: vote.reason.clone(),
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+        self.store_vote_history(&vote.proposal_id, &vote.voter, &history).await
+    }
+
+    /// Withdraw a member's vote on an Active proposal, if the scope allows it. The vote
+    /// and its ballot receipt are removed so it no longer counts in the tally.
+    pub async fn retract_vote(&self, proposal_id: String, voter: &IdentityId, reason: Option<String>) -> Result<(), GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.clone()).await?;
+        if proposal.status != ProposalStatus::Active {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Votes can only be retracted while the proposal is Active, not {:?}", proposal.status
+            )));
+        }
+
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        let previous = self.get_vote(&proposal_id, voter).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Identity {} has no vote to retract on proposal {}", voter.0, proposal_id
+            )))?;
+        let mut history = self.get_vote_history(&proposal_id, voter).await?;
+        let first_vote_at = history.first().map(|r| r.timestamp).unwrap_or(previous.timestamp);
+
+        let policy = self.vote_change_policy(&scope_id).await?;
+        let now = chrono::Utc::now().timestamp();
+        check_revision_allowed(policy.as_ref(), VoteRevisionKind::Retracted, first_vote_at, now)?;
+
+        let vote_cid = self.create_key_cid(&format!("vote::{}::{}", proposal_id, voter.0))?;
+        let receipt_cid = self.create_key_cid(&format!("ballot_receipt::{}::{}", proposal_id, voter.0))?;
+        let storage = self.storage.lock().await;
+        storage.delete_kv(&vote_cid)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        storage.delete_kv(&receipt_cid)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+
+        history.push(VoteRevision {
+            voter: voter.clone(),
+            proposal_id: proposal_id.clone(),
+            kind: VoteRevisionKind::Retracted,
+            choice: None,
+            weight: previous.weight,
+            reason: reason.clone(),
+            timestamp: now,
+        });
+        self.store_vote_history(&proposal_id, voter, &history).await?;
+
+        let event_data = serde_json::json!({
+            "voter": voter.0,
+            "revisions": history.len(),
+            "reason": reason
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::VoteRetracted,
+            voter.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revision_policy_and_window() {
+        let now = 1_700_000_000;
+
+        // Without a policy the first vote is final
+        assert!(check_revision_allowed(None, VoteRevisionKind::Cast, now, now).is_ok());
+        assert!(check_revision_allowed(None, VoteRevisionKind::Changed, now, now).is_err());
+
+        let policy = VoteChangePolicy {
+            allow_change: Some(true),
+            allow_retraction: Some(false),
+            window_hours: Some(24),
+        };
+        assert!(check_revision_allowed(Some(&policy), VoteRevisionKind::Changed, now, now + 3_600).is_ok());
+        assert!(check_revision_allowed(Some(&policy), VoteRevisionKind::Retracted, now, now + 3_600).is_err());
+        assert!(check_revision_allowed(Some(&policy), VoteRevisionKind::Changed, now, now + 24 * 3_600).is_err());
+
+        let unlimited = VoteChangePolicy { window_hours: None, ..policy };
+        assert!(check_revision_allowed(Some(&unlimited), VoteRevisionKind::Changed, now, now + 90 * 86_400).is_ok());
+    }
+
+    fn revision(kind: VoteRevisionKind, timestamp: i64) -> VoteRevision {
+        VoteRevision {
+            voter: IdentityId("did:icn:alice".to_string()),
+            proposal_id: "prop-1".to_string(),
+            kind,
+            choice: (kind != VoteRevisionKind::Retracted).then_some(VoteChoice::For),
+            weight: 1,
+            reason: None,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_revote_after_retraction_is_change() {
+        let now = 1_700_000_000;
+        let no_changes = VoteChangePolicy {
+            allow_change: Some(false),
+            allow_retraction: Some(true),
+            window_hours: Some(1),
+        };
+
+        assert_eq!(revision_kind(&[], false), VoteRevisionKind::Cast);
+
+        // Retracting and voting again can't get around the change rule or the window
+        let history = vec![revision(VoteRevisionKind::Cast, now), revision(VoteRevisionKind::Retracted, now + 60)];
+        let kind = revision_kind(&history, false);
+        assert_eq!(kind, VoteRevisionKind::Changed);
+        assert!(check_revision_allowed(Some(&no_changes), kind, now, now + 120).is_err());
+
+        let changes_within_hour = VoteChangePolicy { allow_change: Some(true), ..no_changes };
+        assert!(check_revision_allowed(Some(&changes_within_hour), kind, now, now + 120).is_ok());
+        assert!(check_revision_allowed(Some(&changes_within_hour), kind, now, now + 2 * 3_600).is_err());
+    }
+
+    #[test]
+    fn test_second_vote_is_change() {
+        let now = 1_700_000_000;
+        let no_changes = VoteChangePolicy {
+            allow_change: Some(false),
+            allow_retraction: Some(true),
+            window_hours: None,
+        };
+
+        let history = vec![revision(VoteRevisionKind::Cast, now)];
+        let kind = revision_kind(&history, true);
+        assert_eq!(kind, VoteRevisionKind::Changed);
+        assert!(check_revision_allowed(Some(&no_changes), kind, now, now + 60).is_err());
+
+        // Votes recorded before histories were kept still count as a change
+        assert_eq!(revision_kind(&[], true), VoteRevisionKind::Changed);
+    }
+}
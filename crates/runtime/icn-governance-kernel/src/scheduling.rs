@@ -0,0 +1,383 @@
+/*!
+# Scheduled Execution
+
+Some proposals pass now but shouldn't take effect until later, like a dues change
+that applies from next quarter. A proposal with an `effective_at` time is held once it
+passes: finalizing it schedules its execution instead of leaving it free to execute,
+and `execute_proposal` refuses it until the time comes. `activate_due_proposals`
+executes every held proposal of a scope whose time has come. One that fails to execute
+is marked as failed and doesn't hold up the others.
+
+Until then members can still change their minds. A counter-proposal names the held
+proposal in its `cancels_scheduled` metadata; if it passes before the held proposal
+takes effect, the scheduled execution is cancelled and never runs.
+
+Passing and taking effect are anchored as separate events, `ProposalExecutionScheduled`
+and `ScheduledProposalActivated`, so the record shows both when a change was decided
+and when it began to apply.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::coi::ConflictAwareTally;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Metadata key on a counter-proposal naming the held proposal it would cancel
+pub const CANCELS_SCHEDULED_KEY: &str = "cancels_scheduled";
+
+/// Where a held proposal stands
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ScheduleStatus {
+    /// Waiting for its effective time
+    Scheduled,
+    /// Executed at its effective time
+    Activated { activated_at: i64 },
+    /// Cancelled by a counter-proposal that passed first
+    Cancelled { by: String, cancelled_at: i64 },
+    /// Reached its effective time but failed to execute
+    Failed { reason: String, failed_at: i64 },
+}
+
+/// A passed proposal held until its effective time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledExecution {
+    pub proposal_id: String,
+    pub scope_id: String,
+    /// When the proposal passed (Unix timestamp)
+    pub passed_at: i64,
+    /// When the proposal takes effect (Unix timestamp)
+    pub effective_at: i64,
+    pub status: ScheduleStatus,
+}
+
+impl ScheduledExecution {
+    /// Whether the proposal should be executed at `now`
+    pub fn is_due(&self, now: i64) -> bool {
+        self.status == ScheduleStatus::Scheduled && now >= self.effective_at
+    }
+
+    /// Reject executing the proposal at `now`, unless its time has come
+    pub fn check_executable(&self, now: i64) -> Result<(), GovernanceError> {
+        match &self.status {
+            ScheduleStatus::Scheduled if now < self.effective_at => Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} is held until {}", self.proposal_id, self.effective_at
+            ))),
+            ScheduleStatus::Cancelled { by, .. } => Err(GovernanceError::InvalidProposal(format!(
+                "Scheduled execution of proposal {} was cancelled by {}", self.proposal_id, by
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Cancel the execution for a counter-proposal that passed at `now`
+    pub fn cancel(&mut self, counter_proposal_id: &str, now: i64) -> Result<(), GovernanceError> {
+        if self.status != ScheduleStatus::Scheduled || now >= self.effective_at {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} can no longer be cancelled: it took effect at {}", self.proposal_id, self.effective_at
+            )));
+        }
+        self.status = ScheduleStatus::Cancelled { by: counter_proposal_id.to_string(), cancelled_at: now };
+        Ok(())
+    }
+}
+
+/// Whether a finalized proposal passed, given the tally of its recorded votes
+pub fn proposal_passed(proposal: &Proposal, tally: &ConflictAwareTally) -> bool {
+    match proposal.status {
+        ProposalStatus::Passed => true,
+        ProposalStatus::Finalized | ProposalStatus::Executed => tally.votes_for > tally.votes_against,
+        _ => false,
+    }
+}
+
+fn schedule_key(proposal_id: &str) -> String {
+    format!("schedule::proposal::{}", proposal_id)
+}
+
+fn schedule_index_key(scope_id: &str) -> String {
+    format!("schedule::scope::{}", scope_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Whether a finalized proposal passed, counting its stored votes
+    pub async fn proposal_has_passed(&self, proposal_id: &str, proposal: &Proposal) -> Result<bool, GovernanceError> {
+        if !matches!(proposal.status, ProposalStatus::Finalized | ProposalStatus::Executed | ProposalStatus::Passed) {
+            return Ok(false);
+        }
+        Ok(proposal_passed(proposal, &self.conflict_aware_tally(proposal_id).await?))
+    }
+
+    /// The scheduled execution of a proposal, if it was held
+    pub async fn get_scheduled_execution(&self, proposal_id: &str) -> Result<Option<ScheduledExecution>, GovernanceError> {
+        self.load_record(&schedule_key(proposal_id), "scheduled execution").await
+    }
+
+    /// Every proposal of a scope that was held for a later effective time
+    pub async fn get_scope_schedule(&self, scope_id: &str) -> Result<Vec<ScheduledExecution>, GovernanceError> {
+        let mut schedule = Vec::new();
+        for proposal_id in self.load_index(&schedule_index_key(scope_id)).await? {
+            if let Some(scheduled) = self.get_scheduled_execution(&proposal_id).await? {
+                schedule.push(scheduled);
+            }
+        }
+        schedule.sort_by_key(|s| s.effective_at);
+        Ok(schedule)
+    }
+
+    /// Check a counter-proposal at submission: it must name a held proposal of its own
+    /// scope that hasn't taken effect yet
+    pub(crate) async fn check_counter_proposal(&self, proposal: &Proposal) -> Result<(), GovernanceError> {
+        let Some(target_id) = proposal.metadata.get(CANCELS_SCHEDULED_KEY) else {
+            return Ok(());
+        };
+        let scheduled = self.get_scheduled_execution(target_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Proposal {} is not scheduled for later execution", target_id
+            )))?;
+        if proposal.scope_id.as_ref().map(|sid| sid.0.as_str()) != Some(scheduled.scope_id.as_str()) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} is not in scope {}", target_id, scheduled.scope_id
+            )));
+        }
+        if scheduled.status != ScheduleStatus::Scheduled {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Scheduled execution of proposal {} is already {:?}", target_id, scheduled.status
+            )));
+        }
+        Ok(())
+    }
+
+    /// Hold a proposal with a future effective time once it passes
+    pub(crate) async fn schedule_if_deferred(&self, proposal_id: &str, proposal: &Proposal) -> Result<Option<ScheduledExecution>, GovernanceError> {
+        let now = chrono::Utc::now().timestamp();
+        let effective_at = match proposal.effective_at {
+            Some(effective_at) if effective_at > now => effective_at,
+            _ => return Ok(None),
+        };
+        if !self.proposal_has_passed(proposal_id, proposal).await? {
+            return Ok(None);
+        }
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+
+        let scheduled = ScheduledExecution {
+            proposal_id: proposal_id.to_string(),
+            scope_id: scope_id.clone(),
+            passed_at: now,
+            effective_at,
+            status: ScheduleStatus::Scheduled,
+        };
+        self.store_schedule(&scheduled).await?;
+        self.append_to_index(&schedule_index_key(&scope_id), proposal_id).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ProposalExecutionScheduled,
+            proposal.proposer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "title": proposal.title,
+                "passed_at": now,
+                "effective_at": effective_at
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(Some(scheduled))
+    }
+
+    /// Cancel the held proposal a passed counter-proposal names, if it hasn't taken
+    /// effect yet
+    pub(crate) async fn apply_counter_proposal(&self, proposal_id: &str, proposal: &Proposal) -> Result<Option<ScheduledExecution>, GovernanceError> {
+        let Some(target_id) = proposal.metadata.get(CANCELS_SCHEDULED_KEY) else {
+            return Ok(None);
+        };
+        if !self.proposal_has_passed(proposal_id, proposal).await? {
+            return Ok(None);
+        }
+        let mut scheduled = self.get_scheduled_execution(target_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Proposal {} is not scheduled for later execution", target_id
+            )))?;
+        let now = chrono::Utc::now().timestamp();
+        scheduled.cancel(proposal_id, now)?;
+        self.store_schedule(&scheduled).await?;
+
+        let target = self.get_proposal(target_id.clone()).await?;
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ScheduledExecutionCancelled,
+            proposal.proposer.clone(),
+            target.scope,
+            target.scope_id.clone(),
+            Some(target_id.clone()),
+            serde_json::json!({
+                "title": target.title,
+                "cancelled_by": proposal_id,
+                "effective_at": scheduled.effective_at
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(Some(scheduled))
+    }
+
+    /// Refuse to execute a proposal before its effective time, or after its scheduled
+    /// execution was cancelled
+    pub(crate) async fn check_effective(&self, proposal_id: &str, proposal: &Proposal) -> Result<(), GovernanceError> {
+        let now = chrono::Utc::now().timestamp();
+        match self.get_scheduled_execution(proposal_id).await? {
+            Some(scheduled) => scheduled.check_executable(now),
+            None => match proposal.effective_at {
+                Some(effective_at) if effective_at > now => Err(GovernanceError::InvalidProposal(format!(
+                    "Proposal {} doesn't take effect until {}", proposal_id, effective_at
+                ))),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Execute every held proposal of a scope whose effective time has come by `now`.
+    /// Returns the IDs of the proposals activated; any that fail to execute are marked
+    /// as failed and skipped.
+    pub async fn activate_due_proposals(&self, scope_id: &str, now: i64, caller: &IdentityId) -> Result<Vec<String>, GovernanceError> {
+        if !self.check_permission(caller, scope_id, "execute_proposals").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to execute proposals in scope {}", caller.0, scope_id
+            )));
+        }
+
+        let mut activated = Vec::new();
+        for mut scheduled in self.get_scope_schedule(scope_id).await? {
+            if !scheduled.is_due(now) {
+                continue;
+            }
+            let result = self.execute_passed_proposal(scheduled.proposal_id.clone(), None).await;
+            scheduled.status = match &result {
+                Ok(()) => ScheduleStatus::Activated { activated_at: now },
+                Err(e) => ScheduleStatus::Failed { reason: e.to_string(), failed_at: now },
+            };
+            self.store_schedule(&scheduled).await?;
+
+            let proposal = self.get_proposal(scheduled.proposal_id.clone()).await?;
+            let event_type = if result.is_ok() {
+                GovernanceEventType::ScheduledProposalActivated
+            } else {
+                GovernanceEventType::ScheduledActivationFailed
+            };
+            let event = GovernanceEvent::new(
+                event_type,
+                caller.clone(),
+                proposal.scope,
+                proposal.scope_id.clone(),
+                Some(scheduled.proposal_id.clone()),
+                serde_json::json!({
+                    "title": proposal.title,
+                    "passed_at": scheduled.passed_at,
+                    "effective_at": scheduled.effective_at,
+                    "activated_at": now,
+                    "error": result.as_ref().err().map(|e| e.to_string())
+                })
+            );
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+            if result.is_ok() {
+                activated.push(scheduled.proposal_id);
+            }
+        }
+        Ok(activated)
+    }
+
+    async fn store_schedule(&self, scheduled: &ScheduledExecution) -> Result<(), GovernanceError> {
+        let bytes = serde_json::to_vec(scheduled)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize scheduled execution: {}", e)))?;
+        self.store_record(&schedule_key(&scheduled.proposal_id), bytes).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduled() -> ScheduledExecution {
+        ScheduledExecution {
+            proposal_id: "proposal:raise-dues".to_string(),
+            scope_id: "coop-1".to_string(),
+            passed_at: 1_700_000_000,
+            effective_at: 1_704_067_200,
+            status: ScheduleStatus::Scheduled,
+        }
+    }
+
+    #[test]
+    fn test_held_proposal_runs_at_effective_time_unless_cancelled() {
+        let held = scheduled();
+        assert!(!held.is_due(1_704_067_199));
+        assert!(held.check_executable(1_704_067_199).is_err());
+        assert!(held.is_due(1_704_067_200));
+        assert!(held.check_executable(1_704_067_200).is_ok());
+
+        // A counter-proposal only cancels before the effective time
+        let mut late = scheduled();
+        assert!(late.cancel("proposal:keep-dues", 1_704_067_200).is_err());
+        assert_eq!(late.status, ScheduleStatus::Scheduled);
+
+        let mut cancelled = scheduled();
+        cancelled.cancel("proposal:keep-dues", 1_702_000_000).unwrap();
+        assert!(!cancelled.is_due(1_704_067_200));
+        assert!(cancelled.check_executable(1_704_067_200).is_err());
+        assert!(cancelled.cancel("proposal:keep-dues-again", 1_702_000_001).is_err());
+
+        let mut activated = scheduled();
+        activated.status = ScheduleStatus::Activated { activated_at: 1_704_067_200 };
+        assert!(!activated.is_due(1_704_067_300));
+
+        // A failed run isn't retried on the next pass
+        let mut failed = scheduled();
+        failed.status = ScheduleStatus::Failed { reason: "invariant broken".to_string(), failed_at: 1_704_067_200 };
+        assert!(!failed.is_due(1_704_067_300));
+    }
+
+    #[test]
+    fn test_pass_is_decided_by_the_tally() {
+        // Counters on the proposal itself are ignored
+        let mut proposal = Proposal {
+            title: "Raise dues".to_string(),
+            description: "Raise dues from next quarter".to_string(),
+            proposer: IdentityId("did:icn:alice".to_string()),
+            scope: icn_identity::IdentityScope::Cooperative,
+            scope_id: Some(IdentityId("coop-1".to_string())),
+            status: ProposalStatus::Finalized,
+            voting_end_time: 0,
+            votes_for: 10,
+            votes_against: 0,
+            votes_abstain: 0,
+            ccl_code: None,
+            wasm_bytes: None,
+            wasm_cid: None,
+            thread_id: None,
+            metadata: std::collections::HashMap::new(),
+            proposal_type: None,
+            created_at: 0,
+            effects: Vec::new(),
+            effective_at: Some(1_704_067_200),
+        };
+
+        let against = ConflictAwareTally { votes_for: 1, votes_against: 2, ..Default::default() };
+        let in_favour = ConflictAwareTally { votes_for: 3, votes_against: 2, ..Default::default() };
+        assert!(!proposal_passed(&proposal, &against));
+        assert!(proposal_passed(&proposal, &in_favour));
+
+        proposal.status = ProposalStatus::Rejected;
+        assert!(!proposal_passed(&proposal, &in_favour));
+        proposal.status = ProposalStatus::Passed;
+        assert!(proposal_passed(&proposal, &against));
+    }
+}
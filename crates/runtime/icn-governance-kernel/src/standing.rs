@@ -0,0 +1,63 @@
+/*!
+# Dues Standing
+
+A scope's membership dues can carry a late policy (`Dues::late_policy`) with a grace
+period, late fees, hardship waivers and a point after which late members lose their
+vote. The dues ledger itself lives in `icn_economics::dues`; the kernel reads it from
+its own storage to decide whether a member in arrears may still vote.
+*/
+
+use icn_economics::dues::{DuesPolicy, DuesStanding, member_standing};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError};
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The dues late policy of a scope, if its config has one, with late fees rounded
+    /// under the scope's rounding policy
+    pub async fn dues_policy(&self, scope_id: &str) -> Result<Option<DuesPolicy>, GovernanceError> {
+        let late_policy = self.load_governance_config(scope_id).await?
+            .and_then(|config| config.membership)
+            .and_then(|membership| membership.dues)
+            .and_then(|dues| dues.late_policy);
+        let Some(p) = late_policy else {
+            return Ok(None);
+        };
+        let rounding = self.rounding_policy(scope_id).await?;
+
+        let policy = DuesPolicy::from_membership_dues(
+            p.grace_period_days,
+            p.late_fee,
+            p.late_fee_bps,
+            p.suspend_voting_after_days,
+            p.waiver_approvals,
+        ).map_err(|e| GovernanceError::InvalidProposal(format!("Invalid dues late policy for scope {}: {}", scope_id, e)))?;
+        Ok(Some(policy.with_rounding(rounding)))
+    }
+
+    /// A member's dues standing in a scope. Scopes without a late policy treat every
+    /// member as in good standing.
+    pub async fn dues_standing(&self, scope_id: &str, member: &IdentityId) -> Result<DuesStanding, GovernanceError> {
+        let policy = match self.dues_policy(scope_id).await? {
+            Some(policy) => policy,
+            None => return Ok(DuesStanding::Good),
+        };
+
+        let storage = self.storage.lock().await;
+        member_standing(scope_id, &member.0, &policy, chrono::Utc::now().timestamp(), &*storage)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to load dues ledger: {}", e)))
+    }
+
+    /// Reject votes from members whose vote is suspended for unpaid dues
+    pub(crate) async fn check_dues_standing(&self, voter: &IdentityId, scope_id: &str) -> Result<(), GovernanceError> {
+        let standing = self.dues_standing(scope_id, voter).await?;
+        if !standing.can_vote() {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} can't vote in scope {} while its dues are overdue", voter.0, scope_id
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+/*!
+# Surplus Distribution
+
+At the end of an accounting period a scope's surplus is divided as its bylaws say, in
+the `surplus_distribution` field of the economic model: equally, by patronage, or with
+a reserve set aside first. The engine in `icn_economics::surplus` turns the policy and
+the members' contributions into a draft transfer plan from the treasury; the kernel
+checks who may plan a distribution, puts the plan before the members by attaching it
+to a proposal, and pays it out only once that proposal has passed.
+
+A rejected proposal rejects its plan, and the period can be planned again.
+*/
+
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use icn_economics::rounding::RoundingPolicy;
+use icn_economics::surplus::{self, SurplusPeriod, SurplusPolicy};
+use icn_economics::transfer_plan::{self, TransferExecutor, TransferPlan, TransferPlanStatus};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, ProposalStatus};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// The surplus plan a proposal puts to the vote
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SurplusProposal {
+    pub scope_id: String,
+    pub period: String,
+    pub plan_id: Uuid,
+}
+
+fn surplus_proposal_key(proposal_id: &str) -> String {
+    format!("surplus::proposal::{}", proposal_id)
+}
+
+fn economics_error(context: &str, e: icn_economics::EconomicsError) -> GovernanceError {
+    match e {
+        icn_economics::EconomicsError::Unauthorized(msg) => GovernanceError::Unauthorized(msg),
+        other => GovernanceError::InvalidProposal(format!("{}: {}", context, other)),
+    }
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// The surplus policy a scope's bylaws set
+    pub async fn surplus_policy(&self, scope_id: &str) -> Result<SurplusPolicy, GovernanceError> {
+        let model = self.load_governance_config(scope_id).await?
+            .and_then(|config| config.economic_model);
+        let spec = model.as_ref()
+            .and_then(|model| model.surplus_distribution.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                "Scope {} has no surplus distribution policy", scope_id
+            )))?;
+        let hourly_rates = model
+            .and_then(|model| model.compensation_policy)
+            .and_then(|policy| policy.hourly_rates);
+
+        SurplusPolicy::from_economic_model(&spec, hourly_rates)
+            .map_err(|e| GovernanceError::InvalidProposal(format!(
+                "Invalid surplus distribution policy for scope {}: {}", scope_id, e
+            )))
+    }
+
+    /// How a scope's bylaws round monetary amounts split in proportion; rounding down
+    /// with the residue handed out by largest remainder if they don't say
+    pub async fn rounding_policy(&self, scope_id: &str) -> Result<RoundingPolicy, GovernanceError> {
+        let spec = self.load_governance_config(scope_id).await?
+            .and_then(|config| config.economic_model)
+            .and_then(|model| model.rounding);
+
+        match spec {
+            Some(spec) => RoundingPolicy::from_economic_model(&spec)
+                .map_err(|e| GovernanceError::InvalidProposal(format!(
+                    "Economic model of scope {}: {}", scope_id, e
+                ))),
+            None => Ok(RoundingPolicy::default()),
+        }
+    }
+
+    /// Divide a period's surplus under the scope's policy into a draft transfer plan.
+    /// The caller needs the `distribute_surplus` permission.
+    pub async fn plan_surplus_distribution(
+        &self,
+        caller: &IdentityId,
+        period: &SurplusPeriod,
+    ) -> Result<TransferPlan, GovernanceError> {
+        if !self.check_permission(caller, &period.scope_id, "distribute_surplus").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to distribute surplus in scope {}", caller.0, period.scope_id
+            )));
+        }
+
+        let policy = self.surplus_policy(&period.scope_id).await?;
+        let rounding = self.rounding_policy(&period.scope_id).await?;
+        let mut storage = self.storage.lock().await;
+        surplus::create_surplus_plan(period, &policy, &rounding, &mut *storage)
+            .await
+            .map_err(|e| economics_error("Failed to plan surplus distribution", e))
+    }
+
+    /// Put a draft surplus plan to the vote in a proposal of the same scope. Returns the
+    /// plan's summary, for the proposal's description.
+    pub async fn attach_surplus_plan(&self, proposal_id: &str, plan_id: &Uuid) -> Result<String, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if !matches!(proposal.status, ProposalStatus::Draft | ProposalStatus::Active) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot attach a surplus plan to proposal with status {:?}", proposal.status
+            )));
+        }
+        if self.get_surplus_proposal(proposal_id).await?.is_some() {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} already has a surplus plan", proposal_id
+            )));
+        }
+
+        let (plan, summary) = {
+            let mut storage = self.storage.lock().await;
+            let plan = transfer_plan::load_transfer_plan(plan_id, &*storage)
+                .await
+                .map_err(|e| economics_error("Failed to load surplus plan", e))?;
+            if plan.purpose != "surplus" {
+                return Err(GovernanceError::InvalidProposal(format!("Transfer plan {} is not a surplus plan", plan_id)));
+            }
+            if proposal.scope_id.as_ref().map(|sid| sid.0.as_str()) != Some(plan.scope_id.as_str()) {
+                return Err(GovernanceError::InvalidProposal(format!(
+                    "Proposal {} is not in scope {}", proposal_id, plan.scope_id
+                )));
+            }
+            let summary = transfer_plan::submit_transfer_plan(plan_id, &mut *storage)
+                .await
+                .map_err(|e| economics_error("Failed to submit surplus plan", e))?;
+            (plan, summary)
+        };
+
+        let period = plan.metadata.as_ref()
+            .and_then(|metadata| metadata.get("period"))
+            .and_then(|period| period.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let link = SurplusProposal { scope_id: plan.scope_id.clone(), period: period.clone(), plan_id: plan.id };
+        let bytes = serde_json::to_vec(&link)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize surplus proposal: {}", e)))?;
+        self.store_record(&surplus_proposal_key(proposal_id), bytes).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::SurplusDistributionProposed,
+            proposal.proposer.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "plan_id": plan.id,
+                "period": period,
+                "total": plan.total_amount(),
+                "transfers": plan.transfers.len()
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(summary)
+    }
+
+    /// The surplus plan attached to a proposal, if any
+    pub async fn get_surplus_proposal(&self, proposal_id: &str) -> Result<Option<SurplusProposal>, GovernanceError> {
+        self.load_record(&surplus_proposal_key(proposal_id), "surplus proposal").await
+    }
+
+    /// Record the decision of a finalized proposal on its surplus plan: approved if it
+    /// passed, rejected otherwise
+    pub async fn settle_surplus_proposal(&self, proposal_id: &str) -> Result<TransferPlanStatus, GovernanceError> {
+        let link = self.get_surplus_proposal(proposal_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Proposal {} has no surplus plan", proposal_id)))?;
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if !matches!(proposal.status, ProposalStatus::Finalized | ProposalStatus::Executed | ProposalStatus::Passed | ProposalStatus::Rejected) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} hasn't been decided, so its surplus plan can't be settled", proposal_id
+            )));
+        }
+        let approved = self.proposal_has_passed(proposal_id, &proposal).await?;
+
+        let mut storage = self.storage.lock().await;
+        let plan = transfer_plan::load_transfer_plan(&link.plan_id, &*storage)
+            .await
+            .map_err(|e| economics_error("Failed to load surplus plan", e))?;
+        if plan.status != TransferPlanStatus::PendingApproval {
+            return Ok(plan.status);
+        }
+        transfer_plan::record_transfer_plan_decision(&link.plan_id, proposal_id, approved, &mut *storage)
+            .await
+            .map_err(|e| economics_error("Failed to record surplus decision", e))
+    }
+
+    /// Pay out the surplus plan of a passed proposal. Retrying with the same
+    /// `idempotency_key` returns the executed plan without paying again.
+    pub async fn execute_surplus_distribution(
+        &self,
+        proposal_id: &str,
+        idempotency_key: &str,
+        executor: &mut impl TransferExecutor,
+    ) -> Result<TransferPlan, GovernanceError> {
+        let status = self.settle_surplus_proposal(proposal_id).await?;
+        if let TransferPlanStatus::Rejected { .. } = status {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Proposal {} was not passed, so its surplus plan won't be paid", proposal_id
+            )));
+        }
+        let link = self.get_surplus_proposal(proposal_id).await?
+            .ok_or_else(|| GovernanceError::InvalidProposal(format!("Proposal {} has no surplus plan", proposal_id)))?;
+
+        let plan = {
+            let mut storage = self.storage.lock().await;
+            transfer_plan::execute_transfer_plan(&link.plan_id, idempotency_key, executor, &mut *storage)
+                .await
+                .map_err(|e| economics_error("Failed to distribute surplus", e))?
+        };
+
+        // A replayed call paid nothing, so it isn't logged again
+        if !self.surplus_distributed_event_exists(proposal_id).await {
+            let proposal = self.get_proposal(proposal_id.to_string()).await?;
+            let event = GovernanceEvent::new(
+                GovernanceEventType::SurplusDistributed,
+                proposal.proposer.clone(),
+                proposal.scope,
+                proposal.scope_id.clone(),
+                Some(proposal_id.to_string()),
+                serde_json::json!({
+                    "plan_id": plan.id,
+                    "period": link.period,
+                    "total": plan.total_amount(),
+                    "recipients": plan.transfers.iter().map(|t| &t.recipient_did).collect::<Vec<_>>()
+                })
+            );
+            self.emit_event(event).await
+                .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        }
+
+        Ok(plan)
+    }
+
+    async fn surplus_distributed_event_exists(&self, proposal_id: &str) -> bool {
+        self.get_proposal_events(proposal_id.to_string()).await
+            .iter()
+            .any(|event| event.event_type == GovernanceEventType::SurplusDistributed)
+    }
+}
@@ -32,6 +32,9 @@ mod tests {
                 quorum: Some(0.51),
                 majority: Some(0.67),
                 term_length: Some(365),
+                invariants: None,
+                member_classes: None,
+                calendar: None,
                 roles: Some(vec![
                     Role {
                         name: "admin".to_string(),
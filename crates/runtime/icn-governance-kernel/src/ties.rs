@@ -0,0 +1,306 @@
+/*!
+# Tie-Breaking
+
+Small memberships tie often. Each proposal type can choose how a tie is resolved
+(`ProposalType::tie_break`); finalization applies the policy automatically when the
+conflict-aware tally has as many votes for as against, records how the tie was broken
+in the finalization event, and marks the proposal `Passed` or `Rejected` accordingly.
+
+- `Fail`: the proposal is rejected.
+- `ChairDecides`: a member with the `break_ties` permission records the outcome with
+  [`GovernanceKernel::record_tie_decision`]; finalization waits for it.
+- `RandomBeacon`: the outcome is drawn from the SHA-256 of a beacon document (the
+  tally and the sorted list of voters), which is anchored content-addressed so anyone
+  can recompute the draw.
+- `Revote`: voting reopens for the configured number of hours so the remaining
+  members can vote, and members may revise their vote if the scope allows it. A
+  second tie rejects the proposal.
+
+Proposal types without a policy keep the previous behaviour: the tie is finalized
+without an outcome being drawn.
+*/
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::coi::ConflictAwareTally;
+use crate::config::TieBreakPolicy;
+use crate::deliberation::find_proposal_type;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+const SECONDS_PER_HOUR: i64 = 3_600;
+
+/// How a tie was resolved
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TieOutcome {
+    Passed,
+    Rejected,
+    /// Voting was reopened until the given time (Unix timestamp)
+    Revote { voting_end_time: i64 },
+}
+
+/// A chair's decision on a tied proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChairTieDecision {
+    pub decided_by: IdentityId,
+    pub passes: bool,
+    pub reason: String,
+    /// When the decision was recorded (Unix timestamp)
+    pub decided_at: i64,
+}
+
+/// Input to a random-beacon draw
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TieBeacon {
+    pub proposal_id: String,
+    pub tally: ConflictAwareTally,
+    /// Everyone who voted, sorted
+    pub voters: Vec<String>,
+    /// Number of re-votes held before the draw
+    pub round: u32,
+}
+
+impl TieBeacon {
+    /// SHA-256 of the serialized beacon, hex-encoded
+    pub fn value(&self) -> Result<String, GovernanceError> {
+        let bytes = serde_json::to_vec(self)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize tie beacon: {}", e)))?;
+        Ok(format!("{:x}", Sha256::digest(&bytes)))
+    }
+}
+
+/// Whether a beacon value draws a passing outcome: the low bit of its first byte is clear
+pub fn beacon_passes(beacon_value: &str) -> bool {
+    beacon_value.get(..2)
+        .and_then(|first| u8::from_str_radix(first, 16).ok())
+        .map(|b| b & 1 == 0)
+        .unwrap_or(false)
+}
+
+/// Record of how a tie on a proposal was broken
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TieBreakRecord {
+    pub proposal_id: String,
+    pub policy: TieBreakPolicy,
+    pub outcome: TieOutcome,
+    /// Number of re-votes held, including one this record schedules
+    pub revotes: u32,
+    /// CID of the anchored beacon, for random draws
+    pub beacon_cid: Option<String>,
+    pub beacon_value: Option<String>,
+    /// The chair's decision, when the chair broke the tie
+    pub chair_decision: Option<ChairTieDecision>,
+    /// When the tie was resolved (Unix timestamp)
+    pub resolved_at: i64,
+}
+
+/// Whether a tally is tied: votes were cast, but as many for as against
+pub fn is_tie(tally: &ConflictAwareTally) -> bool {
+    let cast = tally.votes_for + tally.votes_against + tally.votes_abstain;
+    cast > 0 && tally.votes_for == tally.votes_against
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Record the chair's decision on a tied proposal whose type lets the chair decide
+    pub async fn record_tie_decision(&self, proposal_id: &str, caller: &IdentityId, passes: bool, reason: String) -> Result<(), GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+        if proposal.status != ProposalStatus::Active {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Cannot break a tie on proposal with status {:?}", proposal.status
+            )));
+        }
+
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+        if !self.check_permission(caller, &scope_id, "break_ties").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not authorized to break ties in scope {}", caller.0, scope_id
+            )));
+        }
+        if self.tie_break_policy(&proposal).await? != Some(TieBreakPolicy::ChairDecides) {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "Ties on proposal {} are not decided by the chair", proposal_id
+            )));
+        }
+        if !is_tie(&self.conflict_aware_tally(proposal_id).await?) {
+            return Err(GovernanceError::InvalidProposal(format!("Proposal {} is not tied", proposal_id)));
+        }
+
+        let decision = ChairTieDecision {
+            decided_by: caller.clone(),
+            passes,
+            reason,
+            decided_at: chrono::Utc::now().timestamp(),
+        };
+        let bytes = serde_json::to_vec(&decision)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize tie decision: {}", e)))?;
+        self.store_record(&format!("proposal::tie_break::chair::{}", proposal_id), bytes).await
+    }
+
+    /// How the last tie on a proposal was broken, if it tied
+    pub async fn get_tie_break(&self, proposal_id: &str) -> Result<Option<TieBreakRecord>, GovernanceError> {
+        self.load_tie_record(&format!("proposal::tie_break::{}", proposal_id)).await
+    }
+
+    /// Resolve a tie before finalization. Returns None if the proposal isn't tied or its
+    /// type has no policy. A `Revote` outcome means the proposal was reopened and must
+    /// not be finalized.
+    pub(crate) async fn apply_tie_break(&self, proposal_id: &str, proposal: &Proposal) -> Result<Option<TieBreakRecord>, GovernanceError> {
+        let tally = self.conflict_aware_tally(proposal_id).await?;
+        if !is_tie(&tally) {
+            return Ok(None);
+        }
+        let policy = match self.tie_break_policy(proposal).await? {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+
+        let previous = self.get_tie_break(proposal_id).await?;
+        let revotes = previous.as_ref().map(|r| r.revotes).unwrap_or(0);
+        let now = chrono::Utc::now().timestamp();
+        let mut record = TieBreakRecord {
+            proposal_id: proposal_id.to_string(),
+            policy: policy.clone(),
+            outcome: TieOutcome::Rejected,
+            revotes,
+            beacon_cid: None,
+            beacon_value: None,
+            chair_decision: None,
+            resolved_at: now,
+        };
+
+        match &policy {
+            TieBreakPolicy::Fail => {}
+            TieBreakPolicy::ChairDecides => {
+                let decision: ChairTieDecision = self.load_tie_record(&format!("proposal::tie_break::chair::{}", proposal_id)).await?
+                    .ok_or_else(|| GovernanceError::InvalidProposal(format!(
+                        "Proposal {} is tied and awaiting the chair's decision", proposal_id
+                    )))?;
+                record.outcome = if decision.passes { TieOutcome::Passed } else { TieOutcome::Rejected };
+                record.chair_decision = Some(decision);
+            }
+            TieBreakPolicy::RandomBeacon => {
+                let mut voters = self.load_index(&format!("proposal::voters::{}", proposal_id)).await?;
+                voters.sort();
+                let beacon = TieBeacon { proposal_id: proposal_id.to_string(), tally, voters, round: revotes };
+                let beacon_bytes = serde_json::to_vec(&beacon)
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize tie beacon: {}", e)))?;
+
+                let storage = self.storage.lock().await;
+                let beacon_cid = storage.put_blob(&beacon_bytes)
+                    .await
+                    .map_err(|e| GovernanceError::StorageError(format!("Failed to anchor tie beacon: {}", e)))?
+                    .to_string();
+                drop(storage);
+
+                let value = beacon.value()?;
+                record.outcome = if beacon_passes(&value) { TieOutcome::Passed } else { TieOutcome::Rejected };
+                record.beacon_cid = Some(beacon_cid);
+                record.beacon_value = Some(value);
+            }
+            TieBreakPolicy::Revote { extension_hours } => {
+                // Only one re-vote; a second tie rejects the proposal
+                if revotes == 0 {
+                    let voting_end_time = proposal.voting_end_time.max(now)
+                        .saturating_add((*extension_hours as i64).saturating_mul(SECONDS_PER_HOUR));
+                    record.outcome = TieOutcome::Revote { voting_end_time };
+                    record.revotes = 1;
+                    self.reopen_for_revote(proposal_id, proposal, &tally, voting_end_time).await?;
+                }
+            }
+        }
+
+        let bytes = serde_json::to_vec(&record)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize tie-break record: {}", e)))?;
+        self.store_record(&format!("proposal::tie_break::{}", proposal_id), bytes).await?;
+        Ok(Some(record))
+    }
+
+    async fn reopen_for_revote(&self, proposal_id: &str, proposal: &Proposal, tally: &ConflictAwareTally, voting_end_time: i64) -> Result<(), GovernanceError> {
+        let mut reopened = proposal.clone();
+        reopened.status = ProposalStatus::Active;
+        reopened.voting_end_time = voting_end_time;
+        let proposal_bytes = serde_json::to_vec(&reopened)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to serialize proposal: {}", e)))?;
+        self.store_record(&format!("proposal::{}", proposal_id), proposal_bytes).await?;
+
+        let event_data = serde_json::json!({
+            "title": proposal.title,
+            "votes_for": tally.votes_for,
+            "votes_against": tally.votes_against,
+            "voting_end_time": voting_end_time
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::TieRevoteScheduled,
+            IdentityId(self.identity.did().to_string()),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+        Ok(())
+    }
+
+    async fn tie_break_policy(&self, proposal: &Proposal) -> Result<Option<TieBreakPolicy>, GovernanceError> {
+        let scope_id = match &proposal.scope_id {
+            Some(sid) => &sid.0,
+            None => return Ok(None),
+        };
+        let config = match self.load_governance_config(scope_id).await? {
+            Some(config) => config,
+            None => return Ok(None),
+        };
+        Ok(find_proposal_type(&config, proposal)?.and_then(|t| t.tie_break.clone()))
+    }
+
+    async fn load_tie_record<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>, GovernanceError> {
+        let key_cid = self.create_key_cid(key)?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize tie-break record: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load tie-break record: {}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tie_detection_and_beacon_draw() {
+        let tally = ConflictAwareTally { votes_for: 2, votes_against: 2, ..Default::default() };
+        assert!(is_tie(&tally));
+        assert!(!is_tie(&ConflictAwareTally::default()));
+        // Nobody for or against, with abstentions, is still a tie
+        assert!(is_tie(&ConflictAwareTally { votes_abstain: 3, ..Default::default() }));
+        assert!(!is_tie(&ConflictAwareTally { votes_for: 3, votes_against: 2, ..Default::default() }));
+
+        let beacon = TieBeacon {
+            proposal_id: "prop-1".to_string(),
+            tally,
+            voters: vec!["did:icn:a".to_string(), "did:icn:b".to_string()],
+            round: 0,
+        };
+        // Anyone holding the anchored beacon draws the same outcome
+        let value = beacon.value().unwrap();
+        assert_eq!(value, beacon.clone().value().unwrap());
+        assert_eq!(beacon_passes(&value), beacon_passes(&beacon.value().unwrap()));
+
+        assert!(beacon_passes("00ff"));
+        assert!(!beacon_passes("01ff"));
+        assert!(!beacon_passes("not hex"));
+    }
+}
@@ -0,0 +1,553 @@
+/*!
+# Public Transparency Mode
+
+Cooperatives that commit to radical transparency can expose a read-only public view of
+their governance activity. Transparency is configured per scope and covers proposals,
+vote tallies, executed outcomes, published treasury summaries and the signed monthly
+treasury statements closed in `icn_economics::statements`. Fields that may carry
+personally identifying information are redacted according to configurable rules before
+anything leaves the kernel.
+*/
+
+use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
+use icn_economics::statements::{AnchoredStatement, StatementPeriod, get_statement, list_statements};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::coi::ConflictAwareTally;
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Replacement text for redacted content
+pub const REDACTED: &str = "[redacted]";
+
+/// How a potentially identifying field is presented in the public view
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionMode {
+    /// Show the field as-is
+    Visible,
+    /// Replace the field with a stable, non-reversible pseudonym
+    Pseudonymize,
+    /// Remove the field entirely
+    Hidden,
+}
+
+impl Default for RedactionMode {
+    fn default() -> Self {
+        RedactionMode::Pseudonymize
+    }
+}
+
+/// Rules for redacting PII from the public view
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactionRules {
+    /// How proposer identities are presented
+    #[serde(default)]
+    pub proposer: RedactionMode,
+
+    /// Whether links to deliberation threads are hidden
+    #[serde(default)]
+    pub hide_thread_links: bool,
+
+    /// Whether CCL code attached to proposals is hidden
+    #[serde(default)]
+    pub hide_ccl_code: bool,
+
+    /// Literal terms (names, emails, addresses) scrubbed from every published text field
+    #[serde(default)]
+    pub redacted_terms: Vec<String>,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self {
+            proposer: RedactionMode::Pseudonymize,
+            hide_thread_links: false,
+            hide_ccl_code: false,
+            redacted_terms: Vec::new(),
+        }
+    }
+}
+
+impl RedactionRules {
+    /// Apply the identity redaction mode to a DID
+    pub fn redact_identity(&self, mode: &RedactionMode, id: &IdentityId) -> Option<String> {
+        match mode {
+            RedactionMode::Visible => Some(id.0.clone()),
+            RedactionMode::Pseudonymize => {
+                let digest = Sha256::digest(id.0.as_bytes());
+                let hex: String = digest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+                Some(format!("anon:{}", hex))
+            },
+            RedactionMode::Hidden => None,
+        }
+    }
+
+    /// Scrub configured terms from free text
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut result = text.to_string();
+        for term in &self.redacted_terms {
+            if !term.is_empty() {
+                result = result.replace(term.as_str(), REDACTED);
+            }
+        }
+        result
+    }
+}
+
+/// Per-scope transparency settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransparencySettings {
+    /// Whether the public view is enabled for this scope
+    pub enabled: bool,
+
+    /// Expose proposal titles, descriptions and status
+    #[serde(default = "default_true")]
+    pub expose_proposals: bool,
+
+    /// Expose vote tallies
+    #[serde(default = "default_true")]
+    pub expose_tallies: bool,
+
+    /// Expose outcomes of executed proposals
+    #[serde(default = "default_true")]
+    pub expose_outcomes: bool,
+
+    /// Expose published treasury summaries
+    #[serde(default = "default_true")]
+    pub expose_treasury: bool,
+
+    /// PII redaction rules
+    #[serde(default)]
+    pub redaction: RedactionRules,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for TransparencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            expose_proposals: true,
+            expose_tallies: true,
+            expose_outcomes: true,
+            expose_treasury: true,
+            redaction: RedactionRules::default(),
+        }
+    }
+}
+
+/// Vote tally as shown publicly
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicTally {
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub votes_abstain: u64,
+    pub total: u64,
+}
+
+/// Outcome of an executed proposal as shown publicly
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicOutcome {
+    /// Final status of the proposal
+    pub status: ProposalStatus,
+    /// When the proposal was executed (Unix timestamp), if recorded
+    pub executed_at: Option<i64>,
+}
+
+/// Read-only public view of a proposal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicProposalView {
+    pub proposal_id: String,
+    pub title: String,
+    pub description: String,
+    /// Proposer after redaction (None when hidden)
+    pub proposer: Option<String>,
+    pub status: ProposalStatus,
+    pub voting_end_time: i64,
+    /// Present only when tallies are exposed
+    pub tally: Option<PublicTally>,
+    /// Present only for executed proposals when outcomes are exposed
+    pub outcome: Option<PublicOutcome>,
+    pub thread_id: Option<String>,
+    pub ccl_code: Option<String>,
+}
+
+/// Aggregate treasury summary published for a reporting period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TreasurySummary {
+    /// Start of the reporting period (Unix timestamp)
+    pub period_start: i64,
+    /// End of the reporting period (Unix timestamp)
+    pub period_end: i64,
+    /// Unit the amounts are denominated in (e.g., a token or currency code)
+    pub unit: String,
+    pub opening_balance: u64,
+    pub total_inflows: u64,
+    pub total_outflows: u64,
+    pub closing_balance: u64,
+    /// Aggregate outflows per spending category
+    #[serde(default)]
+    pub outflows_by_category: Vec<(String, u64)>,
+}
+
+/// Complete public view of a scope
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PublicScopeView {
+    pub scope_id: String,
+    pub proposals: Vec<PublicProposalView>,
+    pub treasury: Vec<TreasurySummary>,
+    /// Signed monthly statements, oldest first
+    #[serde(default)]
+    pub statements: Vec<AnchoredStatement>,
+}
+
+/// Build the public view of a proposal according to the transparency settings.
+/// `tally` is the conflict-aware tally of the proposal's stored votes.
+pub fn build_public_view(
+    proposal_id: &str,
+    proposal: &Proposal,
+    tally: &ConflictAwareTally,
+    executed_at: Option<i64>,
+    settings: &TransparencySettings,
+) -> PublicProposalView {
+    let rules = &settings.redaction;
+
+    let tally = if settings.expose_tallies {
+        Some(PublicTally {
+            votes_for: tally.votes_for,
+            votes_against: tally.votes_against,
+            votes_abstain: tally.votes_abstain,
+            total: tally.votes_for + tally.votes_against + tally.votes_abstain,
+        })
+    } else {
+        None
+    };
+
+    let outcome = if settings.expose_outcomes && proposal.status == ProposalStatus::Executed {
+        Some(PublicOutcome {
+            status: proposal.status.clone(),
+            executed_at,
+        })
+    } else {
+        None
+    };
+
+    PublicProposalView {
+        proposal_id: proposal_id.to_string(),
+        title: rules.redact_text(&proposal.title),
+        description: rules.redact_text(&proposal.description),
+        proposer: rules.redact_identity(&rules.proposer, &proposal.proposer),
+        status: proposal.status.clone(),
+        voting_end_time: proposal.voting_end_time,
+        tally,
+        outcome,
+        thread_id: if rules.hide_thread_links { None } else { proposal.thread_id.as_deref().map(|t| rules.redact_text(t)) },
+        ccl_code: if rules.hide_ccl_code { None } else { proposal.ccl_code.as_deref().map(|c| rules.redact_text(c)) },
+    }
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Store the transparency settings for a scope
+    pub async fn store_transparency_settings(&self, scope_id: &str, settings: TransparencySettings) -> Result<(), GovernanceError> {
+        let key_str = format!("transparency::config::{}", scope_id);
+        let key_cid = self.create_key_cid(&key_str)?;
+
+        let settings_bytes = serde_json::to_vec(&settings)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize transparency settings: {}", e)))?;
+
+        let storage = self.storage.lock().await;
+        storage.put_kv(key_cid, settings_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(e.to_string()))?;
+        drop(storage);
+
+        let event_data = serde_json::json!({
+            "scope_id": scope_id,
+            "config_type": "transparency",
+            "enabled": settings.enabled,
+            "timestamp": chrono::Utc::now().timestamp()
+        });
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ConfigUpdated,
+            IdentityId("did:icn:system:governance".to_string()),
+            self.scope_type_for(scope_id).await?,
+            Some(IdentityId(scope_id.to_string())),
+            None,
+            event_data
+        );
+
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// Get the transparency settings for a scope (disabled by default)
+    pub async fn get_transparency_settings(&self, scope_id: &str) -> Result<TransparencySettings, GovernanceError> {
+        let key_str = format!("transparency::config::{}", scope_id);
+        let key_cid = self.create_key_cid(&key_str)?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize transparency settings: {}", e))),
+            Ok(None) => Ok(TransparencySettings::default()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load transparency settings: {}", e))),
+        }
+    }
+
+    /// Publish an aggregate treasury summary for a scope
+    pub async fn publish_treasury_summary(&self, scope_id: &str, summary: TreasurySummary) -> Result<(), GovernanceError> {
+        if summary.period_end < summary.period_start {
+            return Err(GovernanceError::InvalidProposal(
+                "Treasury summary period ends before it starts".to_string()
+            ));
+        }
+
+        let key_str = format!("transparency::treasury::{}", scope_id);
+        let key_cid = self.create_key_cid(&key_str)?;
+
+        let storage = self.storage.lock().await;
+        let mut summaries = match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice::<Vec<TreasurySummary>>(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize treasury summaries: {}", e)))?,
+            Ok(None) => Vec::new(),
+            Err(e) => return Err(GovernanceError::StorageError(format!("Failed to load treasury summaries: {}", e))),
+        };
+
+        // A republished period replaces the previous summary for that period
+        summaries.retain(|s| !(s.period_start == summary.period_start && s.period_end == summary.period_end));
+        summaries.push(summary);
+        summaries.sort_by_key(|s| s.period_start);
+
+        let summaries_bytes = serde_json::to_vec(&summaries)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize treasury summaries: {}", e)))?;
+
+        storage.put_kv(key_cid, summaries_bytes)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to store treasury summaries: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Get the public view of a single proposal in a scope
+    pub async fn get_public_proposal(&self, scope_id: &str, proposal_id: &str) -> Result<PublicProposalView, GovernanceError> {
+        let settings = self.require_public_section(scope_id, |s| s.expose_proposals, "proposals").await?;
+
+        let proposal_ids = self.get_scope_proposal_ids(scope_id).await?;
+        if !proposal_ids.iter().any(|id| id == proposal_id) {
+            return Err(GovernanceError::ProposalNotFound(proposal_id.to_string()));
+        }
+
+        self.public_view_for(proposal_id, &settings).await
+    }
+
+    /// Get the public views of all proposals in a scope
+    pub async fn get_public_proposals(&self, scope_id: &str) -> Result<Vec<PublicProposalView>, GovernanceError> {
+        let settings = self.require_public_section(scope_id, |s| s.expose_proposals, "proposals").await?;
+
+        let mut views = Vec::new();
+        for proposal_id in self.get_scope_proposal_ids(scope_id).await? {
+            views.push(self.public_view_for(&proposal_id, &settings).await?);
+        }
+
+        Ok(views)
+    }
+
+    /// Get the published treasury summaries for a scope
+    pub async fn get_public_treasury_summaries(&self, scope_id: &str) -> Result<Vec<TreasurySummary>, GovernanceError> {
+        self.require_public_section(scope_id, |s| s.expose_treasury, "treasury summaries").await?;
+
+        let key_str = format!("transparency::treasury::{}", scope_id);
+        let key_cid = self.create_key_cid(&key_str)?;
+
+        let storage = self.storage.lock().await;
+        match storage.get_kv(&key_cid).await {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes)
+                .map_err(|e| GovernanceError::StorageError(format!("Failed to deserialize treasury summaries: {}", e))),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(GovernanceError::StorageError(format!("Failed to load treasury summaries: {}", e))),
+        }
+    }
+
+    /// Get the signed treasury statements closed for a scope
+    pub async fn get_public_treasury_statements(&self, scope_id: &str) -> Result<Vec<AnchoredStatement>, GovernanceError> {
+        self.require_public_section(scope_id, |s| s.expose_treasury, "treasury statements").await?;
+
+        let storage = self.storage.lock().await;
+        list_statements(scope_id, &*storage)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to load treasury statements: {}", e)))
+    }
+
+    /// Get the signed treasury statement of a scope for one month
+    pub async fn get_public_treasury_statement(&self, scope_id: &str, period: StatementPeriod) -> Result<Option<AnchoredStatement>, GovernanceError> {
+        self.require_public_section(scope_id, |s| s.expose_treasury, "treasury statements").await?;
+
+        let storage = self.storage.lock().await;
+        get_statement(scope_id, period, &*storage)
+            .await
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to load treasury statement: {}", e)))
+    }
+
+    /// Get the complete public view of a scope, omitting sections that are not exposed
+    pub async fn get_public_scope_view(&self, scope_id: &str) -> Result<PublicScopeView, GovernanceError> {
+        let settings = self.get_transparency_settings(scope_id).await?;
+        if !settings.enabled {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Public transparency is not enabled for scope {}", scope_id
+            )));
+        }
+
+        let proposals = if settings.expose_proposals {
+            self.get_public_proposals(scope_id).await?
+        } else {
+            Vec::new()
+        };
+
+        let (treasury, statements) = if settings.expose_treasury {
+            (self.get_public_treasury_summaries(scope_id).await?, self.get_public_treasury_statements(scope_id).await?)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        Ok(PublicScopeView {
+            scope_id: scope_id.to_string(),
+            proposals,
+            treasury,
+            statements,
+        })
+    }
+
+    /// Load settings and ensure the requested section is publicly exposed
+    async fn require_public_section(
+        &self,
+        scope_id: &str,
+        section: impl Fn(&TransparencySettings) -> bool,
+        section_name: &str,
+    ) -> Result<TransparencySettings, GovernanceError> {
+        let settings = self.get_transparency_settings(scope_id).await?;
+
+        if !settings.enabled {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Public transparency is not enabled for scope {}", scope_id
+            )));
+        }
+
+        if !section(&settings) {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Public {} are not exposed for scope {}", section_name, scope_id
+            )));
+        }
+
+        Ok(settings)
+    }
+
+    /// Build the public view for a stored proposal
+    async fn public_view_for(&self, proposal_id: &str, settings: &TransparencySettings) -> Result<PublicProposalView, GovernanceError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await?;
+
+        let executed_at = self.get_proposal_events(proposal_id.to_string()).await
+            .iter()
+            .filter(|e| e.event_type == GovernanceEventType::ProposalExecuted)
+            .filter_map(|e| e.data.get("execution_timestamp").and_then(|t| t.as_i64()))
+            .max();
+
+        let tally = self.conflict_aware_tally(proposal_id).await?;
+
+        Ok(build_public_view(proposal_id, &proposal, &tally, executed_at, settings))
+    }
+
+    /// Determine the scope type from the stored governance config
+    pub(crate) async fn scope_type_for(&self, scope_id: &str) -> Result<icn_identity::IdentityScope, GovernanceError> {
+        Ok(self.load_governance_config(scope_id).await?
+            .map(|c| c.governing_scope)
+            .unwrap_or(icn_identity::IdentityScope::Cooperative))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::IdentityScope;
+
+    fn sample_proposal() -> Proposal {
+        Proposal {
+            title: "Hire Alice Smith".to_string(),
+            description: "Contact alice@example.coop for details".to_string(),
+            proposer: IdentityId("did:icn:member:alice".to_string()),
+            scope: IdentityScope::Cooperative,
+            scope_id: Some(IdentityId("coop-1".to_string())),
+            status: ProposalStatus::Executed,
+            voting_end_time: 100,
+            votes_for: 0,
+            votes_against: 0,
+            votes_abstain: 0,
+            ccl_code: Some("action \"hire\" // Alice Smith".to_string()),
+            wasm_bytes: None,
+            wasm_cid: None,
+            thread_id: Some("thread-1".to_string()),
+            metadata: std::collections::HashMap::new(),
+            proposal_type: None,
+            created_at: 0,
+            effects: Vec::new(),
+            effective_at: None,
+        }
+    }
+
+    fn sample_tally() -> ConflictAwareTally {
+        ConflictAwareTally { votes_for: 5, votes_against: 2, votes_abstain: 1, excluded: Vec::new() }
+    }
+
+    #[test]
+    fn test_public_view_redacts_pii() {
+        let settings = TransparencySettings {
+            enabled: true,
+            redaction: RedactionRules {
+                proposer: RedactionMode::Pseudonymize,
+                hide_thread_links: true,
+                hide_ccl_code: false,
+                redacted_terms: vec!["Alice Smith".to_string(), "alice@example.coop".to_string()],
+            },
+            ..Default::default()
+        };
+
+        let view = build_public_view("proposal:hire", &sample_proposal(), &sample_tally(), Some(200), &settings);
+
+        assert_eq!(view.title, "Hire [redacted]");
+        assert_eq!(view.description, "Contact [redacted] for details");
+        assert!(view.proposer.as_ref().unwrap().starts_with("anon:"));
+        assert!(!view.proposer.as_ref().unwrap().contains("alice"));
+        assert_eq!(view.thread_id, None);
+        assert_eq!(view.ccl_code.as_deref(), Some("action \"hire\" // [redacted]"));
+        assert_eq!(view.tally.as_ref().unwrap().total, 8);
+        assert_eq!(view.outcome.as_ref().unwrap().executed_at, Some(200));
+    }
+
+    #[test]
+    fn test_public_view_respects_exposed_sections() {
+        let settings = TransparencySettings {
+            enabled: true,
+            expose_tallies: false,
+            expose_outcomes: false,
+            redaction: RedactionRules {
+                proposer: RedactionMode::Hidden,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let view = build_public_view("proposal:hire", &sample_proposal(), &sample_tally(), Some(200), &settings);
+
+        assert_eq!(view.proposer, None);
+        assert_eq!(view.tally, None);
+        assert_eq!(view.outcome, None);
+        assert_eq!(view.thread_id, Some("thread-1".to_string()));
+    }
+}
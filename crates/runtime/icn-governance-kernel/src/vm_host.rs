@@ -0,0 +1,115 @@
+/*!
+# Governance Host for Modules
+
+Compiled workflows create follow-up proposals and cast votes through the VM's
+`host_create_proposal` and `host_cast_vote` host functions. The kernel serves those
+calls by implementing [`GovernanceHost`]: requests go through
+[`GovernanceKernel::process_proposal`] and [`GovernanceKernel::record_vote`], so a
+module acting for a member can do exactly what that member could do through the
+external API and no more. Attach the kernel to an execution with
+`ConcreteHostEnvironment::with_governance_host`.
+*/
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use icn_core_vm::governance_host::{GovernanceHost, GovernanceHostError, ProposalDraft, VoteChoice as HostVoteChoice};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus, Vote, VoteChoice};
+
+/// Metadata key linking a module-created proposal to the proposal whose execution created it
+pub const PARENT_PROPOSAL_KEY: &str = "parent_proposal_id";
+
+/// The proposal a module's draft describes, submitted by `caller`
+pub fn proposal_from_draft(caller: &str, draft: ProposalDraft) -> Proposal {
+    let mut metadata = HashMap::new();
+    if let Some(parent) = draft.parent_proposal_id {
+        metadata.insert(PARENT_PROPOSAL_KEY.to_string(), parent);
+    }
+
+    Proposal {
+        title: draft.title,
+        description: draft.description,
+        proposer: IdentityId(caller.to_string()),
+        scope: draft.scope,
+        scope_id: Some(IdentityId(draft.scope_id)),
+        status: ProposalStatus::Draft,
+        voting_end_time: draft.voting_end_time.unwrap_or(0),
+        votes_for: 0,
+        votes_against: 0,
+        votes_abstain: 0,
+        ccl_code: draft.ccl_code,
+        wasm_bytes: None,
+        wasm_cid: None,
+        thread_id: None,
+        metadata,
+        proposal_type: draft.proposal_type,
+        created_at: 0,
+        effects: Vec::new(),
+        effective_at: None,
+    }
+}
+
+fn host_error(e: GovernanceError) -> GovernanceHostError {
+    match e {
+        GovernanceError::Unauthorized(msg) => GovernanceHostError::Unauthorized(msg),
+        other => GovernanceHostError::Rejected(other.to_string()),
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceHost for GovernanceKernel<S> {
+    async fn create_proposal(&self, caller: &str, draft: ProposalDraft) -> Result<String, GovernanceHostError> {
+        self.process_proposal(proposal_from_draft(caller, draft)).await.map_err(host_error)
+    }
+
+    async fn cast_vote(&self, caller: &str, proposal_id: &str, choice: HostVoteChoice) -> Result<(), GovernanceHostError> {
+        let proposal = self.get_proposal(proposal_id.to_string()).await.map_err(host_error)?;
+
+        let vote = Vote {
+            voter: IdentityId(caller.to_string()),
+            proposal_id: proposal_id.to_string(),
+            choice: match choice {
+                HostVoteChoice::For => VoteChoice::For,
+                HostVoteChoice::Against => VoteChoice::Against,
+                HostVoteChoice::Abstain => VoteChoice::Abstain,
+            },
+            weight: 1,
+            scope: proposal.scope,
+            scope_id: proposal.scope_id,
+            reason: Some("Cast by an executing module".to_string()),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        // The module acts for the member through the host, so there's no voter to hand the
+        // receipt to; the stored record still lets the vote show up in the published hashes
+        self.record_vote(vote).await.map(|_| ()).map_err(host_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icn_identity::IdentityScope;
+
+    #[test]
+    fn test_draft_becomes_proposal_from_caller() {
+        let draft = ProposalDraft {
+            scope: IdentityScope::Cooperative,
+            scope_id: "coop-1".to_string(),
+            title: "Renew lease".to_string(),
+            description: "Follow-up to the budget".to_string(),
+            proposal_type: Some("operational".to_string()),
+            ccl_code: None,
+            voting_end_time: Some(1_000),
+            parent_proposal_id: Some("proposal:adopt-budget".to_string()),
+        };
+
+        let proposal = proposal_from_draft("did:icn:treasurer", draft);
+        assert_eq!(proposal.proposer.0, "did:icn:treasurer");
+        assert_eq!(proposal.status, ProposalStatus::Draft);
+        assert_eq!(proposal.scope_id.unwrap().0, "coop-1");
+        assert_eq!(proposal.metadata.get(PARENT_PROPOSAL_KEY).map(String::as_str), Some("proposal:adopt-budget"));
+        assert_eq!(proposal.calculate_id(), "proposal:renew-lease");
+    }
+}
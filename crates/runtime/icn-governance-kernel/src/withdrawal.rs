@@ -0,0 +1,246 @@
+/*!
+# Withdrawal and Superseding
+
+A proposal that is still a draft or open for voting can be taken off the table in two
+ways. Withdrawing it closes it outright. Superseding it closes it in favour of a newer
+proposal in the same scope, usually a revised version. Either can be done by the
+proposer or by a member holding the `withdraw_proposals` permission.
+
+Both close voting: the old proposal's status becomes `Withdrawn` or `Superseded`, so it
+accepts no more votes and can't be finalized or executed. A superseded proposal is
+linked both ways with its successor through the `superseded_by` and `supersedes`
+metadata keys. Its discussion thread and meeting minutes carry forward to the
+successor, so the deliberation so far stays with the live proposal. Attestations are
+signed for the proposal they were attached to and stay on the old record.
+*/
+
+use serde::{Serialize, Deserialize};
+use icn_identity::IdentityId;
+use icn_storage::StorageBackend;
+
+use crate::{GovernanceKernel, GovernanceError, Proposal, ProposalStatus};
+use crate::events::{GovernanceEvent, GovernanceEventType, EventEmitter};
+
+/// Metadata key on a superseded proposal naming its successor
+pub const SUPERSEDED_BY_KEY: &str = "superseded_by";
+
+/// Metadata key on a proposal naming the proposal it supersedes
+pub const SUPERSEDES_KEY: &str = "supersedes";
+
+/// How a proposal was closed before its vote finished
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ClosureKind {
+    Withdrawn,
+    Superseded { by: String },
+}
+
+/// Record of a proposal closed before its vote finished
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProposalClosure {
+    pub proposal_id: String,
+    pub kind: ClosureKind,
+    /// Who closed the proposal
+    pub closed_by: IdentityId,
+    pub reason: Option<String>,
+    /// When the proposal was closed (Unix timestamp)
+    pub closed_at: i64,
+}
+
+/// Reject closing a proposal that is no longer a draft or open for voting
+pub fn check_closable(proposal: &Proposal, action: &str) -> Result<(), GovernanceError> {
+    match proposal.status {
+        ProposalStatus::Draft | ProposalStatus::Active => Ok(()),
+        _ => Err(GovernanceError::InvalidProposal(format!(
+            "Cannot {} proposal with status {:?}", action, proposal.status
+        ))),
+    }
+}
+
+/// Close `old` in favour of `new`: link the two and carry the old discussion thread
+/// forward if the successor has none
+pub fn link_successor(old_id: &str, old: &mut Proposal, new_id: &str, new: &mut Proposal) {
+    old.status = ProposalStatus::Superseded;
+    old.metadata.insert(SUPERSEDED_BY_KEY.to_string(), new_id.to_string());
+    new.metadata.insert(SUPERSEDES_KEY.to_string(), old_id.to_string());
+    if new.thread_id.is_none() {
+        new.thread_id = old.thread_id.clone();
+    }
+}
+
+fn closure_key(proposal_id: &str) -> String {
+    format!("proposal::closure::{}", proposal_id)
+}
+
+impl<S: StorageBackend + Send + Sync + 'static> GovernanceKernel<S> {
+    /// Withdraw a proposal before it is finalized
+    pub async fn withdraw_proposal(&self, proposal_id: &str, caller: &IdentityId, reason: Option<String>) -> Result<(), GovernanceError> {
+        let mut proposal = self.get_proposal(proposal_id.to_string()).await?;
+        check_closable(&proposal, "withdraw")?;
+        self.check_may_close(&proposal, caller, "withdraw").await?;
+
+        proposal.status = ProposalStatus::Withdrawn;
+        self.store_proposal(proposal_id, &proposal).await?;
+
+        let closure = ProposalClosure {
+            proposal_id: proposal_id.to_string(),
+            kind: ClosureKind::Withdrawn,
+            closed_by: caller.clone(),
+            reason,
+            closed_at: chrono::Utc::now().timestamp(),
+        };
+        self.store_closure(&closure).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ProposalWithdrawn,
+            caller.clone(),
+            proposal.scope,
+            proposal.scope_id.clone(),
+            Some(proposal_id.to_string()),
+            serde_json::json!({
+                "title": proposal.title,
+                "reason": closure.reason
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// Supersede a proposal with a newer one in the same scope, closing voting on the
+    /// old proposal and carrying its deliberation forward
+    pub async fn supersede_proposal(&self, old_id: &str, new_id: &str, caller: &IdentityId) -> Result<(), GovernanceError> {
+        if old_id == new_id {
+            return Err(GovernanceError::InvalidProposal("A proposal can't supersede itself".to_string()));
+        }
+        let mut old = self.get_proposal(old_id.to_string()).await?;
+        let mut new = self.get_proposal(new_id.to_string()).await?;
+        check_closable(&old, "supersede")?;
+        check_closable(&new, "supersede with")?;
+        if old.scope_id != new.scope_id {
+            return Err(GovernanceError::InvalidProposal(format!(
+                "{} and {} belong to different scopes", old_id, new_id
+            )));
+        }
+        self.check_may_close(&old, caller, "supersede").await?;
+
+        link_successor(old_id, &mut old, new_id, &mut new);
+        self.store_proposal(new_id, &new).await?;
+        self.store_proposal(old_id, &old).await?;
+
+        // Minutes that discussed the old proposal are listed for its successor too
+        for anchored in self.get_proposal_minutes(old_id).await? {
+            self.append_to_index(&format!("proposal_minutes::{}", new_id), &anchored.cid).await?;
+        }
+
+        let closure = ProposalClosure {
+            proposal_id: old_id.to_string(),
+            kind: ClosureKind::Superseded { by: new_id.to_string() },
+            closed_by: caller.clone(),
+            reason: None,
+            closed_at: chrono::Utc::now().timestamp(),
+        };
+        self.store_closure(&closure).await?;
+
+        let event = GovernanceEvent::new(
+            GovernanceEventType::ProposalSuperseded,
+            caller.clone(),
+            old.scope,
+            old.scope_id.clone(),
+            Some(old_id.to_string()),
+            serde_json::json!({
+                "title": old.title,
+                "superseded_by": new_id,
+                "thread_id": new.thread_id
+            })
+        );
+        self.emit_event(event).await
+            .map_err(|e| GovernanceError::EventEmissionError(e))?;
+
+        Ok(())
+    }
+
+    /// How a proposal was closed before its vote finished, if it was
+    pub async fn get_proposal_closure(&self, proposal_id: &str) -> Result<Option<ProposalClosure>, GovernanceError> {
+        self.load_record(&closure_key(proposal_id), "proposal closure").await
+    }
+
+    /// Only the proposer or a holder of `withdraw_proposals` may close a proposal
+    async fn check_may_close(&self, proposal: &Proposal, caller: &IdentityId, action: &str) -> Result<(), GovernanceError> {
+        if &proposal.proposer == caller {
+            return Ok(());
+        }
+        let scope_id = proposal.scope_id.as_ref()
+            .map(|sid| sid.0.clone())
+            .ok_or_else(|| GovernanceError::InvalidProposal("Proposal must have a scope_id".to_string()))?;
+        if !self.check_permission(caller, &scope_id, "withdraw_proposals").await? {
+            return Err(GovernanceError::Unauthorized(format!(
+                "Identity {} is not the proposer and is not authorized to {} proposals in scope {}",
+                caller.0, action, scope_id
+            )));
+        }
+        Ok(())
+    }
+
+    async fn store_proposal(&self, proposal_id: &str, proposal: &Proposal) -> Result<(), GovernanceError> {
+        let proposal_bytes = serde_json::to_vec(proposal)
+            .map_err(|e| GovernanceError::InvalidProposal(format!("Failed to serialize proposal: {}", e)))?;
+        self.store_record(&format!("proposal::{}", proposal_id), proposal_bytes).await
+    }
+
+    async fn store_closure(&self, closure: &ProposalClosure) -> Result<(), GovernanceError> {
+        let bytes = serde_json::to_vec(closure)
+            .map_err(|e| GovernanceError::StorageError(format!("Failed to serialize proposal closure: {}", e)))?;
+        self.store_record(&closure_key(&closure.proposal_id), bytes).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use icn_identity::IdentityScope;
+
+    fn proposal(title: &str, thread_id: Option<&str>) -> Proposal {
+        Proposal {
+            title: title.to_string(),
+            description: "Change the quorum rules".to_string(),
+            proposer: IdentityId("did:icn:member:alice".to_string()),
+            scope: IdentityScope::Cooperative,
+            scope_id: Some(IdentityId("coop-1".to_string())),
+            status: ProposalStatus::Active,
+            voting_end_time: 0,
+            votes_for: 0,
+            votes_against: 0,
+            votes_abstain: 0,
+            ccl_code: None,
+            wasm_bytes: None,
+            wasm_cid: None,
+            thread_id: thread_id.map(str::to_string),
+            metadata: HashMap::new(),
+            proposal_type: None,
+            created_at: 0,
+            effects: Vec::new(),
+            effective_at: None,
+        }
+    }
+
+    #[test]
+    fn test_superseding_links_records_and_closes_voting() {
+        let mut old = proposal("Amend bylaws", Some("thread-42"));
+        let mut new = proposal("Amend bylaws v2", None);
+        let (old_id, new_id) = (old.calculate_id(), new.calculate_id());
+
+        link_successor(&old_id, &mut old, &new_id, &mut new);
+        assert_eq!(old.status, ProposalStatus::Superseded);
+        assert_eq!(old.metadata[SUPERSEDED_BY_KEY], new_id);
+        assert_eq!(new.metadata[SUPERSEDES_KEY], old_id);
+        assert_eq!(new.thread_id.as_deref(), Some("thread-42"));
+
+        // Once closed the old proposal can't be closed again
+        assert!(check_closable(&new, "withdraw").is_ok());
+        assert!(check_closable(&old, "withdraw").is_err());
+        new.status = ProposalStatus::Finalized;
+        assert!(check_closable(&new, "withdraw").is_err());
+    }
+}
@@ -45,6 +45,9 @@ fn create_test_governance_config() -> GovernanceConfig {
         quorum: Some(0.5),
         majority: Some(0.66),
         term_length: None,
+        invariants: None,
+        member_classes: None,
+        calendar: None,
         roles: Some(vec![admin_role, voter_role]),
     };
     
@@ -77,7 +80,7 @@ fn create_test_proposal(proposer: &IdentityId, scope_id: &str) -> Proposal {
         proposer: proposer.clone(),
         scope: IdentityScope::Community,
         scope_id: Some(IdentityId(scope_id.to_string())),
-        status: ProposalStatus::Draft,
+        status: ProposalStatus::Active,
         voting_end_time: chrono::Utc::now().timestamp() + 86400, // 24 hour voting period
         votes_for: 0,
         votes_against: 0,
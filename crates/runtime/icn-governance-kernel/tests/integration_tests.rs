@@ -36,7 +36,7 @@ async fn test_governance_event_emission() {
         proposer: IdentityId::new("did:icn:test"),
         scope: IdentityScope::Federation,
         scope_id: Some(IdentityId::new("did:icn:federation:test")),
-        status: ProposalStatus::Draft,
+        status: ProposalStatus::Active,
         voting_end_time: chrono::Utc::now().timestamp() + 86400, // 24 hour voting period
         votes_for: 0,
         votes_against: 0,
@@ -48,10 +48,12 @@ async fn test_governance_event_emission() {
     // Submit the proposal and capture the CID
     let proposal_id = kernel.process_proposal(proposal.clone()).await.unwrap();
     
-    // Verify a ProposalCreated event was emitted
+    // Verify a ProposalCreated event was emitted, followed by the activation that
+    // opened voting
     let events = kernel.get_proposal_events(proposal_id.clone()).await;
-    assert_eq!(events.len(), 1, "Expected exactly one event");
+    assert_eq!(events.len(), 2, "Expected creation and activation events");
     assert_eq!(events[0].event_type, GovernanceEventType::ProposalCreated);
+    assert_eq!(events[1].event_type, GovernanceEventType::ProposalActivated);
     
     // Verify event contains correct identifiers
     assert_eq!(events[0].proposal_cid, Some(proposal_id.clone()));
@@ -72,7 +74,7 @@ async fn test_governance_event_emission() {
     
     // Verify a VoteCast event was emitted
     let events = kernel.get_proposal_events(proposal_id.clone()).await;
-    assert_eq!(events.len(), 2, "Expected two events after voting");
+    assert_eq!(events.len(), 3, "Expected three events after voting");
     assert!(events.iter().any(|e| e.event_type == GovernanceEventType::VoteCast));
     
     // Finalize the proposal and verify event
@@ -80,7 +82,7 @@ async fn test_governance_event_emission() {
     
     // Verify a ProposalFinalized event was emitted
     let events = kernel.get_proposal_events(proposal_id.clone()).await;
-    assert_eq!(events.len(), 3, "Expected three events after finalization");
+    assert_eq!(events.len(), 4, "Expected four events after finalization");
     assert!(events.iter().any(|e| e.event_type == GovernanceEventType::ProposalFinalized));
     
     // Execute the proposal and verify event
@@ -88,7 +90,7 @@ async fn test_governance_event_emission() {
     
     // Verify a ProposalExecuted event was emitted
     let events = kernel.get_proposal_events(proposal_id.clone()).await;
-    assert_eq!(events.len(), 4, "Expected four events after execution");
+    assert_eq!(events.len(), 5, "Expected five events after execution");
     assert!(events.iter().any(|e| e.event_type == GovernanceEventType::ProposalExecuted));
     
     // Check the final proposal state
@@ -112,7 +114,7 @@ async fn test_proposal_event_filtering() {
         proposer: IdentityId::new("did:icn:test"),
         scope: IdentityScope::Federation,
         scope_id: Some(IdentityId::new("did:icn:federation:test")),
-        status: ProposalStatus::Draft,
+        status: ProposalStatus::Active,
         voting_end_time: chrono::Utc::now().timestamp() + 86400,
         votes_for: 0,
         votes_against: 0,
@@ -127,7 +129,7 @@ async fn test_proposal_event_filtering() {
         proposer: IdentityId::new("did:icn:test"),
         scope: IdentityScope::Federation,
         scope_id: Some(IdentityId::new("did:icn:federation:test")),
-        status: ProposalStatus::Draft,
+        status: ProposalStatus::Active,
         voting_end_time: chrono::Utc::now().timestamp() + 86400,
         votes_for: 0,
         votes_against: 0,
@@ -158,8 +160,8 @@ async fn test_proposal_event_filtering() {
     let events1 = kernel.get_proposal_events(cid1.clone()).await;
     let events2 = kernel.get_proposal_events(cid2.clone()).await;
     
-    assert_eq!(events1.len(), 2, "First proposal should have 2 events");
-    assert_eq!(events2.len(), 1, "Second proposal should have 1 event");
+    assert_eq!(events1.len(), 3, "First proposal should have 3 events");
+    assert_eq!(events2.len(), 2, "Second proposal should have 2 events");
     
     assert!(events1.iter().any(|e| e.event_type == GovernanceEventType::VoteCast),
             "First proposal should have a VoteCast event");
@@ -224,6 +224,7 @@ fn test_budget_proposal() {
         "name": "Q2 Budget",
         "economic_model": {
             "surplus_distribution": "equal",
+            "rounding": "half_even(unit=100, residue=reserve)",
             "compensation_policy": {
                 "hourly_rates": {
                     "standard": 15
@@ -241,6 +242,7 @@ fn test_budget_proposal() {
     
     let economic = config.economic_model.unwrap();
     assert_eq!(economic.surplus_distribution, Some("equal".to_string()));
+    assert_eq!(economic.rounding, Some("half_even(unit=100, residue=reserve)".to_string()));
     
     let compensation = economic.compensation_policy.unwrap();
     let rates = compensation.hourly_rates.unwrap();
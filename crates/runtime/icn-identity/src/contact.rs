@@ -0,0 +1,383 @@
+/*!
+# Contact Verification Attestations
+
+Onboarding flows often ask for a verified email address or phone number. The gateway
+registers one [`ContactVerifier`] per delivery method (an email magic link, an SMS
+code) with a [`ContactVerification`] service. Verification happens in two steps:
+[`ContactVerification::start`] sends a one-time secret to the address through the named
+verifier, and [`ContactVerification::confirm`] checks the secret the member sends back.
+The service keeps only a hash of the secret, and a challenge allows a limited number
+of wrong attempts.
+
+A confirmed address yields a [`ContactAttestation`] signed by the service's issuer and
+bound to the member's DID. The attestation holds a hash of the address salted with the
+DID rather than the address itself, so it can be checked against an address the member
+discloses without publishing it. Scopes list the attestations they need among their
+onboarding requirements (`verified_email`, `verified_phone`).
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::did::IdentityId;
+use crate::error::{IdentityError, IdentityResult};
+use crate::keypair::{KeyPair, Signature};
+use crate::{sign_message, verify_signature};
+
+/// Wrong secrets a challenge accepts before it is discarded
+pub const MAX_VERIFICATION_ATTEMPTS: u32 = 5;
+
+/// Kind of contact information being verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContactChannel {
+    Email,
+    Phone,
+}
+
+impl ContactChannel {
+    /// The onboarding requirement an attestation for this channel satisfies
+    pub fn requirement(&self) -> &'static str {
+        match self {
+            ContactChannel::Email => "verified_email",
+            ContactChannel::Phone => "verified_phone",
+        }
+    }
+
+    /// The channel an onboarding requirement asks for, if it is a contact requirement
+    pub fn from_requirement(requirement: &str) -> Option<Self> {
+        match requirement {
+            "verified_email" => Some(ContactChannel::Email),
+            "verified_phone" => Some(ContactChannel::Phone),
+            _ => None,
+        }
+    }
+
+    /// Canonical form of an address, so equivalent spellings hash the same
+    pub fn normalize(&self, address: &str) -> String {
+        let address = address.trim();
+        match self {
+            ContactChannel::Email => address.to_lowercase(),
+            ContactChannel::Phone => address.chars()
+                .enumerate()
+                .filter(|(i, c)| c.is_ascii_digit() || (*i == 0 && *c == '+'))
+                .map(|(_, c)| c)
+                .collect(),
+        }
+    }
+
+    /// Whether an address is plausible for this channel
+    fn is_valid(&self, normalized: &str) -> bool {
+        match self {
+            ContactChannel::Email => normalized.split_once('@')
+                .map(|(local, domain)| !local.is_empty() && domain.contains('.'))
+                .unwrap_or(false),
+            ContactChannel::Phone => normalized.trim_start_matches('+').len() >= 7,
+        }
+    }
+
+    /// A fresh one-time secret: a link token for email, a six-digit code for phones
+    fn generate_secret(&self) -> String {
+        match self {
+            ContactChannel::Email => Uuid::new_v4().simple().to_string(),
+            ContactChannel::Phone => format!("{:06}", rand::thread_rng().gen_range(0..1_000_000)),
+        }
+    }
+}
+
+/// Hash of an address salted with the DID it is attested for
+pub fn address_hash(did: &IdentityId, channel: ContactChannel, address: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(did.as_str().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(channel.normalize(address).as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn secret_hash(challenge_id: &str, secret: &str) -> String {
+    hex::encode(Sha256::digest(format!("{}:{}", challenge_id, secret.trim()).as_bytes()))
+}
+
+/// Delivers verification secrets, run by the gateway
+#[async_trait]
+pub trait ContactVerifier: Send + Sync {
+    /// Name the verifier is registered under (e.g., "email-link", "sms")
+    fn name(&self) -> &str;
+
+    /// Kind of address the verifier delivers to
+    fn channel(&self) -> ContactChannel;
+
+    /// Send the secret of a challenge to an address
+    async fn deliver(&self, address: &str, challenge_id: &str, secret: &str) -> Result<()>;
+}
+
+/// A verification waiting for the member to send back its secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationChallenge {
+    pub id: String,
+    pub did: IdentityId,
+    pub channel: ContactChannel,
+    /// Verifier that delivered the secret
+    pub verifier: String,
+    pub address_hash: String,
+    secret_hash: String,
+    pub attempts_left: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Signed evidence that a DID controls an email address or phone number
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactAttestation {
+    pub id: String,
+    /// The member the address is verified for
+    pub did: IdentityId,
+    pub channel: ContactChannel,
+    /// See [`address_hash`]
+    pub address_hash: String,
+    /// Verifier that delivered the secret
+    pub verifier: String,
+    /// Identity that signed the attestation
+    pub issuer: IdentityId,
+    pub verified_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Issuer's signature over every other field
+    pub signature: Signature,
+}
+
+impl ContactAttestation {
+    fn signing_bytes(&self) -> IdentityResult<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signature = Signature::new(Vec::new());
+        serde_json::to_vec(&unsigned)
+            .map_err(|e| IdentityError::SerializationError(format!("Failed to serialize attestation: {}", e)))
+    }
+
+    /// Whether the attestation is signed by its issuer, bound to `did` and in effect at `at`
+    pub fn is_valid_for(&self, did: &IdentityId, at: DateTime<Utc>) -> IdentityResult<bool> {
+        if &self.did != did || self.expires_at.map(|expires| at >= expires).unwrap_or(false) {
+            return Ok(false);
+        }
+        verify_signature(&self.signing_bytes()?, &self.signature, &self.issuer)
+    }
+
+    /// Whether the attestation is for `address`
+    pub fn matches_address(&self, address: &str) -> bool {
+        self.address_hash == address_hash(&self.did, self.channel, address)
+    }
+}
+
+/// Runs contact verifications and signs the resulting attestations
+pub struct ContactVerification {
+    verifiers: HashMap<String, Arc<dyn ContactVerifier>>,
+    issuer: IdentityId,
+    keypair: KeyPair,
+    challenges: Mutex<HashMap<String, VerificationChallenge>>,
+    challenge_ttl: Duration,
+    attestation_validity: Option<Duration>,
+}
+
+impl ContactVerification {
+    pub fn new(issuer: IdentityId, keypair: KeyPair) -> Self {
+        Self {
+            verifiers: HashMap::new(),
+            issuer,
+            keypair,
+            challenges: Mutex::new(HashMap::new()),
+            challenge_ttl: Duration::minutes(15),
+            attestation_validity: None,
+        }
+    }
+
+    /// Register a verifier members can choose
+    pub fn with_verifier(mut self, verifier: Arc<dyn ContactVerifier>) -> Self {
+        self.verifiers.insert(verifier.name().to_string(), verifier);
+        self
+    }
+
+    /// How long a delivered secret stays valid
+    pub fn with_challenge_ttl(mut self, ttl: Duration) -> Self {
+        self.challenge_ttl = ttl;
+        self
+    }
+
+    /// Make attestations expire this long after verification, so addresses are re-verified
+    pub fn with_attestation_validity(mut self, validity: Duration) -> Self {
+        self.attestation_validity = Some(validity);
+        self
+    }
+
+    fn lock_challenges(&self) -> IdentityResult<std::sync::MutexGuard<'_, HashMap<String, VerificationChallenge>>> {
+        self.challenges.lock()
+            .map_err(|_| IdentityError::StorageError("Failed to lock verification challenges".to_string()))
+    }
+
+    /// Send a one-time secret to `address` through the named verifier. Returns the
+    /// challenge ID the secret is confirmed against.
+    pub async fn start(&self, did: &IdentityId, verifier: &str, address: &str) -> IdentityResult<String> {
+        let verifier_impl = self.verifiers.get(verifier)
+            .ok_or_else(|| IdentityError::VerificationError(format!("Unknown contact verifier: {}", verifier)))?;
+        let channel = verifier_impl.channel();
+        if !channel.is_valid(&channel.normalize(address)) {
+            return Err(IdentityError::VerificationError(format!("Not a valid {:?} address", channel)));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let secret = channel.generate_secret();
+        verifier_impl.deliver(address, &id, &secret).await
+            .map_err(|e| IdentityError::VerificationError(format!("Verifier {} failed to deliver: {}", verifier, e)))?;
+
+        let challenge = VerificationChallenge {
+            id: id.clone(),
+            did: did.clone(),
+            channel,
+            verifier: verifier.to_string(),
+            address_hash: address_hash(did, channel, address),
+            secret_hash: secret_hash(&id, &secret),
+            attempts_left: MAX_VERIFICATION_ATTEMPTS,
+            expires_at: Utc::now() + self.challenge_ttl,
+        };
+        self.lock_challenges()?.insert(id.clone(), challenge);
+        Ok(id)
+    }
+
+    /// Check the secret the member sent back and, if it matches, attest the address
+    pub async fn confirm(&self, challenge_id: &str, secret: &str) -> IdentityResult<ContactAttestation> {
+        let now = Utc::now();
+        let challenge = {
+            let mut challenges = self.lock_challenges()?;
+            let challenge = challenges.get_mut(challenge_id)
+                .ok_or_else(|| IdentityError::VerificationError(format!("No pending verification {}", challenge_id)))?;
+
+            if now >= challenge.expires_at {
+                challenges.remove(challenge_id);
+                return Err(IdentityError::VerificationError(format!("Verification {} has expired", challenge_id)));
+            }
+            if challenge.secret_hash != secret_hash(challenge_id, secret) {
+                challenge.attempts_left = challenge.attempts_left.saturating_sub(1);
+                if challenge.attempts_left == 0 {
+                    challenges.remove(challenge_id);
+                }
+                return Err(IdentityError::VerificationError("Verification secret does not match".to_string()));
+            }
+            challenges.remove(challenge_id).expect("challenge was found above")
+        };
+
+        let mut attestation = ContactAttestation {
+            id: format!("urn:uuid:{}", Uuid::new_v4()),
+            did: challenge.did,
+            channel: challenge.channel,
+            address_hash: challenge.address_hash,
+            verifier: challenge.verifier,
+            issuer: self.issuer.clone(),
+            verified_at: now,
+            expires_at: self.attestation_validity.map(|validity| now + validity),
+            signature: Signature::new(Vec::new()),
+        };
+        attestation.signature = sign_message(&attestation.signing_bytes()?, &self.keypair)?;
+        Ok(attestation)
+    }
+}
+
+/// The contact requirements among `requirements` that none of `attestations` satisfies
+/// for `did` at `at`. Attestations must come from one of `trusted_issuers`.
+pub fn missing_contact_requirements(
+    requirements: &[String],
+    attestations: &[ContactAttestation],
+    did: &IdentityId,
+    trusted_issuers: &[IdentityId],
+    at: DateTime<Utc>,
+) -> IdentityResult<Vec<String>> {
+    let mut satisfied = Vec::new();
+    for attestation in attestations {
+        if trusted_issuers.contains(&attestation.issuer) && attestation.is_valid_for(did, at)? {
+            satisfied.push(attestation.channel);
+        }
+    }
+
+    Ok(requirements.iter()
+        .filter(|requirement| ContactChannel::from_requirement(requirement)
+            .map(|channel| !satisfied.contains(&channel))
+            .unwrap_or(false))
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingSms {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl ContactVerifier for RecordingSms {
+        fn name(&self) -> &str {
+            "sms"
+        }
+
+        fn channel(&self) -> ContactChannel {
+            ContactChannel::Phone
+        }
+
+        async fn deliver(&self, address: &str, _challenge_id: &str, secret: &str) -> Result<()> {
+            self.sent.lock().unwrap().push((address.to_string(), secret.to_string()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_code_yields_bound_attestation() {
+        let sms = Arc::new(RecordingSms::default());
+        let issuer = IdentityId::new("did:key:z6MkGateway");
+        let service = ContactVerification::new(issuer.clone(), KeyPair::generate_random())
+            .with_verifier(sms.clone());
+        let alice = IdentityId::new("did:key:z6MkAlice");
+
+        assert!(service.start(&alice, "email-link", "alice@example.org").await.is_err());
+        assert!(service.start(&alice, "sms", "12").await.is_err());
+
+        let challenge_id = service.start(&alice, "sms", "+1 (555) 010-9999").await.unwrap();
+        let code = sms.sent.lock().unwrap()[0].1.clone();
+        assert_eq!(code.len(), 6);
+
+        assert!(service.confirm(&challenge_id, "not-it").await.is_err());
+        let attestation = service.confirm(&challenge_id, &code).await.unwrap();
+        assert!(service.confirm(&challenge_id, &code).await.is_err());
+
+        assert_eq!(attestation.channel, ContactChannel::Phone);
+        assert!(attestation.matches_address("+15550109999"));
+        assert!(!attestation.matches_address("+15550100000"));
+        assert!(attestation.is_valid_for(&alice, Utc::now()).unwrap());
+        assert!(!attestation.is_valid_for(&IdentityId::new("did:key:z6MkBob"), Utc::now()).unwrap());
+
+        let requirements = vec!["verified_email".to_string(), "verified_phone".to_string(), "sponsor".to_string()];
+        let missing = missing_contact_requirements(&requirements, &[attestation.clone()], &alice, &[issuer], Utc::now()).unwrap();
+        assert_eq!(missing, vec!["verified_email".to_string()]);
+        let untrusted = missing_contact_requirements(&requirements, &[attestation], &alice, &[], Utc::now()).unwrap();
+        assert_eq!(untrusted.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_challenge_discarded_after_too_many_attempts() {
+        let sms = Arc::new(RecordingSms::default());
+        let service = ContactVerification::new(IdentityId::new("did:key:z6MkGateway"), KeyPair::generate_random())
+            .with_verifier(sms.clone());
+        let alice = IdentityId::new("did:key:z6MkAlice");
+
+        let challenge_id = service.start(&alice, "sms", "+15550109999").await.unwrap();
+        let code = sms.sent.lock().unwrap()[0].1.clone();
+        for _ in 0..MAX_VERIFICATION_ATTEMPTS {
+            assert!(service.confirm(&challenge_id, "wrong").await.is_err());
+        }
+        assert!(service.confirm(&challenge_id, &code).await.is_err());
+    }
+}
@@ -0,0 +1,416 @@
+/*!
+# Device Keys
+
+Members sign from several devices, so a member DID can hold one key per device. Each
+[`DeviceKey`] has a [`DeviceScope`] naming what it may sign for: a `Full` device can do
+anything, including managing the member's other devices, while a `VoteOnly` phone can
+only cast votes.
+
+A member's first device is registered directly and gets the `Full` scope. Later devices
+are enrolled in two steps: the new device opens an [`EnrollmentRequest`] with its public
+key and the scope it asks for, and an existing `Full` device approves it by signing the
+request. Revocation is signed the same way, and the last active `Full` device can't be
+revoked so a member can't lock themselves out.
+
+[`DeviceRegistry::verify_device_signature`] checks a signature against the device's
+key and the device's scope, so a vote-only key can't be used to sign anything else.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::did::IdentityId;
+use crate::error::{IdentityError, IdentityResult};
+use crate::keypair::Signature;
+use crate::verify_signature;
+
+/// Capability to cast votes
+pub const CAP_VOTE: &str = "vote";
+/// Capability to enroll and revoke the member's other devices
+pub const CAP_MANAGE_DEVICES: &str = "manage_devices";
+
+/// What a device key may sign for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceScope {
+    /// Every capability, including device management
+    Full,
+    /// Casting votes only
+    VoteOnly,
+    /// A named set of capabilities
+    Custom { name: String, capabilities: Vec<String> },
+}
+
+impl DeviceScope {
+    /// Name of the scope (e.g., "full", "vote-only")
+    pub fn name(&self) -> &str {
+        match self {
+            DeviceScope::Full => "full",
+            DeviceScope::VoteOnly => "vote-only",
+            DeviceScope::Custom { name, .. } => name,
+        }
+    }
+
+    /// Whether a key with this scope may sign for a capability
+    pub fn allows(&self, capability: &str) -> bool {
+        match self {
+            DeviceScope::Full => true,
+            DeviceScope::VoteOnly => capability == CAP_VOTE,
+            DeviceScope::Custom { capabilities, .. } => capabilities.iter().any(|c| c == capability),
+        }
+    }
+}
+
+/// A key held on one of a member's devices
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceKey {
+    pub device_id: String,
+    pub member: IdentityId,
+    /// Name the member gave the device (e.g., "phone")
+    pub label: String,
+    pub public_key: Vec<u8>,
+    pub scope: DeviceScope,
+    pub enrolled_at: DateTime<Utc>,
+    /// Device that approved the enrollment; None for the member's first device
+    pub approved_by: Option<String>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revocation_reason: Option<String>,
+}
+
+impl DeviceKey {
+    /// Verification method ID of the key in the member's DID document
+    pub fn key_id(&self) -> String {
+        format!("{}#device-{}", self.member, self.device_id)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+}
+
+/// A new device's request to be enrolled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrollmentRequest {
+    pub id: String,
+    pub member: IdentityId,
+    pub label: String,
+    pub public_key: Vec<u8>,
+    pub scope: DeviceScope,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl EnrollmentRequest {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Bytes an approving device signs
+    pub fn signing_payload(&self) -> Vec<u8> {
+        format!(
+            "enroll:{}:{}:{}:{}",
+            self.id, self.member, hex::encode(&self.public_key), self.scope.name()
+        ).into_bytes()
+    }
+}
+
+/// Bytes a device signs to revoke another device
+pub fn revocation_payload(member: &IdentityId, device_id: &str) -> Vec<u8> {
+    format!("revoke:{}:{}", member, device_id).into_bytes()
+}
+
+/// Defines the interface for storing device keys and pending enrollments.
+#[async_trait]
+pub trait DeviceStorage: Send + Sync {
+    /// Stores a pending enrollment request.
+    async fn store_request(&self, request: &EnrollmentRequest) -> Result<()>;
+    /// Removes and returns a pending enrollment request.
+    async fn take_request(&self, id: &str) -> Result<Option<EnrollmentRequest>>;
+    /// Stores or replaces a device key.
+    async fn store_device(&self, device: &DeviceKey) -> Result<()>;
+    /// Retrieves every device key of a member, revoked ones included.
+    async fn devices_for(&self, member: &str) -> Result<Vec<DeviceKey>>;
+}
+
+/// Simple in-memory device storage using Mutex-protected HashMaps.
+#[derive(Debug, Default)]
+pub struct InMemoryDeviceStorage {
+    requests: Mutex<HashMap<String, EnrollmentRequest>>,
+    devices: Mutex<HashMap<(String, String), DeviceKey>>,
+}
+
+#[async_trait]
+impl DeviceStorage for InMemoryDeviceStorage {
+    async fn store_request(&self, request: &EnrollmentRequest) -> Result<()> {
+        let mut requests = self.requests.lock().map_err(|_| anyhow!("Failed to lock enrollment storage"))?;
+        requests.insert(request.id.clone(), request.clone());
+        Ok(())
+    }
+
+    async fn take_request(&self, id: &str) -> Result<Option<EnrollmentRequest>> {
+        let mut requests = self.requests.lock().map_err(|_| anyhow!("Failed to lock enrollment storage"))?;
+        Ok(requests.remove(id))
+    }
+
+    async fn store_device(&self, device: &DeviceKey) -> Result<()> {
+        let mut devices = self.devices.lock().map_err(|_| anyhow!("Failed to lock device storage"))?;
+        devices.insert((device.member.as_str().to_string(), device.device_id.clone()), device.clone());
+        Ok(())
+    }
+
+    async fn devices_for(&self, member: &str) -> Result<Vec<DeviceKey>> {
+        let devices = self.devices.lock().map_err(|_| anyhow!("Failed to lock device storage"))?;
+        let mut keys: Vec<DeviceKey> = devices.values().filter(|d| d.member.as_str() == member).cloned().collect();
+        keys.sort_by(|a, b| a.enrolled_at.cmp(&b.enrolled_at));
+        Ok(keys)
+    }
+}
+
+/// Enrolls, revokes and verifies the device keys of members
+pub struct DeviceRegistry {
+    storage: Arc<dyn DeviceStorage>,
+    enrollment_ttl: Duration,
+}
+
+impl DeviceRegistry {
+    pub fn new(storage: Arc<dyn DeviceStorage>) -> Self {
+        Self {
+            storage,
+            enrollment_ttl: Duration::minutes(15),
+        }
+    }
+
+    /// How long enrollment requests stay valid
+    pub fn with_enrollment_ttl(mut self, ttl: Duration) -> Self {
+        self.enrollment_ttl = ttl;
+        self
+    }
+
+    /// Every device key of a member, revoked ones included
+    pub async fn devices(&self, member: &IdentityId) -> IdentityResult<Vec<DeviceKey>> {
+        self.storage.devices_for(member.as_str()).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    async fn device(&self, member: &IdentityId, device_id: &str) -> IdentityResult<DeviceKey> {
+        self.devices(member).await?
+            .into_iter()
+            .find(|d| d.device_id == device_id)
+            .ok_or_else(|| IdentityError::InvalidDevice(format!("{} has no device {}", member, device_id)))
+    }
+
+    async fn store(&self, device: &DeviceKey) -> IdentityResult<()> {
+        self.storage.store_device(device).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    /// Register a member's first device, with the full scope
+    pub async fn register_first_device(&self, member: &IdentityId, label: &str, public_key: Vec<u8>) -> IdentityResult<DeviceKey> {
+        if !self.devices(member).await?.is_empty() {
+            return Err(IdentityError::InvalidDevice(format!(
+                "{} already has devices; new ones must be enrolled", member
+            )));
+        }
+
+        let device = DeviceKey {
+            device_id: Uuid::new_v4().simple().to_string(),
+            member: member.clone(),
+            label: label.to_string(),
+            public_key,
+            scope: DeviceScope::Full,
+            enrolled_at: Utc::now(),
+            approved_by: None,
+            revoked_at: None,
+            revocation_reason: None,
+        };
+        self.store(&device).await?;
+        Ok(device)
+    }
+
+    /// Ask for a new device to be enrolled with the given scope
+    pub async fn request_enrollment(
+        &self,
+        member: &IdentityId,
+        label: &str,
+        public_key: Vec<u8>,
+        scope: DeviceScope,
+    ) -> IdentityResult<EnrollmentRequest> {
+        if public_key.is_empty() {
+            return Err(IdentityError::InvalidDevice("Device public key must not be empty".to_string()));
+        }
+        if self.devices(member).await?.iter().any(|d| d.is_active() && d.public_key == public_key) {
+            return Err(IdentityError::InvalidDevice("This key is already enrolled".to_string()));
+        }
+
+        let requested_at = Utc::now();
+        let request = EnrollmentRequest {
+            id: Uuid::new_v4().to_string(),
+            member: member.clone(),
+            label: label.to_string(),
+            public_key,
+            scope,
+            requested_at,
+            expires_at: requested_at + self.enrollment_ttl,
+        };
+        self.storage.store_request(&request).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))?;
+        Ok(request)
+    }
+
+    /// Approve an enrollment with a signature over [`EnrollmentRequest::signing_payload`]
+    /// from one of the member's devices that may manage devices
+    pub async fn approve_enrollment(
+        &self,
+        request_id: &str,
+        approving_device: &str,
+        signature: &Signature,
+    ) -> IdentityResult<DeviceKey> {
+        let request = self.storage.take_request(request_id).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))?
+            .ok_or_else(|| IdentityError::InvalidDevice(format!("No pending enrollment {}", request_id)))?;
+        if request.is_expired(Utc::now()) {
+            return Err(IdentityError::InvalidDevice(format!("Enrollment {} has expired", request_id)));
+        }
+
+        self.verify_device_signature(
+            &request.member, approving_device, CAP_MANAGE_DEVICES, &request.signing_payload(), signature,
+        ).await?;
+
+        let device = DeviceKey {
+            device_id: Uuid::new_v4().simple().to_string(),
+            member: request.member,
+            label: request.label,
+            public_key: request.public_key,
+            scope: request.scope,
+            enrolled_at: Utc::now(),
+            approved_by: Some(approving_device.to_string()),
+            revoked_at: None,
+            revocation_reason: None,
+        };
+        self.store(&device).await?;
+        Ok(device)
+    }
+
+    /// Revoke a device with a signature over [`revocation_payload`] from one of the
+    /// member's devices that may manage devices
+    pub async fn revoke_device(
+        &self,
+        member: &IdentityId,
+        device_id: &str,
+        revoking_device: &str,
+        signature: &Signature,
+        reason: &str,
+    ) -> IdentityResult<DeviceKey> {
+        self.verify_device_signature(
+            member, revoking_device, CAP_MANAGE_DEVICES, &revocation_payload(member, device_id), signature,
+        ).await?;
+
+        let devices = self.devices(member).await?;
+        let mut device = devices.iter()
+            .find(|d| d.device_id == device_id)
+            .cloned()
+            .ok_or_else(|| IdentityError::InvalidDevice(format!("{} has no device {}", member, device_id)))?;
+        if !device.is_active() {
+            return Err(IdentityError::InvalidDevice(format!("Device {} is already revoked", device_id)));
+        }
+
+        let managers = devices.iter().filter(|d| d.is_active() && d.scope.allows(CAP_MANAGE_DEVICES)).count();
+        if device.scope.allows(CAP_MANAGE_DEVICES) && managers <= 1 {
+            return Err(IdentityError::InvalidDevice(format!(
+                "Device {} is the last one that can manage {}'s devices", device_id, member
+            )));
+        }
+
+        device.revoked_at = Some(Utc::now());
+        device.revocation_reason = Some(reason.to_string());
+        self.store(&device).await?;
+        Ok(device)
+    }
+
+    /// Verify that a member's device signed a message and that its scope allows the
+    /// capability the signature is used for. Returns the device.
+    pub async fn verify_device_signature(
+        &self,
+        member: &IdentityId,
+        device_id: &str,
+        capability: &str,
+        message: &[u8],
+        signature: &Signature,
+    ) -> IdentityResult<DeviceKey> {
+        let device = self.device(member, device_id).await?;
+        if !device.is_active() {
+            return Err(IdentityError::InvalidDevice(format!("Device {} has been revoked", device_id)));
+        }
+        if !device.scope.allows(capability) {
+            return Err(IdentityError::ScopeViolation(format!(
+                "Device {} has scope {} and may not sign for {}", device_id, device.scope.name(), capability
+            )));
+        }
+        if !verify_signature(message, signature, &IdentityId::new(device.key_id()))? {
+            return Err(IdentityError::InvalidSignature(format!("Bad signature from device {}", device_id)));
+        }
+        Ok(device)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sign_message, KeyPair};
+
+    #[tokio::test]
+    async fn test_enrollment_needs_a_full_device_and_scopes_are_enforced() {
+        let registry = DeviceRegistry::new(Arc::new(InMemoryDeviceStorage::default()));
+        let alice = IdentityId::new("did:key:z6MkAlice");
+        let laptop_key = KeyPair::generate_random();
+        let phone_key = KeyPair::generate_random();
+
+        let laptop = registry.register_first_device(&alice, "laptop", laptop_key.public_key().to_vec()).await.unwrap();
+        assert!(registry.register_first_device(&alice, "tablet", vec![7; 32]).await.is_err());
+
+        let request = registry.request_enrollment(&alice, "phone", phone_key.public_key().to_vec(), DeviceScope::VoteOnly).await.unwrap();
+        let approval = sign_message(&request.signing_payload(), &laptop_key).unwrap();
+        let phone = registry.approve_enrollment(&request.id, &laptop.device_id, &approval).await.unwrap();
+        assert_eq!(phone.approved_by, Some(laptop.device_id.clone()));
+
+        let ballot = b"proposal-1:for";
+        let vote_sig = sign_message(ballot, &phone_key).unwrap();
+        registry.verify_device_signature(&alice, &phone.device_id, CAP_VOTE, ballot, &vote_sig).await.unwrap();
+        assert!(matches!(
+            registry.verify_device_signature(&alice, &phone.device_id, "sign_credentials", ballot, &vote_sig).await,
+            Err(IdentityError::ScopeViolation(_))
+        ));
+
+        // The vote-only phone can't enroll further devices
+        let request = registry.request_enrollment(&alice, "tablet", vec![9; 32], DeviceScope::Full).await.unwrap();
+        let approval = sign_message(&request.signing_payload(), &phone_key).unwrap();
+        assert!(registry.approve_enrollment(&request.id, &phone.device_id, &approval).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revocation_keeps_a_managing_device() {
+        let registry = DeviceRegistry::new(Arc::new(InMemoryDeviceStorage::default()));
+        let alice = IdentityId::new("did:key:z6MkAlice");
+        let laptop_key = KeyPair::generate_random();
+        let laptop = registry.register_first_device(&alice, "laptop", laptop_key.public_key().to_vec()).await.unwrap();
+
+        let request = registry.request_enrollment(&alice, "phone", vec![5; 32], DeviceScope::VoteOnly).await.unwrap();
+        let approval = sign_message(&request.signing_payload(), &laptop_key).unwrap();
+        let phone = registry.approve_enrollment(&request.id, &laptop.device_id, &approval).await.unwrap();
+
+        let self_revoke = sign_message(&revocation_payload(&alice, &laptop.device_id), &laptop_key).unwrap();
+        assert!(registry.revoke_device(&alice, &laptop.device_id, &laptop.device_id, &self_revoke, "lost").await.is_err());
+
+        let revoke = sign_message(&revocation_payload(&alice, &phone.device_id), &laptop_key).unwrap();
+        let revoked = registry.revoke_device(&alice, &phone.device_id, &laptop.device_id, &revoke, "lost").await.unwrap();
+        assert!(!revoked.is_active());
+
+        let vote_sig = sign_message(b"ballot", &laptop_key).unwrap();
+        assert!(registry.verify_device_signature(&alice, &phone.device_id, CAP_VOTE, b"ballot", &vote_sig).await.is_err());
+    }
+}
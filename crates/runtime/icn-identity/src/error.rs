@@ -46,6 +46,15 @@ pub enum IdentityError {
     #[error("Storage error: {0}")]
     StorageError(String),
 
+    #[error("Invalid profile: {0}")]
+    InvalidProfile(String),
+
+    #[error("Invalid link: {0}")]
+    InvalidLink(String),
+
+    #[error("Invalid device: {0}")]
+    InvalidDevice(String),
+
     #[error("Internal error: {0}")]
     InternalError(#[from] anyhow::Error), // Allow conversion from anyhow
 }
@@ -11,9 +11,14 @@ Verifiable Credentials, TrustBundles, and ZK disclosure.
 - TrustBundles for federation anchoring
 */
 
+pub mod contact;
+pub mod devices;
 pub mod did;
 pub mod error;
 pub mod keypair;
+pub mod linking;
+pub mod profile;
+pub mod renewal;
 
 // Standard library imports
 use std::collections::HashMap;
@@ -50,8 +55,13 @@ use icn_common::DagStore;
 use crate::error::{IdentityError, IdentityResult};
 
 // Re-export essential types for external use
+pub use crate::contact::{ContactAttestation, ContactChannel, ContactVerification, ContactVerifier, VerificationChallenge};
+pub use crate::devices::{DeviceKey, DeviceRegistry, DeviceScope, EnrollmentRequest};
 pub use crate::did::IdentityId;
 pub use crate::keypair::{KeyPair, Signature};
+pub use crate::linking::{ExternalAccountLink, LinkChallenge, LinkConsent, LinkRegistry, LinkViewer};
+pub use crate::profile::{FieldVisibility, MemberProfile, ProfileRegistry, ProfileView, ViewerContext};
+pub use crate::renewal::{CredentialExpiryMonitor, ExpiryNotifier, RenewalNotice};
 
 /// Simple DID resolver trait that will be expanded later
 pub trait SimpleDIDResolver {
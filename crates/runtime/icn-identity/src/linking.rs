@@ -0,0 +1,468 @@
+/*!
+# External Account Linking
+
+Verified links between member DIDs and accounts on external systems such as payroll or
+a forum. A member asks for a [`LinkChallenge`] naming the provider and the account; the
+challenge carries a one-time token. The member proves control of the account either by
+posting the token on the external system, or by having the provider call back with a
+webhook signed with the HMAC-SHA256 secret the cooperative shares with that provider.
+
+A verified link is recorded as an [`ExternalAccountLink`] holding an
+`ExternalAccountLinkCredential`. The member decides which integrations may see each link
+through its [`LinkConsent`], and can change that consent or revoke the link at any time.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::did::IdentityId;
+use crate::error::{IdentityError, IdentityResult};
+use crate::VerifiableCredential;
+
+/// Prefix of the token a member posts on the external system
+pub const LINK_TOKEN_PREFIX: &str = "icn-link:";
+
+/// A pending request to link a DID to an external account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkChallenge {
+    pub id: String,
+    /// The DID asking for the link
+    pub did: IdentityId,
+    /// External system (e.g., "payroll", "forum")
+    pub provider: String,
+    /// Account identifier on the external system
+    pub external_account: String,
+    /// One-time token the proof must contain
+    pub token: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl LinkChallenge {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Payload a provider posts back to confirm a link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookCallback {
+    pub challenge_id: String,
+    pub token: String,
+    /// The account the provider authenticated, which must match the challenge
+    pub external_account: String,
+}
+
+/// How a link was proven
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkMethod {
+    /// The token was posted publicly on the external system at this location
+    PostedProof { location: String },
+    /// The provider confirmed the link through a signed webhook
+    WebhookCallback,
+}
+
+/// Which integrations may resolve a link
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkConsent {
+    /// Integrations allowed to see the link, by name (e.g., "payroll-sync")
+    pub integrations: Vec<String>,
+    /// Whether other members may see the link
+    pub members: bool,
+}
+
+/// Who is asking about a member's links
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkViewer {
+    Member(IdentityId),
+    Integration(String),
+}
+
+/// A verified link between a DID and an external account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalAccountLink {
+    pub did: IdentityId,
+    pub provider: String,
+    pub external_account: String,
+    pub method: LinkMethod,
+    pub credential: VerifiableCredential,
+    pub consent: LinkConsent,
+    pub linked_at: DateTime<Utc>,
+}
+
+impl ExternalAccountLink {
+    /// Whether the owner's consent lets a viewer see this link
+    pub fn visible_to(&self, viewer: &LinkViewer) -> bool {
+        match viewer {
+            LinkViewer::Member(did) => did == &self.did || self.consent.members,
+            LinkViewer::Integration(name) => self.consent.integrations.contains(name),
+        }
+    }
+}
+
+/// Settings for an external system accounts can be linked on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkProvider {
+    pub name: String,
+    /// Shared secret the provider signs webhook callbacks with. Providers without one
+    /// only accept posted proofs.
+    pub webhook_secret: Option<Vec<u8>>,
+}
+
+/// Sign a webhook body the way a provider does; returns the hex-encoded HMAC-SHA256
+pub fn sign_webhook(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn verify_webhook(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Defines the interface for storing link challenges and links.
+#[async_trait]
+pub trait LinkStorage: Send + Sync {
+    /// Stores a pending challenge.
+    async fn store_challenge(&self, challenge: &LinkChallenge) -> Result<()>;
+    /// Removes and returns a pending challenge.
+    async fn take_challenge(&self, id: &str) -> Result<Option<LinkChallenge>>;
+    /// Stores or replaces the link for a DID, provider and account.
+    async fn store_link(&self, link: &ExternalAccountLink) -> Result<()>;
+    /// Retrieves every link held by a DID.
+    async fn links_for(&self, did: &str) -> Result<Vec<ExternalAccountLink>>;
+    /// Retrieves the link for an external account, if any.
+    async fn link_for_account(&self, provider: &str, external_account: &str) -> Result<Option<ExternalAccountLink>>;
+    /// Deletes the link for an external account.
+    async fn delete_link(&self, provider: &str, external_account: &str) -> Result<()>;
+}
+
+/// Simple in-memory link storage using Mutex-protected HashMaps.
+#[derive(Debug, Default)]
+pub struct InMemoryLinkStorage {
+    challenges: Mutex<HashMap<String, LinkChallenge>>,
+    links: Mutex<HashMap<(String, String), ExternalAccountLink>>,
+}
+
+#[async_trait]
+impl LinkStorage for InMemoryLinkStorage {
+    async fn store_challenge(&self, challenge: &LinkChallenge) -> Result<()> {
+        let mut challenges = self.challenges.lock().map_err(|_| anyhow!("Failed to lock challenge storage"))?;
+        challenges.insert(challenge.id.clone(), challenge.clone());
+        Ok(())
+    }
+
+    async fn take_challenge(&self, id: &str) -> Result<Option<LinkChallenge>> {
+        let mut challenges = self.challenges.lock().map_err(|_| anyhow!("Failed to lock challenge storage"))?;
+        Ok(challenges.remove(id))
+    }
+
+    async fn store_link(&self, link: &ExternalAccountLink) -> Result<()> {
+        let mut links = self.links.lock().map_err(|_| anyhow!("Failed to lock link storage"))?;
+        links.insert((link.provider.clone(), link.external_account.clone()), link.clone());
+        Ok(())
+    }
+
+    async fn links_for(&self, did: &str) -> Result<Vec<ExternalAccountLink>> {
+        let links = self.links.lock().map_err(|_| anyhow!("Failed to lock link storage"))?;
+        Ok(links.values().filter(|l| l.did.as_str() == did).cloned().collect())
+    }
+
+    async fn link_for_account(&self, provider: &str, external_account: &str) -> Result<Option<ExternalAccountLink>> {
+        let links = self.links.lock().map_err(|_| anyhow!("Failed to lock link storage"))?;
+        Ok(links.get(&(provider.to_string(), external_account.to_string())).cloned())
+    }
+
+    async fn delete_link(&self, provider: &str, external_account: &str) -> Result<()> {
+        let mut links = self.links.lock().map_err(|_| anyhow!("Failed to lock link storage"))?;
+        links.remove(&(provider.to_string(), external_account.to_string()));
+        Ok(())
+    }
+}
+
+/// Issues link challenges, verifies proofs and answers consent-gated queries
+pub struct LinkRegistry {
+    storage: Arc<dyn LinkStorage>,
+    /// Identity that issues link credentials
+    issuer: IdentityId,
+    providers: HashMap<String, LinkProvider>,
+    challenge_ttl: Duration,
+}
+
+impl LinkRegistry {
+    pub fn new(storage: Arc<dyn LinkStorage>, issuer: IdentityId) -> Self {
+        Self {
+            storage,
+            issuer,
+            providers: HashMap::new(),
+            challenge_ttl: Duration::hours(1),
+        }
+    }
+
+    /// Register an external system accounts can be linked on
+    pub fn with_provider(mut self, provider: LinkProvider) -> Self {
+        self.providers.insert(provider.name.clone(), provider);
+        self
+    }
+
+    /// How long challenges stay valid
+    pub fn with_challenge_ttl(mut self, ttl: Duration) -> Self {
+        self.challenge_ttl = ttl;
+        self
+    }
+
+    fn provider(&self, name: &str) -> IdentityResult<&LinkProvider> {
+        self.providers.get(name)
+            .ok_or_else(|| IdentityError::InvalidLink(format!("Unknown link provider: {}", name)))
+    }
+
+    /// Start linking a DID to an external account
+    pub async fn issue_challenge(&self, did: &IdentityId, provider: &str, external_account: &str) -> IdentityResult<LinkChallenge> {
+        self.provider(provider)?;
+        if external_account.trim().is_empty() {
+            return Err(IdentityError::InvalidLink("External account must not be empty".to_string()));
+        }
+
+        let issued_at = Utc::now();
+        let challenge = LinkChallenge {
+            id: Uuid::new_v4().to_string(),
+            did: did.clone(),
+            provider: provider.to_string(),
+            external_account: external_account.to_string(),
+            token: format!("{}{}", LINK_TOKEN_PREFIX, Uuid::new_v4().simple()),
+            issued_at,
+            expires_at: issued_at + self.challenge_ttl,
+        };
+
+        self.storage.store_challenge(&challenge).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))?;
+        Ok(challenge)
+    }
+
+    async fn take_live_challenge(&self, challenge_id: &str) -> IdentityResult<LinkChallenge> {
+        let challenge = self.storage.take_challenge(challenge_id).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))?
+            .ok_or_else(|| IdentityError::InvalidLink(format!("No pending challenge {}", challenge_id)))?;
+        if challenge.is_expired(Utc::now()) {
+            return Err(IdentityError::InvalidLink(format!("Challenge {} has expired", challenge_id)));
+        }
+        Ok(challenge)
+    }
+
+    /// Complete a challenge with content fetched from the external system. The content
+    /// must contain the challenge token.
+    pub async fn complete_with_posted_proof(
+        &self,
+        challenge_id: &str,
+        location: &str,
+        content: &str,
+    ) -> IdentityResult<ExternalAccountLink> {
+        let challenge = self.take_live_challenge(challenge_id).await?;
+        if !content.contains(&challenge.token) {
+            return Err(IdentityError::VerificationError(format!(
+                "Posted proof at {} does not contain the challenge token", location
+            )));
+        }
+        self.record_link(challenge, LinkMethod::PostedProof { location: location.to_string() }).await
+    }
+
+    /// Complete a challenge from a provider's webhook. `signature` is the hex-encoded
+    /// HMAC-SHA256 of `body` under the provider's shared secret.
+    pub async fn complete_with_webhook(
+        &self,
+        provider: &str,
+        body: &[u8],
+        signature: &str,
+    ) -> IdentityResult<ExternalAccountLink> {
+        let secret = self.provider(provider)?.webhook_secret.as_ref()
+            .ok_or_else(|| IdentityError::InvalidLink(format!("Provider {} does not accept webhooks", provider)))?;
+        if !verify_webhook(secret, body, signature) {
+            return Err(IdentityError::InvalidSignature(format!("Webhook from {} has a bad signature", provider)));
+        }
+
+        let callback: WebhookCallback = serde_json::from_slice(body)
+            .map_err(|e| IdentityError::SerializationError(format!("Invalid webhook body: {}", e)))?;
+        let challenge = self.take_live_challenge(&callback.challenge_id).await?;
+        if challenge.provider != provider
+            || challenge.token != callback.token
+            || challenge.external_account != callback.external_account
+        {
+            return Err(IdentityError::VerificationError(
+                "Webhook does not match the pending challenge".to_string()
+            ));
+        }
+        self.record_link(challenge, LinkMethod::WebhookCallback).await
+    }
+
+    async fn record_link(&self, challenge: LinkChallenge, method: LinkMethod) -> IdentityResult<ExternalAccountLink> {
+        if let Some(existing) = self.get_account_link(&challenge.provider, &challenge.external_account).await? {
+            if existing.did != challenge.did {
+                return Err(IdentityError::InvalidLink(format!(
+                    "{} account {} is already linked to another DID", challenge.provider, challenge.external_account
+                )));
+            }
+        }
+
+        let credential = VerifiableCredential::new(
+            vec!["VerifiableCredential".to_string(), "ExternalAccountLinkCredential".to_string()],
+            &self.issuer,
+            &challenge.did,
+            serde_json::json!({
+                "provider": challenge.provider,
+                "externalAccount": challenge.external_account,
+                "method": method,
+            }),
+        );
+
+        // New links are visible to no one but the owner until they grant consent
+        let link = ExternalAccountLink {
+            did: challenge.did,
+            provider: challenge.provider,
+            external_account: challenge.external_account,
+            method,
+            credential,
+            consent: LinkConsent::default(),
+            linked_at: Utc::now(),
+        };
+
+        self.storage.store_link(&link).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))?;
+        Ok(link)
+    }
+
+    async fn get_account_link(&self, provider: &str, external_account: &str) -> IdentityResult<Option<ExternalAccountLink>> {
+        self.storage.link_for_account(provider, external_account).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    async fn owned_link(&self, editor: &IdentityId, provider: &str, external_account: &str) -> IdentityResult<ExternalAccountLink> {
+        let link = self.get_account_link(provider, external_account).await?
+            .ok_or_else(|| IdentityError::InvalidLink(format!("{} account {} is not linked", provider, external_account)))?;
+        if editor != &link.did {
+            return Err(IdentityError::ScopeViolation(format!(
+                "{} cannot change the link of {}", editor, link.did
+            )));
+        }
+        Ok(link)
+    }
+
+    /// Replace the consent on a link. Only the linked member may change it.
+    pub async fn set_consent(
+        &self,
+        editor: &IdentityId,
+        provider: &str,
+        external_account: &str,
+        consent: LinkConsent,
+    ) -> IdentityResult<()> {
+        let mut link = self.owned_link(editor, provider, external_account).await?;
+        link.consent = consent;
+        self.storage.store_link(&link).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    /// Remove a link. Only the linked member may remove it.
+    pub async fn revoke_link(&self, editor: &IdentityId, provider: &str, external_account: &str) -> IdentityResult<()> {
+        self.owned_link(editor, provider, external_account).await?;
+        self.storage.delete_link(provider, external_account).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    /// The links of a DID the viewer may see
+    pub async fn links_for(&self, did: &IdentityId, viewer: &LinkViewer) -> IdentityResult<Vec<ExternalAccountLink>> {
+        let links = self.storage.links_for(did.as_str()).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))?;
+        Ok(links.into_iter().filter(|l| l.visible_to(viewer)).collect())
+    }
+
+    /// Resolve an external account to its DID, if the member consented to the viewer
+    /// seeing the link
+    pub async fn resolve_account(
+        &self,
+        provider: &str,
+        external_account: &str,
+        viewer: &LinkViewer,
+    ) -> IdentityResult<Option<IdentityId>> {
+        Ok(self.get_account_link(provider, external_account).await?
+            .filter(|l| l.visible_to(viewer))
+            .map(|l| l.did))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"payroll-shared-secret";
+
+    fn registry() -> LinkRegistry {
+        LinkRegistry::new(Arc::new(InMemoryLinkStorage::default()), IdentityId::new("did:key:z6MkCoop"))
+            .with_provider(LinkProvider { name: "forum".to_string(), webhook_secret: None })
+            .with_provider(LinkProvider { name: "payroll".to_string(), webhook_secret: Some(SECRET.to_vec()) })
+    }
+
+    #[tokio::test]
+    async fn test_posted_proof_links_and_consent_gates_queries() {
+        let registry = registry();
+        let alice = IdentityId::new("did:key:z6MkAlice");
+        let bob = IdentityId::new("did:key:z6MkBob");
+        let payroll = LinkViewer::Integration("payroll-sync".to_string());
+
+        let challenge = registry.issue_challenge(&alice, "forum", "alice42").await.unwrap();
+        assert!(registry.complete_with_posted_proof(&challenge.id, "https://forum.example/p/1", "hello").await.is_err());
+
+        // A failed attempt consumes the challenge
+        let challenge = registry.issue_challenge(&alice, "forum", "alice42").await.unwrap();
+        let post = format!("Linking my ICN identity: {}", challenge.token);
+        let link = registry.complete_with_posted_proof(&challenge.id, "https://forum.example/p/2", &post).await.unwrap();
+        assert!(link.credential.type_.contains(&"ExternalAccountLinkCredential".to_string()));
+
+        assert_eq!(registry.links_for(&alice, &LinkViewer::Member(alice.clone())).await.unwrap().len(), 1);
+        assert!(registry.resolve_account("forum", "alice42", &payroll).await.unwrap().is_none());
+
+        let consent = LinkConsent { integrations: vec!["payroll-sync".to_string()], members: false };
+        assert!(registry.set_consent(&bob, "forum", "alice42", consent.clone()).await.is_err());
+        registry.set_consent(&alice, "forum", "alice42", consent).await.unwrap();
+        assert_eq!(registry.resolve_account("forum", "alice42", &payroll).await.unwrap(), Some(alice.clone()));
+        assert!(registry.links_for(&alice, &LinkViewer::Member(bob.clone())).await.unwrap().is_empty());
+
+        registry.revoke_link(&alice, "forum", "alice42").await.unwrap();
+        assert!(registry.resolve_account("forum", "alice42", &payroll).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_requires_valid_signature_and_matching_challenge() {
+        let registry = registry();
+        let alice = IdentityId::new("did:key:z6MkAlice");
+
+        let challenge = registry.issue_challenge(&alice, "payroll", "emp-0042").await.unwrap();
+        let body = serde_json::to_vec(&WebhookCallback {
+            challenge_id: challenge.id.clone(),
+            token: challenge.token.clone(),
+            external_account: "emp-0042".to_string(),
+        }).unwrap();
+
+        let forged = sign_webhook(b"wrong-secret", &body);
+        assert!(matches!(
+            registry.complete_with_webhook("payroll", &body, &forged).await,
+            Err(IdentityError::InvalidSignature(_))
+        ));
+        assert!(registry.complete_with_webhook("forum", &body, &sign_webhook(SECRET, &body)).await.is_err());
+
+        let link = registry.complete_with_webhook("payroll", &body, &sign_webhook(SECRET, &body)).await.unwrap();
+        assert_eq!(link.method, LinkMethod::WebhookCallback);
+        assert_eq!(link.did, alice);
+    }
+}
@@ -0,0 +1,368 @@
+/*!
+# Member Profiles
+
+DID-linked profiles holding display names, pronouns, contact channels and skills.
+The member sets a visibility on every field, and the registry only discloses a field to
+viewers that visibility allows. Governance UIs use the redacted [`ProfileView`] to show
+proposers and voters with human-readable names, without leaking contact details beyond
+the audience the member chose.
+*/
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::did::IdentityId;
+use crate::error::{IdentityError, IdentityResult};
+
+/// Who a profile field is disclosed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum FieldVisibility {
+    /// Anyone, including unauthenticated viewers
+    Public,
+    /// Members of any federation the profile owner belongs to
+    Federation,
+    /// Members of any cooperative the profile owner belongs to
+    Cooperative,
+    /// Only the profile owner
+    Private,
+}
+
+/// A profile value together with its visibility
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileField<T> {
+    pub value: T,
+    pub visibility: FieldVisibility,
+}
+
+impl<T> ProfileField<T> {
+    pub fn new(value: T, visibility: FieldVisibility) -> Self {
+        Self { value, visibility }
+    }
+}
+
+/// A way of contacting a member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactChannel {
+    /// Channel kind (e.g., "email", "matrix", "phone")
+    pub kind: String,
+    /// Address on that channel
+    pub address: String,
+}
+
+/// A member's profile as stored in the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberProfile {
+    /// The DID the profile belongs to
+    pub did: IdentityId,
+    /// Display name
+    pub display_name: Option<ProfileField<String>>,
+    /// Pronouns
+    pub pronouns: Option<ProfileField<String>>,
+    /// Contact channels, each with its own visibility
+    pub contact_channels: Vec<ProfileField<ContactChannel>>,
+    /// Skills the member offers
+    pub skills: Option<ProfileField<Vec<String>>>,
+    /// Cooperatives the member belongs to
+    pub cooperatives: Vec<String>,
+    /// Federations the member belongs to
+    pub federations: Vec<String>,
+    /// Last time the profile was updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MemberProfile {
+    /// Create an empty profile for a DID
+    pub fn new(did: IdentityId) -> Self {
+        Self {
+            did,
+            display_name: None,
+            pronouns: None,
+            contact_channels: Vec::new(),
+            skills: None,
+            cooperatives: Vec::new(),
+            federations: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// The viewer a profile is being rendered for
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewerContext {
+    /// The viewer's DID, if authenticated
+    pub did: Option<IdentityId>,
+    /// Cooperatives the viewer belongs to
+    pub cooperatives: Vec<String>,
+    /// Federations the viewer belongs to
+    pub federations: Vec<String>,
+}
+
+impl ViewerContext {
+    /// An unauthenticated viewer
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    /// The most restrictive visibility this viewer may see on a profile
+    pub fn access_level(&self, profile: &MemberProfile) -> FieldVisibility {
+        if self.did.as_ref() == Some(&profile.did) {
+            FieldVisibility::Private
+        } else if self.cooperatives.iter().any(|c| profile.cooperatives.contains(c)) {
+            FieldVisibility::Cooperative
+        } else if self.federations.iter().any(|f| profile.federations.contains(f)) {
+            FieldVisibility::Federation
+        } else {
+            FieldVisibility::Public
+        }
+    }
+}
+
+/// A profile redacted to the fields a particular viewer may see
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileView {
+    pub did: IdentityId,
+    pub display_name: Option<String>,
+    pub pronouns: Option<String>,
+    pub contact_channels: Vec<ContactChannel>,
+    pub skills: Vec<String>,
+}
+
+impl ProfileView {
+    /// A view that discloses nothing but the DID
+    pub fn anonymous(did: IdentityId) -> Self {
+        Self {
+            did,
+            display_name: None,
+            pronouns: None,
+            contact_channels: Vec::new(),
+            skills: Vec::new(),
+        }
+    }
+
+    /// Label to render for this identity: the display name if visible, otherwise a
+    /// shortened DID
+    pub fn label(&self) -> String {
+        match &self.display_name {
+            Some(name) => name.clone(),
+            None => shorten_did(self.did.as_str()),
+        }
+    }
+}
+
+/// Redact a profile to what a viewer may see
+pub fn redact_profile(profile: &MemberProfile, viewer: &ViewerContext) -> ProfileView {
+    let level = viewer.access_level(profile);
+    let visible = |visibility: FieldVisibility| visibility <= level;
+
+    ProfileView {
+        did: profile.did.clone(),
+        display_name: profile.display_name.as_ref()
+            .filter(|f| visible(f.visibility))
+            .map(|f| f.value.clone()),
+        pronouns: profile.pronouns.as_ref()
+            .filter(|f| visible(f.visibility))
+            .map(|f| f.value.clone()),
+        contact_channels: profile.contact_channels.iter()
+            .filter(|f| visible(f.visibility))
+            .map(|f| f.value.clone())
+            .collect(),
+        skills: profile.skills.as_ref()
+            .filter(|f| visible(f.visibility))
+            .map(|f| f.value.clone())
+            .unwrap_or_default(),
+    }
+}
+
+fn shorten_did(did: &str) -> String {
+    const KEEP: usize = 8;
+    let id = did.rsplit(':').next().unwrap_or(did);
+    if id.chars().count() <= KEEP * 2 {
+        return did.to_string();
+    }
+    let head: String = id.chars().take(KEEP).collect();
+    let tail: String = id.chars().skip(id.chars().count() - KEEP).collect();
+    let method = did.strip_suffix(id).unwrap_or("");
+    format!("{}{}…{}", method, head, tail)
+}
+
+/// Defines the interface for storing member profiles.
+#[async_trait]
+pub trait ProfileStorage: Send + Sync {
+    /// Stores or replaces the profile for a DID.
+    async fn store_profile(&self, profile: &MemberProfile) -> Result<()>;
+    /// Retrieves the profile for a DID.
+    async fn retrieve_profile(&self, did: &str) -> Result<Option<MemberProfile>>;
+    /// Deletes the profile for a DID.
+    async fn delete_profile(&self, did: &str) -> Result<()>;
+}
+
+/// Simple in-memory profile storage using Mutex-protected HashMap.
+#[derive(Debug, Default)]
+pub struct InMemoryProfileStorage {
+    profiles: Mutex<HashMap<String, MemberProfile>>,
+}
+
+#[async_trait]
+impl ProfileStorage for InMemoryProfileStorage {
+    async fn store_profile(&self, profile: &MemberProfile) -> Result<()> {
+        let mut profiles = self.profiles.lock().map_err(|_| anyhow!("Failed to lock profile storage"))?;
+        profiles.insert(profile.did.as_str().to_string(), profile.clone());
+        Ok(())
+    }
+
+    async fn retrieve_profile(&self, did: &str) -> Result<Option<MemberProfile>> {
+        let profiles = self.profiles.lock().map_err(|_| anyhow!("Failed to lock profile storage"))?;
+        Ok(profiles.get(did).cloned())
+    }
+
+    async fn delete_profile(&self, did: &str) -> Result<()> {
+        let mut profiles = self.profiles.lock().map_err(|_| anyhow!("Failed to lock profile storage"))?;
+        profiles.remove(did);
+        Ok(())
+    }
+}
+
+/// Registry of member profiles keyed by DID
+pub struct ProfileRegistry {
+    storage: Arc<dyn ProfileStorage>,
+}
+
+impl ProfileRegistry {
+    pub fn new(storage: Arc<dyn ProfileStorage>) -> Self {
+        Self { storage }
+    }
+
+    /// Create or replace a profile. Only the profile's owner may change it.
+    pub async fn upsert_profile(&self, editor: &IdentityId, mut profile: MemberProfile) -> IdentityResult<()> {
+        if editor != &profile.did {
+            return Err(IdentityError::ScopeViolation(format!(
+                "{} cannot edit the profile of {}", editor, profile.did
+            )));
+        }
+        if profile.display_name.as_ref().is_some_and(|f| f.value.trim().is_empty()) {
+            return Err(IdentityError::InvalidProfile("Display name must not be empty".to_string()));
+        }
+
+        profile.updated_at = Utc::now();
+        self.storage.store_profile(&profile).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    /// Remove a profile. Only the profile's owner may remove it.
+    pub async fn remove_profile(&self, editor: &IdentityId, did: &IdentityId) -> IdentityResult<()> {
+        if editor != did {
+            return Err(IdentityError::ScopeViolation(format!(
+                "{} cannot remove the profile of {}", editor, did
+            )));
+        }
+        self.storage.delete_profile(did.as_str()).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    /// Get the raw profile for a DID. Callers must redact it before display.
+    pub async fn get_profile(&self, did: &IdentityId) -> IdentityResult<Option<MemberProfile>> {
+        self.storage.retrieve_profile(did.as_str()).await
+            .map_err(|e| IdentityError::StorageError(e.to_string()))
+    }
+
+    /// Get a profile redacted for a viewer. DIDs without a profile get an anonymous view.
+    pub async fn view_profile(&self, did: &IdentityId, viewer: &ViewerContext) -> IdentityResult<ProfileView> {
+        Ok(match self.get_profile(did).await? {
+            Some(profile) => redact_profile(&profile, viewer),
+            None => ProfileView::anonymous(did.clone()),
+        })
+    }
+
+    /// Resolve display labels for a set of DIDs (e.g., a proposal's voters)
+    pub async fn resolve_labels(
+        &self,
+        dids: &[IdentityId],
+        viewer: &ViewerContext,
+    ) -> IdentityResult<HashMap<IdentityId, String>> {
+        let mut labels = HashMap::new();
+        for did in dids {
+            let view = self.view_profile(did, viewer).await?;
+            labels.insert(did.clone(), view.label());
+        }
+        Ok(labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alice_profile() -> MemberProfile {
+        let mut profile = MemberProfile::new(IdentityId::new("did:key:z6MkAlice0000000000000000000000"));
+        profile.display_name = Some(ProfileField::new("Alice".to_string(), FieldVisibility::Public));
+        profile.pronouns = Some(ProfileField::new("they/them".to_string(), FieldVisibility::Federation));
+        profile.contact_channels = vec![
+            ProfileField::new(
+                ContactChannel { kind: "matrix".to_string(), address: "@alice:coop.example".to_string() },
+                FieldVisibility::Cooperative,
+            ),
+            ProfileField::new(
+                ContactChannel { kind: "phone".to_string(), address: "+1 555 0100".to_string() },
+                FieldVisibility::Private,
+            ),
+        ];
+        profile.skills = Some(ProfileField::new(vec!["bookkeeping".to_string()], FieldVisibility::Cooperative));
+        profile.cooperatives = vec!["coop-a".to_string()];
+        profile.federations = vec!["fed-1".to_string()];
+        profile
+    }
+
+    #[test]
+    fn test_redaction_follows_viewer_relationship() {
+        let profile = alice_profile();
+
+        let public = redact_profile(&profile, &ViewerContext::anonymous());
+        assert_eq!(public.display_name.as_deref(), Some("Alice"));
+        assert!(public.pronouns.is_none());
+        assert!(public.contact_channels.is_empty());
+
+        let federation_peer = ViewerContext {
+            did: Some(IdentityId::new("did:key:z6MkBob")),
+            cooperatives: vec!["coop-b".to_string()],
+            federations: vec!["fed-1".to_string()],
+        };
+        let view = redact_profile(&profile, &federation_peer);
+        assert_eq!(view.pronouns.as_deref(), Some("they/them"));
+        assert!(view.skills.is_empty());
+
+        let coop_peer = ViewerContext {
+            cooperatives: vec!["coop-a".to_string()],
+            ..federation_peer
+        };
+        let view = redact_profile(&profile, &coop_peer);
+        assert_eq!(view.contact_channels.len(), 1);
+        assert_eq!(view.skills, vec!["bookkeeping".to_string()]);
+
+        let owner = ViewerContext { did: Some(profile.did.clone()), ..Default::default() };
+        assert_eq!(redact_profile(&profile, &owner).contact_channels.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_registry_only_lets_owner_edit() {
+        let registry = ProfileRegistry::new(Arc::new(InMemoryProfileStorage::default()));
+        let profile = alice_profile();
+        let alice = profile.did.clone();
+        let bob = IdentityId::new("did:key:z6MkBob");
+
+        assert!(registry.upsert_profile(&bob, profile.clone()).await.is_err());
+        registry.upsert_profile(&alice, profile).await.unwrap();
+
+        let labels = registry.resolve_labels(&[alice.clone(), bob.clone()], &ViewerContext::anonymous()).await.unwrap();
+        assert_eq!(labels[&alice], "Alice");
+        assert_eq!(labels[&bob], "did:key:z6MkBob");
+
+        assert!(registry.remove_profile(&bob, &alice).await.is_err());
+        registry.remove_profile(&alice, &alice).await.unwrap();
+        assert!(registry.get_profile(&alice).await.unwrap().is_none());
+    }
+}
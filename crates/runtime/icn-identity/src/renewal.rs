@@ -0,0 +1,329 @@
+/*!
+# Credential Expiry and Renewal
+
+Credentials carry an `expirationDate`, and permissions that rest on one stop working
+the moment it passes. A [`CredentialExpiryMonitor`] tracks the expiring credentials of
+each scope. The node runs [`CredentialExpiryMonitor::check`] periodically. Each run
+emits a [`RenewalNotice`] through the registered [`ExpiryNotifier`]s for every
+credential that has entered one of the reminder windows (30, 7 and 1 days before
+expiry by default), and once more when a credential lapses. A window's notice is sent
+once per credential.
+
+The issuer renews a credential with [`CredentialExpiryMonitor::renew`]. The renewal
+is a new credential with the same types, issuer and subject claims, a new expiry and a
+fresh signature. The monitor records its lineage, the credentials it replaced oldest
+first, and stops reminding about the old one.
+*/
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use ssi_jwk::JWK;
+use uuid::Uuid;
+
+use crate::error::{IdentityError, IdentityResult};
+use crate::{sign_credential, VerifiableCredential};
+
+/// Receives renewal notices, e.g. to message the holder or publish an event
+#[async_trait]
+pub trait ExpiryNotifier: Send + Sync {
+    async fn renewal_needed(&self, notice: &RenewalNotice) -> Result<()>;
+}
+
+/// A credential approaching or past its expiry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenewalNotice {
+    pub credential_id: String,
+    pub scope: String,
+    pub issuer: String,
+    /// The credential's subject DID, if it names one
+    pub subject: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    /// The reminder window that was entered; None once the credential has expired
+    pub window: Option<Duration>,
+}
+
+impl RenewalNotice {
+    /// Whether the credential has already expired
+    pub fn is_expired(&self) -> bool {
+        self.window.is_none()
+    }
+}
+
+/// A credential the monitor watches
+#[derive(Debug, Clone)]
+struct TrackedCredential {
+    credential: VerifiableCredential,
+    scope: String,
+    expires_at: DateTime<Utc>,
+    /// IDs of the credentials this one renews, oldest first
+    lineage: Vec<String>,
+    /// The credential that renewed this one
+    renewed_by: Option<String>,
+    /// Reminder windows already notified for
+    windows_notified: BTreeSet<Duration>,
+    expiry_notified: bool,
+}
+
+/// Expiry time of a credential, if it has one
+pub fn credential_expiry(credential: &VerifiableCredential) -> IdentityResult<Option<DateTime<Utc>>> {
+    credential.expirationDate.as_deref()
+        .map(|date| DateTime::parse_from_rfc3339(date)
+            .map(|date| date.with_timezone(&Utc))
+            .map_err(|e| IdentityError::InvalidCredential(format!(
+                "Credential {} has an invalid expirationDate: {}", credential.id, e
+            ))))
+        .transpose()
+}
+
+/// Watches credential expiry and issues renewals
+pub struct CredentialExpiryMonitor {
+    notifiers: Vec<Arc<dyn ExpiryNotifier>>,
+    /// Reminder windows, widest first
+    windows: Vec<Duration>,
+    credentials: Mutex<HashMap<String, TrackedCredential>>,
+}
+
+impl Default for CredentialExpiryMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialExpiryMonitor {
+    pub fn new() -> Self {
+        Self {
+            notifiers: Vec::new(),
+            windows: vec![Duration::days(30), Duration::days(7), Duration::days(1)],
+            credentials: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send renewal notices to a notifier as well
+    pub fn with_notifier(mut self, notifier: Arc<dyn ExpiryNotifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Remind this long before expiry instead of the default windows
+    pub fn with_reminder_windows(mut self, mut windows: Vec<Duration>) -> Self {
+        windows.sort_by(|a, b| b.cmp(a));
+        windows.dedup();
+        self.windows = windows;
+        self
+    }
+
+    fn lock_credentials(&self) -> IdentityResult<std::sync::MutexGuard<'_, HashMap<String, TrackedCredential>>> {
+        self.credentials.lock()
+            .map_err(|_| IdentityError::StorageError("Failed to lock tracked credentials".to_string()))
+    }
+
+    /// Watch a credential issued in `scope`. Credentials without an expiry are refused.
+    pub fn track(&self, credential: VerifiableCredential, scope: &str) -> IdentityResult<()> {
+        self.track_with_lineage(credential, scope, Vec::new())
+    }
+
+    fn track_with_lineage(&self, credential: VerifiableCredential, scope: &str, lineage: Vec<String>) -> IdentityResult<()> {
+        let expires_at = credential_expiry(&credential)?
+            .ok_or_else(|| IdentityError::InvalidCredential(format!("Credential {} does not expire", credential.id)))?;
+        let mut credentials = self.lock_credentials()?;
+        if credentials.contains_key(&credential.id) {
+            return Err(IdentityError::InvalidCredential(format!("Credential {} is already tracked", credential.id)));
+        }
+
+        credentials.insert(credential.id.clone(), TrackedCredential {
+            credential,
+            scope: scope.to_string(),
+            expires_at,
+            lineage,
+            renewed_by: None,
+            windows_notified: BTreeSet::new(),
+            expiry_notified: false,
+        });
+        Ok(())
+    }
+
+    /// Notify about every credential that entered a reminder window or expired since
+    /// the last check. A credential that skipped several windows between checks gets
+    /// one notice, for the narrowest. Returns the notices sent.
+    pub async fn check(&self, now: DateTime<Utc>) -> IdentityResult<Vec<RenewalNotice>> {
+        let notices = {
+            let mut credentials = self.lock_credentials()?;
+            let mut notices = Vec::new();
+            for tracked in credentials.values_mut().filter(|t| t.renewed_by.is_none()) {
+                let remaining = tracked.expires_at - now;
+                let window = if remaining <= Duration::zero() {
+                    if tracked.expiry_notified {
+                        continue;
+                    }
+                    tracked.expiry_notified = true;
+                    None
+                } else {
+                    let entered: Vec<Duration> = self.windows.iter()
+                        .filter(|w| remaining <= **w && !tracked.windows_notified.contains(*w))
+                        .copied()
+                        .collect();
+                    let Some(narrowest) = entered.last().copied() else { continue };
+                    tracked.windows_notified.extend(entered);
+                    Some(narrowest)
+                };
+
+                notices.push(RenewalNotice {
+                    credential_id: tracked.credential.id.clone(),
+                    scope: tracked.scope.clone(),
+                    issuer: tracked.credential.issuer.clone(),
+                    subject: tracked.credential.credentialSubject.get("id")
+                        .and_then(|id| id.as_str())
+                        .map(str::to_string),
+                    expires_at: tracked.expires_at,
+                    window,
+                });
+            }
+            notices.sort_by(|a, b| a.expires_at.cmp(&b.expires_at).then_with(|| a.credential_id.cmp(&b.credential_id)));
+            notices
+        };
+
+        for notice in &notices {
+            for notifier in &self.notifiers {
+                if let Err(e) = notifier.renewal_needed(notice).await {
+                    tracing::warn!("Failed to send renewal notice for {}: {}", notice.credential_id, e);
+                }
+            }
+        }
+        Ok(notices)
+    }
+
+    /// Renew a credential until `expires_at` with a fresh signature from its issuer.
+    /// The renewal keeps the credential's types and subject claims and records the
+    /// credential it replaces in its lineage.
+    pub async fn renew(
+        &self,
+        credential_id: &str,
+        issuer_did: &str,
+        issuer_key: &JWK,
+        expires_at: DateTime<Utc>,
+    ) -> IdentityResult<VerifiableCredential> {
+        let (previous, scope, mut lineage) = {
+            let credentials = self.lock_credentials()?;
+            let tracked = credentials.get(credential_id)
+                .ok_or_else(|| IdentityError::InvalidCredential(format!("Credential {} is not tracked", credential_id)))?;
+            if let Some(renewal) = &tracked.renewed_by {
+                return Err(IdentityError::InvalidCredential(format!(
+                    "Credential {} has already been renewed as {}", credential_id, renewal
+                )));
+            }
+            (tracked.credential.clone(), tracked.scope.clone(), tracked.lineage.clone())
+        };
+        if previous.issuer != issuer_did {
+            return Err(IdentityError::VerificationError(format!(
+                "Only the issuer {} can renew credential {}", previous.issuer, credential_id
+            )));
+        }
+        if expires_at <= Utc::now().max(credential_expiry(&previous)?.unwrap_or_else(Utc::now)) {
+            return Err(IdentityError::InvalidCredential(
+                "A renewal must expire later than the credential it replaces".to_string()
+            ));
+        }
+
+        let mut renewal = previous.clone();
+        renewal.id = format!("urn:uuid:{}", Uuid::new_v4());
+        renewal.issuanceDate = Utc::now().to_rfc3339();
+        renewal.proof = None;
+        let renewal = sign_credential(renewal.with_expiration(expires_at), issuer_did, issuer_key).await?;
+
+        lineage.push(previous.id.clone());
+        self.track_with_lineage(renewal.clone(), &scope, lineage)?;
+        let mut credentials = self.lock_credentials()?;
+        if let Some(tracked) = credentials.get_mut(credential_id) {
+            tracked.renewed_by = Some(renewal.id.clone());
+        }
+        Ok(renewal)
+    }
+
+    /// IDs of the credentials a credential renews, oldest first
+    pub fn lineage(&self, credential_id: &str) -> IdentityResult<Vec<String>> {
+        Ok(self.lock_credentials()?.get(credential_id)
+            .map(|tracked| tracked.lineage.clone())
+            .unwrap_or_default())
+    }
+
+    /// Number of unrenewed credentials in each scope that are still valid at `now` but
+    /// expire within `within`
+    pub fn expiring_within(&self, now: DateTime<Utc>, within: Duration) -> IdentityResult<BTreeMap<String, usize>> {
+        let mut counts = BTreeMap::new();
+        for tracked in self.lock_credentials()?.values() {
+            if tracked.renewed_by.is_none() && tracked.expires_at > now && tracked.expires_at <= now + within {
+                *counts.entry(tracked.scope.clone()).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did::IdentityId;
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        notices: Mutex<Vec<RenewalNotice>>,
+    }
+
+    #[async_trait]
+    impl ExpiryNotifier for RecordingNotifier {
+        async fn renewal_needed(&self, notice: &RenewalNotice) -> Result<()> {
+            self.notices.lock().unwrap().push(notice.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reminders_then_renewal_preserves_lineage() {
+        let notifier = Arc::new(RecordingNotifier::default());
+        let monitor = CredentialExpiryMonitor::new().with_notifier(notifier.clone());
+        let issuer = IdentityId::new("did:key:z6MkCoopIssuer");
+        let member = IdentityId::new("did:key:z6MkAlice");
+        let now = Utc::now();
+
+        let membership = VerifiableCredential::new(
+            vec!["VerifiableCredential".to_string(), "MembershipCredential".to_string()],
+            &issuer, &member, serde_json::json!({ "role": "worker-owner" }),
+        ).with_expiration(now + Duration::days(10));
+        let id = membership.id.clone();
+        monitor.track(membership, "coop-1").unwrap();
+        let never_expires = VerifiableCredential::new(vec![], &issuer, &member, serde_json::json!({}));
+        assert!(monitor.track(never_expires, "coop-1").is_err());
+
+        // Ten days out the 30-day window has been entered, once
+        let notices = monitor.check(now).await.unwrap();
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].window, Some(Duration::days(30)));
+        assert_eq!(notices[0].subject.as_deref(), Some("did:key:z6MkAlice"));
+        assert!(monitor.check(now).await.unwrap().is_empty());
+
+        // Skipping past the 7- and 1-day windows yields one notice, then one on expiry
+        let notices = monitor.check(now + Duration::days(9) + Duration::hours(12)).await.unwrap();
+        assert_eq!(notices[0].window, Some(Duration::days(1)));
+        assert!(monitor.check(now + Duration::days(11)).await.unwrap()[0].is_expired());
+        assert_eq!(notifier.notices.lock().unwrap().len(), 3);
+
+        let metrics = monitor.expiring_within(now, Duration::days(14)).unwrap();
+        assert_eq!(metrics.get("coop-1"), Some(&1));
+
+        let key = JWK::generate_ed25519().unwrap();
+        assert!(monitor.renew(&id, "did:key:z6MkSomeoneElse", &key, now + Duration::days(365)).await.is_err());
+        let renewal = monitor.renew(&id, issuer.as_str(), &key, now + Duration::days(365)).await.unwrap();
+        assert_ne!(renewal.id, id);
+        assert!(renewal.proof.is_some());
+        assert_eq!(renewal.credentialSubject["role"], "worker-owner");
+        assert_eq!(monitor.lineage(&renewal.id).unwrap(), vec![id.clone()]);
+        assert!(monitor.renew(&id, issuer.as_str(), &key, now + Duration::days(400)).await.is_err());
+
+        // The renewed credential no longer counts as expiring
+        assert!(monitor.expiring_within(now, Duration::days(14)).unwrap().is_empty());
+    }
+}